@@ -0,0 +1,43 @@
+// Error module
+// The error type core calls return, so embedders can tell failures apart
+
+use alloc::string::String;
+use core::fmt;
+
+/// Why a core call failed
+///
+/// Every kind carries a message ready to show the player; match on the kind
+/// to tell a generator that ran out of options from a bad setting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoreError {
+    /// No pattern could be generated or derived from the ones given
+    Generation(String),
+    /// A setting, typed answer, or file's contents was invalid
+    Config(String),
+}
+
+impl CoreError {
+    /// The message, without its kind
+    pub fn message(&self) -> &str {
+        match self {
+            CoreError::Generation(message) | CoreError::Config(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for CoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl core::error::Error for CoreError {}
+
+/// For callers that only show the message
+impl From<CoreError> for String {
+    fn from(error: CoreError) -> Self {
+        match error {
+            CoreError::Generation(message) | CoreError::Config(message) => message,
+        }
+    }
+}
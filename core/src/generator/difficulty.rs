@@ -0,0 +1,155 @@
+use crate::error::CoreError;
+use crate::generator::PatternGenerator;
+use crate::models::{ComplexityLevel, DifficultyRange, Pattern, TimeSignature};
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::format;
+
+/// Patterns drawn from the wrapped generator before giving up on the range
+const ATTEMPTS: usize = 300;
+
+/// Keeps only patterns whose [`Pattern::difficulty_score`] falls in a range
+///
+/// Instead of the complexity passed to `generate`, the wrapped generator is
+/// asked for each complexity level in turn, starting with the one most
+/// likely to score in the range, so one range can span several levels.
+///
+/// # Examples
+///
+/// ```no_run
+/// use kickbeats_core::generator::{DifficultyTarget, PatternGenerator, WeightedGenerator};
+/// use kickbeats_core::models::{ComplexityLevel, DifficultyRange, TimeSignature};
+/// use std::collections::VecDeque;
+///
+/// let range = DifficultyRange::new(30, 50)?;
+/// let mut generator = DifficultyTarget::new(Box::new(WeightedGenerator::new()), range);
+/// let pattern =
+///     generator.generate(TimeSignature::four_four(), ComplexityLevel::Medium, &VecDeque::new())?;
+/// assert!(range.accepts(&pattern));
+/// # Ok::<(), kickbeats_core::CoreError>(())
+/// ```
+pub struct DifficultyTarget {
+    /// Generator patterns are drawn from
+    generator: Box<dyn PatternGenerator>,
+    /// Scores accepted
+    range: DifficultyRange,
+}
+
+impl DifficultyTarget {
+    /// Wrap a generator to produce patterns scoring in `range`
+    pub fn new(generator: Box<dyn PatternGenerator>, range: DifficultyRange) -> Self {
+        Self { generator, range }
+    }
+
+    /// Scores accepted
+    pub fn range(&self) -> DifficultyRange {
+        self.range
+    }
+
+    /// Complexity level to draw the `attempt`th pattern at
+    fn level(&self, attempt: usize) -> ComplexityLevel {
+        self.range.levels()[attempt % 3]
+    }
+
+    /// Error for a range none of the wrapped generator's patterns scored in
+    fn out_of_reach(&self) -> CoreError {
+        CoreError::Generation(format!(
+            "Failed to generate a pattern with difficulty {}-{} after {} attempts. Try a wider range",
+            self.range.min, self.range.max, ATTEMPTS
+        ))
+    }
+}
+
+impl PatternGenerator for DifficultyTarget {
+    fn generate(
+        &mut self,
+        time_signature: TimeSignature,
+        _complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<Pattern, CoreError> {
+        for attempt in 0..ATTEMPTS {
+            let level = self.level(attempt);
+            // Some generators can't meet the uniqueness rule at every level
+            if let Ok(pattern) = self.generator.generate(time_signature, level, history) {
+                if self.range.accepts(&pattern) {
+                    return Ok(pattern);
+                }
+            }
+        }
+        Err(self.out_of_reach())
+    }
+
+    fn generate_unique(
+        &mut self,
+        time_signature: TimeSignature,
+        _complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<(Pattern, u32), CoreError> {
+        for attempt in 0..ATTEMPTS {
+            let level = self.level(attempt);
+            let (pattern, distance) =
+                self.generator
+                    .generate_unique(time_signature, level, history)?;
+            if self.range.accepts(&pattern) {
+                return Ok((pattern, distance));
+            }
+        }
+        Err(self.out_of_reach())
+    }
+
+    fn record_result(&mut self, pattern: &Pattern, accuracy: f32) {
+        self.generator.record_result(pattern, accuracy);
+    }
+
+    fn last_source(&self) -> Option<&str> {
+        self.generator.last_source()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::generator::{EuclideanGenerator, WeightedGenerator};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_patterns_score_in_range() {
+        for (min, max) in [(0, 15), (30, 50), (70, 100)] {
+            let range = DifficultyRange::new(min, max).unwrap();
+            // Seeded, since only a handful of simple patterns score 15 or below
+            let weighted = WeightedGenerator::with_rng(StdRng::seed_from_u64(1));
+            let mut generator = DifficultyTarget::new(Box::new(weighted), range);
+            let mut history = VecDeque::new();
+            for _ in 0..5 {
+                let (pattern, _) = generator
+                    .generate_unique(
+                        TimeSignature::four_four(),
+                        ComplexityLevel::Simple,
+                        &history,
+                    )
+                    .unwrap();
+                let score = pattern.difficulty_score();
+                assert!(range.contains(score), "{} outside {}-{}", score, min, max);
+                history.push_back(pattern);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unreachable_range_fails() {
+        // E(4,16) is only ever four on the floor
+        let range = DifficultyRange::new(50, 100).unwrap();
+        let euclidean = EuclideanGenerator::new().with_hits(4);
+        let mut generator = DifficultyTarget::new(Box::new(euclidean), range);
+        let result = generator.generate(
+            TimeSignature::four_four(),
+            ComplexityLevel::Complex,
+            &VecDeque::new(),
+        );
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Try a wider range"));
+    }
+}
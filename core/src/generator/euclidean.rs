@@ -0,0 +1,264 @@
+use crate::error::CoreError;
+#[cfg(feature = "std")]
+use crate::generator::weighted::entropy_rng;
+use crate::generator::{is_pattern_unique, random_id, DefaultRng, PatternGenerator};
+use crate::models::{BeatGrid, ComplexityLevel, Pattern, TimeSignature};
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use rand::seq::SliceRandom;
+use rand::RngCore;
+
+/// Generates Euclidean (Bjorklund) rhythms
+///
+/// A Euclidean rhythm E(k, n) spreads k kicks as evenly as possible over n
+/// positions, e.g. E(3, 8) is the tresillo `x..x..x.` and E(5, 16) is a
+/// bossa-like figure. Every rotation that keeps a kick on the downbeat is a
+/// candidate, so consecutive patterns vary while keeping the same feel.
+///
+/// # Examples
+///
+/// ```no_run
+/// use kickbeats_core::generator::{EuclideanGenerator, PatternGenerator};
+/// use kickbeats_core::models::{TimeSignature, ComplexityLevel};
+/// use std::collections::VecDeque;
+///
+/// let mut generator = EuclideanGenerator::new().with_hits(5);
+/// let time_sig = TimeSignature::four_four();
+/// let pattern = generator.generate(time_sig, ComplexityLevel::Medium, &VecDeque::new())?;
+/// # Ok::<(), kickbeats_core::CoreError>(())
+/// ```
+pub struct EuclideanGenerator<R: RngCore = DefaultRng> {
+    /// Random number generator (rotation choice and pattern ids)
+    rng: R,
+    /// Number of kicks per measure; derived from complexity when unset
+    hits: Option<usize>,
+}
+
+impl EuclideanGenerator {
+    /// Create a new Euclidean generator
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::with_rng(entropy_rng())
+    }
+}
+
+impl<R: RngCore> EuclideanGenerator<R> {
+    /// Create a generator drawing from the given random source
+    pub fn with_rng(rng: R) -> Self {
+        Self { rng, hits: None }
+    }
+
+    /// Use a fixed number of kicks per measure instead of one based on complexity
+    pub fn with_hits(mut self, hits: usize) -> Self {
+        self.hits = Some(hits);
+        self
+    }
+
+    /// Kicks per measure for a complexity level (middle of the weighted generator's range)
+    fn hits_for_complexity(complexity: ComplexityLevel) -> usize {
+        match complexity {
+            ComplexityLevel::Simple => 3,
+            ComplexityLevel::Medium => 5,
+            ComplexityLevel::Complex => 7,
+        }
+    }
+
+    /// All rotations of E(hits, positions) that start with a kick, in random order
+    fn candidates(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+    ) -> Result<Vec<Pattern>, CoreError> {
+        let positions = BeatGrid::new(time_signature, 16, 1).total_positions();
+        let hits = self
+            .hits
+            .unwrap_or_else(|| Self::hits_for_complexity(complexity));
+
+        if hits == 0 || hits > positions {
+            return Err(CoreError::Generation(format!(
+                "Cannot place {} kicks in {} positions",
+                hits, positions
+            )));
+        }
+
+        let base = euclidean_steps(hits, positions);
+        let mut rotations: Vec<Vec<bool>> = Vec::new();
+        for offset in (0..positions).filter(|&i| base[i]) {
+            let mut steps = base.clone();
+            steps.rotate_left(offset);
+            if !rotations.contains(&steps) {
+                rotations.push(steps);
+            }
+        }
+        rotations.shuffle(&mut self.rng);
+
+        let mut patterns = Vec::with_capacity(rotations.len());
+        for steps in rotations {
+            let id = random_id(&mut self.rng);
+            let pattern = Pattern::with_id(id, steps, time_signature, complexity);
+            pattern.validate_steps().map_err(|e| {
+                CoreError::Generation(format!(
+                    "E({},{}) is not a valid pattern: {}",
+                    hits, positions, e
+                ))
+            })?;
+            patterns.push(pattern);
+        }
+        Ok(patterns)
+    }
+}
+
+impl<R: RngCore + Send> PatternGenerator for EuclideanGenerator<R> {
+    fn generate(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<Pattern, CoreError> {
+        self.candidates(time_signature, complexity)?
+            .into_iter()
+            .find(|pattern| is_pattern_unique(pattern, history, 3))
+            .ok_or_else(|| {
+                CoreError::Generation(
+                    "Every rotation of this Euclidean rhythm is in recent history".to_string(),
+                )
+            })
+    }
+
+    /// Relaxes the distance 3 → 2 → 1 like the weighted generator; because a
+    /// Euclidean rhythm has only a few rotations, it finally repeats one
+    /// (constraint 0) rather than failing
+    fn generate_unique(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<(Pattern, u32), CoreError> {
+        let mut candidates = self.candidates(time_signature, complexity)?;
+
+        for min_distance in [3, 2, 1] {
+            if let Some(idx) = candidates
+                .iter()
+                .position(|pattern| is_pattern_unique(pattern, history, min_distance))
+            {
+                return Ok((candidates.swap_remove(idx), min_distance));
+            }
+        }
+
+        Ok((candidates.swap_remove(0), 0))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for EuclideanGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Steps of the Euclidean rhythm E(hits, positions), starting with a kick
+///
+/// Uses the Bresenham formulation, which yields the same necklaces as
+/// Bjorklund's algorithm.
+pub fn euclidean_steps(hits: usize, positions: usize) -> Vec<bool> {
+    (0..positions)
+        .map(|i| (i * hits) % positions < hits)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Seeded, so the tests also run without `std`
+    fn generator() -> EuclideanGenerator<StdRng> {
+        EuclideanGenerator::with_rng(StdRng::seed_from_u64(7))
+    }
+
+    fn to_string(steps: &[bool]) -> String {
+        steps.iter().map(|&s| if s { 'x' } else { '.' }).collect()
+    }
+
+    #[test]
+    fn test_euclidean_steps() {
+        assert_eq!(to_string(&euclidean_steps(3, 8)), "x..x..x.");
+        assert_eq!(to_string(&euclidean_steps(4, 16)), "x...x...x...x...");
+        // A rotation of Bjorklund's x..x..x..x..x...
+        assert_eq!(to_string(&euclidean_steps(5, 16)), "x...x..x..x..x..");
+    }
+
+    #[test]
+    fn test_generate_has_requested_hits() {
+        let mut gen = generator().with_hits(5);
+        let pattern = gen
+            .generate(
+                TimeSignature::four_four(),
+                ComplexityLevel::Medium,
+                &VecDeque::new(),
+            )
+            .unwrap();
+
+        assert_eq!(pattern.steps.len(), 16);
+        assert_eq!(pattern.steps.iter().filter(|&&s| s).count(), 5);
+        assert!(pattern.steps[0]);
+    }
+
+    #[test]
+    fn test_hits_follow_complexity_by_default() {
+        let mut gen = generator();
+        let pattern = gen
+            .generate(
+                TimeSignature::three_four(),
+                ComplexityLevel::Simple,
+                &VecDeque::new(),
+            )
+            .unwrap();
+
+        assert_eq!(pattern.steps.len(), 12);
+        assert_eq!(pattern.steps.iter().filter(|&&s| s).count(), 3);
+    }
+
+    #[test]
+    fn test_generate_unique_cycles_rotations() {
+        let mut gen = generator().with_hits(5);
+        let mut history = VecDeque::new();
+
+        // E(5,16) has exactly five rotations starting on a kick
+        for _ in 0..5 {
+            let (pattern, distance) = gen
+                .generate_unique(
+                    TimeSignature::four_four(),
+                    ComplexityLevel::Medium,
+                    &history,
+                )
+                .unwrap();
+            assert!(distance >= 1);
+            history.push_back(pattern);
+        }
+
+        let (_, distance) = gen
+            .generate_unique(
+                TimeSignature::four_four(),
+                ComplexityLevel::Medium,
+                &history,
+            )
+            .unwrap();
+        assert_eq!(distance, 0);
+    }
+
+    #[test]
+    fn test_rejects_invalid_hit_counts() {
+        let mut gen = generator().with_hits(12);
+        assert!(gen
+            .generate(
+                TimeSignature::four_four(),
+                ComplexityLevel::Medium,
+                &VecDeque::new()
+            )
+            .is_err());
+    }
+}
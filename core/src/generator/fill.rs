@@ -0,0 +1,149 @@
+#[cfg(feature = "std")]
+use crate::generator::weighted::entropy_rng;
+use crate::generator::DefaultRng;
+use crate::models::{BeatGrid, Pattern};
+use alloc::vec;
+use alloc::vec::Vec;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::RngCore;
+
+/// Fewest kicks a fill adds
+const MIN_FILL_KICKS: usize = 2;
+
+/// Generates fills: busier variations of a pattern's last bar, for the end
+/// of a phrase
+///
+/// A fill keeps every kick of the pattern and adds more to its last bar,
+/// about half as many again, drawn with a phrase-position-aware weighting:
+/// off-beats are favored over beats, and each position counts for more the
+/// nearer it is to the bar line, so fills build into the next phrase.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::generator::FillGenerator;
+/// use kickbeats_core::models::{ComplexityLevel, Pattern};
+/// use rand::SeedableRng;
+///
+/// let pattern = Pattern::from_pattern_string("4/4:16:x.......x.......", ComplexityLevel::Simple)?;
+/// let mut fills = FillGenerator::with_rng(rand::rngs::StdRng::seed_from_u64(7));
+/// let fill = fills.fill(&pattern);
+/// assert!(fill.steps.iter().filter(|&&kick| kick).count() > 2);
+/// assert!(fill.steps[0] && fill.steps[8]);
+/// # Ok::<(), kickbeats_core::CoreError>(())
+/// ```
+pub struct FillGenerator<R: RngCore = DefaultRng> {
+    /// Random number generator
+    rng: R,
+}
+
+impl FillGenerator {
+    /// Create a fill generator seeded from the OS
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::with_rng(entropy_rng())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for FillGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: RngCore> FillGenerator<R> {
+    /// Create a fill generator drawing from the given random source
+    pub fn with_rng(rng: R) -> Self {
+        Self { rng }
+    }
+
+    /// Weight of adding a kick at each step of `pattern`: zero outside its
+    /// last bar and where it already kicks, otherwise highest on off-beats
+    /// near the end of the bar
+    pub fn fill_weights(pattern: &Pattern) -> Vec<f32> {
+        let mut weights = vec![0.0; pattern.steps.len()];
+        let Some(&(start, meter)) = pattern.measures().last() else {
+            return weights;
+        };
+        let grid = BeatGrid::new(meter, pattern.subdivision, 1);
+        let len = pattern.steps.len().saturating_sub(start).max(1);
+        for (i, weight) in weights.iter_mut().enumerate().skip(start) {
+            if pattern.steps[i] {
+                continue;
+            }
+            let position = i - start;
+            let offbeat = 1.0 - grid.position_strength(position) + 0.1;
+            let lateness = 0.25 + position as f32 / len as f32;
+            *weight = offbeat * lateness;
+        }
+        weights
+    }
+
+    /// A fill for `pattern`: the same kicks, plus more in its last bar
+    ///
+    /// A bar with no room for more kicks comes back as it was.
+    pub fn fill(&mut self, pattern: &Pattern) -> Pattern {
+        let mut weights = Self::fill_weights(pattern);
+        let open = weights.iter().filter(|&&w| w > 0.0).count();
+        let last_bar = pattern.measures().last().map_or(0, |&(start, _)| start);
+        let kicks = pattern.steps[last_bar..].iter().filter(|&&k| k).count();
+        let extra = (kicks / 2).max(MIN_FILL_KICKS).min(open);
+
+        let mut fill = pattern.clone();
+        for _ in 0..extra {
+            let Ok(dist) = WeightedIndex::new(&weights) else {
+                break;
+            };
+            let idx = dist.sample(&mut self.rng);
+            fill.steps[idx] = true;
+            weights[idx] = 0.0;
+        }
+        fill
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ComplexityLevel;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn pattern(text: &str) -> Pattern {
+        Pattern::from_pattern_string(text, ComplexityLevel::Medium).unwrap()
+    }
+
+    #[test]
+    fn test_weights_favor_late_offbeats_in_the_last_bar() {
+        let weights =
+            FillGenerator::<StdRng>::fill_weights(&pattern("4/4+2/4:16:x.......x.......x......."));
+        // Nothing in the first bar or on existing kicks
+        assert!(weights[..16].iter().all(|&w| w == 0.0));
+        assert_eq!(weights[16], 0.0);
+        // Off-beats outweigh beats, and later outweighs earlier
+        assert!(weights[19] > weights[20]);
+        assert!(weights[23] > weights[17]);
+    }
+
+    #[test]
+    fn test_fill_adds_kicks_and_keeps_the_pattern() {
+        let original = pattern("4/4:16:x...x.......x...");
+        let mut fills = FillGenerator::with_rng(StdRng::seed_from_u64(1));
+        for _ in 0..20 {
+            let fill = fills.fill(&original);
+            let added: Vec<usize> = (0..16)
+                .filter(|&i| fill.steps[i] && !original.steps[i])
+                .collect();
+            assert_eq!(added.len(), 2);
+            assert!(original
+                .steps
+                .iter()
+                .zip(&fill.steps)
+                .all(|(&was, &now)| !was || now));
+        }
+
+        let full = pattern("4/4:16:xxxxxxxxxxxxxxxx");
+        assert_eq!(fills.fill(&full).steps, full.steps);
+    }
+}
@@ -0,0 +1,538 @@
+use crate::error::CoreError;
+#[cfg(feature = "std")]
+use crate::generator::weighted::entropy_rng;
+use crate::generator::{is_pattern_unique, random_id, DefaultRng, PatternGenerator};
+use crate::models::{ComplexityLevel, Pattern, TimeSignature};
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use rand::seq::SliceRandom;
+use rand::RngCore;
+
+/// General MIDI acoustic and electric bass drum notes
+const KICK_NOTES: [u8; 2] = [35, 36];
+
+/// MIDI channel 10 (drums), zero-based
+const DRUM_CHANNEL: u8 = 9;
+
+/// One measure of kicks taken from an imported MIDI file
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrooveMeasure {
+    /// Meter of the measure
+    pub time_signature: TimeSignature,
+    /// Kick (true) or rest (false) for each sixteenth of the measure
+    pub steps: Vec<bool>,
+}
+
+impl GrooveMeasure {
+    /// Complexity band by kick count, matching the generators' ranges
+    /// (simple 2-4, medium 4-6, complex 6-8 kicks)
+    pub fn complexity(&self) -> ComplexityLevel {
+        match self.steps.iter().filter(|&&s| s).count() {
+            0..=4 => ComplexityLevel::Simple,
+            5..=6 => ComplexityLevel::Medium,
+            _ => ComplexityLevel::Complex,
+        }
+    }
+}
+
+/// Plays kick drum parts imported from Standard MIDI Files
+///
+/// Every measure with at least one kick (notes 35/36 on channel 10) becomes a
+/// candidate pattern, quantized to sixteenths in the file's own meter. Draws
+/// prefer measures whose kick count fits the requested complexity and fall
+/// back to any measure in the requested meter.
+///
+/// # Examples
+///
+/// ```no_run
+/// use kickbeats_core::generator::{GrooveGenerator, PatternGenerator};
+/// use kickbeats_core::models::{TimeSignature, ComplexityLevel};
+/// use std::collections::VecDeque;
+///
+/// let mut grooves = GrooveGenerator::load_dir(std::path::Path::new("grooves"))?;
+/// let time_sig = TimeSignature::four_four();
+/// let pattern = grooves.generate(time_sig, ComplexityLevel::Medium, &VecDeque::new())?;
+/// # Ok::<(), kickbeats_core::CoreError>(())
+/// ```
+pub struct GrooveGenerator<R: RngCore = DefaultRng> {
+    /// Random number generator (measure choice and pattern ids)
+    rng: R,
+    /// Imported measures
+    measures: Vec<GrooveMeasure>,
+}
+
+#[cfg(feature = "std")]
+impl GrooveGenerator {
+    /// Import every `.mid`/`.midi` file in a directory
+    pub fn load_dir(dir: &std::path::Path) -> Result<Self, CoreError> {
+        let entries = std::fs::read_dir(dir).map_err(|e| {
+            CoreError::Config(format!(
+                "Failed to read grooves directory {}: {}",
+                dir.display(),
+                e
+            ))
+        })?;
+
+        let mut paths: Vec<std::path::PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| {
+                        ext.eq_ignore_ascii_case("mid") || ext.eq_ignore_ascii_case("midi")
+                    })
+            })
+            .collect();
+        paths.sort();
+
+        let mut generator = Self::with_rng(entropy_rng());
+        for path in paths {
+            let bytes = std::fs::read(&path).map_err(|e| {
+                CoreError::Config(format!("Failed to read {}: {}", path.display(), e))
+            })?;
+            generator
+                .add_midi(&bytes)
+                .map_err(|e| CoreError::Config(format!("{}: {}", path.display(), e)))?;
+        }
+
+        if generator.is_empty() {
+            return Err(CoreError::Config(format!(
+                "No kick drum measures found in MIDI files under {}",
+                dir.display()
+            )));
+        }
+        Ok(generator)
+    }
+}
+
+impl<R: RngCore> GrooveGenerator<R> {
+    /// Create an empty generator; import files with `add_midi`
+    pub fn with_rng(rng: R) -> Self {
+        Self {
+            rng,
+            measures: Vec::new(),
+        }
+    }
+
+    /// Import the kick measures of a Standard MIDI File, returning how many were added
+    pub fn add_midi(&mut self, bytes: &[u8]) -> Result<usize, CoreError> {
+        let measures = parse_kick_measures(bytes)?;
+        let added = measures.len();
+        self.measures.extend(measures);
+        Ok(added)
+    }
+
+    /// Number of imported measures
+    pub fn len(&self) -> usize {
+        self.measures.len()
+    }
+
+    /// Whether no measures have been imported
+    pub fn is_empty(&self) -> bool {
+        self.measures.is_empty()
+    }
+
+    /// Measures in the requested meter as patterns, best complexity match first
+    fn candidates(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+    ) -> Result<Vec<Pattern>, CoreError> {
+        let mut matching: Vec<GrooveMeasure> = Vec::new();
+        let mut others: Vec<GrooveMeasure> = Vec::new();
+        for measure in self
+            .measures
+            .iter()
+            .filter(|m| m.time_signature.same_meter(&time_signature))
+        {
+            if measure.complexity() == complexity {
+                matching.push(measure.clone());
+            } else {
+                others.push(measure.clone());
+            }
+        }
+
+        if matching.is_empty() && others.is_empty() {
+            return Err(CoreError::Generation(format!(
+                "No imported groove in {}/{}",
+                time_signature.numerator, time_signature.denominator
+            )));
+        }
+
+        matching.shuffle(&mut self.rng);
+        others.shuffle(&mut self.rng);
+        let mut patterns = Vec::with_capacity(matching.len() + others.len());
+        for measure in matching.into_iter().chain(others) {
+            let id = random_id(&mut self.rng);
+            let level = measure.complexity();
+            patterns.push(Pattern::with_id(id, measure.steps, time_signature, level));
+        }
+        Ok(patterns)
+    }
+}
+
+impl<R: RngCore + Send> PatternGenerator for GrooveGenerator<R> {
+    fn generate(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<Pattern, CoreError> {
+        self.candidates(time_signature, complexity)?
+            .into_iter()
+            .find(|pattern| is_pattern_unique(pattern, history, 3))
+            .ok_or_else(|| {
+                CoreError::Generation("Every imported groove is in recent history".to_string())
+            })
+    }
+
+    /// Relaxes the distance 3 → 2 → 1, then repeats a groove (constraint 0)
+    fn generate_unique(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<(Pattern, u32), CoreError> {
+        let mut candidates = self.candidates(time_signature, complexity)?;
+
+        for min_distance in [3, 2, 1] {
+            if let Some(idx) = candidates
+                .iter()
+                .position(|pattern| is_pattern_unique(pattern, history, min_distance))
+            {
+                return Ok((candidates.swap_remove(idx), min_distance));
+            }
+        }
+
+        Ok((candidates.swap_remove(0), 0))
+    }
+}
+
+/// Reads big-endian fields and variable-length quantities from SMF data
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| "Truncated MIDI file".to_string())?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Variable-length quantity: 7 bits per byte, high bit set on all but the last
+    fn varlen(&mut self) -> Result<u32, String> {
+        let mut value: u32 = 0;
+        for _ in 0..4 {
+            let b = self.byte()?;
+            value = (value << 7) | u32::from(b & 0x7F);
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("Invalid variable-length quantity in MIDI file".to_string())
+    }
+
+    fn is_done(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+}
+
+/// Kick note-on ticks and time signature changes from every track of a file
+struct Events {
+    ticks_per_quarter: u32,
+    kicks: Vec<u64>,
+    meters: Vec<(u64, Option<TimeSignature>)>,
+}
+
+fn read_events(bytes: &[u8]) -> Result<Events, String> {
+    let mut file = Reader { bytes, pos: 0 };
+    if file.take(4)? != b"MThd" {
+        return Err("Not a Standard MIDI File".to_string());
+    }
+    let header_len = file.u32()? as usize;
+    if header_len < 6 {
+        return Err("Invalid MIDI header".to_string());
+    }
+    let _format = file.u16()?;
+    let tracks = file.u16()?;
+    let division = file.u16()?;
+    file.take(header_len - 6)?;
+    if division & 0x8000 != 0 || division == 0 {
+        return Err("SMPTE-timed MIDI files are not supported".to_string());
+    }
+
+    let mut events = Events {
+        ticks_per_quarter: u32::from(division),
+        kicks: Vec::new(),
+        meters: Vec::new(),
+    };
+
+    for _ in 0..tracks {
+        let id = file.take(4)?;
+        let len = file.u32()? as usize;
+        let data = file.take(len)?;
+        if id != b"MTrk" {
+            continue;
+        }
+
+        let mut track = Reader {
+            bytes: data,
+            pos: 0,
+        };
+        let mut tick: u64 = 0;
+        let mut running: Option<u8> = None;
+        while !track.is_done() {
+            tick += u64::from(track.varlen()?);
+            let first = track.byte()?;
+            match first {
+                0xFF => {
+                    let kind = track.byte()?;
+                    let len = track.varlen()? as usize;
+                    let data = track.take(len)?;
+                    match kind {
+                        0x2F => break,
+                        0x58 if len >= 2 => {
+                            // Denominator is stored as a power of two; anything
+                            // past sixteenths can't be shown on the grid
+                            let denominator = 1u32.checked_shl(u32::from(data[1]));
+                            let meter = denominator
+                                .filter(|d| [1, 2, 4, 8, 16].contains(d))
+                                .map(|d| TimeSignature::new(data[0], d as u8))
+                                .filter(|m| m.numerator > 0);
+                            events.meters.push((tick, meter));
+                        }
+                        _ => {}
+                    }
+                }
+                0xF0 | 0xF7 => {
+                    let len = track.varlen()? as usize;
+                    track.take(len)?;
+                }
+                _ => {
+                    let (status, data1) = if first & 0x80 != 0 {
+                        running = Some(first);
+                        (first, track.byte()?)
+                    } else {
+                        let status =
+                            running.ok_or_else(|| "MIDI data byte without a status".to_string())?;
+                        (status, first)
+                    };
+                    let data2 = match status & 0xF0 {
+                        0xC0 | 0xD0 => 0,
+                        _ => track.byte()?,
+                    };
+                    if status & 0xF0 == 0x90
+                        && status & 0x0F == DRUM_CHANNEL
+                        && data2 > 0
+                        && KICK_NOTES.contains(&data1)
+                    {
+                        events.kicks.push(tick);
+                    }
+                }
+            }
+        }
+    }
+
+    events.meters.sort_by_key(|&(tick, _)| tick);
+    Ok(events)
+}
+
+/// Split a Standard MIDI File's kick drum part into sixteenth-note measures
+///
+/// Kicks are General MIDI notes 35/36 on channel 10, rounded to the nearest
+/// sixteenth. Measures follow the file's time signatures (4/4 until the first
+/// one); measures without kicks, and measures in meters finer than sixteenths
+/// (e.g., 7/32), are left out.
+pub fn parse_kick_measures(bytes: &[u8]) -> Result<Vec<GrooveMeasure>, CoreError> {
+    let events = read_events(bytes).map_err(CoreError::Config)?;
+    let tpq = u64::from(events.ticks_per_quarter);
+    let to_step = |tick: u64| (tick * 4 + tpq / 2) / tpq;
+
+    let mut kicks: Vec<u64> = events.kicks.iter().map(|&t| to_step(t)).collect();
+    kicks.sort_unstable();
+    kicks.dedup();
+    let Some(&last_kick) = kicks.last() else {
+        return Ok(Vec::new());
+    };
+
+    let meters: Vec<(u64, Option<TimeSignature>)> = events
+        .meters
+        .iter()
+        .map(|&(tick, meter)| (to_step(tick), meter))
+        .collect();
+
+    let mut measures = Vec::new();
+    let mut meter = Some(TimeSignature::four_four());
+    let mut next_meter = 0;
+    let mut start: u64 = 0;
+    while start <= last_kick {
+        // A time signature takes effect at the first barline at or after it
+        while next_meter < meters.len() && meters[next_meter].0 <= start {
+            meter = meters[next_meter].1;
+            next_meter += 1;
+        }
+        // Measures in unsupported meters are skipped as one 4/4 bar
+        let len = meter.map_or(16, |m| m.sixteenths_per_measure().max(1)) as u64;
+
+        if let Some(time_signature) = meter {
+            let steps: Vec<bool> = (start..start + len)
+                .map(|step| kicks.binary_search(&step).is_ok())
+                .collect();
+            if steps.iter().any(|&s| s) {
+                measures.push(GrooveMeasure {
+                    time_signature,
+                    steps,
+                });
+            }
+        }
+        start += len;
+    }
+
+    Ok(measures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Variable-length quantity encoding
+    fn varlen(mut value: u32) -> Vec<u8> {
+        let mut bytes = vec![(value & 0x7F) as u8];
+        value >>= 7;
+        while value > 0 {
+            bytes.insert(0, (value & 0x7F) as u8 | 0x80);
+            value >>= 7;
+        }
+        bytes
+    }
+
+    /// A format 0 file at 96 ticks per quarter with the given track events
+    fn smf(events: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let mut track = Vec::new();
+        for (delta, event) in events {
+            track.extend(varlen(*delta));
+            track.extend(event);
+        }
+        track.extend([0x00, 0xFF, 0x2F, 0x00]);
+
+        let mut file = b"MThd".to_vec();
+        file.extend(6u32.to_be_bytes());
+        file.extend([0, 0, 0, 1, 0, 96]);
+        file.extend(b"MTrk");
+        file.extend((track.len() as u32).to_be_bytes());
+        file.extend(track);
+        file
+    }
+
+    fn to_string(steps: &[bool]) -> String {
+        steps.iter().map(|&s| if s { 'x' } else { '.' }).collect()
+    }
+
+    #[test]
+    fn test_parses_kicks_into_measures() {
+        // Kicks on 1, the "+" of 2 (running status), and 4; a snare and a
+        // bass-channel C2 are ignored; bar 2 is empty, bar 3 has a downbeat
+        let file = smf(&[
+            (0, vec![0x99, 36, 100]),
+            (0, vec![0x99, 38, 100]),
+            (24, vec![36, 0]),
+            (120, vec![36, 90]),
+            (0, vec![0x90, 36, 90]),
+            (144, vec![0x99, 35, 80]),
+            (96 + 96 * 4, vec![0x99, 36, 100]),
+        ]);
+
+        let measures = parse_kick_measures(&file).unwrap();
+        assert_eq!(measures.len(), 2);
+        assert_eq!(measures[0].time_signature, TimeSignature::four_four());
+        assert_eq!(to_string(&measures[0].steps), "x.....x.....x...");
+        assert_eq!(to_string(&measures[1].steps), "x...............");
+    }
+
+    #[test]
+    fn test_follows_time_signature_changes() {
+        // 3/4 from the start, then 6/8 (6 eighths, denominator 2^3) after one bar
+        let file = smf(&[
+            (0, vec![0xFF, 0x58, 4, 3, 2, 24, 8]),
+            (0, vec![0x99, 36, 100]),
+            (288, vec![0xFF, 0x58, 4, 6, 3, 24, 8]),
+            (0, vec![0x99, 36, 100]),
+            (144, vec![0x99, 36, 100]),
+        ]);
+
+        let measures = parse_kick_measures(&file).unwrap();
+        assert_eq!(measures.len(), 2);
+        assert_eq!(measures[0].time_signature, TimeSignature::three_four());
+        assert_eq!(to_string(&measures[0].steps), "x...........");
+        assert_eq!(measures[1].time_signature, TimeSignature::six_eight());
+        assert_eq!(to_string(&measures[1].steps), "x.....x.....");
+    }
+
+    #[test]
+    fn test_rejects_non_midi_data() {
+        assert!(parse_kick_measures(b"RIFF....").is_err());
+        assert!(parse_kick_measures(b"MThd").is_err());
+    }
+
+    #[test]
+    fn test_draws_grooves_in_requested_meter() {
+        let file = smf(&[(0, vec![0x99, 36, 100]), (192, vec![0x99, 36, 100])]);
+        let mut grooves = GrooveGenerator::with_rng(StdRng::seed_from_u64(7));
+        assert_eq!(grooves.add_midi(&file).unwrap(), 1);
+
+        let pattern = grooves
+            .generate(
+                TimeSignature::four_four(),
+                ComplexityLevel::Medium,
+                &VecDeque::new(),
+            )
+            .unwrap();
+        assert_eq!(to_string(&pattern.steps), "x.......x.......");
+        assert_eq!(pattern.complexity_level, ComplexityLevel::Simple);
+
+        assert!(grooves
+            .generate(
+                TimeSignature::three_four(),
+                ComplexityLevel::Medium,
+                &VecDeque::new(),
+            )
+            .is_err());
+
+        // The only groove repeats once it's in the history
+        let history: VecDeque<Pattern> = vec![pattern].into();
+        let (_, constraint) = grooves
+            .generate_unique(
+                TimeSignature::four_four(),
+                ComplexityLevel::Medium,
+                &history,
+            )
+            .unwrap();
+        assert_eq!(constraint, 0);
+    }
+}
@@ -0,0 +1,160 @@
+use crate::error::CoreError;
+use crate::generator::{is_pattern_unique, PatternGenerator};
+use crate::models::{ComplexityLevel, Pattern, TimeSignature};
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// Phrases tried at each uniqueness distance before relaxing it
+const ATTEMPTS_PER_DISTANCE: usize = 20;
+
+/// Most measures in one phrase
+pub const MAX_MEASURES: usize = 8;
+
+/// Strings one-measure patterns in changing meters into a single looped phrase
+///
+/// Each measure comes from the wrapped generator in its own meter (e.g., a
+/// bar of 4/4 then a bar of 3/4), so every bar follows that generator's rules
+/// and starts with a kick. The time signature passed to `generate` is ignored
+/// in favor of the phrase's meters.
+///
+/// # Examples
+///
+/// ```no_run
+/// use kickbeats_core::generator::{MeterChanges, PatternGenerator, WeightedGenerator};
+/// use kickbeats_core::models::{TimeSignature, ComplexityLevel};
+/// use std::collections::VecDeque;
+///
+/// let meters = vec![TimeSignature::four_four(), TimeSignature::three_four()];
+/// let mut generator = MeterChanges::new(Box::new(WeightedGenerator::new()), meters.clone())?;
+/// let phrase = generator.generate(meters[0], ComplexityLevel::Medium, &VecDeque::new())?;
+/// assert_eq!(phrase.steps.len(), 28);
+/// # Ok::<(), kickbeats_core::CoreError>(())
+/// ```
+pub struct MeterChanges {
+    /// Generator each measure is drawn from
+    generator: Box<dyn PatternGenerator>,
+    /// Meter of each measure, in order
+    meters: Vec<TimeSignature>,
+}
+
+impl MeterChanges {
+    /// Wrap a generator to produce phrases of 2 to [`MAX_MEASURES`] measures in `meters`
+    pub fn new(
+        generator: Box<dyn PatternGenerator>,
+        meters: Vec<TimeSignature>,
+    ) -> Result<Self, CoreError> {
+        if !(2..=MAX_MEASURES).contains(&meters.len()) {
+            return Err(CoreError::Config(format!(
+                "Meter changes need 2 to {} measures, not {}",
+                MAX_MEASURES,
+                meters.len()
+            )));
+        }
+        Ok(Self { generator, meters })
+    }
+
+    /// Meter of each measure, in order
+    pub fn meters(&self) -> &[TimeSignature] {
+        &self.meters
+    }
+
+    /// Draw one measure per meter and join them
+    fn phrase(&mut self, complexity: ComplexityLevel) -> Result<Pattern, CoreError> {
+        let mut measures = Vec::with_capacity(self.meters.len());
+        for &meter in &self.meters {
+            measures.push(
+                self.generator
+                    .generate(meter, complexity, &VecDeque::new())?,
+            );
+        }
+        Pattern::concat(&measures)
+            .ok_or_else(|| CoreError::Generation("Meter changes need measures".to_string()))
+    }
+}
+
+impl PatternGenerator for MeterChanges {
+    fn generate(
+        &mut self,
+        _time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<Pattern, CoreError> {
+        for _ in 0..ATTEMPTS_PER_DISTANCE {
+            let phrase = self.phrase(complexity)?;
+            if is_pattern_unique(&phrase, history, 3) {
+                return Ok(phrase);
+            }
+        }
+        Err(CoreError::Generation(
+            "Failed to generate a unique phrase".to_string(),
+        ))
+    }
+
+    fn generate_unique(
+        &mut self,
+        _time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<(Pattern, u32), CoreError> {
+        for min_distance in [3, 2, 1] {
+            for _ in 0..ATTEMPTS_PER_DISTANCE {
+                let phrase = self.phrase(complexity)?;
+                if is_pattern_unique(&phrase, history, min_distance) {
+                    return Ok((phrase, min_distance));
+                }
+            }
+        }
+        Ok((self.phrase(complexity)?, 0))
+    }
+
+    fn last_source(&self) -> Option<&str> {
+        self.generator.last_source()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::generator::WeightedGenerator;
+    use alloc::vec;
+
+    #[test]
+    fn test_phrase_has_a_measure_per_meter() {
+        let meters = vec![TimeSignature::four_four(), TimeSignature::three_four()];
+        let mut generator =
+            MeterChanges::new(Box::new(WeightedGenerator::new()), meters.clone()).unwrap();
+
+        let (phrase, _) = generator
+            .generate_unique(
+                TimeSignature::six_eight(),
+                ComplexityLevel::Medium,
+                &VecDeque::new(),
+            )
+            .unwrap();
+
+        assert_eq!(phrase.steps.len(), 16 + 12);
+        assert_eq!(phrase.meters, meters);
+        assert_eq!(phrase.time_signature, TimeSignature::four_four());
+        assert_eq!(phrase.num_measures, 2);
+        assert_eq!(phrase.measures(), vec![(0, meters[0]), (16, meters[1])]);
+        assert_eq!(phrase.meter_label(), "4/4 + 3/4");
+        // Every bar starts with a kick
+        assert!(phrase.steps[0] && phrase.steps[16]);
+    }
+
+    #[test]
+    fn test_rejects_too_few_or_many_meters() {
+        let new = |count| {
+            MeterChanges::new(
+                Box::new(WeightedGenerator::new()),
+                vec![TimeSignature::four_four(); count],
+            )
+        };
+        assert!(new(1).is_err());
+        assert!(new(MAX_MEASURES + 1).is_err());
+        assert!(new(MAX_MEASURES).is_ok());
+    }
+}
@@ -0,0 +1,41 @@
+// Generator module
+// Random pattern generation with complexity controls
+
+pub mod difficulty;
+pub mod euclidean;
+pub mod fill;
+pub mod groove;
+pub mod meters;
+pub mod mystery;
+pub mod playlist;
+pub mod profile;
+pub mod rejections;
+pub mod style;
+pub mod templates;
+pub mod traits;
+pub mod unique;
+pub mod weighted;
+
+pub use difficulty::DifficultyTarget;
+pub use euclidean::EuclideanGenerator;
+pub use fill::FillGenerator;
+pub use groove::GrooveGenerator;
+pub use meters::MeterChanges;
+pub use mystery::{MysteryBag, GROOVE_SOURCE, REVIEW_SOURCE};
+pub use playlist::PlaylistGenerator;
+pub use profile::WeightProfiles;
+pub use rejections::{Constraint, Rejections, RetryGuard};
+pub use style::StyleGenerator;
+pub use templates::Style;
+pub use traits::PatternGenerator;
+pub use unique::is_pattern_unique;
+pub use weighted::{DefaultRng, WeightedGenerator};
+
+use rand::Rng;
+use uuid::{Builder, Uuid};
+
+/// Draw a random (v4) pattern id from a generator's own RNG, so seeded
+/// generators repeat their ids too
+pub(crate) fn random_id(rng: &mut impl Rng) -> Uuid {
+    Builder::from_random_bytes(rng.gen()).into_uuid()
+}
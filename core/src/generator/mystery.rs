@@ -0,0 +1,383 @@
+use crate::error::CoreError;
+#[cfg(feature = "std")]
+use crate::generator::weighted::entropy_rng;
+use crate::generator::{DefaultRng, PatternGenerator};
+use crate::models::{ComplexityLevel, Pattern, TimeSignature};
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::RngCore;
+
+/// Most missed patterns kept for review
+const REVIEW_CAPACITY: usize = 20;
+
+/// Default likelihood of drawing from the review queue, relative to sources
+const DEFAULT_REVIEW_WEIGHT: f32 = 2.0;
+
+/// Source name reported for patterns replayed from the review queue
+pub const REVIEW_SOURCE: &str = "review";
+
+/// Source name for kick parts imported from MIDI files (`--grooves`)
+pub const GROOVE_SOURCE: &str = "imported groove";
+
+/// One named pattern source with its draw weight
+struct Source {
+    name: String,
+    generator: Box<dyn PatternGenerator>,
+    weight: f32,
+}
+
+/// Draws each pattern from a randomly chosen source
+///
+/// Sources are picked by weight for every pattern: any number of generators
+/// (including imported MIDI grooves), plus a review queue of patterns the player missed this session (fed by
+/// `record_result`). A source that can't produce a pattern for the current
+/// settings (e.g., a 4/4-only style in 3/4) is skipped for that draw. The
+/// chosen source is reported by `last_source` so callers can keep it secret
+/// until the player has answered.
+///
+/// # Examples
+///
+/// ```no_run
+/// use kickbeats_core::generator::{MysteryBag, PatternGenerator};
+/// use kickbeats_core::models::{TimeSignature, ComplexityLevel};
+/// use std::collections::VecDeque;
+///
+/// let mut bag = MysteryBag::standard();
+/// let time_sig = TimeSignature::four_four();
+/// let (pattern, _) = bag.generate_unique(time_sig, ComplexityLevel::Medium, &VecDeque::new())?;
+/// println!("from {}", bag.last_source().unwrap());
+/// # Ok::<(), kickbeats_core::CoreError>(())
+/// ```
+pub struct MysteryBag<R: RngCore = DefaultRng> {
+    /// Random number generator used to pick sources
+    rng: R,
+    /// Generators to draw fresh patterns from
+    sources: Vec<Source>,
+    /// Missed patterns waiting to be replayed, oldest first
+    review: VecDeque<Pattern>,
+    /// Likelihood of drawing from the review queue when it has a pattern
+    review_weight: f32,
+    /// Name of the source of the most recent pattern
+    last_source: Option<String>,
+}
+
+#[cfg(feature = "std")]
+impl MysteryBag {
+    /// Create an empty bag; add sources with `with_source`
+    pub fn new() -> Self {
+        Self::with_rng(entropy_rng())
+    }
+
+    /// Bag with every built-in generator: weighted, Euclidean, and each style
+    pub fn standard() -> Self {
+        use crate::generator::{EuclideanGenerator, Style, StyleGenerator, WeightedGenerator};
+
+        let mut bag = Self::new()
+            .with_source("weighted", Box::new(WeightedGenerator::new()), 3.0)
+            .with_source("euclidean", Box::new(EuclideanGenerator::new()), 1.0);
+        for style in Style::ALL {
+            bag = bag.with_source(
+                &format!("{} style", style.name()),
+                Box::new(StyleGenerator::new(style)),
+                1.0,
+            );
+        }
+        bag
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for MysteryBag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: RngCore> MysteryBag<R> {
+    /// Create an empty bag picking sources with the given random source
+    pub fn with_rng(rng: R) -> Self {
+        Self {
+            rng,
+            sources: Vec::new(),
+            review: VecDeque::new(),
+            review_weight: DEFAULT_REVIEW_WEIGHT,
+            last_source: None,
+        }
+    }
+
+    /// Add a named generator drawn with the given relative weight
+    pub fn with_source(
+        mut self,
+        name: &str,
+        generator: Box<dyn PatternGenerator>,
+        weight: f32,
+    ) -> Self {
+        self.sources.push(Source {
+            name: name.to_string(),
+            generator,
+            weight,
+        });
+        self
+    }
+
+    /// Set how likely a queued review pattern is drawn, relative to sources
+    pub fn with_review_weight(mut self, weight: f32) -> Self {
+        self.review_weight = weight;
+        self
+    }
+
+    /// Number of missed patterns waiting for review
+    pub fn review_len(&self) -> usize {
+        self.review.len()
+    }
+
+    /// Pick a source and draw a pattern from it
+    ///
+    /// `None` in the candidate list stands for the review queue, which is
+    /// offered only when `allow_review` is set.
+    fn draw(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+        allow_review: bool,
+    ) -> Result<(Pattern, u32), CoreError> {
+        let review_idx = self
+            .review
+            .iter()
+            .position(|p| p.time_signature.same_meter(&time_signature));
+
+        let mut candidates: Vec<Option<usize>> = (0..self.sources.len()).map(Some).collect();
+        if allow_review && review_idx.is_some() {
+            candidates.push(None);
+        }
+
+        while !candidates.is_empty() {
+            let weights: Vec<f32> = candidates
+                .iter()
+                .map(|c| match c {
+                    Some(i) => self.sources[*i].weight,
+                    None => self.review_weight,
+                })
+                .collect();
+            let Ok(dist) = WeightedIndex::new(&weights) else {
+                break;
+            };
+            let pick = dist.sample(&mut self.rng);
+
+            let result = match candidates[pick] {
+                None => {
+                    let mut pattern = review_idx
+                        .and_then(|idx| self.review.remove(idx))
+                        .ok_or_else(|| {
+                            CoreError::Generation("Review queue is empty".to_string())
+                        })?;
+                    pattern.regroup(time_signature);
+                    self.last_source = Some(REVIEW_SOURCE.to_string());
+                    // A replayed pattern is already in the history
+                    return Ok((pattern, 0));
+                }
+                Some(i) => {
+                    let source = &mut self.sources[i];
+                    let result = if allow_review {
+                        source
+                            .generator
+                            .generate_unique(time_signature, complexity, history)
+                    } else {
+                        source
+                            .generator
+                            .generate(time_signature, complexity, history)
+                            .map(|p| (p, 3))
+                    };
+                    result.map(|r| (r, source.name.clone()))
+                }
+            };
+
+            match result {
+                Ok((generated, name)) => {
+                    self.last_source = Some(name);
+                    return Ok(generated);
+                }
+                // This source can't serve these settings; try the others
+                Err(_) => {
+                    candidates.remove(pick);
+                }
+            }
+        }
+
+        Err(CoreError::Generation(format!(
+            "No pattern source could generate a {}/{} pattern",
+            time_signature.numerator, time_signature.denominator
+        )))
+    }
+}
+
+impl<R: RngCore + Send> PatternGenerator for MysteryBag<R> {
+    /// Draws from the generators only; review patterns repeat the history
+    fn generate(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<Pattern, CoreError> {
+        self.draw(time_signature, complexity, history, false)
+            .map(|(pattern, _)| pattern)
+    }
+
+    /// Draws from the generators or the review queue; a review pattern
+    /// reports constraint 0 since it deliberately repeats
+    fn generate_unique(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<(Pattern, u32), CoreError> {
+        self.draw(time_signature, complexity, history, true)
+    }
+
+    /// Queue imperfect answers for review; a perfect answer clears the pattern
+    fn record_result(&mut self, pattern: &Pattern, accuracy: f32) {
+        if accuracy >= 1.0 {
+            self.review.retain(|p| p.id != pattern.id);
+        } else if !self.review.iter().any(|p| p.id == pattern.id) {
+            if self.review.len() >= REVIEW_CAPACITY {
+                self.review.pop_front();
+            }
+            self.review.push_back(pattern.clone());
+        }
+    }
+
+    fn last_source(&self) -> Option<&str> {
+        self.last_source.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{GrooveGenerator, Style, StyleGenerator, WeightedGenerator};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Seeded, so the tests also run without `std`
+    fn bag() -> MysteryBag<StdRng> {
+        MysteryBag::with_rng(StdRng::seed_from_u64(7))
+    }
+
+    fn weighted() -> Box<dyn PatternGenerator> {
+        Box::new(WeightedGenerator::with_rng(StdRng::seed_from_u64(8)))
+    }
+
+    fn rock() -> Box<dyn PatternGenerator> {
+        Box::new(StyleGenerator::with_rng(
+            Style::Rock,
+            StdRng::seed_from_u64(9),
+        ))
+    }
+
+    #[test]
+    fn test_reports_source() {
+        let mut bag = bag().with_source("weighted", weighted(), 1.0);
+        assert_eq!(bag.last_source(), None);
+
+        bag.generate(
+            TimeSignature::four_four(),
+            ComplexityLevel::Medium,
+            &VecDeque::new(),
+        )
+        .unwrap();
+        assert_eq!(bag.last_source(), Some("weighted"));
+    }
+
+    #[test]
+    fn test_skips_sources_that_cannot_serve_meter() {
+        let mut bag =
+            bag()
+                .with_source("rock style", rock(), 100.0)
+                .with_source("weighted", weighted(), 1.0);
+
+        for _ in 0..10 {
+            bag.generate_unique(
+                TimeSignature::three_four(),
+                ComplexityLevel::Medium,
+                &VecDeque::new(),
+            )
+            .unwrap();
+            assert_eq!(bag.last_source(), Some("weighted"));
+        }
+    }
+
+    #[test]
+    fn test_errors_when_no_source_fits() {
+        let mut bag = bag().with_source("rock style", rock(), 1.0);
+        assert!(bag
+            .generate(
+                TimeSignature::three_four(),
+                ComplexityLevel::Medium,
+                &VecDeque::new(),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_missed_patterns_come_back_for_review() {
+        let mut bag = bag()
+            .with_source("weighted", weighted(), 1.0)
+            .with_review_weight(1000.0);
+        let time_sig = TimeSignature::four_four();
+
+        let missed = bag
+            .generate(time_sig, ComplexityLevel::Medium, &VecDeque::new())
+            .unwrap();
+        bag.record_result(&missed, 0.75);
+        bag.record_result(&missed, 0.5);
+        assert_eq!(bag.review_len(), 1);
+
+        let (pattern, constraint) = bag
+            .generate_unique(time_sig, ComplexityLevel::Medium, &VecDeque::new())
+            .unwrap();
+        assert_eq!(pattern.id, missed.id);
+        assert_eq!(constraint, 0);
+        assert_eq!(bag.last_source(), Some(REVIEW_SOURCE));
+        assert_eq!(bag.review_len(), 0);
+
+        // A perfect answer keeps it out of the queue
+        bag.record_result(&missed, 0.5);
+        bag.record_result(&missed, 1.0);
+        assert_eq!(bag.review_len(), 0);
+    }
+
+    #[test]
+    fn test_draws_imported_grooves() {
+        // One 4/4 bar of kicks on channel 10 at 96 ticks per quarter: 1 and 3
+        let track = [
+            0x00, 0x99, 36, 100, 0x81, 0x40, 0x99, 36, 100, 0x00, 0xFF, 0x2F, 0x00,
+        ];
+        let mut file = b"MThd".to_vec();
+        file.extend(6u32.to_be_bytes());
+        file.extend([0, 0, 0, 1, 0, 96]);
+        file.extend(b"MTrk");
+        file.extend((track.len() as u32).to_be_bytes());
+        file.extend(track);
+
+        let mut grooves = GrooveGenerator::with_rng(StdRng::seed_from_u64(10));
+        grooves.add_midi(&file).unwrap();
+        let mut bag = bag()
+            .with_source(GROOVE_SOURCE, Box::new(grooves), 1000.0)
+            .with_source("weighted", weighted(), 1.0);
+
+        let pattern = bag
+            .generate(
+                TimeSignature::four_four(),
+                ComplexityLevel::Simple,
+                &VecDeque::new(),
+            )
+            .unwrap();
+        assert_eq!(bag.last_source(), Some(GROOVE_SOURCE));
+        assert_eq!(pattern.note_positions(), alloc::vec![0, 8]);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::error::CoreError;
+use crate::generator::traits::PatternGenerator;
+use crate::models::{ComplexityLevel, Pattern, TimeSignature};
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Plays a fixed list of patterns in order, starting over after the last
+///
+/// Each pattern keeps its own time signature; the requested one is ignored.
+/// A teacher's playlist is in the order they chose, so nothing is skipped for
+/// being too close to recent history.
+#[derive(Debug, Clone)]
+pub struct PlaylistGenerator {
+    patterns: Vec<(Vec<bool>, TimeSignature)>,
+    next: usize,
+}
+
+impl PlaylistGenerator {
+    /// Create a playlist from steps and their time signatures
+    pub fn new(patterns: Vec<(Vec<bool>, TimeSignature)>) -> Result<Self, CoreError> {
+        if patterns.is_empty() {
+            return Err(CoreError::Config(String::from(
+                "A playlist needs at least one pattern",
+            )));
+        }
+        Ok(Self { patterns, next: 0 })
+    }
+
+    /// Number of patterns in the playlist
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Whether the playlist has no patterns
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+}
+
+impl PatternGenerator for PlaylistGenerator {
+    fn generate(
+        &mut self,
+        _time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        _history: &VecDeque<Pattern>,
+    ) -> Result<Pattern, CoreError> {
+        let (steps, time_signature) = &self.patterns[self.next];
+        self.next = (self.next + 1) % self.patterns.len();
+        Ok(Pattern::new(steps.clone(), *time_signature, complexity))
+    }
+
+    /// Always the next pattern in the list, reported as meeting the full constraint
+    fn generate_unique(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<(Pattern, u32), CoreError> {
+        Ok((self.generate(time_signature, complexity, history)?, 3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_plays_in_order_and_repeats() {
+        let waltz = TimeSignature::new(3, 4);
+        let mut playlist = PlaylistGenerator::new(vec![
+            (vec![true; 16], TimeSignature::four_four()),
+            (vec![false; 12], waltz),
+        ])
+        .unwrap();
+        let history = VecDeque::new();
+        let four_four = TimeSignature::four_four();
+
+        let mut next = || {
+            playlist
+                .generate_unique(four_four, ComplexityLevel::Simple, &history)
+                .unwrap()
+                .0
+        };
+        assert_eq!(next().steps, vec![true; 16]);
+        let second = next();
+        assert_eq!(second.steps, vec![false; 12]);
+        assert_eq!(second.time_signature, waltz);
+        assert_eq!(next().steps, vec![true; 16]);
+    }
+
+    #[test]
+    fn test_rejects_empty_playlist() {
+        assert!(PlaylistGenerator::new(Vec::new()).is_err());
+    }
+}
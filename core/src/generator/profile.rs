@@ -0,0 +1,187 @@
+use crate::error::CoreError;
+use crate::models::{BeatGrid, TimeSignature};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::vec::Vec;
+
+/// User-defined metrical weight tables, one per time signature
+///
+/// Each table replaces the built-in [`BeatGrid`] weights for its time signature
+/// (one weight per sixteenth position); complexity adjustments are still applied
+/// on top. Time signatures without a table fall back to the built-in weights.
+///
+/// Profiles are loaded from TOML with one array per time signature:
+///
+/// ```toml
+/// # Emphasize the "e" of each beat
+/// "4/4" = [1.0, 0.8, 0.3, 0.1, 0.6, 0.8, 0.3, 0.1, 0.7, 0.8, 0.3, 0.1, 0.6, 0.8, 0.3, 0.1]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WeightProfiles {
+    /// Weight tables keyed by (numerator, denominator)
+    tables: BTreeMap<(u8, u8), Vec<f32>>,
+}
+
+impl WeightProfiles {
+    /// Create an empty set of profiles (built-in weights everywhere)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace the weight table for a time signature
+    ///
+    /// The table must have one non-negative weight per position of the time
+    /// signature's sixteenth-note grid, and at least one non-zero weight.
+    pub fn insert(
+        &mut self,
+        time_signature: TimeSignature,
+        weights: Vec<f32>,
+    ) -> Result<(), CoreError> {
+        let expected = BeatGrid::new(time_signature, 16, 1).total_positions();
+        if weights.len() != expected {
+            return Err(CoreError::Config(format!(
+                "Weights for {}/{} must have {} entries (one per sixteenth), found {}",
+                time_signature.numerator,
+                time_signature.denominator,
+                expected,
+                weights.len()
+            )));
+        }
+
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            return Err(CoreError::Config(format!(
+                "Weights for {}/{} must be non-negative numbers",
+                time_signature.numerator, time_signature.denominator
+            )));
+        }
+
+        if weights.iter().all(|w| *w == 0.0) {
+            return Err(CoreError::Config(format!(
+                "Weights for {}/{} must include at least one non-zero value",
+                time_signature.numerator, time_signature.denominator
+            )));
+        }
+
+        self.tables.insert(
+            (time_signature.numerator, time_signature.denominator),
+            weights,
+        );
+        Ok(())
+    }
+
+    /// Weight table for a time signature, if one was defined
+    pub fn get(&self, time_signature: TimeSignature) -> Option<&[f32]> {
+        self.tables
+            .get(&(time_signature.numerator, time_signature.denominator))
+            .map(Vec::as_slice)
+    }
+
+    /// Every custom table with its time signature, in time signature order
+    pub fn iter(&self) -> impl Iterator<Item = (TimeSignature, &[f32])> {
+        self.tables
+            .iter()
+            .map(|(&(numerator, denominator), weights)| {
+                (
+                    TimeSignature::new(numerator, denominator),
+                    weights.as_slice(),
+                )
+            })
+    }
+
+    /// Whether no time signature has a custom table
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// Parse profiles from a TOML document of `"N/D" = [weights...]` entries
+    #[cfg(feature = "config")]
+    pub fn from_toml_str(s: &str) -> Result<Self, CoreError> {
+        let raw: BTreeMap<String, Vec<f32>> = toml::from_str(s)
+            .map_err(|e| CoreError::Config(format!("Invalid weights file: {}", e)))?;
+
+        let mut profiles = Self::new();
+        for (key, weights) in raw {
+            let time_signature = key.parse::<TimeSignature>().map_err(CoreError::Config)?;
+            profiles.insert(time_signature, weights)?;
+        }
+        Ok(profiles)
+    }
+
+    /// Load profiles from a TOML file
+    #[cfg(feature = "config")]
+    pub fn load(path: &std::path::Path) -> Result<Self, CoreError> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            CoreError::Config(format!(
+                "Failed to read weights file {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::from_toml_str(&contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut profiles = WeightProfiles::new();
+        assert!(profiles.is_empty());
+
+        let weights = vec![1.0, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5];
+        profiles
+            .insert(TimeSignature::three_four(), weights.clone())
+            .unwrap();
+
+        assert_eq!(
+            profiles.get(TimeSignature::three_four()),
+            Some(&weights[..])
+        );
+        assert_eq!(profiles.get(TimeSignature::four_four()), None);
+    }
+
+    #[test]
+    fn test_insert_rejects_wrong_length() {
+        let mut profiles = WeightProfiles::new();
+        let err = profiles
+            .insert(TimeSignature::four_four(), vec![1.0; 12])
+            .unwrap_err();
+        assert!(err.message().contains("16 entries"));
+    }
+
+    #[test]
+    fn test_insert_rejects_negative_and_all_zero() {
+        let mut profiles = WeightProfiles::new();
+        let mut weights = vec![0.5; 16];
+        weights[3] = -0.1;
+        assert!(profiles
+            .insert(TimeSignature::four_four(), weights)
+            .is_err());
+        assert!(profiles
+            .insert(TimeSignature::four_four(), vec![0.0; 16])
+            .is_err());
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_from_toml_str() {
+        let toml = r#"
+            "4/4" = [1.0, 0.8, 0.3, 0.1, 0.6, 0.8, 0.3, 0.1, 0.7, 0.8, 0.3, 0.1, 0.6, 0.8, 0.3, 0.1]
+            "6/8" = [1, 0.1, 0.2, 0.1, 0.2, 0.1, 0.8, 0.1, 0.2, 0.1, 0.2, 0.1]
+        "#;
+        let profiles = WeightProfiles::from_toml_str(toml).unwrap();
+
+        assert_eq!(profiles.get(TimeSignature::four_four()).unwrap()[1], 0.8);
+        assert_eq!(profiles.get(TimeSignature::six_eight()).unwrap().len(), 12);
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_from_toml_str_rejects_bad_time_signature() {
+        let err = WeightProfiles::from_toml_str(r#""4-4" = [1.0]"#).unwrap_err();
+        assert!(err.message().contains("Invalid time signature"));
+    }
+}
@@ -0,0 +1,304 @@
+use crate::error::CoreError;
+use crate::models::{ComplexityLevel, ComplexityParams, TimeSignature, Violation};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A constraint that can throw out a generated candidate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Constraint {
+    /// Too few or too many kicks (density, or a custom kick range)
+    Density,
+    /// Too many kicks in a row
+    Consecutive,
+    /// No 2-step rest, or more than 8 rests in a row
+    Rests,
+    /// Too close to a recent pattern
+    Uniqueness,
+}
+
+/// Counts of why a generator threw candidates out, to explain a failure
+///
+/// Instead of a bare "failed after N attempts", [`explain`](Self::explain)
+/// says which constraint rejected the most candidates and how to relax it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Rejections {
+    /// Candidates drawn
+    pub candidates: u32,
+    /// Candidates that broke the kick count or density rule
+    pub density: u32,
+    /// Candidates with too many kicks in a row
+    pub consecutive: u32,
+    /// Candidates without a 2-step rest or with more than 8 rests in a row
+    pub rests: u32,
+    /// Valid candidates too close to a recent pattern
+    pub uniqueness: u32,
+}
+
+impl Rejections {
+    /// Start counting
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a candidate drawn
+    pub fn draw(&mut self) {
+        self.candidates += 1;
+    }
+
+    /// Count a candidate that broke `violation`
+    pub fn reject(&mut self, violation: Violation) {
+        match violation {
+            Violation::Density(_) | Violation::KickCount { .. } | Violation::NoKicks => {
+                self.density += 1
+            }
+            Violation::ConsecutiveKicks(_) => self.consecutive += 1,
+            Violation::NoLongRest | Violation::LongRestRun => self.rests += 1,
+            // Generators always place the downbeat; count it with the kicks
+            Violation::NoDownbeat => self.density += 1,
+        }
+    }
+
+    /// Count a valid candidate too close to a recent pattern
+    pub fn duplicate(&mut self) {
+        self.uniqueness += 1;
+    }
+
+    /// Whether any candidate passed every rule but uniqueness, i.e. whether
+    /// relaxing uniqueness could help
+    pub fn any_valid(&self) -> bool {
+        self.uniqueness > 0
+    }
+
+    /// Each constraint with the candidates it rejected, most first, leaving
+    /// out those that rejected none
+    pub fn ranked(&self) -> Vec<(Constraint, u32)> {
+        let mut ranked: Vec<(Constraint, u32)> = [
+            (Constraint::Density, self.density),
+            (Constraint::Consecutive, self.consecutive),
+            (Constraint::Rests, self.rests),
+            (Constraint::Uniqueness, self.uniqueness),
+        ]
+        .into_iter()
+        .filter(|&(_, count)| count > 0)
+        .collect();
+        // Stable, so ties keep the order above
+        ranked.sort_by_key(|&(_, count)| core::cmp::Reverse(count));
+        ranked
+    }
+
+    /// The constraint that rejected the most candidates
+    pub fn worst(&self) -> Option<Constraint> {
+        self.ranked().first().map(|&(constraint, _)| constraint)
+    }
+
+    /// Why no pattern came out, and what to relax, for candidates generated
+    /// to `params` (`custom` when they were set by hand rather than a level)
+    ///
+    /// e.g. "Failed to generate a pattern after 3000 candidates: 2410 had
+    /// more than 1 kick in a row, 590 were too close to recent patterns. Try
+    /// allowing longer runs (--allow-consecutive 2), ..."
+    pub fn explain(&self, params: &ComplexityParams, custom: bool) -> String {
+        let reasons: Vec<String> = self
+            .ranked()
+            .into_iter()
+            .map(|(constraint, count)| format!("{} {}", count, reason(constraint, params, custom)))
+            .collect();
+        let mut message = format!(
+            "Failed to generate a pattern after {} candidates",
+            self.candidates
+        );
+        if !reasons.is_empty() {
+            message.push_str(": ");
+            message.push_str(&reasons.join(", "));
+        }
+        if let Some(worst) = self.worst() {
+            message.push_str(". Try ");
+            message.push_str(&suggestion(worst, params, custom));
+        }
+        message
+    }
+}
+
+/// Calls that fail straight away after settings broke every rule, before
+/// the next real search
+pub const RETRY_INTERVAL: u32 = 8;
+
+/// Rate limit on regenerating with settings where no candidate passed the
+/// pattern rules
+///
+/// History doesn't matter to such a failure, so searching again right away
+/// would most likely draw thousands more candidates to the same end. After
+/// one, the guard fails calls with the same meter and complexity straight
+/// away with the same explanation, and lets one in every
+/// [`RETRY_INTERVAL`] through to search again.
+#[derive(Debug, Clone, Default)]
+pub struct RetryGuard {
+    /// The settings that failed, their explanation, and the calls failed
+    /// straight away since
+    failed: Option<(TimeSignature, ComplexityLevel, String, u32)>,
+}
+
+impl RetryGuard {
+    /// Start with nothing failed
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail straight away with the last explanation if these settings failed
+    /// every rule recently; `Ok` means search
+    pub fn check(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+    ) -> Result<(), CoreError> {
+        match &mut self.failed {
+            Some((failed_signature, failed_complexity, message, skipped))
+                if *failed_signature == time_signature
+                    && *failed_complexity == complexity
+                    && *skipped + 1 < RETRY_INTERVAL =>
+            {
+                *skipped += 1;
+                Err(CoreError::Generation(message.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Note a failed search with these settings, holding off the next ones
+    /// if no candidate passed the rules
+    pub fn fail(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        rejections: &Rejections,
+        message: &str,
+    ) {
+        self.failed = (!rejections.any_valid())
+            .then(|| (time_signature, complexity, String::from(message), 0));
+    }
+
+    /// Note a search that found a pattern
+    pub fn pass(&mut self) {
+        self.failed = None;
+    }
+}
+
+/// What the candidates a constraint rejected had wrong
+fn reason(constraint: Constraint, params: &ComplexityParams, custom: bool) -> String {
+    match constraint {
+        Constraint::Density if custom => format!(
+            "had a measure outside {}-{} kicks",
+            params.min_kicks, params.max_kicks
+        ),
+        Constraint::Density => String::from("had too few or too many kicks"),
+        Constraint::Consecutive => format!(
+            "had more than {} kick{} in a row",
+            params.max_consecutive,
+            if params.max_consecutive == 1 { "" } else { "s" }
+        ),
+        Constraint::Rests => String::from("had no 2-step rest or more than 8 rests in a row"),
+        Constraint::Uniqueness => String::from("were too close to recent patterns"),
+    }
+}
+
+/// Concrete relaxation of the constraint that rejected the most candidates
+fn suggestion(constraint: Constraint, params: &ComplexityParams, custom: bool) -> String {
+    match constraint {
+        Constraint::Density if custom => String::from(
+            "a wider kick range (--kicks-min/--kicks-max), or weights that give more steps a chance",
+        ),
+        Constraint::Density => {
+            String::from("weights that give more steps a chance, or another --complexity")
+        }
+        Constraint::Consecutive => format!(
+            "allowing longer runs (--allow-consecutive {}), fewer kicks (--kicks-max), or less --syncopation",
+            params.max_consecutive + 1
+        ),
+        Constraint::Rests => String::from(
+            "a kick range that leaves room for rests (--kicks-min/--kicks-max), or weights that spread kicks out",
+        ),
+        Constraint::Uniqueness => String::from(
+            "a wider kick range or more --syncopation for more variety, or another --complexity",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranks_constraints_by_rejections() {
+        let mut rejections = Rejections::new();
+        for _ in 0..3 {
+            rejections.draw();
+            rejections.reject(Violation::ConsecutiveKicks(1));
+        }
+        rejections.draw();
+        rejections.duplicate();
+
+        assert_eq!(
+            rejections.ranked(),
+            vec![(Constraint::Consecutive, 3), (Constraint::Uniqueness, 1)]
+        );
+        assert_eq!(rejections.worst(), Some(Constraint::Consecutive));
+        assert!(rejections.any_valid());
+    }
+
+    #[test]
+    fn test_explains_the_worst_constraint() {
+        let mut rejections = Rejections::new();
+        for _ in 0..4 {
+            rejections.draw();
+        }
+        rejections.reject(Violation::ConsecutiveKicks(1));
+        rejections.reject(Violation::ConsecutiveKicks(1));
+        rejections.reject(Violation::KickCount {
+            kicks: 5,
+            min: 3,
+            max: 3,
+        });
+        rejections.duplicate();
+        let params = ComplexityParams::new(3, 3, 0.5, 1).unwrap();
+
+        assert_eq!(
+            rejections.explain(&params, true),
+            "Failed to generate a pattern after 4 candidates: 2 had more than 1 kick in a row, \
+             1 had a measure outside 3-3 kicks, 1 were too close to recent patterns. \
+             Try allowing longer runs (--allow-consecutive 2), fewer kicks (--kicks-max), or less --syncopation"
+        );
+    }
+
+    #[test]
+    fn test_guard_holds_off_only_after_every_rule_failed() {
+        let four_four = TimeSignature::four_four();
+        let medium = ComplexityLevel::Medium;
+        let mut guard = RetryGuard::new();
+        assert!(guard.check(four_four, medium).is_ok());
+
+        // Valid candidates too close to history: new history may help
+        let mut close = Rejections::new();
+        close.draw();
+        close.duplicate();
+        guard.fail(four_four, medium, &close, "too close");
+        assert!(guard.check(four_four, medium).is_ok());
+
+        let mut broken = Rejections::new();
+        broken.draw();
+        broken.reject(Violation::NoLongRest);
+        guard.fail(four_four, medium, &broken, "no rests");
+        for _ in 1..RETRY_INTERVAL {
+            assert_eq!(
+                guard.check(four_four, medium),
+                Err(CoreError::Generation(String::from("no rests")))
+            );
+        }
+        assert!(guard.check(TimeSignature::three_four(), medium).is_ok());
+        assert!(guard.check(four_four, medium).is_ok());
+
+        guard.fail(four_four, medium, &broken, "no rests");
+        guard.pass();
+        assert!(guard.check(four_four, medium).is_ok());
+    }
+}
@@ -0,0 +1,217 @@
+use crate::error::CoreError;
+use crate::generator::templates::{parse_template, Style};
+#[cfg(feature = "std")]
+use crate::generator::weighted::entropy_rng;
+use crate::generator::{is_pattern_unique, random_id, DefaultRng, PatternGenerator};
+use crate::models::{ComplexityLevel, Pattern, TimeSignature};
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::ToString;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+
+/// Generates idiomatic patterns from a style's curated templates
+///
+/// Each pattern starts from a random seed template of the style and toggles a
+/// few kicks, drawn from the style's variation weights, so results stay in the
+/// genre while still varying. More complex settings apply more variations.
+///
+/// # Examples
+///
+/// ```no_run
+/// use kickbeats_core::generator::{PatternGenerator, Style, StyleGenerator};
+/// use kickbeats_core::models::{TimeSignature, ComplexityLevel};
+/// use std::collections::VecDeque;
+///
+/// let mut generator = StyleGenerator::new(Style::Funk);
+/// let time_sig = TimeSignature::four_four();
+/// let pattern = generator.generate(time_sig, ComplexityLevel::Medium, &VecDeque::new())?;
+/// # Ok::<(), kickbeats_core::CoreError>(())
+/// ```
+pub struct StyleGenerator<R: RngCore = DefaultRng> {
+    /// Random number generator
+    rng: R,
+    /// Style whose templates are used
+    style: Style,
+}
+
+impl StyleGenerator {
+    /// Create a new generator for a style
+    #[cfg(feature = "std")]
+    pub fn new(style: Style) -> Self {
+        Self::with_rng(style, entropy_rng())
+    }
+}
+
+impl<R: RngCore> StyleGenerator<R> {
+    /// Create a generator drawing from the given random source
+    pub fn with_rng(style: Style, rng: R) -> Self {
+        Self { rng, style }
+    }
+
+    /// Range of kick toggles applied to a seed for a complexity level
+    fn variations_for_complexity(complexity: ComplexityLevel) -> (usize, usize) {
+        match complexity {
+            ComplexityLevel::Simple => (0, 1),
+            ComplexityLevel::Medium => (1, 2),
+            ComplexityLevel::Complex => (2, 4),
+        }
+    }
+
+    /// Build one candidate: a random seed with weighted kick toggles
+    fn candidate(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+    ) -> Result<Pattern, CoreError> {
+        if (time_signature.numerator, time_signature.denominator) != (4, 4) {
+            return Err(CoreError::Config(format!(
+                "Style templates are only available in 4/4 (got {}/{})",
+                time_signature.numerator, time_signature.denominator
+            )));
+        }
+
+        let template = self.style.template();
+        let seed = template
+            .seeds
+            .choose(&mut self.rng)
+            .ok_or_else(|| CoreError::Config("Style has no templates".to_string()))?;
+        let mut steps = parse_template(seed);
+
+        let dist = WeightedIndex::new(template.variation_weights).map_err(|e| {
+            CoreError::Config(format!("Failed to create weighted distribution: {}", e))
+        })?;
+        let (min, max) = Self::variations_for_complexity(complexity);
+        let variations = self.rng.gen_range(min..=max);
+        for _ in 0..variations {
+            let idx = dist.sample(&mut self.rng);
+            steps[idx] = !steps[idx];
+        }
+
+        let id = random_id(&mut self.rng);
+        Ok(Pattern::with_id(id, steps, time_signature, complexity))
+    }
+
+    /// Try up to 100 candidates for one that is valid and unique enough
+    fn try_generate_with_distance(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+        min_distance: u32,
+    ) -> Result<Pattern, CoreError> {
+        for _ in 0..100 {
+            let pattern = self.candidate(time_signature, complexity)?;
+
+            if pattern.validate_steps().is_err() || self.style.check(&pattern.steps).is_err() {
+                continue;
+            }
+
+            if is_pattern_unique(&pattern, history, min_distance) {
+                return Ok(pattern);
+            }
+        }
+
+        Err(CoreError::Generation(format!(
+            "Failed to generate {:?} pattern with distance >= {}",
+            self.style, min_distance
+        )))
+    }
+}
+
+impl<R: RngCore + Send> PatternGenerator for StyleGenerator<R> {
+    fn generate(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<Pattern, CoreError> {
+        self.try_generate_with_distance(time_signature, complexity, history, 3)
+    }
+
+    /// Relaxes the distance 3 → 2 → 1 like the weighted generator; a style
+    /// has few seeds, so at low complexity it finally repeats a pattern
+    /// (constraint 0) rather than failing
+    fn generate_unique(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<(Pattern, u32), CoreError> {
+        for min_distance in [3, 2, 1, 0] {
+            if let Ok(pattern) =
+                self.try_generate_with_distance(time_signature, complexity, history, min_distance)
+            {
+                return Ok((pattern, min_distance));
+            }
+        }
+
+        Err(CoreError::Generation(format!(
+            "Failed to generate a valid {:?} pattern",
+            self.style
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Seeded, so the tests also run without `std`
+    fn generator(style: Style) -> StyleGenerator<StdRng> {
+        StyleGenerator::with_rng(style, StdRng::seed_from_u64(7))
+    }
+
+    #[test]
+    fn test_generated_patterns_follow_style_rules() {
+        for style in Style::ALL {
+            let mut gen = generator(style);
+            let mut history = VecDeque::new();
+
+            for _ in 0..10 {
+                let (pattern, _) = gen
+                    .generate_unique(
+                        TimeSignature::four_four(),
+                        ComplexityLevel::Complex,
+                        &history,
+                    )
+                    .unwrap();
+                assert!(pattern.validate_steps().is_ok());
+                assert!(style.check(&pattern.steps).is_ok(), "{:?}", style);
+                history.push_back(pattern);
+            }
+        }
+    }
+
+    #[test]
+    fn test_four_on_floor_keeps_quarter_notes() {
+        let mut gen = generator(Style::FourOnFloor);
+        let pattern = gen
+            .generate(
+                TimeSignature::four_four(),
+                ComplexityLevel::Complex,
+                &VecDeque::new(),
+            )
+            .unwrap();
+
+        for pos in [0, 4, 8, 12] {
+            assert!(pattern.steps[pos]);
+        }
+    }
+
+    #[test]
+    fn test_rejects_other_time_signatures() {
+        let mut gen = generator(Style::Rock);
+        let err = gen
+            .generate(
+                TimeSignature::three_four(),
+                ComplexityLevel::Medium,
+                &VecDeque::new(),
+            )
+            .unwrap_err();
+        assert!(err.message().contains("only available in 4/4"));
+    }
+}
@@ -0,0 +1,247 @@
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::String;
+use core::str::FromStr;
+
+/// Musical style used to pick idiomatic pattern templates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Straight eighth-note rock beats, kicks around the 2 and 4 backbeat
+    Rock,
+    /// Syncopated sixteenth-note funk
+    Funk,
+    /// Tresillo-based Latin figures
+    Latin,
+    /// Drum and bass two-step
+    Dnb,
+    /// Kick on every quarter note
+    FourOnFloor,
+}
+
+/// Curated seed patterns and variation rules for one style (4/4, sixteenths)
+#[derive(Debug)]
+pub struct StyleTemplate {
+    /// Seed patterns as `x`/`.` strings, 16 steps each
+    pub seeds: &'static [&'static str],
+    /// Positions that must always keep a kick
+    pub anchors: &'static [usize],
+    /// Positions that must never have a kick (e.g., the snare backbeat)
+    pub forbidden: &'static [usize],
+    /// Likelihood of each position being added or removed during variation
+    pub variation_weights: [f32; 16],
+    /// Maximum kicks per measure after variation
+    pub max_kicks: usize,
+}
+
+const ROCK: StyleTemplate = StyleTemplate {
+    seeds: &[
+        "x.......x.......",
+        "x.......x.x.....",
+        "x......xx.......",
+        "x.x.....x.x.....",
+        "x.......x.....x.",
+    ],
+    anchors: &[0, 8],
+    forbidden: &[4, 12],
+    variation_weights: [
+        0.0, 0.1, 0.6, 0.2, 0.0, 0.1, 0.4, 0.5, 0.0, 0.1, 0.8, 0.2, 0.0, 0.1, 0.6, 0.3,
+    ],
+    max_kicks: 5,
+};
+
+const FUNK: StyleTemplate = StyleTemplate {
+    seeds: &[
+        "x..x......x..x..",
+        "x.x....x..x.....",
+        "x..x...x.x......",
+        "x......x..x..x..",
+    ],
+    anchors: &[0],
+    forbidden: &[4, 12],
+    variation_weights: [
+        0.0, 0.2, 0.5, 0.8, 0.0, 0.3, 0.7, 0.8, 0.4, 0.6, 0.8, 0.7, 0.0, 0.3, 0.8, 0.5,
+    ],
+    max_kicks: 7,
+};
+
+const LATIN: StyleTemplate = StyleTemplate {
+    seeds: &[
+        "x..x..x...x.....",
+        "x..x....x..x....",
+        "x.....x...x...x.",
+        "x..x..x.x..x..x.",
+    ],
+    anchors: &[0],
+    forbidden: &[],
+    variation_weights: [
+        0.0, 0.1, 0.2, 0.8, 0.2, 0.1, 0.8, 0.2, 0.5, 0.1, 0.6, 0.8, 0.2, 0.1, 0.8, 0.2,
+    ],
+    max_kicks: 6,
+};
+
+const DNB: StyleTemplate = StyleTemplate {
+    seeds: &[
+        "x.x.......x.....",
+        "x......x..x.....",
+        "x........xx.....",
+        "x.x.......x..x..",
+    ],
+    anchors: &[0, 10],
+    forbidden: &[4, 12],
+    variation_weights: [
+        0.0, 0.1, 0.6, 0.2, 0.0, 0.1, 0.3, 0.6, 0.2, 0.4, 0.0, 0.4, 0.0, 0.2, 0.6, 0.2,
+    ],
+    max_kicks: 5,
+};
+
+const FOUR_ON_FLOOR: StyleTemplate = StyleTemplate {
+    seeds: &[
+        "x...x...x...x...",
+        "x...x...x...x..x",
+        "x...x...x.x.x...",
+        "x...x..xx...x...",
+    ],
+    anchors: &[0, 4, 8, 12],
+    forbidden: &[],
+    variation_weights: [
+        0.0, 0.1, 0.5, 0.4, 0.0, 0.1, 0.5, 0.4, 0.0, 0.1, 0.5, 0.4, 0.0, 0.1, 0.5, 0.6,
+    ],
+    max_kicks: 7,
+};
+
+impl Style {
+    /// All styles, in display order
+    pub const ALL: [Style; 5] = [
+        Style::Rock,
+        Style::Funk,
+        Style::Latin,
+        Style::Dnb,
+        Style::FourOnFloor,
+    ];
+
+    /// Name used on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Style::Rock => "rock",
+            Style::Funk => "funk",
+            Style::Latin => "latin",
+            Style::Dnb => "dnb",
+            Style::FourOnFloor => "four-on-floor",
+        }
+    }
+
+    /// Seed templates and variation rules for this style
+    pub fn template(&self) -> &'static StyleTemplate {
+        match self {
+            Style::Rock => &ROCK,
+            Style::Funk => &FUNK,
+            Style::Latin => &LATIN,
+            Style::Dnb => &DNB,
+            Style::FourOnFloor => &FOUR_ON_FLOOR,
+        }
+    }
+
+    /// Check a 16-step pattern against this style's anchors, forbidden
+    /// positions, and kick limit
+    pub fn check(&self, steps: &[bool]) -> Result<(), CoreError> {
+        let template = self.template();
+
+        if let Some(&pos) = template.anchors.iter().find(|&&pos| !steps[pos]) {
+            return Err(CoreError::Generation(format!(
+                "{:?} pattern must keep a kick at position {}",
+                self, pos
+            )));
+        }
+
+        if let Some(&pos) = template.forbidden.iter().find(|&&pos| steps[pos]) {
+            return Err(CoreError::Generation(format!(
+                "{:?} pattern must not have a kick at position {}",
+                self, pos
+            )));
+        }
+
+        let kicks = steps.iter().filter(|&&s| s).count();
+        if kicks > template.max_kicks {
+            return Err(CoreError::Generation(format!(
+                "{:?} pattern has {} kicks (max {})",
+                self, kicks, template.max_kicks
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    /// Parse a style from string (e.g., "rock", "four-on-floor")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rock" => Ok(Style::Rock),
+            "funk" => Ok(Style::Funk),
+            "latin" => Ok(Style::Latin),
+            "dnb" => Ok(Style::Dnb),
+            "four-on-floor" => Ok(Style::FourOnFloor),
+            _ => Err(format!(
+                "Invalid style '{}'. Must be one of: rock, funk, latin, dnb, four-on-floor",
+                s
+            )),
+        }
+    }
+}
+
+/// Convert an `x`/`.` template string to steps
+pub fn parse_template(template: &str) -> alloc::vec::Vec<bool> {
+    template.chars().map(|c| c == 'x').collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, Pattern, TimeSignature};
+
+    #[test]
+    fn test_parse_style() {
+        assert_eq!("rock".parse::<Style>(), Ok(Style::Rock));
+        assert_eq!("Four-On-Floor".parse::<Style>(), Ok(Style::FourOnFloor));
+        assert!("polka".parse::<Style>().is_err());
+    }
+
+    #[test]
+    fn test_seed_templates_are_valid() {
+        for style in Style::ALL {
+            let template = style.template();
+            for seed in template.seeds {
+                assert_eq!(seed.len(), 16, "{:?} seed {}", style, seed);
+
+                let steps = parse_template(seed);
+                assert!(style.check(&steps).is_ok(), "{:?} seed {}", style, seed);
+
+                let pattern =
+                    Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium);
+                assert!(
+                    pattern.validate_steps().is_ok(),
+                    "{:?} seed {}",
+                    style,
+                    seed
+                );
+            }
+
+            for &pos in template.anchors.iter().chain(template.forbidden) {
+                assert_eq!(
+                    template.variation_weights[pos], 0.0,
+                    "{:?} pos {}",
+                    style, pos
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_rejects_backbeat_kick() {
+        let steps = parse_template("x...x...x.......");
+        assert!(Style::Rock.check(&steps).is_err());
+        assert!(Style::Latin.check(&steps).is_ok());
+    }
+}
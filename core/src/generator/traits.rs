@@ -0,0 +1,42 @@
+use crate::error::CoreError;
+use crate::models::{ComplexityLevel, Pattern, TimeSignature};
+use alloc::collections::VecDeque;
+
+/// Common interface for pattern generators
+///
+/// Lets the CLI and session code swap generation strategies (weighted,
+/// Euclidean, ...) while sharing the same uniqueness checks and playback path.
+/// Generators are `Send` so a session can generate its next patterns on a
+/// background thread.
+pub trait PatternGenerator: Send {
+    /// Generate a pattern that differs from every pattern in `history`
+    /// by at least 3 positions
+    fn generate(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<Pattern, CoreError>;
+
+    /// Generate a unique pattern, relaxing the uniqueness requirement if needed
+    ///
+    /// Returns (pattern, constraint_used) where constraint_used is the minimum
+    /// Hamming distance the pattern satisfies against `history`.
+    fn generate_unique(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<(Pattern, u32), CoreError>;
+
+    /// Report how accurately the player transcribed a generated pattern
+    ///
+    /// Generators that revisit missed patterns use this; others ignore it.
+    fn record_result(&mut self, _pattern: &Pattern, _accuracy: f32) {}
+
+    /// Where the most recent pattern came from, for generators that mix
+    /// several sources (e.g., "euclidean", "review")
+    fn last_source(&self) -> Option<&str> {
+        None
+    }
+}
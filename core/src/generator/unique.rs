@@ -1,5 +1,5 @@
 use crate::models::Pattern;
-use std::collections::VecDeque;
+use alloc::collections::VecDeque;
 
 /// Check if a pattern is sufficiently unique compared to history
 ///
@@ -36,9 +36,7 @@ mod tests {
     fn test_is_pattern_unique_empty_history() {
         let pattern = Pattern::new(
             vec![
-                true, false, false, false,
-                true, false, false, false,
-                true, false, false, false,
+                true, false, false, false, true, false, false, false, true, false, false, false,
                 true, false, false, false,
             ],
             TimeSignature::four_four(),
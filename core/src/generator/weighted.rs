@@ -0,0 +1,603 @@
+use crate::error::CoreError;
+use crate::generator::{
+    is_pattern_unique, random_id, PatternGenerator, Rejections, RetryGuard, WeightProfiles,
+};
+use crate::models::{
+    BeatGrid, ComplexityLevel, ComplexityParams, Pattern, TimeSignature, Violation,
+};
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{Rng, RngCore};
+
+/// Random source used by [`WeightedGenerator::new`]
+///
+/// With the `std` feature `new` seeds it from the OS; without it there is no
+/// entropy source, so callers seed it themselves via `with_rng`. `StdRng` is
+/// portable across platforms, so the same seed yields the same patterns
+/// natively and in WASM, and it can move to another thread, so a session can
+/// generate patterns in the background.
+pub type DefaultRng = rand::rngs::StdRng;
+
+/// A [`DefaultRng`] seeded from the OS
+#[cfg(feature = "std")]
+pub(crate) fn entropy_rng() -> DefaultRng {
+    rand::SeedableRng::from_entropy()
+}
+
+/// Generates rhythmic patterns using weighted probability
+///
+/// The generator creates kick drum patterns by assigning probability weights
+/// to each position in a beat grid based on metrical hierarchy (downbeats are
+/// more likely than off-beats). Complexity levels adjust these weights to create
+/// simpler or more syncopated patterns; [`WeightedGenerator::with_params`]
+/// replaces the levels' presets with a custom kick range and syncopation.
+///
+/// # Examples
+///
+/// ```no_run
+/// use kickbeats_core::generator::WeightedGenerator;
+/// use kickbeats_core::models::{TimeSignature, ComplexityLevel};
+/// use std::collections::VecDeque;
+///
+/// let mut generator = WeightedGenerator::new();
+/// let time_sig = TimeSignature::four_four();
+/// let pattern = generator.generate(time_sig, ComplexityLevel::Medium, &VecDeque::new())?;
+/// # Ok::<(), kickbeats_core::CoreError>(())
+/// ```
+pub struct WeightedGenerator<R: RngCore = DefaultRng> {
+    /// Random number generator
+    rng: R,
+    /// User-defined weight tables that override the built-in metrical weights
+    profiles: WeightProfiles,
+    /// Custom kick range, syncopation, and run length used instead of the
+    /// complexity level's preset
+    params: Option<ComplexityParams>,
+    /// Holds off searching again right after settings broke every rule
+    guard: RetryGuard,
+}
+
+impl WeightedGenerator {
+    /// Create a new weighted generator
+    #[cfg(feature = "std")]
+    pub fn new() -> Self {
+        Self::with_rng(entropy_rng())
+    }
+
+    /// Generate base metrical weights using BeatGrid
+    /// Returns weights for all positions based on time signature
+    fn base_weights(time_signature: TimeSignature) -> Vec<f32> {
+        let grid = BeatGrid::new(time_signature, 16, 1);
+        let total_positions = grid.total_positions();
+
+        (0..total_positions)
+            .map(|idx| grid.position_strength(idx))
+            .collect()
+    }
+
+    /// Generate base metrical weights for 4/4 time signature
+    /// Returns weights for 16 positions (one measure of sixteenth notes)
+    pub fn base_weights_4_4() -> Vec<f32> {
+        Self::base_weights(TimeSignature::four_four())
+    }
+}
+
+impl<R: RngCore> WeightedGenerator<R> {
+    /// Create a generator drawing from the given random source
+    pub fn with_rng(rng: R) -> Self {
+        Self {
+            rng,
+            profiles: WeightProfiles::new(),
+            params: None,
+            guard: RetryGuard::new(),
+        }
+    }
+
+    /// Use custom weight tables for the time signatures they define
+    pub fn with_profiles(mut self, profiles: WeightProfiles) -> Self {
+        self.profiles = profiles;
+        self
+    }
+
+    /// Generate to custom parameters instead of each complexity level's preset
+    pub fn with_params(mut self, params: ComplexityParams) -> Self {
+        self.params = Some(params);
+        self
+    }
+
+    /// Metrical weights for a time signature: the custom profile if one is
+    /// defined, otherwise the built-in weights
+    fn weights_for(&self, time_signature: TimeSignature) -> Vec<f32> {
+        match self.profiles.get(time_signature) {
+            Some(weights) => weights.to_vec(),
+            None => WeightedGenerator::base_weights(time_signature),
+        }
+    }
+
+    /// Parameters to generate at: the custom ones if set, otherwise the
+    /// complexity level's preset
+    fn params_for(&self, complexity: ComplexityLevel) -> ComplexityParams {
+        self.params.unwrap_or_else(|| complexity.params())
+    }
+
+    /// Adjust weights for the parameters' syncopation
+    fn adjust_weights(
+        &self,
+        base_weights: &[f32],
+        params: &ComplexityParams,
+        time_signature: TimeSignature,
+    ) -> Vec<f32> {
+        // Scale on-beat positions (0, 4, 8, 12, or each group's start in a
+        // grouped meter) and off-beats separately
+        let (on_beat, off_beat) = params.beat_multipliers();
+        let group_starts = time_signature
+            .grouping
+            .map(|_| time_signature.group_starts());
+        base_weights
+            .iter()
+            .enumerate()
+            .map(|(i, &w)| {
+                let accented = match &group_starts {
+                    Some(starts) => starts.contains(&i),
+                    None => i % 4 == 0,
+                };
+                if accented {
+                    w * on_beat
+                } else {
+                    w * off_beat
+                }
+            })
+            .collect()
+    }
+
+    /// Check a candidate against the standard rules, or against the custom
+    /// parameters' kick range and run length
+    fn validate(&self, pattern: &Pattern) -> Result<(), Violation> {
+        match &self.params {
+            Some(params) => pattern.check_params(params),
+            None => pattern.check_steps(),
+        }
+    }
+
+    /// Generate a pattern using weighted probabilities
+    ///
+    /// On failure the error says which rule threw out the most candidates
+    /// and what to relax (see [`Rejections::explain`]). Right after settings
+    /// broke every rule, calls with them fail straight away with the same
+    /// error (see [`RetryGuard`]).
+    pub fn generate(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<Pattern, CoreError> {
+        self.guard.check(time_signature, complexity)?;
+        let mut rejections = Rejections::new();
+        // Try up to 1000 times to generate a valid, unique pattern
+        match self.search(time_signature, complexity, history, 3, &mut rejections)? {
+            Some(pattern) => {
+                self.guard.pass();
+                Ok(pattern)
+            }
+            None => Err(self.fail(time_signature, complexity, &rejections)),
+        }
+    }
+
+    /// Generate a unique pattern with retry logic and relaxed constraints
+    ///
+    /// Attempts to generate a pattern with decreasing uniqueness requirements,
+    /// up to 1000 candidates each: Hamming distance >= 3, then >= 2, then >= 1.
+    /// If no candidate at all passes the pattern rules at distance 3, relaxing
+    /// uniqueness can't help, so it gives up there rather than drawing
+    /// thousands more, and fails the next calls with the same settings
+    /// straight away (see [`RetryGuard`]).
+    ///
+    /// Returns (pattern, constraint_used) where constraint_used indicates
+    /// which distance threshold was successful
+    pub fn generate_unique(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<(Pattern, u32), CoreError> {
+        self.guard.check(time_signature, complexity)?;
+        let mut rejections = Rejections::new();
+        for min_distance in [3, 2, 1] {
+            if let Some(pattern) = self.search(
+                time_signature,
+                complexity,
+                history,
+                min_distance,
+                &mut rejections,
+            )? {
+                self.guard.pass();
+                return Ok((pattern, min_distance));
+            }
+            if !rejections.any_valid() {
+                break;
+            }
+        }
+
+        Err(self.fail(time_signature, complexity, &rejections))
+    }
+
+    /// Draw up to 1000 candidates, returning the first valid one at least
+    /// `min_distance` from every pattern in `history`, and counting why the
+    /// others were thrown out
+    fn search(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+        min_distance: u32,
+        rejections: &mut Rejections,
+    ) -> Result<Option<Pattern>, CoreError> {
+        let base_weights = self.weights_for(time_signature);
+        let num_positions = base_weights.len();
+        let params = self.params_for(complexity);
+        let adjusted_weights = self.adjust_weights(&base_weights, &params, time_signature);
+        let (min_kicks, max_kicks) = (params.min_kicks, params.max_kicks);
+        let dist = WeightedIndex::new(&adjusted_weights).map_err(|e| {
+            CoreError::Generation(format!("Failed to create weighted distribution: {}", e))
+        })?;
+
+        for _ in 0..1000 {
+            let mut steps = vec![false; num_positions];
+
+            // Position 0 (downbeat) is always true per FR-002
+            steps[0] = true;
+
+            // Target number of total kicks
+            let target_kicks = min_kicks + (self.rng.gen::<usize>() % (max_kicks - min_kicks + 1));
+
+            // Generate kicks (already have 1 from position 0)
+            let mut attempts = 0;
+            while steps.iter().filter(|&&s| s).count() < target_kicks && attempts < 100 {
+                let idx = dist.sample(&mut self.rng);
+                steps[idx] = true;
+                attempts += 1;
+            }
+
+            // Create candidate pattern
+            let id = random_id(&mut self.rng);
+            let pattern = Pattern::with_id(id, steps, time_signature, complexity);
+            rejections.draw();
+
+            // Validate pattern
+            if let Err(violation) = self.validate(&pattern) {
+                rejections.reject(violation);
+                continue; // Try again
+            }
+
+            // Check uniqueness against history with specified distance
+            if is_pattern_unique(&pattern, history, min_distance) {
+                return Ok(Some(pattern));
+            }
+            rejections.duplicate();
+        }
+
+        Ok(None)
+    }
+
+    /// Failure for candidates generated at `complexity`, noted with the guard
+    fn fail(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        rejections: &Rejections,
+    ) -> CoreError {
+        let message = rejections.explain(&self.params_for(complexity), self.params.is_some());
+        self.guard
+            .fail(time_signature, complexity, rejections, &message);
+        CoreError::Generation(message)
+    }
+}
+
+impl<R: RngCore + Send> PatternGenerator for WeightedGenerator<R> {
+    fn generate(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<Pattern, CoreError> {
+        WeightedGenerator::generate(self, time_signature, complexity, history)
+    }
+
+    fn generate_unique(
+        &mut self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<(Pattern, u32), CoreError> {
+        WeightedGenerator::generate_unique(self, time_signature, complexity, history)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for WeightedGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::rejections::RETRY_INTERVAL;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Seeded, so the tests also run without `std`
+    fn generator() -> WeightedGenerator<StdRng> {
+        WeightedGenerator::with_rng(StdRng::seed_from_u64(7))
+    }
+
+    #[test]
+    fn test_base_weights_4_4() {
+        let weights = WeightedGenerator::base_weights_4_4();
+        assert_eq!(weights.len(), 16);
+        assert_eq!(weights[0], 1.0); // Downbeat strongest
+        assert_eq!(weights[8], 0.7); // Beat 3
+    }
+
+    #[test]
+    fn test_grouping_accents_each_group() {
+        let seven = TimeSignature::seven_eight()
+            .with_grouping("3+2+2".parse().unwrap())
+            .unwrap();
+        let weights = WeightedGenerator::base_weights(seven);
+        assert_eq!(weights.len(), 14);
+        assert_eq!((weights[0], weights[6], weights[10]), (1.0, 0.6, 0.6));
+        assert_eq!((weights[4], weights[5]), (0.3, 0.2));
+
+        let pattern = generator()
+            .generate(seven, ComplexityLevel::Medium, &VecDeque::new())
+            .unwrap();
+        assert_eq!(pattern.time_signature, seven);
+    }
+
+    #[test]
+    fn test_generate_simple_pattern() {
+        let mut gen = generator();
+        let result = gen.generate(
+            TimeSignature::four_four(),
+            ComplexityLevel::Simple,
+            &VecDeque::new(),
+        );
+        assert!(result.is_ok());
+        let pattern = result.unwrap();
+        assert!(pattern.steps[0]); // Downbeat must be true
+        assert!(pattern.validate_steps().is_ok());
+    }
+
+    #[test]
+    fn test_generate_three_four_pattern() {
+        let mut gen = generator();
+        let result = gen.generate(
+            TimeSignature::three_four(),
+            ComplexityLevel::Medium,
+            &VecDeque::new(),
+        );
+        assert!(result.is_ok());
+        let pattern = result.unwrap();
+        assert_eq!(pattern.steps.len(), 12); // 3 beats * 4 sixteenth notes per beat
+        assert!(pattern.steps[0]); // Downbeat must be true
+        assert!(pattern.validate_steps().is_ok());
+    }
+
+    #[test]
+    fn test_generate_six_eight_pattern() {
+        let mut gen = generator();
+        let result = gen.generate(
+            TimeSignature::six_eight(),
+            ComplexityLevel::Medium,
+            &VecDeque::new(),
+        );
+        assert!(result.is_ok());
+        let pattern = result.unwrap();
+        assert_eq!(pattern.steps.len(), 12); // 6 beats * 2 sixteenth notes per beat (8th note = 2 sixteenths)
+        assert!(pattern.steps[0]); // Downbeat must be true
+        assert!(pattern.validate_steps().is_ok());
+    }
+
+    #[test]
+    fn test_generate_five_four_pattern() {
+        let mut gen = generator();
+        let result = gen.generate(
+            TimeSignature::five_four(),
+            ComplexityLevel::Complex,
+            &VecDeque::new(),
+        );
+        assert!(result.is_ok());
+        let pattern = result.unwrap();
+        assert_eq!(pattern.steps.len(), 20); // 5 beats * 4 sixteenth notes per beat
+        assert!(pattern.steps[0]); // Downbeat must be true
+        assert!(pattern.validate_steps().is_ok());
+    }
+
+    #[test]
+    fn test_custom_profile_overrides_base_weights() {
+        // Only the downbeat and the "e" of each beat can sound
+        let mut weights = vec![0.0; 16];
+        for beat in 0..4 {
+            weights[beat * 4 + 1] = 1.0;
+        }
+        let mut profiles = WeightProfiles::new();
+        profiles
+            .insert(TimeSignature::four_four(), weights)
+            .unwrap();
+
+        let mut gen = generator().with_profiles(profiles);
+        for _ in 0..10 {
+            let pattern = gen
+                .generate(
+                    TimeSignature::four_four(),
+                    ComplexityLevel::Simple,
+                    &VecDeque::new(),
+                )
+                .unwrap();
+            for (i, &hit) in pattern.steps.iter().enumerate() {
+                if hit {
+                    assert!(i == 0 || i % 4 == 1, "unexpected kick at {}", i);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_params_bound_kicks_and_runs() {
+        let params = ComplexityParams::new(3, 3, 1.0, 1).unwrap();
+        let mut gen = generator().with_params(params);
+        for _ in 0..10 {
+            let pattern = gen
+                .generate(
+                    TimeSignature::four_four(),
+                    ComplexityLevel::Medium,
+                    &VecDeque::new(),
+                )
+                .unwrap();
+            assert_eq!(pattern.note_positions().len(), 3);
+            assert!(pattern.validate_params(&params).is_ok());
+            assert!(!pattern.steps.windows(2).any(|pair| pair[0] && pair[1]));
+        }
+    }
+
+    #[test]
+    fn test_custom_params_allow_denser_patterns() {
+        // Ten kicks is past the standard density limit
+        let params = ComplexityParams::new(10, 10, 0.5, 3).unwrap();
+        let mut gen = generator().with_params(params);
+        let pattern = gen
+            .generate(
+                TimeSignature::four_four(),
+                ComplexityLevel::Complex,
+                &VecDeque::new(),
+            )
+            .unwrap();
+        assert_eq!(pattern.note_positions().len(), 10);
+        assert!(pattern.validate_steps().is_err());
+    }
+
+    #[test]
+    fn test_over_constrained_params_explain_failure() {
+        // Nine kicks can't fit in 16 steps with none adjacent
+        let params = ComplexityParams::new(9, 9, 0.5, 1).unwrap();
+        let mut gen = generator().with_params(params);
+        let err = gen
+            .generate_unique(
+                TimeSignature::four_four(),
+                ComplexityLevel::Medium,
+                &VecDeque::new(),
+            )
+            .unwrap_err();
+        assert!(matches!(err, CoreError::Generation(_)));
+        // Nothing valid at distance 3, so it doesn't go on relaxing uniqueness
+        assert!(err
+            .message()
+            .starts_with("Failed to generate a pattern after 1000 candidates: "));
+        assert!(
+            err.message().contains("had more than 1 kick in a row"),
+            "{}",
+            err
+        );
+        assert!(
+            err.message()
+                .contains("Try allowing longer runs (--allow-consecutive 2)"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_over_constrained_params_hold_off_searching_again() {
+        let params = ComplexityParams::new(9, 9, 0.5, 1).unwrap();
+        let mut gen = generator().with_params(params);
+        let four_four = TimeSignature::four_four();
+        let attempt = |gen: &mut WeightedGenerator<StdRng>, complexity| {
+            let drawn = gen.rng.clone().next_u64();
+            let result = gen.generate_unique(four_four, complexity, &VecDeque::new());
+            (
+                result.map(|(pattern, _)| pattern.steps),
+                gen.rng.clone().next_u64() != drawn,
+            )
+        };
+
+        let (first, searched) = attempt(&mut gen, ComplexityLevel::Medium);
+        assert!(searched);
+        for _ in 1..RETRY_INTERVAL {
+            // Same settings: the same error, without drawing a candidate
+            let (again, searched) = attempt(&mut gen, ComplexityLevel::Medium);
+            assert_eq!(again, first);
+            assert!(!searched);
+        }
+        let (_, searched) = attempt(&mut gen, ComplexityLevel::Medium);
+        assert!(searched);
+
+        // Other settings aren't held off
+        let (_, searched) = attempt(&mut gen, ComplexityLevel::Simple);
+        assert!(searched);
+    }
+
+    #[test]
+    fn test_counts_candidates_too_close_to_history() {
+        // Only one 3-kick pattern fits, so it can't be unique against itself
+        let mut weights = vec![0.0; 16];
+        weights[8] = 1.0;
+        weights[12] = 1.0;
+        let mut profiles = WeightProfiles::new();
+        profiles
+            .insert(TimeSignature::four_four(), weights)
+            .unwrap();
+        let params = ComplexityParams::new(3, 3, 0.5, 1).unwrap();
+        let mut gen = generator().with_profiles(profiles).with_params(params);
+        let first = gen
+            .generate(
+                TimeSignature::four_four(),
+                ComplexityLevel::Medium,
+                &VecDeque::new(),
+            )
+            .unwrap();
+
+        let err = gen
+            .generate_unique(
+                TimeSignature::four_four(),
+                ComplexityLevel::Medium,
+                &VecDeque::from([first]),
+            )
+            .unwrap_err();
+        assert!(
+            err.message().starts_with(
+                "Failed to generate a pattern after 3000 candidates: 3000 were too close"
+            ),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_same_seed_generates_same_patterns() {
+        let mut gen_a = WeightedGenerator::with_rng(StdRng::seed_from_u64(42));
+        let mut gen_b = WeightedGenerator::with_rng(StdRng::seed_from_u64(42));
+
+        for _ in 0..5 {
+            let a = gen_a
+                .generate(
+                    TimeSignature::four_four(),
+                    ComplexityLevel::Medium,
+                    &VecDeque::new(),
+                )
+                .unwrap();
+            let b = gen_b
+                .generate(
+                    TimeSignature::four_four(),
+                    ComplexityLevel::Medium,
+                    &VecDeque::new(),
+                )
+                .unwrap();
+            assert_eq!(a.steps, b.steps);
+            assert_eq!(a.id, b.id);
+        }
+    }
+}
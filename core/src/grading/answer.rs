@@ -0,0 +1,465 @@
+use crate::error::CoreError;
+use crate::models::Pattern;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+/// How typed answers are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnswerFormat {
+    /// One symbol per position (`x...x...x.x....x`)
+    #[default]
+    Grid,
+    /// Inter-onset intervals in sixteenths (`4-4-2-5-1`); grid answers are
+    /// still accepted
+    Ioi,
+}
+
+impl AnswerFormat {
+    /// Parse an answer written in this format into steps
+    pub fn parse(&self, input: &str, expected_len: usize) -> Result<Vec<bool>, CoreError> {
+        match self {
+            AnswerFormat::Ioi if input.chars().any(|c| c.is_ascii_digit()) => {
+                parse_ioi_answer(input, expected_len)
+            }
+            _ => parse_answer(input, expected_len),
+        }
+    }
+}
+
+impl FromStr for AnswerFormat {
+    type Err = String;
+
+    /// Parse an answer format from string ("grid" or "ioi")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "grid" => Ok(AnswerFormat::Grid),
+            "ioi" => Ok(AnswerFormat::Ioi),
+            _ => Err(format!(
+                "Invalid answer format '{}'. Must be one of: grid, ioi",
+                s
+            )),
+        }
+    }
+}
+
+/// Grid the answer is written at
+///
+/// Patterns are always generated at sixteenths; answering at eighths lets a
+/// beginner write half as many positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AnswerResolution {
+    /// One position per sixteenth, as generated
+    #[default]
+    Sixteenth,
+    /// One position per eighth; kicks off the eighth grid count toward the
+    /// eighth they follow
+    Eighth,
+}
+
+impl AnswerResolution {
+    /// Sixteenths covered by one answer position
+    pub fn steps_per_slot(&self) -> usize {
+        match self {
+            AnswerResolution::Sixteenth => 1,
+            AnswerResolution::Eighth => 2,
+        }
+    }
+
+    /// Note value one answer position stands for ("sixteenth" or "eighth")
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnswerResolution::Sixteenth => "sixteenth",
+            AnswerResolution::Eighth => "eighth",
+        }
+    }
+
+    /// Number of positions an answer to `pattern` has at this resolution
+    pub fn answer_len(&self, pattern: &Pattern) -> usize {
+        pattern.steps.len().div_ceil(self.steps_per_slot())
+    }
+
+    /// The pattern as it reads at this resolution
+    ///
+    /// Still on the sixteenth grid, but each slot holds at most one kick, on
+    /// its first sixteenth.
+    pub fn truth(&self, pattern: &Pattern) -> Pattern {
+        let slot = self.steps_per_slot();
+        let mut truth = pattern.clone();
+        truth.steps = (0..pattern.steps.len())
+            .map(|i| {
+                i % slot == 0
+                    && pattern.steps[i..(i + slot).min(pattern.steps.len())]
+                        .iter()
+                        .any(|&kick| kick)
+            })
+            .collect();
+        truth
+    }
+
+    /// Kicks that fall between positions at this resolution
+    pub fn ambiguous_kicks(&self, pattern: &Pattern) -> Vec<usize> {
+        let slot = self.steps_per_slot();
+        pattern
+            .note_positions()
+            .into_iter()
+            .filter(|&i| i % slot != 0)
+            .collect()
+    }
+
+    /// Explain what the coarse grid can't tell apart, if the pattern has
+    /// kicks between its positions
+    pub fn ambiguity_note(&self, pattern: &Pattern) -> Option<String> {
+        let kicks = self.ambiguous_kicks(pattern);
+        if kicks.is_empty() {
+            return None;
+        }
+        let positions: Vec<String> = kicks.iter().map(|k| format!("{}", k + 1)).collect();
+        Some(format!(
+            "Note: at eighth-note resolution, the kick{} on sixteenth{} {} fall{} between \
+             eighths and {} graded on the eighth before. The answer can't tell a kick on the \
+             'e' or 'a' from one on the beat or the '&'.",
+            if kicks.len() == 1 { "" } else { "s" },
+            if kicks.len() == 1 { "" } else { "s" },
+            positions.join(", "),
+            if kicks.len() == 1 { "s" } else { "" },
+            if kicks.len() == 1 { "is" } else { "are" },
+        ))
+    }
+}
+
+impl FromStr for AnswerResolution {
+    type Err = String;
+
+    /// Parse a resolution from string ("16th" or "8th")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "16th" | "16" | "sixteenth" | "sixteenths" => Ok(AnswerResolution::Sixteenth),
+            "8th" | "8" | "eighth" | "eighths" => Ok(AnswerResolution::Eighth),
+            _ => Err(format!(
+                "Invalid answer resolution '{}'. Must be one of: 16th, 8th",
+                s
+            )),
+        }
+    }
+}
+
+/// Result of comparing a typed answer against the real pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnswerGrade {
+    /// The parsed answer (true = kick)
+    pub answer: Vec<bool>,
+    /// Positions where the answer differs from the pattern
+    pub wrong_positions: Vec<usize>,
+    /// Kicks in the pattern that the answer left out
+    pub missed_kicks: Vec<usize>,
+    /// Kicks in the answer that the pattern does not have
+    pub extra_kicks: Vec<usize>,
+    /// Fraction of positions answered correctly (0.0-1.0)
+    pub accuracy: f32,
+}
+
+impl AnswerGrade {
+    /// Whether every position matched
+    pub fn is_perfect(&self) -> bool {
+        self.wrong_positions.is_empty()
+    }
+}
+
+/// Parse a typed rhythm such as `"x...x...x.x....x"` into steps
+///
+/// `x`/`X` marks a kick, `.`/`-` marks a rest. Whitespace and `|` bar lines
+/// are ignored so answers can be grouped by beat (`"x... x... |x.x. ...x"`).
+pub fn parse_answer(input: &str, expected_len: usize) -> Result<Vec<bool>, CoreError> {
+    let mut steps = Vec::with_capacity(expected_len);
+
+    for c in input.chars() {
+        match c {
+            'x' | 'X' => steps.push(true),
+            '.' | '-' => steps.push(false),
+            '|' => {}
+            c if c.is_whitespace() => {}
+            other => {
+                return Err(CoreError::Config(format!(
+                    "Invalid character '{}' in answer. Use 'x' for kicks and '.' for rests",
+                    other
+                )))
+            }
+        }
+    }
+
+    if steps.len() != expected_len {
+        return Err(CoreError::Config(format!(
+            "Answer has {} positions but the pattern has {}",
+            steps.len(),
+            expected_len
+        )));
+    }
+
+    Ok(steps)
+}
+
+/// Parse an inter-onset interval answer such as `"3-3-2-4-4"` into steps
+///
+/// Kicks start on the downbeat unless the list opens with a rest in
+/// parentheses (`"(2)-2-4-8"`); the last interval runs to the end of the
+/// measure, so everything must add up to the pattern length. Intervals may be
+/// separated by `-` or whitespace.
+pub fn parse_ioi_answer(input: &str, expected_len: usize) -> Result<Vec<bool>, CoreError> {
+    let mut steps = vec![false; expected_len];
+    let mut position = 0;
+    let mut kicks = 0;
+
+    let parts = input
+        .split(|c: char| c == '-' || c.is_whitespace())
+        .filter(|part| !part.is_empty());
+    for (i, part) in parts.enumerate() {
+        let rest = part.strip_prefix('(').and_then(|p| p.strip_suffix(')'));
+        let text = rest.unwrap_or(part);
+        let length: usize = text.parse().ok().filter(|&n| n > 0).ok_or_else(|| {
+            CoreError::Config(format!(
+                "Invalid interval '{}'. Use whole numbers of sixteenths like 3-3-2",
+                part
+            ))
+        })?;
+
+        if rest.is_some() && i > 0 {
+            return Err(CoreError::Config(String::from(
+                "Only the first interval can be a (rest)",
+            )));
+        }
+        if rest.is_none() {
+            if position >= expected_len {
+                return Err(CoreError::Config(format!(
+                    "Intervals add up to more than the pattern's {} positions",
+                    expected_len
+                )));
+            }
+            steps[position] = true;
+            kicks += 1;
+        }
+        position += length;
+    }
+
+    if kicks == 0 && position == 0 {
+        return Err(CoreError::Config(String::from("Answer has no intervals")));
+    }
+    if position != expected_len {
+        return Err(CoreError::Config(format!(
+            "Intervals add up to {} but the pattern has {} positions",
+            position, expected_len
+        )));
+    }
+
+    Ok(steps)
+}
+
+/// Grade a typed answer against a pattern
+pub fn grade_answer(pattern: &Pattern, input: &str) -> Result<AnswerGrade, CoreError> {
+    grade_answer_as(pattern, input, AnswerFormat::Grid)
+}
+
+/// Grade an answer written in `format` against a pattern
+pub fn grade_answer_as(
+    pattern: &Pattern,
+    input: &str,
+    format: AnswerFormat,
+) -> Result<AnswerGrade, CoreError> {
+    let answer = format.parse(input, pattern.steps.len())?;
+    Ok(compare(&pattern.steps, answer))
+}
+
+/// Grade an answer written in `format` at `resolution` against a pattern
+///
+/// A coarse answer is spread back onto the sixteenth grid and compared with
+/// [`AnswerResolution::truth`]; accuracy counts answer positions, not
+/// sixteenths.
+pub fn grade_answer_at(
+    pattern: &Pattern,
+    input: &str,
+    format: AnswerFormat,
+    resolution: AnswerResolution,
+) -> Result<AnswerGrade, CoreError> {
+    let slot = resolution.steps_per_slot();
+    if slot == 1 {
+        return grade_answer_as(pattern, input, format);
+    }
+
+    let slots = resolution.answer_len(pattern);
+    let coarse = format.parse(input, slots)?;
+    let mut answer = vec![false; pattern.steps.len()];
+    for (i, &kick) in coarse.iter().enumerate() {
+        answer[i * slot] = kick;
+    }
+
+    let mut grade = compare(&resolution.truth(pattern).steps, answer);
+    grade.accuracy = (slots - grade.wrong_positions.len()) as f32 / slots as f32;
+    Ok(grade)
+}
+
+/// Compare answer steps position by position with the expected steps
+fn compare(expected_steps: &[bool], answer: Vec<bool>) -> AnswerGrade {
+    let mut wrong_positions = Vec::new();
+    let mut missed_kicks = Vec::new();
+    let mut extra_kicks = Vec::new();
+
+    for (i, (&expected, &given)) in expected_steps.iter().zip(answer.iter()).enumerate() {
+        if expected != given {
+            wrong_positions.push(i);
+            if expected {
+                missed_kicks.push(i);
+            } else {
+                extra_kicks.push(i);
+            }
+        }
+    }
+
+    let total = expected_steps.len();
+    let accuracy = (total - wrong_positions.len()) as f32 / total as f32;
+
+    AnswerGrade {
+        answer,
+        wrong_positions,
+        missed_kicks,
+        extra_kicks,
+        accuracy,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, TimeSignature};
+
+    fn sample_pattern() -> Pattern {
+        let steps = vec![
+            true, false, false, false, // Beat 1
+            true, false, false, false, // Beat 2
+            true, false, true, false, // Beat 3
+            false, false, false, true, // Beat 4
+        ];
+        Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium)
+    }
+
+    #[test]
+    fn test_parse_answer_ignores_grouping() {
+        let steps = parse_answer("x... x... |x.x. ...x", 16).unwrap();
+        assert_eq!(steps, sample_pattern().steps);
+    }
+
+    #[test]
+    fn test_parse_answer_rejects_wrong_length() {
+        assert!(parse_answer("x...x...", 16).is_err());
+        assert!(parse_answer("x...x...x.x....o", 16).is_err());
+    }
+
+    #[test]
+    fn test_parse_ioi_answer() {
+        assert_eq!(
+            parse_ioi_answer("4-4-2-5-1", 16).unwrap(),
+            sample_pattern().steps
+        );
+        assert_eq!(
+            parse_ioi_answer("4 4 2 5 1", 16).unwrap(),
+            sample_pattern().steps
+        );
+
+        let steps = parse_ioi_answer("(2)-2-4-8", 16).unwrap();
+        let kicks: Vec<usize> = (0..16).filter(|&i| steps[i]).collect();
+        assert_eq!(kicks, vec![2, 4, 8]);
+    }
+
+    #[test]
+    fn test_parse_ioi_answer_rejects_bad_input() {
+        assert!(parse_ioi_answer("4-4-4", 16).is_err());
+        assert!(parse_ioi_answer("8-8-4", 16).is_err());
+        assert!(parse_ioi_answer("4-(4)-8", 16).is_err());
+        assert!(parse_ioi_answer("4-0-12", 16).is_err());
+        assert!(parse_ioi_answer("4-q-8", 16).is_err());
+    }
+
+    #[test]
+    fn test_ioi_format_also_accepts_grid() {
+        let format = AnswerFormat::Ioi;
+        assert_eq!(
+            format.parse("x...x...x.x....x", 16).unwrap(),
+            sample_pattern().steps
+        );
+        assert_eq!(
+            format.parse("4-4-2-5-1", 16).unwrap(),
+            sample_pattern().steps
+        );
+        assert!(AnswerFormat::Grid.parse("4-4-2-5-1", 16).is_err());
+        assert_eq!("IOI".parse::<AnswerFormat>(), Ok(AnswerFormat::Ioi));
+    }
+
+    #[test]
+    fn test_grade_perfect_answer() {
+        let grade = grade_answer(&sample_pattern(), "x...x...x.x....x").unwrap();
+        assert!(grade.is_perfect());
+        assert_eq!(grade.accuracy, 1.0);
+    }
+
+    #[test]
+    fn test_grade_reports_missed_and_extra_kicks() {
+        // Missed the kick on 10, added one on 11
+        let grade = grade_answer(&sample_pattern(), "x...x...x..x...x").unwrap();
+        assert_eq!(grade.wrong_positions, vec![10, 11]);
+        assert_eq!(grade.missed_kicks, vec![10]);
+        assert_eq!(grade.extra_kicks, vec![11]);
+        assert_eq!(grade.accuracy, 14.0 / 16.0);
+    }
+
+    #[test]
+    fn test_eighth_resolution_truth_and_ambiguity() {
+        // Kicks on 0, 4, 8, 10 and 15: only 15 is off the eighth grid
+        let pattern = sample_pattern();
+        let eighth = AnswerResolution::Eighth;
+        assert_eq!(eighth.answer_len(&pattern), 8);
+        assert_eq!(
+            eighth.truth(&pattern).note_positions(),
+            vec![0, 4, 8, 10, 14]
+        );
+        assert_eq!(eighth.ambiguous_kicks(&pattern), vec![15]);
+        assert!(eighth
+            .ambiguity_note(&pattern)
+            .unwrap()
+            .contains("sixteenth 16 falls"));
+        assert_eq!(AnswerResolution::Sixteenth.ambiguity_note(&pattern), None);
+        assert_eq!(
+            "8th".parse::<AnswerResolution>(),
+            Ok(AnswerResolution::Eighth)
+        );
+        assert!("32nd".parse::<AnswerResolution>().is_err());
+    }
+
+    #[test]
+    fn test_grade_coarse_answer() {
+        let pattern = sample_pattern();
+        let grade = |input, format| {
+            grade_answer_at(&pattern, input, format, AnswerResolution::Eighth).unwrap()
+        };
+
+        // The last kick is a sixteenth late but reads as the final eighth
+        let perfect = grade("x.x.xx.x", AnswerFormat::Grid);
+        assert!(perfect.is_perfect());
+        assert_eq!(perfect.answer.len(), 16);
+        assert!(grade("2-2-1-2-1", AnswerFormat::Ioi).is_perfect());
+
+        // Missing the '&' of three costs one eighth out of eight
+        let missed = grade("x.x.x..x", AnswerFormat::Grid);
+        assert_eq!(missed.missed_kicks, vec![10]);
+        assert_eq!(missed.accuracy, 7.0 / 8.0);
+
+        // Sixteenth answers don't fit the coarse grid
+        assert!(grade_answer_at(
+            &pattern,
+            "x...x...x.x....x",
+            AnswerFormat::Grid,
+            AnswerResolution::Eighth
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,14 @@
+// Grading module
+// Comparing user answers against the generated pattern
+
+pub mod answer;
+pub mod taps;
+
+pub use answer::{
+    grade_answer, grade_answer_as, grade_answer_at, parse_answer, parse_ioi_answer, AnswerFormat,
+    AnswerGrade, AnswerResolution,
+};
+pub use taps::{
+    grade_on_lane, grade_tap, HitTiming, KitTimings, Limb, LimbScores, TapCounts, TapGrade,
+    TapScore,
+};
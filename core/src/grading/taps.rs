@@ -0,0 +1,545 @@
+use crate::models::{Overlay, Pattern};
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Taps within this many seconds of a kick count as perfect
+pub const PERFECT_SECS: f64 = 0.025;
+
+/// Taps within this many seconds of a kick count as good
+pub const GOOD_SECS: f64 = 0.060;
+
+/// Number of recent taps the rolling score covers
+pub const TAP_WINDOW: usize = 8;
+
+/// How close one tap landed to the nearest kick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapGrade {
+    /// Within [`PERFECT_SECS`] either side
+    Perfect,
+    /// Within [`GOOD_SECS`] either side
+    Good,
+    /// Further ahead of the kick than good
+    Early,
+    /// Further behind the kick than good
+    Late,
+}
+
+/// Grade a tap at fractional grid `position` in the loop, against the nearest kick
+///
+/// Kicks near the end of the loop also count for taps just after the top, and
+/// vice versa. Returns the grade and the signed offset in seconds (negative =
+/// early), or `None` if the pattern has no kicks.
+pub fn grade_tap(pattern: &Pattern, position: f64, step_secs: f64) -> Option<(TapGrade, f64)> {
+    grade_on_lane(&pattern.steps, position, step_secs)
+}
+
+/// Grade a hit at fractional grid `position` against the nearest note in
+/// `lane`, wrapping around the loop like [`grade_tap`]
+///
+/// `None` if the lane has no notes.
+pub fn grade_on_lane(lane: &[bool], position: f64, step_secs: f64) -> Option<(TapGrade, f64)> {
+    let (_, steps) = nearest_note(lane, position)?;
+    let offset = steps * step_secs;
+    Some((grade_offset(offset), offset))
+}
+
+/// Grade a hit `offset` seconds off its note (negative = early)
+fn grade_offset(offset: f64) -> TapGrade {
+    if offset.abs() <= PERFECT_SECS {
+        TapGrade::Perfect
+    } else if offset.abs() <= GOOD_SECS {
+        TapGrade::Good
+    } else if offset < 0.0 {
+        TapGrade::Early
+    } else {
+        TapGrade::Late
+    }
+}
+
+/// Step of the nearest note in `lane` to fractional grid `position`, and how
+/// many steps the position is from it (negative = ahead of it), wrapping
+/// around the loop
+fn nearest_note(lane: &[bool], position: f64) -> Option<(usize, f64)> {
+    let len = lane.len() as f64;
+    lane.iter()
+        .enumerate()
+        .filter(|&(_, &note)| note)
+        .map(|(note, _)| {
+            let mut steps = (position - note as f64) % len;
+            if steps >= len / 2.0 {
+                steps -= len;
+            } else if steps < -len / 2.0 {
+                steps += len;
+            }
+            (note, steps)
+        })
+        .min_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+}
+
+/// Counts of each grade among the recent taps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TapCounts {
+    /// Taps graded perfect
+    pub perfect: usize,
+    /// Taps graded good
+    pub good: usize,
+    /// Taps too far ahead of the kick
+    pub early: usize,
+    /// Taps too far behind the kick
+    pub late: usize,
+}
+
+impl TapCounts {
+    /// Taps counted
+    pub fn total(&self) -> usize {
+        self.perfect + self.good + self.early + self.late
+    }
+
+    /// Taps graded perfect or good
+    pub fn on_time(&self) -> usize {
+        self.perfect + self.good
+    }
+
+    /// Count one more tap
+    pub fn add(&mut self, grade: TapGrade) {
+        match grade {
+            TapGrade::Perfect => self.perfect += 1,
+            TapGrade::Good => self.good += 1,
+            TapGrade::Early => self.early += 1,
+            TapGrade::Late => self.late += 1,
+        }
+    }
+}
+
+/// Rolling score over the last [`TAP_WINDOW`] taps, updated as each one lands
+#[derive(Debug, Clone, Default)]
+pub struct TapScore {
+    recent: VecDeque<TapGrade>,
+}
+
+impl TapScore {
+    /// Start with no taps
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a tap, dropping the oldest once the window is full
+    pub fn push(&mut self, grade: TapGrade) {
+        if self.recent.len() == TAP_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(grade);
+    }
+
+    /// Grades of the taps in the window
+    pub fn counts(&self) -> TapCounts {
+        let mut counts = TapCounts::default();
+        for &grade in &self.recent {
+            counts.add(grade);
+        }
+        counts
+    }
+
+    /// One-line readout, e.g. "last 8: 5 perfect · 2 good · 1 late"
+    ///
+    /// Grades with no taps are left out; empty before the first tap.
+    pub fn summary(&self) -> String {
+        let counts = self.counts();
+        if counts.total() == 0 {
+            return String::new();
+        }
+        let parts = [
+            (counts.perfect, "perfect"),
+            (counts.good, "good"),
+            (counts.early, "early"),
+            (counts.late, "late"),
+        ];
+        let mut summary = format!("last {}:", counts.total());
+        let mut first = true;
+        for (count, label) in parts.into_iter().filter(|&(count, _)| count > 0) {
+            summary.push_str(if first { " " } else { " · " });
+            summary.push_str(&format!("{} {}", count, label));
+            first = false;
+        }
+        summary
+    }
+
+    /// Forget every tap (e.g., for a new pattern)
+    pub fn clear(&mut self) {
+        self.recent.clear();
+    }
+}
+
+/// Which part of an electronic kit a hit came from, and so which lane of the
+/// loop it's graded against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limb {
+    /// Kick pad, graded against the pattern
+    Kick,
+    /// Snare pad, graded against a clave or tresillo overlay
+    Snare,
+    /// Hi-hat, graded against an eighths overlay
+    HiHat,
+}
+
+impl Limb {
+    /// Every limb, in the order they're reported
+    pub const ALL: [Limb; 3] = [Limb::Kick, Limb::Snare, Limb::HiHat];
+
+    /// Name as shown in scores
+    pub fn name(&self) -> &'static str {
+        match self {
+            Limb::Kick => "kick",
+            Limb::Snare => "snare",
+            Limb::HiHat => "hi-hat",
+        }
+    }
+
+    /// Steps this limb plays in `pattern` with `overlay` alongside it
+    ///
+    /// The kick plays the pattern. Claves and tresillo go to the snare and
+    /// eighths to the hi-hat, over the pattern's first measure as in
+    /// playback. `None` if the limb has nothing to play.
+    pub fn lane(&self, pattern: &Pattern, overlay: Option<Overlay>) -> Option<Vec<bool>> {
+        if *self == Limb::Kick {
+            return Some(pattern.steps.clone());
+        }
+        let overlay = overlay?;
+        let limb = match overlay {
+            Overlay::Eighths => Limb::HiHat,
+            Overlay::SonClave | Overlay::RumbaClave | Overlay::Tresillo => Limb::Snare,
+        };
+        if *self != limb {
+            return None;
+        }
+        let mut steps = overlay.steps(pattern.time_signature).ok()?;
+        steps.resize(pattern.steps.len(), false);
+        Some(steps)
+    }
+}
+
+/// Grades of every hit from an electronic kit, kept limb by limb
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LimbScores {
+    /// Grades for each limb, in [`Limb::ALL`] order
+    counts: [TapCounts; 3],
+    /// Hits from limbs with no lane to play, or pads no limb plays
+    pub unscored: usize,
+}
+
+impl LimbScores {
+    /// Start with no hits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a hit by `limb`, or an unscored one if it had no lane to be
+    /// graded against (`grade` is `None`)
+    pub fn record(&mut self, limb: Limb, grade: Option<TapGrade>) {
+        match grade {
+            Some(grade) => self.counts[limb as usize].add(grade),
+            None => self.unscored += 1,
+        }
+    }
+
+    /// Grades of one limb's hits
+    pub fn counts(&self, limb: Limb) -> TapCounts {
+        self.counts[limb as usize]
+    }
+
+    /// Every hit counted, scored or not
+    pub fn total(&self) -> usize {
+        self.counts.iter().map(TapCounts::total).sum::<usize>() + self.unscored
+    }
+
+    /// One-line readout of each limb's hits on time, e.g.
+    /// "kick 6/8 on time · snare 3/4 on time · 2 unscored"
+    ///
+    /// Limbs that haven't played are left out; empty before the first hit.
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = Limb::ALL
+            .into_iter()
+            .map(|limb| (limb, self.counts(limb)))
+            .filter(|(_, counts)| counts.total() > 0)
+            .map(|(limb, counts)| {
+                format!(
+                    "{} {}/{} on time",
+                    limb.name(),
+                    counts.on_time(),
+                    counts.total()
+                )
+            })
+            .collect();
+        if self.unscored > 0 {
+            parts.push(format!("{} unscored", self.unscored));
+        }
+        parts.join(" · ")
+    }
+
+    /// Forget every hit (e.g., for a new pattern)
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// When one hit from the kit landed against the note it was timed against
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HitTiming {
+    /// Limb that played it
+    pub limb: Limb,
+    /// Step of the nearest note in the limb's lane
+    pub step: usize,
+    /// Seconds off that note (negative = early)
+    pub offset_secs: f64,
+}
+
+impl HitTiming {
+    /// Time a hit by `limb` at fractional grid `position` against the
+    /// nearest note in `lane`, wrapping around the loop like [`grade_tap`]
+    ///
+    /// `None` if the lane has no notes.
+    pub fn on_lane(limb: Limb, lane: &[bool], position: f64, step_secs: f64) -> Option<Self> {
+        let (step, steps) = nearest_note(lane, position)?;
+        Some(Self {
+            limb,
+            step,
+            offset_secs: steps * step_secs,
+        })
+    }
+
+    /// Grade of the hit
+    pub fn grade(&self) -> TapGrade {
+        grade_offset(self.offset_secs)
+    }
+
+    /// Offset in whole milliseconds, signed, e.g. "+4ms" or "-38ms"
+    pub fn offset_label(&self) -> String {
+        // Round half away from zero (`f64::round` needs std)
+        let ms = self.offset_secs * 1000.0;
+        let ms = (ms + 0.5_f64.copysign(ms)) as i64;
+        if ms > 0 {
+            format!("+{}ms", ms)
+        } else {
+            format!("{}ms", ms)
+        }
+    }
+
+    /// "rushed" or "dragged" for hits graded early or late, `None` otherwise
+    pub fn verdict(&self) -> Option<&'static str> {
+        match self.grade() {
+            TapGrade::Early => Some("rushed"),
+            TapGrade::Late => Some("dragged"),
+            TapGrade::Perfect | TapGrade::Good => None,
+        }
+    }
+}
+
+/// Timing of every graded hit from an electronic kit, kept loop by loop so
+/// a take can be reviewed for where it rushed or dragged
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KitTimings {
+    /// Hits in the order they landed, by loop number
+    loops: BTreeMap<u64, Vec<HitTiming>>,
+}
+
+impl KitTimings {
+    /// Start with no hits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep a hit that landed in loop `loop_index`
+    pub fn record(&mut self, loop_index: u64, timing: HitTiming) {
+        self.loops.entry(loop_index).or_default().push(timing);
+    }
+
+    /// Each loop with hits, earliest first, with its hits in the order they landed
+    pub fn loops(&self) -> impl Iterator<Item = (u64, &[HitTiming])> {
+        self.loops
+            .iter()
+            .map(|(&index, hits)| (index, hits.as_slice()))
+    }
+
+    /// Hits in loop `loop_index`
+    pub fn hits(&self, loop_index: u64) -> &[HitTiming] {
+        self.loops.get(&loop_index).map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether no hit has been kept
+    pub fn is_empty(&self) -> bool {
+        self.loops.is_empty()
+    }
+
+    /// Mean offset of a limb's hits across every loop, in seconds (negative =
+    /// ahead of the pattern), or `None` if it hasn't played
+    pub fn mean_offset(&self, limb: Limb) -> Option<f64> {
+        let offsets: Vec<f64> = self
+            .loops
+            .values()
+            .flatten()
+            .filter(|hit| hit.limb == limb)
+            .map(|hit| hit.offset_secs)
+            .collect();
+        if offsets.is_empty() {
+            return None;
+        }
+        Some(offsets.iter().sum::<f64>() / offsets.len() as f64)
+    }
+
+    /// Forget every hit (e.g., for a new pattern)
+    pub fn clear(&mut self) {
+        self.loops.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, TimeSignature};
+    use alloc::vec;
+
+    fn pattern() -> Pattern {
+        // Kicks on 0 and 10 of 16
+        let steps = (0..16).map(|i| i == 0 || i == 10).collect();
+        Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Simple)
+    }
+
+    #[test]
+    fn test_grade_tap_against_nearest_kick() {
+        // 120 BPM sixteenths: 125ms per step
+        let step = 0.125;
+        let grade = |position| grade_tap(&pattern(), position, step).map(|(grade, _)| grade);
+
+        assert_eq!(grade(10.1), Some(TapGrade::Perfect));
+        assert_eq!(grade(9.6), Some(TapGrade::Good));
+        assert_eq!(grade(9.0), Some(TapGrade::Early));
+        assert_eq!(grade(11.0), Some(TapGrade::Late));
+
+        // Just before the top of the loop is early for the downbeat
+        let (grade, offset) = grade_tap(&pattern(), 15.9, step).unwrap();
+        assert_eq!(grade, TapGrade::Perfect);
+        assert!((offset + 0.0125).abs() < 1e-9);
+
+        let empty = Pattern::new(
+            vec![false; 16],
+            TimeSignature::four_four(),
+            ComplexityLevel::Simple,
+        );
+        assert_eq!(grade_tap(&empty, 3.0, step), None);
+    }
+
+    #[test]
+    fn test_score_rolls_over_the_window() {
+        let mut score = TapScore::new();
+        assert_eq!(score.summary(), "");
+
+        score.push(TapGrade::Late);
+        for _ in 0..5 {
+            score.push(TapGrade::Perfect);
+        }
+        score.push(TapGrade::Good);
+        score.push(TapGrade::Good);
+        assert_eq!(score.summary(), "last 8: 5 perfect · 2 good · 1 late");
+
+        // The late tap falls out of the window
+        score.push(TapGrade::Early);
+        assert_eq!(
+            score.counts(),
+            TapCounts {
+                perfect: 5,
+                good: 2,
+                early: 1,
+                late: 0
+            }
+        );
+
+        score.clear();
+        assert_eq!(score.counts().total(), 0);
+    }
+
+    #[test]
+    fn test_limbs_play_their_own_lanes() {
+        let kick = Limb::Kick.lane(&pattern(), None).unwrap();
+        assert_eq!(kick, pattern().steps);
+        assert_eq!(Limb::Snare.lane(&pattern(), None), None);
+
+        let snare = Limb::Snare
+            .lane(&pattern(), Some(Overlay::SonClave))
+            .unwrap();
+        let hits: Vec<usize> = (0..16).filter(|&i| snare[i]).collect();
+        assert_eq!(hits, [0, 3, 6, 10, 12]);
+        assert_eq!(Limb::HiHat.lane(&pattern(), Some(Overlay::SonClave)), None);
+
+        let hat = Limb::HiHat
+            .lane(&pattern(), Some(Overlay::Eighths))
+            .unwrap();
+        assert_eq!(hat.iter().filter(|&&note| note).count(), 8);
+
+        // Each lane grades against its own notes: 120 BPM sixteenths
+        let step = 0.125;
+        let grade = |lane: &[bool], position| grade_on_lane(lane, position, step).map(|(g, _)| g);
+        assert_eq!(grade(&snare, 3.1), Some(TapGrade::Perfect));
+        assert_eq!(grade(&kick, 3.1), Some(TapGrade::Late));
+        assert_eq!(grade(&hat, 14.6), Some(TapGrade::Late));
+    }
+
+    #[test]
+    fn test_limb_scores_summary() {
+        let mut scores = LimbScores::new();
+        assert_eq!(scores.summary(), "");
+
+        for grade in [TapGrade::Perfect, TapGrade::Good, TapGrade::Late] {
+            scores.record(Limb::Kick, Some(grade));
+        }
+        scores.record(Limb::Snare, Some(TapGrade::Early));
+        scores.record(Limb::HiHat, None);
+        scores.record(Limb::HiHat, None);
+        assert_eq!(
+            scores.summary(),
+            "kick 2/3 on time · snare 0/1 on time · 2 unscored"
+        );
+        assert_eq!(scores.counts(Limb::Kick).late, 1);
+        assert_eq!(scores.total(), 6);
+
+        scores.clear();
+        assert_eq!(scores.total(), 0);
+    }
+
+    #[test]
+    fn test_kit_timings_keep_each_loop() {
+        // 120 BPM sixteenths: 125ms per step
+        let step = 0.125;
+        let kick = Limb::Kick.lane(&pattern(), None).unwrap();
+        let time = |position| HitTiming::on_lane(Limb::Kick, &kick, position, step).unwrap();
+
+        let rushed = time(9.6);
+        assert_eq!(rushed.step, 10);
+        assert_eq!(rushed.offset_label(), "-50ms");
+        assert_eq!(rushed.verdict(), None);
+        assert_eq!(time(9.0).verdict(), Some("rushed"));
+        assert_eq!(time(11.0).verdict(), Some("dragged"));
+        // Just before the top of the loop is timed against the downbeat
+        let wrapped = time(15.8);
+        assert_eq!(wrapped.step, 0);
+        assert_eq!(wrapped.offset_label(), "-25ms");
+        assert_eq!(time(0.0).offset_label(), "0ms");
+
+        let mut timings = KitTimings::new();
+        assert!(timings.is_empty());
+        timings.record(2, time(0.2));
+        timings.record(0, time(9.0));
+        timings.record(2, time(10.0));
+        let loops: Vec<(u64, usize)> = timings.loops().map(|(i, hits)| (i, hits.len())).collect();
+        assert_eq!(loops, [(0, 1), (2, 2)]);
+        assert_eq!(timings.hits(2)[0].step, 0);
+        assert!(timings.hits(1).is_empty());
+        let mean = timings.mean_offset(Limb::Kick).unwrap();
+        assert!((mean + 0.0333).abs() < 1e-3, "mean {}", mean);
+        assert_eq!(timings.mean_offset(Limb::Snare), None);
+
+        timings.clear();
+        assert!(timings.is_empty());
+    }
+}
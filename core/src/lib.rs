@@ -0,0 +1,18 @@
+// Kickbeats Core - Rhythm Pattern Library
+//
+// Models, generators, grading, and visualizers shared by the kickbeats CLI and
+// its bindings. Everything here only needs `alloc`, so it builds without default
+// features for embedded or WASM targets. The public API follows semver: breaking
+// changes only land in a new major (or, before 1.0, minor) version.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+pub mod error;
+pub mod generator;
+pub mod grading;
+pub mod models;
+pub mod visualizer;
+
+pub use error::CoreError;
@@ -0,0 +1,104 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::str::FromStr;
+
+/// Longest answer window a drill allows, in seconds
+const MAX_ANSWER_SECS: u32 = 300;
+
+/// Timed-answer drill: listen for a fixed number of loops, then answer in time
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::AnswerDrill;
+///
+/// let drill: AnswerDrill = "4loops/20s".parse().unwrap();
+/// assert_eq!(drill.loops, 4);
+/// assert_eq!(drill.answer_secs, 20);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnswerDrill {
+    /// Pattern loops played before playback stops
+    pub loops: u32,
+    /// Seconds allowed to enter the answer once playback stops
+    pub answer_secs: u32,
+}
+
+impl AnswerDrill {
+    /// Create a new answer drill
+    pub fn new(loops: u32, answer_secs: u32) -> Self {
+        Self { loops, answer_secs }
+    }
+}
+
+impl FromStr for AnswerDrill {
+    type Err = String;
+
+    /// Parse a drill such as "4loops/20s", "4/20", or "2bars/30sec"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (loops, secs) = s.split_once('/').ok_or_else(|| {
+            format!(
+                "Invalid drill '{}'. Format should be <n>loops/<secs>s (e.g., 4loops/20s)",
+                s
+            )
+        })?;
+
+        let loops = loops.trim().to_lowercase();
+        let loops = ["loops", "loop", "bars", "bar"]
+            .iter()
+            .find_map(|suffix| loops.strip_suffix(suffix))
+            .unwrap_or(&loops);
+        let loops = loops.trim().parse::<u32>().map_err(|_| {
+            format!(
+                "Invalid drill loop count '{}'. Must be a positive number",
+                loops
+            )
+        })?;
+
+        let secs = secs.trim().to_lowercase();
+        let secs = ["secs", "sec", "s"]
+            .iter()
+            .find_map(|suffix| secs.strip_suffix(suffix))
+            .unwrap_or(&secs);
+        let answer_secs = secs.trim().parse::<u32>().map_err(|_| {
+            format!(
+                "Invalid drill answer time '{}'. Must be a positive number",
+                secs
+            )
+        })?;
+
+        if loops == 0 {
+            return Err("Drill must play at least 1 loop".to_string());
+        }
+        if answer_secs == 0 || answer_secs > MAX_ANSWER_SECS {
+            return Err(format!(
+                "Drill answer time must be between 1 and {} seconds",
+                MAX_ANSWER_SECS
+            ));
+        }
+
+        Ok(Self::new(loops, answer_secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_drill_formats() {
+        assert_eq!("4loops/20s".parse(), Ok(AnswerDrill::new(4, 20)));
+        assert_eq!("4/20".parse(), Ok(AnswerDrill::new(4, 20)));
+        assert_eq!("2bars/30sec".parse(), Ok(AnswerDrill::new(2, 30)));
+        assert_eq!("1Loop/5S".parse(), Ok(AnswerDrill::new(1, 5)));
+    }
+
+    #[test]
+    fn test_parse_drill_rejects_invalid() {
+        assert!("4loops".parse::<AnswerDrill>().is_err());
+        assert!("0loops/20s".parse::<AnswerDrill>().is_err());
+        assert!("4loops/0s".parse::<AnswerDrill>().is_err());
+        assert!("4loops/900s".parse::<AnswerDrill>().is_err());
+        assert!("many/20s".parse::<AnswerDrill>().is_err());
+    }
+}
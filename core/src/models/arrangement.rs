@@ -0,0 +1,386 @@
+use super::pattern::Pattern;
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+/// Most loops one section of a song form can play
+pub const MAX_SECTION_LOOPS: u32 = 16;
+
+/// One section of a song form: which part plays, and for how many loops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormSection {
+    /// Part played, an uppercase letter (e.g., 'A')
+    pub label: char,
+    /// Times the part's pattern loops before the next section
+    pub loops: u32,
+}
+
+/// Where a part of a song form gets its pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartSource {
+    /// The pattern with this number in the session
+    Number(u32),
+    /// The most recent pattern noted with this tag (lowercase, without the `#`)
+    Tag(String),
+    /// A pattern written out in pattern notation (e.g., "4/4:16:x...x...")
+    Written(String),
+}
+
+/// A song form and the pattern each of its parts plays, as typed
+///
+/// The form is a run of part letters, each optionally followed by how many
+/// loops that section plays (one if not given), and each part is assigned a
+/// pattern with `letter=source`: a session pattern number, a `#tag` from the
+/// notes, or a pattern in pattern notation.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{ArrangementSpec, FormSection, PartSource};
+///
+/// let spec: ArrangementSpec = "A2 A2 B4 A2 A=3 b=#bridge".parse().unwrap();
+/// assert_eq!(spec.form_label(), "AABA");
+/// assert_eq!(spec.form[2], FormSection { label: 'B', loops: 4 });
+/// assert_eq!(spec.parts[1], ('B', PartSource::Tag("bridge".to_string())));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArrangementSpec {
+    /// Sections in the order they play
+    pub form: Vec<FormSection>,
+    /// Each part's letter and where its pattern comes from
+    pub parts: Vec<(char, PartSource)>,
+}
+
+impl ArrangementSpec {
+    /// The form's letters, without loop counts (e.g., "AABA")
+    pub fn form_label(&self) -> String {
+        self.form.iter().map(|section| section.label).collect()
+    }
+}
+
+impl FromStr for ArrangementSpec {
+    type Err = String;
+
+    /// Parse a form and its parts, such as "AABA A=3 B=5" or "A2 A2 B4 A2 A=3 B=#bridge"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut form = Vec::new();
+        let mut parts: Vec<(char, PartSource)> = Vec::new();
+        for token in s.split_whitespace() {
+            match token.split_once('=') {
+                Some((label, source)) => {
+                    let label = part_label(label)?;
+                    if parts.iter().any(|&(l, _)| l == label) {
+                        return Err(format!("Part {} is given more than one pattern", label));
+                    }
+                    parts.push((label, source.parse()?));
+                }
+                None => form.extend(parse_form(token)?),
+            }
+        }
+        if form.is_empty() {
+            return Err("An arrangement needs a form, e.g. \"AABA A=3 B=5\"".to_string());
+        }
+        if let Some(missing) = form
+            .iter()
+            .find(|section| !parts.iter().any(|&(l, _)| l == section.label))
+        {
+            return Err(format!(
+                "Part {} has no pattern (e.g., {}=3 for pattern #3)",
+                missing.label, missing.label
+            ));
+        }
+        Ok(Self { form, parts })
+    }
+}
+
+impl FromStr for PartSource {
+    type Err = String;
+
+    /// Parse a pattern number ("3"), a tag ("#bridge"), or pattern notation
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(tag) = s.strip_prefix('#') {
+            return match tag {
+                "" => Err("A part's tag needs a name, e.g. #bridge".to_string()),
+                tag => Ok(PartSource::Tag(tag.to_lowercase())),
+            };
+        }
+        if s.contains(':') {
+            return Ok(PartSource::Written(s.to_string()));
+        }
+        match s.parse::<u32>() {
+            Ok(number) if number > 0 => Ok(PartSource::Number(number)),
+            _ => Err(format!(
+                "Invalid part '{}': expected a pattern number, a #tag, or a pattern such as 4/4:16:x...x...",
+                s
+            )),
+        }
+    }
+}
+
+/// The part letter in a `letter=source` assignment
+fn part_label(s: &str) -> Result<char, String> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(label), None) if label.is_ascii_alphabetic() => Ok(label.to_ascii_uppercase()),
+        _ => Err(format!(
+            "Invalid part name '{}': parts are single letters",
+            s
+        )),
+    }
+}
+
+/// Sections in a run of form letters and loop counts (e.g., "A2A2B4A2")
+fn parse_form(s: &str) -> Result<Vec<FormSection>, String> {
+    let mut sections: Vec<FormSection> = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !c.is_ascii_alphabetic() {
+            return Err(format!(
+                "Invalid form '{}': use part letters, each optionally followed by its loops (e.g., A2 A2 B4 A2)",
+                s
+            ));
+        }
+        let mut digits = String::new();
+        while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+            digits.push(d);
+            chars.next();
+        }
+        let loops = match digits.as_str() {
+            "" => 1,
+            digits => digits.parse().unwrap_or(0),
+        };
+        if !(1..=MAX_SECTION_LOOPS).contains(&loops) {
+            return Err(format!(
+                "Section {}{} must play 1 to {} loops",
+                c.to_ascii_uppercase(),
+                digits,
+                MAX_SECTION_LOOPS
+            ));
+        }
+        sections.push(FormSection {
+            label: c.to_ascii_uppercase(),
+            loops,
+        });
+    }
+    Ok(sections)
+}
+
+/// A section of an arrangement, laid out on its timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArrangedSection {
+    /// Part played
+    pub label: char,
+    /// Loops of the part's pattern
+    pub loops: u32,
+    /// First step of the section
+    pub start: usize,
+    /// Steps in one loop of the part's pattern
+    pub loop_len: usize,
+}
+
+impl ArrangedSection {
+    /// Steps in the whole section
+    pub fn len(&self) -> usize {
+        self.loop_len * self.loops as usize
+    }
+
+    /// Whether the section has no steps
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Patterns chained into a song form, such as AABA, and played through once
+/// without a break
+///
+/// The whole form is worked out ahead of time as one pattern, so playback
+/// keeps time across section changes; [`section_at`](Self::section_at) tells
+/// where each section starts.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{Arrangement, ArrangementSpec, ComplexityLevel, Pattern};
+///
+/// let a = Pattern::from_pattern_string("4/4:16:x.......x.......", ComplexityLevel::Simple).unwrap();
+/// let b = Pattern::from_pattern_string("4/4:16:x..x..x.........", ComplexityLevel::Simple).unwrap();
+/// let spec: ArrangementSpec = "A2 B A A=1 B=2".parse().unwrap();
+/// let arrangement = Arrangement::new(&spec.form, &[('A', a), ('B', b)]).unwrap();
+/// assert_eq!(arrangement.pattern.steps.len(), 64);
+/// assert_eq!(arrangement.section_at(20), Some(0));
+/// assert_eq!(arrangement.section_at(32), Some(1));
+/// assert_eq!(arrangement.section_at(64), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Arrangement {
+    /// Every section, one after another, as one pattern
+    pub pattern: Pattern,
+    /// Each section in the order it plays
+    pub sections: Vec<ArrangedSection>,
+}
+
+impl Arrangement {
+    /// Lay out `form`, playing each part's pattern from `parts`
+    ///
+    /// Fails if a part has no pattern, if parts are on different grids
+    /// (e.g., triplets and sixteenths can't share bar lines), or if the
+    /// form runs past 255 bars.
+    pub fn new(form: &[FormSection], parts: &[(char, Pattern)]) -> Result<Self, CoreError> {
+        let mut loops = Vec::new();
+        let mut sections = Vec::new();
+        let mut start = 0;
+        for section in form {
+            let (_, pattern) = parts
+                .iter()
+                .find(|(label, _)| *label == section.label)
+                .ok_or_else(|| {
+                    CoreError::Config(format!("Part {} has no pattern", section.label))
+                })?;
+            if pattern.subdivision != parts[0].1.subdivision {
+                return Err(CoreError::Config(format!(
+                    "Part {} is on a different grid from part {}, so they can't share bar lines",
+                    section.label, parts[0].0
+                )));
+            }
+            let arranged = ArrangedSection {
+                label: section.label,
+                loops: section.loops,
+                start,
+                loop_len: pattern.steps.len(),
+            };
+            start += arranged.len();
+            sections.push(arranged);
+            loops.extend(core::iter::repeat_n(
+                pattern.clone(),
+                section.loops as usize,
+            ));
+        }
+        let bars: usize = loops.iter().map(|pattern| pattern.meters.len()).sum();
+        if bars > usize::from(u8::MAX) {
+            return Err(CoreError::Config(format!(
+                "The form runs to {} bars; arrangements can be at most {}",
+                bars,
+                u8::MAX
+            )));
+        }
+        let pattern = Pattern::concat(&loops)
+            .ok_or_else(|| CoreError::Config(String::from("An arrangement needs a form")))?;
+        Ok(Self { pattern, sections })
+    }
+
+    /// Index of the section sounding at `step`; `None` past the end
+    pub fn section_at(&self, step: usize) -> Option<usize> {
+        self.sections
+            .iter()
+            .position(|section| (section.start..section.start + section.len()).contains(&step))
+    }
+
+    /// The form's letters (e.g., "AABA")
+    pub fn form_label(&self) -> String {
+        self.sections.iter().map(|section| section.label).collect()
+    }
+
+    /// Number of sections
+    pub fn len(&self) -> usize {
+        self.sections.len()
+    }
+
+    /// Whether the arrangement has no sections
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, TimeSignature};
+    use alloc::vec;
+
+    fn pattern(text: &str) -> Pattern {
+        Pattern::from_pattern_string(text, ComplexityLevel::Medium).unwrap()
+    }
+
+    #[test]
+    fn test_parse_form_and_parts() {
+        let spec: ArrangementSpec = "aaba A=3 B=4/4:16:x...x...x...x...".parse().unwrap();
+        assert_eq!(spec.form_label(), "AABA");
+        assert!(spec.form.iter().all(|section| section.loops == 1));
+        assert_eq!(
+            spec.parts,
+            vec![
+                ('A', PartSource::Number(3)),
+                (
+                    'B',
+                    PartSource::Written("4/4:16:x...x...x...x...".to_string())
+                ),
+            ]
+        );
+
+        for bad in [
+            "A=3",
+            "AB A=3",
+            "A A=3 A=4",
+            "A17 A=3",
+            "A0 A=3",
+            "A-B A=3",
+            "A AB=3",
+            "A A=0",
+            "A A=#",
+        ] {
+            assert!(bad.parse::<ArrangementSpec>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_lays_sections_end_to_end() {
+        let form: ArrangementSpec = "A2 B A B=2 A=1".parse().unwrap();
+        let arrangement = Arrangement::new(
+            &form.form,
+            &[
+                ('A', pattern("4/4:16:x.......x.......")),
+                ('B', pattern("3/4:16:x...x...x...")),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(arrangement.form_label(), "ABA");
+        assert_eq!(
+            arrangement
+                .sections
+                .iter()
+                .map(|s| (s.start, s.len()))
+                .collect::<Vec<_>>(),
+            vec![(0, 32), (32, 12), (44, 16)]
+        );
+        assert_eq!(arrangement.pattern.steps.len(), 60);
+        assert_eq!(
+            arrangement.pattern.meters,
+            vec![
+                TimeSignature::four_four(),
+                TimeSignature::four_four(),
+                TimeSignature::three_four(),
+                TimeSignature::four_four(),
+            ]
+        );
+        assert_eq!(arrangement.section_at(31), Some(0));
+        assert_eq!(arrangement.section_at(43), Some(1));
+        assert_eq!(arrangement.section_at(59), Some(2));
+    }
+
+    #[test]
+    fn test_rejects_mixed_grids_and_overlong_forms() {
+        let mut triplets = pattern("4/4:16:x...x...x...x...");
+        triplets.subdivision = 12;
+        let spec: ArrangementSpec = "AB A=1 B=2".parse().unwrap();
+        let parts = [('A', pattern("4/4:16:x.......x.......")), ('B', triplets)];
+        assert!(Arrangement::new(&spec.form, &parts).is_err());
+
+        let long: ArrangementSpec = "A16A16A16A16A16A16A16A16A16A16A16A16A16A16A16A16 A=1"
+            .parse()
+            .unwrap();
+        assert!(Arrangement::new(&long.form, &parts[..1]).is_err());
+    }
+}
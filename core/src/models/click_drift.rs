@@ -0,0 +1,199 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+/// Widest a drifting click will wander from the pattern tempo, in BPM
+pub const MAX_DRIFT_BPM: u16 = 40;
+
+/// Tempo trainer: the click slowly drifts sharp and flat of the pattern tempo
+///
+/// The first loop plays the pattern against a steady click to set the
+/// reference. From then on the click rushes up to `depth_bpm` ahead, falls
+/// back to `depth_bpm` behind, and returns, once every `period_loops` loops.
+/// The pattern keeps its original tempo, so taps are still scored against it.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::ClickDrift;
+///
+/// let drift: ClickDrift = "4bpm/16bars".parse().unwrap();
+/// assert_eq!(drift.offset_at(0), 0.0);
+/// assert_eq!(drift.offset_at(4), 4.0);
+/// assert_eq!(drift.offset_at(12), -4.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClickDrift {
+    /// Furthest the click tempo strays either side of the pattern tempo
+    pub depth_bpm: u16,
+    /// Loops for one full swing: sharp, back, flat, and back again
+    pub period_loops: u32,
+}
+
+impl ClickDrift {
+    /// Create a new click drift
+    pub fn new(depth_bpm: u16, period_loops: u32) -> Self {
+        Self {
+            depth_bpm,
+            period_loops,
+        }
+    }
+
+    /// BPM the click is off the pattern tempo during loop `loop_index`
+    /// (positive = rushing)
+    pub fn offset_at(&self, loop_index: u64) -> f64 {
+        let period = u64::from(self.period_loops.max(1));
+        let x = (loop_index % period) as f64 / period as f64;
+        let swing = if x < 0.25 {
+            4.0 * x
+        } else if x < 0.75 {
+            2.0 - 4.0 * x
+        } else {
+            4.0 * x - 4.0
+        };
+        f64::from(self.depth_bpm) * swing
+    }
+
+    /// Click tempo during loop `loop_index` against a pattern at `pattern_bpm`
+    pub fn click_tempo(&self, pattern_bpm: u16, loop_index: u64) -> f64 {
+        (f64::from(pattern_bpm) + self.offset_at(loop_index)).max(1.0)
+    }
+
+    /// Clicks during loop `loop_index`, as offsets in pattern beats from the
+    /// loop's downbeat, each flagged when it starts one of the click's bars
+    ///
+    /// The click keeps its phase from loop to loop, so after drifting it
+    /// lands between the pattern's beats rather than snapping back to them.
+    pub fn clicks_in_loop(
+        &self,
+        pattern_bpm: u16,
+        loop_index: u64,
+        beats_per_loop: usize,
+    ) -> Vec<(f64, bool)> {
+        let beats = beats_per_loop.max(1) as f64;
+        let ratio = |index: u64| self.click_tempo(pattern_bpm, index) / f64::from(pattern_bpm);
+
+        // Click beats played before this loop, and this loop's click beats per pattern beat
+        let phase: f64 = (0..loop_index).map(|index| beats * ratio(index)).sum();
+        let rate = ratio(loop_index);
+        let end = phase + beats * rate;
+
+        // Nudge past rounding error so a click exactly on the downbeat isn't lost
+        let start = phase - 1e-9;
+        let first = start as u64 + u64::from((start as u64 as f64) < start);
+        (first..)
+            .take_while(|&click| (click as f64) < end - 1e-9)
+            .map(|click| {
+                let offset = ((click as f64 - phase) / rate).max(0.0);
+                (offset, click % beats_per_loop.max(1) as u64 == 0)
+            })
+            .collect()
+    }
+}
+
+impl core::fmt::Display for ClickDrift {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "±{} BPM every {} bars",
+            self.depth_bpm, self.period_loops
+        )
+    }
+}
+
+impl FromStr for ClickDrift {
+    type Err = String;
+
+    /// Parse a drift such as "4bpm/16bars" or "4/16" (depth / bars per swing)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (depth, period) = s.split_once('/').ok_or_else(|| {
+            format!(
+                "Invalid click drift '{}'. Format should be <bpm>bpm/<n>bars (e.g., 4bpm/16bars)",
+                s
+            )
+        })?;
+
+        let depth = depth.trim().to_lowercase();
+        let depth = depth.strip_suffix("bpm").unwrap_or(&depth);
+        let depth_bpm = depth.trim().parse::<u16>().map_err(|_| {
+            format!(
+                "Invalid click drift depth '{}'. Must be a positive number",
+                depth
+            )
+        })?;
+
+        let period = period.trim().to_lowercase();
+        let period = ["bars", "bar", "loops", "loop"]
+            .iter()
+            .find_map(|suffix| period.strip_suffix(suffix))
+            .unwrap_or(&period);
+        let period_loops = period.trim().parse::<u32>().map_err(|_| {
+            format!(
+                "Invalid click drift period '{}'. Must be a positive number",
+                period
+            )
+        })?;
+
+        if depth_bpm == 0 || depth_bpm > MAX_DRIFT_BPM {
+            return Err(format!(
+                "Click drift must be between 1 and {} BPM",
+                MAX_DRIFT_BPM
+            ));
+        }
+        if period_loops < 4 {
+            return Err("Click drift needs at least 4 bars per swing".to_string());
+        }
+
+        Ok(Self::new(depth_bpm, period_loops))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_parse_click_drift() {
+        assert_eq!("4bpm/16bars".parse(), Ok(ClickDrift::new(4, 16)));
+        assert_eq!("6/8".parse(), Ok(ClickDrift::new(6, 8)));
+        assert!("4bpm".parse::<ClickDrift>().is_err());
+        assert!("0/16".parse::<ClickDrift>().is_err());
+        assert!("41/16".parse::<ClickDrift>().is_err());
+        assert!("4/3".parse::<ClickDrift>().is_err());
+    }
+
+    #[test]
+    fn test_offset_swings_sharp_then_flat() {
+        let drift = ClickDrift::new(8, 8);
+        let offsets: Vec<f64> = (0..9).map(|i| drift.offset_at(i)).collect();
+        assert_eq!(
+            offsets,
+            vec![0.0, 4.0, 8.0, 4.0, 0.0, -4.0, -8.0, -4.0, 0.0]
+        );
+        assert_eq!(drift.click_tempo(100, 2), 108.0);
+    }
+
+    #[test]
+    fn test_clicks_keep_their_phase_across_loops() {
+        // 100 BPM pattern, click at 125 BPM for loop 1: five clicks in four beats
+        let drift = ClickDrift::new(25, 4);
+        assert_eq!(
+            drift.clicks_in_loop(100, 0, 4),
+            vec![(0.0, true), (1.0, false), (2.0, false), (3.0, false)]
+        );
+        let clicks = drift.clicks_in_loop(100, 1, 4);
+        let expected = [0.0, 0.8, 1.6, 2.4, 3.2];
+        assert_eq!(clicks.len(), expected.len());
+        for ((beat, _), want) in clicks.iter().zip(expected) {
+            assert!((beat - want).abs() < 1e-9, "{} != {}", beat, want);
+        }
+        assert!(clicks[0].1 && clicks[4].1);
+
+        // Back at the pattern tempo, the click is a whole beat ahead of its own bar
+        let clicks = drift.clicks_in_loop(100, 2, 4);
+        let accents: Vec<bool> = clicks.iter().map(|(_, accented)| *accented).collect();
+        assert_eq!(accents, vec![false, false, false, true]);
+    }
+}
@@ -0,0 +1,110 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::str::FromStr;
+
+/// Adaptive click volume: the click gets quieter with each good score
+///
+/// Every answer scoring at or above the threshold lowers the click velocity
+/// by one step (down to silence); every answer below it raises the click a
+/// step again (up to full volume).
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::ClickFade;
+///
+/// let fade: ClickFade = "90%/10".parse().unwrap();
+/// assert_eq!(fade.next_velocity(80, 0.95, 80), 70);
+/// assert_eq!(fade.next_velocity(70, 0.50, 80), 80);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClickFade {
+    /// Accuracy (percent) an answer needs to make the click quieter
+    pub threshold_percent: u8,
+    /// Velocity removed after a good answer, or restored after a miss
+    pub step: u8,
+}
+
+impl ClickFade {
+    /// Create a new click fade
+    pub fn new(threshold_percent: u8, step: u8) -> Self {
+        Self {
+            threshold_percent,
+            step,
+        }
+    }
+
+    /// Click velocity after an answer with the given accuracy (0.0-1.0)
+    ///
+    /// `full` is the click's normal velocity, which a miss never goes above.
+    pub fn next_velocity(&self, velocity: u8, accuracy: f32, full: u8) -> u8 {
+        if accuracy * 100.0 >= f32::from(self.threshold_percent) {
+            velocity.saturating_sub(self.step)
+        } else {
+            velocity.saturating_add(self.step).min(full)
+        }
+    }
+}
+
+impl FromStr for ClickFade {
+    type Err = String;
+
+    /// Parse a fade such as "90%/10" or "90/10" (threshold percent / velocity step)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (threshold, step) = s.split_once('/').ok_or_else(|| {
+            format!(
+                "Invalid click fade '{}'. Format should be <percent>%/<step> (e.g., 90%/10)",
+                s
+            )
+        })?;
+
+        let threshold = threshold.trim();
+        let threshold = threshold.strip_suffix('%').unwrap_or(threshold);
+        let threshold_percent = threshold.trim().parse::<u8>().map_err(|_| {
+            format!(
+                "Invalid click fade threshold '{}'. Must be a percentage",
+                threshold
+            )
+        })?;
+        let step = step.trim().parse::<u8>().map_err(|_| {
+            format!(
+                "Invalid click fade step '{}'. Must be a velocity step (1-127)",
+                step.trim()
+            )
+        })?;
+
+        if threshold_percent == 0 || threshold_percent > 100 {
+            return Err("Click fade threshold must be between 1% and 100%".to_string());
+        }
+        if step == 0 || step > 127 {
+            return Err("Click fade step must be between 1 and 127".to_string());
+        }
+
+        Ok(Self::new(threshold_percent, step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_click_fade() {
+        assert_eq!("90%/10".parse(), Ok(ClickFade::new(90, 10)));
+        assert_eq!("75/5".parse(), Ok(ClickFade::new(75, 5)));
+        assert!("90%".parse::<ClickFade>().is_err());
+        assert!("0%/10".parse::<ClickFade>().is_err());
+        assert!("150%/10".parse::<ClickFade>().is_err());
+        assert!("90%/0".parse::<ClickFade>().is_err());
+        assert!("90%/200".parse::<ClickFade>().is_err());
+    }
+
+    #[test]
+    fn test_velocity_steps_down_to_silence_and_back_to_full() {
+        let fade = ClickFade::new(90, 30);
+        assert_eq!(fade.next_velocity(80, 0.9, 80), 50);
+        assert_eq!(fade.next_velocity(20, 1.0, 80), 0);
+        assert_eq!(fade.next_velocity(0, 0.89, 80), 30);
+        assert_eq!(fade.next_velocity(70, 0.0, 80), 80);
+    }
+}
@@ -0,0 +1,115 @@
+use alloc::format;
+use alloc::string::String;
+use core::str::FromStr;
+
+/// Most bars a click gap can sound or stay silent for
+pub const MAX_GAP_BARS: u32 = 16;
+
+/// Internal-time trainer: the click drops out for some bars of every cycle
+///
+/// The click sounds for `on_bars` bars, then stays silent for `off_bars`,
+/// and so on for as long as the pattern loops. The kicks carry on through
+/// the gap unless `mute_kicks` is set, in which case the player keeps time
+/// alone until the click comes back on the next cycle's first downbeat.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::ClickGap;
+///
+/// let gap: ClickGap = "2on/2off".parse().unwrap();
+/// let sounding: Vec<bool> = (0..6).map(|bar| gap.is_sounding(bar)).collect();
+/// assert_eq!(sounding, vec![true, true, false, false, true, true]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClickGap {
+    /// Bars the click sounds at the start of each cycle
+    pub on_bars: u32,
+    /// Silent bars that follow
+    pub off_bars: u32,
+    /// Whether the kicks drop out of the silent bars too
+    pub mute_kicks: bool,
+}
+
+impl ClickGap {
+    /// Create a gap that leaves the kicks playing
+    pub fn new(on_bars: u32, off_bars: u32) -> Self {
+        Self {
+            on_bars,
+            off_bars,
+            mute_kicks: false,
+        }
+    }
+
+    /// Drop the kicks out of the silent bars as well as the click
+    pub fn with_muted_kicks(mut self) -> Self {
+        self.mute_kicks = true;
+        self
+    }
+
+    /// Whether the click sounds during bar `bar_index`, counted from the
+    /// first bar after the count-in
+    pub fn is_sounding(&self, bar_index: u64) -> bool {
+        let cycle = u64::from(self.on_bars + self.off_bars).max(1);
+        bar_index % cycle < u64::from(self.on_bars)
+    }
+}
+
+impl core::fmt::Display for ClickGap {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{} on, {} silent", self.on_bars, self.off_bars)
+    }
+}
+
+impl FromStr for ClickGap {
+    type Err = String;
+
+    /// Parse a gap such as "2on/2off" or "2/2" (bars on / bars silent)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (on, off) = s.split_once('/').ok_or_else(|| {
+            format!(
+                "Invalid click gap '{}'. Format should be <n>on/<n>off (e.g., 2on/2off)",
+                s
+            )
+        })?;
+
+        let bars = |part: &str, suffix: &str| {
+            let part = part.trim().to_lowercase();
+            let count = part.strip_suffix(suffix).unwrap_or(&part).trim();
+            count
+                .parse::<u32>()
+                .ok()
+                .filter(|bars| (1..=MAX_GAP_BARS).contains(bars))
+                .ok_or_else(|| {
+                    format!(
+                        "Invalid click gap bars '{}'. Must be between 1 and {}",
+                        count, MAX_GAP_BARS
+                    )
+                })
+        };
+        Ok(Self::new(bars(on, "on")?, bars(off, "off")?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_click_gap() {
+        assert_eq!("2on/2off".parse(), Ok(ClickGap::new(2, 2)));
+        assert_eq!("3 / 1".parse(), Ok(ClickGap::new(3, 1)));
+        assert!("2on".parse::<ClickGap>().is_err());
+        assert!("0/2".parse::<ClickGap>().is_err());
+        assert!("2on/17off".parse::<ClickGap>().is_err());
+    }
+
+    #[test]
+    fn test_silent_bars_follow_the_sounding_ones() {
+        let gap = ClickGap::new(3, 1).with_muted_kicks();
+        assert!(gap.is_sounding(0) && gap.is_sounding(2));
+        assert!(!gap.is_sounding(3));
+        assert!(gap.is_sounding(4));
+        assert_eq!(gap.to_string(), "3 on, 1 silent");
+    }
+}
@@ -0,0 +1,154 @@
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+/// Largest number of beats on either side of a click ratio
+pub const MAX_RATIO_BEATS: u8 = 16;
+
+/// Tempo canon: the click runs at a simple ratio of the pattern tempo
+///
+/// A ratio of 3:4 plays three evenly spaced clicks in the time of four
+/// pattern beats, so the click runs at 3/4 of the pattern tempo. The two
+/// lanes share a downbeat every `pattern_beats` beats, and the canon starts
+/// over from a shared downbeat every [`ClickRatio::cycle_loops`] loops.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::ClickRatio;
+///
+/// let ratio: ClickRatio = "3:4".parse().unwrap();
+/// assert_eq!(ratio.click_tempo(120), 90.0);
+///
+/// // Over a 4-beat loop the clicks fall on beats 0, 1⅓, and 2⅔
+/// let clicks = ratio.clicks_in_loop(0, 4);
+/// assert_eq!(clicks.len(), 3);
+/// assert_eq!(clicks[1].0, 4.0 / 3.0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClickRatio {
+    /// Clicks in each alignment span
+    pub click_beats: u8,
+    /// Pattern beats in each alignment span
+    pub pattern_beats: u8,
+}
+
+impl ClickRatio {
+    /// A ratio of `click_beats` clicks to `pattern_beats` pattern beats, in lowest terms
+    pub fn new(click_beats: u8, pattern_beats: u8) -> Result<Self, CoreError> {
+        if !(1..=MAX_RATIO_BEATS).contains(&click_beats)
+            || !(1..=MAX_RATIO_BEATS).contains(&pattern_beats)
+        {
+            return Err(CoreError::Config(format!(
+                "Click ratio beats must be between 1 and {}",
+                MAX_RATIO_BEATS
+            )));
+        }
+        if click_beats == pattern_beats {
+            return Err(CoreError::Config(
+                "Click ratio 1:1 is the normal click; pick two different counts".to_string(),
+            ));
+        }
+        let divisor = gcd(u64::from(click_beats), u64::from(pattern_beats)) as u8;
+        Ok(Self {
+            click_beats: click_beats / divisor,
+            pattern_beats: pattern_beats / divisor,
+        })
+    }
+
+    /// Tempo the click runs at when the pattern plays at `pattern_bpm`
+    pub fn click_tempo(&self, pattern_bpm: u16) -> f64 {
+        f64::from(pattern_bpm) * f64::from(self.click_beats) / f64::from(self.pattern_beats)
+    }
+
+    /// Loops of `beats_per_loop` beats after which both lanes meet on a loop downbeat again
+    pub fn cycle_loops(&self, beats_per_loop: usize) -> u64 {
+        let pattern_beats = u64::from(self.pattern_beats);
+        pattern_beats / gcd(pattern_beats, beats_per_loop.max(1) as u64)
+    }
+
+    /// Clicks during loop `loop_index`, as offsets in pattern beats from the
+    /// loop's downbeat, each flagged when it lands on a pattern beat too
+    pub fn clicks_in_loop(&self, loop_index: u64, beats_per_loop: usize) -> Vec<(f64, bool)> {
+        let (p, q) = (u64::from(self.click_beats), u64::from(self.pattern_beats));
+        let beats = beats_per_loop.max(1) as u64;
+        let loop_in_cycle = loop_index % self.cycle_loops(beats_per_loop);
+
+        // Click k sounds k * q / p pattern beats into the cycle
+        let start = loop_in_cycle * beats * p;
+        let end = start + beats * p;
+        (start.div_ceil(q)..)
+            .take_while(|k| k * q < end)
+            .map(|k| ((k * q - start) as f64 / p as f64, k % p == 0))
+            .collect()
+    }
+}
+
+impl core::fmt::Display for ClickRatio {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}:{}", self.click_beats, self.pattern_beats)
+    }
+}
+
+impl FromStr for ClickRatio {
+    type Err = String;
+
+    /// Parse a ratio such as "3:4" or "3/4" (clicks : pattern beats)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid click ratio '{}'. Format should be <clicks>:<beats> (e.g., 3:4)",
+                s
+            )
+        };
+        let (click, pattern) = s.split_once([':', '/']).ok_or_else(invalid)?;
+        let click = click.trim().parse::<u8>().map_err(|_| invalid())?;
+        let pattern = pattern.trim().parse::<u8>().map_err(|_| invalid())?;
+        Self::new(click, pattern).map_err(String::from)
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_parse_and_reduce() {
+        let ratio: ClickRatio = "6/8".parse().unwrap();
+        assert_eq!(ratio, ClickRatio::new(3, 4).unwrap());
+        assert_eq!(ratio.to_string(), "3:4");
+
+        assert!("4:4".parse::<ClickRatio>().unwrap_err().contains("1:1"));
+        assert!("0:4".parse::<ClickRatio>().is_err());
+        assert!("3:17".parse::<ClickRatio>().is_err());
+        assert!("three".parse::<ClickRatio>().is_err());
+    }
+
+    #[test]
+    fn test_clicks_realign_on_a_shared_downbeat() {
+        // 2:3 over a 4-beat loop: clicks every 1.5 beats, aligned again after 3 loops
+        let ratio = ClickRatio::new(2, 3).unwrap();
+        assert_eq!(ratio.cycle_loops(4), 3);
+
+        let loops: Vec<Vec<(f64, bool)>> = (0..4).map(|i| ratio.clicks_in_loop(i, 4)).collect();
+        assert_eq!(loops[0], vec![(0.0, true), (1.5, false), (3.0, true)]);
+        assert_eq!(loops[1], vec![(0.5, false), (2.0, true), (3.5, false)]);
+        assert_eq!(loops[2], vec![(1.0, true), (2.5, false)]);
+        assert_eq!(loops[3], loops[0]);
+
+        // 3:4 fits a 4-beat loop exactly
+        assert_eq!(ClickRatio::new(3, 4).unwrap().cycle_loops(4), 1);
+        assert_eq!(ClickRatio::new(3, 2).unwrap().clicks_in_loop(0, 4).len(), 6);
+    }
+}
@@ -0,0 +1,328 @@
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::str::FromStr;
+
+/// Pattern complexity levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ComplexityLevel {
+    /// Simple patterns: 2-4 kicks, mostly on-beats, low syncopation
+    Simple,
+    /// Medium patterns: 4-6 kicks, balanced, moderate syncopation
+    #[default]
+    Medium,
+    /// Complex patterns: 6-8 kicks, off-beats emphasized, high syncopation
+    Complex,
+}
+
+impl ComplexityLevel {
+    /// Generation parameters this level is a preset for
+    pub fn params(self) -> ComplexityParams {
+        let (min_kicks, max_kicks, syncopation) = match self {
+            ComplexityLevel::Simple => (2, 4, 0.0),
+            ComplexityLevel::Medium => (4, 6, 0.5),
+            ComplexityLevel::Complex => (6, 8, 1.0),
+        };
+        ComplexityParams {
+            min_kicks,
+            max_kicks,
+            syncopation,
+            max_consecutive: 2,
+        }
+    }
+}
+
+impl FromStr for ComplexityLevel {
+    type Err = String;
+
+    /// Parse complexity level from string ("simple", "m", "3", ...)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "simple" | "s" | "1" => Ok(ComplexityLevel::Simple),
+            "medium" | "m" | "2" => Ok(ComplexityLevel::Medium),
+            "complex" | "c" | "3" => Ok(ComplexityLevel::Complex),
+            _ => Err(format!(
+                "Invalid complexity '{}'. Use: simple, medium, or complex",
+                s
+            )),
+        }
+    }
+}
+
+/// A point on a 1-10 complexity scale, for finer steps than the three levels
+///
+/// The levels sit at 2 (simple), 5 (medium), and 8 (complex), and the points
+/// between and beyond them interpolate the kick range and syncopation (and so
+/// the on- and off-beat weights), so moving up one point makes patterns a
+/// little harder rather than jumping a whole level.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{ComplexityLevel, ComplexityScale};
+///
+/// let six: ComplexityScale = "6".parse().unwrap();
+/// assert_eq!(six.level(), ComplexityLevel::Medium);
+/// assert!(six.params().syncopation > ComplexityLevel::Medium.params().syncopation);
+/// assert_eq!(ComplexityScale::from(ComplexityLevel::Complex).get(), 8);
+/// assert!(ComplexityScale::new(11).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ComplexityScale(u8);
+
+impl ComplexityScale {
+    /// Lowest point on the scale
+    pub const MIN: u8 = 1;
+    /// Highest point on the scale
+    pub const MAX: u8 = 10;
+
+    /// A point on the scale, checking it's from 1 to 10
+    pub fn new(value: u8) -> Result<Self, CoreError> {
+        if !(Self::MIN..=Self::MAX).contains(&value) {
+            return Err(CoreError::Config(format!(
+                "Complexity {} is out of range. Use a value from {} to {}",
+                value,
+                Self::MIN,
+                Self::MAX
+            )));
+        }
+        Ok(Self(value))
+    }
+
+    /// The point, from 1 to 10
+    pub fn get(self) -> u8 {
+        self.0
+    }
+
+    /// The nearest of the three levels, which patterns generated here are
+    /// filed under (in goals, stats, and the practice history)
+    pub fn level(self) -> ComplexityLevel {
+        match self.0 {
+            ..=3 => ComplexityLevel::Simple,
+            4..=6 => ComplexityLevel::Medium,
+            _ => ComplexityLevel::Complex,
+        }
+    }
+
+    /// The next point up, if this isn't the top
+    pub fn harder(self) -> Option<Self> {
+        Self::new(self.0 + 1).ok()
+    }
+
+    /// The next point down, if this isn't the bottom
+    pub fn easier(self) -> Option<Self> {
+        Self::new(self.0 - 1).ok()
+    }
+
+    /// Generation parameters at this point
+    ///
+    /// Each point adds two-thirds of a kick to the range and a sixth to the
+    /// syncopation, matching the level presets at 2, 5, and 8; syncopation
+    /// stops at 0.0 and 1.0, so 1 is sparser than simple and 9-10 denser
+    /// than complex.
+    pub fn params(self) -> ComplexityParams {
+        let from_simple = f32::from(self.0) - 2.0;
+        // Rounded to the nearest kick; casting truncates, so add a half first
+        let min_kicks = (2.0 + from_simple * 2.0 / 3.0 + 0.5) as usize;
+        ComplexityParams {
+            min_kicks,
+            max_kicks: min_kicks + 2,
+            syncopation: (from_simple / 6.0).clamp(0.0, 1.0),
+            max_consecutive: 2,
+        }
+    }
+}
+
+impl From<ComplexityLevel> for ComplexityScale {
+    /// The point a level's preset sits at
+    fn from(level: ComplexityLevel) -> Self {
+        match level {
+            ComplexityLevel::Simple => Self(2),
+            ComplexityLevel::Medium => Self(5),
+            ComplexityLevel::Complex => Self(8),
+        }
+    }
+}
+
+impl FromStr for ComplexityScale {
+    type Err = String;
+
+    /// Parse a point from 1 to 10
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim().parse::<u8>().map_err(|_| {
+            format!(
+                "Invalid complexity '{}'. Use a whole number from {} to {}",
+                s,
+                Self::MIN,
+                Self::MAX
+            )
+        })?;
+        Self::new(value).map_err(String::from)
+    }
+}
+
+/// What a generated pattern may look like, in finer steps than a [`ComplexityLevel`]
+///
+/// Each level is a preset of these (see [`ComplexityLevel::params`]); custom
+/// values let players dial in, say, sparse but heavily syncopated patterns.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{ComplexityLevel, ComplexityParams};
+///
+/// let params = ComplexityParams::new(3, 5, 0.9, 1).unwrap();
+/// assert!(params.syncopation > ComplexityLevel::Medium.params().syncopation);
+/// assert!(ComplexityParams::new(5, 3, 0.5, 2).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComplexityParams {
+    /// Fewest kicks per measure, counting the downbeat
+    pub min_kicks: usize,
+    /// Most kicks per measure
+    pub max_kicks: usize,
+    /// How strongly off-beats are favored: 0.0 leans on the beats, 0.5 follows
+    /// the metrical weights as they are, and 1.0 pushes kicks off the beat
+    pub syncopation: f32,
+    /// Longest run of back-to-back sixteenth kicks allowed
+    pub max_consecutive: usize,
+}
+
+impl ComplexityParams {
+    /// Create parameters, checking that the kick range and syncopation make sense
+    pub fn new(
+        min_kicks: usize,
+        max_kicks: usize,
+        syncopation: f32,
+        max_consecutive: usize,
+    ) -> Result<Self, CoreError> {
+        if min_kicks == 0 {
+            return Err(CoreError::Config(
+                "Patterns always have a kick on the downbeat. Use at least 1 kick".to_string(),
+            ));
+        }
+        if min_kicks > max_kicks {
+            return Err(CoreError::Config(format!(
+                "Kick range {}-{} is backwards. Put the lower count first",
+                min_kicks, max_kicks
+            )));
+        }
+        if !(0.0..=1.0).contains(&syncopation) {
+            return Err(CoreError::Config(format!(
+                "Syncopation {} is out of range. Use a value from 0.0 to 1.0",
+                syncopation
+            )));
+        }
+        if max_consecutive == 0 {
+            return Err(CoreError::Config(
+                "Allow at least 1 consecutive kick".to_string(),
+            ));
+        }
+        Ok(Self {
+            min_kicks,
+            max_kicks,
+            syncopation,
+            max_consecutive,
+        })
+    }
+
+    /// Weight multipliers for on-beat and off-beat positions
+    ///
+    /// Interpolates between the simple (2.0, 0.5), medium (1.0, 1.0), and
+    /// complex (1.0, 1.5) presets.
+    pub fn beat_multipliers(&self) -> (f32, f32) {
+        if self.syncopation <= 0.5 {
+            let t = self.syncopation / 0.5;
+            (2.0 - t, 0.5 + 0.5 * t)
+        } else {
+            let t = (self.syncopation - 0.5) / 0.5;
+            (1.0, 1.0 + 0.5 * t)
+        }
+    }
+}
+
+impl Default for ComplexityParams {
+    fn default() -> Self {
+        ComplexityLevel::default().params()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_presets_match_level_multipliers() {
+        assert_eq!(
+            ComplexityLevel::Simple.params().beat_multipliers(),
+            (2.0, 0.5)
+        );
+        assert_eq!(
+            ComplexityLevel::Medium.params().beat_multipliers(),
+            (1.0, 1.0)
+        );
+        assert_eq!(
+            ComplexityLevel::Complex.params().beat_multipliers(),
+            (1.0, 1.5)
+        );
+    }
+
+    #[test]
+    fn test_scale_matches_presets_at_levels_and_steps_between() {
+        for level in [
+            ComplexityLevel::Simple,
+            ComplexityLevel::Medium,
+            ComplexityLevel::Complex,
+        ] {
+            let point = ComplexityScale::from(level);
+            assert_eq!(point.params(), level.params());
+            assert_eq!(point.level(), level);
+        }
+
+        let kicks: Vec<(usize, usize)> = (1..=10)
+            .map(|n| ComplexityScale::new(n).unwrap().params())
+            .map(|params| (params.min_kicks, params.max_kicks))
+            .collect();
+        assert_eq!(
+            kicks,
+            vec![
+                (1, 3),
+                (2, 4),
+                (3, 5),
+                (3, 5),
+                (4, 6),
+                (5, 7),
+                (5, 7),
+                (6, 8),
+                (7, 9),
+                (7, 9)
+            ]
+        );
+        // Off-beats gain weight a little at each step
+        let off_beats: Vec<f32> = (2..=8)
+            .map(|n| {
+                ComplexityScale::new(n)
+                    .unwrap()
+                    .params()
+                    .beat_multipliers()
+                    .1
+            })
+            .collect();
+        assert!(off_beats.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(ComplexityScale::new(1).unwrap().easier(), None);
+        assert_eq!(ComplexityScale::new(10).unwrap().harder(), None);
+        assert!("0".parse::<ComplexityScale>().is_err());
+        assert!("medium".parse::<ComplexityScale>().is_err());
+    }
+
+    #[test]
+    fn test_new_rejects_bad_values() {
+        assert!(ComplexityParams::new(0, 4, 0.5, 2).is_err());
+        assert!(ComplexityParams::new(5, 4, 0.5, 2).is_err());
+        assert!(ComplexityParams::new(2, 4, 1.5, 2).is_err());
+        assert!(ComplexityParams::new(2, 4, 0.5, 0).is_err());
+        assert!(ComplexityParams::new(4, 4, 0.0, 1).is_ok());
+    }
+}
@@ -0,0 +1,220 @@
+use super::time_signature::TimeSignature;
+use crate::error::CoreError;
+use alloc::format;
+use alloc::vec::Vec;
+
+/// Most bars a count-in can last
+pub const MAX_COUNT_IN_BARS: u32 = 4;
+
+/// Sixteenths in an eighth note, the spacing of a subdivided count-in
+const EIGHTH_SIXTEENTHS: usize = 2;
+
+/// How each new pattern is counted in
+///
+/// `bars` bars of clicks on the meter's pulse (or each group's start in a
+/// grouped meter), every bar's downbeat accented. The last bar can fill in
+/// the eighths between its pulses, and can be voiced as a count-off: its
+/// pulses play consecutive notes from `count_off_note` up, so a sampler
+/// with "one", "two", ... on those keys speaks the count.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{CountIn, TimeSignature};
+///
+/// let count_in = CountIn::new(2)?.with_subdivision();
+/// let clicks = count_in.clicks(TimeSignature::three_four());
+/// let offsets: Vec<usize> = clicks.iter().map(|click| click.offset).collect();
+/// assert_eq!(offsets, vec![0, 4, 8, 12, 14, 16, 18, 20, 22]);
+/// assert_eq!(clicks[3].count, Some(1));
+/// # Ok::<(), kickbeats_core::CoreError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountIn {
+    /// Bars of clicks before the pattern starts (0 starts it right away)
+    pub bars: u32,
+    /// Whether the last bar clicks the eighths between its pulses too
+    pub subdivide: bool,
+    /// First of the consecutive notes a voiced count-off plays, one per
+    /// pulse of the last bar
+    pub count_off_note: Option<u8>,
+}
+
+/// One click of a count-in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountInClick {
+    /// Sixteenths from the start of the count-in
+    pub offset: usize,
+    /// Whether it falls on a bar's downbeat
+    pub accented: bool,
+    /// Whether it's an eighth between the last bar's pulses
+    pub subdivision: bool,
+    /// Which pulse of the last bar it is, counting from 1 (`None` for
+    /// earlier bars and subdivisions)
+    pub count: Option<usize>,
+}
+
+impl CountIn {
+    /// Count in with `bars` bars of plain clicks
+    pub fn new(bars: u32) -> Result<Self, CoreError> {
+        if bars > MAX_COUNT_IN_BARS {
+            return Err(CoreError::Config(format!(
+                "Count-in must be 0 to {} bars",
+                MAX_COUNT_IN_BARS
+            )));
+        }
+        Ok(Self {
+            bars,
+            ..Self::default()
+        })
+    }
+
+    /// Fill in the eighths between the last bar's pulses
+    pub fn with_subdivision(mut self) -> Self {
+        self.subdivide = true;
+        self
+    }
+
+    /// Voice the last bar's pulses on consecutive notes from `note` up
+    pub fn with_count_off(mut self, note: u8) -> Self {
+        self.count_off_note = Some(note);
+        self
+    }
+
+    /// Note the count-off plays on pulse `count` (from 1) of the last bar,
+    /// if it's voiced and the note is in range
+    pub fn count_off_note(&self, count: usize) -> Option<u8> {
+        let first = self.count_off_note?;
+        u8::try_from(usize::from(first) + count - 1)
+            .ok()
+            .filter(|&note| note <= 127)
+    }
+
+    /// Length of the count-in in sixteenths
+    pub fn sixteenths(&self, time_signature: TimeSignature) -> usize {
+        self.bars as usize * time_signature.sixteenths_per_measure()
+    }
+
+    /// Every click of the count-in, in order
+    pub fn clicks(&self, time_signature: TimeSignature) -> Vec<CountInClick> {
+        let bar_len = time_signature.sixteenths_per_measure();
+        let pulses = time_signature.group_starts();
+        let mut clicks = Vec::new();
+        for bar in 0..self.bars as usize {
+            let last = bar + 1 == self.bars as usize;
+            let start = bar * bar_len;
+            for (pulse, &offset) in pulses.iter().enumerate() {
+                clicks.push(CountInClick {
+                    offset: start + offset,
+                    accented: pulse == 0,
+                    subdivision: false,
+                    count: last.then_some(pulse + 1),
+                });
+            }
+            if last && self.subdivide {
+                clicks.extend(
+                    (0..bar_len)
+                        .step_by(EIGHTH_SIXTEENTHS)
+                        .filter(|offset| !pulses.contains(offset))
+                        .map(|offset| CountInClick {
+                            offset: start + offset,
+                            accented: false,
+                            subdivision: true,
+                            count: None,
+                        }),
+                );
+            }
+        }
+        clicks.sort_by_key(|click| click.offset);
+        clicks
+    }
+}
+
+impl Default for CountIn {
+    /// One bar of plain clicks
+    fn default() -> Self {
+        Self {
+            bars: 1,
+            subdivide: false,
+            count_off_note: None,
+        }
+    }
+}
+
+impl core::fmt::Display for CountIn {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if self.bars == 0 {
+            return write!(f, "none");
+        }
+        write!(
+            f,
+            "{} bar{}",
+            self.bars,
+            if self.bars == 1 { "" } else { "s" }
+        )?;
+        if self.subdivide {
+            write!(f, ", eighths in the last")?;
+        }
+        if let Some(note) = self.count_off_note {
+            write!(f, ", counted off from note {}", note)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn test_count_in_bars() {
+        let four_four = TimeSignature::four_four();
+        assert_eq!(CountIn::default().clicks(four_four).len(), 4);
+        assert!(CountIn::new(0).unwrap().clicks(four_four).is_empty());
+        assert_eq!(CountIn::new(0).unwrap().sixteenths(four_four), 0);
+        assert_eq!(CountIn::new(2).unwrap().sixteenths(four_four), 32);
+        assert!(CountIn::new(MAX_COUNT_IN_BARS + 1).is_err());
+
+        let clicks = CountIn::new(2).unwrap().clicks(four_four);
+        let accents: Vec<usize> = clicks
+            .iter()
+            .filter(|click| click.accented)
+            .map(|click| click.offset)
+            .collect();
+        assert_eq!(accents, vec![0, 16]);
+        let counts: Vec<Option<usize>> = clicks.iter().map(|click| click.count).collect();
+        assert_eq!(&counts[..5], &[None, None, None, None, Some(1)]);
+    }
+
+    #[test]
+    fn test_subdivides_between_the_pulses() {
+        let six_eight = CountIn::default()
+            .with_subdivision()
+            .clicks(TimeSignature::six_eight());
+        let subdivisions: Vec<usize> = six_eight
+            .iter()
+            .filter(|click| click.subdivision)
+            .map(|click| click.offset)
+            .collect();
+        assert_eq!(subdivisions, vec![2, 4, 8, 10]);
+
+        let seven_eight: TimeSignature = "7/8".parse().unwrap();
+        let eighths = CountIn::default()
+            .with_subdivision()
+            .clicks(seven_eight.with_grouping("2+2+3".parse().unwrap()).unwrap());
+        assert_eq!(eighths.len(), 7);
+        assert_eq!(eighths.iter().filter(|click| !click.subdivision).count(), 3);
+    }
+
+    #[test]
+    fn test_count_off_notes() {
+        let count_in = CountIn::default().with_count_off(60);
+        assert_eq!(count_in.count_off_note(1), Some(60));
+        assert_eq!(count_in.count_off_note(4), Some(63));
+        assert_eq!(CountIn::default().count_off_note(1), None);
+        assert_eq!(count_in.to_string(), "1 bar, counted off from note 60");
+        assert_eq!(CountIn::new(0).unwrap().to_string(), "none");
+    }
+}
@@ -0,0 +1,217 @@
+use super::complexity::ComplexityLevel;
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// When a curriculum stage is passed: enough good answers in a row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progression {
+    /// Accuracy (percent) an answer needs to count toward the streak
+    pub accuracy_percent: u8,
+    /// Answers in a row at or above that accuracy needed to move on
+    pub answers: u32,
+}
+
+/// One step of a curriculum: what to practice and when to move on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurriculumStage {
+    /// Short description shown when the stage begins (e.g., "Quarter notes")
+    pub name: String,
+    /// Complexity of patterns generated during the stage
+    pub complexity: ComplexityLevel,
+    /// Tempo for the stage, or the session's tempo if unset
+    pub tempo: Option<u16>,
+    /// What passes the stage; the last stage may have none
+    pub progression: Option<Progression>,
+}
+
+/// Ordered stages a student works through, advancing on answer accuracy
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{ComplexityLevel, Curriculum, CurriculumStage, Progression};
+///
+/// let stage = |name: &str, complexity| CurriculumStage {
+///     name: name.to_string(),
+///     complexity,
+///     tempo: None,
+///     progression: Some(Progression { accuracy_percent: 90, answers: 2 }),
+/// };
+/// let mut curriculum = Curriculum::new(vec![
+///     stage("Basics", ComplexityLevel::Simple),
+///     stage("Syncopation", ComplexityLevel::Medium),
+/// ])
+/// .unwrap();
+///
+/// assert!(curriculum.record(0.95).is_none());
+/// assert_eq!(curriculum.record(1.0).unwrap().name, "Syncopation");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Curriculum {
+    stages: Vec<CurriculumStage>,
+    /// Index of the stage being practiced
+    current: usize,
+    /// Good answers in a row during the current stage
+    streak: u32,
+}
+
+impl Curriculum {
+    /// Start a curriculum at its first stage
+    ///
+    /// Every stage but the last needs a progression, or the stages after it
+    /// could never be reached.
+    pub fn new(stages: Vec<CurriculumStage>) -> Result<Self, CoreError> {
+        if stages.is_empty() {
+            return Err(CoreError::Config(String::from(
+                "A curriculum needs at least one stage",
+            )));
+        }
+        for (i, stage) in stages.iter().enumerate() {
+            match stage.progression {
+                None if i + 1 < stages.len() => {
+                    return Err(CoreError::Config(format!(
+                        "Curriculum stage {} ({}) needs progression criteria to reach the next stage",
+                        i + 1,
+                        stage.name
+                    )));
+                }
+                Some(p) if p.accuracy_percent == 0 || p.accuracy_percent > 100 => {
+                    return Err(CoreError::Config(format!(
+                        "Curriculum stage {} ({}): accuracy must be between 1% and 100%",
+                        i + 1,
+                        stage.name
+                    )));
+                }
+                Some(p) if p.answers == 0 => {
+                    return Err(CoreError::Config(format!(
+                        "Curriculum stage {} ({}): needs at least 1 answer to advance",
+                        i + 1,
+                        stage.name
+                    )));
+                }
+                _ => {}
+            }
+            if let Some(tempo) = stage.tempo.filter(|t| !(40..=300).contains(t)) {
+                return Err(CoreError::Config(format!(
+                    "Curriculum stage {} ({}): tempo {} must be between 40 and 300",
+                    i + 1,
+                    stage.name,
+                    tempo
+                )));
+            }
+        }
+
+        Ok(Self {
+            stages,
+            current: 0,
+            streak: 0,
+        })
+    }
+
+    /// Every stage, in order
+    pub fn stages(&self) -> &[CurriculumStage] {
+        &self.stages
+    }
+
+    /// The stage being practiced
+    pub fn current(&self) -> &CurriculumStage {
+        &self.stages[self.current]
+    }
+
+    /// Position of the current stage, counting from 1
+    pub fn stage_number(&self) -> usize {
+        self.current + 1
+    }
+
+    /// Count an answer (accuracy 0.0-1.0) toward the current stage
+    ///
+    /// Returns the next stage when this answer passed the current one. A
+    /// miss restarts the streak; the last stage never advances.
+    pub fn record(&mut self, accuracy: f32) -> Option<&CurriculumStage> {
+        let progression = self.current().progression?;
+        if self.current + 1 == self.stages.len() {
+            return None;
+        }
+
+        if accuracy * 100.0 >= f32::from(progression.accuracy_percent) {
+            self.streak += 1;
+        } else {
+            self.streak = 0;
+        }
+        if self.streak < progression.answers {
+            return None;
+        }
+
+        self.current += 1;
+        self.streak = 0;
+        Some(self.current())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn stage(name: &str, progression: Option<Progression>) -> CurriculumStage {
+        CurriculumStage {
+            name: name.to_string(),
+            complexity: ComplexityLevel::Simple,
+            tempo: Some(90),
+            progression,
+        }
+    }
+
+    fn three_in_a_row() -> Option<Progression> {
+        Some(Progression {
+            accuracy_percent: 80,
+            answers: 3,
+        })
+    }
+
+    #[test]
+    fn test_advances_after_a_streak() {
+        let mut curriculum = Curriculum::new(vec![
+            stage("one", three_in_a_row()),
+            stage("two", three_in_a_row()),
+            stage("three", None),
+        ])
+        .unwrap();
+
+        assert!(curriculum.record(0.9).is_none());
+        assert!(curriculum.record(0.5).is_none());
+        assert!(curriculum.record(0.8).is_none());
+        assert!(curriculum.record(1.0).is_none());
+        assert_eq!(curriculum.record(0.85).unwrap().name, "two");
+        assert_eq!(curriculum.stage_number(), 2);
+
+        for _ in 0..2 {
+            assert!(curriculum.record(1.0).is_none());
+        }
+        assert_eq!(curriculum.record(1.0).unwrap().name, "three");
+        assert!(curriculum.record(1.0).is_none());
+        assert_eq!(curriculum.stage_number(), 3);
+    }
+
+    #[test]
+    fn test_rejects_unreachable_and_invalid_stages() {
+        assert!(Curriculum::new(vec![]).is_err());
+        assert!(
+            Curriculum::new(vec![stage("one", None), stage("two", None)])
+                .unwrap_err()
+                .message()
+                .contains("needs progression criteria")
+        );
+        let never = Some(Progression {
+            accuracy_percent: 0,
+            answers: 3,
+        });
+        assert!(Curriculum::new(vec![stage("one", never)]).is_err());
+        let mut fast = stage("one", None);
+        fast.tempo = Some(400);
+        assert!(Curriculum::new(vec![fast]).is_err());
+    }
+}
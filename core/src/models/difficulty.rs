@@ -0,0 +1,149 @@
+use super::complexity::ComplexityLevel;
+use super::pattern::Pattern;
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::String;
+use core::str::FromStr;
+
+/// Highest [`Pattern::difficulty_score`]
+pub const MAX_DIFFICULTY: u8 = 100;
+
+/// Range of [`Pattern::difficulty_score`] values a generator aims for
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::DifficultyRange;
+///
+/// let range: DifficultyRange = "30-50".parse().unwrap();
+/// assert!(range.contains(40));
+/// assert!(!range.contains(51));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DifficultyRange {
+    /// Lowest score accepted
+    pub min: u8,
+    /// Highest score accepted
+    pub max: u8,
+}
+
+impl DifficultyRange {
+    /// Create a range from `min` to `max`, inclusive
+    pub fn new(min: u8, max: u8) -> Result<Self, CoreError> {
+        if min > max {
+            return Err(CoreError::Config(format!(
+                "Difficulty range {}-{} is backwards. Put the lower score first",
+                min, max
+            )));
+        }
+        if max > MAX_DIFFICULTY {
+            return Err(CoreError::Config(format!(
+                "Difficulty {} is out of range. Scores run from 0 to {}",
+                max, MAX_DIFFICULTY
+            )));
+        }
+        Ok(Self { min, max })
+    }
+
+    /// Whether `score` falls in the range
+    pub fn contains(&self, score: u8) -> bool {
+        (self.min..=self.max).contains(&score)
+    }
+
+    /// Whether `pattern` scores in the range
+    pub fn accepts(&self, pattern: &Pattern) -> bool {
+        self.contains(pattern.difficulty_score())
+    }
+
+    /// Complexity levels to generate at, the one most likely to land in the range first
+    ///
+    /// Weighted simple patterns mostly score 0-35, medium ones 20-70, and complex ones 40-90.
+    pub fn levels(&self) -> [ComplexityLevel; 3] {
+        use ComplexityLevel::*;
+        match (u16::from(self.min) + u16::from(self.max)) / 2 {
+            0..=25 => [Simple, Medium, Complex],
+            26..=55 => [Medium, Simple, Complex],
+            _ => [Complex, Medium, Simple],
+        }
+    }
+}
+
+impl FromStr for DifficultyRange {
+    type Err = String;
+
+    /// Parse a range such as "30-50", or a single score such as "40"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |score: &str| {
+            score.trim().parse::<u8>().map_err(|_| {
+                format!(
+                    "Invalid difficulty '{}'. Use a score from 0 to {} or a range (e.g., 30-50)",
+                    s, MAX_DIFFICULTY
+                )
+            })
+        };
+        match s.split_once('-') {
+            Some((min, max)) => Self::new(parse(min)?, parse(max)?).map_err(String::from),
+            None => {
+                let score = parse(s)?;
+                Self::new(score, score).map_err(String::from)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TimeSignature;
+    use alloc::vec::Vec;
+
+    fn pattern(steps: &str, time_signature: TimeSignature) -> Pattern {
+        let steps: Vec<bool> = steps.chars().map(|c| c == 'x').collect();
+        Pattern::new(steps, time_signature, ComplexityLevel::Medium)
+    }
+
+    #[test]
+    fn test_parse_difficulty_range() {
+        assert_eq!("30-50".parse(), Ok(DifficultyRange::new(30, 50).unwrap()));
+        assert_eq!(
+            " 0 - 100 ".parse(),
+            Ok(DifficultyRange::new(0, 100).unwrap())
+        );
+        assert_eq!("40".parse(), Ok(DifficultyRange::new(40, 40).unwrap()));
+        assert!("50-30".parse::<DifficultyRange>().is_err());
+        assert!("30-101".parse::<DifficultyRange>().is_err());
+        assert!("hard".parse::<DifficultyRange>().is_err());
+    }
+
+    #[test]
+    fn test_on_beat_patterns_score_lowest() {
+        let four_four = TimeSignature::four_four();
+        let four_on_floor = pattern("x...x...x...x...", four_four);
+        let tresillo = pattern("x..x..x.x...x...", four_four);
+        let busy = pattern("x.xx.x..xx.x.x..", four_four);
+
+        assert_eq!(pattern("x.......x.......", four_four).difficulty_score(), 0);
+        assert_eq!(four_on_floor.difficulty_score(), 10);
+        assert!(four_on_floor.difficulty_score() < tresillo.difficulty_score());
+        assert!(tresillo.difficulty_score() < busy.difficulty_score());
+        assert!(busy.difficulty_score() <= MAX_DIFFICULTY);
+    }
+
+    #[test]
+    fn test_compound_meter_beats_are_dotted_quarters() {
+        let six_eight = TimeSignature::six_eight();
+        // Kicks on both dotted-quarter beats aren't syncopated
+        let on_beats = pattern("x.....x.....", six_eight);
+        // Tying over beat 2 from the eighth before it is
+        let pushed = pattern("x...x.......", six_eight);
+        assert!(on_beats.difficulty_score() < pushed.difficulty_score());
+    }
+
+    #[test]
+    fn test_levels_start_with_the_likeliest() {
+        let levels = |min, max| DifficultyRange::new(min, max).unwrap().levels()[0];
+        assert_eq!(levels(0, 20), ComplexityLevel::Simple);
+        assert_eq!(levels(30, 50), ComplexityLevel::Medium);
+        assert_eq!(levels(60, 100), ComplexityLevel::Complex);
+    }
+}
@@ -0,0 +1,120 @@
+use super::beat_grid::BeatGrid;
+use super::pattern::Pattern;
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use core::str::FromStr;
+
+/// Variation trainer: kicks off the beat only sound some of the time
+///
+/// Kicks on a counted beat play every loop; the rest (the ghost steps)
+/// each sound with `chance` percent probability, rolled fresh every loop,
+/// so the pattern shifts subtly from one repetition to the next.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{GhostSteps, Pattern};
+///
+/// let ghosts: GhostSteps = "chance=60".parse().unwrap();
+/// let pattern: Pattern = "4/4:16:x..x....x.x.....".parse().unwrap();
+/// let ghosted = ghosts.apply(&pattern);
+/// assert_eq!((ghosted.chance(0), ghosted.chance(3)), (100, 60));
+/// assert_eq!(ghosted.chance(8), 100);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GhostSteps {
+    /// Percent chance each ghost step sounds on a given loop
+    pub chance: u8,
+}
+
+impl GhostSteps {
+    /// Create ghost steps that sound `chance` percent of the time
+    pub fn new(chance: u8) -> Result<Self, CoreError> {
+        if !(1..=99).contains(&chance) {
+            return Err(CoreError::Config(String::from(
+                "Ghost steps must sound between 1% and 99% of the time",
+            )));
+        }
+        Ok(Self { chance })
+    }
+
+    /// Copy of `pattern` with its off-beat kicks made ghost steps
+    pub fn apply(&self, pattern: &Pattern) -> Pattern {
+        let mut ghosted = pattern.clone();
+        ghosted.chances = vec![100; pattern.steps.len()];
+        for (start, meter) in pattern.measures() {
+            let grid = BeatGrid::new(meter, pattern.subdivision, 1);
+            let beats = grid.beat_positions();
+            for position in 0..grid.total_positions() {
+                let step = start + position;
+                if pattern.steps.get(step) == Some(&true) && !beats.contains(&position) {
+                    ghosted.chances[step] = self.chance;
+                }
+            }
+        }
+        ghosted
+    }
+}
+
+impl Default for GhostSteps {
+    /// Ghost steps that sound 60% of the time
+    fn default() -> Self {
+        Self { chance: 60 }
+    }
+}
+
+impl core::fmt::Display for GhostSteps {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "off-beat kicks sound {}% of the time", self.chance)
+    }
+}
+
+impl FromStr for GhostSteps {
+    type Err = String;
+
+    /// Parse settings such as "chance=60"; left out, the chance is 60%
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ghosts = Self::default();
+        for setting in s.split([' ', ',']).filter(|part| !part.is_empty()) {
+            let invalid = || {
+                format!(
+                    "Invalid ghost step setting '{}'. Format should be chance=<percent> (e.g., chance=60)",
+                    setting
+                )
+            };
+            let (key, value) = setting.split_once('=').ok_or_else(invalid)?;
+            let value = value.trim().trim_end_matches('%');
+            match key.trim().to_lowercase().as_str() {
+                "chance" => ghosts.chance = value.parse().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            }
+        }
+        Self::new(ghosts.chance).map_err(String::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ghost_steps() {
+        assert_eq!("chance=60".parse(), Ok(GhostSteps::new(60).unwrap()));
+        assert_eq!("chance=35%".parse(), Ok(GhostSteps::new(35).unwrap()));
+        assert_eq!("".parse(), Ok(GhostSteps::default()));
+        assert!("chance=100".parse::<GhostSteps>().is_err());
+        assert!("chance=0".parse::<GhostSteps>().is_err());
+        assert!("odds=50".parse::<GhostSteps>().is_err());
+    }
+
+    #[test]
+    fn test_only_offbeat_kicks_become_ghosts() {
+        let pattern: Pattern = "4/4+3/4:16:x.x.x.......x...x...x..x....".parse().unwrap();
+        let ghosted = GhostSteps::new(40).unwrap().apply(&pattern);
+        let ghosts: alloc::vec::Vec<usize> = (0..28).filter(|&i| ghosted.chance(i) < 100).collect();
+        assert_eq!(ghosts, vec![2, 23]);
+        assert_eq!(ghosted.steps, pattern.steps);
+    }
+}
@@ -0,0 +1,299 @@
+use super::complexity::ComplexityLevel;
+use super::pattern::Pattern;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::str::FromStr;
+use uuid::Uuid;
+
+/// Built-in goals, by name, with the spec each one stands for
+pub const GOAL_TEMPLATES: &[(&str, &str)] = &[
+    ("warmup", "5 simple, 80%"),
+    ("steady", "10 medium @ 100+ BPM, 80%"),
+    ("mixed", "4 simple + 4 medium + 2 complex, 90%"),
+    ("gauntlet", "5 medium + 5 complex @ 120+ BPM, 100%"),
+];
+
+/// What a session sets out to do, e.g. "identify 10 medium patterns at 100+ BPM"
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{ComplexityLevel, SessionGoal};
+///
+/// let goal: SessionGoal = "4 simple + 6 medium @ 100+ BPM, 80%".parse().unwrap();
+/// assert_eq!(goal.total(), 10);
+/// assert_eq!(goal.min_tempo, Some(100));
+/// assert_eq!(goal.targets[1], (Some(ComplexityLevel::Medium), 6));
+///
+/// let steady: SessionGoal = "steady".parse().unwrap();
+/// assert_eq!(steady.to_string(), "10 medium @ 100+ BPM, 80%");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionGoal {
+    /// Patterns to identify at each complexity (`None` = any complexity)
+    pub targets: Vec<(Option<ComplexityLevel>, u32)>,
+    /// Slowest tempo an answer counts at
+    pub min_tempo: Option<u16>,
+    /// Lowest accuracy (0.0-1.0) an answer counts at
+    pub min_accuracy: f32,
+}
+
+impl SessionGoal {
+    /// Patterns to identify in all
+    pub fn total(&self) -> u32 {
+        self.targets.iter().map(|&(_, count)| count).sum()
+    }
+}
+
+impl core::fmt::Display for SessionGoal {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let targets: Vec<String> = self
+            .targets
+            .iter()
+            .map(|&(complexity, count)| match complexity {
+                Some(level) => format!("{} {}", count, level_name(level)),
+                None => count.to_string(),
+            })
+            .collect();
+        write!(f, "{}", targets.join(" + "))?;
+        if let Some(tempo) = self.min_tempo {
+            write!(f, " @ {}+ BPM", tempo)?;
+        }
+        write!(f, ", {:.0}%", self.min_accuracy * 100.0)
+    }
+}
+
+impl FromStr for SessionGoal {
+    type Err = String;
+
+    /// Parse a template name ("steady") or a spec such as
+    /// "4 simple + 6 medium @ 100+ BPM, 80%"
+    ///
+    /// Counts without a complexity accept any; the score defaults to 100%.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((_, spec)) = GOAL_TEMPLATES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s.trim()))
+        {
+            return spec.parse();
+        }
+
+        let invalid = || {
+            format!(
+                "Invalid goal '{}'. Use a template ({}) or e.g. \"10 medium @ 100+ BPM, 80%\"",
+                s,
+                GOAL_TEMPLATES
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
+
+        let mut targets = Vec::new();
+        let mut min_tempo = None;
+        let mut min_accuracy = 1.0;
+
+        let lower = s.to_lowercase();
+        for clause in lower
+            .split([',', '@'])
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+        {
+            if let Some(percent) = clause.strip_suffix('%') {
+                let percent: u32 = percent.trim().parse().map_err(|_| invalid())?;
+                if percent == 0 || percent > 100 {
+                    return Err("Goal score must be between 1% and 100%".to_string());
+                }
+                min_accuracy = percent as f32 / 100.0;
+            } else if let Some(tempo) = clause.strip_suffix("bpm") {
+                let tempo = tempo.trim().trim_end_matches('+').trim();
+                min_tempo = Some(tempo.parse::<u16>().map_err(|_| invalid())?);
+            } else {
+                for part in clause.split('+').map(str::trim) {
+                    let mut words = part.split_whitespace();
+                    let count: u32 = words
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .ok_or_else(invalid)?;
+                    let complexity = match words.next() {
+                        None | Some("patterns") | Some("pattern") | Some("any") => None,
+                        Some(level) => Some(level.parse::<ComplexityLevel>()?),
+                    };
+                    if words.next().is_some_and(|w| !w.starts_with("pattern")) {
+                        return Err(invalid());
+                    }
+                    if count == 0 {
+                        return Err("Goal counts must be at least 1".to_string());
+                    }
+                    targets.push((complexity, count));
+                }
+            }
+        }
+
+        if targets.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            targets,
+            min_tempo,
+            min_accuracy,
+        })
+    }
+}
+
+/// Lowercase name of a complexity level, as written in goals
+fn level_name(level: ComplexityLevel) -> &'static str {
+    match level {
+        ComplexityLevel::Simple => "simple",
+        ComplexityLevel::Medium => "medium",
+        ComplexityLevel::Complex => "complex",
+    }
+}
+
+/// How far a session has come toward its goal
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalProgress {
+    /// The goal being worked toward
+    pub goal: SessionGoal,
+    /// Patterns identified so far for each of the goal's targets
+    pub counts: Vec<u32>,
+    /// Patterns already counted, so retries don't count twice
+    counted: Vec<Uuid>,
+}
+
+impl GoalProgress {
+    /// Start working toward `goal`
+    pub fn new(goal: SessionGoal) -> Self {
+        Self {
+            counts: alloc::vec![0; goal.targets.len()],
+            goal,
+            counted: Vec::new(),
+        }
+    }
+
+    /// Count an answer to `pattern` at `tempo_bpm` if it meets the goal's bar
+    ///
+    /// A specific complexity target is filled before an "any" one. Returns
+    /// whether the answer counted.
+    pub fn record(&mut self, pattern: &Pattern, tempo_bpm: u16, accuracy: f32) -> bool {
+        if accuracy < self.goal.min_accuracy
+            || self.goal.min_tempo.is_some_and(|min| tempo_bpm < min)
+            || self.counted.contains(&pattern.id)
+        {
+            return false;
+        }
+
+        let open = |i: &usize| self.counts[*i] < self.goal.targets[*i].1;
+        let slot = (0..self.counts.len())
+            .filter(open)
+            .find(|&i| self.goal.targets[i].0 == Some(pattern.complexity_level))
+            .or_else(|| {
+                (0..self.counts.len())
+                    .filter(open)
+                    .find(|&i| self.goal.targets[i].0.is_none())
+            });
+        match slot {
+            Some(i) => {
+                self.counts[i] += 1;
+                self.counted.push(pattern.id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Patterns counted toward the goal so far
+    pub fn done(&self) -> u32 {
+        self.counts.iter().sum()
+    }
+
+    /// Whether every target has been reached
+    pub fn is_met(&self) -> bool {
+        self.done() >= self.goal.total()
+    }
+
+    /// One-line progress readout, e.g. "3/4 simple · 1/6 medium · 100+ BPM · 80%+"
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = self
+            .goal
+            .targets
+            .iter()
+            .zip(&self.counts)
+            .map(|(&(complexity, target), &done)| match complexity {
+                Some(level) => format!("{}/{} {}", done, target, level_name(level)),
+                None => format!("{}/{}", done, target),
+            })
+            .collect();
+        if let Some(tempo) = self.goal.min_tempo {
+            parts.push(format!("{}+ BPM", tempo));
+        }
+        parts.push(format!("{:.0}%+", self.goal.min_accuracy * 100.0));
+        parts.join(" · ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TimeSignature;
+    use alloc::vec;
+    use core::sync::atomic::{AtomicU64, Ordering};
+
+    /// A pattern with its own id, even where ids are otherwise all nil (no_std)
+    fn pattern(complexity: ComplexityLevel) -> Pattern {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        let id = Uuid::from_u64_pair(0, NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        Pattern::with_id(id, vec![true; 16], TimeSignature::four_four(), complexity)
+    }
+
+    #[test]
+    fn test_parse_goal_specs_and_templates() {
+        let goal: SessionGoal = "10 medium patterns @ 100+ BPM, 80%".parse().unwrap();
+        assert_eq!(goal.targets, vec![(Some(ComplexityLevel::Medium), 10)]);
+        assert_eq!(goal.min_tempo, Some(100));
+        assert_eq!(goal.min_accuracy, 0.8);
+
+        let any: SessionGoal = "6".parse().unwrap();
+        assert_eq!(any.targets, vec![(None, 6)]);
+        assert_eq!(any.min_accuracy, 1.0);
+        assert_eq!(any.to_string(), "6, 100%");
+
+        for (name, spec) in GOAL_TEMPLATES {
+            let goal: SessionGoal = name.parse().unwrap();
+            assert_eq!(goal.to_string(), *spec);
+        }
+
+        assert!("".parse::<SessionGoal>().is_err());
+        assert!("10 hard".parse::<SessionGoal>().is_err());
+        assert!("0 simple".parse::<SessionGoal>().is_err());
+        assert!("5 simple, 120%".parse::<SessionGoal>().is_err());
+        assert!("5 simple @ fast bpm".parse::<SessionGoal>().is_err());
+    }
+
+    #[test]
+    fn test_progress_counts_each_qualifying_pattern_once() {
+        let goal: SessionGoal = "1 complex + 2 @ 100+ BPM, 80%".parse().unwrap();
+        let mut progress = GoalProgress::new(goal);
+        let complex = pattern(ComplexityLevel::Complex);
+        let simple = pattern(ComplexityLevel::Simple);
+
+        // Too slow, then too inaccurate
+        assert!(!progress.record(&simple, 90, 1.0));
+        assert!(!progress.record(&simple, 120, 0.5));
+
+        assert!(progress.record(&simple, 120, 0.9));
+        assert!(!progress.record(&simple, 120, 1.0));
+        assert!(progress.record(&complex, 100, 1.0));
+        assert_eq!(progress.counts, vec![1, 1]);
+        assert_eq!(progress.summary(), "1/1 complex · 1/2 · 100+ BPM · 80%+");
+        assert!(!progress.is_met());
+
+        // With the complex target full, another complex pattern fills "any"
+        assert!(progress.record(&pattern(ComplexityLevel::Complex), 100, 1.0));
+        assert!(progress.is_met());
+        assert!(!progress.record(&pattern(ComplexityLevel::Medium), 100, 1.0));
+    }
+}
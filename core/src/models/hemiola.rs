@@ -0,0 +1,184 @@
+use super::time_signature::TimeSignature;
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::String;
+use core::str::FromStr;
+
+/// Most bars each pulse holds before the other takes over
+pub const MAX_HEMIOLA_BARS: u32 = 16;
+
+/// Pulses that have a name, in sixteenths
+const PULSE_NAMES: [(u8, &str); 6] = [
+    (2, "eighth"),
+    (3, "dotted-eighth"),
+    (4, "quarter"),
+    (6, "dotted-quarter"),
+    (8, "half"),
+    (12, "dotted-half"),
+];
+
+/// Metric modulation trainer: the click alternates between the written beat
+/// and an implied pulse
+///
+/// For the first `bars` bars of every `2 * bars` the click marks the meter's
+/// own beat; for the next `bars` it marks `pulse` instead (a dotted quarter
+/// over 4/4, say), on a click note of its own and running on across the bar
+/// lines, so the same kicks can be heard against either interpretation.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::Hemiola;
+///
+/// let hemiola: Hemiola = "pulse=dotted-quarter bars=2".parse().unwrap();
+/// assert_eq!(hemiola.pulse, 6);
+/// let implied: Vec<Option<u64>> = (0..6).map(|bar| hemiola.implied_bar(bar)).collect();
+/// assert_eq!(implied, vec![None, None, Some(0), Some(1), None, None]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hemiola {
+    /// Length of each implied beat, in sixteenths
+    pub pulse: u8,
+    /// Bars each pulse holds before the other takes over
+    pub bars: u32,
+}
+
+impl Hemiola {
+    /// Create a trainer alternating the written beat with a `pulse`-sixteenth
+    /// pulse every `bars` bars
+    pub fn new(pulse: u8, bars: u32) -> Result<Self, CoreError> {
+        if !(2..=12).contains(&pulse) {
+            return Err(CoreError::Config(String::from(
+                "Hemiola pulse must be between 2 and 12 sixteenths",
+            )));
+        }
+        if !(1..=MAX_HEMIOLA_BARS).contains(&bars) {
+            return Err(CoreError::Config(format!(
+                "Hemiola must switch pulses every 1 to {} bars",
+                MAX_HEMIOLA_BARS
+            )));
+        }
+        Ok(Self { pulse, bars })
+    }
+
+    /// Check that the pulse differs from `time_signature`'s own beat
+    pub fn check_meter(&self, time_signature: TimeSignature) -> Result<(), CoreError> {
+        if time_signature.pulse_sixteenths() == usize::from(self.pulse) {
+            return Err(CoreError::Config(format!(
+                "A {} pulse is already the beat of {}/{}; pick another hemiola pulse",
+                self.pulse_name(),
+                time_signature.numerator,
+                time_signature.denominator
+            )));
+        }
+        Ok(())
+    }
+
+    /// How far into its run of implied bars bar `bar_index` is, counted
+    /// from the first bar after the count-in, or `None` if it keeps the
+    /// written beat
+    pub fn implied_bar(&self, bar_index: u64) -> Option<u64> {
+        let bars = u64::from(self.bars);
+        (bar_index % (2 * bars)).checked_sub(bars)
+    }
+
+    /// Name of the pulse (e.g., "dotted-quarter")
+    pub fn pulse_name(&self) -> String {
+        PULSE_NAMES
+            .iter()
+            .find(|&&(sixteenths, _)| sixteenths == self.pulse)
+            .map_or_else(
+                || format!("{}-sixteenth", self.pulse),
+                |&(_, name)| String::from(name),
+            )
+    }
+}
+
+impl Default for Hemiola {
+    /// A dotted-quarter pulse, switching every 2 bars
+    fn default() -> Self {
+        Self { pulse: 6, bars: 2 }
+    }
+}
+
+impl core::fmt::Display for Hemiola {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "{} bar{} of the beat, then {} of a {} pulse",
+            self.bars,
+            if self.bars == 1 { "" } else { "s" },
+            self.bars,
+            self.pulse_name()
+        )
+    }
+}
+
+impl FromStr for Hemiola {
+    type Err = String;
+
+    /// Parse settings such as "pulse=dotted-quarter bars=2" (spaces or
+    /// commas between them); the pulse is a note name or a number of
+    /// sixteenths, and either may be left out for its default
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut hemiola = Self::default();
+        for setting in s.split([' ', ',']).filter(|part| !part.is_empty()) {
+            let invalid = || {
+                format!(
+                    "Invalid hemiola setting '{}'. Format should be pulse=<note or sixteenths> bars=<n> (e.g., pulse=dotted-quarter bars=2)",
+                    setting
+                )
+            };
+            let (key, value) = setting.split_once('=').ok_or_else(invalid)?;
+            let value = value.trim().to_lowercase();
+            match key.trim().to_lowercase().as_str() {
+                "pulse" => {
+                    hemiola.pulse = PULSE_NAMES
+                        .iter()
+                        .find(|&&(_, name)| name == value)
+                        .map(|&(sixteenths, _)| sixteenths)
+                        .map_or_else(|| value.parse().map_err(|_| invalid()), Ok)?;
+                }
+                "bars" => hemiola.bars = value.parse().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            }
+        }
+        Self::new(hemiola.pulse, hemiola.bars).map_err(String::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hemiola() {
+        assert_eq!("pulse=6 bars=2".parse(), Ok(Hemiola::new(6, 2).unwrap()));
+        assert_eq!(
+            "bars=4,pulse=dotted-eighth".parse(),
+            Ok(Hemiola::new(3, 4).unwrap())
+        );
+        assert_eq!("pulse=quarter".parse(), Ok(Hemiola::new(4, 2).unwrap()));
+        assert_eq!("".parse(), Ok(Hemiola::default()));
+        assert!("pulse=1".parse::<Hemiola>().is_err());
+        assert!("pulse=triplet".parse::<Hemiola>().is_err());
+        assert!("bars=0".parse::<Hemiola>().is_err());
+        assert!("beats=2".parse::<Hemiola>().is_err());
+    }
+
+    #[test]
+    fn test_pulse_must_differ_from_the_beat() {
+        let hemiola = Hemiola::new(6, 1).unwrap();
+        assert!(hemiola.check_meter(TimeSignature::four_four()).is_ok());
+        assert!(hemiola
+            .check_meter(TimeSignature::six_eight())
+            .unwrap_err()
+            .message()
+            .contains("dotted-quarter pulse is already the beat of 6/8"));
+        assert_eq!(Hemiola::new(5, 1).unwrap().pulse_name(), "5-sixteenth");
+        assert_eq!(
+            hemiola.to_string(),
+            "1 bar of the beat, then 1 of a dotted-quarter pulse"
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use super::pattern::Pattern;
+use std::collections::VecDeque;
+use std::time::SystemTime;
+
+/// Number of patterns remembered for uniqueness checks and browsing
+pub const HISTORY_CAPACITY: usize = 20;
+
+/// A pattern generated during the session, with when and how it was seen
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    /// The generated pattern
+    pub pattern: Pattern,
+    /// Position in the session's generation order (1 = first pattern)
+    pub number: u32,
+    /// When the pattern was generated
+    pub generated_at: SystemTime,
+    /// Whether the pattern has been revealed or graded
+    pub revealed: bool,
+}
+
+/// Bounded, navigable history of the session's most recent patterns
+#[derive(Debug, Clone)]
+pub struct PatternHistory {
+    entries: VecDeque<HistoryEntry>,
+}
+
+impl PatternHistory {
+    /// Create an empty history
+    pub fn new() -> Self {
+        Self {
+            entries: VecDeque::with_capacity(HISTORY_CAPACITY),
+        }
+    }
+
+    /// Record a newly generated pattern, evicting the oldest if at capacity
+    pub fn push(&mut self, pattern: Pattern, number: u32) {
+        if self.entries.len() >= HISTORY_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(HistoryEntry {
+            pattern,
+            number,
+            generated_at: SystemTime::now(),
+            revealed: false,
+        });
+    }
+
+    /// Number of remembered patterns
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no patterns have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries from most recent to oldest
+    pub fn iter_recent(&self) -> impl DoubleEndedIterator<Item = &HistoryEntry> {
+        self.entries.iter().rev()
+    }
+
+    /// Entry by recency (0 = most recent)
+    pub fn recent(&self, index: usize) -> Option<&HistoryEntry> {
+        self.entries.iter().rev().nth(index)
+    }
+
+    /// Find the entry for a pattern id
+    pub fn find(&self, id: uuid::Uuid) -> Option<&HistoryEntry> {
+        self.entries.iter().find(|e| e.pattern.id == id)
+    }
+
+    /// The entry generated just before the pattern with the given id
+    pub fn previous(&self, id: uuid::Uuid) -> Option<&HistoryEntry> {
+        let index = self.entries.iter().position(|e| e.pattern.id == id)?;
+        index.checked_sub(1).and_then(|i| self.entries.get(i))
+    }
+
+    /// Mark the pattern with the given id as revealed
+    pub fn mark_revealed(&mut self, id: uuid::Uuid) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.pattern.id == id) {
+            entry.revealed = true;
+        }
+    }
+
+    /// Patterns in generation order, for the generator's uniqueness check
+    pub fn patterns(&self) -> VecDeque<Pattern> {
+        self.entries.iter().map(|e| e.pattern.clone()).collect()
+    }
+}
+
+impl Default for PatternHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, TimeSignature};
+
+    fn pattern() -> Pattern {
+        Pattern::new(
+            vec![true; 16],
+            TimeSignature::four_four(),
+            ComplexityLevel::Simple,
+        )
+    }
+
+    #[test]
+    fn test_history_evicts_oldest() {
+        let mut history = PatternHistory::new();
+        for number in 1..=25 {
+            history.push(pattern(), number);
+        }
+
+        assert_eq!(history.len(), HISTORY_CAPACITY);
+        assert_eq!(history.recent(0).unwrap().number, 25);
+        assert_eq!(history.iter_recent().last().unwrap().number, 6);
+        assert_eq!(history.patterns().len(), HISTORY_CAPACITY);
+    }
+
+    #[test]
+    fn test_mark_revealed() {
+        let mut history = PatternHistory::new();
+        let first = pattern();
+        let id = first.id;
+        history.push(first, 1);
+        history.push(pattern(), 2);
+
+        history.mark_revealed(id);
+
+        assert!(history.find(id).unwrap().revealed);
+        assert!(!history.recent(0).unwrap().revealed);
+    }
+
+    #[test]
+    fn test_previous_walks_back_in_generation_order() {
+        let mut history = PatternHistory::new();
+        let patterns: Vec<Pattern> = (0..3).map(|_| pattern()).collect();
+        for (i, p) in patterns.iter().enumerate() {
+            history.push(p.clone(), i as u32 + 1);
+        }
+
+        assert_eq!(history.previous(patterns[2].id).unwrap().number, 2);
+        assert_eq!(history.previous(patterns[1].id).unwrap().number, 1);
+        assert!(history.previous(patterns[0].id).is_none());
+    }
+}
@@ -0,0 +1,131 @@
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::String;
+use core::str::FromStr;
+
+/// Most bars in one dropout cycle
+pub const MAX_DROPOUT_CYCLE: u32 = 32;
+
+/// Inner-hearing trainer: the kicks drop out for the last bars of every cycle
+///
+/// Of every `every` bars, the kicks play for the first `every - bars` and
+/// go silent for the last `bars` while the click keeps going, so the player
+/// carries the pattern in their head until it comes back to check against.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::KickDropout;
+///
+/// let dropout: KickDropout = "bars=1 every=4".parse().unwrap();
+/// let playing: Vec<bool> = (0..8).map(|bar| dropout.is_playing(bar)).collect();
+/// assert_eq!(playing, vec![true, true, true, false, true, true, true, false]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KickDropout {
+    /// Silent bars at the end of each cycle
+    pub bars: u32,
+    /// Bars in each cycle, counting the silent ones
+    pub every: u32,
+}
+
+impl KickDropout {
+    /// Create a dropout of `bars` silent bars in every `every`
+    pub fn new(bars: u32, every: u32) -> Result<Self, CoreError> {
+        if !(2..=MAX_DROPOUT_CYCLE).contains(&every) {
+            return Err(CoreError::Config(format!(
+                "Dropout cycle must be between 2 and {} bars",
+                MAX_DROPOUT_CYCLE
+            )));
+        }
+        if bars == 0 || bars >= every {
+            return Err(CoreError::Config(format!(
+                "Dropout must leave the kicks playing for at least one of every {} bars",
+                every
+            )));
+        }
+        Ok(Self { bars, every })
+    }
+
+    /// Whether the kicks play during bar `bar_index`, counted from the
+    /// first bar after the count-in
+    pub fn is_playing(&self, bar_index: u64) -> bool {
+        bar_index % u64::from(self.every) < u64::from(self.every - self.bars)
+    }
+}
+
+impl Default for KickDropout {
+    /// One silent bar in every four
+    fn default() -> Self {
+        Self { bars: 1, every: 4 }
+    }
+}
+
+impl core::fmt::Display for KickDropout {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "{} silent bar{} every {}",
+            self.bars,
+            if self.bars == 1 { "" } else { "s" },
+            self.every
+        )
+    }
+}
+
+impl FromStr for KickDropout {
+    type Err = String;
+
+    /// Parse settings such as "bars=2 every=4" (spaces or commas between
+    /// them); either may be left out for its default (1 bar every 4)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut dropout = Self::default();
+        for setting in s.split([' ', ',']).filter(|part| !part.is_empty()) {
+            let invalid = || {
+                format!(
+                    "Invalid dropout setting '{}'. Format should be bars=<n> every=<n> (e.g., bars=2 every=4)",
+                    setting
+                )
+            };
+            let (key, value) = setting.split_once('=').ok_or_else(invalid)?;
+            let value = value.trim().parse::<u32>().map_err(|_| invalid())?;
+            match key.trim().to_lowercase().as_str() {
+                "bars" => dropout.bars = value,
+                "every" => dropout.every = value,
+                _ => return Err(invalid()),
+            }
+        }
+        Self::new(dropout.bars, dropout.every).map_err(String::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dropout() {
+        assert_eq!(
+            "bars=2 every=4".parse(),
+            Ok(KickDropout::new(2, 4).unwrap())
+        );
+        assert_eq!(
+            "every=8,bars=3".parse(),
+            Ok(KickDropout::new(3, 8).unwrap())
+        );
+        assert_eq!("every=8".parse(), Ok(KickDropout::new(1, 8).unwrap()));
+        assert!("bars=4 every=4".parse::<KickDropout>().is_err());
+        assert!("bars=0".parse::<KickDropout>().is_err());
+        assert!("bars two".parse::<KickDropout>().is_err());
+        assert!("beats=2".parse::<KickDropout>().is_err());
+    }
+
+    #[test]
+    fn test_silent_bars_end_each_cycle() {
+        let dropout = KickDropout::new(2, 4).unwrap();
+        assert!(dropout.is_playing(0) && dropout.is_playing(1));
+        assert!(!dropout.is_playing(2) && !dropout.is_playing(3));
+        assert!(dropout.is_playing(4));
+        assert_eq!(dropout.to_string(), "2 silent bars every 4");
+    }
+}
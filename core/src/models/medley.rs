@@ -0,0 +1,144 @@
+use super::pattern::Pattern;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A session's patterns played back to back, once each, for review
+///
+/// A bar of rests, in the meter the next pattern opens with, sits between
+/// each pattern and the next, so the click carries on alone and marks where
+/// one ends and the next begins.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{ComplexityLevel, Medley, Pattern, TimeSignature};
+///
+/// let four = |kicks: &[usize]| {
+///     let steps = (0..16).map(|i| kicks.contains(&i)).collect();
+///     Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Simple)
+/// };
+/// let medley = Medley::new(&[(1, four(&[0, 8])), (2, four(&[0, 6, 12]))]).unwrap();
+/// assert_eq!(medley.pattern.steps.len(), 48);
+/// assert_eq!(medley.number_at(3), Some(1));
+/// assert_eq!(medley.number_at(20), None);
+/// assert_eq!(medley.number_at(40), Some(2));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Medley {
+    /// Every part and the rest bars between them, as one pattern
+    pub pattern: Pattern,
+    /// Session number, first step, and length in steps of each part, in order
+    pub parts: Vec<(u32, usize, usize)>,
+}
+
+impl Medley {
+    /// Play `parts` (session number and pattern) in order, a bar of rests
+    /// between each
+    ///
+    /// Parts on a different grid from the first (e.g., triplets among
+    /// sixteenths) are left out, since they can't share its bar lines.
+    /// `None` if there are no parts.
+    pub fn new(parts: &[(u32, Pattern)]) -> Option<Self> {
+        let subdivision = parts.first()?.1.subdivision;
+        let mut sections = Vec::new();
+        let mut spans = Vec::new();
+        let mut start = 0;
+        for (number, pattern) in parts.iter().filter(|(_, p)| p.subdivision == subdivision) {
+            if !sections.is_empty() {
+                let rest = rest_bar(pattern);
+                start += rest.steps.len();
+                sections.push(rest);
+            }
+            spans.push((*number, start, pattern.steps.len()));
+            start += pattern.steps.len();
+            sections.push(pattern.clone());
+        }
+        Some(Self {
+            pattern: Pattern::concat(&sections)?,
+            parts: spans,
+        })
+    }
+
+    /// Session number of the part sounding at `step`; `None` in a rest bar
+    pub fn number_at(&self, step: usize) -> Option<u32> {
+        self.parts
+            .iter()
+            .find(|&&(_, start, len)| (start..start + len).contains(&step))
+            .map(|&(number, _, _)| number)
+    }
+
+    /// Number of parts
+    pub fn len(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// Whether the medley has no parts
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+}
+
+/// A measure of rests in the meter `pattern` opens with, on its grid
+fn rest_bar(pattern: &Pattern) -> Pattern {
+    let measures = pattern.measures();
+    let len = measures
+        .get(1)
+        .map_or(pattern.steps.len(), |&(start, _)| start);
+    let mut rest = pattern.clone();
+    rest.steps = vec![false; len];
+    rest.meters = vec![pattern.time_signature];
+    rest.num_measures = 1;
+    rest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, TimeSignature};
+
+    fn pattern(text: &str) -> Pattern {
+        Pattern::from_pattern_string(text, ComplexityLevel::Medium).unwrap()
+    }
+
+    #[test]
+    fn test_rests_a_bar_between_parts_in_their_meter() {
+        let medley = Medley::new(&[
+            (3, pattern("4/4:16:x...x..x....x.x.")),
+            (4, pattern("3/4+2/4:16:x...x.......x..x....")),
+        ])
+        .unwrap();
+
+        // 16 steps, a 3/4 rest bar of 12, then 12 + 8
+        assert_eq!(medley.parts, vec![(3, 0, 16), (4, 28, 20)]);
+        assert_eq!(medley.pattern.steps.len(), 48);
+        assert!(medley.pattern.steps[16..28].iter().all(|&kick| !kick));
+        assert_eq!(
+            medley.pattern.meters,
+            vec![
+                TimeSignature::four_four(),
+                TimeSignature::three_four(),
+                TimeSignature::three_four(),
+                TimeSignature::two_four(),
+            ]
+        );
+        assert_eq!(medley.number_at(27), None);
+        assert_eq!(medley.number_at(28), Some(4));
+        assert_eq!(medley.number_at(48), None);
+    }
+
+    #[test]
+    fn test_leaves_out_parts_on_another_grid() {
+        let mut triplets = pattern("4/4:16:x...x...x...x...");
+        triplets.subdivision = 12;
+        let medley = Medley::new(&[
+            (1, pattern("4/4:16:x.......x.......")),
+            (2, triplets),
+            (3, pattern("4/4:16:x..x..x.........")),
+        ])
+        .unwrap();
+
+        assert_eq!(medley.len(), 2);
+        assert_eq!(medley.number_at(32), Some(3));
+        assert!(Medley::new(&[]).is_none());
+    }
+}
@@ -0,0 +1,75 @@
+// Models module
+// Core data structures for the rhythm practice tool
+
+pub mod answer_drill;
+pub mod arrangement;
+pub mod beat_grid;
+pub mod click_drift;
+pub mod click_fade;
+pub mod click_gap;
+pub mod click_ratio;
+pub mod complexity;
+pub mod count_in;
+pub mod curriculum;
+pub mod difficulty;
+pub mod ghost_steps;
+pub mod goal;
+pub mod hemiola;
+#[cfg(feature = "std")]
+pub mod history;
+pub mod kick_dropout;
+pub mod medley;
+pub mod morph;
+pub mod note;
+pub mod overlay;
+pub mod pattern;
+pub mod phrase_fill;
+pub mod plan;
+pub mod review;
+#[cfg(feature = "std")]
+pub mod session;
+pub mod tempo_ramp;
+pub mod tempo_range;
+pub mod time_signature;
+pub mod transform;
+
+// Re-export main types for convenience
+pub use answer_drill::AnswerDrill;
+pub use arrangement::{
+    ArrangedSection, Arrangement, ArrangementSpec, FormSection, PartSource, MAX_SECTION_LOOPS,
+};
+pub use beat_grid::BeatGrid;
+pub use click_drift::{ClickDrift, MAX_DRIFT_BPM};
+pub use click_fade::ClickFade;
+pub use click_gap::{ClickGap, MAX_GAP_BARS};
+pub use click_ratio::{ClickRatio, MAX_RATIO_BEATS};
+pub use complexity::{ComplexityLevel, ComplexityParams, ComplexityScale};
+pub use count_in::{CountIn, CountInClick, MAX_COUNT_IN_BARS};
+pub use curriculum::{Curriculum, CurriculumStage, Progression};
+pub use difficulty::{DifficultyRange, MAX_DIFFICULTY};
+pub use ghost_steps::GhostSteps;
+pub use goal::{GoalProgress, SessionGoal, GOAL_TEMPLATES};
+pub use hemiola::{Hemiola, MAX_HEMIOLA_BARS};
+#[cfg(feature = "std")]
+pub use history::{HistoryEntry, PatternHistory};
+pub use kick_dropout::{KickDropout, MAX_DROPOUT_CYCLE};
+pub use medley::Medley;
+pub use morph::Morph;
+pub use note::{NotedPattern, PatternNote, MAX_NOTE_CHARS};
+pub use overlay::Overlay;
+pub use pattern::{Pattern, Violation, PATTERN_STRING_RESOLUTION};
+pub use phrase_fill::{PhraseFill, MAX_FILL_PHRASE};
+pub use plan::{
+    PlanProgress, PlanSegment, PlanTransition, SegmentStats, SessionPlan, MAX_SEGMENT_MINUTES,
+};
+pub use review::{
+    review_quality, ReviewCard, ReviewQueue, MIN_EASINESS, PASSING_QUALITY, START_EASINESS,
+};
+#[cfg(feature = "std")]
+pub use session::{
+    relaxed_summary, today, PracticeSession, DEFAULT_ADVANCE_LOOPS, MIN_REVIEW_TAPS,
+};
+pub use tempo_ramp::TempoRamp;
+pub use tempo_range::TempoRange;
+pub use time_signature::{Grouping, TimeSignature};
+pub use transform::Transform;
@@ -0,0 +1,106 @@
+use super::pattern::Pattern;
+use crate::error::CoreError;
+use alloc::vec::Vec;
+
+/// Playback that turns one pattern into another, one step per stage
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{ComplexityLevel, Morph, Pattern, TimeSignature};
+///
+/// let four = |kicks: &[usize]| {
+///     let steps = (0..16).map(|i| kicks.contains(&i)).collect();
+///     Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Simple)
+/// };
+/// let morph = Morph::new(&four(&[0, 4, 8, 12]), &four(&[0, 6, 8, 14]), 2).unwrap();
+/// assert_eq!(morph.stages.len(), 5);
+/// assert_eq!(morph.stage_at(3), 1);
+/// assert_eq!(morph.stage_at(100), 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Morph {
+    /// Patterns played in turn, from the starting pattern to the target
+    pub stages: Vec<Pattern>,
+    /// Loops each stage plays before the next step changes
+    pub loops_per_stage: u32,
+}
+
+impl Morph {
+    /// Morph from `from` into `to`, holding each stage for `loops_per_stage` loops
+    pub fn new(from: &Pattern, to: &Pattern, loops_per_stage: u32) -> Result<Self, CoreError> {
+        Ok(Self {
+            stages: from.morph_path(to)?,
+            loops_per_stage: loops_per_stage.max(1),
+        })
+    }
+
+    /// Stage playing on loop `loop_index`; the target holds once reached
+    pub fn stage_at(&self, loop_index: u64) -> usize {
+        let stage = loop_index / u64::from(self.loops_per_stage);
+        (stage as usize).min(self.stages.len().saturating_sub(1))
+    }
+
+    /// Pattern the morph ends on
+    pub fn target(&self) -> &Pattern {
+        self.stages.last().expect("a morph has at least one stage")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, TimeSignature};
+    use alloc::vec;
+
+    fn pattern(kicks: &[usize]) -> Pattern {
+        let steps = (0..16).map(|i| kicks.contains(&i)).collect();
+        Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium)
+    }
+
+    #[test]
+    fn test_path_changes_one_step_at_a_time() {
+        let from = pattern(&[0, 4, 8, 12]);
+        let to = pattern(&[0, 3, 6, 8, 10]);
+        let path = from.morph_path(&to).unwrap();
+
+        // Additions and removals alternate, each left to right
+        let kicks: Vec<Vec<usize>> = path.iter().map(Pattern::note_positions).collect();
+        assert_eq!(
+            kicks,
+            vec![
+                vec![0, 4, 8, 12],
+                vec![0, 3, 4, 8, 12],
+                vec![0, 3, 8, 12],
+                vec![0, 3, 6, 8, 12],
+                vec![0, 3, 6, 8],
+                vec![0, 3, 6, 8, 10],
+            ]
+        );
+        assert!(path.windows(2).all(|w| w[0].hamming_distance(&w[1]) == 1));
+        assert_eq!(path[0].id, from.id);
+        assert_eq!(path.last().unwrap().id, to.id);
+    }
+
+    #[test]
+    fn test_identical_and_mismatched_patterns() {
+        let from = pattern(&[0, 8]);
+        assert_eq!(from.morph_path(&from.clone()).unwrap().len(), 1);
+
+        let waltz = Pattern::new(
+            vec![true; 12],
+            TimeSignature::three_four(),
+            ComplexityLevel::Simple,
+        );
+        assert!(Morph::new(&from, &waltz, 1).is_err());
+    }
+
+    #[test]
+    fn test_stage_holds_for_its_loops() {
+        let morph = Morph::new(&pattern(&[0]), &pattern(&[4, 8]), 3).unwrap();
+        assert_eq!(morph.stages.len(), 4);
+        let stages: Vec<usize> = (0..12).map(|i| morph.stage_at(i)).collect();
+        assert_eq!(stages, vec![0, 0, 0, 1, 1, 1, 2, 2, 2, 3, 3, 3]);
+        assert_eq!(morph.target().note_positions(), vec![4, 8]);
+    }
+}
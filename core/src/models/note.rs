@@ -0,0 +1,130 @@
+use super::pattern::Pattern;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+/// Longest note kept, in characters
+pub const MAX_NOTE_CHARS: usize = 200;
+
+/// A player's annotation of a pattern: free text, tags, or both
+///
+/// Parsed from what the player types, where words starting with `#` are tags
+/// and everything else is the note. Tags are kept lowercase, without the
+/// `#`, in the order first given.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::PatternNote;
+///
+/// let note: PatternNote = "#tricky push on the and of 3 #Samba-ish".parse().unwrap();
+/// assert_eq!(note.text, "push on the and of 3");
+/// assert_eq!(note.tags, vec!["tricky", "samba-ish"]);
+/// assert!(note.has_tag("#Tricky"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PatternNote {
+    /// Free text, empty if only tags were given
+    pub text: String,
+    /// Tags, lowercase and without the `#`
+    pub tags: Vec<String>,
+}
+
+impl PatternNote {
+    /// Whether the note is tagged `tag` (with or without the `#`, in any case)
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.trim().trim_start_matches('#');
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+}
+
+/// A pattern the player annotated, with its number in the session
+#[derive(Debug, Clone)]
+pub struct NotedPattern {
+    /// Position of the pattern in the session's generation order
+    pub number: u32,
+    /// The pattern annotated
+    pub pattern: Pattern,
+    /// What the player noted about it
+    pub note: PatternNote,
+}
+
+impl core::fmt::Display for PatternNote {
+    /// The note as typed: its text, then its tags (e.g., "push on 3 #tricky")
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(&self.text)?;
+        for (i, tag) in self.tags.iter().enumerate() {
+            let space = if i == 0 && self.text.is_empty() {
+                ""
+            } else {
+                " "
+            };
+            write!(f, "{}#{}", space, tag)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PatternNote {
+    type Err = String;
+
+    /// Parse a note such as "push on the and of 3 #tricky #samba-ish"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = Vec::new();
+        let mut tags: Vec<String> = Vec::new();
+        for word in s.split_whitespace() {
+            match word.strip_prefix('#') {
+                Some(tag) if !tag.is_empty() => {
+                    let tag = tag.to_lowercase();
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+                _ => words.push(word),
+            }
+        }
+        let text = words.join(" ");
+        if text.is_empty() && tags.is_empty() {
+            return Err("A note needs some text or #tags".to_string());
+        }
+        if text.chars().count() > MAX_NOTE_CHARS {
+            return Err(format!(
+                "Notes can be at most {} characters",
+                MAX_NOTE_CHARS
+            ));
+        }
+        Ok(Self { text, tags })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_parse_text_and_tags() {
+        let note: PatternNote = "  watch  the pickup #Tricky #tricky #fills "
+            .parse()
+            .unwrap();
+        assert_eq!(note.text, "watch the pickup");
+        assert_eq!(note.tags, vec!["tricky", "fills"]);
+        assert_eq!(note.to_string(), "watch the pickup #tricky #fills");
+
+        let tags_only: PatternNote = "#samba-ish".parse().unwrap();
+        assert!(tags_only.text.is_empty());
+        assert_eq!(tags_only.to_string(), "#samba-ish");
+        assert!(tags_only.has_tag("SAMBA-ISH"));
+        assert!(!tags_only.has_tag("samba"));
+    }
+
+    #[test]
+    fn test_rejects_empty_and_overlong_notes() {
+        assert!("".parse::<PatternNote>().is_err());
+        assert!("x"
+            .repeat(MAX_NOTE_CHARS + 1)
+            .parse::<PatternNote>()
+            .is_err());
+    }
+}
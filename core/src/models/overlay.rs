@@ -0,0 +1,125 @@
+use super::beat_grid::BeatGrid;
+use super::time_signature::TimeSignature;
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+/// A fixed reference figure played alongside the kick pattern on its own voice
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlay {
+    /// 3-2 son clave (`x..x..x...x.x...`)
+    SonClave,
+    /// 3-2 rumba clave (`x..x...x..x.x...`)
+    RumbaClave,
+    /// Tresillo, repeated every two beats (`x..x..x.`)
+    Tresillo,
+    /// Constant eighth notes
+    Eighths,
+}
+
+impl Overlay {
+    /// One cycle of the figure in sixteenths
+    fn figure(&self) -> &'static str {
+        match self {
+            Overlay::SonClave => "x..x..x...x.x...",
+            Overlay::RumbaClave => "x..x...x..x.x...",
+            Overlay::Tresillo => "x..x..x.",
+            Overlay::Eighths => "x.",
+        }
+    }
+
+    /// Name as accepted on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Overlay::SonClave => "son-clave",
+            Overlay::RumbaClave => "rumba-clave",
+            Overlay::Tresillo => "tresillo",
+            Overlay::Eighths => "eighths",
+        }
+    }
+
+    /// Overlay steps for one measure, repeating the figure to fill it
+    ///
+    /// Fails if the measure is not a whole number of figure cycles (e.g., a
+    /// clave in 3/4).
+    pub fn steps(&self, time_signature: TimeSignature) -> Result<Vec<bool>, CoreError> {
+        let figure = self.figure();
+        let positions = BeatGrid::new(time_signature, 16, 1).total_positions();
+
+        if positions == 0 || !positions.is_multiple_of(figure.len()) {
+            return Err(CoreError::Config(format!(
+                "Overlay {} does not fit {}/{} (needs a multiple of {} sixteenths per measure)",
+                self.name(),
+                time_signature.numerator,
+                time_signature.denominator,
+                figure.len()
+            )));
+        }
+
+        Ok(figure
+            .chars()
+            .map(|c| c == 'x')
+            .cycle()
+            .take(positions)
+            .collect())
+    }
+}
+
+impl FromStr for Overlay {
+    type Err = String;
+
+    /// Parse an overlay from string (e.g., "son-clave", "tresillo")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "son-clave" => Ok(Overlay::SonClave),
+            "rumba-clave" => Ok(Overlay::RumbaClave),
+            "tresillo" => Ok(Overlay::Tresillo),
+            "eighths" => Ok(Overlay::Eighths),
+            _ => Err(format!(
+                "Invalid overlay '{}'. Must be one of: son-clave, rumba-clave, tresillo, eighths",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_string(steps: &[bool]) -> String {
+        steps.iter().map(|&s| if s { 'x' } else { '.' }).collect()
+    }
+
+    #[test]
+    fn test_parse_overlay() {
+        assert_eq!("son-clave".parse::<Overlay>(), Ok(Overlay::SonClave));
+        assert_eq!("Eighths".parse::<Overlay>(), Ok(Overlay::Eighths));
+        assert!("bell".parse::<Overlay>().is_err());
+    }
+
+    #[test]
+    fn test_son_clave_in_four_four() {
+        let steps = Overlay::SonClave.steps(TimeSignature::four_four()).unwrap();
+        assert_eq!(to_string(&steps), "x..x..x...x.x...");
+    }
+
+    #[test]
+    fn test_figures_repeat_to_fill_measure() {
+        let steps = Overlay::Tresillo.steps(TimeSignature::four_four()).unwrap();
+        assert_eq!(to_string(&steps), "x..x..x.x..x..x.");
+
+        let steps = Overlay::Eighths.steps(TimeSignature::six_eight()).unwrap();
+        assert_eq!(to_string(&steps), "x.x.x.x.x.x.");
+    }
+
+    #[test]
+    fn test_rejects_meters_that_do_not_fit() {
+        assert!(Overlay::SonClave
+            .steps(TimeSignature::three_four())
+            .is_err());
+        assert!(Overlay::Tresillo.steps(TimeSignature::two_four()).is_ok());
+    }
+}
@@ -0,0 +1,715 @@
+use super::beat_grid::BeatGrid;
+use super::complexity::{ComplexityLevel, ComplexityParams};
+use super::time_signature::TimeSignature;
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+use rand::Rng;
+use uuid::Uuid;
+
+/// Syncopation per measure that scores the full syncopation share of
+/// [`Pattern::difficulty_score`]
+const MAX_SYNCOPATION: f32 = 6.0;
+
+/// Back-to-back sixteenth kicks per measure that score the full share
+const MAX_KICK_PAIRS: f32 = 2.0;
+
+/// Steps per whole note written by [`Pattern::to_pattern_string`]
+pub const PATTERN_STRING_RESOLUTION: usize = 16;
+
+/// Represents a rhythmic sequence of kick drum hits and rests
+///
+/// A Pattern consists of a binary array where each element represents a sixteenth note
+/// position. `true` indicates a kick drum hit, `false` indicates a rest. Patterns are
+/// immutable after creation and include metadata about their generation.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{Pattern, TimeSignature, ComplexityLevel};
+///
+/// // Four-on-the-floor pattern (kick on every beat)
+/// let steps = vec![
+///     true, false, false, false,  // Beat 1
+///     true, false, false, false,  // Beat 2
+///     true, false, false, false,  // Beat 3
+///     true, false, false, false,  // Beat 4
+/// ];
+/// let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Simple);
+/// assert_eq!(pattern.note_positions(), vec![0, 4, 8, 12]);
+/// assert_eq!(pattern.density(), 0.25);  // 4 kicks out of 16 positions
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    /// Unique identifier
+    pub id: Uuid,
+    /// Binary array representing 16th note positions (true = kick, false = rest)
+    pub steps: Vec<bool>,
+    /// Musical time signature
+    pub time_signature: TimeSignature,
+    /// Rhythmic resolution (16 = sixteenth notes)
+    pub subdivision: u8,
+    /// Number of measures in pattern
+    pub num_measures: u8,
+    /// Meter of each measure in order; `time_signature` is the first
+    pub meters: Vec<TimeSignature>,
+    /// Generation complexity level
+    pub complexity_level: ComplexityLevel,
+    /// Percent chance each step's kick sounds on a given loop; empty if
+    /// every kick always sounds
+    pub chances: Vec<u8>,
+}
+
+impl Pattern {
+    /// Create a new pattern with a random identifier
+    ///
+    /// Without the `std` feature there is no entropy source, so the id is nil;
+    /// use [`Pattern::with_id`] to supply one.
+    pub fn new(
+        steps: Vec<bool>,
+        time_signature: TimeSignature,
+        complexity_level: ComplexityLevel,
+    ) -> Self {
+        #[cfg(feature = "std")]
+        let id = Uuid::new_v4();
+        #[cfg(not(feature = "std"))]
+        let id = Uuid::nil();
+
+        Self::with_id(id, steps, time_signature, complexity_level)
+    }
+
+    /// Create a new pattern with a caller-supplied identifier
+    pub fn with_id(
+        id: Uuid,
+        steps: Vec<bool>,
+        time_signature: TimeSignature,
+        complexity_level: ComplexityLevel,
+    ) -> Self {
+        let num_measures = 1; // Single measure for now
+        let subdivision = 16; // 16th notes
+
+        Self {
+            id,
+            steps,
+            time_signature,
+            subdivision,
+            num_measures,
+            meters: vec![time_signature],
+            complexity_level,
+            chances: Vec::new(),
+        }
+    }
+
+    /// Join single-measure patterns into one pattern, with a bar line (and
+    /// possibly a meter change) between each part
+    ///
+    /// The result keeps the first part's id and complexity; `None` if `parts` is empty.
+    pub fn concat(parts: &[Pattern]) -> Option<Pattern> {
+        let mut pattern = parts.first()?.clone();
+        pattern.steps = parts.iter().flat_map(|p| p.steps.iter().copied()).collect();
+        pattern.meters = parts
+            .iter()
+            .flat_map(|p| p.meters.iter().copied())
+            .collect();
+        pattern.num_measures = pattern.meters.len() as u8;
+        pattern.chances = if parts.iter().all(Pattern::is_certain) {
+            Vec::new()
+        } else {
+            parts
+                .iter()
+                .flat_map(|p| (0..p.steps.len()).map(move |i| p.chance(i)))
+                .collect()
+        };
+        Some(pattern)
+    }
+
+    /// Group a single-measure pattern's beats as `time_signature` does, if
+    /// it's the pattern's meter (e.g., a saved 7/8 pattern played as 2+2+3)
+    pub fn regroup(&mut self, time_signature: TimeSignature) {
+        if !self.is_multi_measure() && self.time_signature.same_meter(&time_signature) {
+            self.time_signature = time_signature;
+            self.meters = vec![time_signature];
+        }
+    }
+
+    /// Whether the pattern spans more than one measure
+    pub fn is_multi_measure(&self) -> bool {
+        self.meters.len() > 1
+    }
+
+    /// First step and meter of each measure, laid out on the pattern's grid
+    pub fn measures(&self) -> Vec<(usize, TimeSignature)> {
+        let mut start = 0;
+        self.meters
+            .iter()
+            .map(|&meter| {
+                let measure = (start, meter);
+                start += BeatGrid::new(meter, self.subdivision, 1).total_positions();
+                measure
+            })
+            .collect()
+    }
+
+    /// Meters joined for display (e.g., "4/4", or "4/4 + 3/4")
+    pub fn meter_label(&self) -> String {
+        self.meters
+            .iter()
+            .map(|meter| format!("{}/{}", meter.numerator, meter.denominator))
+            .collect::<Vec<_>>()
+            .join(" + ")
+    }
+
+    /// Identifier for the pattern's rhythm that is the same in every session,
+    /// e.g. "4/4:x...x.x.....x..." or "4/4+3/4:..." for several measures
+    pub fn fingerprint(&self) -> String {
+        let meters: Vec<String> = self
+            .meters
+            .iter()
+            .map(|meter| format!("{}/{}", meter.numerator, meter.denominator))
+            .collect();
+        let steps: String = self
+            .steps
+            .iter()
+            .map(|&kick| if kick { 'x' } else { '.' })
+            .collect();
+        format!("{}:{}", meters.join("+"), steps)
+    }
+
+    /// Rebuild a pattern from its [`Pattern::fingerprint`], with a new id
+    pub fn from_fingerprint(
+        fingerprint: &str,
+        complexity_level: ComplexityLevel,
+    ) -> Result<Pattern, CoreError> {
+        let invalid =
+            || CoreError::Config(format!("Invalid pattern fingerprint '{}'", fingerprint));
+        let (meters, steps) = fingerprint.split_once(':').ok_or_else(invalid)?;
+        let steps: Vec<bool> = steps
+            .chars()
+            .map(|c| match c {
+                'x' => Ok(true),
+                '.' => Ok(false),
+                _ => Err(invalid()),
+            })
+            .collect::<Result<_, _>>()?;
+
+        let mut parts = Vec::new();
+        let mut start = 0;
+        for meter in meters.split('+') {
+            let meter: TimeSignature = meter.parse().map_err(|_| invalid())?;
+            let end = start + BeatGrid::new(meter, 16, 1).total_positions();
+            let measure = steps.get(start..end).ok_or_else(invalid)?;
+            parts.push(Pattern::new(measure.to_vec(), meter, complexity_level));
+            start = end;
+        }
+        if start != steps.len() {
+            return Err(invalid());
+        }
+        Pattern::concat(&parts).ok_or_else(invalid)
+    }
+
+    /// Compact text form for sharing the pattern, e.g. "4/4:16:x...x..x..x...x."
+    ///
+    /// The meter (each measure's, joined with `+`), the steps per whole note
+    /// (always 16 here), and one `x` (kick) or `.` (rest) per step. Read it
+    /// back with [`Pattern::from_pattern_string`] or `parse`.
+    pub fn to_pattern_string(&self) -> String {
+        let (meters, steps) = self
+            .fingerprint()
+            .split_once(':')
+            .map(|(meters, steps)| (meters.to_string(), steps.to_string()))
+            .unwrap_or_default();
+        format!("{}:{}:{}", meters, PATTERN_STRING_RESOLUTION, steps)
+    }
+
+    /// Read a pattern string (see [`Pattern::to_pattern_string`]), with a new id
+    ///
+    /// Steps may be written at 16 (sixteenths), 8 (eighths), 4 (quarters), 2,
+    /// or 1 per whole note; coarser steps are spread onto the sixteenth grid.
+    /// `X` counts as a kick and `-` as a rest, and `|` and spaces between
+    /// steps are ignored, so "4/4:8:x.x. | ..x." reads too.
+    pub fn from_pattern_string(
+        text: &str,
+        complexity_level: ComplexityLevel,
+    ) -> Result<Pattern, CoreError> {
+        let text = text.trim();
+        let usage = || {
+            CoreError::Config(format!(
+                "Invalid pattern '{}'. Format should be meter:resolution:steps (e.g., 4/4:16:x...x..x..x...x.)",
+                text
+            ))
+        };
+        let mut fields = text.splitn(3, ':');
+        let (Some(meters), Some(resolution), Some(steps)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            return Err(usage());
+        };
+        let resolution: usize = resolution.trim().parse().map_err(|_| usage())?;
+        if resolution == 0 || !PATTERN_STRING_RESOLUTION.is_multiple_of(resolution) {
+            return Err(CoreError::Config(format!(
+                "Invalid resolution {} in pattern '{}'. Must be 16, 8, 4, 2, or 1",
+                resolution, text
+            )));
+        }
+        let spread = PATTERN_STRING_RESOLUTION / resolution;
+
+        let mut kicks = Vec::new();
+        for c in steps.chars().filter(|c| !c.is_whitespace() && *c != '|') {
+            let kick = match c {
+                'x' | 'X' => true,
+                '.' | '-' => false,
+                _ => {
+                    return Err(CoreError::Config(format!(
+                        "Invalid step '{}' in pattern '{}'. Use x for a kick and . for a rest",
+                        c, text
+                    )))
+                }
+            };
+            kicks.push(kick);
+            kicks.extend(core::iter::repeat_n(false, spread - 1));
+        }
+
+        let mut parts = Vec::new();
+        let mut start = 0;
+        for meter in meters.split('+') {
+            let meter: TimeSignature = meter.trim().parse().map_err(CoreError::Config)?;
+            let length = BeatGrid::new(meter, 16, 1).total_positions();
+            if !length.is_multiple_of(spread) {
+                return Err(CoreError::Config(format!(
+                    "A measure of {}/{} doesn't divide into steps at resolution {}",
+                    meter.numerator, meter.denominator, resolution
+                )));
+            }
+            let measure = kicks.get(start..start + length).unwrap_or_default();
+            parts.push(Pattern::new(measure.to_vec(), meter, complexity_level));
+            start += length;
+        }
+        if start != kicks.len() {
+            return Err(CoreError::Config(format!(
+                "Pattern '{}' has {} steps, but its meter needs {}",
+                text,
+                kicks.len() / spread,
+                start / spread
+            )));
+        }
+        Pattern::concat(&parts).ok_or_else(usage)
+    }
+
+    /// Get indices where kicks occur (steps[i] == true)
+    pub fn note_positions(&self) -> Vec<usize> {
+        self.steps
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &has_kick)| if has_kick { Some(i) } else { None })
+            .collect()
+    }
+
+    /// Calculate ratio of kicks to total positions (0.0-1.0)
+    pub fn density(&self) -> f32 {
+        let kicks = self.steps.iter().filter(|&&s| s).count();
+        kicks as f32 / self.steps.len() as f32
+    }
+
+    /// How hard the pattern is to hear and play, from 0 (easiest) to 100
+    ///
+    /// Half the score is syncopation, measured Longuet-Higgins & Lee style:
+    /// each kick followed by rests on a stronger position (e.g., a kick on
+    /// the "a" of beat 1 with beat 2 silent) adds the difference in metrical
+    /// level. Density adds up to 30 and back-to-back sixteenth kicks up to 20.
+    /// The pattern loops, so rests at the end run into the first kick.
+    pub fn difficulty_score(&self) -> u8 {
+        let len = self.steps.len();
+        let kicks = self.note_positions();
+        if kicks.is_empty() {
+            return 0;
+        }
+        let levels = self.metrical_levels();
+
+        let mut syncopation = 0;
+        for (k, &kick) in kicks.iter().enumerate() {
+            let next = kicks.get(k + 1).copied().unwrap_or(kicks[0] + len);
+            let strongest_rest = (kick + 1..next).map(|i| levels[i % len]).max();
+            if let Some(rest) = strongest_rest.filter(|&rest| rest > levels[kick]) {
+                syncopation += rest - levels[kick];
+            }
+        }
+        let pairs = (0..len)
+            .filter(|&i| self.steps[i] && self.steps[(i + 1) % len])
+            .count();
+
+        let measures = self.meters.len().max(1) as f32;
+        let syncopation = (syncopation as f32 / measures / MAX_SYNCOPATION).min(1.0);
+        let density = ((self.density() - 0.125) / 0.375).clamp(0.0, 1.0);
+        let pairs = (pairs as f32 / measures / MAX_KICK_PAIRS).min(1.0);
+        // Round to the nearest point without `f32::round` (unavailable in no_std)
+        (100.0 * (0.5 * syncopation + 0.3 * density + 0.2 * pairs) + 0.5) as u8
+    }
+
+    /// Metrical level of each step: 3 on a measure's downbeat, 2 on beats
+    /// (dotted quarters in 6/8 and 9/8), 1 on eighths, and 0 on sixteenths
+    fn metrical_levels(&self) -> Vec<u32> {
+        let mut levels = Vec::with_capacity(self.steps.len());
+        for (_, meter) in self.measures() {
+            let eighth = usize::from(self.subdivision / 8).max(1);
+            let mut beat = usize::from(self.subdivision / meter.denominator).max(1);
+            if meter.denominator == 8 && meter.numerator % 3 == 0 {
+                beat *= 3;
+            }
+            let len = BeatGrid::new(meter, self.subdivision, 1).total_positions();
+            levels.extend((0..len).map(|offset| match offset {
+                0 => 3,
+                _ if offset.is_multiple_of(beat) => 2,
+                _ if offset.is_multiple_of(eighth) => 1,
+                _ => 0,
+            }));
+        }
+        levels.resize(self.steps.len(), 0);
+        levels
+    }
+
+    /// Number of grid positions in one counted beat, matching the grid's
+    /// separators (a quarter in 4/4, an eighth in 7/8, a dotted quarter in 6/8)
+    pub fn beat_len(&self) -> usize {
+        (self.time_signature.pulse_sixteenths() * usize::from(self.subdivision) / 16).max(1)
+    }
+
+    /// Number of beats the pattern spans on its grid
+    pub fn beat_count(&self) -> usize {
+        self.steps.len().div_ceil(self.beat_len())
+    }
+
+    /// Percent chance the kick at `step` sounds on a given loop
+    pub fn chance(&self, step: usize) -> u8 {
+        self.chances.get(step).copied().unwrap_or(100)
+    }
+
+    /// Whether every kick sounds on every loop
+    pub fn is_certain(&self) -> bool {
+        self.chances.iter().all(|&chance| chance >= 100)
+    }
+
+    /// The kicks that sound on one loop: each kept with its chance
+    ///
+    /// The result's kicks are all certain.
+    pub fn roll<R: Rng + ?Sized>(&self, rng: &mut R) -> Pattern {
+        let mut pattern = self.clone();
+        for (i, step) in pattern.steps.iter_mut().enumerate() {
+            let chance = self.chance(i);
+            if *step && chance < 100 {
+                *step = rng.gen_range(0..100) < chance;
+            }
+        }
+        pattern.chances.clear();
+        pattern
+    }
+
+    /// Copy of the pattern keeping only the kicks in its first `len` positions
+    pub fn prefix(&self, len: usize) -> Pattern {
+        let mut pattern = self.clone();
+        for step in pattern.steps.iter_mut().skip(len) {
+            *step = false;
+        }
+        pattern
+    }
+
+    /// Patterns leading from this one to `target`, one differing step at a time
+    ///
+    /// Starts with this pattern and ends with `target`. Added and removed
+    /// kicks alternate (additions first, each left to right) so the density
+    /// stays close to both ends along the way.
+    pub fn morph_path(&self, target: &Pattern) -> Result<Vec<Pattern>, CoreError> {
+        if self.steps.len() != target.steps.len() || self.meters != target.meters {
+            return Err(CoreError::Generation(format!(
+                "Can't morph {} into {}: patterns must share a length and meter",
+                self.meter_label(),
+                target.meter_label()
+            )));
+        }
+
+        let differing = |kick_in_target: bool| {
+            (0..self.steps.len()).filter(move |&i| {
+                self.steps[i] != target.steps[i] && target.steps[i] == kick_in_target
+            })
+        };
+        let mut additions = differing(true);
+        let mut removals = differing(false);
+
+        let mut path = vec![self.clone()];
+        let mut steps = self.steps.clone();
+        loop {
+            let flips = [additions.next(), removals.next()];
+            if flips.iter().all(Option::is_none) {
+                break;
+            }
+            for i in flips.into_iter().flatten() {
+                steps[i] = !steps[i];
+                let mut stage = target.clone();
+                stage.steps = steps.clone();
+                path.push(stage);
+            }
+        }
+        // The last stage is the target itself
+        if path.len() > 1 {
+            path.pop();
+            path.push(target.clone());
+        }
+        Ok(path)
+    }
+
+    /// Calculate Hamming distance to another pattern (number of differing positions)
+    pub fn hamming_distance(&self, other: &Pattern) -> u32 {
+        self.steps
+            .iter()
+            .zip(other.steps.iter())
+            .filter(|(a, b)| a != b)
+            .count() as u32
+    }
+
+    /// Validate pattern according to requirements
+    pub fn validate_steps(&self) -> Result<(), CoreError> {
+        self.check_steps()
+            .map_err(|violation| CoreError::Generation(violation.to_string()))
+    }
+
+    /// Validate a pattern generated to custom [`ComplexityParams`]: the kick
+    /// count of each measure and the longest run of kicks come from `params`
+    /// instead of the standard density and 2-kick limits
+    pub fn validate_params(&self, params: &ComplexityParams) -> Result<(), CoreError> {
+        self.check_params(params)
+            .map_err(|violation| CoreError::Generation(violation.to_string()))
+    }
+
+    /// [`validate_steps`](Self::validate_steps), naming the rule broken
+    pub fn check_steps(&self) -> Result<(), Violation> {
+        self.check_downbeat()?;
+
+        // 3. Density check: 0.125 (2 kicks) to 0.5 (8 kicks) per measure
+        let density = self.density();
+        if !(0.125..=0.5).contains(&density) {
+            return Err(Violation::Density(density));
+        }
+
+        self.check_runs(2)
+    }
+
+    /// [`validate_params`](Self::validate_params), naming the rule broken
+    pub fn check_params(&self, params: &ComplexityParams) -> Result<(), Violation> {
+        self.check_downbeat()?;
+
+        for (start, meter) in self.measures() {
+            let len = BeatGrid::new(meter, self.subdivision, 1).total_positions();
+            let end = (start + len).min(self.steps.len());
+            let kicks = self.steps[start..end].iter().filter(|&&s| s).count();
+            if !(params.min_kicks..=params.max_kicks).contains(&kicks) {
+                return Err(Violation::KickCount {
+                    kicks,
+                    min: params.min_kicks,
+                    max: params.max_kicks,
+                });
+            }
+        }
+
+        self.check_runs(params.max_consecutive)
+    }
+
+    /// 1-2. At least one kick, and a kick on beat 1
+    fn check_downbeat(&self) -> Result<(), Violation> {
+        if !self.steps.iter().any(|&s| s) {
+            return Err(Violation::NoKicks);
+        }
+        if !self.steps[0] {
+            return Err(Violation::NoDownbeat);
+        }
+        Ok(())
+    }
+
+    /// 4-6. Runs of kicks and rests
+    fn check_runs(&self, max_consecutive: usize) -> Result<(), Violation> {
+        // 4. No more than `max_consecutive` consecutive kicks
+        let mut consecutive = 0;
+        for &has_kick in &self.steps {
+            if has_kick {
+                consecutive += 1;
+                if consecutive > max_consecutive {
+                    return Err(Violation::ConsecutiveKicks(max_consecutive));
+                }
+            } else {
+                consecutive = 0;
+            }
+        }
+
+        // 5. At least one rest of 2+ positions required
+        let mut has_long_rest = false;
+        let mut rest_count = 0;
+        for &has_kick in &self.steps {
+            if !has_kick {
+                rest_count += 1;
+                if rest_count >= 2 {
+                    has_long_rest = true;
+                }
+            } else {
+                rest_count = 0;
+            }
+        }
+        if !has_long_rest {
+            return Err(Violation::NoLongRest);
+        }
+
+        // 6. Maximum 8 consecutive rests
+        rest_count = 0;
+        for &has_kick in &self.steps {
+            if !has_kick {
+                rest_count += 1;
+                if rest_count > 8 {
+                    return Err(Violation::LongRestRun);
+                }
+            } else {
+                rest_count = 0;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A rule of [`Pattern::validate_steps`] or [`Pattern::validate_params`] that
+/// a pattern breaks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Violation {
+    /// No kicks at all
+    NoKicks,
+    /// No kick on beat 1
+    NoDownbeat,
+    /// Density outside 0.125-0.5
+    Density(f32),
+    /// A measure's kick count outside a custom range
+    KickCount {
+        /// Kicks in the measure
+        kicks: usize,
+        /// Fewest allowed
+        min: usize,
+        /// Most allowed
+        max: usize,
+    },
+    /// A run of kicks longer than this many
+    ConsecutiveKicks(usize),
+    /// No rest of 2+ positions
+    NoLongRest,
+    /// More than 8 rests in a row
+    LongRestRun,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::NoKicks => write!(f, "Pattern must have at least one kick"),
+            Violation::NoDownbeat => write!(f, "Pattern must have kick on beat 1 (position 0)"),
+            Violation::Density(density) => write!(
+                f,
+                "Pattern density {:.3} out of range [0.125, 0.5]",
+                density
+            ),
+            Violation::KickCount { kicks, min, max } => write!(
+                f,
+                "Pattern has {} kicks in a measure, outside {}-{}",
+                kicks, min, max
+            ),
+            Violation::ConsecutiveKicks(max) => write!(
+                f,
+                "Pattern must not have more than {} consecutive kicks",
+                max
+            ),
+            Violation::NoLongRest => {
+                write!(f, "Pattern must have at least one rest of 2+ positions")
+            }
+            Violation::LongRestRun => {
+                write!(f, "Pattern must not have more than 8 consecutive rests")
+            }
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    /// Parse a pattern string (e.g., "4/4:16:x...x..x..x...x."), at the
+    /// default complexity level
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Pattern::from_pattern_string(s, ComplexityLevel::default()).map_err(String::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_string_round_trips() {
+        let pattern: Pattern = "4/4:16:x...x..x..x...x.".parse().unwrap();
+        assert_eq!(pattern.note_positions(), vec![0, 4, 7, 10, 14]);
+        assert_eq!(pattern.to_pattern_string(), "4/4:16:x...x..x..x...x.");
+
+        let pattern: Pattern = "4/4+3/4:16:x...x...x...x...x.......x...".parse().unwrap();
+        assert_eq!(pattern.meters.len(), 2);
+        assert_eq!(
+            pattern.to_pattern_string(),
+            "4/4+3/4:16:x...x...x...x...x.......x..."
+        );
+    }
+
+    #[test]
+    fn test_pattern_string_spreads_coarser_steps() {
+        let pattern: Pattern = "4/4:8:x.x. | ..X-".parse().unwrap();
+        assert_eq!(pattern.to_pattern_string(), "4/4:16:x...x.......x...");
+    }
+
+    #[test]
+    fn test_pattern_string_rejects_bad_input() {
+        assert!("x...x..."
+            .parse::<Pattern>()
+            .unwrap_err()
+            .contains("meter:resolution:steps"));
+        assert!("4/4:16:x...x..."
+            .parse::<Pattern>()
+            .unwrap_err()
+            .contains("has 8 steps, but its meter needs 16"));
+        assert!("4/4:16:x...o..x..x...x."
+            .parse::<Pattern>()
+            .unwrap_err()
+            .contains("Invalid step 'o'"));
+        assert!("4/4:12:x..x..x..x.."
+            .parse::<Pattern>()
+            .unwrap_err()
+            .contains("resolution 12"));
+        assert!("3/4:1:x".parse::<Pattern>().unwrap_err().contains("3/4"));
+    }
+
+    #[test]
+    fn test_roll_keeps_certain_kicks_and_varies_the_rest() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut pattern: Pattern = "4/4:16:x.x.x.......x...".parse().unwrap();
+        assert!(pattern.is_certain());
+        pattern.chances = vec![100; 16];
+        pattern.chances[2] = 50;
+        assert!(!pattern.is_certain());
+
+        let mut rng = StdRng::seed_from_u64(3);
+        let rolls: Vec<Pattern> = (0..50).map(|_| pattern.roll(&mut rng)).collect();
+        assert!(rolls
+            .iter()
+            .all(|roll| roll.steps[0] && roll.steps[4] && roll.is_certain()));
+        assert!(rolls.iter().any(|roll| roll.steps[2]));
+        assert!(rolls.iter().any(|roll| !roll.steps[2]));
+
+        let certain: Pattern = "4/4:16:x.x.............".parse().unwrap();
+        let joined = Pattern::concat(&[pattern.clone(), certain]).unwrap();
+        assert_eq!(joined.chances.len(), 32);
+        assert_eq!((joined.chance(2), joined.chance(18)), (50, 100));
+    }
+}
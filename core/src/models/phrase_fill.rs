@@ -0,0 +1,103 @@
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::String;
+use core::str::FromStr;
+
+/// Longest phrase a fill can end, in bars
+pub const MAX_FILL_PHRASE: u32 = 32;
+
+/// Fills at phrase ends: the last bar of every `every` plays a busier
+/// variation of the pattern instead of the pattern itself
+///
+/// Bars are counted from the first bar after the count-in, so with a fill
+/// every 4 the fills land on bars 4, 8, 12, and so on.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::PhraseFill;
+///
+/// let fills: PhraseFill = "every=4".parse().unwrap();
+/// let filled: Vec<bool> = (0..8).map(|bar| fills.is_fill(bar)).collect();
+/// assert_eq!(filled, vec![false, false, false, true, false, false, false, true]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhraseFill {
+    /// Bars in each phrase, counting the fill
+    pub every: u32,
+}
+
+impl PhraseFill {
+    /// Create fills at the end of every `every` bars
+    pub fn new(every: u32) -> Result<Self, CoreError> {
+        if !(2..=MAX_FILL_PHRASE).contains(&every) {
+            return Err(CoreError::Config(format!(
+                "Fills must come every 2 to {} bars",
+                MAX_FILL_PHRASE
+            )));
+        }
+        Ok(Self { every })
+    }
+
+    /// Whether bar `bar_index`, counted from the first bar after the
+    /// count-in, is a fill
+    pub fn is_fill(&self, bar_index: u64) -> bool {
+        bar_index % u64::from(self.every) == u64::from(self.every - 1)
+    }
+}
+
+impl Default for PhraseFill {
+    /// A fill every four bars
+    fn default() -> Self {
+        Self { every: 4 }
+    }
+}
+
+impl core::fmt::Display for PhraseFill {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "a fill every {} bars", self.every)
+    }
+}
+
+impl FromStr for PhraseFill {
+    type Err = String;
+
+    /// Parse settings such as "every=4"; an empty string is a fill every 4
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fills = Self::default();
+        for setting in s.split([' ', ',']).filter(|part| !part.is_empty()) {
+            let invalid = || {
+                format!(
+                    "Invalid fill setting '{}'. Format should be every=<n> (e.g., every=4)",
+                    setting
+                )
+            };
+            let (key, value) = setting.split_once('=').ok_or_else(invalid)?;
+            let value = value.trim().parse::<u32>().map_err(|_| invalid())?;
+            match key.trim().to_lowercase().as_str() {
+                "every" => fills.every = value,
+                _ => return Err(invalid()),
+            }
+        }
+        Self::new(fills.every).map_err(String::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fills() {
+        assert_eq!("every=8".parse(), Ok(PhraseFill::new(8).unwrap()));
+        assert_eq!("".parse(), Ok(PhraseFill::new(4).unwrap()));
+        assert!("every=1".parse::<PhraseFill>().is_err());
+        assert!("every=33".parse::<PhraseFill>().is_err());
+        assert!("bars=2".parse::<PhraseFill>().is_err());
+        assert!("every four".parse::<PhraseFill>().is_err());
+        assert_eq!(
+            PhraseFill::new(2).unwrap().to_string(),
+            "a fill every 2 bars"
+        );
+    }
+}
@@ -0,0 +1,426 @@
+use super::complexity::ComplexityLevel;
+use super::time_signature::TimeSignature;
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// Longest segment a plan can hold, in minutes
+pub const MAX_SEGMENT_MINUTES: u32 = 240;
+
+/// One timed block of a session plan and the settings it switches to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanSegment {
+    /// Label announced when the segment starts (e.g., "Warm-up")
+    pub name: Option<String>,
+    /// How long the segment lasts
+    pub minutes: u32,
+    /// Complexity to switch to, if any
+    pub complexity: Option<ComplexityLevel>,
+    /// Tempo to switch to, if any
+    pub tempo: Option<u16>,
+    /// Time signature to switch to, if any
+    pub time_signature: Option<TimeSignature>,
+}
+
+impl PlanSegment {
+    /// The segment's name, or "Segment N" for the segment at `index`
+    pub fn label(&self, index: usize) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("Segment {}", index + 1),
+        }
+    }
+}
+
+impl core::fmt::Display for PlanSegment {
+    /// e.g. "5 min simple in 3/4 @ 90 BPM"
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{} min", self.minutes)?;
+        if let Some(complexity) = self.complexity {
+            write!(f, " {}", format!("{:?}", complexity).to_lowercase())?;
+        }
+        if let Some(ts) = self.time_signature {
+            write!(f, " in {}/{}", ts.numerator, ts.denominator)?;
+        }
+        if let Some(tempo) = self.tempo {
+            write!(f, " @ {} BPM", tempo)?;
+        }
+        Ok(())
+    }
+}
+
+/// A practice session laid out as timed segments, played one after another
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{ComplexityLevel, PlanSegment, SessionPlan};
+///
+/// let segment = |minutes, complexity, tempo| PlanSegment {
+///     name: None,
+///     minutes,
+///     complexity: Some(complexity),
+///     tempo: Some(tempo),
+///     time_signature: None,
+/// };
+/// let plan = SessionPlan::new(vec![
+///     segment(5, ComplexityLevel::Simple, 90),
+///     segment(10, ComplexityLevel::Medium, 120),
+/// ])
+/// .unwrap();
+/// assert_eq!(plan.total_minutes(), 15);
+/// assert_eq!(plan.segment_at(4 * 60), Some(0));
+/// assert_eq!(plan.segment_at(5 * 60), Some(1));
+/// assert_eq!(plan.segment_at(15 * 60), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionPlan {
+    /// Segments in the order they're played
+    pub segments: Vec<PlanSegment>,
+}
+
+impl SessionPlan {
+    /// A plan of `segments`, checking each one's length and tempo
+    pub fn new(segments: Vec<PlanSegment>) -> Result<Self, CoreError> {
+        if segments.is_empty() {
+            return Err(CoreError::Config(
+                "A session plan needs at least one segment".to_string(),
+            ));
+        }
+        for (i, segment) in segments.iter().enumerate() {
+            if !(1..=MAX_SEGMENT_MINUTES).contains(&segment.minutes) {
+                return Err(CoreError::Config(format!(
+                    "{}: minutes must be between 1 and {}",
+                    segment.label(i),
+                    MAX_SEGMENT_MINUTES
+                )));
+            }
+            if let Some(tempo) = segment.tempo.filter(|t| !(40..=300).contains(t)) {
+                return Err(CoreError::Config(format!(
+                    "{}: tempo {} must be between 40 and 300",
+                    segment.label(i),
+                    tempo
+                )));
+            }
+        }
+        Ok(Self { segments })
+    }
+
+    /// Length of the whole plan
+    pub fn total_minutes(&self) -> u32 {
+        self.segments.iter().map(|s| s.minutes).sum()
+    }
+
+    /// Segment running `elapsed_secs` into the plan, or `None` once it's over
+    pub fn segment_at(&self, elapsed_secs: u64) -> Option<usize> {
+        let mut end = 0;
+        self.segments.iter().position(|segment| {
+            end += u64::from(segment.minutes) * 60;
+            elapsed_secs < end
+        })
+    }
+
+    /// Seconds into the plan at which the segment at `index` starts
+    pub fn start_secs(&self, index: usize) -> u64 {
+        self.segments[..index]
+            .iter()
+            .map(|s| u64::from(s.minutes) * 60)
+            .sum()
+    }
+
+    /// Parse a plan from TOML: one `[[segment]]` table per segment
+    ///
+    /// ```toml
+    /// [[segment]]
+    /// name = "Warm-up"
+    /// minutes = 5
+    /// complexity = "simple"
+    /// tempo = 90
+    ///
+    /// [[segment]]
+    /// minutes = 10
+    /// complexity = "medium"
+    /// tempo = 120
+    /// time_signature = "4/4"
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn from_toml_str(s: &str) -> Result<Self, CoreError> {
+        #[derive(serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct RawPlan {
+            segment: Vec<RawSegment>,
+        }
+
+        #[derive(serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct RawSegment {
+            name: Option<String>,
+            minutes: u32,
+            complexity: Option<String>,
+            tempo: Option<u16>,
+            time_signature: Option<String>,
+        }
+
+        let invalid =
+            |e: &dyn core::fmt::Display| CoreError::Config(format!("Invalid plan file: {}", e));
+        let raw: RawPlan = toml::from_str(s).map_err(|e| invalid(&e))?;
+        let segments = raw
+            .segment
+            .into_iter()
+            .map(|raw| {
+                Ok(PlanSegment {
+                    name: raw.name,
+                    minutes: raw.minutes,
+                    complexity: raw.complexity.as_deref().map(str::parse).transpose()?,
+                    tempo: raw.tempo,
+                    time_signature: raw.time_signature.as_deref().map(str::parse).transpose()?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(|e| invalid(&e))?;
+        Self::new(segments).map_err(|e| invalid(&e))
+    }
+
+    /// Load a plan from a TOML file
+    #[cfg(feature = "config")]
+    pub fn load(path: &std::path::Path) -> Result<Self, CoreError> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            CoreError::Config(format!(
+                "Failed to read plan file {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Self::from_toml_str(&contents)
+    }
+}
+
+/// What happened during one segment of a plan
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SegmentStats {
+    /// Patterns played during the segment
+    pub patterns: u32,
+    /// Accuracy (0.0-1.0) of each answer checked during the segment
+    pub accuracies: Vec<f32>,
+}
+
+impl SegmentStats {
+    /// Mean accuracy of the segment's answers, if there were any
+    pub fn average_accuracy(&self) -> Option<f32> {
+        (!self.accuracies.is_empty())
+            .then(|| self.accuracies.iter().sum::<f32>() / self.accuracies.len() as f32)
+    }
+}
+
+/// A change of segment reported by [`PlanProgress::update`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanTransition {
+    /// The segment at this index has started
+    Segment(usize),
+    /// The last segment has ended
+    Finished,
+}
+
+/// Where a session is in its plan, with what happened in each segment
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanProgress {
+    /// The plan being followed
+    pub plan: SessionPlan,
+    /// Index of the segment running (the last one once finished)
+    pub current: usize,
+    /// Patterns and answers for each segment
+    pub stats: Vec<SegmentStats>,
+    /// Whether the last segment has ended
+    pub finished: bool,
+}
+
+impl PlanProgress {
+    /// Start following `plan` at its first segment
+    pub fn new(plan: SessionPlan) -> Self {
+        Self {
+            stats: alloc::vec![SegmentStats::default(); plan.segments.len()],
+            plan,
+            current: 0,
+            finished: false,
+        }
+    }
+
+    /// The segment running, unless the plan is over
+    pub fn segment(&self) -> Option<&PlanSegment> {
+        (!self.finished).then(|| &self.plan.segments[self.current])
+    }
+
+    /// Move to the segment due `elapsed_secs` into the plan
+    ///
+    /// Returns the transition when the segment changes; segments whose whole
+    /// span passed at once are skipped.
+    pub fn update(&mut self, elapsed_secs: u64) -> Option<PlanTransition> {
+        if self.finished {
+            return None;
+        }
+        match self.plan.segment_at(elapsed_secs) {
+            Some(index) if index > self.current => {
+                self.current = index;
+                Some(PlanTransition::Segment(index))
+            }
+            Some(_) => None,
+            None => {
+                self.finished = true;
+                Some(PlanTransition::Finished)
+            }
+        }
+    }
+
+    /// Seconds left in the running segment
+    pub fn remaining_secs(&self, elapsed_secs: u64) -> u64 {
+        if self.finished {
+            return 0;
+        }
+        let end = self.plan.start_secs(self.current + 1);
+        end.saturating_sub(elapsed_secs)
+    }
+
+    /// Count a pattern toward the running segment
+    pub fn record_pattern(&mut self) {
+        if !self.finished {
+            self.stats[self.current].patterns += 1;
+        }
+    }
+
+    /// Count an answer toward the running segment
+    pub fn record_answer(&mut self, accuracy: f32) {
+        if !self.finished {
+            self.stats[self.current].accuracies.push(accuracy);
+        }
+    }
+
+    /// One line per segment, e.g. "Warm-up (5 min simple @ 90 BPM): 4 patterns, 3 answers, 87% avg"
+    pub fn summary_lines(&self) -> Vec<String> {
+        self.plan
+            .segments
+            .iter()
+            .zip(&self.stats)
+            .enumerate()
+            .map(|(i, (segment, stats))| {
+                let mut line = format!(
+                    "{} ({}): {} pattern{}",
+                    segment.label(i),
+                    segment,
+                    stats.patterns,
+                    if stats.patterns == 1 { "" } else { "s" }
+                );
+                if let Some(average) = stats.average_accuracy() {
+                    line.push_str(&format!(
+                        ", {} answer{}, {:.0}% avg",
+                        stats.accuracies.len(),
+                        if stats.accuracies.len() == 1 { "" } else { "s" },
+                        average * 100.0
+                    ));
+                }
+                line
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn plan() -> SessionPlan {
+        SessionPlan::new(vec![
+            PlanSegment {
+                name: Some("Warm-up".to_string()),
+                minutes: 5,
+                complexity: Some(ComplexityLevel::Simple),
+                tempo: Some(90),
+                time_signature: None,
+            },
+            PlanSegment {
+                name: None,
+                minutes: 10,
+                complexity: Some(ComplexityLevel::Medium),
+                tempo: Some(120),
+                time_signature: Some(TimeSignature::three_four()),
+            },
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rejects_empty_plans_and_bad_segments() {
+        assert!(SessionPlan::new(vec![]).is_err());
+
+        let mut segments = plan().segments;
+        segments[1].minutes = 0;
+        assert_eq!(
+            SessionPlan::new(segments.clone()).unwrap_err(),
+            CoreError::Config(String::from("Segment 2: minutes must be between 1 and 240"))
+        );
+        segments[1].minutes = 10;
+        segments[0].tempo = Some(20);
+        assert!(SessionPlan::new(segments)
+            .unwrap_err()
+            .message()
+            .starts_with("Warm-up: tempo 20"));
+    }
+
+    #[test]
+    fn test_progress_moves_through_segments_and_keeps_stats() {
+        let mut progress = PlanProgress::new(plan());
+        assert_eq!(progress.update(0), None);
+        progress.record_pattern();
+        progress.record_answer(1.0);
+        progress.record_answer(0.5);
+        assert_eq!(progress.remaining_secs(60), 240);
+
+        assert_eq!(progress.update(300), Some(PlanTransition::Segment(1)));
+        assert_eq!(progress.update(301), None);
+        progress.record_pattern();
+        assert_eq!(
+            progress.segment().unwrap().to_string(),
+            "10 min medium in 3/4 @ 120 BPM"
+        );
+
+        assert_eq!(progress.update(900), Some(PlanTransition::Finished));
+        assert_eq!(progress.update(1000), None);
+        assert_eq!(progress.segment(), None);
+        progress.record_pattern();
+
+        assert_eq!(
+            progress.summary_lines(),
+            vec![
+                "Warm-up (5 min simple @ 90 BPM): 1 pattern, 2 answers, 75% avg",
+                "Segment 2 (10 min medium in 3/4 @ 120 BPM): 1 pattern",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skips_segments_that_passed_at_once() {
+        let mut segments = plan().segments;
+        segments.push(segments[0].clone());
+        let mut progress = PlanProgress::new(SessionPlan::new(segments).unwrap());
+        assert_eq!(progress.update(16 * 60), Some(PlanTransition::Segment(2)));
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn test_parse_toml_plan() {
+        let text = "[[segment]]\nname = \"Warm-up\"\nminutes = 5\ncomplexity = \"simple\"\n\
+                    tempo = 90\n\n[[segment]]\nminutes = 10\ncomplexity = \"medium\"\n\
+                    tempo = 120\ntime_signature = \"3/4\"\n";
+        assert_eq!(SessionPlan::from_toml_str(text).unwrap(), plan());
+
+        assert!(SessionPlan::from_toml_str("[[segment]]\nminutes = 5\nbpm = 90\n").is_err());
+        assert!(
+            SessionPlan::from_toml_str("[[segment]]\nminutes = 5\ncomplexity = \"hard\"\n")
+                .unwrap_err()
+                .message()
+                .starts_with("Invalid plan file: Invalid complexity 'hard'")
+        );
+        assert!(SessionPlan::from_toml_str("").is_err());
+    }
+}
@@ -0,0 +1,342 @@
+use super::complexity::ComplexityLevel;
+use super::pattern::Pattern;
+use super::time_signature::TimeSignature;
+use crate::error::CoreError;
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Easiness factor a new review card starts with (SM-2)
+pub const START_EASINESS: f32 = 2.5;
+
+/// Lowest easiness factor a card can fall to (SM-2)
+pub const MIN_EASINESS: f32 = 1.3;
+
+/// Lowest quality (0-5) that counts as remembering a pattern
+pub const PASSING_QUALITY: u8 = 3;
+
+/// SM-2 quality (0-5) for an answer or tap score with the given accuracy (0.0-1.0)
+pub fn review_quality(accuracy: f32) -> u8 {
+    match accuracy {
+        a if a >= 1.0 => 5,
+        a if a >= 0.9 => 4,
+        a if a >= 0.75 => 3,
+        a if a >= 0.5 => 2,
+        a if a > 0.0 => 1,
+        _ => 0,
+    }
+}
+
+/// A missed pattern and when it is next due for review
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewCard {
+    /// The pattern's [`Pattern::fingerprint`]
+    pub fingerprint: String,
+    /// Complexity the pattern was generated at
+    pub complexity: ComplexityLevel,
+    /// How easily the pattern is remembered (SM-2 easiness factor)
+    pub easiness: f32,
+    /// Reviews passed in a row
+    pub repetitions: u32,
+    /// Days between the last review and the next
+    pub interval_days: u32,
+    /// Day number (days since 1970-01-01) the card is next due
+    pub due_day: u64,
+    /// Times the pattern was missed
+    pub lapses: u32,
+}
+
+impl ReviewCard {
+    /// A new card for `pattern`, not yet reviewed
+    pub fn new(pattern: &Pattern, today: u64) -> Self {
+        Self {
+            fingerprint: pattern.fingerprint(),
+            complexity: pattern.complexity_level,
+            easiness: START_EASINESS,
+            repetitions: 0,
+            interval_days: 0,
+            due_day: today,
+            lapses: 0,
+        }
+    }
+
+    /// Schedule the next review after one graded `quality` (0-5) on day `today`
+    pub fn review(&mut self, quality: u8, today: u64) {
+        let quality = quality.min(5);
+        if quality < PASSING_QUALITY {
+            self.repetitions = 0;
+            self.interval_days = 1;
+            self.lapses += 1;
+        } else {
+            self.repetitions += 1;
+            self.interval_days = match self.repetitions {
+                1 => 1,
+                2 => 6,
+                // Round to the nearest day without `f32::round` (unavailable in no_std)
+                _ => (self.interval_days as f32 * self.easiness + 0.5) as u32,
+            };
+        }
+        let miss = f32::from(5 - quality);
+        self.easiness = (self.easiness + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASINESS);
+        self.due_day = today + u64::from(self.interval_days);
+    }
+
+    /// Whether the card is due on day `today`
+    pub fn is_due(&self, today: u64) -> bool {
+        self.due_day <= today
+    }
+
+    /// The card's pattern, with a new id
+    pub fn pattern(&self) -> Result<Pattern, CoreError> {
+        Pattern::from_fingerprint(&self.fingerprint, self.complexity)
+    }
+}
+
+/// Spaced-repetition queue of patterns that were answered or tapped badly
+///
+/// Missed patterns join the queue and come back for review on an SM-2
+/// schedule: soon after a miss, then at growing intervals as they're
+/// answered well. Each pattern is served and graded at most once a session.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{ComplexityLevel, Pattern, ReviewQueue, TimeSignature};
+///
+/// let steps = (0..16).map(|i| i % 6 == 0).collect();
+/// let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium);
+///
+/// let mut queue = ReviewQueue::new();
+/// queue.record(&pattern, 2, 100);
+/// assert_eq!(queue.due_count(100), 0);
+/// assert_eq!(queue.due_count(101), 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ReviewQueue {
+    cards: Vec<ReviewCard>,
+    /// Fingerprints served for review this session
+    served: BTreeSet<String>,
+    /// Fingerprints graded this session
+    graded: BTreeSet<String>,
+}
+
+impl ReviewQueue {
+    /// Create an empty queue
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A queue holding previously saved cards
+    pub fn from_cards(cards: Vec<ReviewCard>) -> Self {
+        Self {
+            cards,
+            ..Self::default()
+        }
+    }
+
+    /// Every card, in the order they joined the queue
+    pub fn cards(&self) -> &[ReviewCard] {
+        &self.cards
+    }
+
+    /// Number of patterns in the queue
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    /// Whether no patterns are queued
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    /// Cards due on day `today`
+    pub fn due_count(&self, today: u64) -> usize {
+        self.cards.iter().filter(|card| card.is_due(today)).count()
+    }
+
+    /// Grade a pattern on day `today` with `quality` (0-5)
+    ///
+    /// A queued pattern moves along its schedule; a pattern that isn't queued
+    /// joins the queue if it was missed. Only the first grade for a pattern
+    /// each session counts. Returns whether the pattern is in the queue.
+    pub fn record(&mut self, pattern: &Pattern, quality: u8, today: u64) -> bool {
+        let fingerprint = pattern.fingerprint();
+        let index = self
+            .cards
+            .iter()
+            .position(|card| card.fingerprint == fingerprint);
+        if !self.graded.insert(fingerprint) {
+            return index.is_some();
+        }
+        match index {
+            Some(index) => self.cards[index].review(quality, today),
+            None if quality < PASSING_QUALITY => {
+                let mut card = ReviewCard::new(pattern, today);
+                card.review(quality, today);
+                self.cards.push(card);
+            }
+            None => return false,
+        }
+        true
+    }
+
+    /// Cards graded this session
+    pub fn graded_cards(&self) -> impl Iterator<Item = &ReviewCard> {
+        self.cards
+            .iter()
+            .filter(|card| self.graded.contains(&card.fingerprint))
+    }
+
+    /// `saved` with this session's grades laid over it
+    ///
+    /// For saving a queue another session may have saved since this one
+    /// loaded it: cards this session didn't grade keep their saved schedule.
+    pub fn merged_onto(&self, saved: ReviewQueue) -> ReviewQueue {
+        let mut cards = saved.cards;
+        for card in self.graded_cards() {
+            match cards
+                .iter_mut()
+                .find(|saved| saved.fingerprint == card.fingerprint)
+            {
+                Some(saved) => *saved = card.clone(),
+                None => cards.push(card.clone()),
+            }
+        }
+        ReviewQueue::from_cards(cards)
+    }
+
+    /// Take the most overdue card in `time_signature` not yet served this
+    /// session, returning its pattern
+    pub fn next_due(&mut self, today: u64, time_signature: TimeSignature) -> Option<Pattern> {
+        let card = self
+            .cards
+            .iter()
+            .filter(|card| card.is_due(today) && !self.served.contains(&card.fingerprint))
+            .filter_map(|card| Some((card, card.pattern().ok()?)))
+            .filter(|(_, pattern)| pattern.time_signature.same_meter(&time_signature))
+            .min_by_key(|(card, _)| card.due_day);
+        let (fingerprint, mut pattern) =
+            card.map(|(card, pattern)| (card.fingerprint.clone(), pattern))?;
+        pattern.regroup(time_signature);
+        self.served.insert(fingerprint);
+        Some(pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn pattern(kicks: &[usize]) -> Pattern {
+        let steps = (0..16).map(|i| kicks.contains(&i)).collect();
+        Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium)
+    }
+
+    #[test]
+    fn test_fingerprint_round_trips() {
+        let original = pattern(&[0, 3, 10]);
+        assert_eq!(original.fingerprint(), "4/4:x..x......x.....");
+        let rebuilt = Pattern::from_fingerprint(&original.fingerprint(), ComplexityLevel::Medium);
+        assert_eq!(rebuilt.unwrap().steps, original.steps);
+
+        let waltz = Pattern::new(
+            vec![true; 12],
+            TimeSignature::three_four(),
+            ComplexityLevel::Simple,
+        );
+        let both = Pattern::concat(&[original, waltz]).unwrap();
+        let rebuilt =
+            Pattern::from_fingerprint(&both.fingerprint(), ComplexityLevel::Medium).unwrap();
+        assert_eq!(rebuilt.meters, both.meters);
+        assert_eq!(rebuilt.steps, both.steps);
+
+        assert!(Pattern::from_fingerprint("4/4:x...", ComplexityLevel::Medium).is_err());
+        assert!(Pattern::from_fingerprint("4/4x...", ComplexityLevel::Medium).is_err());
+    }
+
+    #[test]
+    fn test_sm2_intervals_grow_and_reset_on_a_miss() {
+        let mut card = ReviewCard::new(&pattern(&[0, 8]), 0);
+        card.review(5, 0);
+        assert_eq!((card.interval_days, card.due_day), (1, 1));
+        card.review(5, 1);
+        assert_eq!((card.interval_days, card.due_day), (6, 7));
+        card.review(4, 7);
+        // 6 days at easiness 2.7, rounded
+        assert_eq!(card.interval_days, 16);
+        assert!((card.easiness - 2.7).abs() < 1e-6);
+
+        card.review(1, 23);
+        assert_eq!(
+            (card.repetitions, card.interval_days, card.lapses),
+            (0, 1, 1)
+        );
+        assert!(card.easiness < 2.7);
+
+        for _ in 0..10 {
+            card.review(0, 24);
+        }
+        assert_eq!(card.easiness, MIN_EASINESS);
+    }
+
+    #[test]
+    fn test_misses_join_the_queue_and_come_back_once_a_session() {
+        let missed = pattern(&[0, 3, 10]);
+        let mut queue = ReviewQueue::new();
+        assert!(!queue.record(&pattern(&[0, 8]), review_quality(1.0), 10));
+        assert!(queue.record(&missed, review_quality(0.6), 10));
+        // A second answer the same session doesn't count again
+        assert!(queue.record(&missed, review_quality(0.0), 10));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.cards()[0].lapses, 1);
+
+        let mut next_session = ReviewQueue::from_cards(queue.cards().to_vec());
+        assert!(next_session
+            .next_due(10, TimeSignature::four_four())
+            .is_none());
+        assert!(next_session
+            .next_due(11, TimeSignature::three_four())
+            .is_none());
+        let due = next_session
+            .next_due(11, TimeSignature::four_four())
+            .unwrap();
+        assert_eq!(due.steps, missed.steps);
+        assert!(next_session
+            .next_due(11, TimeSignature::four_four())
+            .is_none());
+
+        // Answering it well pushes it out
+        assert!(next_session.record(&due, 5, 11));
+        assert_eq!(next_session.cards()[0].due_day, 12);
+        assert_eq!(next_session.due_count(11), 0);
+    }
+
+    #[test]
+    fn test_merging_keeps_cards_another_session_saved() {
+        let mut first = ReviewQueue::new();
+        let mut second = ReviewQueue::new();
+        first.record(&pattern(&[0, 3, 10]), 1, 10);
+        second.record(&pattern(&[0, 6, 12]), 1, 10);
+        // Ungraded cards loaded at the start don't undo newer saves
+        let mut stale = ReviewQueue::from_cards(first.cards().to_vec());
+        stale.record(&pattern(&[0, 5]), 2, 10);
+
+        let saved = second.merged_onto(first.clone());
+        assert_eq!(saved.len(), 2);
+        let saved = stale.merged_onto(saved);
+        assert_eq!(saved.len(), 3);
+        assert_eq!(saved.cards()[1], second.cards()[0]);
+        assert_eq!(stale.graded_cards().count(), 1);
+    }
+
+    #[test]
+    fn test_review_quality() {
+        assert_eq!(review_quality(1.0), 5);
+        assert_eq!(review_quality(0.9), 4);
+        assert_eq!(review_quality(0.8), 3);
+        assert_eq!(review_quality(0.5), 2);
+        assert_eq!(review_quality(0.1), 1);
+        assert_eq!(review_quality(0.0), 0);
+    }
+}
@@ -0,0 +1,458 @@
+use super::answer_drill::AnswerDrill;
+use super::click_drift::ClickDrift;
+use super::click_fade::ClickFade;
+use super::click_gap::ClickGap;
+use super::click_ratio::ClickRatio;
+use super::complexity::ComplexityLevel;
+use super::count_in::CountIn;
+use super::curriculum::{Curriculum, CurriculumStage};
+use super::ghost_steps::GhostSteps;
+use super::goal::GoalProgress;
+use super::hemiola::Hemiola;
+use super::history::PatternHistory;
+use super::kick_dropout::KickDropout;
+use super::note::{NotedPattern, PatternNote};
+use super::overlay::Overlay;
+use super::pattern::Pattern;
+use super::phrase_fill::PhraseFill;
+use super::plan::PlanProgress;
+use super::review::{review_quality, ReviewQueue};
+use super::tempo_ramp::TempoRamp;
+use super::tempo_range::TempoRange;
+use super::time_signature::TimeSignature;
+use crate::grading::{AnswerFormat, AnswerResolution, TapCounts, TapGrade};
+use crate::visualizer::RevealStyle;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Loops each pattern plays under auto-advance unless `--loops` says otherwise
+pub const DEFAULT_ADVANCE_LOOPS: u32 = 4;
+
+/// Kick taps on a pattern needed before they count toward its review schedule
+pub const MIN_REVIEW_TAPS: usize = 4;
+
+/// Represents a single user interaction with the tool
+#[derive(Debug)]
+pub struct PracticeSession {
+    /// Unique session identifier
+    pub session_id: Uuid,
+    /// Currently playing/displayed pattern
+    pub current_pattern: Option<Pattern>,
+    /// Last N patterns generated (max 20, for uniqueness checking and browsing)
+    pub pattern_history: PatternHistory,
+    /// Playback tempo in beats per minute (40-300)
+    pub tempo_bpm: u16,
+    /// Optional gradual tempo increase while a pattern loops
+    pub tempo_ramp: Option<TempoRamp>,
+    /// Tempos each new pattern picks from at random, if set
+    pub tempo_range: Option<TempoRange>,
+    /// Pattern complexity setting
+    pub complexity_level: ComplexityLevel,
+    /// Time signature for pattern generation
+    pub time_signature: TimeSignature,
+    /// Whether the click track plays along with the pattern
+    pub click_enabled: bool,
+    /// MIDI velocity of the kicks (0 silences them); the engine's default if unset
+    pub kick_velocity: Option<u8>,
+    /// MIDI velocity of the click (0 silences it); the engine's default if unset
+    pub click_velocity: Option<u8>,
+    /// Make the click quieter as answers improve, louder again after misses
+    pub click_fade: Option<ClickFade>,
+    /// Tempo canon: the click runs at this ratio of the pattern tempo
+    pub click_ratio: Option<ClickRatio>,
+    /// Metric modulation trainer: the click alternates with an implied pulse every few bars
+    pub hemiola: Option<Hemiola>,
+    /// Tempo trainer: the click drifts sharp and flat while the pattern holds its tempo
+    pub click_drift: Option<ClickDrift>,
+    /// Internal-time trainer: the click drops out for some bars of every cycle
+    pub click_gap: Option<ClickGap>,
+    /// Inner-hearing trainer: the kicks drop out for some bars of every cycle
+    pub kick_dropout: Option<KickDropout>,
+    /// Fills: the last bar of each phrase plays a busier variation of the pattern
+    pub fills: Option<PhraseFill>,
+    /// Variation trainer: off-beat kicks only sound some of the time
+    pub ghost_steps: Option<GhostSteps>,
+    /// Bars per phrase; a cue marks each phrase's first downbeat
+    pub phrase_bars: Option<u32>,
+    /// Send MIDI clock, start, and stop so external gear follows the tempo
+    pub send_clock: bool,
+    /// MIDI input port whose clock drives playback (any part of its name)
+    pub sync_port: Option<String>,
+    /// MIDI input port of an electronic kit graded limb by limb (any part of its name)
+    pub kit_port: Option<String>,
+    /// Play the kit's hits back through the MIDI output as they arrive
+    pub kit_echo: bool,
+    /// Reference figure played alongside the pattern on its own voice
+    pub overlay: Option<Overlay>,
+    /// Timed-answer drill: stop after some loops and answer against the clock
+    pub drill: Option<AnswerDrill>,
+    /// Move on to a new pattern by itself once the current one has played
+    /// `advance_loops` times
+    pub auto_advance: bool,
+    /// Loops each pattern plays before auto-advance moves on
+    pub advance_loops: u32,
+    /// Reveal each pattern just before auto-advance replaces it
+    pub reveal_on_advance: bool,
+    /// Loops each step of a morph holds before the next one changes
+    pub morph_bars: u32,
+    /// Goal the session is working toward, with progress so far
+    pub goal: Option<GoalProgress>,
+    /// Timed segments the session works through, with each one's results
+    pub plan: Option<PlanProgress>,
+    /// Missed patterns from earlier sessions, brought back for review
+    pub review: Option<ReviewQueue>,
+    /// Kick timing and velocity jitter during playback (0-100)
+    pub humanize: u8,
+    /// Silent seconds before the count-in of each new pattern
+    pub listen_delay_secs: u32,
+    /// Bars, subdivision, and voicing of each pattern's count-in
+    pub count_in: CountIn,
+    /// Sound an extra count-in click before patterns that had to settle for
+    /// less than 3 steps from recent ones
+    pub repeat_cue: bool,
+    /// Representations shown when a pattern is revealed, in order
+    pub reveal_styles: Vec<RevealStyle>,
+    /// How typed answers are written
+    pub answer_format: AnswerFormat,
+    /// Grid typed answers are written at
+    pub answer_resolution: AnswerResolution,
+    /// Whether current pattern has been shown
+    pub pattern_revealed: bool,
+    /// Distance from recent patterns the current pattern settled for, when
+    /// it couldn't be kept 3 steps from all of them
+    pub current_relaxed: Option<u32>,
+    /// Patterns this session that settled for less than 3 steps from recent
+    /// ones, counted by the distance they settled for
+    pub relaxed_patterns: BTreeMap<u32, u32>,
+    /// Total patterns created this session
+    pub patterns_generated: u32,
+    /// Accuracy (0.0-1.0) of each answer checked this session
+    pub answer_accuracies: Vec<f32>,
+    /// Stages from a practice pack, advanced as answers improve
+    pub curriculum: Option<Curriculum>,
+    /// Patterns revealed this session
+    pub reveals: u32,
+    /// Patterns annotated this session, in the order first noted
+    pub pattern_notes: Vec<NotedPattern>,
+    /// Grades of every drum pad kick tap this session
+    pub taps: TapCounts,
+    /// Grades of the kick taps on the current pattern
+    pub pattern_taps: TapCounts,
+    /// Time spent at each complexity before the current one
+    pub complexity_time: Vec<(ComplexityLevel, Duration)>,
+    /// When the current complexity was chosen
+    pub complexity_since: SystemTime,
+    /// When session began
+    pub session_start: SystemTime,
+    /// Most recent user interaction
+    pub last_activity: SystemTime,
+}
+
+impl PracticeSession {
+    /// Create a new practice session
+    pub fn new(
+        tempo_bpm: u16,
+        complexity_level: ComplexityLevel,
+        time_signature: TimeSignature,
+    ) -> Self {
+        Self {
+            session_id: Uuid::new_v4(),
+            current_pattern: None,
+            pattern_history: PatternHistory::new(),
+            tempo_bpm,
+            tempo_ramp: None,
+            tempo_range: None,
+            complexity_level,
+            time_signature,
+            click_enabled: true,
+            kick_velocity: None,
+            click_velocity: None,
+            click_fade: None,
+            click_ratio: None,
+            hemiola: None,
+            click_drift: None,
+            click_gap: None,
+            kick_dropout: None,
+            fills: None,
+            ghost_steps: None,
+            phrase_bars: None,
+            send_clock: false,
+            sync_port: None,
+            kit_port: None,
+            kit_echo: false,
+            overlay: None,
+            drill: None,
+            auto_advance: false,
+            advance_loops: DEFAULT_ADVANCE_LOOPS,
+            reveal_on_advance: false,
+            morph_bars: 1,
+            goal: None,
+            plan: None,
+            review: None,
+            humanize: 0,
+            listen_delay_secs: 0,
+            count_in: CountIn::default(),
+            repeat_cue: false,
+            reveal_styles: vec![RevealStyle::Grid],
+            answer_format: AnswerFormat::Grid,
+            answer_resolution: AnswerResolution::Sixteenth,
+            pattern_revealed: false,
+            current_relaxed: None,
+            relaxed_patterns: BTreeMap::new(),
+            patterns_generated: 0,
+            answer_accuracies: Vec::new(),
+            curriculum: None,
+            reveals: 0,
+            pattern_notes: Vec::new(),
+            taps: TapCounts::default(),
+            pattern_taps: TapCounts::default(),
+            complexity_time: Vec::new(),
+            complexity_since: SystemTime::now(),
+            session_start: SystemTime::now(),
+            last_activity: SystemTime::now(),
+        }
+    }
+
+    /// Add a pattern to history, evicting oldest if at capacity
+    ///
+    /// The entry is numbered with the current `patterns_generated` count.
+    pub fn add_to_history(&mut self, pattern: Pattern) {
+        self.review_taps();
+        self.pattern_history.push(pattern, self.patterns_generated);
+        if let Some(plan) = &mut self.plan {
+            plan.record_pattern();
+        }
+    }
+
+    /// Note the distance from recent patterns the current pattern settled
+    /// for, if it had to settle for less than 3
+    pub fn record_relaxed(&mut self, distance: Option<u32>) {
+        self.current_relaxed = distance;
+        if let Some(distance) = distance {
+            *self.relaxed_patterns.entry(distance).or_insert(0) += 1;
+        }
+    }
+
+    /// Mark the current pattern as revealed, both for the session and in history
+    pub fn mark_revealed(&mut self) {
+        if !self.pattern_revealed {
+            self.reveals += 1;
+        }
+        self.pattern_revealed = true;
+        if let Some(pattern) = &self.current_pattern {
+            self.pattern_history.mark_revealed(pattern.id);
+        }
+    }
+
+    /// Number of the current pattern in the session (its history entry's,
+    /// if it came back from history), or 0 before the first
+    pub fn current_number(&self) -> u32 {
+        self.current_pattern
+            .as_ref()
+            .and_then(|pattern| self.pattern_history.find(pattern.id))
+            .map_or(self.patterns_generated, |entry| entry.number)
+    }
+
+    /// Annotate the current pattern, replacing any note it had, and return
+    /// its number
+    pub fn annotate(&mut self, note: PatternNote) -> Option<u32> {
+        let pattern = self.current_pattern.clone()?;
+        let number = self.current_number();
+        match self.pattern_notes.iter_mut().find(|n| n.number == number) {
+            Some(noted) => noted.note = note,
+            None => self.pattern_notes.push(NotedPattern {
+                number,
+                pattern,
+                note,
+            }),
+        }
+        Some(number)
+    }
+
+    /// What was noted about pattern number `number`, if anything
+    pub fn note_for(&self, number: u32) -> Option<&PatternNote> {
+        self.pattern_notes
+            .iter()
+            .find(|noted| noted.number == number)
+            .map(|noted| &noted.note)
+    }
+
+    /// Record the accuracy of a checked answer for the current pattern
+    ///
+    /// Returns whether this answer completed the session goal.
+    pub fn record_answer(&mut self, accuracy: f32) -> bool {
+        self.answer_accuracies.push(accuracy);
+        if let (Some(review), Some(pattern)) = (&mut self.review, &self.current_pattern) {
+            review.record(pattern, review_quality(accuracy), today());
+        }
+        if let Some(plan) = &mut self.plan {
+            plan.record_answer(accuracy);
+        }
+        match (&mut self.goal, &self.current_pattern) {
+            (Some(goal), Some(pattern)) if !goal.is_met() => {
+                goal.record(pattern, self.tempo_bpm, accuracy) && goal.is_met()
+            }
+            _ => false,
+        }
+    }
+
+    /// Practice `curriculum`, starting with its first stage's complexity and tempo
+    pub fn start_curriculum(&mut self, curriculum: Curriculum) {
+        self.apply_stage(&curriculum.current().clone());
+        self.curriculum = Some(curriculum);
+    }
+
+    /// Count an answer toward the curriculum
+    ///
+    /// Returns the stage the answer moved on to, whose complexity and tempo
+    /// now apply to the session.
+    pub fn advance_curriculum(&mut self, accuracy: f32) -> Option<CurriculumStage> {
+        let stage = self.curriculum.as_mut()?.record(accuracy)?.clone();
+        self.apply_stage(&stage);
+        Some(stage)
+    }
+
+    fn apply_stage(&mut self, stage: &CurriculumStage) {
+        self.set_complexity(stage.complexity);
+        if let Some(tempo) = stage.tempo {
+            self.tempo_bpm = tempo;
+        }
+    }
+
+    /// Mean accuracy over all answers this session, if any were checked
+    pub fn average_accuracy(&self) -> Option<f32> {
+        if self.answer_accuracies.is_empty() {
+            return None;
+        }
+        let total: f32 = self.answer_accuracies.iter().sum();
+        Some(total / self.answer_accuracies.len() as f32)
+    }
+
+    /// Loops after which playback stops by itself: the drill's listening
+    /// loops, or the auto-advance count while auto-advance is on
+    pub fn loop_limit(&self) -> Option<u32> {
+        match self.drill {
+            Some(drill) => Some(drill.loops),
+            None if self.auto_advance => Some(self.advance_loops),
+            None => None,
+        }
+    }
+
+    /// Switch tempo, complexity, and meter to those of the running plan segment
+    pub fn apply_plan_segment(&mut self) {
+        let Some(segment) = self.plan.as_ref().and_then(PlanProgress::segment).cloned() else {
+            return;
+        };
+        self.tempo_bpm = segment.tempo.unwrap_or(self.tempo_bpm);
+        if let Some(level) = segment.complexity {
+            self.set_complexity(level);
+        }
+        self.time_signature = segment.time_signature.unwrap_or(self.time_signature);
+    }
+
+    /// Switch complexity, crediting the time spent at the old one
+    pub fn set_complexity(&mut self, level: ComplexityLevel) {
+        let now = SystemTime::now();
+        let spent = now
+            .duration_since(self.complexity_since)
+            .unwrap_or_default();
+        add_time(&mut self.complexity_time, self.complexity_level, spent);
+        self.complexity_level = level;
+        self.complexity_since = now;
+    }
+
+    /// Time spent at each complexity, up to the last interaction, in the order first played
+    pub fn time_at_complexity(&self) -> Vec<(ComplexityLevel, Duration)> {
+        let mut times = self.complexity_time.clone();
+        let current = self
+            .last_activity
+            .duration_since(self.complexity_since)
+            .unwrap_or_default();
+        add_time(&mut times, self.complexity_level, current);
+        times
+    }
+
+    /// Count a drum pad kick tap
+    pub fn record_tap(&mut self, grade: TapGrade) {
+        self.taps.add(grade);
+        self.pattern_taps.add(grade);
+    }
+
+    /// Grade the current pattern's kick taps for review, once there are
+    /// enough of them, and start counting afresh
+    ///
+    /// Runs when a new pattern is added to history; call it once more
+    /// before saving the review queue at the end of the session.
+    pub fn review_taps(&mut self) {
+        let taps = std::mem::take(&mut self.pattern_taps);
+        if taps.total() < MIN_REVIEW_TAPS {
+            return;
+        }
+        if let (Some(review), Some(pattern)) = (&mut self.review, &self.current_pattern) {
+            let accuracy = taps.on_time() as f32 / taps.total() as f32;
+            review.record(pattern, review_quality(accuracy), today());
+        }
+    }
+
+    /// A missed pattern due for review in the session's meter, if any
+    pub fn next_review(&mut self) -> Option<Pattern> {
+        let time_signature = self.time_signature;
+        self.review.as_mut()?.next_due(today(), time_signature)
+    }
+
+    /// Seconds since the session started, which is where its plan starts
+    pub fn elapsed_secs(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(self.session_start)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+
+    /// Update last activity timestamp
+    pub fn update_activity(&mut self) {
+        self.last_activity = SystemTime::now();
+    }
+}
+
+/// Add `spent` to the running total for `level`
+fn add_time(times: &mut Vec<(ComplexityLevel, Duration)>, level: ComplexityLevel, spent: Duration) {
+    match times.iter_mut().find(|(l, _)| *l == level) {
+        Some((_, total)) => *total += spent,
+        None => times.push((level, spent)),
+    }
+}
+
+/// Patterns that settled for less than 3 steps from recent ones, e.g.
+/// "3 patterns (2 at distance 2, 1 at distance 1)"; `None` if there were none
+pub fn relaxed_summary(counts: &BTreeMap<u32, u32>) -> Option<String> {
+    let total: u32 = counts.values().sum();
+    if total == 0 {
+        return None;
+    }
+    let by_distance: Vec<String> = counts
+        .iter()
+        .rev()
+        .map(|(distance, count)| format!("{} at distance {}", count, distance))
+        .collect();
+    Some(format!(
+        "{} pattern{} ({})",
+        total,
+        if total == 1 { "" } else { "s" },
+        by_distance.join(", ")
+    ))
+}
+
+/// Today's day number (days since 1970-01-01, UTC), which reviews are scheduled in
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or(0)
+}
+
+impl Default for PracticeSession {
+    fn default() -> Self {
+        Self::new(120, ComplexityLevel::Medium, TimeSignature::four_four())
+    }
+}
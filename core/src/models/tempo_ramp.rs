@@ -0,0 +1,119 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::str::FromStr;
+
+/// Fastest tempo a ramp will climb to (matches the CLI tempo range)
+const MAX_TEMPO_BPM: u16 = 300;
+
+/// Gradual tempo increase applied while a pattern loops
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::TempoRamp;
+///
+/// let ramp: TempoRamp = "5bpm/4bars".parse().unwrap();
+/// assert_eq!(ramp.step_bpm, 5);
+/// assert_eq!(ramp.every_loops, 4);
+/// assert_eq!(ramp.next_tempo(120), 125);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TempoRamp {
+    /// BPM added at each step
+    pub step_bpm: u16,
+    /// Number of pattern loops (bars) between steps
+    pub every_loops: u32,
+}
+
+impl TempoRamp {
+    /// Create a new tempo ramp
+    pub fn new(step_bpm: u16, every_loops: u32) -> Self {
+        Self {
+            step_bpm,
+            every_loops,
+        }
+    }
+
+    /// Whether the tempo should step up before the given loop starts
+    pub fn steps_at(&self, loop_count: u64) -> bool {
+        loop_count > 0 && loop_count.is_multiple_of(self.every_loops as u64)
+    }
+
+    /// Tempo after one ramp step, capped at the maximum tempo
+    pub fn next_tempo(&self, tempo_bpm: u16) -> u16 {
+        tempo_bpm.saturating_add(self.step_bpm).min(MAX_TEMPO_BPM)
+    }
+}
+
+impl FromStr for TempoRamp {
+    type Err = String;
+
+    /// Parse a ramp such as "5bpm/4bars", "5/4", or "10bpm/2loops"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (step, every) = s.split_once('/').ok_or_else(|| {
+            format!(
+                "Invalid ramp '{}'. Format should be <bpm>bpm/<n>bars (e.g., 5bpm/4bars)",
+                s
+            )
+        })?;
+
+        let step = step.trim().to_lowercase();
+        let step = step.strip_suffix("bpm").unwrap_or(&step);
+        let step_bpm = step
+            .trim()
+            .parse::<u16>()
+            .map_err(|_| format!("Invalid ramp step '{}'. Must be a positive number", step))?;
+
+        let every = every.trim().to_lowercase();
+        let every = ["bars", "bar", "loops", "loop"]
+            .iter()
+            .find_map(|suffix| every.strip_suffix(suffix))
+            .unwrap_or(&every);
+        let every_loops = every.trim().parse::<u32>().map_err(|_| {
+            format!(
+                "Invalid ramp interval '{}'. Must be a positive number",
+                every
+            )
+        })?;
+
+        if step_bpm == 0 {
+            return Err("Ramp step must be at least 1 BPM".to_string());
+        }
+        if every_loops == 0 {
+            return Err("Ramp interval must be at least 1 bar".to_string());
+        }
+
+        Ok(Self::new(step_bpm, every_loops))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ramp_formats() {
+        assert_eq!("5bpm/4bars".parse(), Ok(TempoRamp::new(5, 4)));
+        assert_eq!("5/4".parse(), Ok(TempoRamp::new(5, 4)));
+        assert_eq!("10BPM/1bar".parse(), Ok(TempoRamp::new(10, 1)));
+        assert_eq!("2bpm/8loops".parse(), Ok(TempoRamp::new(2, 8)));
+    }
+
+    #[test]
+    fn test_parse_ramp_rejects_invalid() {
+        assert!("5bpm".parse::<TempoRamp>().is_err());
+        assert!("0bpm/4bars".parse::<TempoRamp>().is_err());
+        assert!("5bpm/0bars".parse::<TempoRamp>().is_err());
+        assert!("fast/4bars".parse::<TempoRamp>().is_err());
+    }
+
+    #[test]
+    fn test_ramp_steps_and_caps() {
+        let ramp = TempoRamp::new(5, 4);
+        assert!(!ramp.steps_at(0));
+        assert!(!ramp.steps_at(3));
+        assert!(ramp.steps_at(4));
+        assert!(ramp.steps_at(8));
+        assert_eq!(ramp.next_tempo(298), 300);
+    }
+}
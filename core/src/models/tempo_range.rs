@@ -0,0 +1,109 @@
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::String;
+use core::str::FromStr;
+use rand::Rng;
+
+/// Tempos a range can span (matches the CLI tempo range)
+const TEMPO_LIMITS: (u16, u16) = (40, 300);
+
+/// Tempos each new pattern picks from at random, so rhythms are learned
+/// apart from their speed
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::TempoRange;
+/// use rand::SeedableRng;
+///
+/// let range: TempoRange = "90-140".parse().unwrap();
+/// let tempo = range.pick(&mut rand::rngs::StdRng::seed_from_u64(7));
+/// assert!((90..=140).contains(&tempo));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TempoRange {
+    /// Slowest tempo picked, in BPM
+    pub min: u16,
+    /// Fastest tempo picked, in BPM
+    pub max: u16,
+}
+
+impl TempoRange {
+    /// Create a range from `min` to `max` BPM, inclusive
+    pub fn new(min: u16, max: u16) -> Result<Self, CoreError> {
+        if min >= max {
+            return Err(CoreError::Config(format!(
+                "Tempo range {}-{} needs a slower tempo first, then a faster one",
+                min, max
+            )));
+        }
+        let (slowest, fastest) = TEMPO_LIMITS;
+        if min < slowest || max > fastest {
+            return Err(CoreError::Config(format!(
+                "Tempo range {}-{} must stay within {}-{} BPM",
+                min, max, slowest, fastest
+            )));
+        }
+        Ok(Self { min, max })
+    }
+
+    /// A tempo from the range, every one equally likely
+    pub fn pick<R: Rng + ?Sized>(&self, rng: &mut R) -> u16 {
+        rng.gen_range(self.min..=self.max)
+    }
+}
+
+impl core::fmt::Display for TempoRange {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}-{} BPM", self.min, self.max)
+    }
+}
+
+impl FromStr for TempoRange {
+    type Err = String;
+
+    /// Parse a range such as "90-140"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid tempo range '{}'. Format should be <min>-<max> (e.g., 90-140)",
+                s
+            )
+        };
+        let (min, max) = s.split_once('-').ok_or_else(invalid)?;
+        let min = min.trim().parse::<u16>().map_err(|_| invalid())?;
+        let max = max.trim().parse::<u16>().map_err(|_| invalid())?;
+        Self::new(min, max).map_err(String::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_parse_tempo_range() {
+        assert_eq!("90-140".parse(), Ok(TempoRange::new(90, 140).unwrap()));
+        assert_eq!(" 60 - 80 ".parse(), Ok(TempoRange::new(60, 80).unwrap()));
+        assert!("140-90".parse::<TempoRange>().is_err());
+        assert!("100-100".parse::<TempoRange>().is_err());
+        assert!("30-90"
+            .parse::<TempoRange>()
+            .unwrap_err()
+            .contains("within 40-300"));
+        assert!("fast".parse::<TempoRange>().is_err());
+    }
+
+    #[test]
+    fn test_pick_covers_the_range() {
+        let range = TempoRange::new(100, 103).unwrap();
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut seen = [false; 4];
+        for _ in 0..100 {
+            seen[usize::from(range.pick(&mut rng) - 100)] = true;
+        }
+        assert!(seen.iter().all(|&picked| picked));
+    }
+}
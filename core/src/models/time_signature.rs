@@ -0,0 +1,310 @@
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+/// Most groups a [`Grouping`] can have
+const MAX_GROUPS: usize = 8;
+
+/// How a measure's beats fall into groups, e.g. 2+2+3 eighths in 7/8
+///
+/// Each group's first beat is accented: it gets the generator's weight,
+/// the click's accent, and a bar line of its own in grids.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{Grouping, TimeSignature};
+///
+/// let grouping: Grouping = "2+2+3".parse().unwrap();
+/// let seven = TimeSignature::seven_eight().with_grouping(grouping).unwrap();
+/// assert_eq!(seven.group_starts(), vec![0, 4, 8]);
+/// assert!(TimeSignature::five_four().with_grouping(grouping).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Grouping {
+    /// Beats in each group, in order; only the first `len` are used
+    sizes: [u8; MAX_GROUPS],
+    /// Number of groups
+    len: u8,
+}
+
+impl Grouping {
+    /// Beats in each group, in order
+    pub fn sizes(&self) -> &[u8] {
+        &self.sizes[..usize::from(self.len)]
+    }
+
+    /// Beats in all the groups together
+    pub fn beats(&self) -> usize {
+        self.sizes().iter().map(|&size| usize::from(size)).sum()
+    }
+}
+
+impl FromStr for Grouping {
+    type Err = String;
+
+    /// Parse group sizes joined by `+` (e.g., "2+2+3", "3+2")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut sizes = [0; MAX_GROUPS];
+        let mut len = 0;
+        for part in s.split('+') {
+            let size = part.trim().parse::<u8>().ok().filter(|&size| size > 0);
+            let Some(size) = size else {
+                return Err(format!(
+                    "Invalid grouping '{}'. Write beats per group joined by + (e.g., 2+2+3)",
+                    s
+                ));
+            };
+            if len == MAX_GROUPS {
+                return Err(format!(
+                    "Grouping '{}' has more than {} groups",
+                    s, MAX_GROUPS
+                ));
+            }
+            sizes[len] = size;
+            len += 1;
+        }
+        Ok(Self {
+            sizes,
+            len: len as u8,
+        })
+    }
+}
+
+impl fmt::Display for Grouping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, size) in self.sizes().iter().enumerate() {
+            if i > 0 {
+                f.write_str("+")?;
+            }
+            write!(f, "{}", size)?;
+        }
+        Ok(())
+    }
+}
+
+/// Musical time signature representation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeSignature {
+    /// Beats per measure (e.g., 4 in 4/4 time)
+    pub numerator: u8,
+    /// Note value per beat (e.g., 4 = quarter note)
+    pub denominator: u8,
+    /// How the beats are grouped (e.g., 2+2+3 in 7/8); `None` counts every
+    /// pulse alike
+    pub grouping: Option<Grouping>,
+}
+
+impl TimeSignature {
+    /// Create a new time signature
+    pub fn new(numerator: u8, denominator: u8) -> Self {
+        Self {
+            numerator,
+            denominator,
+            grouping: None,
+        }
+    }
+
+    /// Whether `other` is the same meter, however either groups its beats
+    pub fn same_meter(&self, other: &TimeSignature) -> bool {
+        (self.numerator, self.denominator) == (other.numerator, other.denominator)
+    }
+
+    /// Group the beats as `grouping`, which must add up to the numerator
+    pub fn with_grouping(self, grouping: Grouping) -> Result<Self, CoreError> {
+        if grouping.beats() != usize::from(self.numerator) {
+            return Err(CoreError::Config(format!(
+                "Grouping {} adds up to {} beats, but {}/{} has {}",
+                grouping,
+                grouping.beats(),
+                self.numerator,
+                self.denominator,
+                self.numerator
+            )));
+        }
+        Ok(Self {
+            grouping: Some(grouping),
+            ..self
+        })
+    }
+
+    /// Create standard 4/4 time signature
+    pub fn four_four() -> Self {
+        Self::new(4, 4)
+    }
+
+    /// Create standard 3/4 time signature (waltz time)
+    pub fn three_four() -> Self {
+        Self::new(3, 4)
+    }
+
+    /// Create standard 6/8 time signature (compound duple)
+    pub fn six_eight() -> Self {
+        Self::new(6, 8)
+    }
+
+    /// Create standard 2/4 time signature (march time)
+    pub fn two_four() -> Self {
+        Self::new(2, 4)
+    }
+
+    /// Create 5/4 time signature
+    pub fn five_four() -> Self {
+        Self::new(5, 4)
+    }
+
+    /// Create 7/8 time signature
+    pub fn seven_eight() -> Self {
+        Self::new(7, 8)
+    }
+
+    /// Whether the meter groups its beats in threes (6/8, 9/8, 12/8)
+    pub fn is_compound(&self) -> bool {
+        self.denominator == 8 && self.numerator > 3 && self.numerator.is_multiple_of(3)
+    }
+
+    /// Length of one measure in sixteenth notes
+    pub fn sixteenths_per_measure(&self) -> usize {
+        self.numerator as usize * 16 / self.denominator as usize
+    }
+
+    /// Length of the counted pulse in sixteenth notes
+    ///
+    /// Simple meters count their beat unit (quarters in 3/4, eighths in 7/8);
+    /// compound meters count dotted quarters.
+    pub fn pulse_sixteenths(&self) -> usize {
+        let beat = (16 / self.denominator as usize).max(1);
+        if self.is_compound() {
+            beat * 3
+        } else {
+            beat
+        }
+    }
+
+    /// Number of counted pulses in one measure (e.g., 3 in 3/4, 2 in 6/8)
+    pub fn pulses_per_measure(&self) -> usize {
+        self.sixteenths_per_measure() / self.pulse_sixteenths()
+    }
+
+    /// Offsets in sixteenths of the accented beats in a measure: where each
+    /// group starts, or every pulse when the meter isn't grouped
+    pub fn group_starts(&self) -> Vec<usize> {
+        match self.grouping {
+            Some(grouping) => {
+                let beat = (16 / usize::from(self.denominator)).max(1);
+                let mut start = 0;
+                grouping
+                    .sizes()
+                    .iter()
+                    .map(|&size| {
+                        let group = start;
+                        start += usize::from(size) * beat;
+                        group
+                    })
+                    .collect()
+            }
+            None => (0..self.sixteenths_per_measure())
+                .step_by(self.pulse_sixteenths().max(1))
+                .collect(),
+        }
+    }
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self::four_four()
+    }
+}
+
+impl FromStr for TimeSignature {
+    type Err = String;
+
+    /// Parse time signature from string (e.g., "4/4", "3/4", "6/8")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split('/').collect();
+        if parts.len() != 2 {
+            return Err(format!(
+                "Invalid time signature '{}'. Format should be numerator/denominator (e.g., 4/4, 3/4, 6/8)",
+                s
+            ));
+        }
+
+        let numerator = parts[0].parse::<u8>().map_err(|_| {
+            format!(
+                "Invalid numerator '{}' in time signature. Must be a positive number",
+                parts[0]
+            )
+        })?;
+
+        let denominator = parts[1].parse::<u8>().map_err(|_| {
+            format!(
+                "Invalid denominator '{}' in time signature. Must be a positive number",
+                parts[1]
+            )
+        })?;
+
+        // Validate denominator is a power of 2 (common in music)
+        if ![1, 2, 4, 8, 16].contains(&denominator) {
+            return Err(format!(
+                "Denominator {} is not standard. Use 1, 2, 4, 8, or 16",
+                denominator
+            ));
+        }
+
+        if numerator == 0 {
+            return Err("Numerator must be at least 1".to_string());
+        }
+
+        Ok(TimeSignature::new(numerator, denominator))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_pulses_per_measure() {
+        assert_eq!(TimeSignature::four_four().pulses_per_measure(), 4);
+        assert_eq!(TimeSignature::three_four().pulses_per_measure(), 3);
+        assert_eq!(TimeSignature::two_four().pulses_per_measure(), 2);
+        assert_eq!(TimeSignature::seven_eight().pulses_per_measure(), 7);
+
+        let six_eight = TimeSignature::six_eight();
+        assert!(six_eight.is_compound());
+        assert_eq!(six_eight.pulse_sixteenths(), 6);
+        assert_eq!(six_eight.pulses_per_measure(), 2);
+        assert_eq!(TimeSignature::new(12, 8).pulses_per_measure(), 4);
+    }
+
+    #[test]
+    fn test_grouping_places_accents() {
+        let five = TimeSignature::five_four()
+            .with_grouping("3+2".parse().unwrap())
+            .unwrap();
+        assert_eq!(five.group_starts(), vec![0, 12]);
+        assert_eq!(
+            TimeSignature::six_eight().group_starts(),
+            vec![0, 6],
+            "ungrouped meters accent their pulses"
+        );
+
+        let grouping: Grouping = "3 + 2 + 2".parse().unwrap();
+        assert_eq!(grouping.sizes(), &[3, 2, 2]);
+        assert_eq!(grouping.to_string(), "3+2+2");
+        assert!("2+0+5".parse::<Grouping>().is_err());
+        assert!("1+1+1+1+1+1+1+1+1".parse::<Grouping>().is_err());
+        assert_eq!(
+            TimeSignature::seven_eight()
+                .with_grouping("2+2+2".parse().unwrap())
+                .unwrap_err(),
+            CoreError::Config(String::from(
+                "Grouping 2+2+2 adds up to 6 beats, but 7/8 has 7"
+            ))
+        );
+    }
+}
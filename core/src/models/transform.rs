@@ -0,0 +1,281 @@
+use super::beat_grid::BeatGrid;
+use super::pattern::Pattern;
+use crate::error::CoreError;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+/// Longest run of back-to-back kicks an inversion adds
+const MAX_INVERTED_RUN: usize = 2;
+
+/// Most kicks an inversion leaves, as a share of the pattern's steps
+const MAX_INVERTED_DENSITY: f32 = 0.5;
+
+/// A variation of a pattern, for exploring a rhythm once it's learned
+///
+/// Every variation keeps the rules generated patterns follow: a kick on
+/// beat 1, a density of 0.125-0.5, and so on. Steps are numbered from 1.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_core::models::{Pattern, Transform};
+///
+/// let pattern: Pattern = "4/4:16:x..x..x...x.x...".parse()?;
+/// let rotated = "rotate -3".parse::<Transform>()?.apply(&pattern)?;
+/// assert_eq!(rotated.to_pattern_string(), "4/4:16:x..x...x.x...x..");
+/// let reversed = Transform::Reverse.apply(&pattern)?;
+/// assert_eq!(reversed.to_pattern_string(), "4/4:16:x...x.x...x..x..");
+/// # Ok::<(), String>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    /// Shift every kick this many steps later (earlier if negative), wrapping
+    /// around the bar line
+    Rotate(i32),
+    /// Play the pattern backwards from beat 1 (retrograde)
+    Reverse,
+    /// Kick where the pattern rests, strongest positions first, up to the
+    /// density limit
+    Invert,
+    /// Add a kick at this step
+    Add(usize),
+    /// Take the kick off this step
+    Remove(usize),
+}
+
+impl Transform {
+    /// The pattern this variation makes of `pattern`
+    ///
+    /// Fails with the reason if the result would break a pattern rule, or
+    /// if the step to add or remove doesn't fit.
+    pub fn apply(&self, pattern: &Pattern) -> Result<Pattern, CoreError> {
+        let len = pattern.steps.len();
+        // A variation is a new pattern, with its own id and every kick certain
+        let mut varied = Pattern::new(
+            pattern.steps.clone(),
+            pattern.time_signature,
+            pattern.complexity_level,
+        );
+        varied.meters = pattern.meters.clone();
+        varied.num_measures = pattern.num_measures;
+        varied.subdivision = pattern.subdivision;
+        match *self {
+            Transform::Rotate(shift) => {
+                varied
+                    .steps
+                    .rotate_right(shift.rem_euclid(len.max(1) as i32) as usize);
+                if !varied.steps[0] {
+                    let turns: Vec<String> = pattern
+                        .note_positions()
+                        .iter()
+                        .filter(|&&i| i > 0)
+                        .map(|&i| format!("-{}", i))
+                        .collect();
+                    return Err(CoreError::Generation(format!(
+                        "Rotating by {} leaves beat 1 without a kick; try {}",
+                        shift,
+                        turns.join(", ")
+                    )));
+                }
+            }
+            Transform::Reverse => {
+                varied.steps = (0..len).map(|i| pattern.steps[(len - i) % len]).collect();
+            }
+            Transform::Invert => varied.steps = inverted(pattern),
+            Transform::Add(step) => {
+                let i = step_index(step, len).map_err(CoreError::Generation)?;
+                if pattern.steps[i] {
+                    return Err(CoreError::Generation(format!(
+                        "Step {} already has a kick",
+                        step
+                    )));
+                }
+                varied.steps[i] = true;
+            }
+            Transform::Remove(step) => {
+                let i = step_index(step, len).map_err(CoreError::Generation)?;
+                if !pattern.steps[i] {
+                    return Err(CoreError::Generation(format!(
+                        "Step {} has no kick to remove",
+                        step
+                    )));
+                }
+                varied.steps[i] = false;
+            }
+        }
+        varied.check_steps().map_err(|violation| {
+            CoreError::Generation(format!(
+                "{} would break a pattern rule: {}",
+                self, violation
+            ))
+        })?;
+        Ok(varied)
+    }
+}
+
+/// Index of 1-based `step` in a pattern of `len` steps
+fn step_index(step: usize, len: usize) -> Result<usize, String> {
+    if !(1..=len).contains(&step) {
+        return Err(format!("Step must be between 1 and {}", len));
+    }
+    Ok(step - 1)
+}
+
+/// Kicks on `pattern`'s rests, beat 1 kept, adding the strongest positions
+/// (eighths before sixteenths) first while the density, runs of kicks, and
+/// a long rest stay within the rules
+fn inverted(pattern: &Pattern) -> Vec<bool> {
+    let len = pattern.steps.len();
+    let mut strengths = vec![0.0; len];
+    for (start, meter) in pattern.measures() {
+        let grid = BeatGrid::new(meter, pattern.subdivision, 1);
+        for (position, strength) in strengths.iter_mut().skip(start).enumerate() {
+            if position >= grid.total_positions() {
+                break;
+            }
+            *strength = grid.position_strength(position);
+        }
+    }
+    let mut rests: Vec<usize> = (1..len).filter(|&i| !pattern.steps[i]).collect();
+    rests.sort_by(|&a, &b| {
+        strengths[b]
+            .total_cmp(&strengths[a])
+            .then((b % 2 == 0).cmp(&(a % 2 == 0)))
+    });
+
+    let most = (len as f32 * MAX_INVERTED_DENSITY) as usize;
+    let mut steps = vec![false; len];
+    steps[0] = true;
+    let mut kicks = 1;
+    for i in rests {
+        if kicks >= most {
+            break;
+        }
+        let before = steps[..i].iter().rev().take_while(|&&kick| kick).count();
+        let after = steps[i + 1..].iter().take_while(|&&kick| kick).count();
+        if before + after >= MAX_INVERTED_RUN {
+            continue;
+        }
+        steps[i] = true;
+        if steps.windows(2).any(|pair| pair == [false, false]) {
+            kicks += 1;
+        } else {
+            steps[i] = false;
+        }
+    }
+    steps
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transform::Rotate(shift) => write!(f, "Rotating by {}", shift),
+            Transform::Reverse => write!(f, "Reversing"),
+            Transform::Invert => write!(f, "Inverting"),
+            Transform::Add(step) => write!(f, "Adding a kick at step {}", step),
+            Transform::Remove(step) => write!(f, "Removing the kick at step {}", step),
+        }
+    }
+}
+
+impl FromStr for Transform {
+    type Err = String;
+
+    /// Parse a variation such as "rotate 3", "rotate -2", "reverse",
+    /// "invert", "add 7", or "remove 5"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid variation '{}'. Use rotate <steps>, reverse, invert, add <step>, or remove <step>",
+                s.trim()
+            )
+        };
+        let mut words = s.split_whitespace();
+        let command = words.next().ok_or_else(invalid)?.to_lowercase();
+        let argument = words.next();
+        if words.next().is_some() {
+            return Err(invalid());
+        }
+        let step = || {
+            argument
+                .and_then(|a| a.parse::<usize>().ok())
+                .ok_or_else(invalid)
+        };
+        match (command.as_str(), argument) {
+            ("rotate", Some(shift)) => shift.parse().map(Transform::Rotate).map_err(|_| invalid()),
+            ("reverse" | "retrograde", None) => Ok(Transform::Reverse),
+            ("invert", None) => Ok(Transform::Invert),
+            ("add", Some(_)) => step().map(Transform::Add),
+            ("remove", Some(_)) => step().map(Transform::Remove),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(text: &str) -> Pattern {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_transforms() {
+        assert_eq!("rotate 3".parse(), Ok(Transform::Rotate(3)));
+        assert_eq!("Rotate -2".parse(), Ok(Transform::Rotate(-2)));
+        assert_eq!("retrograde".parse(), Ok(Transform::Reverse));
+        assert_eq!("invert".parse(), Ok(Transform::Invert));
+        assert_eq!("add 7".parse(), Ok(Transform::Add(7)));
+        assert_eq!("remove 5".parse(), Ok(Transform::Remove(5)));
+        assert!("rotate".parse::<Transform>().is_err());
+        assert!("invert 2".parse::<Transform>().is_err());
+        assert!("add -1".parse::<Transform>().is_err());
+        assert!("shuffle".parse::<Transform>().is_err());
+    }
+
+    #[test]
+    fn test_rotate_needs_a_kick_on_beat_one() {
+        let original = pattern("4/4:16:x...x..x..x.....");
+        let rotated = Transform::Rotate(-4).apply(&original).unwrap();
+        assert_eq!(rotated.to_pattern_string(), "4/4:16:x..x..x.....x...");
+        assert_eq!(
+            Transform::Rotate(12).apply(&original).unwrap().steps,
+            rotated.steps
+        );
+        let err = Transform::Rotate(1).apply(&original).unwrap_err();
+        assert!(err.message().contains("try -4, -7, -10"), "{}", err);
+    }
+
+    #[test]
+    fn test_invert_stays_within_the_rules() {
+        let original = pattern("4/4:16:x...x...x...x...");
+        let inverted = Transform::Invert.apply(&original).unwrap();
+        assert!(inverted.steps[0]);
+        assert!(inverted.density() <= 0.5);
+        assert!((1..16).all(|i| !inverted.steps[i] || !original.steps[i]));
+        assert!(inverted.validate_steps().is_ok());
+    }
+
+    #[test]
+    fn test_add_and_remove_kicks() {
+        let original = pattern("4/4:16:x...x..x..x.....");
+        let added = Transform::Add(3).apply(&original).unwrap();
+        assert_eq!(added.note_positions(), vec![0, 2, 4, 7, 10]);
+        let removed = Transform::Remove(5).apply(&original).unwrap();
+        assert_eq!(removed.note_positions(), vec![0, 7, 10]);
+
+        assert!(Transform::Add(5).apply(&original).is_err());
+        assert!(Transform::Remove(2).apply(&original).is_err());
+        assert!(Transform::Add(17).apply(&original).is_err());
+        assert!(Transform::Remove(1)
+            .apply(&original)
+            .unwrap_err()
+            .message()
+            .contains("beat 1"));
+    }
+}
@@ -0,0 +1,431 @@
+use crate::models::{Pattern, TimeSignature};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Convert a pattern to ASCII art visualization
+///
+/// Example output for a 4/4 pattern with kicks on positions 0, 4, 10, 14:
+/// ```text
+/// |1 e + a |2 e + a |3 e + a |4 e + a |
+/// |X . . . |X . . . |. . X . |. . . X |
+/// ```
+pub fn pattern_to_ascii(pattern: &Pattern) -> String {
+    let mut output = count_row(pattern);
+    output.push_str(&kick_row(pattern, pattern.steps.len()));
+    output
+}
+
+/// Pattern line with X for kick and . for rest, hiding positions from
+/// `visible` on with `?`
+pub fn kick_row(pattern: &Pattern, visible: usize) -> String {
+    let mut output = String::new();
+    output.push('|');
+    for (i, (&has_kick, separator)) in pattern.steps.iter().zip(separators(pattern)).enumerate() {
+        output.push_str(match (i < visible, has_kick) {
+            (false, _) => "?",
+            (true, true) => "X",
+            (true, false) => ".",
+        });
+        output.push_str(separator);
+    }
+    output.push('\n');
+
+    output
+}
+
+/// Text following each step in a grid row
+///
+/// Each counted pulse closes with ` |`: a beat in simple meters (an eighth in
+/// 7/8) and a dotted quarter in compound ones (6/8, 12/8). A grouped meter
+/// closes each group instead (`2+2+3` in 7/8). In a pattern with meter
+/// changes the count restarts at each bar line, which closes with ` ||`.
+pub fn separators(pattern: &Pattern) -> Vec<&'static str> {
+    let len = pattern.steps.len();
+    let measures = pattern.measures();
+    let mut separators = Vec::with_capacity(len);
+    for (m, &(start, meter)) in measures.iter().enumerate() {
+        let measure = meter.sixteenths_per_measure().max(1);
+        let group_starts = meter.group_starts();
+        let end = measures.get(m + 1).map_or(len, |&(next, _)| next.min(len));
+        for i in start..end {
+            separators.push(if i + 1 == end && end < len {
+                " ||"
+            } else if group_starts.contains(&((i + 1 - start) % measure)) || i + 1 == end {
+                " |"
+            } else {
+                " "
+            });
+        }
+    }
+    separators
+}
+
+/// Count label for the step `offset` sixteenths into a measure of `meter`
+///
+/// Simple meters count their beat unit with sixteenths between (`1 e + a`
+/// in 4/4, `1 +` in 7/8); compound meters count dotted quarters with their
+/// eighths (`1 . + . a .` in 6/8).
+fn count_label(meter: TimeSignature, offset: usize) -> String {
+    let beat = (16 / usize::from(meter.denominator)).max(1);
+    if meter.is_compound() {
+        let pulse = beat * 3;
+        return match offset % pulse {
+            0 => format!("{}", offset / pulse + 1),
+            step if step == beat => String::from("+"),
+            step if step == beat * 2 => String::from("a"),
+            _ => String::from("."),
+        };
+    }
+    match offset % beat {
+        0 => format!("{}", offset / beat + 1),
+        step if step * 2 == beat => String::from("+"),
+        step if step * 4 == beat => String::from("e"),
+        step if step * 4 == beat * 3 => String::from("a"),
+        _ => String::from("."),
+    }
+}
+
+/// Header line with beat labels (`|1 e + a |2 e + a |...`)
+///
+/// Labels follow the same pulses as [`separators`]: compound meters count
+/// dotted quarters (`|1 . + . a . |2 . + . a . |` in 6/8), and each meter
+/// change starts again from 1.
+pub fn count_row(pattern: &Pattern) -> String {
+    let mut measures = pattern.measures().into_iter().peekable();
+    let (mut start, mut meter) = measures.next().unwrap_or((0, pattern.time_signature));
+    let mut output = String::from("|");
+    for (i, separator) in separators(pattern).into_iter().enumerate() {
+        if let Some(next) = measures.next_if(|&(next, _)| next == i) {
+            (start, meter) = next;
+        }
+        let measure = meter.sixteenths_per_measure().max(1);
+        output.push_str(&count_label(meter, (i - start) % measure));
+        output.push_str(separator);
+    }
+    output.push('\n');
+    output
+}
+
+/// Column of a step's symbol in a [`kick_row`] line
+///
+/// Each step takes one column plus its [`separators`] text, after the leading
+/// `|`.
+pub fn step_column(pattern: &Pattern, step: usize) -> usize {
+    1 + separators(pattern)
+        .iter()
+        .take(step)
+        .map(|separator| 1 + separator.len())
+        .sum::<usize>()
+}
+
+/// Where a step falls as it's counted, e.g. "3" or "2+" (`1 e + a` in 4/4)
+///
+/// Steps between beats are named after the beat they follow, as in the
+/// [`count_row`]. Patterns of more than one measure name the bar too
+/// ("bar 2, 1e").
+pub fn step_count(pattern: &Pattern, step: usize) -> String {
+    let measures = pattern.measures();
+    let (bar, &(start, meter)) = measures
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, &(start, _))| start <= step)
+        .unwrap_or((0, &(0, pattern.time_signature)));
+    let measure = meter.sixteenths_per_measure().max(1);
+    let offset = (step - start.min(step)) % measure;
+    let is_beat = |label: &String| label.starts_with(|c: char| c.is_ascii_digit());
+    let label = count_label(meter, offset);
+    let count = if is_beat(&label) {
+        label
+    } else {
+        let beat = (0..offset)
+            .rev()
+            .map(|before| count_label(meter, before))
+            .find(is_beat)
+            .unwrap_or_default();
+        format!("{}{}", beat, label)
+    };
+    if pattern.is_multi_measure() {
+        format!("bar {}, {}", bar + 1, count)
+    } else {
+        count
+    }
+}
+
+/// Convert a pattern to ASCII art, wrapping beats onto more rows if needed
+///
+/// Rows break at beat separators so that no line exceeds `max_width`
+/// (a single beat wider than that still gets its own row). Narrow terminals
+/// get the same grid split into stacked label/kick row pairs:
+/// ```text
+/// |1 e + a |2 e + a |
+/// |X . . . |X . . . |
+/// |3 e + a |4 e + a |
+/// |. . X . |. . . X |
+/// ```
+pub fn pattern_to_ascii_wrapped(pattern: &Pattern, max_width: usize) -> String {
+    let ascii = pattern_to_ascii(pattern);
+    let mut lines = ascii.lines();
+    let header = lines.next().unwrap_or("");
+    let kicks = lines.next().unwrap_or("");
+
+    if header.len().max(kicks.len()) <= max_width {
+        return ascii;
+    }
+
+    // Break at the kick row's beat separators, after the last `|` of a bar
+    // line (both rows are ASCII)
+    let separators: Vec<usize> = kicks
+        .char_indices()
+        .filter(|&(i, c)| c == '|' && kicks.as_bytes().get(i + 1) != Some(&b'|'))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut output = String::new();
+    let mut start = 0;
+    while start + 1 < kicks.len() {
+        let next = separators.iter().copied().filter(|&i| i > start);
+        let end = next
+            .clone()
+            .take_while(|&i| i - start < max_width)
+            .last()
+            .or_else(|| next.clone().next())
+            .unwrap_or(kicks.len() - 1);
+
+        // The last row takes whatever remains of the label row
+        let header_end = if end + 1 >= kicks.len() {
+            header.len()
+        } else {
+            (end + 1).min(header.len())
+        };
+        output.push_str(header.get(start..header_end).unwrap_or(""));
+        output.push('\n');
+        output.push_str(&kicks[start..=end]);
+        output.push('\n');
+
+        start = end;
+    }
+
+    output
+}
+
+/// Pattern id, tempo, time signature, and density lines shown above a reveal
+pub fn metadata_header(pattern: &Pattern, tempo_bpm: u16) -> String {
+    let mut output = String::new();
+
+    // Pattern info
+    output.push_str(&format!(
+        "Pattern: {} | Tempo: {} BPM | Complexity: {:?}\n",
+        pattern.id, tempo_bpm, pattern.complexity_level
+    ));
+
+    output.push_str(&format!(
+        "Time: {} | Density: {:.1}% | Difficulty: {}/100\n\n",
+        pattern.meter_label(),
+        pattern.density() * 100.0,
+        pattern.difficulty_score()
+    ));
+
+    output
+}
+
+/// Format a pattern with additional metadata
+pub fn format_pattern_with_metadata(pattern: &Pattern, tempo_bpm: u16) -> String {
+    let mut output = metadata_header(pattern, tempo_bpm);
+
+    // ASCII visualization
+    output.push_str(&pattern_to_ascii(pattern));
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ComplexityLevel;
+
+    #[test]
+    fn test_pattern_to_ascii_basic() {
+        let steps = vec![
+            true, false, false, false, // Beat 1: X on downbeat
+            true, false, false, false, // Beat 2: X on beat 2
+            false, false, true, false, // Beat 3: X on "+"
+            false, false, false, true, // Beat 4: X on "a"
+        ];
+
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium);
+        let ascii = pattern_to_ascii(&pattern);
+
+        // Should contain header
+        assert!(ascii.contains("|1 e + a |2 e + a |3 e + a |4 e + a |"));
+
+        // Should contain pattern line with X and .
+        assert!(ascii.contains("X . . . |"));
+        assert!(ascii.contains("|X . . . |"));
+    }
+
+    #[test]
+    fn test_step_column_matches_kick_row() {
+        let steps = (0..16).map(|i| i == 6).collect();
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium);
+        let row = kick_row(&pattern, 16);
+
+        assert_eq!(row.find('X'), Some(step_column(&pattern, 6)));
+        assert_eq!(
+            &row[step_column(&pattern, 4) - 1..step_column(&pattern, 4)],
+            "|"
+        );
+
+        assert_eq!(step_count(&pattern, 6), "2+");
+        assert_eq!(step_count(&pattern, 13), "4e");
+
+        let steps = (0..12).map(|i| i == 7).collect();
+        let pattern = Pattern::new(steps, TimeSignature::six_eight(), ComplexityLevel::Medium);
+        assert_eq!(step_count(&pattern, 7), "2.");
+        assert_eq!(step_count(&pattern, 8), "2+");
+        let row = kick_row(&pattern, 12);
+        assert_eq!(row.find('X'), Some(step_column(&pattern, 7)));
+        assert_eq!(
+            &row[step_column(&pattern, 6) - 1..step_column(&pattern, 6)],
+            "|"
+        );
+    }
+
+    #[test]
+    fn test_pattern_to_ascii_wrapped() {
+        let steps = vec![
+            true, false, false, false, true, false, false, false, false, false, true, false, false,
+            false, false, true,
+        ];
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium);
+
+        // Wide enough: unchanged
+        assert_eq!(
+            pattern_to_ascii_wrapped(&pattern, 80),
+            pattern_to_ascii(&pattern)
+        );
+
+        // Two beats per row
+        assert_eq!(
+            pattern_to_ascii_wrapped(&pattern, 20),
+            "|1 e + a |2 e + a |\n|X . . . |X . . . |\n|3 e + a |4 e + a |\n|. . X . |. . . X |\n"
+        );
+
+        // Narrower than one beat: one beat per row
+        assert_eq!(pattern_to_ascii_wrapped(&pattern, 5).lines().count(), 8);
+    }
+
+    #[test]
+    fn test_wrapping_keeps_every_kick_in_all_meters() {
+        let meters = [
+            TimeSignature::two_four(),
+            TimeSignature::three_four(),
+            TimeSignature::five_four(),
+            TimeSignature::six_eight(),
+            TimeSignature::seven_eight(),
+        ];
+        for time_signature in meters {
+            let len = crate::models::BeatGrid::new(time_signature, 16, 1).total_positions();
+            let steps = (0..len).map(|i| i % 3 == 0).collect();
+            let pattern = Pattern::new(steps, time_signature, ComplexityLevel::Medium);
+            let kicks = pattern_to_ascii(&pattern).matches('X').count();
+
+            for width in 5..80 {
+                let wrapped = pattern_to_ascii_wrapped(&pattern, width);
+                assert_eq!(
+                    wrapped.matches('X').count(),
+                    kicks,
+                    "{:?} at {}",
+                    time_signature,
+                    width
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_pattern_with_metadata() {
+        let steps = vec![
+            true, false, false, false, true, false, false, false, false, false, true, false, false,
+            false, false, true,
+        ];
+
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Simple);
+        let formatted = format_pattern_with_metadata(&pattern, 120);
+
+        // Should contain metadata
+        assert!(formatted.contains("Tempo: 120 BPM"));
+        assert!(formatted.contains("Complexity: Simple"));
+        assert!(formatted.contains("Time: 4/4"));
+        assert!(formatted.contains("Density:"));
+
+        // Should contain ASCII visualization
+        assert!(formatted.contains("|1 e + a |"));
+    }
+
+    #[test]
+    fn test_compound_and_odd_meters_count_their_own_pulses() {
+        let steps = "x..x..x..x..".chars().map(|c| c == 'x').collect();
+        let pattern = Pattern::new(steps, TimeSignature::six_eight(), ComplexityLevel::Medium);
+        assert_eq!(
+            pattern_to_ascii(&pattern),
+            "|1 . + . a . |2 . + . a . |\n|X . . X . . |X . . X . . |\n"
+        );
+
+        let steps = "x.x.x..x.x....".chars().map(|c| c == 'x').collect();
+        let pattern = Pattern::new(steps, TimeSignature::seven_eight(), ComplexityLevel::Medium);
+        assert_eq!(
+            pattern_to_ascii(&pattern),
+            "|1 + |2 + |3 + |4 + |5 + |6 + |7 + |\n|X . |X . |X . |. X |. X |. . |. . |\n"
+        );
+    }
+
+    #[test]
+    fn test_grouped_meters_close_each_group() {
+        let steps = "x.x.x..x.x....".chars().map(|c| c == 'x').collect();
+        let seven = TimeSignature::seven_eight()
+            .with_grouping("2+2+3".parse().unwrap())
+            .unwrap();
+        let pattern = Pattern::new(steps, seven, ComplexityLevel::Medium);
+        assert_eq!(
+            pattern_to_ascii(&pattern),
+            "|1 + 2 + |3 + 4 + |5 + 6 + 7 + |\n|X . X . |X . . X |. X . . . . |\n"
+        );
+    }
+
+    #[test]
+    fn test_meter_changes_restart_the_count() {
+        let pattern = Pattern::concat(&[
+            Pattern::new(
+                vec![true; 8],
+                TimeSignature::two_four(),
+                ComplexityLevel::Simple,
+            ),
+            Pattern::new(
+                vec![false; 12],
+                TimeSignature::three_four(),
+                ComplexityLevel::Simple,
+            ),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            pattern_to_ascii(&pattern),
+            "|1 e + a |2 e + a ||1 e + a |2 e + a |3 e + a |\n\
+             |X X X X |X X X X ||. . . . |. . . . |. . . . |\n"
+        );
+        assert_eq!(step_column(&pattern, 8), 20);
+        assert_eq!(step_count(&pattern, 6), "bar 1, 2+");
+        assert_eq!(step_count(&pattern, 16), "bar 2, 3");
+        assert!(format_pattern_with_metadata(&pattern, 120).contains("Time: 2/4 + 3/4"));
+
+        // Rows break after the bar line, never inside it
+        assert_eq!(
+            pattern_to_ascii_wrapped(&pattern, 20),
+            "|1 e + a |2 e + a ||\n|X X X X |X X X X ||\n\
+             |1 e + a |2 e + a |\n|. . . . |. . . . |\n\
+             |3 e + a |\n|. . . . |\n"
+        );
+    }
+}
@@ -0,0 +1,46 @@
+use crate::models::Pattern;
+use crate::visualizer::ascii::{count_row, kick_row};
+use alloc::string::String;
+
+/// Frames of a beat-by-beat build-up: the counting header, then one kick row
+/// per stage with one more beat uncovered each time
+///
+/// ```text
+/// |1 e + a |2 e + a |3 e + a |4 e + a |
+/// |X . . . |? ? ? ? |? ? ? ? |? ? ? ? |
+/// |X . . . |X . . X |? ? ? ? |? ? ? ? |
+/// |X . . . |X . . X |. . X . |? ? ? ? |
+/// |X . . . |X . . X |. . X . |. . X . |
+/// ```
+pub fn pattern_to_build_frames(pattern: &Pattern) -> String {
+    let mut output = count_row(pattern);
+    for stage in 1..=pattern.beat_count() {
+        output.push_str(&build_frame(pattern, stage));
+    }
+    output
+}
+
+/// Kick row for one build-up stage, with the first `beats` beats uncovered
+pub fn build_frame(pattern: &Pattern, beats: usize) -> String {
+    kick_row(pattern, beats * pattern.beat_len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, TimeSignature};
+
+    #[test]
+    fn test_build_frames_uncover_one_beat_at_a_time() {
+        let steps = "x..x..x...x.".chars().map(|c| c == 'x').collect();
+        let pattern = Pattern::new(steps, TimeSignature::three_four(), ComplexityLevel::Medium);
+
+        assert_eq!(
+            pattern_to_build_frames(&pattern),
+            "|1 e + a |2 e + a |3 e + a |\n\
+             |X . . X |? ? ? ? |? ? ? ? |\n\
+             |X . . X |. . X . |? ? ? ? |\n\
+             |X . . X |. . X . |. . X . |\n"
+        );
+    }
+}
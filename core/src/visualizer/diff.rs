@@ -0,0 +1,105 @@
+use crate::grading::AnswerGrade;
+use crate::models::Pattern;
+use crate::visualizer::ascii::{count_row, separators};
+use alloc::format;
+use alloc::string::String;
+
+/// Render one row of steps in the same layout as the ASCII grid
+fn steps_row(label: &str, pattern: &Pattern, steps: impl Iterator<Item = &'static str>) -> String {
+    let mut output = String::from(label);
+    output.push('|');
+    for (symbol, separator) in steps.zip(separators(pattern)) {
+        output.push_str(symbol);
+        output.push_str(separator);
+    }
+    output.push('\n');
+    output
+}
+
+/// Render a graded answer against the real pattern
+///
+/// Wrong positions are marked with `^` underneath:
+/// ```text
+///         |1 e + a |2 e + a |3 e + a |4 e + a |
+/// Pattern |X . . . |X . . . |X . X . |. . . X |
+/// Answer  |X . . . |X . . . |X . . X |. . . X |
+///         |        |        |    ^ ^ |        |
+/// ```
+pub fn format_answer_diff(pattern: &Pattern, grade: &AnswerGrade) -> String {
+    let mut output = String::new();
+
+    // Header line with beat labels, indented past the row labels
+    output.push_str("        ");
+    output.push_str(&count_row(pattern));
+
+    let kick_symbol = |&has_kick: &bool| if has_kick { "X" } else { "." };
+    output.push_str(&steps_row(
+        "Pattern ",
+        pattern,
+        pattern.steps.iter().map(kick_symbol),
+    ));
+    output.push_str(&steps_row(
+        "Answer  ",
+        pattern,
+        grade.answer.iter().map(kick_symbol),
+    ));
+    output.push_str(&steps_row(
+        "        ",
+        pattern,
+        (0..pattern.steps.len()).map(|i| {
+            if grade.wrong_positions.contains(&i) {
+                "^"
+            } else {
+                " "
+            }
+        }),
+    ));
+
+    output.push_str(&format!(
+        "\nAccuracy: {:.1}% ({} of {} positions correct)\n",
+        grade.accuracy * 100.0,
+        pattern.steps.len() - grade.wrong_positions.len(),
+        pattern.steps.len()
+    ));
+
+    if !grade.missed_kicks.is_empty() {
+        output.push_str(&format!(
+            "Missed kicks at positions: {:?}\n",
+            grade.missed_kicks
+        ));
+    }
+    if !grade.extra_kicks.is_empty() {
+        output.push_str(&format!(
+            "Extra kicks at positions: {:?}\n",
+            grade.extra_kicks
+        ));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grading::grade_answer;
+    use crate::models::{ComplexityLevel, TimeSignature};
+
+    #[test]
+    fn test_format_answer_diff_marks_wrong_positions() {
+        let steps = vec![
+            true, false, false, false, true, false, false, false, true, false, true, false, false,
+            false, false, true,
+        ];
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium);
+        let grade = grade_answer(&pattern, "x...x...x..x...x").unwrap();
+
+        let diff = format_answer_diff(&pattern, &grade);
+
+        assert!(diff.contains("Pattern |X . . . |X . . . |X . X . |. . . X |"));
+        assert!(diff.contains("Answer  |X . . . |X . . . |X . . X |. . . X |"));
+        assert!(diff.contains("        |        |        |    ^ ^ |        |"));
+        assert!(diff.contains("Accuracy: 87.5%"));
+        assert!(diff.contains("Missed kicks at positions: [10]"));
+        assert!(diff.contains("Extra kicks at positions: [11]"));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::models::Pattern;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// Inter-onset intervals of a pattern in grid positions (sixteenths)
+///
+/// Returns the rest before the first kick and the distance from each kick to
+/// the next; the last kick's interval runs to the end of the measure, so the
+/// rest plus the intervals always add up to the pattern length.
+pub fn inter_onset_intervals(pattern: &Pattern) -> (usize, Vec<usize>) {
+    let kicks = pattern.note_positions();
+    let total = pattern.steps.len();
+    let lead = kicks.first().copied().unwrap_or(total);
+
+    let intervals = kicks
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| kicks.get(i + 1).copied().unwrap_or(total) - start)
+        .collect();
+
+    (lead, intervals)
+}
+
+/// Format intervals as `3-3-2-4-4`, with any leading rest in parentheses (`(2)-2-4`)
+///
+/// This is also the form accepted by IOI answer entry.
+pub fn format_ioi(lead: usize, intervals: &[usize]) -> String {
+    let mut parts: Vec<String> = intervals.iter().map(|i| i.to_string()).collect();
+    if lead > 0 {
+        parts.insert(0, format!("({})", lead));
+    }
+    parts.join("-")
+}
+
+/// Render a pattern as its inter-onset interval sequence
+///
+/// ```text
+/// IOI: 3-3-2-4-4
+///      (sixteenths from each kick to the next)
+/// ```
+pub fn pattern_to_ioi(pattern: &Pattern) -> String {
+    let (lead, intervals) = inter_onset_intervals(pattern);
+    if intervals.is_empty() {
+        return String::from("IOI: (no kicks)\n");
+    }
+
+    let mut output = String::from("IOI: ");
+    output.push_str(&format_ioi(lead, &intervals));
+    output.push('\n');
+    output.push_str("     (sixteenths from each kick to the next");
+    if lead > 0 {
+        output.push_str("; (n) = rest before the first");
+    }
+    output.push_str(")\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, TimeSignature};
+    use alloc::vec;
+
+    fn pattern(kicks: &[usize]) -> Pattern {
+        let steps = (0..16).map(|i| kicks.contains(&i)).collect();
+        Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium)
+    }
+
+    #[test]
+    fn test_inter_onset_intervals() {
+        let (lead, intervals) = inter_onset_intervals(&pattern(&[0, 3, 6, 8, 12]));
+        assert_eq!(lead, 0);
+        assert_eq!(intervals, vec![3, 3, 2, 4, 4]);
+
+        let (lead, intervals) = inter_onset_intervals(&pattern(&[2, 4, 8]));
+        assert_eq!(lead, 2);
+        assert_eq!(intervals, vec![2, 4, 8]);
+    }
+
+    #[test]
+    fn test_pattern_to_ioi() {
+        assert!(pattern_to_ioi(&pattern(&[0, 3, 6, 8, 12])).starts_with("IOI: 3-3-2-4-4\n"));
+        assert!(pattern_to_ioi(&pattern(&[2, 4, 8])).starts_with("IOI: (2)-2-4-8\n"));
+        assert_eq!(pattern_to_ioi(&pattern(&[])), "IOI: (no kicks)\n");
+    }
+}
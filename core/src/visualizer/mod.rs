@@ -0,0 +1,21 @@
+// Visualizer module
+// ASCII art rendering for pattern display
+
+pub mod ascii;
+pub mod build;
+pub mod diff;
+pub mod ioi;
+pub mod notation;
+pub mod notes;
+pub mod reveal;
+pub mod rhythmic;
+pub mod sparkline;
+pub mod tab;
+pub mod timing;
+
+pub use ascii::format_pattern_with_metadata;
+pub use diff::format_answer_diff;
+pub use notation::NotationFormat;
+pub use reveal::{format_reveal, format_reveal_to_width, RevealStyle};
+pub use sparkline::sparkline;
+pub use timing::format_kit_timings;
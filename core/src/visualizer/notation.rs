@@ -0,0 +1,285 @@
+use crate::models::{Pattern, TimeSignature};
+use crate::visualizer::notes::NOTE_VALUES;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+/// Music notation source a pattern can be exported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NotationFormat {
+    /// LilyPond (`.ly`), engraved on a drum staff
+    #[default]
+    Lilypond,
+    /// ABC notation (`.abc`), on a percussion clef
+    Abc,
+}
+
+impl NotationFormat {
+    /// File extension for this format's source
+    pub fn extension(&self) -> &'static str {
+        match self {
+            NotationFormat::Lilypond => "ly",
+            NotationFormat::Abc => "abc",
+        }
+    }
+
+    /// Render a pattern as this format's source, marked at `tempo_bpm`
+    pub fn render(&self, pattern: &Pattern, tempo_bpm: u16) -> String {
+        match self {
+            NotationFormat::Lilypond => pattern_to_lilypond(pattern, tempo_bpm),
+            NotationFormat::Abc => pattern_to_abc(pattern, tempo_bpm),
+        }
+    }
+}
+
+impl FromStr for NotationFormat {
+    type Err = String;
+
+    /// Parse a notation format from string ("lilypond" or "abc")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "lilypond" | "ly" => Ok(NotationFormat::Lilypond),
+            "abc" => Ok(NotationFormat::Abc),
+            _ => Err(format!(
+                "Invalid notation format '{}'. Must be one of: lilypond, abc",
+                s
+            )),
+        }
+    }
+}
+
+/// A note or rest, `length` sixteenths long
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Value {
+    /// Whether it's a kick rather than a rest
+    pub(crate) kick: bool,
+    /// Length in sixteenths, always one of the plain or dotted note values
+    pub(crate) length: usize,
+}
+
+/// Notes and rests of each beat of each measure, with the measure's meter
+///
+/// Kicks are written the way drum parts are read: a kick lasts until the next
+/// one or the end of its beat, whichever comes first, and the rest of the beat
+/// is rests. Lengths no single (dotted) value covers are split, the kick
+/// taking the longest value that fits.
+pub(crate) fn measure_values(pattern: &Pattern) -> Vec<(TimeSignature, Vec<Vec<Value>>)> {
+    let measures = pattern.measures();
+    measures
+        .iter()
+        .enumerate()
+        .map(|(m, &(start, meter))| {
+            let end = measures
+                .get(m + 1)
+                .map_or(pattern.steps.len(), |&(next, _)| next);
+            let pulse = meter.pulse_sixteenths().max(1);
+            let beats = pattern.steps[start..end]
+                .chunks(pulse)
+                .map(beat_values)
+                .collect();
+            (meter, beats)
+        })
+        .collect()
+}
+
+/// Notes and rests for the steps of one beat
+fn beat_values(steps: &[bool]) -> Vec<Value> {
+    let mut values = Vec::new();
+    let mut i = 0;
+    while i < steps.len() {
+        let kick = steps[i];
+        let next = steps[i + 1..]
+            .iter()
+            .position(|&s| s)
+            .map_or(steps.len(), |offset| i + 1 + offset);
+        let mut remaining = next - i;
+        let mut first = true;
+        while remaining > 0 {
+            let length = fitting_value(remaining);
+            values.push(Value {
+                kick: kick && first,
+                length,
+            });
+            remaining -= length;
+            first = false;
+        }
+        i = next;
+    }
+    values
+}
+
+/// Longest plain or dotted note value no longer than `sixteenths`
+fn fitting_value(sixteenths: usize) -> usize {
+    NOTE_VALUES
+        .iter()
+        .map(|&(length, _)| length)
+        .find(|&length| length <= sixteenths)
+        .unwrap_or(1)
+}
+
+/// LilyPond duration for a note value (e.g., 6 → `4.`)
+fn lilypond_duration(length: usize) -> &'static str {
+    match length {
+        16 => "1",
+        12 => "2.",
+        8 => "2",
+        6 => "4.",
+        4 => "4",
+        3 => "8.",
+        2 => "8",
+        _ => "16",
+    }
+}
+
+/// Render a pattern as LilyPond source for a drum staff
+///
+/// Kicks are bass drum (`bd`) notes; the pattern sits in a repeat, as it
+/// loops in practice. Engrave it with `lilypond groove.ly`:
+/// ```text
+/// \drummode {
+///   \tempo 4 = 100
+///   \repeat volta 2 {
+///     \time 4/4
+///     bd4 bd8. bd16 r4 bd8 r8 |
+///   }
+/// }
+/// ```
+pub fn pattern_to_lilypond(pattern: &Pattern, tempo_bpm: u16) -> String {
+    let mut output = String::from("\\version \"2.24.0\"\n\n");
+    output.push_str(&format!(
+        "\\header {{\n  title = \"kickbeats\"\n  subtitle = \"{}\"\n  tagline = ##f\n}}\n\n",
+        pattern.to_pattern_string()
+    ));
+    output.push_str("\\score {\n  \\new DrumStaff {\n    \\drummode {\n");
+    output.push_str(&format!("      \\tempo 4 = {}\n", tempo_bpm));
+    output.push_str("      \\repeat volta 2 {\n");
+
+    let mut previous = None;
+    for (meter, beats) in measure_values(pattern) {
+        if previous != Some(meter) {
+            output.push_str(&format!(
+                "        \\time {}/{}\n",
+                meter.numerator, meter.denominator
+            ));
+            previous = Some(meter);
+        }
+        let notes: Vec<String> = beats
+            .iter()
+            .flatten()
+            .map(|value| {
+                let name = if value.kick { "bd" } else { "r" };
+                format!("{}{}", name, lilypond_duration(value.length))
+            })
+            .collect();
+        output.push_str(&format!("        {} |\n", notes.join(" ")));
+    }
+
+    output.push_str("      }\n    }\n  }\n  \\layout { }\n}\n");
+    output
+}
+
+/// Render a pattern as ABC notation on a percussion clef
+///
+/// Kicks are written on the bass drum's space (`F`), beamed by beat, in a
+/// repeat; `%%MIDI` lines map them to the General MIDI kick for players
+/// like abc2midi:
+/// ```text
+/// M:4/4
+/// L:1/16
+/// Q:1/4=100
+/// K:C clef=perc
+/// |: F4 F3F z4 F2z2 :|
+/// ```
+pub fn pattern_to_abc(pattern: &Pattern, tempo_bpm: u16) -> String {
+    let measures = measure_values(pattern);
+    let mut output = String::from("X:1\nT:kickbeats\n");
+    output.push_str(&format!("T:{}\n", pattern.to_pattern_string()));
+    if let Some((meter, _)) = measures.first() {
+        output.push_str(&format!("M:{}/{}\n", meter.numerator, meter.denominator));
+    }
+    output.push_str(&format!("L:1/16\nQ:1/4={}\nK:C clef=perc\n", tempo_bpm));
+    output.push_str("%%MIDI channel 10\n%%MIDI drummap F 36\n");
+
+    let mut previous = measures.first().map(|&(meter, _)| meter);
+    let bars: Vec<String> = measures
+        .iter()
+        .map(|(meter, beats)| {
+            let mut bar = String::new();
+            if previous != Some(*meter) {
+                bar.push_str(&format!("[M:{}/{}] ", meter.numerator, meter.denominator));
+                previous = Some(*meter);
+            }
+            let beats: Vec<String> = beats
+                .iter()
+                .map(|values| {
+                    values
+                        .iter()
+                        .map(|value| {
+                            let name = if value.kick { "F" } else { "z" };
+                            match value.length {
+                                1 => name.to_string(),
+                                length => format!("{}{}", name, length),
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            bar.push_str(&beats.join(" "));
+            bar
+        })
+        .collect();
+    output.push_str(&format!("|: {} :|\n", bars.join(" | ")));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ComplexityLevel;
+
+    fn pattern(text: &str) -> Pattern {
+        Pattern::from_pattern_string(text, ComplexityLevel::Medium).unwrap()
+    }
+
+    #[test]
+    fn test_lilypond_fills_each_beat() {
+        let lilypond = pattern_to_lilypond(&pattern("4/4:16:x...x..x....x.x."), 100);
+
+        assert!(lilypond.contains("subtitle = \"4/4:16:x...x..x....x.x.\""));
+        assert!(lilypond.contains("\\tempo 4 = 100\n"));
+        assert!(lilypond.contains("\\time 4/4\n        bd4 bd8. bd16 r4 bd8 bd8 |\n"));
+    }
+
+    #[test]
+    fn test_lilypond_marks_meter_changes() {
+        let lilypond = pattern_to_lilypond(&pattern("3/4+6/8:16:x.......x...x.....x....."), 90);
+
+        assert!(lilypond.contains("\\time 3/4\n        bd4 r4 bd4 |\n"));
+        assert!(lilypond.contains("\\time 6/8\n        bd4. bd4. |\n"));
+    }
+
+    #[test]
+    fn test_abc_beams_by_beat() {
+        let abc = pattern_to_abc(&pattern("4/4:16:x...x..x....x.x."), 120);
+
+        assert!(abc.contains("M:4/4\nL:1/16\nQ:1/4=120\nK:C clef=perc\n"));
+        assert!(abc.ends_with("|: F4 F3F z4 F2F2 :|\n"));
+    }
+
+    #[test]
+    fn test_abc_splits_lengths_without_a_single_value() {
+        // 6/8 beats are dotted quarters; five sixteenths is a quarter and a rest
+        let abc = pattern_to_abc(&pattern("6/8+2/4:16:x....x......x......x"), 100);
+
+        assert!(abc.contains("M:6/8\n"));
+        assert!(abc.ends_with("|: F4zF z6 | [M:2/4] F4 z3F :|\n"));
+    }
+
+    #[test]
+    fn test_parses_formats() {
+        assert_eq!("LilyPond".parse(), Ok(NotationFormat::Lilypond));
+        assert_eq!("abc".parse(), Ok(NotationFormat::Abc));
+        assert!("musicxml".parse::<NotationFormat>().is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use crate::models::Pattern;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Note values in sixteenths with their shorthand, longest first
+pub(crate) const NOTE_VALUES: &[(usize, &str)] = &[
+    (16, "w"),
+    (12, "h."),
+    (8, "h"),
+    (6, "q."),
+    (4, "q"),
+    (3, "8."),
+    (2, "8"),
+    (1, "16"),
+];
+
+/// Shorthand for a duration in sixteenths, tying values when no single one fits
+///
+/// E.g. 4 → `q`, 3 → `8.`, 5 → `q~16`.
+pub fn note_value(mut sixteenths: usize) -> String {
+    let mut parts = Vec::new();
+    while sixteenths > 0 {
+        let &(length, name) = NOTE_VALUES
+            .iter()
+            .find(|(length, _)| *length <= sixteenths)
+            .expect("sixteenth note always fits");
+        parts.push(name);
+        sixteenths -= length;
+    }
+    parts.join("~")
+}
+
+/// Render a pattern as the note value of each kick
+///
+/// Each kick lasts until the next one; the last kick rings until the
+/// downbeat of the next loop:
+/// ```text
+/// Notes: q  8.  8.  8  q
+///        (w h q 8 16 = whole..sixteenth, . = dotted, ~ = tie)
+/// ```
+pub fn pattern_to_note_values(pattern: &Pattern) -> String {
+    let kicks = pattern.note_positions();
+    let total = pattern.steps.len();
+    if kicks.is_empty() {
+        return String::from("Notes: (no kicks)\n");
+    }
+
+    let values: Vec<String> = kicks
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = kicks.get(i + 1).copied().unwrap_or(total + kicks[0]);
+            note_value(end - start)
+        })
+        .collect();
+
+    let mut output = String::from("Notes: ");
+    output.push_str(&values.join("  "));
+    output.push('\n');
+    output.push_str("       (w h q 8 16 = whole..sixteenth, . = dotted, ~ = tie)\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, TimeSignature};
+    use alloc::vec;
+
+    #[test]
+    fn test_note_value() {
+        assert_eq!(note_value(1), "16");
+        assert_eq!(note_value(3), "8.");
+        assert_eq!(note_value(4), "q");
+        assert_eq!(note_value(5), "q~16");
+        assert_eq!(note_value(16), "w");
+        assert_eq!(note_value(20), "w~q");
+    }
+
+    #[test]
+    fn test_pattern_to_note_values() {
+        let steps = vec![
+            true, false, false, false, //
+            true, false, false, true, //
+            false, false, true, false, //
+            true, false, false, false, //
+        ];
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium);
+
+        let notes = pattern_to_note_values(&pattern);
+        assert!(notes.starts_with("Notes: q  8.  8.  8  q\n"));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::models::Pattern;
+use crate::visualizer::ascii::{metadata_header, pattern_to_ascii_wrapped};
+use crate::visualizer::build::pattern_to_build_frames;
+use crate::visualizer::ioi::pattern_to_ioi;
+use crate::visualizer::notes::pattern_to_note_values;
+use crate::visualizer::rhythmic::pattern_to_rhythmic;
+use crate::visualizer::tab::pattern_to_tab;
+use alloc::format;
+use alloc::string::String;
+use core::str::FromStr;
+
+/// A representation of a pattern shown on reveal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RevealStyle {
+    /// Counting grid (`|1 e + a |` over `|X . . . |`)
+    #[default]
+    Grid,
+    /// Note value of each kick (`q  8.  8.  8  q`)
+    Notes,
+    /// Drum tab (`BD|o---o--o--o-o---|`)
+    Tab,
+    /// Grid uncovered beat by beat; interactive reveals also solo the kicks
+    /// on playback one beat at a time
+    Build,
+    /// Inter-onset intervals in sixteenths (`3-3-2-4-4`)
+    Ioi,
+    /// Notes and rests in Unicode musical symbols, a beat at a time (`♩  ♪. 𝅘𝅥𝅯`)
+    Rhythmic,
+}
+
+impl RevealStyle {
+    /// All styles, in display order
+    pub const ALL: [RevealStyle; 6] = [
+        RevealStyle::Grid,
+        RevealStyle::Notes,
+        RevealStyle::Tab,
+        RevealStyle::Build,
+        RevealStyle::Ioi,
+        RevealStyle::Rhythmic,
+    ];
+
+    /// Render a pattern in this style, wrapping the grid to `max_width` columns
+    pub fn render(&self, pattern: &Pattern, max_width: usize) -> String {
+        match self {
+            RevealStyle::Grid => pattern_to_ascii_wrapped(pattern, max_width),
+            RevealStyle::Notes => pattern_to_note_values(pattern),
+            RevealStyle::Tab => pattern_to_tab(pattern),
+            RevealStyle::Build => pattern_to_build_frames(pattern),
+            RevealStyle::Ioi => pattern_to_ioi(pattern),
+            RevealStyle::Rhythmic => pattern_to_rhythmic(pattern),
+        }
+    }
+}
+
+impl FromStr for RevealStyle {
+    type Err = String;
+
+    /// Parse a reveal style from string (e.g., "grid", "notes", "tab")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "grid" => Ok(RevealStyle::Grid),
+            "notes" => Ok(RevealStyle::Notes),
+            "tab" => Ok(RevealStyle::Tab),
+            "build" => Ok(RevealStyle::Build),
+            "ioi" => Ok(RevealStyle::Ioi),
+            "rhythmic" => Ok(RevealStyle::Rhythmic),
+            _ => Err(format!(
+                "Invalid reveal style '{}'. Must be one of: grid, notes, tab, build, ioi, rhythmic",
+                s
+            )),
+        }
+    }
+}
+
+/// Format a pattern's metadata followed by each requested style, with a
+/// footer giving its [pattern string](Pattern::to_pattern_string) to share
+///
+/// With `[RevealStyle::Grid]` this is `format_pattern_with_metadata` plus the footer.
+pub fn format_reveal(pattern: &Pattern, tempo_bpm: u16, styles: &[RevealStyle]) -> String {
+    format_reveal_to_width(pattern, tempo_bpm, styles, usize::MAX)
+}
+
+/// Like [`format_reveal`], wrapping wide grids to fit a terminal `max_width` columns wide
+pub fn format_reveal_to_width(
+    pattern: &Pattern,
+    tempo_bpm: u16,
+    styles: &[RevealStyle],
+    max_width: usize,
+) -> String {
+    let mut output = metadata_header(pattern, tempo_bpm);
+
+    for (i, style) in styles.iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+        output.push_str(&style.render(pattern, max_width));
+    }
+    output.push_str(&format!("\nShare: {}\n", pattern.to_pattern_string()));
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, TimeSignature};
+    use crate::visualizer::format_pattern_with_metadata;
+    use alloc::vec;
+
+    fn pattern() -> Pattern {
+        let steps = vec![
+            true, false, false, false, true, false, false, true, false, false, true, false, true,
+            false, false, false,
+        ];
+        Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium)
+    }
+
+    #[test]
+    fn test_parse_reveal_style() {
+        assert_eq!("grid".parse::<RevealStyle>(), Ok(RevealStyle::Grid));
+        assert_eq!("Notes".parse::<RevealStyle>(), Ok(RevealStyle::Notes));
+        assert_eq!("TAB".parse::<RevealStyle>(), Ok(RevealStyle::Tab));
+        assert_eq!("build".parse::<RevealStyle>(), Ok(RevealStyle::Build));
+        assert_eq!("IOI".parse::<RevealStyle>(), Ok(RevealStyle::Ioi));
+        assert_eq!("rhythmic".parse::<RevealStyle>(), Ok(RevealStyle::Rhythmic));
+        assert!("staff".parse::<RevealStyle>().is_err());
+    }
+
+    #[test]
+    fn test_grid_reveal_matches_metadata_format() {
+        let pattern = pattern();
+        assert_eq!(
+            format_reveal(&pattern, 120, &[RevealStyle::Grid]),
+            format!(
+                "{}\nShare: 4/4:16:x...x..x..x.x...\n",
+                format_pattern_with_metadata(&pattern, 120)
+            )
+        );
+    }
+
+    #[test]
+    fn test_reveal_renders_styles_in_order() {
+        let output = format_reveal(&pattern(), 120, &[RevealStyle::Tab, RevealStyle::Notes]);
+
+        let tab = output.find("BD|").unwrap();
+        let notes = output.find("Notes:").unwrap();
+        assert!(tab < notes);
+        assert!(!output.contains("|1 e + a |"));
+    }
+}
@@ -0,0 +1,84 @@
+use crate::models::Pattern;
+use crate::visualizer::notation::{measure_values, Value};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Unicode note or rest for a plain or dotted value, e.g. 6 → `♩.`
+fn symbol(value: Value) -> String {
+    let (base, dotted) = match value.length {
+        16 => (0, false),
+        12 => (1, true),
+        8 => (1, false),
+        6 => (2, true),
+        4 => (2, false),
+        3 => (3, true),
+        2 => (3, false),
+        _ => (4, false),
+    };
+    // Whole, half, quarter, eighth, sixteenth
+    const NOTES: [&str; 5] = ["𝅝", "𝅗𝅥", "♩", "♪", "𝅘𝅥𝅯"];
+    const RESTS: [&str; 5] = ["𝄻", "𝄼", "𝄽", "𝄾", "𝄿"];
+    let mut symbol = String::from(if value.kick { NOTES[base] } else { RESTS[base] });
+    if dotted {
+        symbol.push('.');
+    }
+    symbol
+}
+
+/// Render a pattern as the notes and rests a drummer would read, in Unicode
+/// musical symbols
+///
+/// Steps are grouped a beat at a time the way [notation](super::notation)
+/// writes them: each kick lasts until the next one or the end of its beat,
+/// and the rest of the beat is rests. Beats are two spaces apart:
+/// ```text
+/// Rhythm: | ♩  ♪. 𝅘𝅥𝅯  𝄽  ♪ ♪ |
+///         (♩ quarter, ♪ eighth, 𝅘𝅥𝅯 sixteenth, 𝄽 𝄾 𝄿 rests, . = dotted)
+/// ```
+pub fn pattern_to_rhythmic(pattern: &Pattern) -> String {
+    let measures: Vec<String> = measure_values(pattern)
+        .iter()
+        .map(|(_, beats)| {
+            let beats: Vec<String> = beats
+                .iter()
+                .map(|values| {
+                    let symbols: Vec<String> = values.iter().map(|&value| symbol(value)).collect();
+                    symbols.join(" ")
+                })
+                .collect();
+            beats.join("  ")
+        })
+        .collect();
+
+    let mut output = String::from("Rhythm: | ");
+    output.push_str(&measures.join(" | "));
+    output.push_str(" |\n");
+    output.push_str("        (♩ quarter, ♪ eighth, 𝅘𝅥𝅯 sixteenth, 𝄽 𝄾 𝄿 rests, . = dotted)\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ComplexityLevel;
+
+    fn pattern(text: &str) -> Pattern {
+        Pattern::from_pattern_string(text, ComplexityLevel::Medium).unwrap()
+    }
+
+    #[test]
+    fn test_groups_each_beat_into_note_values() {
+        let rhythmic = pattern_to_rhythmic(&pattern("4/4:16:x...x..x....x.x."));
+
+        assert!(rhythmic.starts_with("Rhythm: | ♩  ♪. 𝅘𝅥𝅯  𝄽  ♪ ♪ |\n"));
+    }
+
+    #[test]
+    fn test_dotted_beats_and_bar_lines() {
+        // 6/8 beats are dotted quarters; the lone kick of the 2/4 bar's
+        // second beat comes after a dotted eighth rest
+        let rhythmic = pattern_to_rhythmic(&pattern("6/8+2/4:16:x.....x.....x......x"));
+
+        assert!(rhythmic.starts_with("Rhythm: | ♩.  ♩. | ♩  𝄾. 𝅘𝅥𝅯 |\n"));
+    }
+}
@@ -0,0 +1,48 @@
+use alloc::string::String;
+
+/// Bar characters from lowest to highest
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Render values as a one-line sparkline
+///
+/// Bars are scaled so that `scale` (or the largest value, if higher) fills
+/// the tallest bar; a fixed scale keeps small jitter from looking dramatic.
+/// ```text
+/// ▁▁▂▁▁▁▅▁▁▂
+/// ```
+pub fn sparkline(values: &[f64], scale: f64) -> String {
+    let max = values.iter().copied().fold(scale, f64::max);
+    if max <= 0.0 {
+        return values.iter().map(|_| BARS[0]).collect();
+    }
+
+    values
+        .iter()
+        .map(|&v| {
+            // Round to the nearest bar (`f64::round` needs std)
+            let level = (v.max(0.0) / max * (BARS.len() - 1) as f64 + 0.5) as usize;
+            BARS[level.min(BARS.len() - 1)]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparkline_scales_to_fixed_maximum() {
+        assert_eq!(sparkline(&[0.0, 5.0, 10.0], 10.0), "▁▅█");
+    }
+
+    #[test]
+    fn test_sparkline_grows_scale_for_outliers() {
+        assert_eq!(sparkline(&[1.0, 20.0], 10.0), "▁█");
+    }
+
+    #[test]
+    fn test_sparkline_empty_and_zero() {
+        assert_eq!(sparkline(&[], 10.0), "");
+        assert_eq!(sparkline(&[0.0, 0.0], 0.0), "▁▁");
+    }
+}
@@ -0,0 +1,100 @@
+use crate::models::Pattern;
+use alloc::string::String;
+
+/// Render a pattern as drum tab
+///
+/// Beat numbers sit above the first step of each beat; `o` is a kick:
+/// ```text
+///    1   2   3   4
+/// BD|o---o--o--o-o---|
+/// ```
+/// Compound meters number their dotted-quarter pulses, like the grid. Meter
+/// changes split the line into bars, each counted from 1.
+pub fn pattern_to_tab(pattern: &Pattern) -> String {
+    let mut header = String::from("   ");
+    let mut line = String::from("BD|");
+    let measures = pattern.measures();
+    for (m, &(start, meter)) in measures.iter().enumerate() {
+        let steps_per_beat = meter.pulse_sixteenths().max(1);
+        let end = measures
+            .get(m + 1)
+            .map_or(pattern.steps.len(), |&(next, _)| next);
+
+        // Header with beat numbers aligned over the tab line
+        if m > 0 {
+            header.push(' ');
+            line.push('|');
+        }
+        for i in 0..end - start {
+            if i % steps_per_beat == 0 {
+                let beat = i / steps_per_beat + 1;
+                header.push(char::from_digit((beat % 10) as u32, 10).unwrap_or(' '));
+            } else {
+                header.push(' ');
+            }
+        }
+        for &has_kick in &pattern.steps[start..end] {
+            line.push(if has_kick { 'o' } else { '-' });
+        }
+    }
+
+    let mut output = String::from(header.trim_end());
+    output.push('\n');
+    output.push_str(&line);
+    output.push_str("|\n");
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, TimeSignature};
+    use alloc::vec;
+
+    #[test]
+    fn test_pattern_to_tab_four_four() {
+        let steps = vec![
+            true, false, false, false, true, false, false, true, false, false, true, false, true,
+            false, false, false,
+        ];
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium);
+
+        assert_eq!(
+            pattern_to_tab(&pattern),
+            "   1   2   3   4\nBD|o---o--o--o-o---|\n"
+        );
+    }
+
+    #[test]
+    fn test_pattern_to_tab_six_eight() {
+        let mut steps = vec![false; 12];
+        steps[0] = true;
+        steps[6] = true;
+        let pattern = Pattern::new(steps, TimeSignature::six_eight(), ComplexityLevel::Simple);
+
+        assert_eq!(pattern_to_tab(&pattern), "   1     2\nBD|o-----o-----|\n");
+    }
+
+    #[test]
+    fn test_pattern_to_tab_meter_changes() {
+        let pattern = Pattern::concat(&[
+            Pattern::new(
+                vec![true; 8],
+                TimeSignature::two_four(),
+                ComplexityLevel::Simple,
+            ),
+            Pattern::new(
+                vec![false; 12],
+                TimeSignature::three_four(),
+                ComplexityLevel::Simple,
+            ),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            pattern_to_tab(&pattern),
+            "   1   2    1   2   3\nBD|oooooooo|------------|\n"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use crate::grading::KitTimings;
+use crate::models::Pattern;
+use crate::visualizer::ascii::step_count;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Review of how a kit's hits landed against `pattern`, one line per loop
+///
+/// Each hit is named by limb and where its note is counted, with its offset
+/// in milliseconds; hits graded early or late are marked rushed or dragged.
+/// Loops count from 1. Empty if no hit was timed.
+/// ```text
+/// Kit timing (+ behind the pattern, - ahead of it):
+///   Loop 1: kick 1 +4ms · kick 2+ -38ms rushed
+///   Loop 2: kick 1 -2ms · snare 3 +71ms dragged
+/// ```
+pub fn format_kit_timings(pattern: &Pattern, timings: &KitTimings) -> String {
+    if timings.is_empty() {
+        return String::new();
+    }
+    let mut output = String::from("Kit timing (+ behind the pattern, - ahead of it):\n");
+    for (index, hits) in timings.loops() {
+        let hits: Vec<String> = hits
+            .iter()
+            .map(|hit| {
+                let mut label = format!(
+                    "{} {} {}",
+                    hit.limb.name(),
+                    step_count(pattern, hit.step),
+                    hit.offset_label()
+                );
+                if let Some(verdict) = hit.verdict() {
+                    label.push(' ');
+                    label.push_str(verdict);
+                }
+                label
+            })
+            .collect();
+        output.push_str(&format!("  Loop {}: {}\n", index + 1, hits.join(" · ")));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grading::{HitTiming, Limb};
+    use crate::models::{ComplexityLevel, TimeSignature};
+
+    #[test]
+    fn test_each_loop_gets_a_line() {
+        let steps = (0..16).map(|i| i == 0 || i == 6).collect();
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Simple);
+        let mut timings = KitTimings::new();
+        assert_eq!(format_kit_timings(&pattern, &timings), "");
+
+        // 120 BPM sixteenths: 125ms per step
+        let hit = |limb, position| HitTiming::on_lane(limb, &pattern.steps, position, 0.125);
+        timings.record(0, hit(Limb::Kick, 0.032).unwrap());
+        timings.record(0, hit(Limb::Kick, 5.5).unwrap());
+        timings.record(2, hit(Limb::Snare, 6.6).unwrap());
+        assert_eq!(
+            format_kit_timings(&pattern, &timings),
+            "Kit timing (+ behind the pattern, - ahead of it):\n\
+             \x20 Loop 1: kick 1 +4ms · kick 2+ -63ms rushed\n\
+             \x20 Loop 3: snare 2+ +75ms dragged\n"
+        );
+    }
+}
@@ -0,0 +1,262 @@
+// Golden tests for every visualizer style across representative meters.
+//
+// Review changes with `cargo insta review`; regenerate with
+// `INSTA_UPDATE=always cargo test -p kickbeats-core --test visualizer_snapshots`.
+
+use kickbeats_core::grading::grade_answer;
+use kickbeats_core::models::{ComplexityLevel, Pattern, TimeSignature};
+use kickbeats_core::visualizer::ascii::pattern_to_ascii;
+use kickbeats_core::visualizer::build::pattern_to_build_frames;
+use kickbeats_core::visualizer::ioi::pattern_to_ioi;
+use kickbeats_core::visualizer::notes::pattern_to_note_values;
+use kickbeats_core::visualizer::rhythmic::pattern_to_rhythmic;
+use kickbeats_core::visualizer::tab::pattern_to_tab;
+use kickbeats_core::visualizer::{format_answer_diff, format_pattern_with_metadata};
+use uuid::Uuid;
+
+/// Representative meters and rhythmic densities, each with a fixed pattern
+/// and a slightly wrong answer
+const CASES: &[(&str, TimeSignature, &str, &str)] = &[
+    (
+        "4_4",
+        TimeSignature {
+            numerator: 4,
+            denominator: 4,
+            grouping: None,
+        },
+        "x...x..x..x...x.",
+        "x...x...x.x...x.",
+    ),
+    (
+        "3_4",
+        TimeSignature {
+            numerator: 3,
+            denominator: 4,
+            grouping: None,
+        },
+        "x..x..x...x.",
+        "x..x...x..x.",
+    ),
+    (
+        "2_4",
+        TimeSignature {
+            numerator: 2,
+            denominator: 4,
+            grouping: None,
+        },
+        "x..x..x.",
+        "x..x.x..",
+    ),
+    (
+        "5_4",
+        TimeSignature {
+            numerator: 5,
+            denominator: 4,
+            grouping: None,
+        },
+        "x...x.x...x...x..x..",
+        "x...x.x...x..x...x..",
+    ),
+    (
+        "6_8",
+        TimeSignature {
+            numerator: 6,
+            denominator: 8,
+            grouping: None,
+        },
+        "x..x..x..x..",
+        "x.....x..x..",
+    ),
+    (
+        "7_8",
+        TimeSignature {
+            numerator: 7,
+            denominator: 8,
+            grouping: None,
+        },
+        "x.x.x..x.x....",
+        "x.x.x....x....",
+    ),
+    (
+        "4_4_quarters",
+        TimeSignature {
+            numerator: 4,
+            denominator: 4,
+            grouping: None,
+        },
+        "x...x...x...x...",
+        "x...x.......x...",
+    ),
+    (
+        "4_4_eighths",
+        TimeSignature {
+            numerator: 4,
+            denominator: 4,
+            grouping: None,
+        },
+        "x.x...x.x.x...x.",
+        "x.x...x...x.x.x.",
+    ),
+    (
+        "4_4_sixteenths",
+        TimeSignature {
+            numerator: 4,
+            denominator: 4,
+            grouping: None,
+        },
+        "xxx.x.xxx.xx.xxx",
+        "xxx.x.xx.xxx.xxx",
+    ),
+    (
+        "4_4_offbeats",
+        TimeSignature {
+            numerator: 4,
+            denominator: 4,
+            grouping: None,
+        },
+        "..x...x...x...x.",
+        ".x....x...x..x..",
+    ),
+    (
+        "4_4_empty",
+        TimeSignature {
+            numerator: 4,
+            denominator: 4,
+            grouping: None,
+        },
+        "................",
+        "x...............",
+    ),
+    (
+        "2_2",
+        TimeSignature {
+            numerator: 2,
+            denominator: 2,
+            grouping: None,
+        },
+        "x..x..x...x..x..",
+        "x..x..x...x...x.",
+    ),
+    (
+        "3_8",
+        TimeSignature {
+            numerator: 3,
+            denominator: 8,
+            grouping: None,
+        },
+        "x..x..",
+        "x.x...",
+    ),
+    (
+        "9_8",
+        TimeSignature {
+            numerator: 9,
+            denominator: 8,
+            grouping: None,
+        },
+        "x..x.....x..x.....",
+        "x..x.....x.....x..",
+    ),
+    (
+        "12_8",
+        TimeSignature {
+            numerator: 12,
+            denominator: 8,
+            grouping: None,
+        },
+        "x.....x..x..x.....x..x..",
+        "x.....x.....x.....x..x..",
+    ),
+];
+
+/// Build a pattern with a fixed id so metadata output is stable
+fn pattern(steps: &str, time_signature: TimeSignature) -> Pattern {
+    Pattern::with_id(
+        Uuid::nil(),
+        steps.chars().map(|c| c == 'x').collect(),
+        time_signature,
+        ComplexityLevel::Medium,
+    )
+}
+
+#[test]
+fn ascii_grid() {
+    for &(name, time_signature, steps, _) in CASES {
+        insta::assert_snapshot!(
+            format!("ascii_{}", name),
+            pattern_to_ascii(&pattern(steps, time_signature))
+        );
+    }
+}
+
+#[test]
+fn note_values() {
+    for &(name, time_signature, steps, _) in CASES {
+        insta::assert_snapshot!(
+            format!("notes_{}", name),
+            pattern_to_note_values(&pattern(steps, time_signature))
+        );
+    }
+}
+
+#[test]
+fn drum_tab() {
+    for &(name, time_signature, steps, _) in CASES {
+        insta::assert_snapshot!(
+            format!("tab_{}", name),
+            pattern_to_tab(&pattern(steps, time_signature))
+        );
+    }
+}
+
+#[test]
+fn build_frames() {
+    for &(name, time_signature, steps, _) in CASES {
+        insta::assert_snapshot!(
+            format!("build_{}", name),
+            pattern_to_build_frames(&pattern(steps, time_signature))
+        );
+    }
+}
+
+#[test]
+fn inter_onset_intervals() {
+    for &(name, time_signature, steps, _) in CASES {
+        insta::assert_snapshot!(
+            format!("ioi_{}", name),
+            pattern_to_ioi(&pattern(steps, time_signature))
+        );
+    }
+}
+
+#[test]
+fn rhythmic() {
+    for &(name, time_signature, steps, _) in CASES {
+        insta::assert_snapshot!(
+            format!("rhythmic_{}", name),
+            pattern_to_rhythmic(&pattern(steps, time_signature))
+        );
+    }
+}
+
+#[test]
+fn metadata() {
+    for &(name, time_signature, steps, _) in CASES {
+        insta::assert_snapshot!(
+            format!("metadata_{}", name),
+            format_pattern_with_metadata(&pattern(steps, time_signature), 120)
+        );
+    }
+}
+
+#[test]
+fn answer_diff() {
+    for &(name, time_signature, steps, answer) in CASES {
+        let pattern = pattern(steps, time_signature);
+        let grade = grade_answer(&pattern, answer).unwrap();
+        insta::assert_snapshot!(
+            format!("diff_{}", name),
+            format_answer_diff(&pattern, &grade)
+        );
+    }
+}
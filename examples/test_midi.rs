@@ -1,4 +1,4 @@
-use kickbeats::engine::midi::MidiEngine;
+use kickbeats_cli::engine::midi::MidiEngine;
 use std::thread;
 use std::time::Duration;
 
@@ -22,7 +22,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         Err(e) => {
             eprintln!("Error listing MIDI ports: {}", e);
-            return Err(e);
+            return Err(e.into());
         }
     }
 
@@ -0,0 +1,52 @@
+/*
+ * Kickbeats C bindings
+ *
+ * Generate and validate kick drum patterns from C-compatible hosts.
+ * Patterns are arrays of bytes, one per sixteenth-note step: 1 = kick, 0 = rest.
+ */
+
+#ifndef KICKBEATS_H
+#define KICKBEATS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define KB_OK 0
+#define KB_ERR_NULL (-1)
+#define KB_ERR_INVALID_ARG (-2)
+#define KB_ERR_BUFFER_TOO_SMALL (-3)
+#define KB_ERR_GENERATION (-4)
+#define KB_ERR_INVALID_PATTERN (-5)
+
+/* Complexity codes accepted by kb_generate */
+#define KB_COMPLEXITY_SIMPLE 1
+#define KB_COMPLEXITY_MEDIUM 2
+#define KB_COMPLEXITY_COMPLEX 3
+
+typedef struct KbGenerator KbGenerator;
+
+/* Create a generator; the same seed always yields the same pattern sequence. */
+KbGenerator *kb_generator_new(uint64_t seed);
+
+/* Free a generator. Passing NULL is a no-op. */
+void kb_generator_free(KbGenerator *generator);
+
+/*
+ * Generate the next pattern into `out`.
+ * Returns the number of steps written (e.g. 16 for 4/4), or a KB_ERR_* code.
+ */
+int32_t kb_generate(KbGenerator *generator, uint8_t numerator, uint8_t denominator,
+                    uint8_t complexity, uint8_t *out, size_t out_len);
+
+/* Returns KB_OK if the pattern passes validation, KB_ERR_INVALID_PATTERN otherwise. */
+int32_t kb_validate(const uint8_t *steps, size_t len, uint8_t numerator, uint8_t denominator);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* KICKBEATS_H */
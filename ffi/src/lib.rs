@@ -0,0 +1,235 @@
+// Kickbeats C bindings
+// Lets non-Rust practice apps (SuperCollider, Max, ...) reuse the pattern generator
+//
+// The matching header lives in include/kickbeats.h. All functions return a
+// non-negative value on success and one of the KB_ERR_* codes on failure.
+
+use kickbeats_core::generator::WeightedGenerator;
+use kickbeats_core::models::{ComplexityLevel, Pattern, TimeSignature};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::VecDeque;
+use std::slice;
+
+/// Success
+pub const KB_OK: i32 = 0;
+/// A required pointer argument was null
+pub const KB_ERR_NULL: i32 = -1;
+/// Time signature or complexity was out of range
+pub const KB_ERR_INVALID_ARG: i32 = -2;
+/// The output buffer cannot hold the pattern
+pub const KB_ERR_BUFFER_TOO_SMALL: i32 = -3;
+/// The generator could not find a valid, unique pattern
+pub const KB_ERR_GENERATION: i32 = -4;
+/// The pattern breaks one of the validation rules
+pub const KB_ERR_INVALID_PATTERN: i32 = -5;
+
+/// Maximum number of patterns remembered for uniqueness checking
+const HISTORY_CAPACITY: usize = 20;
+
+/// Opaque generator handle with its own seeded RNG and history
+pub struct KbGenerator {
+    generator: WeightedGenerator<StdRng>,
+    history: VecDeque<Pattern>,
+}
+
+/// Map the C-facing complexity code (1-3) to a level
+fn complexity_from_code(code: u8) -> Option<ComplexityLevel> {
+    match code {
+        1 => Some(ComplexityLevel::Simple),
+        2 => Some(ComplexityLevel::Medium),
+        3 => Some(ComplexityLevel::Complex),
+        _ => None,
+    }
+}
+
+/// Build a time signature, rejecting the values the CLI parser would reject
+fn time_signature_from_parts(numerator: u8, denominator: u8) -> Option<TimeSignature> {
+    if numerator == 0 || ![1, 2, 4, 8, 16].contains(&denominator) {
+        return None;
+    }
+    Some(TimeSignature::new(numerator, denominator))
+}
+
+/// Create a generator. The same seed always yields the same pattern sequence.
+///
+/// Release it with `kb_generator_free`.
+#[no_mangle]
+pub extern "C" fn kb_generator_new(seed: u64) -> *mut KbGenerator {
+    Box::into_raw(Box::new(KbGenerator {
+        generator: WeightedGenerator::with_rng(StdRng::seed_from_u64(seed)),
+        history: VecDeque::with_capacity(HISTORY_CAPACITY),
+    }))
+}
+
+/// Free a generator created by `kb_generator_new`. Null is ignored.
+///
+/// # Safety
+///
+/// `generator` must be null or a pointer returned by `kb_generator_new` that
+/// has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn kb_generator_free(generator: *mut KbGenerator) {
+    if !generator.is_null() {
+        drop(Box::from_raw(generator));
+    }
+}
+
+/// Generate the next pattern into `out` (1 = kick, 0 = rest)
+///
+/// Returns the number of steps written, or a KB_ERR_* code.
+///
+/// # Safety
+///
+/// `generator` must come from `kb_generator_new`, and `out` must point to at
+/// least `out_len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn kb_generate(
+    generator: *mut KbGenerator,
+    numerator: u8,
+    denominator: u8,
+    complexity: u8,
+    out: *mut u8,
+    out_len: usize,
+) -> i32 {
+    if generator.is_null() || out.is_null() {
+        return KB_ERR_NULL;
+    }
+    let generator = &mut *generator;
+
+    let (Some(time_signature), Some(complexity)) = (
+        time_signature_from_parts(numerator, denominator),
+        complexity_from_code(complexity),
+    ) else {
+        return KB_ERR_INVALID_ARG;
+    };
+    // Check before generating, so a failed call leaves the seeded sequence as it was
+    if time_signature.sixteenths_per_measure() > out_len {
+        return KB_ERR_BUFFER_TOO_SMALL;
+    }
+
+    let Ok((pattern, _)) =
+        generator
+            .generator
+            .generate_unique(time_signature, complexity, &generator.history)
+    else {
+        return KB_ERR_GENERATION;
+    };
+
+    let out = slice::from_raw_parts_mut(out, out_len);
+    for (dst, &step) in out.iter_mut().zip(pattern.steps.iter()) {
+        *dst = step as u8;
+    }
+    let written = pattern.steps.len() as i32;
+
+    if generator.history.len() >= HISTORY_CAPACITY {
+        generator.history.pop_front();
+    }
+    generator.history.push_back(pattern);
+
+    written
+}
+
+/// Check a pattern against the validation rules
+///
+/// Returns `KB_OK` for a valid pattern, `KB_ERR_INVALID_PATTERN` otherwise.
+///
+/// # Safety
+///
+/// `steps` must point to at least `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn kb_validate(
+    steps: *const u8,
+    len: usize,
+    numerator: u8,
+    denominator: u8,
+) -> i32 {
+    if steps.is_null() {
+        return KB_ERR_NULL;
+    }
+    let Some(time_signature) = time_signature_from_parts(numerator, denominator) else {
+        return KB_ERR_INVALID_ARG;
+    };
+
+    let steps = slice::from_raw_parts(steps, len)
+        .iter()
+        .map(|&s| s != 0)
+        .collect();
+    let pattern = Pattern::new(steps, time_signature, ComplexityLevel::default());
+
+    match pattern.validate_steps() {
+        Ok(()) => KB_OK,
+        Err(_) => KB_ERR_INVALID_PATTERN,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_and_validate_round_trip() {
+        let mut buf = [0u8; 32];
+        unsafe {
+            let generator = kb_generator_new(7);
+            let len = kb_generate(generator, 4, 4, 2, buf.as_mut_ptr(), buf.len());
+            assert_eq!(len, 16);
+            assert_eq!(buf[0], 1);
+            assert_eq!(kb_validate(buf.as_ptr(), len as usize, 4, 4), KB_OK);
+            kb_generator_free(generator);
+        }
+    }
+
+    #[test]
+    fn test_generate_reports_errors() {
+        let mut buf = [0u8; 8];
+        unsafe {
+            let generator = kb_generator_new(7);
+            assert_eq!(
+                kb_generate(generator, 4, 4, 2, buf.as_mut_ptr(), buf.len()),
+                KB_ERR_BUFFER_TOO_SMALL
+            );
+            assert_eq!(
+                kb_generate(generator, 4, 3, 2, buf.as_mut_ptr(), buf.len()),
+                KB_ERR_INVALID_ARG
+            );
+            assert_eq!(
+                kb_generate(std::ptr::null_mut(), 4, 4, 2, buf.as_mut_ptr(), buf.len()),
+                KB_ERR_NULL
+            );
+            kb_generator_free(generator);
+        }
+    }
+
+    #[test]
+    fn test_buffer_too_small_leaves_the_sequence_alone() {
+        let mut small = [0u8; 8];
+        let mut retried = [0u8; 16];
+        let mut fresh = [0u8; 16];
+        unsafe {
+            let generator = kb_generator_new(7);
+            assert_eq!(
+                kb_generate(generator, 4, 4, 2, small.as_mut_ptr(), small.len()),
+                KB_ERR_BUFFER_TOO_SMALL
+            );
+            kb_generate(generator, 4, 4, 2, retried.as_mut_ptr(), retried.len());
+            kb_generator_free(generator);
+
+            let generator = kb_generator_new(7);
+            kb_generate(generator, 4, 4, 2, fresh.as_mut_ptr(), fresh.len());
+            kb_generator_free(generator);
+        }
+        assert_eq!(retried, fresh);
+    }
+
+    #[test]
+    fn test_validate_rejects_pattern_without_downbeat() {
+        let steps = [0u8, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0];
+        unsafe {
+            assert_eq!(
+                kb_validate(steps.as_ptr(), steps.len(), 4, 4),
+                KB_ERR_INVALID_PATTERN
+            );
+        }
+    }
+}
@@ -0,0 +1,225 @@
+// Kickbeats Python bindings
+// Pattern generation and analysis for worksheets, notebooks, and teaching scripts
+//
+// Build a wheel with: maturin build --release (from this directory)
+
+use kickbeats_core::generator::WeightedGenerator;
+use kickbeats_core::grading::grade_answer;
+use kickbeats_core::models::{ComplexityLevel, Pattern, TimeSignature};
+use kickbeats_core::visualizer::ascii::pattern_to_ascii;
+use kickbeats_core::visualizer::{NotationFormat, RevealStyle};
+use kickbeats_core::CoreError;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::VecDeque;
+
+/// Maximum number of patterns remembered for uniqueness checking
+const HISTORY_CAPACITY: usize = 20;
+
+fn parse_time_signature(s: &str) -> PyResult<TimeSignature> {
+    s.parse().map_err(PyValueError::new_err)
+}
+
+fn parse_complexity(s: &str) -> PyResult<ComplexityLevel> {
+    s.parse().map_err(PyValueError::new_err)
+}
+
+/// Bad input raises ValueError; a generator that ran out of options, RuntimeError
+fn core_error(error: CoreError) -> PyErr {
+    match error {
+        CoreError::Generation(message) => PyRuntimeError::new_err(message),
+        CoreError::Config(message) => PyValueError::new_err(message),
+    }
+}
+
+/// A kick drum pattern: one bool per sixteenth-note step
+#[pyclass(name = "Pattern", module = "kickbeats")]
+#[derive(Clone)]
+struct PyPattern {
+    inner: Pattern,
+}
+
+#[pymethods]
+impl PyPattern {
+    #[new]
+    #[pyo3(signature = (steps, time_signature = "4/4", complexity = "medium"))]
+    fn new(steps: Vec<bool>, time_signature: &str, complexity: &str) -> PyResult<Self> {
+        Ok(Self {
+            inner: Pattern::new(
+                steps,
+                parse_time_signature(time_signature)?,
+                parse_complexity(complexity)?,
+            ),
+        })
+    }
+
+    #[getter]
+    fn id(&self) -> String {
+        self.inner.id.to_string()
+    }
+
+    #[getter]
+    fn steps(&self) -> Vec<bool> {
+        self.inner.steps.clone()
+    }
+
+    #[getter]
+    fn time_signature(&self) -> String {
+        format!(
+            "{}/{}",
+            self.inner.time_signature.numerator, self.inner.time_signature.denominator
+        )
+    }
+
+    #[getter]
+    fn complexity(&self) -> String {
+        format!("{:?}", self.inner.complexity_level).to_lowercase()
+    }
+
+    /// Indices of the steps that carry a kick
+    fn note_positions(&self) -> Vec<usize> {
+        self.inner.note_positions()
+    }
+
+    /// Ratio of kicks to total steps (0.0-1.0)
+    fn density(&self) -> f32 {
+        self.inner.density()
+    }
+
+    /// How hard the pattern is to hear and play (0-100)
+    fn difficulty_score(&self) -> u8 {
+        self.inner.difficulty_score()
+    }
+
+    /// Number of steps that differ from another pattern
+    fn hamming_distance(&self, other: &PyPattern) -> u32 {
+        self.inner.hamming_distance(&other.inner)
+    }
+
+    /// Raise ValueError if the pattern breaks a generation rule
+    fn validate(&self) -> PyResult<()> {
+        self.inner
+            .validate_steps()
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// Two-line ASCII grid, as shown by the CLI reveal
+    fn to_ascii(&self) -> String {
+        pattern_to_ascii(&self.inner)
+    }
+
+    /// The pattern in one reveal style ("grid", "notes", "tab", "build",
+    /// "ioi", or "rhythmic"), with the grid wrapped to `width` columns
+    #[pyo3(signature = (style = "grid", width = 80))]
+    fn render(&self, style: &str, width: usize) -> PyResult<String> {
+        let style: RevealStyle = style.parse().map_err(PyValueError::new_err)?;
+        Ok(style.render(&self.inner, width))
+    }
+
+    /// Notation source ("lilypond" or "abc"), as written by `kickbeats notation`
+    #[pyo3(signature = (format = "lilypond", tempo = 120))]
+    fn to_notation(&self, format: &str, tempo: u16) -> PyResult<String> {
+        let format: NotationFormat = format.parse().map_err(PyValueError::new_err)?;
+        Ok(format.render(&self.inner, tempo))
+    }
+
+    /// Rhythm string such as "4/4:x...x.x.....x...", the same in every session
+    fn fingerprint(&self) -> String {
+        self.inner.fingerprint()
+    }
+
+    /// Rebuild a pattern from a string returned by `fingerprint`
+    #[staticmethod]
+    #[pyo3(signature = (fingerprint, complexity = "medium"))]
+    fn from_fingerprint(fingerprint: &str, complexity: &str) -> PyResult<Self> {
+        let inner = Pattern::from_fingerprint(fingerprint, parse_complexity(complexity)?)
+            .map_err(core_error)?;
+        Ok(Self { inner })
+    }
+
+    /// Grade a typed answer such as "x...x...x.x....x"
+    ///
+    /// Returns a dict with accuracy, wrong_positions, missed_kicks, and extra_kicks.
+    fn grade<'py>(&self, py: Python<'py>, answer: &str) -> PyResult<Bound<'py, PyDict>> {
+        let grade = grade_answer(&self.inner, answer).map_err(core_error)?;
+        let result = PyDict::new(py);
+        result.set_item("accuracy", grade.accuracy)?;
+        result.set_item("wrong_positions", grade.wrong_positions)?;
+        result.set_item("missed_kicks", grade.missed_kicks)?;
+        result.set_item("extra_kicks", grade.extra_kicks)?;
+        Ok(result)
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.steps.len()
+    }
+
+    fn __repr__(&self) -> String {
+        let steps: String = self
+            .inner
+            .steps
+            .iter()
+            .map(|&s| if s { 'x' } else { '.' })
+            .collect();
+        format!("Pattern('{}', '{}')", steps, self.time_signature())
+    }
+}
+
+/// Weighted pattern generator with session-style uniqueness history
+#[pyclass(name = "Generator", module = "kickbeats")]
+struct PyGenerator {
+    generator: WeightedGenerator<StdRng>,
+    history: VecDeque<Pattern>,
+}
+
+#[pymethods]
+impl PyGenerator {
+    /// Create a generator; pass a seed for a reproducible pattern sequence
+    #[new]
+    #[pyo3(signature = (seed = None))]
+    fn new(seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self {
+            generator: WeightedGenerator::with_rng(rng),
+            history: VecDeque::with_capacity(HISTORY_CAPACITY),
+        }
+    }
+
+    /// Generate a pattern that differs from the last 20 generated
+    #[pyo3(signature = (time_signature = "4/4", complexity = "medium"))]
+    fn generate(&mut self, time_signature: &str, complexity: &str) -> PyResult<PyPattern> {
+        let (pattern, _) = self
+            .generator
+            .generate_unique(
+                parse_time_signature(time_signature)?,
+                parse_complexity(complexity)?,
+                &self.history,
+            )
+            .map_err(core_error)?;
+
+        if self.history.len() >= HISTORY_CAPACITY {
+            self.history.pop_front();
+        }
+        self.history.push_back(pattern.clone());
+
+        Ok(PyPattern { inner: pattern })
+    }
+
+    /// Forget previously generated patterns
+    fn clear_history(&mut self) {
+        self.history.clear();
+    }
+}
+
+#[pymodule]
+fn kickbeats(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyPattern>()?;
+    m.add_class::<PyGenerator>()?;
+    Ok(())
+}
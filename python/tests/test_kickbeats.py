@@ -0,0 +1,49 @@
+import kickbeats
+import pytest
+
+
+def test_seeded_generators_agree():
+    a = kickbeats.Generator(seed=42)
+    b = kickbeats.Generator(seed=42)
+    for _ in range(5):
+        assert a.generate().steps == b.generate().steps
+
+
+def test_generated_pattern_is_valid():
+    pattern = kickbeats.Generator(seed=1).generate("3/4", "simple")
+    assert len(pattern) == 12
+    assert pattern.steps[0]
+    pattern.validate()
+
+
+def test_patterns_have_their_own_ids():
+    generator = kickbeats.Generator(seed=1)
+    ids = {generator.generate().id, kickbeats.Pattern([True] + [False] * 15).id}
+    assert len(ids) == 2
+    assert "00000000-0000-0000-0000-000000000000" not in ids
+
+
+def test_validate_raises_on_missing_downbeat():
+    steps = [False] * 16
+    steps[4] = steps[8] = True
+    with pytest.raises(ValueError):
+        kickbeats.Pattern(steps).validate()
+
+
+def test_grade_answer():
+    pattern = kickbeats.Pattern([c == "x" for c in "x...x...x.x....x"])
+    result = pattern.grade("x...x...x..x...x")
+    assert result["wrong_positions"] == [10, 11]
+    assert result["accuracy"] == pytest.approx(14 / 16)
+
+
+def test_exports_match_the_cli_formats():
+    pattern = kickbeats.Pattern([c == "x" for c in "x..x..x...x.x..."])
+    assert pattern.fingerprint() == "4/4:x..x..x...x.x..."
+    restored = kickbeats.Pattern.from_fingerprint(pattern.fingerprint())
+    assert restored.steps == pattern.steps
+    assert "\\drummode" in pattern.to_notation()
+    assert "Q:1/4=90\nK:C clef=perc" in pattern.to_notation("abc", tempo=90)
+    assert pattern.render("ioi").startswith("IOI: 3-3-4-2-4\n")
+    with pytest.raises(ValueError):
+        pattern.to_notation("midi")
@@ -0,0 +1,347 @@
+use crate::engine::{class_addr, ClassMessage, PracticeEngine};
+use crate::grading::{grade_answer_at, AnswerFormat, AnswerResolution};
+use crate::models::{ComplexityLevel, Pattern};
+use crate::visualizer::{format_answer_diff, format_reveal, RevealStyle};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+/// Longest wait between checks for Ctrl-C
+const WAIT_SLICE: Duration = Duration::from_millis(50);
+
+/// Longest wait for the teacher's machine to answer when joining
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Something for the student loop to deal with
+enum Input {
+    /// A message from the teacher
+    Class(ClassMessage),
+    /// A line the student typed
+    Typed(String),
+    /// The teacher's machine went away
+    Gone,
+}
+
+/// Joins a class hosted with `--host`: hears (or, in a quiz, just sees) each
+/// of the teacher's patterns and sends typed answers back
+///
+/// Answers are graded here, so the student sees how they did straight away,
+/// and sent on for the teacher's machine to grade too. With an engine (see
+/// [`with_engine`](Self::with_engine)) patterns are played as they arrive,
+/// unless the teacher runs the class as a quiz.
+pub struct ClassroomStudent {
+    /// Name the teacher sees
+    name: String,
+    /// Engine the teacher's patterns are played on, if they're to be heard
+    engine: Option<PracticeEngine>,
+    /// Representations shown when a pattern is revealed
+    reveal_styles: Vec<RevealStyle>,
+    /// How answers are typed
+    answer_format: AnswerFormat,
+    /// Grid answers are typed at
+    answer_resolution: AnswerResolution,
+    /// Teacher's current pattern and its number
+    current: Option<(u32, Pattern)>,
+    /// Tempo the teacher last set
+    tempo_bpm: u16,
+}
+
+impl ClassroomStudent {
+    /// Create a student called `name`, seeing the teacher's patterns without sound
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            engine: None,
+            reveal_styles: vec![RevealStyle::Grid],
+            answer_format: AnswerFormat::default(),
+            answer_resolution: AnswerResolution::default(),
+            current: None,
+            tempo_bpm: 120,
+        }
+    }
+
+    /// Play the teacher's patterns on `engine` as they arrive
+    pub fn with_engine(mut self, engine: PracticeEngine) -> Self {
+        self.engine = Some(engine);
+        self
+    }
+
+    /// Show revealed patterns in these styles instead of the grid
+    pub fn with_reveal_styles(mut self, styles: Vec<RevealStyle>) -> Self {
+        self.reveal_styles = styles;
+        self
+    }
+
+    /// Grade answers typed in `format` at `resolution`
+    pub fn with_answer_format(
+        mut self,
+        format: AnswerFormat,
+        resolution: AnswerResolution,
+    ) -> Self {
+        self.answer_format = format;
+        self.answer_resolution = resolution;
+        self
+    }
+
+    /// Join the class at `addr` (e.g. "192.168.1.20:9090"), reading answers
+    /// from stdin until the class ends or `running` is cleared (Ctrl-C)
+    pub fn run(
+        &mut self,
+        addr: &str,
+        running: &AtomicBool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let addr = class_addr(addr);
+        let failed = |e: io::Error| format!("Failed to join the class at {}: {}", addr, e);
+        let resolved = addr
+            .to_socket_addrs()
+            .map_err(failed)?
+            .next()
+            .ok_or_else(|| format!("Failed to join the class at {}: no such address", addr))?;
+        let stream = TcpStream::connect_timeout(&resolved, CONNECT_TIMEOUT).map_err(failed)?;
+        let mut writer = stream.try_clone().map_err(failed)?;
+        writer
+            .write_all(
+                ClassMessage::Hello {
+                    name: self.name.clone(),
+                }
+                .to_line()
+                .as_bytes(),
+            )
+            .map_err(failed)?;
+
+        println!("🎓 Joined the class at {} as {}", addr, self.name);
+        println!("   Type each answer and press Enter; type q to leave.\n");
+
+        let (inputs, received) = mpsc::channel();
+        listen_to_class(stream, inputs.clone());
+        read_answers(inputs);
+
+        while running.load(Ordering::SeqCst) {
+            let input = match received.recv_timeout(WAIT_SLICE) {
+                Ok(input) => input,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            match input {
+                Input::Class(ClassMessage::End) => {
+                    println!("\n🎓 Class is over.");
+                    break;
+                }
+                Input::Class(message) => {
+                    for line in self.receive(message) {
+                        println!("{}", line);
+                    }
+                }
+                Input::Typed(line) if matches!(line.trim(), "q" | "quit") => break,
+                Input::Typed(line) => {
+                    let (lines, answer) = self.answer(line.trim());
+                    for line in lines {
+                        println!("{}", line);
+                    }
+                    if let Some(answer) = answer {
+                        if writer.write_all(answer.to_line().as_bytes()).is_err() {
+                            println!("\n🎓 Lost the connection to the class.");
+                            break;
+                        }
+                    }
+                }
+                Input::Gone => {
+                    println!("\n🎓 Lost the connection to the class.");
+                    break;
+                }
+            }
+        }
+
+        if let Some(engine) = &mut self.engine {
+            engine.stop();
+        }
+        Ok(())
+    }
+
+    /// Act on a message from the teacher, returning the lines to show
+    pub fn receive(&mut self, message: ClassMessage) -> Vec<String> {
+        match message {
+            ClassMessage::Pattern {
+                number,
+                pattern,
+                tempo,
+                quiz,
+            } => {
+                let pattern = match Pattern::from_fingerprint(&pattern, ComplexityLevel::default())
+                {
+                    Ok(pattern) => pattern,
+                    Err(e) => return vec![format!("✗ Couldn't read the teacher's pattern: {}", e)],
+                };
+                self.tempo_bpm = tempo;
+                self.current = Some((number, pattern.clone()));
+                let mut lines = vec![format!(
+                    "♪  Pattern #{} at {} BPM{}. Type what you hear.",
+                    number,
+                    tempo,
+                    if quiz { ", played by your teacher" } else { "" }
+                )];
+                if let (Some(engine), false) = (&mut self.engine, quiz) {
+                    let played = engine
+                        .set_tempo(tempo)
+                        .and_then(|()| engine.replay(pattern));
+                    if let Err(e) = played {
+                        lines.push(format!("✗ Failed to start playback: {}", e));
+                    }
+                } else if let Some(engine) = &mut self.engine {
+                    engine.stop();
+                }
+                lines
+            }
+            ClassMessage::Tempo { bpm } => {
+                self.tempo_bpm = bpm;
+                if let Some(engine) = &mut self.engine {
+                    let _ = engine.set_tempo(bpm);
+                }
+                vec![format!("♩  Tempo: {} BPM", bpm)]
+            }
+            ClassMessage::Reveal { number } => match &self.current {
+                Some((current, pattern)) if *current == number => vec![format!(
+                    "\n{}",
+                    format_reveal(pattern, self.tempo_bpm, &self.reveal_styles)
+                )],
+                _ => Vec::new(),
+            },
+            // Only students say hello and answer; the run loop handles the end
+            _ => Vec::new(),
+        }
+    }
+
+    /// Grade a typed answer to the current pattern, returning the lines to
+    /// show and the answer to send the teacher if it could be graded
+    pub fn answer(&self, input: &str) -> (Vec<String>, Option<ClassMessage>) {
+        if input.is_empty() {
+            return (Vec::new(), None);
+        }
+        let Some((number, pattern)) = &self.current else {
+            return (
+                vec![String::from("Waiting for your teacher's first pattern...")],
+                None,
+            );
+        };
+        let resolution = self.answer_resolution;
+        match grade_answer_at(pattern, input, self.answer_format, resolution) {
+            Ok(grade) => {
+                let lines = vec![
+                    format!(
+                        "\n{}",
+                        format_answer_diff(&resolution.truth(pattern), &grade)
+                    ),
+                    format!(
+                        "Sent to your teacher: {:.0}% on #{}\n",
+                        grade.accuracy * 100.0,
+                        number
+                    ),
+                ];
+                // Sent as a grid, whatever format it was typed in
+                let answer = ClassMessage::Answer {
+                    number: *number,
+                    answer: grade
+                        .answer
+                        .iter()
+                        .step_by(resolution.steps_per_slot())
+                        .map(|&kick| if kick { 'x' } else { '.' })
+                        .collect(),
+                    resolution: resolution.name().to_string(),
+                };
+                (lines, Some(answer))
+            }
+            Err(e) => (vec![format!("✗ {}", e)], None),
+        }
+    }
+}
+
+/// Pass on the teacher's messages until their machine goes away
+fn listen_to_class(stream: TcpStream, inputs: Sender<Input>) {
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            let Ok(message) = serde_json::from_str(&line) else {
+                continue;
+            };
+            if inputs.send(Input::Class(message)).is_err() {
+                return;
+            }
+        }
+        let _ = inputs.send(Input::Gone);
+    });
+}
+
+/// Pass on each line typed on stdin
+fn read_answers(inputs: Sender<Input>) {
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if inputs.send(Input::Typed(line)).is_err() {
+                return;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern_message(quiz: bool) -> ClassMessage {
+        ClassMessage::Pattern {
+            number: 4,
+            pattern: "4/4:x...x.......x...".to_string(),
+            tempo: 96,
+            quiz,
+        }
+    }
+
+    #[test]
+    fn test_answers_are_graded_and_sent_to_the_teacher() {
+        let mut student = ClassroomStudent::new("Ana");
+        let (lines, answer) = student.answer("x...x...");
+        assert!(lines[0].contains("Waiting"));
+        assert!(answer.is_none());
+
+        let lines = student.receive(pattern_message(true));
+        assert!(lines[0].contains("Pattern #4"));
+        assert!(lines[0].contains("played by your teacher"));
+
+        let (lines, answer) = student.answer("x...x.......x...");
+        assert!(lines[1].contains("100% on #4"));
+        assert_eq!(
+            answer,
+            Some(ClassMessage::Answer {
+                number: 4,
+                answer: "x...x.......x...".to_string(),
+                resolution: "sixteenth".to_string(),
+            })
+        );
+
+        let (lines, answer) = student.answer("x?");
+        assert!(lines[0].starts_with('✗'));
+        assert!(answer.is_none());
+    }
+
+    #[test]
+    fn test_reveals_only_the_current_pattern() {
+        let mut student = ClassroomStudent::new("Ana");
+        student.receive(pattern_message(false));
+        assert!(student
+            .receive(ClassMessage::Reveal { number: 3 })
+            .is_empty());
+        assert_eq!(student.receive(ClassMessage::Reveal { number: 4 }).len(), 1);
+        assert_eq!(
+            student.receive(ClassMessage::Tempo { bpm: 110 }),
+            vec!["♩  Tempo: 110 BPM".to_string()]
+        );
+        assert_eq!(student.tempo_bpm, 110);
+    }
+}
@@ -1,31 +1,121 @@
-use crate::engine::MidiPlaybackLoop;
-use crate::generator::WeightedGenerator;
-use crate::models::{ComplexityLevel, PracticeSession};
-use crate::visualizer::format_pattern_with_metadata;
+#[cfg(feature = "webhooks")]
+use crate::cli::SessionEvent;
+use crate::cli::SessionOptions;
+use crate::config::{Action, SettingsUpdate, PAD_KEYS};
+use crate::engine::{
+    ClassEvent, DrumPad, MidiMapping, NextPattern, Notice, Pad, PracticeEngine, DRIFT_WARNING_MS,
+};
+use crate::error::KickbeatsError;
+use crate::grading::{
+    grade_answer_at, grade_tap, AnswerFormat, AnswerResolution, LimbScores, TapCounts, TapScore,
+};
+use crate::models::{
+    relaxed_summary, ArrangementSpec, ComplexityLevel, CountIn, Pattern, PatternNote, PlanProgress,
+    PracticeSession, Transform,
+};
+use crate::visualizer::ascii::{count_row, kick_row, step_column};
+use crate::visualizer::build::build_frame;
+use crate::visualizer::{
+    format_answer_diff, format_kit_timings, format_reveal_to_width, sparkline, RevealStyle,
+};
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent},
-    terminal::{disable_raw_mode, enable_raw_mode},
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    queue,
+    style::Stylize,
+    terminal::{self, disable_raw_mode, enable_raw_mode, Clear, ClearType},
 };
+use std::collections::VecDeque;
 use std::io::{self, Write};
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Number of recent loops shown in the drift sparkline
+const DRIFT_HISTORY: usize = 40;
+
+/// Drift that fills the tallest sparkline bar (the engine's warning threshold)
+const DRIFT_SCALE_MS: f64 = DRIFT_WARNING_MS;
+
+/// Columns used by the drift status line around the sparkline itself
+const DRIFT_LINE_OVERHEAD: usize = 52;
+
+/// Key polling interval while the playback cursor is moving (fast enough for sixteenths at 300 BPM)
+const CURSOR_POLL: Duration = Duration::from_millis(20);
+
+/// Key polling interval otherwise
+const IDLE_POLL: Duration = Duration::from_millis(100);
+
+/// Terminal width assumed when the real size can't be queried
+const DEFAULT_TERM_WIDTH: u16 = 80;
+
+/// What the position line last drew
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+    /// Step highlighted on the revealed kick row
+    step: Option<usize>,
+    /// Bar within the phrase and phrase number, both counting from 1
+    bar: Option<(u32, u64)>,
+    /// Rolling tap-along score, while the drum pad is on
+    score: Option<TapCounts>,
+    /// Each limb's grades, once the e-kit has played
+    kit: Option<LimbScores>,
+    /// Morph stage playing (from 0) and the number of stages, while morphing
+    morph: Option<(usize, usize)>,
+    /// Distance from recent patterns the pattern settled for, when under 3
+    relaxed: Option<u32>,
+}
 
 /// Manages the command-line interface and user input
 pub struct CommandLoop {
-    /// Current practice session
-    session: PracticeSession,
-    /// MIDI playback engine
-    playback: MidiPlaybackLoop,
-    /// Pattern generator
-    generator: WeightedGenerator,
+    /// Session, generator, and playback
+    engine: PracticeEngine,
+    /// Keys and reporting shared with the other front ends
+    options: SessionOptions,
+    /// Seconds shown by the pre-roll countdown, if one is on screen
+    countdown_shown: Option<u64>,
+    /// Whether the live drift sparkline is shown
+    diagnostics: bool,
+    /// Scheduling drift (ms) of the most recent loops, oldest first
+    drift_history: VecDeque<f64>,
+    /// Latest event (ms) in the most recent loop
+    last_jitter_ms: f64,
+    /// Last build-up frame printed (0 = waiting for the first), while one runs
+    build_shown: Option<usize>,
+    /// Playback position on the status line, while it is on screen
+    position_shown: Option<Position>,
+    /// Whether the current pattern still awaits its timed drill answer
+    drill_pending: bool,
+    /// Current terminal width in columns, updated on resize
+    term_width: u16,
+    /// Whether the status line must be redrawn (e.g., after a resize)
+    redraw_status: bool,
+    /// Drum pad for tapping along, while pad mode is on
+    pad: Option<DrumPad>,
+    /// Timing of the most recent kick taps against the loop
+    tap_score: TapScore,
+    /// Whether Ctrl-C was pressed at a prompt, for the input loop to quit on
+    interrupted: bool,
 }
 
 impl CommandLoop {
-    /// Create a new command loop
-    pub fn new(session: PracticeSession) -> Self {
+    /// Create a command loop around an engine whose current pattern plays first
+    pub fn new(engine: PracticeEngine, options: SessionOptions) -> Self {
         Self {
-            session,
-            playback: MidiPlaybackLoop::new(),
-            generator: WeightedGenerator::new(),
+            engine,
+            options,
+            countdown_shown: None,
+            diagnostics: false,
+            drift_history: VecDeque::with_capacity(DRIFT_HISTORY),
+            last_jitter_ms: 0.0,
+            build_shown: None,
+            drill_pending: false,
+            position_shown: None,
+            term_width: terminal::size()
+                .map(|(w, _)| w)
+                .unwrap_or(DEFAULT_TERM_WIDTH),
+            redraw_status: false,
+            pad: None,
+            tap_score: TapScore::new(),
+            interrupted: false,
         }
     }
 
@@ -36,44 +126,274 @@ impl CommandLoop {
         println!("╚═══════════════════════════════════════════════════════════╝\n");
 
         println!("Session Settings:");
-        println!("  Tempo: {} BPM", self.session.tempo_bpm);
-        println!("  Complexity: {:?}", self.session.complexity_level);
-        println!(
-            "  Time Signature: {}/{}",
-            self.session.time_signature.numerator, self.session.time_signature.denominator
-        );
+        match self.engine.session.tempo_range {
+            Some(range) => println!(
+                "  Tempo: {} BPM, picked at random from {} for each pattern",
+                self.engine.session.tempo_bpm, range
+            ),
+            None => println!("  Tempo: {} BPM", self.engine.session.tempo_bpm),
+        }
+        if let Some(ramp) = self.engine.session.tempo_ramp {
+            println!(
+                "  Tempo Ramp: +{} BPM every {} bars",
+                ramp.step_bpm, ramp.every_loops
+            );
+        }
+        if self.engine.session.listen_delay_secs > 0 {
+            println!("  Listen Delay: {}s", self.engine.session.listen_delay_secs);
+        }
+        if self.engine.session.count_in != CountIn::default() {
+            println!("  Count-in: {}", self.engine.session.count_in);
+        }
+        if let Some(overlay) = self.engine.session.overlay {
+            println!("  Overlay: {}", overlay.name());
+        }
+        if self.engine.session.humanize > 0 {
+            println!("  Humanize: {}%", self.engine.session.humanize);
+        }
+        if let Some(goal) = &self.engine.session.goal {
+            println!("  Goal: {} ({} patterns)", goal.goal, goal.goal.total());
+        }
+        if let Some(plan) = &self.engine.session.plan {
+            println!(
+                "  Plan: {} segments, {} min",
+                plan.plan.segments.len(),
+                plan.plan.total_minutes()
+            );
+            for (i, segment) in plan.plan.segments.iter().enumerate() {
+                println!("    {}. {} — {}", i + 1, segment.label(i), segment);
+            }
+        }
+        if let Some(drill) = self.engine.session.drill {
+            println!(
+                "  Drill: {} loops, then {}s to answer",
+                drill.loops, drill.answer_secs
+            );
+        }
+        if self.engine.session.auto_advance {
+            println!(
+                "  Auto-Advance: a new pattern every {} loops{}",
+                self.engine.session.advance_loops,
+                if self.engine.session.reveal_on_advance {
+                    ", revealing the last one"
+                } else {
+                    ""
+                }
+            );
+        }
+        if !self.engine.session.click_enabled {
+            println!(
+                "  Click: off (press {} to turn it on)",
+                self.options.keys.key(Action::Mute)
+            );
+        }
+        if self.engine.session.kick_velocity.is_some()
+            || self.engine.session.click_velocity.is_some()
+        {
+            println!(
+                "  Velocity: kicks {}, click {} (press {}/{} and {}/{} to change)",
+                self.engine.kick_velocity(),
+                self.engine.click_velocity(),
+                self.options.keys.key(Action::KickUp),
+                self.options.keys.key(Action::KickDown),
+                self.options.keys.key(Action::ClickUp),
+                self.options.keys.key(Action::ClickDown)
+            );
+        }
+        if let Some(ratio) = self.engine.session.click_ratio {
+            println!(
+                "  Click Ratio: {} (click at {:.0} BPM against the pattern's {})",
+                ratio,
+                ratio.click_tempo(self.engine.session.tempo_bpm),
+                self.engine.session.tempo_bpm
+            );
+        }
+        if let Some(hemiola) = self.engine.session.hemiola {
+            println!("  Hemiola: {} (hear the kicks against both)", hemiola);
+        }
+        if let Some(drift) = self.engine.session.click_drift {
+            println!(
+                "  Click Drift: {} (hold {} BPM; the kicks drop out after the first bar)",
+                drift, self.engine.session.tempo_bpm
+            );
+        }
+        if let Some(gap) = self.engine.session.click_gap {
+            println!(
+                "  Click Gap: {} bars{}",
+                gap,
+                if gap.mute_kicks {
+                    ", kicks silent too"
+                } else {
+                    ""
+                }
+            );
+        }
+        if let Some(dropout) = self.engine.session.kick_dropout {
+            println!("  Dropout: {} (keep the kicks going in your head)", dropout);
+        }
+        if let Some(fills) = self.engine.session.fills {
+            println!(
+                "  Fills: {} (hear how the pattern fills, then type the groove)",
+                fills
+            );
+        }
+        if let Some(ghosts) = self.engine.session.ghost_steps {
+            println!(
+                "  Ghost Steps: {} (type every kick you hear over the loops)",
+                ghosts
+            );
+        }
+        if let Some(fade) = self.engine.session.click_fade {
+            println!(
+                "  Click Fade: -{} velocity per answer at {}% or better",
+                fade.step, fade.threshold_percent
+            );
+        }
+        if let Some(bars) = self.engine.session.phrase_bars {
+            println!("  Phrase: cue every {} bars", bars);
+        }
+        match self.engine.session.sync_port.as_deref() {
+            Some("") => println!("  Sync: MIDI clock from the first input port (start it to play)"),
+            Some(port) => println!("  Sync: MIDI clock from '{}' (start it to play)", port),
+            None => {}
+        }
+        match self.engine.session.kit_port.as_deref() {
+            Some("") => println!("  Kit: graded from the first input port, limb by limb"),
+            Some(port) => println!("  Kit: graded from '{}', limb by limb", port),
+            None => {}
+        }
+        if self.engine.midi_mapping != MidiMapping::default() {
+            let mapping = self.engine.midi_mapping;
+            println!(
+                "  MIDI: kick note {}, click note {}, channel {}{}",
+                mapping.kick_note,
+                mapping.click_note,
+                mapping.channel + 1,
+                if mapping.click_channel == mapping.channel {
+                    String::new()
+                } else {
+                    format!(" (click on {})", mapping.click_channel + 1)
+                }
+            );
+        }
+        if let Some(curriculum) = &self.engine.session.curriculum {
+            println!(
+                "  Curriculum: stage {} of {}, {}",
+                curriculum.stage_number(),
+                curriculum.stages().len(),
+                curriculum.current().name
+            );
+        }
+        println!("  Complexity: {:?}", self.engine.session.complexity_level);
+        match &self.engine.session.current_pattern {
+            Some(pattern) => println!("  Time Signature: {}", pattern.meter_label()),
+            None => println!(
+                "  Time Signature: {}/{}",
+                self.engine.session.time_signature.numerator,
+                self.engine.session.time_signature.denominator
+            ),
+        }
 
         println!("\nCommands:");
-        println!("  [r] Reveal pattern    - Display the current rhythm as ASCII art");
-        println!("  [n] New pattern       - Generate and play a new rhythm");
-        println!("  [t] Tempo             - Change playback tempo");
-        println!("  [c] Complexity        - Change pattern complexity");
-        println!("  [q] Quit              - Stop playback and exit\n");
+        println!(
+            "  {} Reveal pattern    - Display the current rhythm as ASCII art",
+            self.options.keys.label(Action::Reveal)
+        );
+        println!(
+            "  {} Enter answer      - Type the rhythm you hear and check it",
+            self.options.keys.label(Action::Answer)
+        );
+        println!(
+            "  {} New pattern       - Generate and play a new rhythm",
+            self.options.keys.label(Action::New)
+        );
+        println!(
+            "  {} Back              - Return to the previous pattern",
+            self.options.keys.label(Action::Back)
+        );
+        println!(
+            "  {} Morph             - Turn this pattern into a new one, a step at a time",
+            self.options.keys.label(Action::Morph)
+        );
+        println!(
+            "  {} Vary              - Rotate, reverse, invert, or add/remove a kick",
+            self.options.keys.label(Action::Vary)
+        );
+        println!(
+            "  {} Tempo             - Change playback tempo",
+            self.options.keys.label(Action::Tempo)
+        );
+        println!(
+            "  {} Complexity        - Change pattern complexity",
+            self.options.keys.label(Action::Complexity)
+        );
+        println!(
+            "  {} History           - Browse and replay recent patterns",
+            self.options.keys.label(Action::History)
+        );
+        println!(
+            "  {} Note              - Note or #tag this pattern",
+            self.options.keys.label(Action::Note)
+        );
+        println!(
+            "  {} Medley            - Play this session's patterns back to back",
+            self.options.keys.label(Action::Medley)
+        );
+        println!(
+            "  {} Arrange           - Play patterns in a song form (e.g., AABA)",
+            self.options.keys.label(Action::Arrange)
+        );
+        println!(
+            "  {} Mute click        - Toggle the click track on/off",
+            self.options.keys.label(Action::Mute)
+        );
+        println!(
+            "  {} {} Click level   - Make the click louder/softer",
+            self.options.keys.label(Action::ClickUp),
+            self.options.keys.label(Action::ClickDown)
+        );
+        println!(
+            "  {} {} Kick level    - Make the kicks louder/softer",
+            self.options.keys.label(Action::KickUp),
+            self.options.keys.label(Action::KickDown)
+        );
+        println!(
+            "  {} Auto-advance      - Move on to a new pattern every few loops",
+            self.options.keys.label(Action::Advance)
+        );
+        println!(
+            "  {} Diagnostics       - Show live timing drift per loop",
+            self.options.keys.label(Action::Diagnostics)
+        );
+        println!(
+            "  {} Drum pad          - Tap along: f/z = kick, j/x = snare",
+            self.options.keys.label(Action::Pad)
+        );
+        println!(
+            "  {} Quit              - Stop playback and exit\n",
+            self.options.keys.label(Action::Quit)
+        );
 
         println!("Pattern is now playing with click track...");
         println!("Listen carefully and try to identify the rhythm.\n");
     }
 
     /// Check if the terminal supports interactive mode
-    fn check_terminal_capabilities() -> Result<(), String> {
+    pub(crate) fn check_terminal_capabilities() -> Result<(), String> {
         // Check if stdout is a TTY
         if !atty::is(atty::Stream::Stdout) {
-            return Err(
-                "Error: Standard output is not connected to a terminal.\n\
+            return Err("Error: Standard output is not connected to a terminal.\n\
                  This tool requires an interactive terminal to run.\n\
                  Please run directly in a terminal, not through pipes or redirects."
-                    .to_string(),
-            );
+                .to_string());
         }
 
         // Check if stdin is a TTY
         if !atty::is(atty::Stream::Stdin) {
-            return Err(
-                "Error: Standard input is not connected to a terminal.\n\
+            return Err("Error: Standard input is not connected to a terminal.\n\
                  This tool requires interactive input.\n\
                  Please run directly in a terminal."
-                    .to_string(),
-            );
+                .to_string());
         }
 
         // Try to enable raw mode as a capability test
@@ -93,8 +413,9 @@ impl CommandLoop {
         Ok(())
     }
 
-    /// Start the command loop with the current pattern
-    pub fn run(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    /// Start the command loop with the current pattern, quitting when the
+    /// user does or `running` is cleared (e.g., by Ctrl-C)
+    pub fn run(&mut self, running: &AtomicBool) -> Result<(), Box<dyn std::error::Error>> {
         // Check terminal capabilities before proceeding
         if let Err(e) = Self::check_terminal_capabilities() {
             return Err(e.into());
@@ -104,20 +425,25 @@ impl CommandLoop {
         self.print_welcome();
 
         // Ensure we have a pattern
-        if self.session.current_pattern.is_none() {
+        if self.engine.session.current_pattern.is_none() {
             return Err("No pattern available to play".into());
         }
 
-        // Start playback
-        let pattern = self.session.current_pattern.as_ref().unwrap().clone();
-        self.playback
-            .start(pattern, self.session.tempo_bpm, true)
-            .map_err(|e| format!("Failed to start playback: {}", e))?;
+        #[cfg(feature = "webhooks")]
+        if let Some(webhook) = &mut self.options.webhook {
+            webhook.notify(SessionEvent::Start, &self.engine.session);
+        }
+
+        // Start playback; a failure here ends the session with its own advice
+        self.start_new_pattern()?;
+        if let Some(Err(e)) = self.engine.connect_kit() {
+            println!("⚠  {}. Carrying on without the kit.\n", e);
+        }
 
         // Enable raw mode for single-key input
         enable_raw_mode()?;
 
-        let result = self.input_loop();
+        let result = self.input_loop(running);
 
         // Always disable raw mode on exit
         disable_raw_mode()?;
@@ -126,16 +452,47 @@ impl CommandLoop {
     }
 
     /// Main input loop
-    fn input_loop(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    fn input_loop(&mut self, running: &AtomicBool) -> Result<(), Box<dyn std::error::Error>> {
         loop {
+            if !running.load(Ordering::SeqCst) || self.interrupted {
+                self.handle_quit(true)?;
+                break;
+            }
+            self.update_countdown()?;
+            self.update_diagnostics()?;
+            self.update_drill()?;
+            self.update_build()?;
+            self.update_engine()?;
+            self.update_position()?;
+
             // Poll for key events with timeout
-            if event::poll(Duration::from_millis(100))? {
-                if let Event::Key(key_event) = event::read()? {
-                    // Handle the key
-                    let should_quit = self.handle_key(key_event)?;
-                    if should_quit {
-                        break;
+            let poll = if self.position_shown.is_some_and(|p| p.step.is_some()) {
+                CURSOR_POLL
+            } else {
+                IDLE_POLL
+            };
+            if event::poll(poll)? {
+                match event::read()? {
+                    Event::Key(key_event) => {
+                        // Commands print below a clean line
+                        if self.position_shown.take().is_some() {
+                            self.draw_status("")?;
+                        }
+
+                        // Raw mode turns Ctrl-C into a key rather than a signal
+                        if is_interrupt(&key_event) {
+                            running.store(false, Ordering::SeqCst);
+                            continue;
+                        }
+
+                        // Handle the key
+                        let should_quit = self.handle_key(key_event)?;
+                        if should_quit {
+                            break;
+                        }
                     }
+                    Event::Resize(width, _) => self.handle_resize(width)?,
+                    _ => {}
                 }
             }
         }
@@ -145,53 +502,107 @@ impl CommandLoop {
 
     /// Handle a key press
     fn handle_key(&mut self, key: KeyEvent) -> Result<bool, Box<dyn std::error::Error>> {
-        match key.code {
-            KeyCode::Char('r') | KeyCode::Char('R') => {
-                self.handle_reveal()?;
-                Ok(false)
-            }
-            KeyCode::Char('n') | KeyCode::Char('N') => {
-                self.handle_new_pattern()?;
-                Ok(false)
-            }
-            KeyCode::Char('t') | KeyCode::Char('T') => {
-                self.handle_tempo_change()?;
-                Ok(false)
-            }
-            KeyCode::Char('c') | KeyCode::Char('C') => {
-                self.handle_complexity_change()?;
-                Ok(false)
+        let KeyCode::Char(c) = key.code else {
+            // Ignore other keys
+            return Ok(false);
+        };
+        if self.pad.is_some() && PAD_KEYS.contains(&c) {
+            self.handle_pad_hit(if matches!(c, 'f' | 'z') {
+                Pad::Kick
+            } else {
+                Pad::Snare
+            })?;
+            return Ok(false);
+        }
+
+        match self.options.keys.action(c) {
+            Some(Action::Reveal) => self.handle_reveal()?,
+            Some(Action::Answer) => self.handle_answer()?,
+            Some(Action::New) => self.handle_new_pattern()?,
+            Some(Action::Back) => self.handle_back()?,
+            Some(Action::Morph) => self.handle_morph()?,
+            Some(Action::Vary) => self.handle_vary()?,
+            Some(Action::Tempo) => self.handle_tempo_change()?,
+            Some(Action::Complexity) => self.handle_complexity_change()?,
+            Some(Action::History) => self.handle_history()?,
+            Some(Action::Note) => self.handle_note()?,
+            Some(Action::Medley) => self.handle_medley()?,
+            Some(Action::Arrange) => self.handle_arrange()?,
+            Some(Action::Mute) => self.handle_click_toggle()?,
+            Some(action @ (Action::ClickUp | Action::ClickDown)) => {
+                self.handle_level(false, action == Action::ClickUp)?
             }
-            KeyCode::Char('q') | KeyCode::Char('Q') => {
-                self.handle_quit()?;
-                Ok(true)
+            Some(action @ (Action::KickUp | Action::KickDown)) => {
+                self.handle_level(true, action == Action::KickUp)?
             }
-            _ => {
-                // Ignore other keys
-                Ok(false)
+            Some(Action::Pad) => self.handle_pad_toggle()?,
+            Some(Action::Advance) => self.handle_advance_toggle()?,
+            Some(Action::Diagnostics) => self.handle_diagnostics_toggle()?,
+            Some(Action::Quit) => {
+                self.handle_quit(false)?;
+                return Ok(true);
             }
+            None => {}
         }
+        Ok(false)
     }
 
     /// Handle reveal command ('r')
     fn handle_reveal(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.engine.sync_tempo_from_playback();
+
         // Temporarily disable raw mode to print output
         disable_raw_mode()?;
 
-        if let Some(pattern) = &self.session.current_pattern {
+        if let Some(pattern) = &self.engine.session.current_pattern {
             println!("\n═══════════════════════════════════════════════════════════");
             println!("                     PATTERN REVEALED");
             println!("═══════════════════════════════════════════════════════════\n");
 
-            let formatted = format_pattern_with_metadata(pattern, self.session.tempo_bpm);
+            // The build-up style animates along with playback instead of printing
+            let animate = self
+                .engine
+                .session
+                .reveal_styles
+                .contains(&RevealStyle::Build)
+                && self.engine.playback.is_playing();
+            let styles: Vec<RevealStyle> = self
+                .engine
+                .session
+                .reveal_styles
+                .iter()
+                .copied()
+                .filter(|&style| !(animate && style == RevealStyle::Build))
+                .collect();
+
+            let formatted = self.options.palette.reveal(&format_reveal_to_width(
+                pattern,
+                self.engine.session.tempo_bpm,
+                &styles,
+                usize::from(self.term_width),
+            ));
             println!("{}", formatted);
 
             println!("═══════════════════════════════════════════════════════════\n");
 
-            self.session.pattern_revealed = true;
-            self.session.update_activity();
+            if let Some(timings) = self.engine.kit_timings().filter(|t| !t.is_empty()) {
+                println!("{}", format_kit_timings(pattern, timings));
+            }
+
+            if animate {
+                println!("🔎 Building up from the next loop: one more beat of kicks each time.\n");
+                print!("{}", self.options.palette.reveal(&count_row(pattern)));
+                self.engine.playback.build_up();
+                self.build_shown = Some(0);
+            } else {
+                println!(
+                    "Pattern will continue playing. Press {} to quit.\n",
+                    self.options.keys.label(Action::Quit)
+                );
+            }
 
-            println!("Pattern will continue playing. Press [q] to quit.\n");
+            self.engine.session.mark_revealed();
+            self.engine.session.update_activity();
         } else {
             println!("\nNo pattern available to reveal.\n");
         }
@@ -202,189 +613,1088 @@ impl CommandLoop {
         Ok(())
     }
 
-    /// Handle new pattern command ('n')
-    fn handle_new_pattern(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        // Stop current playback
-        self.playback.stop();
-
-        // Temporarily disable raw mode for output
+    /// Handle answer entry command ('e')
+    fn handle_answer(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // Disable raw mode for line input; playback keeps running while typing
         disable_raw_mode()?;
 
-        println!("\n⏹  Generating new pattern...");
+        let Some(pattern) = &self.engine.session.current_pattern else {
+            println!("\nNo pattern available to answer.\n");
+            enable_raw_mode()?;
+            return Ok(());
+        };
 
-        // Generate new unique pattern
-        let result = self.generator.generate_unique(
-            self.session.time_signature,
-            self.session.complexity_level,
-            &self.session.pattern_history,
+        println!("\n✎  Answer Entry");
+        let resolution = self.engine.session.answer_resolution;
+        println!(
+            "Type the {} positions you hear: 'x' = kick, '.' = rest (spaces and '|' are ignored)",
+            resolution.answer_len(pattern)
         );
+        if resolution == AnswerResolution::Eighth {
+            println!(
+                "  One position per eighth note; kicks on the 'e' or 'a' go on the eighth before"
+            );
+        }
+        if self.engine.session.answer_format == AnswerFormat::Ioi {
+            println!(
+                "  or the {}s between kicks (e.g. 3-3-2-4-4, with (n) first for a leading rest)",
+                resolution.name()
+            );
+        }
+        print!("Answer (or press Enter to cancel): ");
+        io::stdout().flush()?;
 
-        match result {
-            Ok((pattern, constraint_used)) => {
-                // Increment counter
-                self.session.patterns_generated += 1;
-
-                // Add to history
-                self.session.add_to_history(pattern.clone());
-
-                // Set as current pattern
-                self.session.current_pattern = Some(pattern.clone());
-
-                // Reset revealed flag
-                self.session.pattern_revealed = false;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        let input = input.trim();
 
-                // Update activity
-                self.session.update_activity();
+        if input.is_empty() {
+            println!("✗ Answer cancelled.\n");
+            enable_raw_mode()?;
+            return Ok(());
+        }
 
-                // Display pattern number
+        match grade_answer_at(
+            pattern,
+            input,
+            self.engine.session.answer_format,
+            resolution,
+        ) {
+            Ok(grade) => {
                 println!(
-                    "✓ Pattern #{} generated this session",
-                    self.session.patterns_generated
+                    "\n{}",
+                    self.options
+                        .palette
+                        .reveal(&format_answer_diff(&resolution.truth(pattern), &grade))
                 );
-
-                // Warn if uniqueness constraint was relaxed
-                if constraint_used < 3 {
-                    println!("⚠  Could not generate sufficiently unique pattern after 10 attempts");
+                if let Some(note) = resolution.ambiguity_note(pattern) {
+                    println!("{}", note);
+                }
+                if grade.is_perfect() {
                     println!(
-                        "   (Relaxed uniqueness constraint to distance >= {})",
-                        constraint_used
+                        "✓ Perfect! Press {} for a new pattern.\n",
+                        self.options.keys.label(Action::New)
+                    );
+                } else {
+                    println!(
+                        "Press {} to try again or {} for a new pattern.\n",
+                        self.options.keys.label(Action::Answer),
+                        self.options.keys.label(Action::New)
                     );
                 }
 
-                // Re-enable raw mode
-                enable_raw_mode()?;
-
-                // Start playback with new pattern
-                self.playback
-                    .start(pattern, self.session.tempo_bpm, true)
-                    .map_err(|e| format!("Failed to start playback: {}", e))?;
-
-                println!("\n▶  New pattern is now playing. Press [r] to reveal.\n");
+                self.record_result(grade.accuracy);
             }
             Err(e) => {
-                println!("✗ Failed to generate new pattern: {}", e);
-                println!("  Current pattern will continue playing.\n");
-
-                // Re-enable raw mode
-                enable_raw_mode()?;
-
-                // Restart playback with current pattern if it exists
-                if let Some(pattern) = &self.session.current_pattern {
-                    self.playback
-                        .start(pattern.clone(), self.session.tempo_bpm, true)
-                        .map_err(|e| format!("Failed to restart playback: {}", e))?;
-                }
+                println!("✗ {}", e);
+                println!(
+                    "  Press {} to try again.\n",
+                    self.options.keys.label(Action::Answer)
+                );
             }
         }
 
+        enable_raw_mode()?;
+
         Ok(())
     }
 
-    /// Handle tempo change command ('t')
-    fn handle_tempo_change(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        // Stop current playback
-        self.playback.stop();
-
-        // Disable raw mode for input
-        disable_raw_mode()?;
-
-        println!("\n🎵 Tempo Change");
-        println!("Current tempo: {} BPM", self.session.tempo_bpm);
-        print!("Enter new tempo (40-300 BPM, or press Enter to cancel): ");
-        io::stdout().flush()?;
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-
-        let input = input.trim();
-
-        // Empty input cancels
-        if input.is_empty() {
-            println!("✗ Tempo change cancelled.\n");
-            enable_raw_mode()?;
+    /// Record an answer's accuracy for the current pattern and reveal its source
+    fn record_result(&mut self, accuracy: f32) {
+        if let Some(source) = self.engine.pattern_source() {
+            println!("🎲 This pattern came from: {}\n", source);
+        }
+        self.drill_pending = false;
 
-            // Restart playback with current tempo
-            if let Some(pattern) = &self.session.current_pattern {
-                self.playback
-                    .start(pattern.clone(), self.session.tempo_bpm, true)
-                    .map_err(|e| format!("Failed to restart playback: {}", e))?;
+        let outcome = self.engine.record_result(accuracy);
+        if outcome.goal_complete {
+            print_goal_complete(&self.engine.session);
+        } else if let Some(goal) = self
+            .engine
+            .session
+            .goal
+            .as_ref()
+            .filter(|goal| !goal.is_met())
+        {
+            println!("🎯 Goal: {}\n", goal.summary());
+        }
+        if let Some(stage) = outcome.next_stage {
+            println!(
+                "🎓 Curriculum stage passed! Next: {} ({:?}, {} BPM). Press {} for a pattern.\n",
+                stage.name,
+                stage.complexity,
+                self.engine.session.tempo_bpm,
+                self.options.keys.label(Action::New)
+            );
+        }
+        match outcome.click_velocity {
+            Some((old, new)) if new < old => {
+                println!("🔉 Click quieter: velocity {} → {}\n", old, new)
+            }
+            Some((old, new)) if new > old => {
+                println!("🔊 Click louder again: velocity {} → {}\n", old, new)
             }
+            _ => {}
+        }
+    }
 
+    /// Open the drill's answer window once its listening loops have played
+    fn update_drill(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(drill) = self.engine.session.drill else {
+            return Ok(());
+        };
+        if !self.drill_pending || self.engine.playback.is_playing() {
             return Ok(());
         }
 
-        // Parse and validate tempo
-        match input.parse::<u16>() {
-            Ok(tempo) if tempo >= 40 && tempo <= 300 => {
-                // Update session tempo
-                self.session.tempo_bpm = tempo;
-                self.session.update_activity();
+        self.drill_pending = false;
+        self.handle_timed_answer(drill.answer_secs)
+    }
 
-                println!("✓ Tempo changed to {} BPM", tempo);
-                println!("  Playback speed will update immediately.\n");
+    /// Collect an answer against a countdown; running out of time marks the
+    /// pattern incorrect
+    fn handle_timed_answer(&mut self, answer_secs: u32) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(pattern) = self.engine.session.current_pattern.clone() else {
+            return Ok(());
+        };
 
-                // Re-enable raw mode
-                enable_raw_mode()?;
+        disable_raw_mode()?;
+        println!(
+            "\n⏱  Listening time is over. Type the {} positions{} within {}s and press Enter.",
+            self.engine.session.answer_resolution.answer_len(&pattern),
+            if self.engine.session.answer_format == AnswerFormat::Ioi {
+                " (or intervals)"
+            } else {
+                ""
+            },
+            answer_secs
+        );
+        enable_raw_mode()?;
+
+        // Read keys in raw mode so the countdown keeps ticking while typing
+        let deadline = Instant::now() + Duration::from_secs(u64::from(answer_secs));
+        let mut input = String::new();
+        let submitted = loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break false;
+            }
+            self.draw_status(&format!(
+                "⏳ {:>3}s  Answer: {}",
+                remaining.as_secs_f64().ceil() as u64,
+                input
+            ))?;
 
-                // Restart playback with new tempo
-                if let Some(pattern) = &self.session.current_pattern {
-                    self.playback
-                        .start(pattern.clone(), self.session.tempo_bpm, true)
-                        .map_err(|e| format!("Failed to restart playback: {}", e))?;
+            if event::poll(Duration::from_millis(100))? {
+                match event::read()? {
+                    // Raw mode turns Ctrl-C into a key rather than a signal
+                    Event::Key(key) if is_interrupt(&key) => {
+                        self.interrupted = true;
+                        return Ok(());
+                    }
+                    Event::Key(key) => match key.code {
+                        KeyCode::Enter => break true,
+                        KeyCode::Backspace => {
+                            input.pop();
+                        }
+                        KeyCode::Char(c) => input.push(c),
+                        _ => {}
+                    },
+                    Event::Resize(width, _) => self.handle_resize(width)?,
+                    _ => {}
                 }
             }
-            Ok(tempo) => {
-                println!("✗ Tempo {} is out of range (40-300 BPM)", tempo);
-                println!(
-                    "  Keeping current tempo of {} BPM\n",
-                    self.session.tempo_bpm
-                );
+        };
 
-                enable_raw_mode()?;
+        disable_raw_mode()?;
+        println!();
+
+        let graded = if submitted {
+            let resolution = self.engine.session.answer_resolution;
+            grade_answer_at(
+                &pattern,
+                input.trim(),
+                self.engine.session.answer_format,
+                resolution,
+            )
+            .map_err(String::from)
+        } else {
+            Err("Time's up".to_string())
+        };
 
-                // Restart playback with current tempo
-                if let Some(pattern) = &self.session.current_pattern {
-                    self.playback
-                        .start(pattern.clone(), self.session.tempo_bpm, true)
-                        .map_err(|e| format!("Failed to restart playback: {}", e))?;
+        match graded {
+            Ok(grade) => {
+                let resolution = self.engine.session.answer_resolution;
+                println!(
+                    "\n{}",
+                    self.options
+                        .palette
+                        .reveal(&format_answer_diff(&resolution.truth(&pattern), &grade))
+                );
+                if let Some(note) = resolution.ambiguity_note(&pattern) {
+                    println!("{}", note);
                 }
+                self.record_result(grade.accuracy);
             }
-            Err(_) => {
-                println!("✗ Invalid input '{}'. Please enter a number.", input);
+            Err(e) => {
+                println!("✗ {} — marked incorrect.\n", e);
                 println!(
-                    "  Keeping current tempo of {} BPM\n",
-                    self.session.tempo_bpm
+                    "{}",
+                    self.options.palette.reveal(&format_reveal_to_width(
+                        &pattern,
+                        self.engine.session.tempo_bpm,
+                        &self.engine.session.reveal_styles,
+                        usize::from(self.term_width)
+                    ))
                 );
-
-                enable_raw_mode()?;
-
-                // Restart playback with current tempo
-                if let Some(pattern) = &self.session.current_pattern {
-                    self.playback
-                        .start(pattern.clone(), self.session.tempo_bpm, true)
-                        .map_err(|e| format!("Failed to restart playback: {}", e))?;
-                }
+                self.record_result(0.0);
             }
         }
+        println!(
+            "Press {} for the next pattern.\n",
+            self.options.keys.label(Action::New)
+        );
+
+        enable_raw_mode()?;
 
         Ok(())
     }
 
-    /// Handle complexity change command ('c')
-    fn handle_complexity_change(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        // Disable raw mode for output
+    /// Handle new pattern command ('n')
+    fn handle_new_pattern(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // Temporarily disable raw mode for output
         disable_raw_mode()?;
 
-        println!("\n🎛  Complexity Change");
-        println!("Current complexity: {:?}", self.session.complexity_level);
-        println!("\nSelect new complexity:");
-        println!("  [1] Simple   - 2-4 kicks, mostly on-beats");
-        println!("  [2] Medium   - 4-6 kicks, balanced");
-        println!("  [3] Complex  - 6-8 kicks, high syncopation");
-        println!("\nPress 1, 2, 3, or any other key to cancel");
+        println!("\n⏹  Generating new pattern...");
 
-        // Re-enable raw mode for single-key input
-        enable_raw_mode()?;
+        match self.engine.next_pattern() {
+            Ok(next) => {
+                println!(
+                    "✓ Pattern #{} generated this session",
+                    self.engine.session.patterns_generated
+                );
+                if next.review {
+                    println!("🔁 Review: you missed this pattern before. Let's see if it sticks.");
+                }
+
+                if let Some(distance) = next.relaxed {
+                    println!("⚠  Could not generate sufficiently unique pattern after 10 attempts");
+                    println!(
+                        "   (Relaxed uniqueness constraint to distance >= {})",
+                        distance
+                    );
+                }
+
+                // Re-enable raw mode
+                enable_raw_mode()?;
+
+                // Start playback with new pattern
+                self.start_new_pattern()
+                    .map_err(|e| format!("Failed to start playback: {}", e))?;
+
+                println!(
+                    "\n▶  New pattern is now playing. Press {} to reveal.\n",
+                    self.options.keys.label(Action::Reveal)
+                );
+            }
+            Err(e) => {
+                println!("✗ Failed to generate new pattern: {}", e);
+                println!("  Current pattern will continue playing.\n");
+
+                // Re-enable raw mode
+                enable_raw_mode()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle morph command: play the current pattern turning into a new one
+    fn handle_morph(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        disable_raw_mode()?;
+        let steps = match self.engine.morph() {
+            Ok(steps) => steps,
+            Err(e) => {
+                println!(
+                    "\n✗ Can't morph: {}\n  Current pattern will continue playing.\n",
+                    e
+                );
+                enable_raw_mode()?;
+                return Ok(());
+            }
+        };
+
+        let bars = self.engine.session.morph_bars;
+        println!(
+            "\n🔀 Morphing into pattern #{}: {} steps change, one every {} loop{}.",
+            self.engine.session.patterns_generated,
+            steps,
+            bars,
+            if bars == 1 { "" } else { "s" }
+        );
+        println!(
+            "   Press {} to reveal where it ends up.\n",
+            self.options.keys.label(Action::Reveal)
+        );
+        enable_raw_mode()?;
+
+        self.countdown_shown = None;
+        self.build_shown = None;
+        self.drill_pending = self.engine.session.drill.is_some();
+        Ok(())
+    }
+
+    /// Report what the engine picked up since the last tick
+    ///
+    /// Kit hits show up in the status line's scores, so only auto-advance
+    /// and the session plan need saying.
+    fn update_engine(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        for notice in self.engine.poll() {
+            match notice {
+                Notice::KitHit { .. } => {}
+                Notice::Advanced { revealed, next } => self.report_advance(revealed, next)?,
+                Notice::AdvanceFailed(e) => {
+                    if self.position_shown.take().is_some() {
+                        self.draw_status("")?;
+                    }
+                    disable_raw_mode()?;
+                    println!("\n✗ Auto-advance couldn't move on: {}\n", e);
+                    enable_raw_mode()?;
+                }
+                Notice::PlanSegment { index, next } => self.report_plan_segment(index, next)?,
+                Notice::PlanSegmentFailed(e) => {
+                    if self.position_shown.take().is_some() {
+                        self.draw_status("")?;
+                    }
+                    disable_raw_mode()?;
+                    println!("\n✗ The plan's next segment couldn't start: {}\n", e);
+                    enable_raw_mode()?;
+                }
+                Notice::PlanFinished => self.report_plan_finished()?,
+                Notice::MedleyPart {
+                    number,
+                    index,
+                    count,
+                } => {
+                    if self.position_shown.take().is_some() {
+                        self.draw_status("")?;
+                    }
+                    disable_raw_mode()?;
+                    println!("{}", medley_label(number, index, count));
+                    enable_raw_mode()?;
+                }
+                Notice::MedleyFinished => self.report_medley_finished()?,
+                Notice::Section {
+                    label,
+                    loops,
+                    index,
+                    count,
+                } => {
+                    if self.position_shown.take().is_some() {
+                        self.draw_status("")?;
+                    }
+                    disable_raw_mode()?;
+                    println!("{}", section_label(label, loops, index, count));
+                    enable_raw_mode()?;
+                }
+                Notice::ArrangementFinished => self.report_arrangement_finished()?,
+                Notice::Class(event) => {
+                    if self.position_shown.take().is_some() {
+                        self.draw_status("")?;
+                    }
+                    disable_raw_mode()?;
+                    println!("{}", class_label(&event));
+                    enable_raw_mode()?;
+                }
+                Notice::ConfigReloaded(update) => {
+                    if self.position_shown.take().is_some() {
+                        self.draw_status("")?;
+                    }
+                    disable_raw_mode()?;
+                    println!("{}", settings_label(&update));
+                    enable_raw_mode()?;
+                }
+                Notice::ConfigReloadFailed(e) => {
+                    if self.position_shown.take().is_some() {
+                        self.draw_status("")?;
+                    }
+                    disable_raw_mode()?;
+                    println!("✗ Config not reloaded: {}", e);
+                    enable_raw_mode()?;
+                }
+                Notice::OutputLost(e) => {
+                    if self.position_shown.take().is_some() {
+                        self.draw_status("")?;
+                    }
+                    disable_raw_mode()?;
+                    println!("\n⚠  {}\n   Paused until the MIDI port is back.\n", e);
+                    enable_raw_mode()?;
+                }
+                Notice::OutputRestored => {
+                    if self.position_shown.take().is_some() {
+                        self.draw_status("")?;
+                    }
+                    disable_raw_mode()?;
+                    println!("🔌 MIDI port back. Playing on.\n");
+                    enable_raw_mode()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Tell the player auto-advance has moved on, showing the last pattern if
+    /// it was revealed on the way out
+    fn report_advance(
+        &mut self,
+        revealed: Option<Pattern>,
+        next: NextPattern,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.position_shown.take().is_some() {
+            self.draw_status("")?;
+        }
+        self.countdown_shown = None;
+        self.build_shown = None;
+
+        disable_raw_mode()?;
+        if let Some(pattern) = revealed {
+            println!(
+                "\n⏭  Played {} times. That pattern was:\n\n{}",
+                self.engine.session.advance_loops,
+                self.options.palette.reveal(&format_reveal_to_width(
+                    &pattern,
+                    self.engine.session.tempo_bpm,
+                    &self.engine.session.reveal_styles,
+                    usize::from(self.term_width)
+                ))
+            );
+        }
+        println!(
+            "\n⏭  Pattern #{} is now playing. Press {} to reveal, {} to stay on one.\n",
+            self.engine.session.patterns_generated,
+            self.options.keys.label(Action::Reveal),
+            self.options.keys.label(Action::Advance)
+        );
+        if let Some(distance) = next.relaxed {
+            println!(
+                "   (Relaxed uniqueness constraint to distance >= {})\n",
+                distance
+            );
+        }
+        enable_raw_mode()?;
+        Ok(())
+    }
+
+    /// Announce the plan's next segment and the fresh pattern it started with
+    fn report_plan_segment(
+        &mut self,
+        index: usize,
+        next: NextPattern,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(plan) = &self.engine.session.plan else {
+            return Ok(());
+        };
+        let segment = &plan.plan.segments[index];
+        let line = format!(
+            "\n⏱  Plan: {} done. Next up, {} of {}: {} — {}",
+            plan.plan.segments[index - 1].label(index - 1),
+            index + 1,
+            plan.plan.segments.len(),
+            segment.label(index),
+            segment
+        );
+        if self.position_shown.take().is_some() {
+            self.draw_status("")?;
+        }
+        self.countdown_shown = None;
+        self.build_shown = None;
+        self.drill_pending = self.engine.session.drill.is_some();
+
+        disable_raw_mode()?;
+        println!("{}", line);
+        println!(
+            "\n▶  Pattern #{} is now playing. Press {} to reveal.\n",
+            self.engine.session.patterns_generated,
+            self.options.keys.label(Action::Reveal)
+        );
+        if let Some(distance) = next.relaxed {
+            println!(
+                "   (Relaxed uniqueness constraint to distance >= {})\n",
+                distance
+            );
+        }
+        enable_raw_mode()?;
+        Ok(())
+    }
+
+    /// Congratulate the player on finishing the plan, with how each segment went
+    fn report_plan_finished(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.position_shown.take().is_some() {
+            self.draw_status("")?;
+        }
+        disable_raw_mode()?;
+        println!("\n🏁 Plan complete!");
+        if let Some(plan) = &self.engine.session.plan {
+            print_plan_summary(plan);
+        }
+        println!(
+            "  Keep practicing, or press {} to finish.\n",
+            self.options.keys.label(Action::Quit)
+        );
+        enable_raw_mode()?;
+        Ok(())
+    }
+
+    /// Start the current pattern, preceded by the listen delay
+    fn start_new_pattern(&mut self) -> Result<(), KickbeatsError> {
+        self.countdown_shown = None;
+        self.build_shown = None;
+        self.drill_pending = self.engine.session.drill.is_some();
+        self.engine.start()
+    }
+
+    /// Print the next build-up frame as playback uncovers another beat
+    fn update_build(&mut self) -> io::Result<()> {
+        let Some(shown) = self.build_shown else {
+            return Ok(());
+        };
+        let beats = self.engine.playback.build_beats();
+        if beats == shown {
+            return Ok(());
+        }
+        let Some(pattern) = &self.engine.session.current_pattern else {
+            return Ok(());
+        };
+
+        disable_raw_mode()?;
+        self.draw_status("")?;
+        if beats > 0 {
+            print!(
+                "{}",
+                self.options.palette.reveal(&build_frame(pattern, beats))
+            );
+            self.build_shown = Some(beats);
+        } else {
+            if self.engine.playback.is_playing() {
+                println!(
+                    "\n▶  Full pattern again. Press {} to quit.\n",
+                    self.options.keys.label(Action::Quit)
+                );
+            }
+            self.build_shown = None;
+        }
+        io::stdout().flush()?;
+        enable_raw_mode()?;
+
+        Ok(())
+    }
+
+    /// Move the playback cursor along the revealed kick row, count bars, and
+    /// keep score of taps and kit hits
+    ///
+    /// Shown on the status line while a pattern loops, unless the countdown,
+    /// diagnostics, or a build-up is using it. The cursor needs the pattern
+    /// revealed and the row to fit the terminal; the bar counter needs
+    /// `--phrase`; the scores need the drum pad or `--kit-input`.
+    fn update_position(&mut self) -> io::Result<()> {
+        let Some(pattern) = &self.engine.session.current_pattern else {
+            return Ok(());
+        };
+        let active = !self.diagnostics
+            && !self.engine.is_medley_playing()
+            && !self.engine.is_arrangement_playing()
+            && self.build_shown.is_none()
+            && self.engine.playback.preroll_remaining().is_none();
+
+        let bar = match (
+            self.engine.session.phrase_bars,
+            self.engine.playback.current_loop(),
+        ) {
+            (Some(bars), Some(index)) if active => Some(phrase_position(index, bars)),
+            _ => None,
+        };
+        let score = self
+            .pad
+            .as_ref()
+            .map(|_| self.tap_score.counts())
+            .filter(|counts| active && counts.total() > 0);
+        let kit = self
+            .engine
+            .kit_scores()
+            .filter(|scores| active && scores.total() > 0);
+        let mut suffix = match bar {
+            Some((bar, phrase)) => format!(
+                "bar {}/{} · phrase {}",
+                bar,
+                self.engine.session.phrase_bars.unwrap_or_default(),
+                phrase
+            ),
+            None => String::new(),
+        };
+        if score.is_some() {
+            if !suffix.is_empty() {
+                suffix.push_str("   ");
+            }
+            suffix.push_str(&format!("🥁 {}", self.tap_score.summary()));
+        }
+        if let Some(kit) = &kit {
+            if !suffix.is_empty() {
+                suffix.push_str("   ");
+            }
+            suffix.push_str(&format!("🥁 {}", kit.summary()));
+        }
+        let morph = self.engine.playback.morph_stage().filter(|_| active);
+        if let Some((stage, stages)) = morph {
+            if !suffix.is_empty() {
+                suffix.push_str("   ");
+            }
+            suffix.push_str(&format!("🔀 morph {}/{}", stage, stages - 1));
+        }
+        let relaxed = self.engine.session.current_relaxed.filter(|_| active);
+        if let Some(distance) = relaxed {
+            if !suffix.is_empty() {
+                suffix.push_str("   ");
+            }
+            suffix.push_str(&relaxed_marker(distance));
+        }
+
+        let row = kick_row(pattern, pattern.steps.len());
+        let row = row.trim_end();
+        let fits = row.len() + 2 + suffix.chars().count() + 3 < usize::from(self.term_width);
+        let step = if self.engine.session.pattern_revealed && fits && active {
+            self.engine.playback.current_step()
+        } else {
+            None
+        };
+
+        let position = (step.is_some()
+            || bar.is_some()
+            || score.is_some()
+            || kit.is_some()
+            || morph.is_some()
+            || relaxed.is_some())
+        .then_some(Position {
+            step,
+            bar,
+            score,
+            kit,
+            morph,
+            relaxed,
+        });
+        if position == self.position_shown {
+            return Ok(());
+        }
+
+        match step {
+            Some(step) => {
+                // Highlight the step's symbol; the row is ASCII, so byte and column indices agree
+                let column = step_column(pattern, step);
+                let mut stdout = io::stdout();
+                write!(
+                    stdout,
+                    "\r▶ {}{}{}",
+                    self.options.palette.kicks(&row[..column]),
+                    row[column..=column].reverse(),
+                    self.options.palette.kicks(&row[column + 1..])
+                )?;
+                if !suffix.is_empty() {
+                    write!(stdout, "   {}", suffix)?;
+                }
+                queue!(stdout, Clear(ClearType::UntilNewLine))?;
+                stdout.flush()?;
+            }
+            None if !suffix.is_empty() => self.draw_status(&format!("▶ {}", suffix))?,
+            None => self.draw_status("")?,
+        }
+        self.position_shown = position;
+        Ok(())
+    }
+
+    /// Redraw the pre-roll countdown, clearing it once the count-in starts
+    fn update_countdown(&mut self) -> io::Result<()> {
+        let remaining = self
+            .engine
+            .playback
+            .preroll_remaining()
+            .map(|d| d.as_secs() + u64::from(d.subsec_nanos() > 0));
+
+        if remaining == self.countdown_shown {
+            return Ok(());
+        }
+
+        match remaining {
+            Some(secs) => self
+                .draw_painted_status(&format!("⏳ Count-in starts in {}s...", secs), |line| {
+                    self.options.palette.count_in(line)
+                })?,
+            None => self.draw_status("")?,
+        }
+        self.countdown_shown = remaining;
+        Ok(())
+    }
+
+    /// Replace the current terminal line with `text`, cut to the terminal width
+    fn draw_status(&self, text: &str) -> io::Result<()> {
+        self.draw_painted_status(text, str::to_string)
+    }
+
+    /// Like [`draw_status`](Self::draw_status), coloring the line once it's been cut to fit
+    fn draw_painted_status(
+        &self,
+        text: &str,
+        paint: impl FnOnce(&str) -> String,
+    ) -> io::Result<()> {
+        let max = usize::from(self.term_width).saturating_sub(1);
+        let line: String = text.chars().take(max).collect();
+
+        let mut stdout = io::stdout();
+        write!(stdout, "\r{}", paint(&line))?;
+        queue!(stdout, Clear(ClearType::UntilNewLine))?;
+        stdout.flush()
+    }
+
+    /// Re-lay out status lines for a new terminal width
+    fn handle_resize(&mut self, width: u16) -> io::Result<()> {
+        self.term_width = width;
+
+        // Clear whatever the old width left behind, then redraw on the next tick
+        let mut stdout = io::stdout();
+        queue!(stdout, Clear(ClearType::CurrentLine))?;
+        stdout.flush()?;
+        self.countdown_shown = None;
+        self.position_shown = None;
+        self.redraw_status = true;
+
+        Ok(())
+    }
+
+    /// Collect drift telemetry and redraw the sparkline when it is shown
+    fn update_diagnostics(&mut self) -> io::Result<()> {
+        // Always drain so samples don't pile up while the view is hidden
+        let samples = self.engine.playback.drain_drift();
+        if samples.is_empty() && !self.redraw_status {
+            return Ok(());
+        }
+        self.redraw_status = false;
+
+        for sample in samples {
+            if sample.late {
+                if self.position_shown.take().is_some() {
+                    self.draw_status("")?;
+                }
+                disable_raw_mode()?;
+                println!("{}", drift_label(sample.loop_index, sample.drift_ms));
+                enable_raw_mode()?;
+            }
+            if self.drift_history.len() == DRIFT_HISTORY {
+                self.drift_history.pop_front();
+            }
+            self.drift_history.push_back(sample.drift_ms);
+            self.last_jitter_ms = sample.jitter_ms;
+        }
+
+        if self.diagnostics && !self.drift_history.is_empty() {
+            // Show fewer loops on narrow terminals so the line never wraps
+            let bars = usize::from(self.term_width)
+                .saturating_sub(DRIFT_LINE_OVERHEAD)
+                .clamp(1, DRIFT_HISTORY);
+            let skip = self.drift_history.len().saturating_sub(bars);
+            let values: Vec<f64> = self.drift_history.iter().skip(skip).copied().collect();
+            let last = values.last().copied().unwrap_or(0.0);
+            let max = values.iter().copied().fold(0.0, f64::max);
+            self.draw_status(&format!(
+                "⏱  Drift {} last {:5.2}ms  max {:5.2}ms  jitter {:5.2}ms",
+                sparkline(&values, DRIFT_SCALE_MS),
+                last,
+                max,
+                self.last_jitter_ms
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    /// Handle diagnostics command ('d')
+    fn handle_diagnostics_toggle(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.diagnostics = !self.diagnostics;
+
+        disable_raw_mode()?;
+        if self.diagnostics {
+            println!(
+                "\n📈 Diagnostics on: scheduling drift per loop (full bar = {:.0}ms)",
+                DRIFT_SCALE_MS
+            );
+            println!("   and the latest any note went out in the last loop (jitter).");
+            println!("   Flat and low means kickbeats is on time; spikes that line up with");
+            println!("   other activity point to the OS; steady timing but audible lag points");
+            println!(
+                "   to your synth. Press {} again to hide.\n",
+                self.options.keys.label(Action::Diagnostics)
+            );
+        } else {
+            println!("\n📈 Diagnostics off.\n");
+        }
+        enable_raw_mode()?;
+
+        Ok(())
+    }
+
+    /// Handle auto-advance command ('a')
+    fn handle_advance_toggle(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        disable_raw_mode()?;
+        let enabled = !self.engine.session.auto_advance;
+        match self.engine.set_auto_advance(enabled) {
+            Err(e) => println!("\n✗ {}.\n", e),
+            Ok(()) if enabled => println!(
+                "\n⏭  Auto-advance on: a new pattern every {} loops. Press {} to stay on one.\n",
+                self.engine.session.advance_loops,
+                self.options.keys.label(Action::Advance)
+            ),
+            Ok(()) => println!("\n⏸  Auto-advance off: this pattern keeps looping.\n"),
+        }
+        enable_raw_mode()?;
+
+        Ok(())
+    }
+
+    /// Handle drum pad command ('p')
+    fn handle_pad_toggle(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        disable_raw_mode()?;
+        if self.pad.take().is_some() {
+            println!("\n🥁 Drum pad off.\n");
+        } else {
+            match self.engine.playback.drum_pad() {
+                Ok(pad) => {
+                    self.pad = Some(pad);
+                    self.tap_score.clear();
+                    println!(
+                        "\n🥁 Drum pad on: f or z = kick, j or x = snare. Press {} to put it away.",
+                        self.options.keys.label(Action::Pad)
+                    );
+                    println!("   Kick taps are scored on the status line as you go.\n");
+                }
+                Err(e) => println!("\n✗ {}\n", e),
+            }
+        }
+        enable_raw_mode()?;
+
+        Ok(())
+    }
+
+    /// Play a pad hit straight away, alongside the scheduled loop
+    fn handle_pad_hit(&mut self, pad: Pad) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(drum_pad) = &mut self.pad else {
+            return Ok(());
+        };
+        if let Err(e) = drum_pad.hit(pad) {
+            self.pad = None;
+            disable_raw_mode()?;
+            println!("\n✗ {} — drum pad off.\n", e);
+            enable_raw_mode()?;
+            return Ok(());
+        }
+
+        // Score kick taps as they land; the status line picks up the new counts
+        let position = self.engine.playback.step_position();
+        if let (Pad::Kick, Some(pattern), Some((step, step_secs))) =
+            (pad, &self.engine.session.current_pattern, position)
+        {
+            if let Some((grade, _)) = grade_tap(pattern, step, step_secs) {
+                self.tap_score.push(grade);
+                self.engine.session.record_tap(grade);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle back command ('b')
+    fn handle_back(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let previous = self
+            .engine
+            .session
+            .current_pattern
+            .as_ref()
+            .and_then(|current| self.engine.session.pattern_history.previous(current.id))
+            .cloned();
+
+        disable_raw_mode()?;
+
+        let Some(entry) = previous else {
+            println!("\n✗ No earlier pattern in history.\n");
+            enable_raw_mode()?;
+            return Ok(());
+        };
+
+        // Restore the pattern along with whether it had been revealed
+        self.engine
+            .replay(entry.pattern)
+            .map_err(|e| format!("Failed to start playback: {}", e))?;
+        self.engine.session.pattern_revealed = entry.revealed;
+
+        println!("\n⏪ Back to pattern #{}", entry.number);
+        if entry.revealed {
+            println!(
+                "   (already revealed — press {} to see it again)\n",
+                self.options.keys.label(Action::Reveal)
+            );
+        } else {
+            println!(
+                "   Press {} to reveal.\n",
+                self.options.keys.label(Action::Reveal)
+            );
+        }
+
+        enable_raw_mode()?;
+
+        self.drill_pending = self.engine.session.drill.is_some();
+        self.build_shown = None;
+
+        Ok(())
+    }
+
+    /// Handle variation command ('v'): play a transformed copy of the
+    /// current pattern, shown straight away
+    fn handle_vary(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        disable_raw_mode()?;
+        print!(
+            "\n🔀 Variation (rotate <steps>, reverse, invert, add <step>, remove <step>), or press Enter to cancel: "
+        );
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        let input = input.trim();
+        if input.is_empty() {
+            println!("✗ Variation cancelled.\n");
+            enable_raw_mode()?;
+            return Ok(());
+        }
+
+        let varied = input
+            .parse::<Transform>()
+            .map_err(KickbeatsError::Config)
+            .and_then(|transform| self.engine.transform(transform));
+        match (varied, &self.engine.session.current_pattern) {
+            (Ok(()), Some(pattern)) => {
+                let styles: Vec<RevealStyle> = self
+                    .engine
+                    .session
+                    .reveal_styles
+                    .iter()
+                    .copied()
+                    .filter(|&style| style != RevealStyle::Build)
+                    .collect();
+                println!(
+                    "\n{}",
+                    self.options.palette.reveal(&format_reveal_to_width(
+                        pattern,
+                        self.engine.session.tempo_bpm,
+                        &styles,
+                        usize::from(self.term_width),
+                    ))
+                );
+                println!(
+                    "🔀 Playing the variation. Press {} to go back to the original.\n",
+                    self.options.keys.label(Action::Back)
+                );
+            }
+            (Ok(()), None) => {}
+            (Err(e), _) => println!("✗ Can't play that variation: {}\n", e),
+        }
+        enable_raw_mode()?;
+
+        self.drill_pending = false;
+        self.build_shown = None;
+        Ok(())
+    }
+
+    /// Handle tempo change command ('t')
+    fn handle_tempo_change(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.engine.sync_tempo_from_playback();
+
+        // Disable raw mode for input; playback keeps looping while typing
+        disable_raw_mode()?;
+
+        println!("\n🎵 Tempo Change");
+        println!("Current tempo: {} BPM", self.engine.session.tempo_bpm);
+        print!("Enter new tempo (40-300 BPM, or press Enter to cancel): ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        let input = input.trim();
+
+        // Empty input cancels
+        if input.is_empty() {
+            println!("✗ Tempo change cancelled.\n");
+            enable_raw_mode()?;
+            return Ok(());
+        }
+
+        // Parse and validate tempo
+        match input.parse::<u16>() {
+            Ok(tempo) if (40..=300).contains(&tempo) => {
+                // Applies to the running loop at its next loop
+                self.engine.set_tempo(tempo)?;
+
+                println!("✓ Tempo changed to {} BPM", tempo);
+                println!("  Playback speed will update at the start of the next loop.\n");
+
+                // Re-enable raw mode
+                enable_raw_mode()?;
+
+                // Restart if playback had stopped
+                if !self.engine.is_playing() {
+                    if let Some(pattern) = self.engine.session.current_pattern.clone() {
+                        self.engine
+                            .replay(pattern)
+                            .map_err(|e| format!("Failed to restart playback: {}", e))?;
+                    }
+                }
+            }
+            Ok(tempo) => {
+                println!("✗ Tempo {} is out of range (40-300 BPM)", tempo);
+                println!(
+                    "  Keeping current tempo of {} BPM\n",
+                    self.engine.session.tempo_bpm
+                );
+
+                enable_raw_mode()?;
+            }
+            Err(_) => {
+                println!("✗ Invalid input '{}'. Please enter a number.", input);
+                println!(
+                    "  Keeping current tempo of {} BPM\n",
+                    self.engine.session.tempo_bpm
+                );
+
+                enable_raw_mode()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle complexity change command ('c')
+    fn handle_complexity_change(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // Disable raw mode for output
+        disable_raw_mode()?;
+
+        println!("\n🎛  Complexity Change");
+        println!(
+            "Current complexity: {:?}",
+            self.engine.session.complexity_level
+        );
+        println!("\nSelect new complexity:");
+        println!("  [1] Simple   - 2-4 kicks, mostly on-beats");
+        println!("  [2] Medium   - 4-6 kicks, balanced");
+        println!("  [3] Complex  - 6-8 kicks, high syncopation");
+        println!("\nPress 1, 2, 3, or any other key to cancel");
+
+        // Re-enable raw mode for single-key input
+        enable_raw_mode()?;
 
         // Wait for single key press with timeout (10 seconds)
         let mut attempts = 0;
@@ -393,53 +1703,14 @@ impl CommandLoop {
         loop {
             if event::poll(Duration::from_secs(10))? {
                 if let Event::Key(key_event) = event::read()? {
-                    match key_event.code {
-                        KeyCode::Char('1') => {
-                            self.session.complexity_level = ComplexityLevel::Simple;
-                            self.session.update_activity();
-
-                            disable_raw_mode()?;
-                            println!("\n✓ Complexity changed to Simple");
-                            println!("  New patterns will have 2-4 kicks, mostly on-beats");
-                            println!(
-                                "  Press [n] to generate a new pattern with this complexity.\n"
-                            );
-                            enable_raw_mode()?;
-                            break;
-                        }
-                        KeyCode::Char('2') => {
-                            self.session.complexity_level = ComplexityLevel::Medium;
-                            self.session.update_activity();
-
-                            disable_raw_mode()?;
-                            println!("\n✓ Complexity changed to Medium");
-                            println!("  New patterns will have 4-6 kicks with balanced rhythm");
-                            println!(
-                                "  Press [n] to generate a new pattern with this complexity.\n"
-                            );
-                            enable_raw_mode()?;
-                            break;
-                        }
-                        KeyCode::Char('3') => {
-                            self.session.complexity_level = ComplexityLevel::Complex;
-                            self.session.update_activity();
-
-                            disable_raw_mode()?;
-                            println!("\n✓ Complexity changed to Complex");
-                            println!("  New patterns will have 6-8 kicks with high syncopation");
-                            println!(
-                                "  Press [n] to generate a new pattern with this complexity.\n"
-                            );
-                            enable_raw_mode()?;
-                            break;
-                        }
-                        _ => {
-                            disable_raw_mode()?;
-                            println!("\n✗ Complexity change cancelled.\n");
-                            enable_raw_mode()?;
-                            break;
-                        }
+                    if is_interrupt(&key_event) {
+                        self.interrupted = true;
+                        break;
                     }
+                    disable_raw_mode()?;
+                    self.choose_complexity(key_event.code);
+                    enable_raw_mode()?;
+                    break;
                 }
             } else {
                 // Timeout - check attempts
@@ -459,32 +1730,401 @@ impl CommandLoop {
         Ok(())
     }
 
-    /// Handle quit command ('q')
-    fn handle_quit(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        // Stop playback
-        self.playback.stop();
+    /// Apply the complexity picked with `code`, or cancel on any other key
+    fn choose_complexity(&mut self, code: KeyCode) {
+        let (level, description) = match code {
+            KeyCode::Char('1') => (ComplexityLevel::Simple, "2-4 kicks, mostly on-beats"),
+            KeyCode::Char('2') => (ComplexityLevel::Medium, "4-6 kicks with balanced rhythm"),
+            KeyCode::Char('3') => (ComplexityLevel::Complex, "6-8 kicks with high syncopation"),
+            _ => {
+                println!("\n✗ Complexity change cancelled.\n");
+                return;
+            }
+        };
+        self.engine.set_complexity(level);
+
+        println!("\n✓ Complexity changed to {:?}", level);
+        println!("  New patterns will have {}", description);
+        println!(
+            "  Press {} to generate a new pattern with this complexity.\n",
+            self.options.keys.label(Action::New)
+        );
+    }
+
+    /// Handle history browser command ('h')
+    fn handle_history(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        disable_raw_mode()?;
+
+        if self.engine.session.pattern_history.is_empty() {
+            println!("\nNo patterns in history yet.\n");
+            enable_raw_mode()?;
+            return Ok(());
+        }
+
+        let mut tag: Option<String> = None;
+        let mut input = String::new();
+        loop {
+            self.print_history(tag.as_deref());
+            print!("\nEnter a number to replay and reveal, #tag to filter (or press Enter to cancel): ");
+            io::stdout().flush()?;
+
+            input.clear();
+            io::stdin().read_line(&mut input)?;
+            match input.trim() {
+                "" => {
+                    println!("✗ History browser closed.\n");
+                    enable_raw_mode()?;
+                    return Ok(());
+                }
+                filter if filter.starts_with('#') => {
+                    tag = Some(filter.to_string()).filter(|tag| tag.len() > 1);
+                }
+                _ => break,
+            }
+        }
+        let input = input.trim();
+
+        let entry = input
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| self.engine.session.pattern_history.recent(i))
+            .cloned();
+
+        let Some(entry) = entry else {
+            println!(
+                "✗ Invalid selection '{}'. Choose 1-{}.\n",
+                input,
+                self.engine.session.pattern_history.len()
+            );
+            enable_raw_mode()?;
+            return Ok(());
+        };
+
+        // Replay the selected pattern and show it
+        self.engine
+            .replay(entry.pattern.clone())
+            .map_err(|e| format!("Failed to start playback: {}", e))?;
+        self.engine.session.mark_revealed();
+
+        println!(
+            "\n{}",
+            self.options.palette.reveal(&format_reveal_to_width(
+                &entry.pattern,
+                self.engine.session.tempo_bpm,
+                &self.engine.session.reveal_styles,
+                usize::from(self.term_width)
+            ))
+        );
+
+        println!(
+            "▶  Replaying pattern #{}. Press {} for a new pattern.\n",
+            entry.number,
+            self.options.keys.label(Action::New)
+        );
+
+        enable_raw_mode()?;
+
+        Ok(())
+    }
+
+    /// List the session's recent patterns, only those tagged `tag` if given
+    fn print_history(&self, tag: Option<&str>) {
+        let session = &self.engine.session;
+        match tag {
+            Some(tag) => println!("\n📜 Pattern History tagged {} (most recent first)", tag),
+            None => println!("\n📜 Pattern History (most recent first)"),
+        }
+        println!("   #  Pattern  Generated    Density  Kicks  Revealed  Note");
+        let now = SystemTime::now();
+        let current_id = session.current_pattern.as_ref().map(|p| p.id);
+        let mut shown = 0;
+        for (i, entry) in session.pattern_history.iter_recent().enumerate() {
+            let note = session.note_for(entry.number);
+            if tag.is_some_and(|tag| !note.is_some_and(|note| note.has_tag(tag))) {
+                continue;
+            }
+            shown += 1;
+            let age = now
+                .duration_since(entry.generated_at)
+                .unwrap_or_default()
+                .as_secs();
+            let marker = if Some(entry.pattern.id) == current_id {
+                "▶"
+            } else {
+                " "
+            };
+            println!(
+                "{} {:>2}  #{:<6}  {:>2}m {:02}s ago  {:>6.1}%  {:>5}  {:<8}  {}",
+                marker,
+                i + 1,
+                entry.number,
+                age / 60,
+                age % 60,
+                entry.pattern.density() * 100.0,
+                entry.pattern.note_positions().len(),
+                if entry.revealed { "yes" } else { "no" },
+                note.map(ToString::to_string).unwrap_or_default()
+            );
+        }
+        if let (Some(tag), 0) = (tag, shown) {
+            println!(
+                "   No patterns tagged {} yet (#tag them with the note command).",
+                tag
+            );
+        }
+    }
+
+    /// Handle note command ('#')
+    fn handle_note(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        disable_raw_mode()?;
+        let number = self.engine.session.current_number();
+        if let Some(note) = self.engine.session.note_for(number) {
+            println!("\n📝 Pattern #{} is noted: {}", number, note);
+        }
+        print!(
+            "\n📝 Note for pattern #{} (text and #tags, or press Enter to cancel): ",
+            number
+        );
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        let input = input.trim();
+        if input.is_empty() {
+            println!("✗ Note cancelled.\n");
+        } else {
+            match input.parse::<PatternNote>() {
+                Ok(note) => {
+                    if self.engine.session.annotate(note).is_some() {
+                        println!(
+                            "✓ Noted pattern #{}. Filter the history by tag with {}.\n",
+                            number,
+                            self.options.keys.label(Action::History)
+                        );
+                    }
+                }
+                Err(e) => println!("✗ {}\n", e),
+            }
+        }
+        enable_raw_mode()?;
+        Ok(())
+    }
+
+    /// Handle medley command ('y')
+    fn handle_medley(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.countdown_shown = None;
+        self.build_shown = None;
+        self.drill_pending = false;
+
+        disable_raw_mode()?;
+        match self.engine.start_medley() {
+            Ok(medley) => println!(
+                "\n🎞  Medley: the session's {} pattern{}, once each, a bar of click between them.\n   The current pattern picks up again after; press {} to move on instead.\n",
+                medley.len(),
+                if medley.len() == 1 { "" } else { "s" },
+                self.options.keys.label(Action::New)
+            ),
+            Err(e) => println!("\n✗ Can't play a medley: {}\n", e),
+        }
+        enable_raw_mode()?;
+
+        Ok(())
+    }
+
+    /// Pick the current pattern back up once the medley is over
+    fn report_medley_finished(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.position_shown.take().is_some() {
+            self.draw_status("")?;
+        }
+        self.start_new_pattern()
+            .map_err(|e| format!("Failed to start playback: {}", e))?;
+
+        disable_raw_mode()?;
+        println!("\n🎞  Medley over. Back to the pattern you were on.\n");
+        enable_raw_mode()?;
+        Ok(())
+    }
+
+    /// Handle arrange command ('g')
+    fn handle_arrange(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        disable_raw_mode()?;
+        print!(
+            "\n🎼 Song form and parts (e.g., A2 A2 B4 A2 A=3 B=#bridge), or press Enter to cancel: "
+        );
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        let input = input.trim();
+        if input.is_empty() {
+            println!("✗ Arrangement cancelled.\n");
+            enable_raw_mode()?;
+            return Ok(());
+        }
+
+        self.countdown_shown = None;
+        self.build_shown = None;
+        self.drill_pending = false;
+        let started = input
+            .parse::<ArrangementSpec>()
+            .map_err(KickbeatsError::Config)
+            .and_then(|spec| self.engine.start_arrangement(&spec).map(|a| a.form_label()));
+        match started {
+            Ok(form) => println!(
+                "\n🎼 Playing {} through once. The current pattern picks up again after; press {} to move on instead.\n",
+                form,
+                self.options.keys.label(Action::New)
+            ),
+            Err(e) => println!("✗ Can't play that arrangement: {}\n", e),
+        }
+        enable_raw_mode()?;
+        Ok(())
+    }
+
+    /// Pick the current pattern back up once the arrangement is over
+    fn report_arrangement_finished(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.position_shown.take().is_some() {
+            self.draw_status("")?;
+        }
+        self.start_new_pattern()
+            .map_err(|e| format!("Failed to start playback: {}", e))?;
+
+        disable_raw_mode()?;
+        println!("\n🎼 End of the form. Back to the pattern you were on.\n");
+        enable_raw_mode()?;
+        Ok(())
+    }
+
+    /// Handle click mute toggle command ('m')
+    fn handle_click_toggle(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.engine
+            .set_click_enabled(!self.engine.session.click_enabled);
+
+        disable_raw_mode()?;
+        if self.engine.session.click_enabled {
+            println!("\n🔔 Click track on.\n");
+        } else {
+            println!("\n🔕 Click track muted. Keep the pulse yourself!\n");
+        }
+        enable_raw_mode()?;
+
+        Ok(())
+    }
+
+    /// Handle the level commands ('+'/'-' for the click, ']'/'[' for the kicks)
+    fn handle_level(
+        &mut self,
+        kicks: bool,
+        louder: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (voice, velocity) = if kicks {
+            ("Kicks", self.engine.step_kick_velocity(louder))
+        } else {
+            ("Click", self.engine.step_click_velocity(louder))
+        };
+
+        disable_raw_mode()?;
+        match velocity {
+            0 => println!("\n🔇 {} silent (velocity 0).\n", voice),
+            _ if louder => println!("\n🔊 {} velocity {}.\n", voice, velocity),
+            _ => println!("\n🔉 {} velocity {}.\n", voice, velocity),
+        }
+        enable_raw_mode()?;
+
+        Ok(())
+    }
+
+    /// Offer to play the session's patterns back as a medley before exiting,
+    /// labelling each as it comes up
+    ///
+    /// Runs with raw mode off; Enter skips the rest of the medley.
+    fn offer_medley(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let count = self.engine.session.pattern_history.len();
+        if count < 2 {
+            return Ok(());
+        }
+        print!(
+            "\n🎞  Play this session's {} patterns back as a medley before you go? [y/N] ",
+            count
+        );
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if !input.trim().eq_ignore_ascii_case("y") {
+            return Ok(());
+        }
+
+        if let Err(e) = self.engine.start_medley() {
+            println!("✗ Can't play a medley: {}", e);
+            return Ok(());
+        }
+        println!("   Press Enter to skip the rest.\n");
+        // Raw mode so Ctrl-C skips the rest too, rather than going unheard
+        enable_raw_mode()?;
+        while self.engine.is_medley_playing() {
+            for notice in self.engine.poll() {
+                if let Notice::MedleyPart {
+                    number,
+                    index,
+                    count,
+                } = notice
+                {
+                    disable_raw_mode()?;
+                    println!("{}", medley_label(number, index, count));
+                    enable_raw_mode()?;
+                }
+            }
+            if event::poll(IDLE_POLL)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.code == KeyCode::Enter || is_interrupt(&key) {
+                        break;
+                    }
+                }
+            }
+        }
+        disable_raw_mode()?;
+        self.engine.stop();
+        Ok(())
+    }
+
+    /// Handle quit command ('q'), or Ctrl-C when `interrupted`
+    fn handle_quit(&mut self, interrupted: bool) -> Result<(), Box<dyn std::error::Error>> {
+        // Stop playback and release any pad notes
+        self.engine.stop();
+        self.pad = None;
 
         // Temporarily disable raw mode for output
         disable_raw_mode()?;
 
+        // An interrupted session goes straight to its summary
+        if !interrupted {
+            self.offer_medley()?;
+        }
+
         // Display session summary
-        println!("\n═══════════════════════════════════════════════════════════");
-        println!("                     SESSION SUMMARY");
-        println!("═══════════════════════════════════════════════════════════\n");
+        print_session_summary(&self.engine.session);
 
-        println!("Session ID: {}", self.session.session_id);
-        println!("Patterns generated: {}", self.session.patterns_generated);
-        println!("Final tempo: {} BPM", self.session.tempo_bpm);
-        println!("Final complexity: {:?}", self.session.complexity_level);
+        match self.engine.save_midi_log() {
+            Some(Ok(path)) => println!("🎼 Session MIDI saved to {}", path.display()),
+            Some(Err(e)) => println!("⚠  {}", e),
+            None => {}
+        }
+        match self.engine.save_replay() {
+            Some(Ok(path)) => println!("🎞  Session replay saved to {}", path.display()),
+            Some(Err(e)) => println!("⚠  {}", e),
+            None => {}
+        }
+        if let Some(Err(e)) = self.engine.save_session() {
+            println!("⚠  {}", e);
+        }
 
-        if let Ok(duration) = self
-            .session
-            .last_activity
-            .duration_since(self.session.session_start)
-        {
-            let minutes = duration.as_secs() / 60;
-            let seconds = duration.as_secs() % 60;
-            println!("Practice duration: {}m {}s", minutes, seconds);
+        #[cfg(feature = "webhooks")]
+        if let Some(webhook) = &mut self.options.webhook {
+            webhook.notify(SessionEvent::End, &self.engine.session);
+            for error in webhook.finish() {
+                println!("⚠  {}", error);
+            }
         }
 
         println!("\n═══════════════════════════════════════════════════════════");
@@ -495,12 +2135,154 @@ impl CommandLoop {
     }
 }
 
+/// Whether `key` is Ctrl-C, which raw mode delivers as a key press
+pub(crate) fn is_interrupt(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+/// Status line marker for a pattern that settled for `distance` steps from
+/// recent ones, e.g. "⚠ may repeat (distance ≥ 2)"
+pub(crate) fn relaxed_marker(distance: u32) -> String {
+    format!("⚠ may repeat (distance ≥ {})", distance)
+}
+
+/// Label printed as the medley reaches each pattern, e.g. "♪  Pattern #4 (2 of 5)"
+pub(crate) fn medley_label(number: u32, index: usize, count: usize) -> String {
+    format!("♪  Pattern #{} ({} of {})", number, index, count)
+}
+
+/// Label printed as an arrangement reaches each section, e.g.
+/// "🎼 Section B ×4 (3 of 4)"
+pub(crate) fn section_label(label: char, loops: u32, index: usize, count: usize) -> String {
+    format!("🎼 Section {} ×{} ({} of {})", label, loops, index, count)
+}
+
+/// Warning for a loop that started `drift_ms` late, e.g.
+/// "⚠  Timing drift: 12.50ms late at loop #3 (threshold 10ms)"
+pub(crate) fn drift_label(loop_index: u64, drift_ms: f64) -> String {
+    format!(
+        "⚠  Timing drift: {:.2}ms late at loop #{} (threshold {:.0}ms)",
+        drift_ms, loop_index, DRIFT_WARNING_MS
+    )
+}
+
+/// Line reporting what a student in the class did, e.g. "🎓 Ana answered #3: 75%"
+pub(crate) fn class_label(event: &ClassEvent) -> String {
+    match event {
+        ClassEvent::Joined { name } => format!("🎓 {} joined the class", name),
+        ClassEvent::Left { name } => format!("🎓 {} left the class", name),
+        ClassEvent::Answered {
+            name,
+            number,
+            accuracy,
+        } => format!("🎓 {} answered #{}: {:.0}%", name, number, accuracy * 100.0),
+    }
+}
+
+/// Line reporting settings taken on from the config file, e.g.
+/// "⚙  Config reloaded: tempo 110 BPM, kick note 48"
+pub(crate) fn settings_label(update: &SettingsUpdate) -> String {
+    let mut changes = Vec::new();
+    if let Some(tempo) = update.tempo {
+        changes.push(format!("tempo {} BPM", tempo));
+    }
+    if let Some(level) = update.complexity {
+        changes.push(format!("complexity {:?}", level));
+    }
+    if let Some(note) = update.kick_note {
+        changes.push(format!("kick note {}", note));
+    }
+    if let Some(note) = update.click_note {
+        changes.push(format!("click note {}", note));
+    }
+    if let Some(channel) = update.channel {
+        changes.push(format!("channel {}", channel));
+    }
+    format!("⚙  Config reloaded: {}", changes.join(", "))
+}
+
+/// Print the end-of-session summary (patterns, tempo, accuracy, duration)
+pub(crate) fn print_session_summary(session: &PracticeSession) {
+    println!("\n═══════════════════════════════════════════════════════════");
+    println!("                     SESSION SUMMARY");
+    println!("═══════════════════════════════════════════════════════════\n");
+
+    println!("Session ID: {}", session.session_id);
+    println!("Patterns generated: {}", session.patterns_generated);
+    println!("Final tempo: {} BPM", session.tempo_bpm);
+    println!("Final complexity: {:?}", session.complexity_level);
+    if let Some(relaxed) = relaxed_summary(&session.relaxed_patterns) {
+        println!("Relaxed uniqueness: {}", relaxed);
+    }
+
+    if let Some(average) = session.average_accuracy() {
+        println!(
+            "Answers checked: {} (average accuracy {:.1}%)",
+            session.answer_accuracies.len(),
+            average * 100.0
+        );
+    }
+
+    if let Some(goal) = &session.goal {
+        if goal.is_met() {
+            println!("Goal: {} ✓ reached", goal.goal);
+        } else {
+            println!("Goal: {} (not reached: {})", goal.goal, goal.summary());
+        }
+    }
+
+    if let Some(plan) = &session.plan {
+        println!("Plan:");
+        print_plan_summary(plan);
+    }
+
+    if let Ok(duration) = session.last_activity.duration_since(session.session_start) {
+        let minutes = duration.as_secs() / 60;
+        let seconds = duration.as_secs() % 60;
+        println!("Practice duration: {}m {}s", minutes, seconds);
+    }
+}
+
+/// Print what happened in each segment of a session plan
+pub(crate) fn print_plan_summary(plan: &PlanProgress) {
+    for (i, line) in plan.summary_lines().into_iter().enumerate() {
+        println!("  {}. {}", i + 1, line);
+    }
+}
+
+/// Celebrate the answer that completed the session goal
+pub(crate) fn print_goal_complete(session: &PracticeSession) {
+    let Some(goal) = &session.goal else {
+        return;
+    };
+    let elapsed = SystemTime::now()
+        .duration_since(session.session_start)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    println!("═══════════════════════════════════════════════════════════");
+    println!("                  🏆  GOAL COMPLETE  🏆");
+    println!("═══════════════════════════════════════════════════════════");
+    println!("  {} — {}", goal.goal, goal.summary());
+    println!(
+        "  Done in {}m {}s. Keep practicing, or press [q] to finish.",
+        elapsed / 60,
+        elapsed % 60
+    );
+    println!("═══════════════════════════════════════════════════════════\n");
+}
+
+/// Bar within the phrase and phrase number, both counting from 1, for the
+/// loop at `loop_index` when each phrase is `bars` loops long
+pub(crate) fn phrase_position(loop_index: u64, bars: u32) -> (u32, u64) {
+    let bars = u64::from(bars.max(1));
+    ((loop_index % bars) as u32 + 1, loop_index / bars + 1)
+}
+
 impl Drop for CommandLoop {
     fn drop(&mut self) {
-        // Ensure raw mode is disabled
+        // Ensure raw mode is disabled; the engine stops playback itself
         let _ = disable_raw_mode();
-        // Ensure playback is stopped
-        self.playback.stop();
     }
 }
 
@@ -513,18 +2295,72 @@ mod tests {
     fn test_command_loop_creation() {
         let session =
             PracticeSession::new(120, ComplexityLevel::Medium, TimeSignature::four_four());
-        let cmd_loop = CommandLoop::new(session);
+        let cmd_loop = CommandLoop::new(PracticeEngine::new(session), SessionOptions::new());
 
-        assert!(!cmd_loop.playback.is_playing());
+        assert!(!cmd_loop.engine.is_playing());
     }
 
     #[test]
     fn test_welcome_message() {
         let session =
             PracticeSession::new(120, ComplexityLevel::Medium, TimeSignature::four_four());
-        let cmd_loop = CommandLoop::new(session);
+        let cmd_loop = CommandLoop::new(PracticeEngine::new(session), SessionOptions::new());
 
         // Just verify it doesn't crash
         cmd_loop.print_welcome();
     }
+
+    #[test]
+    fn test_complexity_keys_pick_their_level() {
+        let session =
+            PracticeSession::new(120, ComplexityLevel::Medium, TimeSignature::four_four());
+        let mut cmd_loop = CommandLoop::new(PracticeEngine::new(session), SessionOptions::new());
+
+        for (key, level) in [
+            ('1', ComplexityLevel::Simple),
+            ('2', ComplexityLevel::Medium),
+            ('3', ComplexityLevel::Complex),
+        ] {
+            cmd_loop.choose_complexity(KeyCode::Char(key));
+            assert_eq!(cmd_loop.engine.session.complexity_level, level);
+        }
+
+        cmd_loop.choose_complexity(KeyCode::Char('x'));
+        assert_eq!(
+            cmd_loop.engine.session.complexity_level,
+            ComplexityLevel::Complex
+        );
+    }
+
+    #[test]
+    fn test_medley_label() {
+        assert_eq!(medley_label(4, 2, 5), "♪  Pattern #4 (2 of 5)");
+    }
+
+    #[test]
+    fn test_section_label() {
+        assert_eq!(section_label('B', 4, 3, 4), "🎼 Section B ×4 (3 of 4)");
+    }
+
+    #[test]
+    fn test_settings_label() {
+        let update = SettingsUpdate {
+            tempo: Some(110),
+            complexity: Some(ComplexityLevel::Complex),
+            channel: Some(1),
+            ..SettingsUpdate::default()
+        };
+        assert_eq!(
+            settings_label(&update),
+            "⚙  Config reloaded: tempo 110 BPM, complexity Complex, channel 1"
+        );
+    }
+
+    #[test]
+    fn test_phrase_position() {
+        assert_eq!(phrase_position(0, 4), (1, 1));
+        assert_eq!(phrase_position(3, 4), (4, 1));
+        assert_eq!(phrase_position(4, 4), (1, 2));
+        assert_eq!(phrase_position(17, 8), (2, 3));
+    }
 }
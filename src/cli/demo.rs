@@ -0,0 +1,255 @@
+use crate::cli::commands::drift_label;
+use crate::cli::replay::wait_until;
+use crate::cli::{HeadlessCommand, HeadlessEvent, HeadlessLoop, SessionOptions};
+use crate::color::Palette;
+use crate::config::{Action, KeyBindings};
+use crate::engine::PracticeEngine;
+use crate::models::ComplexityLevel;
+use std::io::{self, Write};
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+/// Said before the first pattern starts
+const INTRO: &str = "👋 Welcome to kickbeats! It loops a one-bar kick drum pattern against a click,
+   and you work out the rhythm by ear. Here's a simple one at 90 BPM, after a
+   bar of count-in. Listen for where each kick lands against the click.";
+
+/// One point of the tour: what's said, and the session commands that show it
+struct DemoStep {
+    /// Seconds into the tour the step comes at
+    at_secs: u64,
+    /// Key the narration names in place of `{key}`, as the user has it bound
+    key: Option<Action>,
+    /// What's said
+    narration: &'static str,
+    /// Commands run on the session once it's said
+    commands: &'static [HeadlessCommand],
+}
+
+/// The tour, about two minutes long
+const TOUR: &[DemoStep] = &[
+    DemoStep {
+        at_secs: 20,
+        key: Some(Action::Reveal),
+        narration: "Think you've got it? Press {key} to reveal the pattern. Each X is a kick,
+   and the counting row splits every beat into sixteenths (1 e + a):",
+        commands: &[HeadlessCommand::Reveal],
+    },
+    DemoStep {
+        at_secs: 35,
+        key: Some(Action::New),
+        narration: "Press {key} for a new pattern. It always differs from the last few by at
+   least three steps, so you can't get by on memory:",
+        commands: &[HeadlessCommand::New],
+    },
+    DemoStep {
+        at_secs: 55,
+        key: Some(Action::Tempo),
+        narration: "Too fast to follow? Press {key} to change the tempo. It takes effect at the
+   next loop, so you never lose your place. Slowing down to 70 BPM:",
+        commands: &[HeadlessCommand::SetTempo { bpm: 70 }],
+    },
+    DemoStep {
+        at_secs: 70,
+        key: Some(Action::Complexity),
+        narration: "Press {key} to change complexity. Simple patterns keep to the beat; complex
+   ones have more kicks, off the beat. Switching to complex, with a new pattern:",
+        commands: &[
+            HeadlessCommand::SetComplexity {
+                level: ComplexityLevel::Complex,
+            },
+            HeadlessCommand::New,
+        ],
+    },
+    DemoStep {
+        at_secs: 95,
+        key: None,
+        narration: "Harder to pin down. Here's the answer:",
+        commands: &[HeadlessCommand::Reveal],
+    },
+    DemoStep {
+        at_secs: 105,
+        key: Some(Action::Answer),
+        narration: "When you practice, press {key} before revealing and type what you hear
+   (e.g., x...x..x....x.x.) to see which steps you got right.",
+        commands: &[],
+    },
+];
+
+/// Said once the tour is over
+const OUTRO: &str = "That's the tour! Where to go next:
+   kickbeats                      practice at your own pace (q quits)
+   kickbeats --tui                the same, full screen
+   kickbeats --style funk         idiomatic grooves (rock, funk, latin, dnb)
+   kickbeats --drill 4loops/20s   timed answers, like a test
+   kickbeats --no-click           just the kicks, no click
+   kickbeats --help               everything else";
+
+/// Seconds between the last step and the outro
+const OUTRO_AFTER_SECS: u64 = 15;
+
+/// A scripted, narrated two-minute tour of a practice session
+///
+/// Each step of the tour is a [`HeadlessCommand`] run on a real session,
+/// so what the tour shows is what practicing does: the same patterns,
+/// reveals, and tempo and complexity changes. Narration names the keys as
+/// the user has them bound.
+pub struct DemoTour {
+    /// Session the tour's commands run on
+    session: HeadlessLoop,
+    /// Keys the narration names
+    keys: KeyBindings,
+    /// Colors reveals are printed in
+    palette: Palette,
+    /// Whether steps wait for their time, or run straight away (for tests)
+    paced: bool,
+}
+
+impl DemoTour {
+    /// Give the tour on an engine whose current pattern plays first
+    pub fn new(engine: PracticeEngine, options: SessionOptions) -> Self {
+        Self {
+            keys: options.keys,
+            palette: options.palette,
+            session: HeadlessLoop::new(engine, SessionOptions::new()),
+            paced: true,
+        }
+    }
+
+    /// Run every step straight away instead of at its time
+    pub fn without_pauses(mut self) -> Self {
+        self.paced = false;
+        self
+    }
+
+    /// Give the tour on stdout, stopping early once `running` is cleared (Ctrl-C)
+    pub fn run(&mut self, running: &AtomicBool) -> Result<(), Box<dyn std::error::Error>> {
+        self.run_with(running, io::stdout().lock())
+    }
+
+    /// Give the tour, writing the narration and what happens to `output`
+    pub fn run_with(
+        &mut self,
+        running: &AtomicBool,
+        mut output: impl Write,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        writeln!(output, "{}\n", INTRO)?;
+        let events = self.session.begin()?;
+        self.show(&mut output, &events)?;
+
+        let started = Instant::now();
+        for step in TOUR {
+            if !self.wait(started, step.at_secs, running) {
+                return self.stop(&mut output, "\n⏹  Tour stopped.");
+            }
+            writeln!(output, "\n{}\n", self.narrate(step))?;
+            for &command in step.commands {
+                let events = self.session.handle_command(command);
+                self.show(&mut output, &events)?;
+            }
+            self.show(&mut output, &self.session.drift_warnings())?;
+        }
+
+        let outro_at = TOUR.last().map_or(0, |step| step.at_secs) + OUTRO_AFTER_SECS;
+        if !self.wait(started, outro_at, running) {
+            return self.stop(&mut output, "\n⏹  Tour stopped.");
+        }
+        self.stop(&mut output, &format!("\n{}", OUTRO))
+    }
+
+    /// Wait until `at_secs` into the tour, returning false on Ctrl-C
+    fn wait(&self, started: Instant, at_secs: u64, running: &AtomicBool) -> bool {
+        !self.paced || wait_until(started + Duration::from_secs(at_secs), running)
+    }
+
+    /// A step's narration, with the key it names filled in
+    fn narrate(&self, step: &DemoStep) -> String {
+        match step.key {
+            Some(action) => step.narration.replace("{key}", &self.keys.label(action)),
+            None => step.narration.to_string(),
+        }
+    }
+
+    /// Print what a command did, in words rather than JSON
+    fn show(&self, output: &mut impl Write, events: &[HeadlessEvent]) -> io::Result<()> {
+        for event in events {
+            match event {
+                HeadlessEvent::Pattern { number, .. } => {
+                    writeln!(output, "🎲 Pattern #{} is playing", number)?
+                }
+                HeadlessEvent::Revealed { display, .. } => {
+                    write!(output, "{}", self.palette.reveal(display))?
+                }
+                HeadlessEvent::Tempo { bpm } => writeln!(output, "🎚  Tempo {} BPM", bpm)?,
+                HeadlessEvent::Complexity { level } => writeln!(output, "🎚  Complexity {}", level)?,
+                HeadlessEvent::Error { message } => writeln!(output, "⚠  {}", message)?,
+                HeadlessEvent::DriftWarning {
+                    loop_index,
+                    drift_ms,
+                    ..
+                } => writeln!(output, "{}", drift_label(*loop_index, *drift_ms))?,
+                HeadlessEvent::Ready { .. } | HeadlessEvent::Summary { .. } => {}
+            }
+        }
+        output.flush()
+    }
+
+    /// Stop playback and say goodbye
+    fn stop(
+        &mut self,
+        output: &mut impl Write,
+        farewell: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let events = self.session.finish();
+        self.show(output, &events)?;
+        writeln!(output, "{}", farewell)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::KeysSection;
+    use crate::models::{PracticeSession, TimeSignature};
+
+    fn tour(options: SessionOptions) -> String {
+        let session = PracticeSession::new(90, ComplexityLevel::Simple, TimeSignature::four_four());
+        let mut engine = PracticeEngine::new(session).with_null_output();
+        engine.generate().unwrap();
+        let mut output = Vec::new();
+        DemoTour::new(engine, options)
+            .without_pauses()
+            .run_with(&AtomicBool::new(true), &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_tour_runs_its_commands_on_the_session() {
+        let output = tour(SessionOptions::new());
+
+        assert!(output.starts_with("👋 Welcome to kickbeats!"));
+        assert!(output.contains("🎲 Pattern #1 is playing"));
+        assert!(output.contains("Press [r] to reveal the pattern."));
+        assert_eq!(output.matches("|1 e + a |").count(), 2);
+        assert!(output.contains("🎲 Pattern #3 is playing"));
+        assert!(output.contains("🎚  Tempo 70 BPM"));
+        assert!(output.contains("🎚  Complexity complex"));
+        assert!(output
+            .trim_end()
+            .ends_with("kickbeats --help               everything else"));
+    }
+
+    #[test]
+    fn test_narration_names_rebound_keys() {
+        let section = KeysSection {
+            reveal: Some('k'),
+            ..KeysSection::default()
+        };
+        let keys = KeyBindings::from_section(&section).unwrap();
+        let output = tour(SessionOptions::new().with_key_bindings(keys));
+
+        assert!(output.contains("Press [k] to reveal the pattern."));
+    }
+}
@@ -0,0 +1,493 @@
+use crate::cli::SessionOptions;
+#[cfg(feature = "webhooks")]
+use crate::cli::{SessionEvent, Webhook};
+use crate::engine::{PracticeEngine, DRIFT_WARNING_MS};
+use crate::models::ComplexityLevel;
+use crate::visualizer::{format_reveal, RevealStyle};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// How often the session checks for Ctrl-C while waiting for a line
+const INPUT_POLL: Duration = Duration::from_millis(50);
+
+/// One line of input: `{"cmd": "set-tempo", "bpm": 140}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(tag = "cmd", rename_all = "kebab-case")]
+pub enum HeadlessCommand {
+    /// Generate and play a new pattern
+    New,
+    /// Show the current pattern
+    Reveal,
+    /// Change tempo at the next loop (40-300 BPM)
+    SetTempo { bpm: u16 },
+    /// Generate later patterns at another complexity (simple, medium, or complex)
+    SetComplexity {
+        #[serde(deserialize_with = "complexity")]
+        level: ComplexityLevel,
+    },
+    /// Stop playback and end the session
+    Quit,
+}
+
+/// One line of output: `{"event": "tempo", "bpm": 140}`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum HeadlessEvent {
+    /// The session started with these settings
+    Ready {
+        tempo: u16,
+        complexity: String,
+        time_signature: String,
+    },
+    /// A new pattern is playing (kept hidden until revealed)
+    Pattern { number: u32, steps: usize },
+    /// The current pattern, as `x`/`.` steps and as the reveal text
+    Revealed { steps: String, display: String },
+    /// Tempo now in effect
+    Tempo { bpm: u16 },
+    /// Complexity later patterns are generated at
+    Complexity { level: String },
+    /// A command failed or couldn't be read; the session carries on
+    Error { message: String },
+    /// A loop started later than any before it, and past the warning threshold
+    DriftWarning {
+        loop_index: u64,
+        drift_ms: f64,
+        threshold_ms: f64,
+    },
+    /// Session totals, sent last
+    Summary {
+        patterns: u32,
+        tempo: u16,
+        duration_secs: u64,
+        midi_log: Option<String>,
+        replay: Option<String>,
+    },
+}
+
+/// Practice session driven by line-delimited JSON commands instead of keys
+///
+/// Reads [`HeadlessCommand`]s and writes [`HeadlessEvent`]s, one JSON object
+/// per line, so kickbeats can be embedded in GUIs, scripts, and test harnesses
+/// without a terminal. Playback failures (e.g., no MIDI ports) are reported as
+/// error events rather than ending the session.
+pub struct HeadlessLoop {
+    /// Session, generator, and playback
+    engine: PracticeEngine,
+    /// Practice tracker notified when the session starts and ends
+    #[cfg(feature = "webhooks")]
+    webhook: Option<Webhook>,
+}
+
+impl HeadlessLoop {
+    /// Create a headless session around an engine whose current pattern plays first
+    #[cfg_attr(not(feature = "webhooks"), allow(unused_variables))]
+    pub fn new(engine: PracticeEngine, options: SessionOptions) -> Self {
+        Self {
+            engine,
+            #[cfg(feature = "webhooks")]
+            webhook: options.webhook,
+        }
+    }
+
+    /// Serve commands from stdin until `quit`, end of input, or `running`
+    /// is cleared (e.g., by Ctrl-C)
+    pub fn run(&mut self, running: &AtomicBool) -> Result<(), Box<dyn std::error::Error>> {
+        self.run_with(
+            running,
+            io::BufReader::new(io::stdin()),
+            io::stdout().lock(),
+        )
+    }
+
+    /// Serve commands from `input`, writing events to `output`
+    ///
+    /// Lines are read on a thread of their own, so clearing `running` ends
+    /// the session (with its summary) even while a read is blocked.
+    pub fn run_with(
+        &mut self,
+        running: &AtomicBool,
+        input: impl BufRead + Send + 'static,
+        mut output: impl Write,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for event in self.begin()? {
+            emit(&mut output, &event)?;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for line in input.lines() {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        while running.load(Ordering::SeqCst) {
+            for event in self.drift_warnings() {
+                emit(&mut output, &event)?;
+            }
+            let line = match rx.recv_timeout(INPUT_POLL) {
+                Ok(line) => line?,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let command = match serde_json::from_str::<HeadlessCommand>(&line) {
+                Ok(command) => command,
+                Err(e) => {
+                    let message = format!("Invalid command '{}': {}", line.trim(), e);
+                    emit(&mut output, &HeadlessEvent::Error { message })?;
+                    continue;
+                }
+            };
+            if command == HeadlessCommand::Quit {
+                break;
+            }
+            for event in self.handle_command(command) {
+                emit(&mut output, &event)?;
+            }
+        }
+
+        for event in self.finish() {
+            emit(&mut output, &event)?;
+        }
+        Ok(())
+    }
+
+    /// Start the session and play its current pattern, returning the
+    /// `ready` and `pattern` events
+    pub fn begin(&mut self) -> Result<Vec<HeadlessEvent>, String> {
+        if self.engine.current_pattern().is_none() {
+            return Err("No pattern available to play".to_string());
+        }
+
+        #[cfg(feature = "webhooks")]
+        if let Some(webhook) = &mut self.webhook {
+            webhook.notify(SessionEvent::Start, self.engine.session());
+        }
+
+        let mut events = vec![self.ready()];
+        events.extend(self.start_pattern());
+        Ok(events)
+    }
+
+    fn ready(&self) -> HeadlessEvent {
+        let session = self.engine.session();
+        HeadlessEvent::Ready {
+            tempo: session.tempo_bpm,
+            complexity: complexity_name(session.complexity_level),
+            time_signature: format!(
+                "{}/{}",
+                session.time_signature.numerator, session.time_signature.denominator
+            ),
+        }
+    }
+
+    /// Warnings for loops that started late since the last call
+    pub fn drift_warnings(&self) -> Vec<HeadlessEvent> {
+        self.engine
+            .playback()
+            .drain_drift()
+            .into_iter()
+            .filter(|sample| sample.late)
+            .map(|sample| HeadlessEvent::DriftWarning {
+                loop_index: sample.loop_index,
+                drift_ms: sample.drift_ms,
+                threshold_ms: DRIFT_WARNING_MS,
+            })
+            .collect()
+    }
+
+    /// Apply one command, returning the events it produced
+    pub fn handle_command(&mut self, command: HeadlessCommand) -> Vec<HeadlessEvent> {
+        match command {
+            HeadlessCommand::New => self.handle_new_pattern(),
+            HeadlessCommand::Reveal => self.handle_reveal(),
+            HeadlessCommand::SetTempo { bpm } => self.handle_tempo_change(bpm),
+            HeadlessCommand::SetComplexity { level } => {
+                self.engine.set_complexity(level);
+                vec![HeadlessEvent::Complexity {
+                    level: complexity_name(level),
+                }]
+            }
+            HeadlessCommand::Quit => self.finish(),
+        }
+    }
+
+    fn handle_new_pattern(&mut self) -> Vec<HeadlessEvent> {
+        match self.engine.generate() {
+            Ok(_) => self.start_pattern(),
+            Err(e) => vec![HeadlessEvent::Error {
+                message: format!("Failed to generate new pattern: {}", e),
+            }],
+        }
+    }
+
+    fn handle_reveal(&mut self) -> Vec<HeadlessEvent> {
+        let tempo = self.engine.tempo();
+        // The build-up style is an animation with nothing to show in one shot
+        let styles: Vec<RevealStyle> = self
+            .engine
+            .session()
+            .reveal_styles
+            .iter()
+            .copied()
+            .filter(|&style| style != RevealStyle::Build)
+            .collect();
+
+        match self.engine.reveal() {
+            Some(pattern) => vec![HeadlessEvent::Revealed {
+                steps: pattern
+                    .steps
+                    .iter()
+                    .map(|&kick| if kick { 'x' } else { '.' })
+                    .collect(),
+                display: format_reveal(pattern, tempo, &styles),
+            }],
+            None => vec![HeadlessEvent::Error {
+                message: String::from("No pattern available to reveal"),
+            }],
+        }
+    }
+
+    fn handle_tempo_change(&mut self, bpm: u16) -> Vec<HeadlessEvent> {
+        if let Err(e) = self.engine.set_tempo(bpm) {
+            return vec![HeadlessEvent::Error {
+                message: e.to_string(),
+            }];
+        }
+        let mut events = vec![HeadlessEvent::Tempo { bpm }];
+
+        // A running loop picks the tempo up itself; restart one that had stopped
+        if !self.engine.is_playing() {
+            if let Err(e) = self.engine.start() {
+                events.push(HeadlessEvent::Error {
+                    message: format!("Failed to restart playback: {}", e),
+                });
+            }
+        }
+        events
+    }
+
+    /// Start the current pattern, preceded by the listen delay
+    fn start_pattern(&mut self) -> Vec<HeadlessEvent> {
+        let mut events = vec![HeadlessEvent::Pattern {
+            number: self.engine.session().patterns_generated,
+            steps: self.engine.current_pattern().map_or(0, |p| p.steps.len()),
+        }];
+
+        if let Err(e) = self.engine.start() {
+            events.push(HeadlessEvent::Error {
+                message: format!("Failed to start playback: {}", e),
+            });
+        }
+        events
+    }
+
+    /// Stop playback, save the MIDI log and practice history, and summarize the session
+    pub fn finish(&mut self) -> Vec<HeadlessEvent> {
+        self.engine.stop();
+
+        let mut events = Vec::new();
+        let mut midi_log = None;
+        match self.engine.save_midi_log() {
+            Some(Ok(path)) => midi_log = Some(path.display().to_string()),
+            Some(Err(e)) => events.push(HeadlessEvent::Error {
+                message: e.to_string(),
+            }),
+            None => {}
+        }
+        let mut replay = None;
+        match self.engine.save_replay() {
+            Some(Ok(path)) => replay = Some(path.display().to_string()),
+            Some(Err(e)) => events.push(HeadlessEvent::Error {
+                message: e.to_string(),
+            }),
+            None => {}
+        }
+        if let Some(Err(e)) = self.engine.save_session() {
+            events.push(HeadlessEvent::Error {
+                message: e.to_string(),
+            });
+        }
+        let session = self.engine.session();
+
+        #[cfg(feature = "webhooks")]
+        if let Some(webhook) = &mut self.webhook {
+            webhook.notify(SessionEvent::End, session);
+            for message in webhook.finish() {
+                events.push(HeadlessEvent::Error { message });
+            }
+        }
+
+        let duration_secs = session
+            .last_activity
+            .duration_since(session.session_start)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        events.push(HeadlessEvent::Summary {
+            patterns: session.patterns_generated,
+            tempo: session.tempo_bpm,
+            duration_secs,
+            midi_log,
+            replay,
+        });
+        events
+    }
+}
+
+/// A complexity level as commands and events name it (e.g., "complex")
+fn complexity_name(level: ComplexityLevel) -> String {
+    format!("{:?}", level).to_lowercase()
+}
+
+/// Deserialize a complexity level from its name
+fn complexity<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ComplexityLevel, D::Error> {
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
+
+/// Write one event as a line of JSON, flushed so readers see it right away
+fn emit(output: &mut impl Write, event: &HeadlessEvent) -> io::Result<()> {
+    serde_json::to_writer(&mut *output, event)?;
+    writeln!(output)?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, Pattern, PracticeSession, TimeSignature};
+
+    fn headless() -> HeadlessLoop {
+        let mut session =
+            PracticeSession::new(120, ComplexityLevel::Medium, TimeSignature::four_four());
+        let mut steps = vec![false; 16];
+        steps[0] = true;
+        steps[10] = true;
+        session.current_pattern = Some(Pattern::new(
+            steps,
+            TimeSignature::four_four(),
+            ComplexityLevel::Medium,
+        ));
+        session.patterns_generated = 1;
+        HeadlessLoop::new(PracticeEngine::new(session), SessionOptions::new())
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let parse = |line| serde_json::from_str::<HeadlessCommand>(line).unwrap();
+
+        assert_eq!(parse(r#"{"cmd":"new"}"#), HeadlessCommand::New);
+        assert_eq!(parse(r#"{"cmd":"reveal"}"#), HeadlessCommand::Reveal);
+        assert_eq!(
+            parse(r#"{"cmd":"set-tempo","bpm":140}"#),
+            HeadlessCommand::SetTempo { bpm: 140 }
+        );
+        assert_eq!(
+            parse(r#"{"cmd":"set-complexity","level":"complex"}"#),
+            HeadlessCommand::SetComplexity {
+                level: ComplexityLevel::Complex
+            }
+        );
+        assert_eq!(parse(r#"{"cmd":"quit"}"#), HeadlessCommand::Quit);
+        assert!(serde_json::from_str::<HeadlessCommand>(r#"{"cmd":"dance"}"#).is_err());
+    }
+
+    #[test]
+    fn test_event_json() {
+        let json = serde_json::to_string(&HeadlessEvent::Tempo { bpm: 140 }).unwrap();
+        assert_eq!(json, r#"{"event":"tempo","bpm":140}"#);
+
+        let json = serde_json::to_string(&HeadlessEvent::DriftWarning {
+            loop_index: 3,
+            drift_ms: 12.5,
+            threshold_ms: DRIFT_WARNING_MS,
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            r#"{"event":"drift-warning","loop_index":3,"drift_ms":12.5,"threshold_ms":10.0}"#
+        );
+    }
+
+    #[test]
+    fn test_reveal() {
+        let events = headless().handle_command(HeadlessCommand::Reveal);
+
+        match &events[..] {
+            [HeadlessEvent::Revealed { steps, display }] => {
+                assert_eq!(steps, "x.........x.....");
+                assert!(display.contains("|X"));
+            }
+            other => panic!("unexpected events: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_out_of_range_tempo() {
+        let mut headless = headless();
+        let events = headless.handle_command(HeadlessCommand::SetTempo { bpm: 500 });
+
+        assert!(matches!(&events[..], [HeadlessEvent::Error { .. }]));
+        assert_eq!(headless.engine.tempo(), 120);
+    }
+
+    #[test]
+    fn test_run_reports_bad_lines_and_ends_on_quit() {
+        let input = "{\"cmd\":\"reveal\"}\nnot json\n{\"cmd\":\"quit\"}\n{\"cmd\":\"new\"}\n";
+        let mut output = Vec::new();
+        headless()
+            .run_with(&AtomicBool::new(true), input.as_bytes(), &mut output)
+            .unwrap();
+
+        let events: Vec<serde_json::Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let kinds: Vec<&str> = events
+            .iter()
+            .map(|event| event["event"].as_str().unwrap())
+            .collect();
+
+        assert_eq!(kinds[0], "ready");
+        assert_eq!(kinds[1], "pattern");
+        assert!(kinds.contains(&"revealed"));
+        assert!(events.iter().any(|event| event["message"]
+            .as_str()
+            .is_some_and(|m| m.starts_with("Invalid command 'not json'"))));
+        assert_eq!(*kinds.last().unwrap(), "summary");
+        assert_eq!(events.last().unwrap()["patterns"], 1);
+    }
+
+    #[test]
+    fn test_interrupt_ends_the_session_with_a_summary() {
+        let input = "{\"cmd\":\"reveal\"}\n";
+        let mut output = Vec::new();
+        headless()
+            .run_with(&AtomicBool::new(false), input.as_bytes(), &mut output)
+            .unwrap();
+
+        let kinds: Vec<String> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let event: serde_json::Value = serde_json::from_str(line).unwrap();
+                event["event"].as_str().unwrap().to_string()
+            })
+            .collect();
+        // The reveal was never served; a missing MIDI port may add an error
+        assert_eq!(kinds[..2], ["ready", "pattern"]);
+        assert!(!kinds.iter().any(|kind| kind == "revealed"));
+        assert_eq!(kinds.last().unwrap(), "summary");
+    }
+}
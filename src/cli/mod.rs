@@ -1,6 +1,22 @@
 // CLI module
 // Command-line interface and user input handling
 
+pub mod classroom;
 pub mod commands;
+pub mod demo;
+pub mod headless;
+pub mod options;
+pub mod replay;
+pub mod tui;
+#[cfg(feature = "webhooks")]
+pub mod webhook;
 
+pub use classroom::ClassroomStudent;
 pub use commands::CommandLoop;
+pub use demo::DemoTour;
+pub use headless::{HeadlessCommand, HeadlessEvent, HeadlessLoop};
+pub use options::SessionOptions;
+pub use replay::ReplayPlayer;
+pub use tui::TuiApp;
+#[cfg(feature = "webhooks")]
+pub use webhook::{SessionEvent, Webhook};
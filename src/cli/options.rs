@@ -0,0 +1,47 @@
+#[cfg(feature = "webhooks")]
+use crate::cli::Webhook;
+use crate::color::Palette;
+use crate::config::KeyBindings;
+
+/// What a practice session reports to and how it's controlled, besides the engine itself
+///
+/// Shared by the scrolling, `--tui`, and `--headless` front ends, which each
+/// take a [`PracticeEngine`](crate::engine::PracticeEngine) for the session,
+/// generator, and MIDI settings plus these options. The headless loop reads
+/// commands instead of keys, so it uses only the webhook.
+#[derive(Default)]
+pub struct SessionOptions {
+    /// Key each interactive command runs on
+    pub(crate) keys: KeyBindings,
+    /// Colors reveals, answers, and the count-in are printed in
+    pub(crate) palette: Palette,
+    /// Practice tracker notified when the session starts and ends
+    #[cfg(feature = "webhooks")]
+    pub(crate) webhook: Option<Webhook>,
+}
+
+impl SessionOptions {
+    /// Options with the default keys and no color that report to nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run commands on other keys than the defaults
+    pub fn with_key_bindings(mut self, keys: KeyBindings) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Print in color (reveals, answers, the count-in, and the `--tui` panes)
+    pub fn with_palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Notify a webhook with a session summary at start and end
+    #[cfg(feature = "webhooks")]
+    pub fn with_webhook(mut self, webhook: Webhook) -> Self {
+        self.webhook = Some(webhook);
+        self
+    }
+}
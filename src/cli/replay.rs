@@ -0,0 +1,417 @@
+use crate::engine::PracticeEngine;
+use crate::error::KickbeatsError;
+use crate::models::{ComplexityLevel, Morph, Pattern};
+use crate::storage::{ReplayEntry, ReplayEvent, SessionReplay};
+use crate::visualizer::{format_reveal, RevealStyle};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Longest sleep between checks for Ctrl-C while waiting for the next event
+const WAIT_SLICE: Duration = Duration::from_millis(50);
+
+/// Slowest and fastest a replay can be paced, as a multiple of real time
+pub const REPLAY_SPEEDS: (f64, f64) = (0.25, 16.0);
+
+/// Plays back a recorded `.kbreplay` session
+///
+/// Prints every pattern, command, answer, and tempo change with when it
+/// happened. With an engine (see [`with_engine`](Self::with_engine)) events
+/// are paced as they were recorded and the patterns are heard again;
+/// without one, the whole session is listed straight away, e.g. for a
+/// teacher checking a student's practice.
+pub struct ReplayPlayer {
+    /// Session being played back
+    replay: SessionReplay,
+    /// Engine the patterns are played on, if they're to be heard
+    engine: Option<PracticeEngine>,
+    /// Representations shown when a pattern is revealed
+    reveal_styles: Vec<RevealStyle>,
+    /// Pattern current at this point of the session
+    current: Option<Pattern>,
+    /// Whether the current pattern has been shown yet
+    revealed: bool,
+    /// Patterns the session has gone through so far
+    patterns: u32,
+    /// Reveals so far, including answers
+    reveals: u32,
+    /// Tempo at this point of the session
+    tempo_bpm: u16,
+    /// Whether the click is on at this point of the session
+    click_enabled: bool,
+    /// Multiple of real time events are paced at
+    speed: f64,
+}
+
+impl ReplayPlayer {
+    /// Create a player that lists `replay` without pacing or sound
+    pub fn new(replay: SessionReplay) -> Self {
+        let tempo_bpm = replay.tempo_bpm;
+        let click_enabled = replay.click;
+        Self {
+            replay,
+            engine: None,
+            reveal_styles: vec![RevealStyle::Grid],
+            current: None,
+            revealed: false,
+            patterns: 0,
+            reveals: 0,
+            tempo_bpm,
+            click_enabled,
+            speed: 1.0,
+        }
+    }
+
+    /// Pace events as recorded and play the session's patterns on `engine`
+    pub fn with_engine(mut self, engine: PracticeEngine) -> Self {
+        self.engine = Some(engine);
+        self
+    }
+
+    /// Pace events `speed` times as fast as they happened (e.g. 4 to skim a
+    /// session, 0.5 to slow it down); patterns still play at their tempo
+    pub fn with_speed(mut self, speed: f64) -> Result<Self, KickbeatsError> {
+        let (slowest, fastest) = REPLAY_SPEEDS;
+        if !(slowest..=fastest).contains(&speed) {
+            return Err(KickbeatsError::Config(format!(
+                "Replay speed must be between {} and {}",
+                slowest, fastest
+            )));
+        }
+        self.speed = speed;
+        Ok(self)
+    }
+
+    /// Show revealed patterns in these styles instead of the grid
+    pub fn with_reveal_styles(mut self, styles: Vec<RevealStyle>) -> Self {
+        self.reveal_styles = styles;
+        self
+    }
+
+    /// Play the replay to stdout, stopping early once `running` is cleared (Ctrl-C)
+    pub fn run(&mut self, running: &AtomicBool) -> Result<(), Box<dyn std::error::Error>> {
+        self.run_with(running, io::stdout().lock())
+    }
+
+    /// Play the replay, writing what happens to `output`
+    pub fn run_with(
+        &mut self,
+        running: &AtomicBool,
+        mut output: impl Write,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        writeln!(output, "🎞  Replay of session {}", self.replay.session_id)?;
+        writeln!(
+            output,
+            "   Started at {} BPM, {}, {}\n",
+            self.replay.tempo_bpm, self.replay.complexity, self.replay.time_signature
+        )?;
+
+        let started = Instant::now();
+        let events = self.replay.events.clone();
+        for entry in &events {
+            let due = started + entry.at().div_f64(self.speed);
+            if self.engine.is_some() && !wait_until(due, running) {
+                writeln!(output, "\n⏹  Replay stopped.")?;
+                break;
+            }
+            for line in self.play(entry) {
+                writeln!(output, "{}", line)?;
+            }
+            output.flush()?;
+        }
+        if let Some(engine) = &mut self.engine {
+            engine.stop();
+        }
+
+        writeln!(output, "\n{}", self.summary())?;
+        Ok(())
+    }
+
+    /// Apply one event, returning the lines describing it
+    fn play(&mut self, entry: &ReplayEntry) -> Vec<String> {
+        let stamp = timestamp(entry.at());
+        let mut lines = Vec::new();
+        match &entry.event {
+            ReplayEvent::Pattern {
+                pattern,
+                complexity,
+                review,
+            } => {
+                self.patterns += 1;
+                let mut line = format!("{} 🎲 Pattern #{} ({})", stamp, self.patterns, complexity);
+                if *review {
+                    line.push_str(", back for review");
+                }
+                lines.push(line);
+                let level = complexity.parse().unwrap_or_default();
+                lines.extend(self.select(pattern, level).err());
+            }
+            ReplayEvent::Revisit { pattern } => {
+                lines.push(format!("{} ⏪ Back to an earlier pattern", stamp));
+                let level = self
+                    .current
+                    .as_ref()
+                    .map(|current| current.complexity_level)
+                    .unwrap_or_default();
+                lines.extend(self.select(pattern, level).err());
+            }
+            ReplayEvent::Morph {
+                pattern,
+                loops_per_stage,
+                bpm,
+            } => {
+                self.patterns += 1;
+                lines.push(format!(
+                    "{} 🔀 Morphing into pattern #{} at {} BPM",
+                    stamp, self.patterns, bpm
+                ));
+                let from = self.current.clone();
+                let level = from
+                    .as_ref()
+                    .map(|current| current.complexity_level)
+                    .unwrap_or_default();
+                lines.extend(self.select(pattern, level).err());
+                self.tempo_bpm = *bpm;
+                if let (Some(engine), Some(from), Some(to)) =
+                    (&mut self.engine, from, &self.current)
+                {
+                    let started = Morph::new(&from, to, *loops_per_stage)
+                        .map_err(KickbeatsError::from)
+                        .and_then(|morph| {
+                            engine.playback.start_morph(morph, *bpm, self.click_enabled)
+                        });
+                    lines.extend(started.err().map(|e| format!("   ⚠  {}", e)));
+                }
+            }
+            ReplayEvent::Play { bpm } => {
+                lines.push(format!("{} ▶  Playing at {} BPM", stamp, bpm));
+                self.tempo_bpm = *bpm;
+                if let (Some(engine), Some(pattern)) = (&mut self.engine, &self.current) {
+                    let started = engine
+                        .set_tempo(*bpm)
+                        .and_then(|()| engine.replay(pattern.clone()));
+                    lines.extend(started.err().map(|e| format!("   ⚠  {}", e)));
+                }
+            }
+            ReplayEvent::Stop => {
+                lines.push(format!("{} ⏹  Stopped", stamp));
+                if let Some(engine) = &mut self.engine {
+                    engine.stop();
+                }
+            }
+            ReplayEvent::Tempo { bpm } => {
+                lines.push(format!("{} 🎚  Tempo {} BPM", stamp, bpm));
+                self.tempo_bpm = *bpm;
+                if let Some(engine) = &mut self.engine {
+                    lines.extend(engine.set_tempo(*bpm).err().map(|e| format!("   ⚠  {}", e)));
+                }
+            }
+            ReplayEvent::Complexity { level } => {
+                lines.push(format!("{} 🎚  Complexity {}", stamp, level));
+            }
+            ReplayEvent::TimeSignature { meter } => {
+                lines.push(format!("{} 🎚  Time signature {}", stamp, meter));
+            }
+            ReplayEvent::Click { enabled } => {
+                let state = if *enabled { "on" } else { "off" };
+                lines.push(format!("{} 🔔 Click {}", stamp, state));
+                self.click_enabled = *enabled;
+                if let Some(engine) = &mut self.engine {
+                    engine.set_click_enabled(*enabled);
+                }
+            }
+            ReplayEvent::AutoAdvance { enabled } => {
+                let state = if *enabled { "on" } else { "off" };
+                lines.push(format!("{} ⏭  Auto-advance {}", stamp, state));
+            }
+            ReplayEvent::Reveal => {
+                lines.push(format!("{} 👁  Revealed", stamp));
+                lines.extend(self.reveal());
+            }
+            ReplayEvent::Answer { answer, accuracy } => {
+                lines.push(match answer {
+                    Some(answer) => format!(
+                        "{} ✎  Answered {} — {:.0}%",
+                        stamp,
+                        answer,
+                        accuracy * 100.0
+                    ),
+                    None => format!("{} ✎  Scored {:.0}%", stamp, accuracy * 100.0),
+                });
+                lines.extend(self.reveal());
+            }
+        }
+        lines
+    }
+
+    /// Make the pattern with `fingerprint` current, hidden until revealed
+    fn select(&mut self, fingerprint: &str, level: ComplexityLevel) -> Result<(), String> {
+        self.revealed = false;
+        match Pattern::from_fingerprint(fingerprint, level) {
+            Ok(pattern) => {
+                self.current = Some(pattern);
+                Ok(())
+            }
+            Err(e) => {
+                self.current = None;
+                Err(format!("   ⚠  {}", e))
+            }
+        }
+    }
+
+    /// Show the current pattern, the first time it's revealed
+    fn reveal(&mut self) -> Option<String> {
+        if self.revealed {
+            return None;
+        }
+        let pattern = self.current.as_ref()?;
+        self.revealed = true;
+        self.reveals += 1;
+        Some(format_reveal(pattern, self.tempo_bpm, &self.reveal_styles))
+    }
+
+    /// Totals for the end of the replay
+    fn summary(&self) -> String {
+        let accuracies = self.replay.accuracies();
+        let answers = if accuracies.is_empty() {
+            "no answers checked".to_string()
+        } else {
+            let average = accuracies.iter().sum::<f32>() / accuracies.len() as f32;
+            format!(
+                "{} answer{} checked (average accuracy {:.1}%)",
+                accuracies.len(),
+                if accuracies.len() == 1 { "" } else { "s" },
+                average * 100.0
+            )
+        };
+        let duration = self.replay.duration().as_secs();
+        format!(
+            "Session: {} pattern{}, {}, {} revealed, {}m {}s",
+            self.patterns,
+            if self.patterns == 1 { "" } else { "s" },
+            answers,
+            self.reveals,
+            duration / 60,
+            duration % 60
+        )
+    }
+}
+
+/// Sleep until `deadline`, returning false if `running` is cleared first
+pub(crate) fn wait_until(deadline: Instant, running: &AtomicBool) -> bool {
+    loop {
+        if !running.load(Ordering::SeqCst) {
+            return false;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        thread::sleep((deadline - now).min(WAIT_SLICE));
+    }
+}
+
+/// Minutes and seconds into the session, e.g. "[02:05]"
+fn timestamp(at: Duration) -> String {
+    let secs = at.as_secs();
+    format!("[{:02}:{:02}]", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{PracticeSession, TimeSignature};
+
+    fn replay() -> SessionReplay {
+        let session =
+            PracticeSession::new(100, ComplexityLevel::Medium, TimeSignature::four_four());
+        let mut replay = SessionReplay::new(&session);
+        let events = [
+            (
+                0,
+                ReplayEvent::Pattern {
+                    pattern: "4/4:x...x.......x...".to_string(),
+                    complexity: "medium".to_string(),
+                    review: false,
+                },
+            ),
+            (10, ReplayEvent::Play { bpm: 100 }),
+            (65_000, ReplayEvent::Tempo { bpm: 110 }),
+            (
+                70_000,
+                ReplayEvent::Answer {
+                    answer: Some("x...x.......x...".to_string()),
+                    accuracy: 1.0,
+                },
+            ),
+            (71_000, ReplayEvent::Reveal),
+        ];
+        for (at_ms, event) in events {
+            replay.record(Duration::from_millis(at_ms), event);
+        }
+        replay
+    }
+
+    #[test]
+    fn test_lists_events_with_timestamps() {
+        let mut output = Vec::new();
+        ReplayPlayer::new(replay())
+            .run_with(&AtomicBool::new(true), &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("[00:00] 🎲 Pattern #1 (medium)"));
+        assert!(output.contains("[01:05] 🎚  Tempo 110 BPM"));
+        assert!(output.contains("[01:10] ✎  Answered x...x.......x... — 100%"));
+        // The answer already revealed the pattern
+        assert_eq!(output.matches("|1 e + a |").count(), 1);
+        assert!(output.contains(
+            "Session: 1 pattern, 1 answer checked (average accuracy 100.0%), 1 revealed, 1m 11s"
+        ));
+    }
+
+    #[test]
+    fn test_paces_events_at_speed() {
+        let session =
+            PracticeSession::new(100, ComplexityLevel::Medium, TimeSignature::four_four());
+        let mut paced = SessionReplay::new(&session);
+        paced.record(Duration::ZERO, replay().events[0].event.clone());
+        paced.record(Duration::from_millis(1_600), ReplayEvent::Reveal);
+        let engine = PracticeEngine::new(session).with_null_output();
+
+        let started = Instant::now();
+        let mut output = Vec::new();
+        ReplayPlayer::new(paced)
+            .with_engine(engine)
+            .with_speed(16.0)
+            .unwrap()
+            .run_with(&AtomicBool::new(true), &mut output)
+            .unwrap();
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(100), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(800), "{:?}", elapsed);
+        // Stamps keep the session's own time
+        assert!(String::from_utf8(output).unwrap().contains("[00:01] 👁"));
+
+        assert!(ReplayPlayer::new(replay()).with_speed(100.0).is_err());
+    }
+
+    #[test]
+    fn test_reports_unreadable_patterns() {
+        let mut replay = replay();
+        replay.events[0].event = ReplayEvent::Pattern {
+            pattern: "4/4:xx".to_string(),
+            complexity: "medium".to_string(),
+            review: false,
+        };
+        let mut output = Vec::new();
+        ReplayPlayer::new(replay)
+            .run_with(&AtomicBool::new(true), &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("Invalid pattern fingerprint"));
+        assert!(output.contains("0 revealed"));
+    }
+}
@@ -0,0 +1,1311 @@
+use crate::cli::commands::{
+    class_label, drift_label, is_interrupt, medley_label, phrase_position, print_session_summary,
+    relaxed_marker, section_label, settings_label, CommandLoop,
+};
+#[cfg(feature = "webhooks")]
+use crate::cli::SessionEvent;
+use crate::cli::SessionOptions;
+use crate::color::tui_color;
+use crate::config::{Action, PAD_KEYS};
+use crate::engine::{DrumPad, MidiMapping, NextPattern, Notice, Pad, PracticeEngine};
+use crate::error::KickbeatsError;
+use crate::grading::{grade_answer_at, grade_tap, TapScore};
+use crate::models::{ArrangementSpec, ComplexityLevel, CountIn, Pattern, PatternNote, Transform};
+use crate::visualizer::ascii::{count_row, separators};
+use crate::visualizer::{format_answer_diff, format_kit_timings, format_reveal_to_width};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph, Wrap};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// How often the screen is redrawn while waiting for keys (moves the playhead)
+const FRAME_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Width of the session settings pane
+const SETTINGS_WIDTH: u16 = 34;
+
+/// What the command bar is collecting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prompt {
+    Answer,
+    Tempo,
+    History,
+    Note,
+    Arrange,
+    Vary,
+}
+
+impl Prompt {
+    fn label(&self) -> &'static str {
+        match self {
+            Prompt::Answer => "Answer",
+            Prompt::Tempo => "Tempo (40-300)",
+            Prompt::History => "Replay history #",
+            Prompt::Note => "Note (text and #tags)",
+            Prompt::Arrange => "Form and parts",
+            Prompt::Vary => "Variation",
+        }
+    }
+}
+
+/// Full-screen practice interface with panes for settings, playback position,
+/// history, and the revealed pattern
+///
+/// Offers the same single-key commands as [`CommandLoop`]; prompts are typed
+/// into the command bar instead of scrolling the terminal.
+pub struct TuiApp {
+    /// Session, generator, and playback
+    engine: PracticeEngine,
+    /// Keys and reporting shared with the other front ends
+    options: SessionOptions,
+    /// Contents of the pattern pane (a reveal or graded answer), hidden if `None`
+    pattern_pane: Option<String>,
+    /// Feedback from the last command
+    message: String,
+    /// Prompt being typed into the command bar, with the text so far
+    prompt: Option<(Prompt, String)>,
+    /// Tag the history pane only lists patterns with, if filtered
+    history_tag: Option<String>,
+    /// Drum pad for tapping along, while pad mode is on
+    pad: Option<DrumPad>,
+    /// Timing of the most recent kick taps against the loop
+    tap_score: TapScore,
+}
+
+impl TuiApp {
+    /// Create a full-screen interface around an engine whose current pattern plays first
+    pub fn new(engine: PracticeEngine, options: SessionOptions) -> Self {
+        Self {
+            engine,
+            options,
+            pattern_pane: None,
+            message: String::from("Listen carefully and try to identify the rhythm."),
+            prompt: None,
+            history_tag: None,
+            pad: None,
+            tap_score: TapScore::new(),
+        }
+    }
+
+    /// Take over the terminal and run until the user quits or `running` is
+    /// cleared (e.g., by Ctrl-C)
+    pub fn run(&mut self, running: &AtomicBool) -> Result<(), Box<dyn std::error::Error>> {
+        CommandLoop::check_terminal_capabilities()?;
+
+        if self.engine.session.current_pattern.is_none() {
+            return Err("No pattern available to play".into());
+        }
+
+        #[cfg(feature = "webhooks")]
+        if let Some(webhook) = &mut self.options.webhook {
+            webhook.notify(SessionEvent::Start, &self.engine.session);
+        }
+
+        self.engine.start()?;
+        if let Some(Err(e)) = self.engine.connect_kit() {
+            self.message = format!("⚠  {}. Carrying on without the kit.", e);
+        }
+
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        let result = Terminal::new(CrosstermBackend::new(io::stdout()))
+            .map_err(Into::into)
+            .and_then(|mut terminal| self.event_loop(&mut terminal, running));
+
+        // Always give the terminal back before printing the summary
+        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        disable_raw_mode()?;
+
+        self.finish();
+        result
+    }
+
+    /// Draw and handle keys until quit or interrupt
+    fn event_loop(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+        running: &AtomicBool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        while running.load(Ordering::SeqCst) {
+            self.update_engine();
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if event::poll(FRAME_INTERVAL)? {
+                // Resizes need no handling: the next draw lays out to the new size
+                if let Event::Key(key) = event::read()? {
+                    // Raw mode turns Ctrl-C into a key rather than a signal
+                    if is_interrupt(&key) {
+                        running.store(false, Ordering::SeqCst);
+                    } else if self.handle_key(key)? {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle a key press, returning whether to quit
+    fn handle_key(&mut self, key: KeyEvent) -> Result<bool, String> {
+        if let Some((prompt, mut input)) = self.prompt.take() {
+            match key.code {
+                KeyCode::Enter => self.submit(prompt, input.trim())?,
+                KeyCode::Esc => self.message = format!("{} cancelled.", prompt.label()),
+                KeyCode::Backspace => {
+                    input.pop();
+                    self.prompt = Some((prompt, input));
+                }
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    self.prompt = Some((prompt, input));
+                }
+                _ => self.prompt = Some((prompt, input)),
+            }
+            return Ok(false);
+        }
+
+        let KeyCode::Char(c) = key.code else {
+            return Ok(false);
+        };
+        if self.pad.is_some() && PAD_KEYS.contains(&c) {
+            self.hit_pad(if matches!(c, 'f' | 'z') {
+                Pad::Kick
+            } else {
+                Pad::Snare
+            });
+            return Ok(false);
+        }
+        match self.options.keys.action(c) {
+            Some(Action::Reveal) => self.reveal(),
+            Some(Action::Answer) => self.open_prompt(Prompt::Answer),
+            Some(Action::New) => self.new_pattern()?,
+            Some(Action::Back) => self.back()?,
+            Some(Action::Morph) => self.morph(),
+            Some(Action::Vary) => self.open_prompt(Prompt::Vary),
+            Some(Action::Tempo) => self.open_prompt(Prompt::Tempo),
+            Some(Action::Complexity) => self.cycle_complexity(),
+            Some(Action::History) => self.open_prompt(Prompt::History),
+            Some(Action::Note) => self.open_prompt(Prompt::Note),
+            Some(Action::Medley) => self.medley(),
+            Some(Action::Arrange) => self.open_prompt(Prompt::Arrange),
+            Some(Action::Mute) => self.toggle_click(),
+            Some(action @ (Action::ClickUp | Action::ClickDown)) => {
+                self.step_level(false, action == Action::ClickUp)
+            }
+            Some(action @ (Action::KickUp | Action::KickDown)) => {
+                self.step_level(true, action == Action::KickUp)
+            }
+            Some(Action::Pad) => self.toggle_pad(),
+            Some(Action::Advance) => self.toggle_advance(),
+            Some(Action::Quit) => return Ok(true),
+            // Timing diagnostics are printed by the line interface only
+            Some(Action::Diagnostics) | None => {}
+        }
+        Ok(false)
+    }
+
+    fn open_prompt(&mut self, prompt: Prompt) {
+        self.message = match prompt {
+            Prompt::Answer => {
+                let len = self
+                    .engine
+                    .session
+                    .current_pattern
+                    .as_ref()
+                    .map_or(0, |p| p.steps.len());
+                format!(
+                    "Type the {} positions: 'x' = kick, '.' = rest. Esc cancels.",
+                    len
+                )
+            }
+            Prompt::Tempo => format!("Current tempo: {} BPM. Esc cancels.", self.engine.tempo()),
+            Prompt::History => String::from(
+                "Pick a pattern from the history pane, or type #tag to filter it. Esc cancels.",
+            ),
+            Prompt::Note => {
+                let number = self.engine.session.current_number();
+                match self.engine.session.note_for(number) {
+                    Some(note) => format!("Pattern #{} is noted: {}. Esc cancels.", number, note),
+                    None => format!("Note or #tag pattern #{}. Esc cancels.", number),
+                }
+            }
+            Prompt::Arrange => String::from(
+                "Type a song form and its parts, e.g. A2 A2 B4 A2 A=3 B=#bridge. Esc cancels.",
+            ),
+            Prompt::Vary => String::from(
+                "Type rotate <steps>, reverse, invert, add <step>, or remove <step>. Esc cancels.",
+            ),
+        };
+        self.prompt = Some((prompt, String::new()));
+    }
+
+    /// Act on a completed prompt
+    fn submit(&mut self, prompt: Prompt, input: &str) -> Result<(), String> {
+        if input.is_empty() {
+            self.message = format!("{} cancelled.", prompt.label());
+            return Ok(());
+        }
+        match prompt {
+            Prompt::Answer => self.check_answer(input),
+            Prompt::Tempo => self.change_tempo(input)?,
+            Prompt::History => self.replay_history(input)?,
+            Prompt::Note => self.note(input),
+            Prompt::Arrange => self.arrange(input),
+            Prompt::Vary => self.vary(input),
+        }
+        Ok(())
+    }
+
+    /// Show the current pattern in the pattern pane
+    fn reveal(&mut self) {
+        self.engine.sync_tempo_from_playback();
+        let Some(pattern) = &self.engine.session.current_pattern else {
+            return;
+        };
+        let width = terminal_width().saturating_sub(2);
+        let mut pane = format_reveal_to_width(
+            pattern,
+            self.engine.session.tempo_bpm,
+            &self.engine.session.reveal_styles,
+            width,
+        );
+        if let Some(timings) = self.engine.kit_timings() {
+            pane.push_str(&format_kit_timings(pattern, timings));
+        }
+        self.pattern_pane = Some(pane);
+        self.message = format!(
+            "Pattern revealed. Press {} for a new one.",
+            self.options.keys.label(Action::New)
+        );
+        self.engine.reveal();
+    }
+
+    fn check_answer(&mut self, input: &str) {
+        let Some(pattern) = self.engine.session.current_pattern.clone() else {
+            return;
+        };
+        let resolution = self.engine.session.answer_resolution;
+        match grade_answer_at(
+            &pattern,
+            input,
+            self.engine.session.answer_format,
+            resolution,
+        ) {
+            Ok(grade) => {
+                let truth = resolution.truth(&pattern);
+                let mut pane = format_answer_diff(&truth, &grade);
+                if let Some(note) = resolution.ambiguity_note(&pattern) {
+                    pane.push_str(&format!("\n{}", note));
+                }
+                self.pattern_pane = Some(pane);
+                self.message = if grade.is_perfect() {
+                    format!(
+                        "✓ Perfect! Press {} for a new pattern.",
+                        self.options.keys.label(Action::New)
+                    )
+                } else {
+                    format!(
+                        "{:.0}% correct. Press {} to try again or {} for a new pattern.",
+                        grade.accuracy * 100.0,
+                        self.options.keys.label(Action::Answer),
+                        self.options.keys.label(Action::New)
+                    )
+                };
+                if let Some(source) = self.engine.pattern_source() {
+                    self.message
+                        .push_str(&format!(" (This pattern came from: {})", source));
+                }
+
+                let outcome = self.engine.record_result(grade.accuracy);
+                if outcome.goal_complete {
+                    if let Some(goal) = &self.engine.session.goal {
+                        self.message = format!("🏆 Goal complete: {}!", goal.goal);
+                    }
+                }
+                if let Some(stage) = outcome.next_stage {
+                    self.message.push_str(&format!(
+                        " 🎓 Curriculum stage passed! Next: {}.",
+                        stage.name
+                    ));
+                }
+                if let Some((old, new)) = outcome.click_velocity.filter(|(old, new)| old != new) {
+                    self.message
+                        .push_str(&format!(" Click velocity {} → {}.", old, new));
+                }
+            }
+            Err(e) => {
+                self.message = format!(
+                    "✗ {}. Press {} to try again.",
+                    e,
+                    self.options.keys.label(Action::Answer)
+                )
+            }
+        }
+    }
+
+    fn new_pattern(&mut self) -> Result<(), String> {
+        match self.engine.next_pattern() {
+            Ok(next) => {
+                self.message = format!(
+                    "▶ Pattern #{} is playing. Press {} to reveal.",
+                    self.engine.session.patterns_generated,
+                    self.options.keys.label(Action::Reveal)
+                );
+                if let Some(distance) = next.relaxed {
+                    self.message.push_str(&format!(
+                        " (Uniqueness relaxed to distance >= {})",
+                        distance
+                    ));
+                }
+                if next.review {
+                    self.message
+                        .push_str(" 🔁 Review: you missed this one before.");
+                }
+                self.pattern_pane = None;
+                self.engine
+                    .start()
+                    .map_err(|e| format!("Failed to start playback: {}", e))
+            }
+            // The current pattern keeps playing
+            Err(e) => {
+                self.message = format!("✗ Failed to generate new pattern: {}", e);
+                Ok(())
+            }
+        }
+    }
+
+    /// Play the current pattern turning into a new one, a step at a time
+    fn morph(&mut self) {
+        match self.engine.morph() {
+            Ok(steps) => {
+                self.message = format!(
+                    "🔀 Morphing into pattern #{}: {} steps change. Press {} to reveal where it ends up.",
+                    self.engine.session.patterns_generated,
+                    steps,
+                    self.options.keys.label(Action::Reveal)
+                );
+                self.pattern_pane = None;
+            }
+            Err(e) => self.message = format!("✗ Can't morph: {}", e),
+        }
+    }
+
+    /// Play the session's patterns back to back, then pick the current one up again
+    fn medley(&mut self) {
+        self.message = match self.engine.start_medley() {
+            Ok(medley) => format!(
+                "🎞  Medley of the session's {} pattern{}. Press {} to move on instead.",
+                medley.len(),
+                if medley.len() == 1 { "" } else { "s" },
+                self.options.keys.label(Action::New)
+            ),
+            Err(e) => format!("✗ Can't play a medley: {}", e),
+        };
+    }
+
+    fn arrange(&mut self, input: &str) {
+        let started = input
+            .parse::<ArrangementSpec>()
+            .map_err(KickbeatsError::Config)
+            .and_then(|spec| self.engine.start_arrangement(&spec).map(|a| a.form_label()));
+        self.message = match started {
+            Ok(form) => format!(
+                "🎼 Playing {} through once. Press {} to move on instead.",
+                form,
+                self.options.keys.label(Action::New)
+            ),
+            Err(e) => format!("✗ Can't play that arrangement: {}", e),
+        };
+    }
+
+    fn vary(&mut self, input: &str) {
+        let varied = input
+            .parse::<Transform>()
+            .map_err(KickbeatsError::Config)
+            .and_then(|transform| self.engine.transform(transform));
+        match varied {
+            Ok(()) => {
+                self.reveal();
+                self.message = format!(
+                    "🔀 Playing the variation. Press {} to go back to the original.",
+                    self.options.keys.label(Action::Back)
+                );
+            }
+            Err(e) => self.message = format!("✗ Can't play that variation: {}", e),
+        }
+    }
+
+    fn back(&mut self) -> Result<(), String> {
+        let previous = self
+            .engine
+            .session
+            .current_pattern
+            .as_ref()
+            .and_then(|current| self.engine.session.pattern_history.previous(current.id))
+            .cloned();
+        let Some(entry) = previous else {
+            self.message = String::from("✗ No earlier pattern in history.");
+            return Ok(());
+        };
+
+        self.pattern_pane = None;
+        self.message = format!(
+            "⏪ Back to pattern #{}. Press {} to reveal.",
+            entry.number,
+            self.options.keys.label(Action::Reveal)
+        );
+        self.restart(entry.pattern)?;
+        self.engine.session.pattern_revealed = entry.revealed;
+        Ok(())
+    }
+
+    fn change_tempo(&mut self, input: &str) -> Result<(), String> {
+        match input.parse::<u16>() {
+            Ok(tempo) if (40..=300).contains(&tempo) => {
+                self.engine.set_tempo(tempo).map_err(|e| e.to_string())?;
+                self.message = format!("✓ Tempo changed to {} BPM from the next loop.", tempo);
+
+                if !self.engine.is_playing() {
+                    if let Some(pattern) = self.engine.session.current_pattern.clone() {
+                        self.restart(pattern)?;
+                    }
+                }
+            }
+            Ok(tempo) => self.message = format!("✗ Tempo {} is out of range (40-300 BPM)", tempo),
+            Err(_) => self.message = format!("✗ Invalid tempo '{}'. Please enter a number.", input),
+        }
+        Ok(())
+    }
+
+    fn cycle_complexity(&mut self) {
+        self.engine
+            .set_complexity(match self.engine.session.complexity_level {
+                ComplexityLevel::Simple => ComplexityLevel::Medium,
+                ComplexityLevel::Medium => ComplexityLevel::Complex,
+                ComplexityLevel::Complex => ComplexityLevel::Simple,
+            });
+        self.message = format!(
+            "✓ Complexity changed to {:?}. Press {} for a pattern at this level.",
+            self.engine.session.complexity_level,
+            self.options.keys.label(Action::New)
+        );
+    }
+
+    fn replay_history(&mut self, input: &str) -> Result<(), String> {
+        if input.starts_with('#') {
+            self.history_tag = Some(input.to_string()).filter(|tag| tag.len() > 1);
+            self.open_prompt(Prompt::History);
+            if let Some(tag) = &self.history_tag {
+                self.message = format!(
+                    "History shows patterns tagged {}; type # alone to show them all.",
+                    tag
+                );
+            }
+            return Ok(());
+        }
+        let entry = input
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| self.engine.session.pattern_history.recent(i))
+            .cloned();
+        let Some(entry) = entry else {
+            self.message = format!(
+                "✗ Invalid selection '{}'. Choose 1-{}.",
+                input,
+                self.engine.session.pattern_history.len()
+            );
+            return Ok(());
+        };
+
+        self.restart(entry.pattern)?;
+        self.reveal();
+        self.message = format!(
+            "▶ Replaying pattern #{}. Press {} for a new pattern.",
+            entry.number,
+            self.options.keys.label(Action::New)
+        );
+        Ok(())
+    }
+
+    fn note(&mut self, input: &str) {
+        self.message = match input.parse::<PatternNote>() {
+            Ok(note) => match self.engine.session.annotate(note) {
+                Some(number) => format!(
+                    "📝 Noted pattern #{}. Filter the history by tag with {}.",
+                    number,
+                    self.options.keys.label(Action::History)
+                ),
+                None => String::from("✗ No pattern to note yet."),
+            },
+            Err(e) => format!("✗ {}", e),
+        };
+    }
+
+    fn toggle_click(&mut self) {
+        self.engine
+            .set_click_enabled(!self.engine.session.click_enabled);
+        self.message = if self.engine.session.click_enabled {
+            String::from("🔔 Click track on.")
+        } else {
+            String::from("🔕 Click track muted. Keep the pulse yourself!")
+        };
+    }
+
+    fn step_level(&mut self, kicks: bool, louder: bool) {
+        let (voice, velocity) = if kicks {
+            ("Kicks", self.engine.step_kick_velocity(louder))
+        } else {
+            ("Click", self.engine.step_click_velocity(louder))
+        };
+        self.message = match velocity {
+            0 => format!("🔇 {} silent (velocity 0).", voice),
+            _ if louder => format!("🔊 {} velocity {}.", voice, velocity),
+            _ => format!("🔉 {} velocity {}.", voice, velocity),
+        };
+    }
+
+    fn toggle_advance(&mut self) {
+        let enabled = !self.engine.session.auto_advance;
+        self.message = match self.engine.set_auto_advance(enabled) {
+            Err(e) => format!("✗ {}.", e),
+            Ok(()) if enabled => format!(
+                "⏭ Auto-advance on: a new pattern every {} loops.",
+                self.engine.session.advance_loops
+            ),
+            Ok(()) => String::from("⏸ Auto-advance off: this pattern keeps looping."),
+        };
+    }
+
+    /// Report what the engine picked up since the last frame
+    ///
+    /// Kit hits show up in the settings pane's scores, so only auto-advance
+    /// and the session plan need saying.
+    fn update_engine(&mut self) {
+        // Drained every frame so samples don't pile up; only new worsts show
+        for sample in self.engine.playback().drain_drift() {
+            if sample.late {
+                self.message = drift_label(sample.loop_index, sample.drift_ms);
+            }
+        }
+        for notice in self.engine.poll() {
+            match notice {
+                Notice::KitHit { .. } => {}
+                Notice::Advanced { revealed, next } => self.report_advance(revealed, next),
+                Notice::AdvanceFailed(e) => {
+                    self.message = format!("✗ Auto-advance couldn't move on: {}", e);
+                }
+                Notice::PlanSegment { index, next } => self.report_plan_segment(index, next),
+                Notice::PlanSegmentFailed(e) => {
+                    self.message = format!("✗ The plan's next segment couldn't start: {}", e);
+                }
+                Notice::MedleyPart {
+                    number,
+                    index,
+                    count,
+                } => self.message = medley_label(number, index, count),
+                Notice::Class(event) => self.message = class_label(&event),
+                Notice::ConfigReloaded(update) => self.message = settings_label(&update),
+                Notice::ConfigReloadFailed(e) => {
+                    self.message = format!("✗ Config not reloaded: {}", e);
+                }
+                Notice::OutputLost(e) => {
+                    self.message = format!("⚠  {}. Paused until the MIDI port is back.", e);
+                }
+                Notice::OutputRestored => {
+                    self.message = String::from("🔌 MIDI port back. Playing on.");
+                }
+                Notice::Section {
+                    label,
+                    loops,
+                    index,
+                    count,
+                } => self.message = section_label(label, loops, index, count),
+                Notice::ArrangementFinished => {
+                    self.message = match self.engine.start() {
+                        Ok(()) => {
+                            String::from("🎼 End of the form. Back to the pattern you were on.")
+                        }
+                        Err(e) => format!("✗ Failed to start playback: {}", e),
+                    };
+                }
+                Notice::MedleyFinished => {
+                    self.message = match self.engine.start() {
+                        Ok(()) => String::from("🎞  Medley over. Back to the pattern you were on."),
+                        Err(e) => format!("✗ Failed to start playback: {}", e),
+                    };
+                }
+                Notice::PlanFinished => {
+                    self.message = format!(
+                        "🏁 Plan complete! Keep practicing, or press {} for the summary of each segment.",
+                        self.options.keys.label(Action::Quit)
+                    );
+                }
+            }
+        }
+    }
+
+    /// Say auto-advance has moved on, leaving the last pattern up if it was
+    /// revealed on the way out
+    fn report_advance(&mut self, revealed: Option<Pattern>, next: NextPattern) {
+        self.message = format!(
+            "⏭ Pattern #{} is playing. Press {} to reveal.",
+            self.engine.session.patterns_generated,
+            self.options.keys.label(Action::Reveal)
+        );
+        if let Some(distance) = next.relaxed {
+            self.message.push_str(&format!(
+                " (Uniqueness relaxed to distance >= {})",
+                distance
+            ));
+        }
+        self.pattern_pane = revealed.map(|pattern| {
+            self.message.push_str(" The last pattern is shown below.");
+            format_reveal_to_width(
+                &pattern,
+                self.engine.session.tempo_bpm,
+                &self.engine.session.reveal_styles,
+                terminal_width().saturating_sub(2),
+            )
+        });
+    }
+
+    /// Announce the plan's next segment and the fresh pattern it started with
+    fn report_plan_segment(&mut self, index: usize, next: NextPattern) {
+        let Some(plan) = &self.engine.session.plan else {
+            return;
+        };
+        let segment = &plan.plan.segments[index];
+        self.message = format!(
+            "⏱ Plan: {} of {}, {} — {}. ▶ Pattern #{} is playing. Press {} to reveal.",
+            index + 1,
+            plan.plan.segments.len(),
+            segment.label(index),
+            segment,
+            self.engine.session.patterns_generated,
+            self.options.keys.label(Action::Reveal)
+        );
+        if let Some(distance) = next.relaxed {
+            self.message.push_str(&format!(
+                " (Uniqueness relaxed to distance >= {})",
+                distance
+            ));
+        }
+        self.pattern_pane = None;
+    }
+
+    fn toggle_pad(&mut self) {
+        if self.pad.take().is_some() {
+            self.message = String::from("🥁 Drum pad off.");
+            return;
+        }
+        match self.engine.playback.drum_pad() {
+            Ok(pad) => {
+                self.pad = Some(pad);
+                self.tap_score.clear();
+                self.message = format!(
+                    "🥁 Drum pad on: f or z = kick, j or x = snare. {} puts it away.",
+                    self.options.keys.label(Action::Pad)
+                );
+            }
+            Err(e) => self.message = format!("✗ {}", e),
+        }
+    }
+
+    /// Play a pad hit straight away, alongside the scheduled loop
+    fn hit_pad(&mut self, pad: Pad) {
+        if let Some(drum_pad) = &mut self.pad {
+            if let Err(e) = drum_pad.hit(pad) {
+                self.pad = None;
+                self.message = format!("✗ {} — drum pad off.", e);
+                return;
+            }
+        }
+
+        // Score kick taps as they land, for the settings pane
+        let position = self.engine.playback.step_position();
+        if let (Pad::Kick, Some(pattern), Some((step, step_secs))) =
+            (pad, &self.engine.session.current_pattern, position)
+        {
+            if let Some((grade, _)) = grade_tap(pattern, step, step_secs) {
+                self.tap_score.push(grade);
+                self.engine.session.record_tap(grade);
+            }
+        }
+    }
+
+    /// Play a pattern again straight away (at the next bar line if playing)
+    fn restart(&mut self, pattern: Pattern) -> Result<(), String> {
+        self.engine
+            .replay(pattern)
+            .map_err(|e| format!("Failed to start playback: {}", e))
+    }
+
+    /// Stop playback and print the summary once the terminal is restored
+    fn finish(&mut self) {
+        self.engine.stop();
+        self.pad = None;
+        print_session_summary(&self.engine.session);
+
+        match self.engine.save_midi_log() {
+            Some(Ok(path)) => println!("🎼 Session MIDI saved to {}", path.display()),
+            Some(Err(e)) => println!("⚠  {}", e),
+            None => {}
+        }
+        match self.engine.save_replay() {
+            Some(Ok(path)) => println!("🎞  Session replay saved to {}", path.display()),
+            Some(Err(e)) => println!("⚠  {}", e),
+            None => {}
+        }
+        if let Some(Err(e)) = self.engine.save_session() {
+            println!("⚠  {}", e);
+        }
+
+        #[cfg(feature = "webhooks")]
+        if let Some(webhook) = &mut self.options.webhook {
+            webhook.notify(SessionEvent::End, &self.engine.session);
+            for error in webhook.finish() {
+                println!("⚠  {}", error);
+            }
+        }
+
+        println!("\nThanks for practicing! Keep working on your rhythm skills.\n");
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(9),
+                Constraint::Length(4),
+                Constraint::Min(6),
+                Constraint::Length(4),
+            ])
+            .split(frame.size());
+        let top = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(SETTINGS_WIDTH), Constraint::Min(20)])
+            .split(rows[0]);
+
+        frame.render_widget(self.settings_pane(), top[0]);
+        frame.render_widget(self.history_pane(top[1].height), top[1]);
+        frame.render_widget(self.position_pane(), rows[1]);
+        frame.render_widget(self.pattern_pane(), rows[2]);
+        frame.render_widget(self.command_bar(), rows[3]);
+    }
+
+    fn settings_pane(&self) -> Paragraph<'_> {
+        let status = match self.engine.playback.preroll_remaining() {
+            Some(remaining) => format!("Count-in in {}s", remaining.as_secs() + 1),
+            None if self.engine.playback.is_synced()
+                && self.engine.playback.is_playing()
+                && self.engine.playback.current_step().is_none() =>
+            {
+                String::from("Waiting for MIDI clock")
+            }
+            None if self.engine.playback.is_playing() => String::from("Playing"),
+            None => String::from("Stopped"),
+        };
+        let mut lines = vec![
+            Line::from(match self.engine.session.tempo_range {
+                Some(range) => format!(
+                    "Tempo:      {} BPM ({}-{})",
+                    self.engine.tempo(),
+                    range.min,
+                    range.max
+                ),
+                None => format!("Tempo:      {} BPM", self.engine.tempo()),
+            }),
+            Line::from(format!(
+                "Complexity: {:?}",
+                self.engine.session.complexity_level
+            )),
+            Line::from(format!(
+                "Time:       {}",
+                self.engine.session.current_pattern.as_ref().map_or_else(
+                    || format!(
+                        "{}/{}",
+                        self.engine.session.time_signature.numerator,
+                        self.engine.session.time_signature.denominator
+                    ),
+                    |pattern| pattern.meter_label()
+                )
+            )),
+            Line::from(format!(
+                "Click:      {}",
+                if self.engine.session.click_enabled {
+                    "on"
+                } else {
+                    "muted"
+                }
+            )),
+            Line::from(vec![
+                Span::raw("Status:     "),
+                match self.options.palette.theme() {
+                    Some(theme) if self.engine.playback.preroll_remaining().is_some() => {
+                        Span::styled(status, Style::default().fg(tui_color(theme.count_in)))
+                    }
+                    _ => Span::raw(status),
+                },
+            ]),
+        ];
+        if let Some(distance) = self.engine.session.current_relaxed {
+            lines.push(Line::from(format!(
+                "Unique:     {}",
+                relaxed_marker(distance)
+            )));
+        }
+        if let (Some(bars), Some(index)) = (
+            self.engine.session.phrase_bars,
+            self.engine.playback.current_loop(),
+        ) {
+            let (bar, phrase) = phrase_position(index, bars);
+            lines.push(Line::from(format!(
+                "Bar:        {}/{} (phrase {})",
+                bar, bars, phrase
+            )));
+        }
+        if let Some(ratio) = self.engine.session.click_ratio {
+            lines.push(Line::from(format!(
+                "Click:      {} canon, {:.0} BPM",
+                ratio,
+                ratio.click_tempo(self.engine.playback.current_tempo())
+            )));
+        }
+        if let Some(hemiola) = self.engine.session.hemiola {
+            lines.push(Line::from(format!(
+                "Hemiola:    {} every {}",
+                hemiola.pulse_name(),
+                hemiola.bars
+            )));
+        }
+        if let Some(drift) = self.engine.session.click_drift {
+            let tempo = self.engine.playback.current_tempo();
+            let click = self
+                .engine
+                .playback
+                .current_loop()
+                .map(|i| drift.click_tempo(tempo, i));
+            lines.push(Line::from(format!(
+                "Click:      drifting {:.0} BPM, hold {}",
+                click.unwrap_or(f64::from(tempo)),
+                tempo
+            )));
+        }
+        if let Some(gap) = self.engine.session.click_gap {
+            lines.push(Line::from(format!("Click:      {} bars", gap)));
+        }
+        let count_in = self.engine.session.count_in;
+        if count_in != CountIn::default() {
+            lines.push(Line::from(format!(
+                "Count-in:   {} bar{}{}{}",
+                count_in.bars,
+                if count_in.bars == 1 { "" } else { "s" },
+                if count_in.subdivide { ", 8ths" } else { "" },
+                if count_in.count_off_note.is_some() {
+                    ", voiced"
+                } else {
+                    ""
+                }
+            )));
+        }
+        if let Some(dropout) = self.engine.session.kick_dropout {
+            lines.push(Line::from(format!("Dropout:    {}", dropout)));
+        }
+        if let Some(fills) = self.engine.session.fills {
+            lines.push(Line::from(format!("Fills:      {}", fills)));
+        }
+        if let Some(ghosts) = self.engine.session.ghost_steps {
+            lines.push(Line::from(format!(
+                "Ghosts:     {}% off-beat",
+                ghosts.chance
+            )));
+        }
+        if self.engine.session.kick_velocity.is_some()
+            || self.engine.session.click_velocity.is_some()
+        {
+            lines.push(Line::from(format!(
+                "Velocity:   kicks {}, click {}",
+                self.engine.kick_velocity(),
+                self.engine.click_velocity()
+            )));
+        }
+        if self.engine.session.click_fade.is_some() {
+            lines.push(Line::from(format!(
+                "Click vel:  {}",
+                self.engine.playback.click_velocity()
+            )));
+        }
+        if self.pad.is_some() {
+            lines.push(Line::from("Drum pad:   f/z kick, j/x snare"));
+            let summary = self.tap_score.summary();
+            if !summary.is_empty() {
+                lines.push(Line::from(format!("Taps:       {}", summary)));
+            }
+        }
+        if let Some(goal) = &self.engine.session.goal {
+            lines.push(Line::from(if goal.is_met() {
+                format!("Goal:       🏆 {}", goal.goal)
+            } else {
+                format!("Goal:       {}", goal.summary())
+            }));
+        }
+        if let Some((stage, stages)) = self.engine.playback.morph_stage() {
+            lines.push(Line::from(format!(
+                "Morph:      step {}/{}, every {} loops",
+                stage,
+                stages - 1,
+                self.engine.session.morph_bars
+            )));
+        }
+        if let Some(plan) = &self.engine.session.plan {
+            let remaining = plan.remaining_secs(self.engine.session.elapsed_secs());
+            lines.push(Line::from(match plan.segment() {
+                Some(_) => format!(
+                    "Plan:       {}/{} {} · {}:{:02} left",
+                    plan.current + 1,
+                    plan.plan.segments.len(),
+                    plan.plan.segments[plan.current].label(plan.current),
+                    remaining / 60,
+                    remaining % 60
+                ),
+                None => String::from("Plan:       🏁 complete"),
+            }));
+        }
+        if self.engine.session.auto_advance {
+            lines.push(Line::from(format!(
+                "Advance:    every {} loops",
+                self.engine.session.advance_loops
+            )));
+        }
+        if let Some(scores) = self.engine.kit_scores() {
+            let summary = scores.summary();
+            lines.push(Line::from(format!(
+                "Kit:        {}",
+                if summary.is_empty() {
+                    "listening"
+                } else {
+                    &summary
+                }
+            )));
+        }
+        if let Some(average) = self.engine.session.average_accuracy() {
+            lines.push(Line::from(format!(
+                "Accuracy:   {:.1}% of {}",
+                average * 100.0,
+                self.engine.session.answer_accuracies.len()
+            )));
+        }
+        if self.engine.midi_mapping != MidiMapping::default() {
+            let mapping = self.engine.midi_mapping;
+            let channels = if mapping.click_channel == mapping.channel {
+                format!("{}", mapping.channel + 1)
+            } else {
+                format!("{}/{}", mapping.channel + 1, mapping.click_channel + 1)
+            };
+            lines.push(Line::from(format!(
+                "MIDI:       {}/{} ch {}",
+                mapping.kick_note, mapping.click_note, channels
+            )));
+        }
+
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Session "))
+    }
+
+    fn history_pane(&self, height: u16) -> List<'_> {
+        let session = &self.engine.session;
+        let current_id = session.current_pattern.as_ref().map(|p| p.id);
+        let tag = self.history_tag.as_deref();
+        let items: Vec<ListItem> = session
+            .pattern_history
+            .iter_recent()
+            .enumerate()
+            .filter(|(_, entry)| {
+                tag.is_none_or(|tag| {
+                    session
+                        .note_for(entry.number)
+                        .is_some_and(|note| note.has_tag(tag))
+                })
+            })
+            .take(usize::from(height.saturating_sub(2)))
+            .map(|(i, entry)| {
+                let current = Some(entry.pattern.id) == current_id;
+                let note = session
+                    .note_for(entry.number)
+                    .map(|note| format!("  {}", note))
+                    .unwrap_or_default();
+                let line = format!(
+                    "{} {:>2}  #{:<4} {:>5.1}%  {} kicks{}{}",
+                    if current { "▶" } else { " " },
+                    i + 1,
+                    entry.number,
+                    entry.pattern.density() * 100.0,
+                    entry.pattern.note_positions().len(),
+                    if entry.revealed { "  revealed" } else { "" },
+                    note
+                );
+                let style = if current {
+                    Style::default().add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(line).style(style)
+            })
+            .collect();
+
+        let title = match tag {
+            Some(tag) => format!(" History {} ", tag),
+            None => String::from(" History "),
+        };
+        List::new(items).block(Block::default().borders(Borders::ALL).title(title))
+    }
+
+    /// Counting grid with the step now playing highlighted; kicks show once revealed
+    fn position_pane(&self) -> Paragraph<'_> {
+        let block = Block::default().borders(Borders::ALL).title(" Position ");
+        let Some(pattern) = &self.engine.session.current_pattern else {
+            return Paragraph::new("").block(block);
+        };
+
+        let playing = self.engine.playback.current_step();
+
+        let mut spans = vec![Span::raw("|")];
+        let separators = separators(pattern);
+        for (i, &has_kick) in pattern.steps.iter().enumerate() {
+            let symbol = match (self.engine.session.pattern_revealed, has_kick) {
+                (false, _) => "·",
+                (true, true) => "X",
+                (true, false) => ".",
+            };
+            let mut style = if playing == Some(i) {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            if let (Some(theme), "X") = (self.options.palette.theme(), symbol) {
+                style = style.fg(tui_color(theme.kick)).add_modifier(Modifier::BOLD);
+            }
+            spans.push(Span::styled(symbol, style));
+            spans.push(Span::raw(separators[i]));
+        }
+
+        let labels = count_row(pattern).trim_end().to_string();
+        let labels = match self.options.palette.theme() {
+            // Beat numbers in the theme's color, the subdivisions between them plain
+            Some(theme) => Line::from(
+                labels
+                    .chars()
+                    .map(|c| {
+                        let style = if c.is_ascii_digit() {
+                            Style::default()
+                                .fg(tui_color(theme.beat))
+                                .add_modifier(Modifier::BOLD)
+                        } else {
+                            Style::default()
+                        };
+                        Span::styled(c.to_string(), style)
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            None => Line::from(labels),
+        };
+        Paragraph::new(vec![labels, Line::from(spans)]).block(block)
+    }
+
+    fn pattern_pane(&self) -> Paragraph<'_> {
+        let text = match &self.pattern_pane {
+            Some(text) => Text::from(text.as_str()),
+            None => Text::from(format!(
+                "Hidden. Press {} to reveal or {} to enter your answer.",
+                self.options.keys.label(Action::Reveal),
+                self.options.keys.label(Action::Answer)
+            )),
+        };
+        Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title(" Pattern "))
+    }
+
+    /// The command bar's list of keys, spelled out when any is rebound
+    fn key_hints(&self) -> String {
+        if self.options.keys.is_default() {
+            return String::from(
+                "[r]eveal [e]nter [n]ew [b]ack [t]empo [c]omplexity [h]istory [m]ute [p]ad [a]uto m[o]rph [q]uit",
+            );
+        }
+        Action::ALL
+            .iter()
+            .filter(|&&action| action != Action::Diagnostics)
+            .map(|&action| format!("{} {}", self.options.keys.label(action), action.name()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn command_bar(&self) -> Paragraph<'_> {
+        let input = match &self.prompt {
+            Some((prompt, input)) => Line::from(vec![
+                Span::styled(
+                    format!("{}: ", prompt.label()),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(input.as_str()),
+                Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK)),
+            ]),
+            None => Line::from(self.key_hints()),
+        };
+        Paragraph::new(vec![Line::from(self.message.as_str()), input])
+            .block(Block::default().borders(Borders::ALL))
+    }
+}
+
+impl Drop for TuiApp {
+    fn drop(&mut self) {
+        // Ensure raw mode is disabled; the engine stops playback itself
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Current terminal width in columns, for laying out reveals
+fn terminal_width() -> usize {
+    crossterm::terminal::size().map_or(80, |(width, _)| usize::from(width))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{KeyBindings, KeysSection};
+    use crate::models::{PracticeSession, TimeSignature};
+    use crossterm::event::KeyModifiers;
+
+    fn app() -> TuiApp {
+        let session =
+            PracticeSession::new(120, ComplexityLevel::Medium, TimeSignature::four_four());
+        TuiApp::new(PracticeEngine::new(session), SessionOptions::new())
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_tempo_prompt_updates_session() {
+        let mut app = app();
+        app.handle_key(key(KeyCode::Char('t'))).unwrap();
+        for c in "90".chars() {
+            app.handle_key(key(KeyCode::Char(c))).unwrap();
+        }
+        assert_eq!(app.prompt, Some((Prompt::Tempo, String::from("90"))));
+
+        app.handle_key(key(KeyCode::Enter)).unwrap();
+        assert_eq!(app.prompt, None);
+        assert_eq!(app.engine.session.tempo_bpm, 90);
+    }
+
+    #[test]
+    fn test_escape_cancels_prompt_and_q_quits() {
+        let mut app = app();
+        app.handle_key(key(KeyCode::Char('e'))).unwrap();
+        // Typed keys go into the prompt instead of running commands
+        assert!(!app.handle_key(key(KeyCode::Char('q'))).unwrap());
+        app.handle_key(key(KeyCode::Esc)).unwrap();
+        assert_eq!(app.prompt, None);
+        assert!(app.handle_key(key(KeyCode::Char('q'))).unwrap());
+    }
+
+    #[test]
+    fn test_rebound_keys_run_commands() {
+        let section = KeysSection {
+            complexity: Some('k'),
+            quit: Some('0'),
+            ..Default::default()
+        };
+        let session =
+            PracticeSession::new(120, ComplexityLevel::Medium, TimeSignature::four_four());
+        let keys = KeyBindings::from_section(&section).unwrap();
+        let mut app = TuiApp::new(
+            PracticeEngine::new(session),
+            SessionOptions::new().with_key_bindings(keys),
+        );
+
+        app.handle_key(key(KeyCode::Char('c'))).unwrap();
+        assert_eq!(app.engine.session.complexity_level, ComplexityLevel::Medium);
+        app.handle_key(key(KeyCode::Char('K'))).unwrap();
+        assert_eq!(
+            app.engine.session.complexity_level,
+            ComplexityLevel::Complex
+        );
+        assert!(app.message.contains("Press [n]"));
+        assert!(app.key_hints().contains("[k] complexity"));
+        assert!(!app.handle_key(key(KeyCode::Char('q'))).unwrap());
+        assert!(app.handle_key(key(KeyCode::Char('0'))).unwrap());
+    }
+
+    #[test]
+    fn test_draws_panes_and_hides_unrevealed_kicks() {
+        let mut app = app();
+        let steps = (0..16).map(|i| i % 4 == 0).collect();
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium);
+        app.engine.session.add_to_history(pattern.clone());
+        app.engine.session.current_pattern = Some(pattern);
+
+        let render = |app: &TuiApp| {
+            let mut terminal = Terminal::new(ratatui::backend::TestBackend::new(100, 30)).unwrap();
+            terminal.draw(|frame| app.draw(frame)).unwrap();
+            let buffer = terminal.backend().buffer().clone();
+            buffer
+                .content
+                .iter()
+                .map(|cell| cell.symbol.as_str())
+                .collect::<String>()
+        };
+
+        let screen = render(&app);
+        for title in ["Session", "History", "Position", "Pattern", "[q]uit"] {
+            assert!(screen.contains(title), "missing {}", title);
+        }
+        assert!(screen.contains("|1 e + a |2 e + a |"));
+        assert!(!screen.contains('X'));
+
+        app.handle_key(key(KeyCode::Char('r'))).unwrap();
+        let screen = render(&app);
+        assert!(screen.contains("X . . . |"));
+        assert!(screen.contains("Pattern revealed"));
+    }
+
+    #[test]
+    fn test_complexity_cycles() {
+        let mut app = app();
+        app.handle_key(key(KeyCode::Char('c'))).unwrap();
+        assert_eq!(
+            app.engine.session.complexity_level,
+            ComplexityLevel::Complex
+        );
+        app.handle_key(key(KeyCode::Char('c'))).unwrap();
+        assert_eq!(app.engine.session.complexity_level, ComplexityLevel::Simple);
+    }
+
+    #[test]
+    fn test_auto_advance_toggles_and_sets_the_loop_limit() {
+        let mut app = app();
+        app.handle_key(key(KeyCode::Char('a'))).unwrap();
+        assert!(app.engine.session.auto_advance);
+        assert_eq!(app.engine.session.loop_limit(), Some(4));
+        assert!(app.message.contains("every 4 loops"));
+
+        app.handle_key(key(KeyCode::Char('a'))).unwrap();
+        assert!(!app.engine.session.auto_advance);
+        assert_eq!(app.engine.session.loop_limit(), None);
+
+        // Drills already stop on their own
+        app.engine.session.drill = Some("4loops/20s".parse().unwrap());
+        app.handle_key(key(KeyCode::Char('a'))).unwrap();
+        assert!(!app.engine.session.auto_advance);
+        assert!(app.message.contains("timed drill"));
+    }
+}
@@ -0,0 +1,191 @@
+use crate::models::PracticeSession;
+use serde_json::{json, Value};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How long a webhook request may take before it is abandoned
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Session lifecycle events reported to the webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEvent {
+    /// Practice session began
+    Start,
+    /// Practice session ended (user quit)
+    End,
+}
+
+impl SessionEvent {
+    /// Name used in the JSON payload
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionEvent::Start => "session_start",
+            SessionEvent::End => "session_end",
+        }
+    }
+}
+
+/// Posts session summaries to an external practice tracker
+///
+/// Each event is sent as a JSON `POST` on a background thread so a slow
+/// endpoint never delays playback. Call [`Webhook::finish`] before exiting to
+/// wait for outstanding requests and collect any errors.
+pub struct Webhook {
+    /// Endpoint receiving the JSON payloads
+    url: String,
+    /// Requests still in flight
+    pending: Vec<thread::JoinHandle<Result<(), String>>>,
+}
+
+impl Webhook {
+    /// Create a webhook for an http(s) URL
+    pub fn new(url: &str) -> Result<Self, String> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!(
+                "Invalid webhook URL '{}'. Must start with http:// or https://",
+                url
+            ));
+        }
+
+        Ok(Self {
+            url: url.to_string(),
+            pending: Vec::new(),
+        })
+    }
+
+    /// Send an event with the current session summary in the background
+    pub fn notify(&mut self, event: SessionEvent, session: &PracticeSession) {
+        let url = self.url.clone();
+        let body = session_payload(event, session);
+
+        self.pending.push(thread::spawn(move || {
+            ureq::post(&url)
+                .timeout(REQUEST_TIMEOUT)
+                .send_json(body)
+                .map(|_| ())
+                .map_err(|e| format!("{} webhook failed: {}", event.as_str(), e))
+        }));
+    }
+
+    /// Wait for all pending requests, returning the errors of any that failed
+    pub fn finish(&mut self) -> Vec<String> {
+        self.pending
+            .drain(..)
+            .filter_map(|handle| match handle.join() {
+                Ok(result) => result.err(),
+                Err(_) => Some("Webhook thread panicked".to_string()),
+            })
+            .collect()
+    }
+}
+
+/// Seconds since the Unix epoch, or 0 for times before it
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// JSON summary of a session for webhook consumers
+pub fn session_payload(event: SessionEvent, session: &PracticeSession) -> Value {
+    let duration_secs = session
+        .last_activity
+        .duration_since(session.session_start)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    json!({
+        "event": event.as_str(),
+        "session_id": session.session_id.to_string(),
+        "started_at": unix_secs(session.session_start),
+        "timestamp": unix_secs(SystemTime::now()),
+        "duration_secs": duration_secs,
+        "tempo_bpm": session.tempo_bpm,
+        "complexity": format!("{:?}", session.complexity_level).to_lowercase(),
+        "time_signature": format!(
+            "{}/{}",
+            session.time_signature.numerator, session.time_signature.denominator
+        ),
+        "patterns_generated": session.patterns_generated,
+        "answers_checked": session.answer_accuracies.len(),
+        "average_accuracy": session.average_accuracy(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_rejects_non_http_url() {
+        assert!(Webhook::new("ftp://example.com").is_err());
+        assert!(Webhook::new("https://example.com/hook").is_ok());
+    }
+
+    #[test]
+    fn test_session_payload() {
+        let mut session = PracticeSession {
+            patterns_generated: 3,
+            ..Default::default()
+        };
+        session.record_answer(0.5);
+        session.record_answer(1.0);
+
+        let payload = session_payload(SessionEvent::End, &session);
+
+        assert_eq!(payload["event"], "session_end");
+        assert_eq!(payload["session_id"], session.session_id.to_string());
+        assert_eq!(payload["tempo_bpm"], 120);
+        assert_eq!(payload["complexity"], "medium");
+        assert_eq!(payload["time_signature"], "4/4");
+        assert_eq!(payload["patterns_generated"], 3);
+        assert_eq!(payload["answers_checked"], 2);
+        assert_eq!(payload["average_accuracy"], 0.75);
+    }
+
+    #[test]
+    fn test_notify_posts_json() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header == "\r\n" {
+                    break;
+                }
+                if let Some(value) = header.to_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+
+            (request_line, String::from_utf8(body).unwrap())
+        });
+
+        let mut webhook = Webhook::new(&url).unwrap();
+        webhook.notify(SessionEvent::Start, &PracticeSession::default());
+        assert!(webhook.finish().is_empty());
+
+        let (request_line, body) = server.join().unwrap();
+        assert!(request_line.starts_with("POST /hook"));
+        let payload: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(payload["event"], "session_start");
+    }
+}
@@ -0,0 +1,185 @@
+use crate::config::{Theme, ThemeColor};
+use crossterm::style::{Color, Stylize};
+use std::io::IsTerminal;
+
+/// Whether `--color auto` colors output: stdout is a terminal that isn't
+/// `dumb`, and `NO_COLOR` isn't set (see <https://no-color.org>)
+pub fn stdout_supports_color() -> bool {
+    supports_color(
+        std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()),
+        std::env::var("TERM").ok().as_deref(),
+        std::io::stdout().is_terminal(),
+    )
+}
+
+/// Whether to color a terminal given `NO_COLOR`, `TERM`, and whether it's a terminal at all
+fn supports_color(no_color: bool, term: Option<&str>, terminal: bool) -> bool {
+    terminal && !no_color && term != Some("dumb")
+}
+
+/// The terminal color for a theme color
+pub fn terminal_color(color: ThemeColor) -> Color {
+    match color {
+        ThemeColor::Black => Color::Black,
+        ThemeColor::DarkGrey => Color::DarkGrey,
+        ThemeColor::Red => Color::Red,
+        ThemeColor::DarkRed => Color::DarkRed,
+        ThemeColor::Green => Color::Green,
+        ThemeColor::DarkGreen => Color::DarkGreen,
+        ThemeColor::Yellow => Color::Yellow,
+        ThemeColor::DarkYellow => Color::DarkYellow,
+        ThemeColor::Blue => Color::Blue,
+        ThemeColor::DarkBlue => Color::DarkBlue,
+        ThemeColor::Magenta => Color::Magenta,
+        ThemeColor::DarkMagenta => Color::DarkMagenta,
+        ThemeColor::Cyan => Color::Cyan,
+        ThemeColor::DarkCyan => Color::DarkCyan,
+        ThemeColor::White => Color::White,
+        ThemeColor::Grey => Color::Grey,
+        ThemeColor::Rgb(r, g, b) => Color::Rgb { r, g, b },
+    }
+}
+
+/// The full-screen interface's color for a theme color
+pub fn tui_color(color: ThemeColor) -> ratatui::style::Color {
+    use ratatui::style::Color as Tui;
+    // Crossterm names the bright colors plainly; ratatui names the dim ones plainly
+    match color {
+        ThemeColor::Black => Tui::Black,
+        ThemeColor::DarkGrey => Tui::DarkGray,
+        ThemeColor::Red => Tui::LightRed,
+        ThemeColor::DarkRed => Tui::Red,
+        ThemeColor::Green => Tui::LightGreen,
+        ThemeColor::DarkGreen => Tui::Green,
+        ThemeColor::Yellow => Tui::LightYellow,
+        ThemeColor::DarkYellow => Tui::Yellow,
+        ThemeColor::Blue => Tui::LightBlue,
+        ThemeColor::DarkBlue => Tui::Blue,
+        ThemeColor::Magenta => Tui::LightMagenta,
+        ThemeColor::DarkMagenta => Tui::Magenta,
+        ThemeColor::Cyan => Tui::LightCyan,
+        ThemeColor::DarkCyan => Tui::Cyan,
+        ThemeColor::White => Tui::White,
+        ThemeColor::Grey => Tui::Gray,
+        ThemeColor::Rgb(r, g, b) => Tui::Rgb(r, g, b),
+    }
+}
+
+/// Colors printed output in a [`Theme`], or leaves it plain
+///
+/// Works on text the visualizers already rendered: kicks (`X` in grids, `o`
+/// in tabs) and the beat numbers of counting rows are picked out line by line,
+/// so every reveal style and the answer diff are colored the same way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Palette {
+    /// Colors to use, or `None` for plain output
+    theme: Option<Theme>,
+}
+
+impl Palette {
+    /// Color in `theme`
+    pub fn new(theme: Theme) -> Self {
+        Self { theme: Some(theme) }
+    }
+
+    /// Leave output plain
+    pub fn plain() -> Self {
+        Self::default()
+    }
+
+    /// The theme output is colored in, if it's colored
+    pub fn theme(&self) -> Option<Theme> {
+        self.theme
+    }
+
+    /// Color the kicks and beat numbers of rendered reveal text
+    pub fn reveal(&self, text: &str) -> String {
+        if self.theme.is_none() {
+            return text.to_string();
+        }
+        text.split_inclusive('\n')
+            .map(|line| self.line(line))
+            .collect()
+    }
+
+    /// Color one line: a kick row's kicks or a counting row's beat numbers
+    fn line(&self, line: &str) -> String {
+        let Some((theme, bar)) = self.theme.zip(line.find('|')) else {
+            return line.to_string();
+        };
+        let grid = &line[bar..];
+        let body = grid.trim_end();
+        if body.chars().all(|c| "|X.?^ ".contains(c)) {
+            return self.paint(line, |c| c == 'X', theme.kick);
+        }
+        if body.chars().all(|c| "|o-".contains(c)) {
+            return self.paint(line, |c| c == 'o', theme.kick);
+        }
+        if grid[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            let (label, row) = line.split_at(bar);
+            return format!(
+                "{}{}",
+                label,
+                self.paint(row, |c| c.is_ascii_digit(), theme.beat)
+            );
+        }
+        line.to_string()
+    }
+
+    /// Color the kicks of a kick row (or any part of one)
+    pub fn kicks(&self, row: &str) -> String {
+        match self.theme {
+            Some(theme) => self.paint(row, |c| c == 'X', theme.kick),
+            None => row.to_string(),
+        }
+    }
+
+    /// Color the countdown to the count-in
+    pub fn count_in(&self, text: &str) -> String {
+        match self.theme {
+            Some(theme) => text.with(terminal_color(theme.count_in)).to_string(),
+            None => text.to_string(),
+        }
+    }
+
+    /// Color the characters of `text` that match
+    fn paint(&self, text: &str, matches: impl Fn(char) -> bool, color: ThemeColor) -> String {
+        let color = terminal_color(color);
+        text.chars()
+            .map(|c| {
+                if matches(c) {
+                    c.with(color).bold().to_string()
+                } else {
+                    c.to_string()
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_color_needs_a_terminal_without_no_color() {
+        assert!(supports_color(false, Some("xterm-256color"), true));
+        assert!(!supports_color(true, Some("xterm-256color"), true));
+        assert!(!supports_color(false, Some("dumb"), true));
+        assert!(!supports_color(false, None, false));
+    }
+
+    #[test]
+    fn test_colors_kicks_and_beat_numbers() {
+        let palette = Palette::new(Theme::default());
+        let reveal = "Time: 4/4 | Density: 25.0%\n\n|1 e + a |2 e + a |\n|X . . . |. . X . |\n";
+        let colored = palette.reveal(reveal);
+
+        let kick = 'X'.with(Color::Yellow).bold().to_string();
+        let beat = '2'.with(Color::Cyan).bold().to_string();
+        assert!(colored.starts_with("Time: 4/4 | Density: 25.0%\n\n|"));
+        assert!(colored.contains(&format!("|{} . . . |", kick)));
+        assert!(colored.contains(&format!("|{} e + a |", beat)));
+        assert_eq!(Palette::plain().reveal(reveal), reveal);
+    }
+}
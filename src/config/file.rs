@@ -0,0 +1,530 @@
+use crate::config::keys::{KeyBindings, KeysSection};
+use crate::config::theme::ThemeSection;
+use crate::error::KickbeatsError;
+use crate::models::{ComplexityLevel, TimeSignature};
+use crate::storage::StorageBackend;
+use serde::{Deserialize, Deserializer};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Highest gain `[samples]` can turn a voice up to
+const MAX_SAMPLE_GAIN: f32 = 2.0;
+
+/// Settings read from a kickbeats TOML configuration file
+///
+/// Every section and key is optional; command-line flags override them.
+///
+/// ```toml
+/// [practice]
+/// tempo = 100
+/// complexity = "simple"
+/// time_signature = "3/4"
+///
+/// [midi]
+/// port = "IAC Driver"
+/// kick_note = 36
+/// click_note = 37
+/// channel = 10
+///
+/// [click]
+/// enabled = true
+///
+/// [keys]
+/// reveal = "s"
+///
+/// [webhooks]
+/// url = "https://tracker.example.com/kickbeats"
+///
+/// [kit]
+/// snare = [38, 40]
+/// hat = [42, 46, 26]
+///
+/// [samples]
+/// kick = "/home/me/samples/kick.wav"
+/// click_gain = 0.6
+///
+/// [storage]
+/// backend = "json"
+/// dir = "/home/me/practice-history"
+///
+/// [theme]
+/// kick = "red"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Starting tempo, complexity, and meter
+    pub practice: PracticeSection,
+    /// Output port, note, and channel mapping for playback
+    pub midi: MidiSection,
+    /// Click track options
+    pub click: ClickSection,
+    /// Keys for the interactive commands
+    pub keys: KeysSection,
+    /// Practice tracker notified when sessions start and end
+    pub webhooks: WebhooksSection,
+    /// Which of an electronic kit's notes each limb plays
+    pub kit: KitSection,
+    /// Sounds and levels `kickbeats audio` renders with
+    pub samples: SamplesSection,
+    /// Where practice history is kept
+    pub storage: StorageSection,
+    /// Colors of kicks, beat numbers, and the count-in
+    pub theme: ThemeSection,
+}
+
+/// The `[practice]` section: defaults for a new session
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PracticeSection {
+    /// Tempo in beats per minute (40-300)
+    pub tempo: Option<u16>,
+    /// Complexity level ("simple", "medium", or "complex")
+    #[serde(deserialize_with = "parse_str")]
+    pub complexity: Option<ComplexityLevel>,
+    /// Time signature (e.g., "6/8")
+    #[serde(deserialize_with = "parse_str")]
+    pub time_signature: Option<TimeSignature>,
+}
+
+/// The `[midi]` section: where playback is sent and on which notes and channel
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MidiSection {
+    /// Output port to play on (any part of its name) instead of the first one
+    pub port: Option<String>,
+    /// Note number for kick hits (0-127)
+    pub kick_note: Option<u8>,
+    /// Note number for the click track and count-in (0-127)
+    pub click_note: Option<u8>,
+    /// MIDI channel as numbered on instruments (1-16)
+    pub channel: Option<u8>,
+}
+
+/// The `[click]` section
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClickSection {
+    /// Whether the click plays when a session starts (it can still be toggled)
+    pub enabled: Option<bool>,
+}
+
+/// The `[webhooks]` section: where session summaries are posted
+///
+/// Only used by builds with the `webhooks` feature; `--webhook` overrides it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhooksSection {
+    /// http(s) URL a JSON session summary is POSTed to when practice starts and ends
+    pub url: Option<String>,
+}
+
+/// The `[kit]` section: notes an electronic kit (`--kit-input`) sends from each pad
+///
+/// Each list replaces the General MIDI notes for that limb; a note can only
+/// belong to one limb.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KitSection {
+    /// Notes from the kick pad (0-127)
+    pub kick: Option<Vec<u8>>,
+    /// Notes from the snare pad, head and rim (0-127)
+    pub snare: Option<Vec<u8>>,
+    /// Notes from the hi-hat, open, closed, and pedal (0-127)
+    pub hat: Option<Vec<u8>>,
+}
+
+/// The `[samples]` section: WAV files and levels for each voice of rendered audio
+///
+/// Voices without a sample use the built-in sounds; gains scale each voice
+/// from 0 (silent) to 2 (doubled), 1 leaving it as recorded.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SamplesSection {
+    /// Sample played for each kick
+    pub kick: Option<PathBuf>,
+    /// Sample played for the click and count-in
+    pub click: Option<PathBuf>,
+    /// Sample played for clave and tresillo overlays
+    pub snare: Option<PathBuf>,
+    /// Sample played for the eighths overlay
+    pub hat: Option<PathBuf>,
+    /// Level of the kicks
+    pub kick_gain: Option<f32>,
+    /// Level of the click and count-in
+    pub click_gain: Option<f32>,
+    /// Level of the snare
+    pub snare_gain: Option<f32>,
+    /// Level of the hi-hat
+    pub hat_gain: Option<f32>,
+}
+
+impl SamplesSection {
+    /// Each voice's sample and gain, in kick, click, snare, hat order
+    pub fn voices(&self) -> [(&'static str, Option<&Path>, Option<f32>); 4] {
+        [
+            ("kick", self.kick.as_deref(), self.kick_gain),
+            ("click", self.click.as_deref(), self.click_gain),
+            ("snare", self.snare.as_deref(), self.snare_gain),
+            ("hat", self.hat.as_deref(), self.hat_gain),
+        ]
+    }
+}
+
+/// The `[storage]` section: how and where finished sessions are saved
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageSection {
+    /// History format ("json", or "sqlite" when built with the `sqlite` feature)
+    #[serde(deserialize_with = "parse_str")]
+    pub backend: Option<StorageBackend>,
+    /// Directory the history is kept in instead of the backend's default
+    pub dir: Option<PathBuf>,
+}
+
+/// Deserialize a string with the type's own `FromStr` (and its error messages)
+pub(crate) fn parse_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err = String>,
+{
+    let s = String::deserialize(deserializer)?;
+    s.parse().map(Some).map_err(serde::de::Error::custom)
+}
+
+impl Config {
+    /// Where the configuration file is looked for when `--config` isn't given
+    ///
+    /// `$XDG_CONFIG_HOME/kickbeats/config.toml`, falling back to
+    /// `~/.config/kickbeats/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(base.join("kickbeats").join("config.toml"))
+    }
+
+    /// Load the file at [`Config::default_path`], or an empty configuration if there is none
+    pub fn load_default() -> Result<Self, KickbeatsError> {
+        match Self::default_path() {
+            Some(path) if path.exists() => Self::load(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Parse and validate a TOML configuration document
+    pub fn from_toml_str(s: &str) -> Result<Self, KickbeatsError> {
+        let config: Config = toml::from_str(s)
+            .map_err(|e| KickbeatsError::Config(format!("Invalid config file: {}", e)))?;
+        config.validate().map_err(KickbeatsError::Config)?;
+        Ok(config)
+    }
+
+    /// Load a configuration file
+    pub fn load(path: &Path) -> Result<Self, KickbeatsError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| KickbeatsError::io("read config file", path, e))?;
+        Self::from_toml_str(&contents)
+    }
+
+    /// Fill whatever this configuration leaves unset from `fallback`
+    pub fn or(self, fallback: Config) -> Config {
+        Config {
+            practice: PracticeSection {
+                tempo: self.practice.tempo.or(fallback.practice.tempo),
+                complexity: self.practice.complexity.or(fallback.practice.complexity),
+                time_signature: self
+                    .practice
+                    .time_signature
+                    .or(fallback.practice.time_signature),
+            },
+            midi: MidiSection {
+                port: self.midi.port.or(fallback.midi.port),
+                kick_note: self.midi.kick_note.or(fallback.midi.kick_note),
+                click_note: self.midi.click_note.or(fallback.midi.click_note),
+                channel: self.midi.channel.or(fallback.midi.channel),
+            },
+            click: ClickSection {
+                enabled: self.click.enabled.or(fallback.click.enabled),
+            },
+            keys: self.keys.or(fallback.keys),
+            webhooks: WebhooksSection {
+                url: self.webhooks.url.or(fallback.webhooks.url),
+            },
+            kit: KitSection {
+                kick: self.kit.kick.or(fallback.kit.kick),
+                snare: self.kit.snare.or(fallback.kit.snare),
+                hat: self.kit.hat.or(fallback.kit.hat),
+            },
+            samples: SamplesSection {
+                kick: self.samples.kick.or(fallback.samples.kick),
+                click: self.samples.click.or(fallback.samples.click),
+                snare: self.samples.snare.or(fallback.samples.snare),
+                hat: self.samples.hat.or(fallback.samples.hat),
+                kick_gain: self.samples.kick_gain.or(fallback.samples.kick_gain),
+                click_gain: self.samples.click_gain.or(fallback.samples.click_gain),
+                snare_gain: self.samples.snare_gain.or(fallback.samples.snare_gain),
+                hat_gain: self.samples.hat_gain.or(fallback.samples.hat_gain),
+            },
+            storage: StorageSection {
+                backend: self.storage.backend.or(fallback.storage.backend),
+                dir: self.storage.dir.or(fallback.storage.dir),
+            },
+            theme: self.theme.or(fallback.theme),
+        }
+    }
+
+    /// Check value ranges that TOML types alone don't enforce
+    fn validate(&self) -> Result<(), String> {
+        if let Some(tempo) = self.practice.tempo.filter(|t| !(40..=300).contains(t)) {
+            return Err(format!(
+                "Invalid config file: practice.tempo {} must be between 40 and 300",
+                tempo
+            ));
+        }
+
+        for (key, note) in [
+            ("kick_note", self.midi.kick_note),
+            ("click_note", self.midi.click_note),
+        ] {
+            if let Some(note) = note.filter(|&n| n > 127) {
+                return Err(format!(
+                    "Invalid config file: midi.{} {} must be between 0 and 127",
+                    key, note
+                ));
+            }
+        }
+
+        if let Some(channel) = self.midi.channel.filter(|c| !(1..=16).contains(c)) {
+            return Err(format!(
+                "Invalid config file: midi.channel {} must be between 1 and 16",
+                channel
+            ));
+        }
+
+        KeyBindings::from_section(&self.keys).map_err(|e| format!("Invalid config file: {}", e))?;
+
+        if let Some(url) = self
+            .webhooks
+            .url
+            .as_deref()
+            .filter(|url| !url.starts_with("http://") && !url.starts_with("https://"))
+        {
+            return Err(format!(
+                "Invalid config file: webhooks.url '{}' must start with http:// or https://",
+                url
+            ));
+        }
+
+        let limbs = [
+            ("kick", &self.kit.kick),
+            ("snare", &self.kit.snare),
+            ("hat", &self.kit.hat),
+        ];
+        for (i, &(key, notes)) in limbs.iter().enumerate() {
+            for &note in notes.iter().flatten() {
+                if note > 127 {
+                    return Err(format!(
+                        "Invalid config file: kit.{} note {} must be between 0 and 127",
+                        key, note
+                    ));
+                }
+                if let Some((other, _)) = limbs[i + 1..]
+                    .iter()
+                    .find(|(_, notes)| notes.iter().flatten().any(|&n| n == note))
+                {
+                    return Err(format!(
+                        "Invalid config file: note {} is in both kit.{} and kit.{}",
+                        note, key, other
+                    ));
+                }
+            }
+        }
+
+        for (voice, _, gain) in self.samples.voices() {
+            if let Some(gain) = gain.filter(|gain| !(0.0..=MAX_SAMPLE_GAIN).contains(gain)) {
+                return Err(format!(
+                    "Invalid config file: samples.{}_gain {} must be between 0 and {}",
+                    voice, gain, MAX_SAMPLE_GAIN
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ThemeColor;
+
+    #[test]
+    fn test_parse_midi_section() {
+        let config = Config::from_toml_str("[midi]\nkick_note = 48\nchannel = 1\n").unwrap();
+        assert_eq!(
+            config.midi,
+            MidiSection {
+                port: None,
+                kick_note: Some(48),
+                click_note: None,
+                channel: Some(1),
+            }
+        );
+        assert_eq!(Config::from_toml_str("").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_parse_practice_and_click_sections() {
+        let config = Config::from_toml_str(
+            "[practice]\ntempo = 90\ncomplexity = \"simple\"\ntime_signature = \"6/8\"\n\
+             [midi]\nport = \"IAC\"\n[click]\nenabled = false\n",
+        )
+        .unwrap();
+        assert_eq!(config.practice.tempo, Some(90));
+        assert_eq!(config.practice.complexity, Some(ComplexityLevel::Simple));
+        assert_eq!(
+            config.practice.time_signature,
+            Some(TimeSignature::six_eight())
+        );
+        assert_eq!(config.midi.port.as_deref(), Some("IAC"));
+        assert_eq!(config.click.enabled, Some(false));
+    }
+
+    #[test]
+    fn test_parse_keys_section() {
+        let config = Config::from_toml_str("[keys]\nreveal = \"s\"\nquit = \"0\"\n").unwrap();
+        let keys = KeyBindings::from_section(&config.keys).unwrap();
+        assert_eq!(keys.key(crate::config::Action::Reveal), 's');
+        assert!(Config::from_toml_str("[keys]\nreveal = \"sv\"\n").is_err());
+        assert!(Config::from_toml_str("[keys]\nfly = \"f\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_webhooks_section() {
+        let config =
+            Config::from_toml_str("[webhooks]\nurl = \"https://tracker.example.com/kb\"\n")
+                .unwrap();
+        assert_eq!(
+            config.webhooks.url.as_deref(),
+            Some("https://tracker.example.com/kb")
+        );
+        assert!(Config::from_toml_str("[webhooks]\nsecret = \"x\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_samples_section() {
+        let config = Config::from_toml_str(
+            "[samples]\nkick = \"kick.wav\"\nclick_gain = 0.5\nhat_gain = 0\n",
+        )
+        .unwrap();
+        assert_eq!(config.samples.kick, Some(PathBuf::from("kick.wav")));
+        assert_eq!(config.samples.click_gain, Some(0.5));
+        assert_eq!(config.samples.voices()[3], ("hat", None, Some(0.0)));
+        assert!(Config::from_toml_str("[samples]\nsnare_gain = 3.0\n")
+            .unwrap_err()
+            .to_string()
+            .contains("samples.snare_gain 3 must be between 0 and 2"));
+        assert!(Config::from_toml_str("[samples]\ntom = \"tom.wav\"\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_kit_section() {
+        let config = Config::from_toml_str("[kit]\nkick = [36]\nsnare = [38, 40]\n").unwrap();
+        assert_eq!(config.kit.kick, Some(vec![36]));
+        assert_eq!(config.kit.snare, Some(vec![38, 40]));
+        assert_eq!(config.kit.hat, None);
+        assert!(Config::from_toml_str("[kit]\nsnare = [38, 200]\n")
+            .unwrap_err()
+            .to_string()
+            .contains("kit.snare note 200"));
+        assert!(
+            Config::from_toml_str("[kit]\nkick = [36]\nhat = [42, 36]\n")
+                .unwrap_err()
+                .to_string()
+                .contains("note 36 is in both kit.kick and kit.hat")
+        );
+        assert!(Config::from_toml_str("[kit]\ntom = [45]\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_storage_section() {
+        let config = Config::from_toml_str(
+            "[storage]
+backend = \"json\"
+dir = \"/tmp/history\"
+",
+        )
+        .unwrap();
+        assert_eq!(config.storage.backend, Some(StorageBackend::Json));
+        assert_eq!(config.storage.dir, Some(PathBuf::from("/tmp/history")));
+        let sqlite = Config::from_toml_str("[storage]\nbackend = \"sqlite\"\n");
+        #[cfg(feature = "sqlite")]
+        assert_eq!(
+            sqlite.unwrap().storage.backend,
+            Some(StorageBackend::Sqlite)
+        );
+        #[cfg(not(feature = "sqlite"))]
+        assert!(sqlite.unwrap_err().to_string().contains("isn't available"));
+    }
+
+    #[test]
+    fn test_parse_theme_section() {
+        let config =
+            Config::from_toml_str("[theme]\nkick = \"red\"\ncount_in = \"#00ff80\"\n").unwrap();
+        assert_eq!(config.theme.kick, Some(ThemeColor::Red));
+        assert_eq!(config.theme.beat, None);
+        assert_eq!(config.theme.count_in, Some(ThemeColor::Rgb(0, 255, 128)));
+        assert!(Config::from_toml_str("[theme]\nkick = \"orange\"\n")
+            .unwrap_err()
+            .to_string()
+            .contains("Invalid color 'orange'"));
+        assert!(Config::from_toml_str("[theme]\nsnare = \"red\"\n").is_err());
+    }
+
+    #[test]
+    fn test_or_prefers_own_values() {
+        let pack =
+            Config::from_toml_str("[practice]\ntempo = 90\n[midi]\nkick_note = 48\n").unwrap();
+        let local = Config::from_toml_str(
+            "[practice]\ntempo = 120\ncomplexity = \"simple\"\n[midi]\nport = \"IAC\"\n",
+        )
+        .unwrap();
+
+        let merged = pack.or(local);
+        assert_eq!(merged.practice.tempo, Some(90));
+        assert_eq!(merged.practice.complexity, Some(ComplexityLevel::Simple));
+        assert_eq!(merged.midi.kick_note, Some(48));
+        assert_eq!(merged.midi.port.as_deref(), Some("IAC"));
+    }
+
+    #[test]
+    fn test_rejects_invalid_values() {
+        assert!(Config::from_toml_str("[practice]\ntempo = 500\n")
+            .unwrap_err()
+            .to_string()
+            .contains("between 40 and 300"));
+        assert!(Config::from_toml_str("[practice]\ncomplexity = \"hard\"\n").is_err());
+        assert!(Config::from_toml_str("[practice]\ntime_signature = \"4/3\"\n").is_err());
+        assert!(Config::from_toml_str("[midi]\nkick_note = 200\n")
+            .unwrap_err()
+            .to_string()
+            .contains("between 0 and 127"));
+        assert!(Config::from_toml_str("[midi]\nchannel = 0\n")
+            .unwrap_err()
+            .to_string()
+            .contains("between 1 and 16"));
+        assert!(Config::from_toml_str("[midi]\nsnare_note = 38\n").is_err());
+        assert!(
+            Config::from_toml_str("[webhooks]\nurl = \"ftp://tracker\"\n")
+                .unwrap_err()
+                .to_string()
+                .contains("webhooks.url 'ftp://tracker'")
+        );
+        assert!(Config::from_toml_str("[keys]\nreveal = \"n\"\n")
+            .unwrap_err()
+            .to_string()
+            .contains("keys.reveal and keys.new are both on 'n'"));
+    }
+}
@@ -0,0 +1,376 @@
+use crate::error::KickbeatsError;
+use serde::Deserialize;
+
+/// Keys the drum pad uses while it is on, which commands can't be bound to
+pub const PAD_KEYS: [char; 4] = ['f', 'z', 'j', 'x'];
+
+/// A command of the interactive interfaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Show the current pattern
+    Reveal,
+    /// Type an answer and check it
+    Answer,
+    /// Generate and play a new pattern
+    New,
+    /// Return to the previous pattern
+    Back,
+    /// Turn the current pattern into a new one, a step at a time
+    Morph,
+    /// Play a variation of the current pattern (rotate, reverse, ...)
+    Vary,
+    /// Change the tempo
+    Tempo,
+    /// Change the complexity
+    Complexity,
+    /// Browse recent patterns
+    History,
+    /// Note or tag the current pattern
+    Note,
+    /// Play the session's patterns back to back
+    Medley,
+    /// Chain patterns into a song form and play it through
+    Arrange,
+    /// Toggle the click track
+    Mute,
+    /// Make the click louder
+    ClickUp,
+    /// Make the click softer
+    ClickDown,
+    /// Make the kicks louder
+    KickUp,
+    /// Make the kicks softer
+    KickDown,
+    /// Toggle the drum pad
+    Pad,
+    /// Toggle auto-advance
+    Advance,
+    /// Toggle timing diagnostics
+    Diagnostics,
+    /// Stop playback and exit
+    Quit,
+}
+
+impl Action {
+    /// Every command, in the order help lists them
+    pub const ALL: [Action; 21] = [
+        Action::Reveal,
+        Action::Answer,
+        Action::New,
+        Action::Back,
+        Action::Morph,
+        Action::Vary,
+        Action::Tempo,
+        Action::Complexity,
+        Action::History,
+        Action::Note,
+        Action::Medley,
+        Action::Arrange,
+        Action::Mute,
+        Action::ClickUp,
+        Action::ClickDown,
+        Action::KickUp,
+        Action::KickDown,
+        Action::Advance,
+        Action::Diagnostics,
+        Action::Pad,
+        Action::Quit,
+    ];
+
+    /// Key in the `[keys]` section
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Reveal => "reveal",
+            Action::Answer => "answer",
+            Action::New => "new",
+            Action::Back => "back",
+            Action::Morph => "morph",
+            Action::Vary => "vary",
+            Action::Tempo => "tempo",
+            Action::Complexity => "complexity",
+            Action::History => "history",
+            Action::Note => "note",
+            Action::Medley => "medley",
+            Action::Arrange => "arrange",
+            Action::Mute => "mute",
+            Action::ClickUp => "click_up",
+            Action::ClickDown => "click_down",
+            Action::KickUp => "kick_up",
+            Action::KickDown => "kick_down",
+            Action::Pad => "pad",
+            Action::Advance => "advance",
+            Action::Diagnostics => "diagnostics",
+            Action::Quit => "quit",
+        }
+    }
+
+    /// Key the command is on unless rebound
+    pub fn default_key(self) -> char {
+        match self {
+            Action::Reveal => 'r',
+            Action::Answer => 'e',
+            Action::New => 'n',
+            Action::Back => 'b',
+            Action::Morph => 'o',
+            Action::Vary => 'v',
+            Action::Tempo => 't',
+            Action::Complexity => 'c',
+            Action::History => 'h',
+            Action::Note => '#',
+            Action::Medley => 'y',
+            Action::Arrange => 'g',
+            Action::Mute => 'm',
+            Action::ClickUp => '+',
+            Action::ClickDown => '-',
+            Action::KickUp => ']',
+            Action::KickDown => '[',
+            Action::Pad => 'p',
+            Action::Advance => 'a',
+            Action::Diagnostics => 'd',
+            Action::Quit => 'q',
+        }
+    }
+}
+
+/// The `[keys]` section: which key runs each command
+///
+/// Every key is one letter or digit; unset commands keep their default key.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeysSection {
+    /// Show the current pattern [default: r]
+    pub reveal: Option<char>,
+    /// Type an answer [default: e]
+    pub answer: Option<char>,
+    /// New pattern [default: n]
+    pub new: Option<char>,
+    /// Previous pattern [default: b]
+    pub back: Option<char>,
+    /// Morph into a new pattern [default: o]
+    pub morph: Option<char>,
+    /// Play a variation of the current pattern [default: v]
+    pub vary: Option<char>,
+    /// Change the tempo [default: t]
+    pub tempo: Option<char>,
+    /// Change the complexity [default: c]
+    pub complexity: Option<char>,
+    /// Browse recent patterns [default: h]
+    pub history: Option<char>,
+    /// Note or tag the current pattern [default: #]
+    pub note: Option<char>,
+    /// Play the session's patterns back to back [default: y]
+    pub medley: Option<char>,
+    /// Play patterns in a song form [default: g]
+    pub arrange: Option<char>,
+    /// Toggle the click [default: m]
+    pub mute: Option<char>,
+    /// Louder click [default: +]
+    pub click_up: Option<char>,
+    /// Softer click [default: -]
+    pub click_down: Option<char>,
+    /// Louder kicks [default: ]]
+    pub kick_up: Option<char>,
+    /// Softer kicks [default: []
+    pub kick_down: Option<char>,
+    /// Toggle the drum pad [default: p]
+    pub pad: Option<char>,
+    /// Toggle auto-advance [default: a]
+    pub advance: Option<char>,
+    /// Toggle timing diagnostics [default: d]
+    pub diagnostics: Option<char>,
+    /// Quit [default: q]
+    pub quit: Option<char>,
+}
+
+impl KeysSection {
+    /// The key set for `action`, if any
+    pub fn get(&self, action: Action) -> Option<char> {
+        match action {
+            Action::Reveal => self.reveal,
+            Action::Answer => self.answer,
+            Action::New => self.new,
+            Action::Back => self.back,
+            Action::Morph => self.morph,
+            Action::Vary => self.vary,
+            Action::Tempo => self.tempo,
+            Action::Complexity => self.complexity,
+            Action::History => self.history,
+            Action::Note => self.note,
+            Action::Medley => self.medley,
+            Action::Arrange => self.arrange,
+            Action::Mute => self.mute,
+            Action::ClickUp => self.click_up,
+            Action::ClickDown => self.click_down,
+            Action::KickUp => self.kick_up,
+            Action::KickDown => self.kick_down,
+            Action::Pad => self.pad,
+            Action::Advance => self.advance,
+            Action::Diagnostics => self.diagnostics,
+            Action::Quit => self.quit,
+        }
+    }
+
+    /// Fill whatever this section leaves unset from `fallback`
+    pub fn or(self, fallback: KeysSection) -> KeysSection {
+        KeysSection {
+            reveal: self.reveal.or(fallback.reveal),
+            answer: self.answer.or(fallback.answer),
+            new: self.new.or(fallback.new),
+            back: self.back.or(fallback.back),
+            morph: self.morph.or(fallback.morph),
+            vary: self.vary.or(fallback.vary),
+            tempo: self.tempo.or(fallback.tempo),
+            complexity: self.complexity.or(fallback.complexity),
+            history: self.history.or(fallback.history),
+            note: self.note.or(fallback.note),
+            medley: self.medley.or(fallback.medley),
+            arrange: self.arrange.or(fallback.arrange),
+            mute: self.mute.or(fallback.mute),
+            click_up: self.click_up.or(fallback.click_up),
+            click_down: self.click_down.or(fallback.click_down),
+            kick_up: self.kick_up.or(fallback.kick_up),
+            kick_down: self.kick_down.or(fallback.kick_down),
+            pad: self.pad.or(fallback.pad),
+            advance: self.advance.or(fallback.advance),
+            diagnostics: self.diagnostics.or(fallback.diagnostics),
+            quit: self.quit.or(fallback.quit),
+        }
+    }
+}
+
+/// The key each interactive command runs on
+///
+/// Keys are matched without regard to case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBindings {
+    keys: [(Action, char); 21],
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            keys: Action::ALL.map(|action| (action, action.default_key())),
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Apply a `[keys]` section to the default bindings
+    ///
+    /// Refuses keys that aren't a letter or digit, drum pad keys, and two
+    /// commands on the same key.
+    pub fn from_section(section: &KeysSection) -> Result<Self, KickbeatsError> {
+        let mut bindings = Self::default();
+        for (action, key) in bindings.keys.iter_mut() {
+            let Some(bound) = section.get(*action) else {
+                continue;
+            };
+            if !bound.is_ascii_alphanumeric() {
+                return Err(KickbeatsError::Config(format!(
+                    "keys.{} '{}' must be a letter or digit",
+                    action.name(),
+                    bound
+                )));
+            }
+            let bound = bound.to_ascii_lowercase();
+            if PAD_KEYS.contains(&bound) {
+                return Err(KickbeatsError::Config(format!(
+                    "keys.{} '{}' is a drum pad key (f, z, j, x)",
+                    action.name(),
+                    bound
+                )));
+            }
+            *key = bound;
+        }
+
+        for (i, (action, key)) in bindings.keys.iter().enumerate() {
+            if let Some((other, _)) = bindings.keys[i + 1..].iter().find(|(_, k)| k == key) {
+                return Err(KickbeatsError::Config(format!(
+                    "keys.{} and keys.{} are both on '{}'",
+                    action.name(),
+                    other.name(),
+                    key
+                )));
+            }
+        }
+        Ok(bindings)
+    }
+
+    /// The key that runs `action`
+    pub fn key(&self, action: Action) -> char {
+        self.keys
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map_or(action.default_key(), |&(_, key)| key)
+    }
+
+    /// The key for `action` in brackets, as prompts show it (e.g., "[r]")
+    pub fn label(&self, action: Action) -> String {
+        format!("[{}]", self.key(action))
+    }
+
+    /// The command a key press runs, if any
+    pub fn action(&self, key: char) -> Option<Action> {
+        let key = key.to_ascii_lowercase();
+        self.keys
+            .iter()
+            .find(|&&(_, k)| k == key)
+            .map(|&(action, _)| action)
+    }
+
+    /// Whether every command is on its default key
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebinds_and_matches_either_case() {
+        let section = KeysSection {
+            reveal: Some('S'),
+            quit: Some('0'),
+            ..KeysSection::default()
+        };
+        let keys = KeyBindings::from_section(&section).unwrap();
+
+        assert_eq!(keys.action('s'), Some(Action::Reveal));
+        assert_eq!(keys.action('S'), Some(Action::Reveal));
+        assert_eq!(keys.action('r'), None);
+        assert_eq!(keys.action('0'), Some(Action::Quit));
+        assert_eq!(keys.action('n'), Some(Action::New));
+        assert_eq!(keys.label(Action::Reveal), "[s]");
+        assert!(!keys.is_default());
+        assert!(KeyBindings::default().is_default());
+    }
+
+    #[test]
+    fn test_rejects_clashes_and_pad_keys() {
+        let clash = KeysSection {
+            reveal: Some('n'),
+            ..KeysSection::default()
+        };
+        assert!(KeyBindings::from_section(&clash)
+            .unwrap_err()
+            .to_string()
+            .contains("keys.reveal and keys.new are both on 'n'"));
+
+        let pad = KeysSection {
+            quit: Some('x'),
+            ..KeysSection::default()
+        };
+        assert!(KeyBindings::from_section(&pad)
+            .unwrap_err()
+            .to_string()
+            .contains("drum pad key"));
+
+        let space = KeysSection {
+            new: Some(' '),
+            ..KeysSection::default()
+        };
+        assert!(KeyBindings::from_section(&space).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+// Config module
+// Settings loaded from TOML configuration files
+
+pub mod file;
+pub mod keys;
+pub mod pack;
+pub mod theme;
+pub mod watch;
+
+pub use file::{
+    ClickSection, Config, KitSection, MidiSection, PracticeSection, SamplesSection, StorageSection,
+    WebhooksSection,
+};
+pub use keys::{Action, KeyBindings, KeysSection, PAD_KEYS};
+pub use pack::{PackInfo, PlaylistEntry, PracticePack, TeacherKey, PACK_EXTENSION, PACK_FORMAT};
+pub use theme::{Theme, ThemeColor, ThemeSection};
+pub use watch::{ConfigWatcher, SettingsUpdate};
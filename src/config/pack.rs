@@ -0,0 +1,1024 @@
+use crate::config::file::parse_str;
+use crate::config::{
+    Action, ClickSection, Config, KeysSection, KitSection, MidiSection, PracticeSection,
+    SamplesSection, StorageSection, ThemeSection, WebhooksSection,
+};
+use crate::error::KickbeatsError;
+use crate::generator::WeightProfiles;
+use crate::grading::parse_answer;
+use crate::models::{ComplexityLevel, Curriculum, CurriculumStage, Progression, TimeSignature};
+use crate::storage::{write_atomic, DirLock};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::Deserialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// Newest pack format this version of kickbeats reads and writes
+pub const PACK_FORMAT: u32 = 1;
+
+/// File extension for practice packs
+pub const PACK_EXTENSION: &str = "kbpack";
+
+/// The `[pack]` section: what the pack is and which kickbeats can read it
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackInfo {
+    /// Short name packs are installed and selected by (e.g., "funk101")
+    pub name: String,
+    /// Pack release, as dotted numbers (e.g., "1.2.0")
+    pub version: String,
+    /// Pack format version; packs newer than [`PACK_FORMAT`] are refused
+    pub format: u32,
+    /// Who made the pack
+    #[serde(default)]
+    pub author: Option<String>,
+    /// One-line summary shown by `kickbeats pack list`
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Public half of the teacher key that signed the pack, in hex
+    pub public_key: String,
+    /// Ed25519 signature of the rest of the pack, in hex
+    pub signature: String,
+}
+
+impl PackInfo {
+    /// Short form of the signing key, for students to compare with the one
+    /// their teacher publishes
+    pub fn fingerprint(&self) -> &str {
+        fingerprint(&self.public_key)
+    }
+}
+
+/// The ed25519 key a teacher signs practice packs with
+///
+/// Signing proves a pack came from whoever holds the key: installing checks
+/// the signature, shows the key's fingerprint, and won't replace an installed
+/// pack with one signed by a different key unless forced.
+pub struct TeacherKey {
+    pair: Ed25519KeyPair,
+}
+
+impl TeacherKey {
+    /// A new random key
+    pub fn generate() -> Result<Self, KickbeatsError> {
+        let pkcs8 = generate_pkcs8()?;
+        Self::from_pkcs8(pkcs8.as_ref())
+    }
+
+    /// Read a key saved as PKCS#8 (the teacher key file's contents)
+    pub fn from_pkcs8(bytes: &[u8]) -> Result<Self, KickbeatsError> {
+        Ed25519KeyPair::from_pkcs8(bytes)
+            .map(|pair| Self { pair })
+            .map_err(|e| KickbeatsError::Config(format!("Invalid teacher key: {}", e)))
+    }
+
+    /// Load the key at `path`, creating one only you can read if there is none
+    ///
+    /// Also returns whether the key was just created.
+    pub fn load_or_create(path: &Path) -> Result<(Self, bool), KickbeatsError> {
+        if path.exists() {
+            let bytes =
+                std::fs::read(path).map_err(|e| KickbeatsError::io("read teacher key", path, e))?;
+            return Ok((Self::from_pkcs8(&bytes)?, false));
+        }
+
+        let pkcs8 = generate_pkcs8()?;
+        let key = Self::from_pkcs8(pkcs8.as_ref())?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| KickbeatsError::io("create teacher key directory", dir, e))?;
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(path)
+            .and_then(|mut file| file.write_all(pkcs8.as_ref()))
+            .map_err(|e| KickbeatsError::io("write teacher key", path, e))?;
+        Ok((key, true))
+    }
+
+    /// Where the teacher key lives: `teacher.key` next to the default config file
+    pub fn default_path() -> Option<PathBuf> {
+        Some(Config::default_path()?.parent()?.join("teacher.key"))
+    }
+
+    /// Public half of the key, in hex, as written into signed packs
+    pub fn public_key(&self) -> String {
+        to_hex(self.pair.public_key().as_ref())
+    }
+
+    /// Short form of the public key, shown when exporting and installing
+    pub fn fingerprint(&self) -> String {
+        fingerprint(&self.public_key()).to_string()
+    }
+
+    /// Hex signature of the table's canonical TOML, so formatting and comments don't matter
+    fn sign(&self, table: &Table) -> String {
+        to_hex(self.pair.sign(table.to_string().as_bytes()).as_ref())
+    }
+}
+
+/// One pattern of a pack's playlist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistEntry {
+    /// Meter the pattern is played in
+    pub time_signature: TimeSignature,
+    /// Kick (true) or rest (false) at each sixteenth
+    pub steps: Vec<bool>,
+}
+
+/// A `[[playlist]]` table as written
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPlaylistEntry {
+    steps: String,
+    #[serde(default, deserialize_with = "parse_str")]
+    time_signature: Option<TimeSignature>,
+}
+
+/// A `[[curriculum]]` table as written
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawStage {
+    name: String,
+    #[serde(deserialize_with = "parse_str")]
+    complexity: Option<ComplexityLevel>,
+    #[serde(default)]
+    tempo: Option<u16>,
+    /// Accuracy (percent) an answer needs to count toward advancing
+    #[serde(default)]
+    advance_accuracy: Option<u8>,
+    /// Answers in a row at that accuracy needed to advance
+    #[serde(default)]
+    advance_after: Option<u32>,
+}
+
+/// Settings a teacher bundles into one shareable file
+///
+/// A pack is TOML: a `[pack]` header, the `[practice]`, `[midi]`, `[click]`,
+/// and `[keys]` sections of a config file (minus `midi.port`, `[webhooks]`,
+/// `[kit]`, `[samples]`, `[storage]`, and `[theme]`, which differ from player to player), a `[weights]`
+/// generation profile in the `--weights` file format, a `[[playlist]]` of
+/// patterns to play in order, and `[[curriculum]]` stages with the accuracy
+/// that moves a student on to the next one.
+///
+/// ```toml
+/// [pack]
+/// name = "funk101"
+/// version = "1.0.0"
+/// format = 1
+/// public_key = "3d4017c3e843895a..."
+/// signature = "92a009a9f0d4cab8..."
+///
+/// [practice]
+/// tempo = 96
+///
+/// [weights]
+/// "4/4" = [1.0, 0.8, 0.3, 0.1, 0.6, 0.8, 0.3, 0.1, 0.7, 0.8, 0.3, 0.1, 0.6, 0.8, 0.3, 0.1]
+///
+/// [[playlist]]
+/// steps = "x...x.....x.x..."
+///
+/// [[curriculum]]
+/// name = "Downbeats"
+/// complexity = "simple"
+/// tempo = 80
+/// advance_accuracy = 90
+/// advance_after = 3
+///
+/// [[curriculum]]
+/// name = "Syncopation"
+/// complexity = "medium"
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PracticePack {
+    /// Name, version, and format
+    pub info: PackInfo,
+    /// Practice defaults, voice map, and click settings
+    pub config: Config,
+    /// Custom metrical weights for the weighted generator
+    pub weights: WeightProfiles,
+    /// Patterns played in order instead of generated ones
+    pub playlist: Vec<PlaylistEntry>,
+    /// Stages students work through, advancing on answer accuracy
+    pub curriculum: Option<Curriculum>,
+}
+
+impl PracticePack {
+    /// Bundle settings into a new, unsigned pack
+    ///
+    /// Sign it with [`signed`](Self::signed) once it's complete; students
+    /// can't load unsigned packs.
+    pub fn new(
+        name: &str,
+        version: &str,
+        config: Config,
+        weights: WeightProfiles,
+    ) -> Result<Self, KickbeatsError> {
+        validate_name(name).map_err(KickbeatsError::Config)?;
+        parse_version(version).map_err(KickbeatsError::Config)?;
+        check_shareable(&config).map_err(KickbeatsError::Config)?;
+
+        Ok(Self {
+            info: PackInfo {
+                name: name.to_string(),
+                version: version.to_string(),
+                format: PACK_FORMAT,
+                author: None,
+                description: None,
+                public_key: String::new(),
+                signature: String::new(),
+            },
+            config,
+            weights,
+            playlist: Vec::new(),
+            curriculum: None,
+        })
+    }
+
+    /// Play these patterns in order when practicing with the pack
+    pub fn with_playlist(mut self, playlist: Vec<PlaylistEntry>) -> Self {
+        self.playlist = playlist;
+        self
+    }
+
+    /// Work through these stages when practicing with the pack
+    pub fn with_curriculum(mut self, curriculum: Curriculum) -> Self {
+        self.curriculum = Some(curriculum);
+        self
+    }
+
+    /// Credit an author
+    pub fn with_author(mut self, author: &str) -> Self {
+        self.info.author = Some(author.to_string());
+        self
+    }
+
+    /// Describe the pack for `kickbeats pack list`
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.info.description = Some(description.to_string());
+        self
+    }
+
+    /// Sign the pack with `key`, after its last change
+    pub fn signed(mut self, key: &TeacherKey) -> Self {
+        self.info.public_key = key.public_key();
+        self.info.signature = key.sign(&self.to_table());
+        self
+    }
+
+    /// Parse a pack, checking its format version and signature
+    pub fn from_toml_str(s: &str) -> Result<Self, KickbeatsError> {
+        Self::parse(s).map_err(KickbeatsError::Config)
+    }
+
+    /// [`from_toml_str`](Self::from_toml_str), with its reasons as plain text
+    fn parse(s: &str) -> Result<Self, String> {
+        let mut table: Table =
+            toml::from_str(s).map_err(|e| format!("Invalid practice pack: {}", e))?;
+
+        let Some(Value::Table(header)) = table.get("pack") else {
+            return Err("Invalid practice pack: missing [pack] section".to_string());
+        };
+        let info: PackInfo = Value::Table(header.clone())
+            .try_into()
+            .map_err(|e| format!("Invalid practice pack: {}", e))?;
+        if info.format > PACK_FORMAT {
+            return Err(format!(
+                "Practice pack {} uses format {}, but this kickbeats only reads up to format {}; \
+                 please upgrade kickbeats",
+                info.name, info.format, PACK_FORMAT
+            ));
+        }
+        validate_name(&info.name)?;
+        parse_version(&info.version)?;
+
+        // The signature covers everything but itself, including the key that made it
+        if let Some(Value::Table(header)) = table.get_mut("pack") {
+            header.remove("signature");
+        }
+        if !verify(&table, &info.public_key, &info.signature) {
+            return Err(format!(
+                "Practice pack {} failed its signature check; it was edited or damaged after export",
+                info.name
+            ));
+        }
+        table.remove("pack");
+
+        let weights = match table.remove("weights") {
+            Some(Value::Table(weights)) => WeightProfiles::from_toml_str(&weights.to_string())
+                .map_err(|e| format!("Invalid practice pack: {}", e))?,
+            Some(_) => return Err("Invalid practice pack: [weights] must be a table".to_string()),
+            None => WeightProfiles::new(),
+        };
+        let playlist = table.remove("playlist");
+        let curriculum = table.remove("curriculum");
+        let config = Config::from_toml_str(&table.to_string()).map_err(|e| {
+            e.to_string()
+                .replace("Invalid config file", "Invalid practice pack")
+        })?;
+        check_shareable(&config).map_err(|e| format!("Invalid practice pack: {}", e))?;
+
+        let default_meter = config
+            .practice
+            .time_signature
+            .unwrap_or_else(TimeSignature::four_four);
+        let playlist = match playlist {
+            Some(playlist) => parse_playlist_tables(playlist, default_meter)?,
+            None => Vec::new(),
+        };
+        let curriculum = curriculum.map(parse_curriculum_tables).transpose()?;
+
+        Ok(Self {
+            info,
+            config,
+            weights,
+            playlist,
+            curriculum,
+        })
+    }
+
+    /// Parse a playlist file: one pattern per line (`x...x.....x.x...`),
+    /// optionally after its time signature (`3/4 x..x..x.....`)
+    ///
+    /// Patterns without a time signature are in `default_meter`. Blank lines
+    /// and `#` comments are skipped.
+    pub fn parse_playlist(
+        text: &str,
+        default_meter: TimeSignature,
+    ) -> Result<Vec<PlaylistEntry>, KickbeatsError> {
+        text.lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|line| !line.is_empty())
+            .enumerate()
+            .map(|(i, line)| {
+                let (meter, steps) = match line.split_once(char::is_whitespace) {
+                    Some((meter, steps)) if meter.contains('/') => (meter.parse()?, steps),
+                    _ => (default_meter, line),
+                };
+                playlist_entry(i, steps, meter)
+            })
+            .collect::<Result<_, String>>()
+            .map_err(KickbeatsError::Config)
+    }
+
+    /// Parse a curriculum file of `[[curriculum]]` tables, as packs carry them
+    pub fn parse_curriculum(s: &str) -> Result<Curriculum, KickbeatsError> {
+        let mut table: Table = toml::from_str(s)
+            .map_err(|e| KickbeatsError::Config(format!("Invalid curriculum: {}", e)))?;
+        let stages = table.remove("curriculum").ok_or_else(|| {
+            KickbeatsError::Config("Invalid curriculum: no [[curriculum]] stages".to_string())
+        })?;
+        if let Some(key) = table.keys().next() {
+            return Err(KickbeatsError::Config(format!(
+                "Invalid curriculum: unknown section '{}'",
+                key
+            )));
+        }
+        parse_curriculum_tables(stages).map_err(KickbeatsError::Config)
+    }
+
+    /// Load a pack file
+    pub fn load(path: &Path) -> Result<Self, KickbeatsError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| KickbeatsError::io("read practice pack", path, e))?;
+        Self::from_toml_str(&contents)
+    }
+
+    /// Load the installed pack called `name`
+    pub fn load_installed(name: &str) -> Result<Self, KickbeatsError> {
+        let path = Self::installed_path(name)?;
+        if !path.exists() {
+            return Err(KickbeatsError::Config(format!(
+                "No practice pack named {} is installed (see `kickbeats pack list`)",
+                name
+            )));
+        }
+        Self::load(&path)
+    }
+
+    /// Where installed packs live: a `packs` directory next to the default config file
+    pub fn install_dir() -> Option<PathBuf> {
+        Some(Config::default_path()?.parent()?.join("packs"))
+    }
+
+    /// Where the pack called `name` is (or would be) installed
+    pub fn installed_path(name: &str) -> Result<PathBuf, KickbeatsError> {
+        validate_name(name).map_err(KickbeatsError::Config)?;
+        let dir = Self::install_dir().ok_or_else(|| {
+            KickbeatsError::Config(
+                "Can't find a config directory to install practice packs into".to_string(),
+            )
+        })?;
+        Ok(dir.join(format!("{}.{}", name, PACK_EXTENSION)))
+    }
+
+    /// Every installed pack, by name, skipping files that fail to load
+    pub fn installed() -> Vec<Self> {
+        let Some(entries) = Self::install_dir().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+            return Vec::new();
+        };
+
+        let mut packs: Vec<Self> = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == PACK_EXTENSION))
+            .filter_map(|path| Self::load(&path).ok())
+            .collect();
+        packs.sort_by(|a, b| a.info.name.cmp(&b.info.name));
+        packs
+    }
+
+    /// Verify the pack file at `path` and copy it into the install directory
+    ///
+    /// Refuses to replace an installed copy with an older version, or with one
+    /// signed by a different teacher key, unless `force` is set.
+    pub fn install(path: &Path, force: bool) -> Result<(Self, PathBuf), KickbeatsError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| KickbeatsError::io("read practice pack", path, e))?;
+        let pack = Self::from_toml_str(&contents)?;
+        let target = Self::installed_path(&pack.info.name)?;
+        let dir = target.parent().unwrap_or(Path::new("."));
+
+        // Another instance can't install between the version check and the write
+        let _lock =
+            DirLock::acquire(dir).map_err(|e| KickbeatsError::io("lock pack directory", dir, e))?;
+        if let Ok(installed) = Self::load(&target) {
+            if !force && installed.info.public_key != pack.info.public_key {
+                return Err(KickbeatsError::Config(format!(
+                    "The installed {} was signed by teacher key {}, but this copy by {}; \
+                     use --force if you trust the new key",
+                    pack.info.name,
+                    installed.info.fingerprint(),
+                    pack.info.fingerprint()
+                )));
+            }
+            let (old, new) = (&installed.info.version, &pack.info.version);
+            let version = |v| parse_version(v).map_err(KickbeatsError::Config);
+            if !force && version(old)? > version(new)? {
+                return Err(KickbeatsError::Config(format!(
+                    "{} {} is already installed; use --force to replace it with older version {}",
+                    pack.info.name, old, new
+                )));
+            }
+        }
+
+        write_atomic(&target, contents)
+            .map_err(|e| KickbeatsError::io("install practice pack", &target, e))?;
+        Ok((pack, target))
+    }
+
+    /// Render the pack as TOML, header first
+    pub fn to_toml_string(&self) -> String {
+        let mut header = self.header_table();
+        header.insert(
+            "signature".to_string(),
+            Value::String(self.info.signature.clone()),
+        );
+        let mut top = Table::new();
+        top.insert("pack".to_string(), Value::Table(header));
+
+        let mut table = self.to_table();
+        table.remove("pack");
+        format!("# kickbeats practice pack\n\n{}\n{}", top, table)
+    }
+
+    /// The `[pack]` section without its signature
+    fn header_table(&self) -> Table {
+        let mut header = Table::new();
+        header.insert("name".to_string(), Value::String(self.info.name.clone()));
+        header.insert(
+            "version".to_string(),
+            Value::String(self.info.version.clone()),
+        );
+        header.insert(
+            "format".to_string(),
+            Value::Integer(i64::from(self.info.format)),
+        );
+        if let Some(author) = &self.info.author {
+            header.insert("author".to_string(), Value::String(author.clone()));
+        }
+        if let Some(description) = &self.info.description {
+            header.insert(
+                "description".to_string(),
+                Value::String(description.clone()),
+            );
+        }
+        header.insert(
+            "public_key".to_string(),
+            Value::String(self.info.public_key.clone()),
+        );
+        header
+    }
+
+    /// Everything in the pack except its signature
+    fn to_table(&self) -> Table {
+        let mut table = Table::new();
+        table.insert("pack".to_string(), Value::Table(self.header_table()));
+        insert_section(
+            &mut table,
+            "practice",
+            practice_table(&self.config.practice),
+        );
+        insert_section(&mut table, "midi", midi_table(&self.config.midi));
+        insert_section(&mut table, "click", click_table(&self.config.click));
+        insert_section(&mut table, "keys", keys_table(&self.config.keys));
+
+        let mut weights = Table::new();
+        for (time_signature, table) in self.weights.iter() {
+            let values = table
+                .iter()
+                // Go through the shortest decimal so 0.8f32 stays 0.8 rather than 0.800000011920929
+                .map(|w| Value::Float(w.to_string().parse().unwrap_or(f64::from(*w))))
+                .collect();
+            weights.insert(
+                format!(
+                    "{}/{}",
+                    time_signature.numerator, time_signature.denominator
+                ),
+                Value::Array(values),
+            );
+        }
+        insert_section(&mut table, "weights", weights);
+
+        if !self.playlist.is_empty() {
+            let entries = self.playlist.iter().map(playlist_table).collect();
+            table.insert("playlist".to_string(), Value::Array(entries));
+        }
+        if let Some(curriculum) = &self.curriculum {
+            let stages = curriculum.stages().iter().map(stage_table).collect();
+            table.insert("curriculum".to_string(), Value::Array(stages));
+        }
+        table
+    }
+}
+
+/// Refuse settings that differ from player to player: the MIDI port, the
+/// webhook, the e-kit's notes, drum samples, where history is kept, and colors
+fn check_shareable(config: &Config) -> Result<(), String> {
+    if config.midi.port.is_some() {
+        return Err("Practice packs can't set midi.port".to_string());
+    }
+    if config.webhooks != WebhooksSection::default() {
+        return Err("Practice packs can't set [webhooks]".to_string());
+    }
+    if config.kit != KitSection::default() {
+        return Err("Practice packs can't set [kit]".to_string());
+    }
+    if config.samples != SamplesSection::default() {
+        return Err("Practice packs can't set [samples]".to_string());
+    }
+    if config.storage != StorageSection::default() {
+        return Err("Practice packs can't set [storage]".to_string());
+    }
+    if config.theme != ThemeSection::default() {
+        return Err("Practice packs can't set [theme]".to_string());
+    }
+    Ok(())
+}
+
+/// Check one playlist pattern against its meter
+fn playlist_entry(
+    index: usize,
+    steps: &str,
+    time_signature: TimeSignature,
+) -> Result<PlaylistEntry, String> {
+    let steps = parse_answer(steps, time_signature.sixteenths_per_measure()).map_err(|e| {
+        format!(
+            "Invalid playlist pattern {} in {}/{}: {}",
+            index + 1,
+            time_signature.numerator,
+            time_signature.denominator,
+            e
+        )
+    })?;
+    Ok(PlaylistEntry {
+        time_signature,
+        steps,
+    })
+}
+
+fn parse_playlist_tables(
+    playlist: Value,
+    default_meter: TimeSignature,
+) -> Result<Vec<PlaylistEntry>, String> {
+    let entries: Vec<RawPlaylistEntry> = playlist
+        .try_into()
+        .map_err(|e| format!("Invalid practice pack: [[playlist]]: {}", e))?;
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            playlist_entry(
+                i,
+                &entry.steps,
+                entry.time_signature.unwrap_or(default_meter),
+            )
+        })
+        .collect()
+}
+
+fn parse_curriculum_tables(stages: Value) -> Result<Curriculum, String> {
+    let stages: Vec<RawStage> = stages
+        .try_into()
+        .map_err(|e| format!("Invalid curriculum: {}", e))?;
+    let stages = stages
+        .into_iter()
+        .map(|stage| {
+            let progression = match (stage.advance_accuracy, stage.advance_after) {
+                (Some(accuracy_percent), Some(answers)) => Some(Progression {
+                    accuracy_percent,
+                    answers,
+                }),
+                (None, None) => None,
+                _ => {
+                    return Err(format!(
+                    "Invalid curriculum: stage {} needs both advance_accuracy and advance_after",
+                    stage.name
+                ))
+                }
+            };
+            Ok(CurriculumStage {
+                complexity: stage.complexity.ok_or_else(|| {
+                    format!(
+                        "Invalid curriculum: stage {} needs a complexity",
+                        stage.name
+                    )
+                })?,
+                name: stage.name,
+                tempo: stage.tempo,
+                progression,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Curriculum::new(stages).map_err(|e| format!("Invalid curriculum: {}", e))
+}
+
+fn insert_section(table: &mut Table, key: &str, section: Table) {
+    if !section.is_empty() {
+        table.insert(key.to_string(), Value::Table(section));
+    }
+}
+
+fn practice_table(practice: &PracticeSection) -> Table {
+    let mut table = Table::new();
+    if let Some(tempo) = practice.tempo {
+        table.insert("tempo".to_string(), Value::Integer(i64::from(tempo)));
+    }
+    if let Some(complexity) = practice.complexity {
+        let name = format!("{:?}", complexity).to_lowercase();
+        table.insert("complexity".to_string(), Value::String(name));
+    }
+    if let Some(time_signature) = practice.time_signature {
+        let sig = format!(
+            "{}/{}",
+            time_signature.numerator, time_signature.denominator
+        );
+        table.insert("time_signature".to_string(), Value::String(sig));
+    }
+    table
+}
+
+fn midi_table(midi: &MidiSection) -> Table {
+    let mut table = Table::new();
+    for (key, value) in [
+        ("kick_note", midi.kick_note),
+        ("click_note", midi.click_note),
+        ("channel", midi.channel),
+    ] {
+        if let Some(value) = value {
+            table.insert(key.to_string(), Value::Integer(i64::from(value)));
+        }
+    }
+    table
+}
+
+fn click_table(click: &ClickSection) -> Table {
+    let mut table = Table::new();
+    if let Some(enabled) = click.enabled {
+        table.insert("enabled".to_string(), Value::Boolean(enabled));
+    }
+    table
+}
+
+fn keys_table(keys: &KeysSection) -> Table {
+    let mut table = Table::new();
+    for action in Action::ALL {
+        if let Some(key) = keys.get(action) {
+            table.insert(action.name().to_string(), Value::String(key.to_string()));
+        }
+    }
+    table
+}
+
+fn playlist_table(entry: &PlaylistEntry) -> Value {
+    let mut table = Table::new();
+    let meter = entry.time_signature;
+    table.insert(
+        "time_signature".to_string(),
+        Value::String(format!("{}/{}", meter.numerator, meter.denominator)),
+    );
+    let steps = entry
+        .steps
+        .iter()
+        .map(|&kick| if kick { 'x' } else { '.' })
+        .collect();
+    table.insert("steps".to_string(), Value::String(steps));
+    Value::Table(table)
+}
+
+fn stage_table(stage: &CurriculumStage) -> Value {
+    let mut table = Table::new();
+    table.insert("name".to_string(), Value::String(stage.name.clone()));
+    let complexity = format!("{:?}", stage.complexity).to_lowercase();
+    table.insert("complexity".to_string(), Value::String(complexity));
+    if let Some(tempo) = stage.tempo {
+        table.insert("tempo".to_string(), Value::Integer(i64::from(tempo)));
+    }
+    if let Some(progression) = stage.progression {
+        table.insert(
+            "advance_accuracy".to_string(),
+            Value::Integer(i64::from(progression.accuracy_percent)),
+        );
+        table.insert(
+            "advance_after".to_string(),
+            Value::Integer(i64::from(progression.answers)),
+        );
+    }
+    Value::Table(table)
+}
+
+/// A new random ed25519 key, as PKCS#8
+fn generate_pkcs8() -> Result<ring::pkcs8::Document, KickbeatsError> {
+    Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+        .map_err(|_| KickbeatsError::Config("Couldn't generate a teacher key".to_string()))
+}
+
+/// Whether `signature` is `public_key`'s signature of the table's canonical TOML
+fn verify(table: &Table, public_key: &str, signature: &str) -> bool {
+    let (Some(public_key), Some(signature)) = (from_hex(public_key), from_hex(signature)) else {
+        return false;
+    };
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(table.to_string().as_bytes(), &signature)
+        .is_ok()
+}
+
+/// The first 16 hex digits of a public key
+fn fingerprint(public_key: &str) -> &str {
+    public_key.get(..16).unwrap_or(public_key)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Pack names become file names, so keep them to lowercase letters, digits, `-`, and `_`
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid pack name '{}'. Use lowercase letters, digits, '-', and '_'",
+            name
+        ))
+    }
+}
+
+/// Parse a dotted version ("1.2.0") into comparable numbers
+fn parse_version(version: &str) -> Result<Vec<u32>, String> {
+    version
+        .split('.')
+        .map(|part| part.parse::<u32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| {
+            format!(
+                "Invalid pack version '{}'. Use dotted numbers (e.g., 1.0.0)",
+                version
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, TimeSignature};
+
+    fn funk101() -> PracticePack {
+        let config = Config::from_toml_str(
+            "[practice]\ntempo = 96\ncomplexity = \"complex\"\n[midi]\nkick_note = 35\n\
+             [keys]\nreveal = \"s\"\n",
+        )
+        .unwrap();
+        let mut weights = WeightProfiles::new();
+        weights
+            .insert(
+                TimeSignature::four_four(),
+                vec![
+                    1.0, 0.8, 0.3, 0.1, 0.6, 0.8, 0.3, 0.1, 0.7, 0.8, 0.3, 0.1, 0.6, 0.8, 0.3, 0.1,
+                ],
+            )
+            .unwrap();
+        let playlist = PracticePack::parse_playlist(
+            "x...x.....x.x...\n3/4 x..x..x.....\n",
+            TimeSignature::four_four(),
+        )
+        .unwrap();
+        let curriculum = PracticePack::parse_curriculum(
+            "[[curriculum]]\nname = \"Downbeats\"\ncomplexity = \"simple\"\ntempo = 80\n\
+             advance_accuracy = 90\nadvance_after = 3\n\
+             [[curriculum]]\nname = \"Syncopation\"\ncomplexity = \"medium\"\n",
+        )
+        .unwrap();
+        PracticePack::new("funk101", "1.0.0", config, weights)
+            .unwrap()
+            .with_description("Sixteenth-note funk kicks")
+            .with_playlist(playlist)
+            .with_curriculum(curriculum)
+            .signed(&TeacherKey::generate().unwrap())
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let pack = funk101();
+        let text = pack.to_toml_string();
+
+        assert!(text.starts_with("# kickbeats practice pack\n\n[pack]\n"));
+        assert!(text.contains("name = \"funk101\""));
+        assert!(text.contains("0.8, 0.3"));
+        let parsed = PracticePack::from_toml_str(&text).unwrap();
+        assert_eq!(parsed, pack);
+        assert_eq!(
+            parsed.config.practice.complexity,
+            Some(ComplexityLevel::Complex)
+        );
+        assert_eq!(parsed.config.keys.reveal, Some('s'));
+        assert!(text.contains("[[playlist]]"));
+        assert_eq!(parsed.playlist[1].time_signature, TimeSignature::new(3, 4));
+        let curriculum = parsed.curriculum.unwrap();
+        assert_eq!(curriculum.stages().len(), 2);
+        assert_eq!(
+            curriculum.current().progression,
+            Some(Progression {
+                accuracy_percent: 90,
+                answers: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_rejects_bad_playlists_and_curriculums() {
+        let four_four = TimeSignature::four_four();
+        assert!(PracticePack::parse_playlist("x...x...", four_four)
+            .unwrap_err()
+            .to_string()
+            .contains("pattern 1 in 4/4"));
+        assert!(PracticePack::parse_playlist("# warmup\n\n5/4 x...", four_four).is_err());
+        assert_eq!(
+            PracticePack::parse_playlist("# warmup\n\nx...x...x...x...  # fours", four_four)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        assert!(PracticePack::parse_curriculum(
+            "[[curriculum]]\nname = \"one\"\ncomplexity = \"simple\"\nadvance_after = 3\n"
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("both advance_accuracy and advance_after"));
+        assert!(PracticePack::parse_curriculum(
+            "[[curriculum]]\nname = \"one\"\ncomplexity = \"simple\"\n\
+             [[curriculum]]\nname = \"two\"\ncomplexity = \"medium\"\n"
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("needs progression criteria"));
+    }
+
+    #[test]
+    fn test_signature_ignores_formatting_but_not_values() {
+        let pack = funk101();
+        let text = pack.to_toml_string();
+
+        let reformatted = text.replace("tempo = 96", "tempo=96   # slow groove");
+        assert!(PracticePack::from_toml_str(&reformatted).is_ok());
+
+        let edited = text.replace("tempo = 96", "tempo = 200");
+        assert!(PracticePack::from_toml_str(&edited)
+            .unwrap_err()
+            .to_string()
+            .contains("failed its signature check"));
+
+        // A pack signed with another key doesn't pass for the teacher's
+        let forger = funk101();
+        let forged = forger
+            .to_toml_string()
+            .replace(&forger.info.public_key, &pack.info.public_key);
+        assert!(PracticePack::from_toml_str(&forged).is_err());
+
+        let unsigned =
+            PracticePack::new("funk101", "1.0.0", Config::default(), WeightProfiles::new())
+                .unwrap()
+                .to_toml_string();
+        assert!(PracticePack::from_toml_str(&unsigned).is_err());
+    }
+
+    #[test]
+    fn test_teacher_key_is_created_once() {
+        let dir =
+            std::env::temp_dir().join(format!("kickbeats-teacher-key-{}", std::process::id()));
+        let path = dir.join("teacher.key");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let (created, is_new) = TeacherKey::load_or_create(&path).unwrap();
+        assert!(is_new);
+        let (loaded, is_new) = TeacherKey::load_or_create(&path).unwrap();
+        assert!(!is_new);
+        assert_eq!(loaded.public_key(), created.public_key());
+        assert_eq!(created.fingerprint().len(), 16);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o077, 0);
+        }
+
+        std::fs::write(&path, "not a key").unwrap();
+        assert!(TeacherKey::load_or_create(&path).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rejects_newer_format() {
+        let text = funk101()
+            .to_toml_string()
+            .replace("format = 1", "format = 2");
+
+        assert!(PracticePack::from_toml_str(&text)
+            .unwrap_err()
+            .to_string()
+            .contains("upgrade kickbeats"));
+    }
+
+    #[test]
+    fn test_rejects_bad_names_versions_and_ports() {
+        let new =
+            |name, version, config| PracticePack::new(name, version, config, WeightProfiles::new());
+
+        assert!(new("Funk 101", "1.0", Config::default()).is_err());
+        assert!(new("funk101", "v1", Config::default()).is_err());
+        let with_port = Config::from_toml_str("[midi]\nport = \"IAC\"\n").unwrap();
+        assert!(new("funk101", "1.0", with_port).is_err());
+        let with_webhook =
+            Config::from_toml_str("[webhooks]\nurl = \"https://tracker.example.com\"\n").unwrap();
+        assert!(new("funk101", "1.0", with_webhook)
+            .unwrap_err()
+            .to_string()
+            .contains("[webhooks]"));
+        let with_kit = Config::from_toml_str("[kit]\nsnare = [38]\n").unwrap();
+        assert!(new("funk101", "1.0", with_kit)
+            .unwrap_err()
+            .to_string()
+            .contains("[kit]"));
+        let with_samples = Config::from_toml_str("[samples]\nkick = \"kick.wav\"\n").unwrap();
+        assert!(new("funk101", "1.0", with_samples)
+            .unwrap_err()
+            .to_string()
+            .contains("[samples]"));
+        let with_storage = Config::from_toml_str("[storage]\ndir = \"/tmp\"\n").unwrap();
+        assert!(new("funk101", "1.0", with_storage)
+            .unwrap_err()
+            .to_string()
+            .contains("[storage]"));
+        let with_theme = Config::from_toml_str("[theme]\nkick = \"red\"\n").unwrap();
+        assert!(new("funk101", "1.0", with_theme)
+            .unwrap_err()
+            .to_string()
+            .contains("[theme]"));
+    }
+
+    #[test]
+    fn test_parse_version_orders_numerically() {
+        assert!(parse_version("1.10.0").unwrap() > parse_version("1.9.3").unwrap());
+        assert!(parse_version("2").unwrap() > parse_version("1.9").unwrap());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::config::file::parse_str;
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// A terminal color a theme can use
+///
+/// Named colors follow the terminal's own palette, so they suit light and
+/// dark backgrounds alike; `#rrggbb` needs a true-color terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeColor {
+    Black,
+    DarkGrey,
+    Red,
+    DarkRed,
+    Green,
+    DarkGreen,
+    Yellow,
+    DarkYellow,
+    Blue,
+    DarkBlue,
+    Magenta,
+    DarkMagenta,
+    Cyan,
+    DarkCyan,
+    White,
+    Grey,
+    /// True color, from `#rrggbb`
+    Rgb(u8, u8, u8),
+}
+
+impl ThemeColor {
+    /// Every named color with its name, in the order errors list them
+    const NAMED: [(&'static str, ThemeColor); 16] = [
+        ("black", ThemeColor::Black),
+        ("dark_grey", ThemeColor::DarkGrey),
+        ("red", ThemeColor::Red),
+        ("dark_red", ThemeColor::DarkRed),
+        ("green", ThemeColor::Green),
+        ("dark_green", ThemeColor::DarkGreen),
+        ("yellow", ThemeColor::Yellow),
+        ("dark_yellow", ThemeColor::DarkYellow),
+        ("blue", ThemeColor::Blue),
+        ("dark_blue", ThemeColor::DarkBlue),
+        ("magenta", ThemeColor::Magenta),
+        ("dark_magenta", ThemeColor::DarkMagenta),
+        ("cyan", ThemeColor::Cyan),
+        ("dark_cyan", ThemeColor::DarkCyan),
+        ("white", ThemeColor::White),
+        ("grey", ThemeColor::Grey),
+    ];
+}
+
+impl FromStr for ThemeColor {
+    type Err = String;
+
+    /// Parse a color name (e.g., "yellow", "dark_cyan") or `#rrggbb`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        if let Some(&(_, color)) = Self::NAMED.iter().find(|(name, _)| *name == lower) {
+            return Ok(color);
+        }
+        let hex = lower
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()));
+        if let Some(hex) = hex {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or_default();
+            return Ok(ThemeColor::Rgb(channel(0), channel(2), channel(4)));
+        }
+        let names: Vec<&str> = Self::NAMED.iter().map(|(name, _)| *name).collect();
+        Err(format!(
+            "Invalid color '{}'. Must be #rrggbb or one of: {}",
+            s,
+            names.join(", ")
+        ))
+    }
+}
+
+/// The `[theme]` section: colors of the parts of a pattern
+///
+/// Only used when output is in color (see `--color`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeSection {
+    /// Kicks in revealed grids, tabs, and the playback cursor [default: yellow]
+    #[serde(deserialize_with = "parse_str")]
+    pub kick: Option<ThemeColor>,
+    /// Beat numbers on the counting row [default: cyan]
+    #[serde(deserialize_with = "parse_str")]
+    pub beat: Option<ThemeColor>,
+    /// The countdown to the count-in [default: magenta]
+    #[serde(deserialize_with = "parse_str")]
+    pub count_in: Option<ThemeColor>,
+}
+
+impl ThemeSection {
+    /// Fill whatever this section leaves unset from `fallback`
+    pub fn or(self, fallback: ThemeSection) -> ThemeSection {
+        ThemeSection {
+            kick: self.kick.or(fallback.kick),
+            beat: self.beat.or(fallback.beat),
+            count_in: self.count_in.or(fallback.count_in),
+        }
+    }
+}
+
+/// Colors output is drawn in, with the `[theme]` section's choices applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Kicks
+    pub kick: ThemeColor,
+    /// Beat numbers
+    pub beat: ThemeColor,
+    /// The count-in countdown
+    pub count_in: ThemeColor,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            kick: ThemeColor::Yellow,
+            beat: ThemeColor::Cyan,
+            count_in: ThemeColor::Magenta,
+        }
+    }
+}
+
+impl Theme {
+    /// Apply a `[theme]` section to the default colors
+    pub fn from_section(section: &ThemeSection) -> Self {
+        let default = Self::default();
+        Self {
+            kick: section.kick.unwrap_or(default.kick),
+            beat: section.beat.unwrap_or(default.beat),
+            count_in: section.count_in.unwrap_or(default.count_in),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_names_and_hex() {
+        assert_eq!("Dark_Cyan".parse(), Ok(ThemeColor::DarkCyan));
+        assert_eq!("#ff8000".parse(), Ok(ThemeColor::Rgb(255, 128, 0)));
+        assert!("#ff80".parse::<ThemeColor>().is_err());
+        assert!("orange"
+            .parse::<ThemeColor>()
+            .unwrap_err()
+            .contains("one of: black, dark_grey"));
+    }
+
+    #[test]
+    fn test_section_overrides_defaults() {
+        let section = ThemeSection {
+            kick: Some(ThemeColor::Red),
+            ..ThemeSection::default()
+        };
+        let theme = Theme::from_section(&section);
+
+        assert_eq!(theme.kick, ThemeColor::Red);
+        assert_eq!(theme.beat, ThemeColor::Cyan);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::config::Config;
+use crate::error::KickbeatsError;
+use crate::models::ComplexityLevel;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often the watcher checks whether the file has been saved
+const POLL: Duration = Duration::from_millis(500);
+
+/// Longest wait between checks for the watcher being dropped
+const WAIT_SLICE: Duration = Duration::from_millis(50);
+
+/// Settings a running session takes on from a saved config file, each
+/// `None` if saving left it as it was
+///
+/// A setting removed from the file leaves the session as it is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SettingsUpdate {
+    /// New `practice.tempo`
+    pub tempo: Option<u16>,
+    /// New `practice.complexity`
+    pub complexity: Option<ComplexityLevel>,
+    /// New `midi.kick_note`
+    pub kick_note: Option<u8>,
+    /// New `midi.click_note`
+    pub click_note: Option<u8>,
+    /// New `midi.channel`, as numbered on instruments (1-16)
+    pub channel: Option<u8>,
+}
+
+impl SettingsUpdate {
+    /// The live settings that differ from `old` in `new`
+    pub fn between(old: &Config, new: &Config) -> Self {
+        Self {
+            tempo: changed(old.practice.tempo, new.practice.tempo),
+            complexity: changed(old.practice.complexity, new.practice.complexity),
+            kick_note: changed(old.midi.kick_note, new.midi.kick_note),
+            click_note: changed(old.midi.click_note, new.midi.click_note),
+            channel: changed(old.midi.channel, new.midi.channel),
+        }
+    }
+
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether the notes or channel playback is sent on changed
+    pub fn remaps(&self) -> bool {
+        self.kick_note.is_some() || self.click_note.is_some() || self.channel.is_some()
+    }
+}
+
+/// `new` if it's set and differs from `old`
+fn changed<T: PartialEq>(old: Option<T>, new: Option<T>) -> Option<T> {
+    if new != old {
+        new
+    } else {
+        None
+    }
+}
+
+/// Watches a config file for saves, passing on the live settings each one
+/// changed
+///
+/// A background thread checks the file's modification time and size every
+/// half second. Saves that change nothing live (a new key binding, say) are
+/// passed over; saves that don't parse are reported so the player can fix
+/// them, and the settings last read stay in effect until they do.
+pub struct ConfigWatcher {
+    /// Updates, or why a save couldn't be read, oldest first
+    updates: Receiver<Result<SettingsUpdate, KickbeatsError>>,
+    /// Cleared to stop the watcher thread
+    running: Arc<AtomicBool>,
+    /// Watcher thread
+    handle: Option<thread::JoinHandle<()>>,
+    /// File watched
+    path: PathBuf,
+}
+
+impl ConfigWatcher {
+    /// Start watching `path`, which needn't exist yet
+    ///
+    /// Changes are measured against the file as it is now, so settings
+    /// given on the command line stay until the file changes them.
+    pub fn start(path: PathBuf) -> Self {
+        let mut settings = Config::load(&path).unwrap_or_default();
+        let mut saved = stamp(&path);
+        let (sender, updates) = mpsc::channel();
+        let running = Arc::new(AtomicBool::new(true));
+        let handle = {
+            let running = Arc::clone(&running);
+            let path = path.clone();
+            thread::spawn(move || {
+                while wait(&running) {
+                    let current = stamp(&path);
+                    if current == saved {
+                        continue;
+                    }
+                    saved = current;
+                    // A deleted file leaves the settings as they were
+                    if saved.is_none() {
+                        continue;
+                    }
+                    let update = Config::load(&path).map(|config| {
+                        let update = SettingsUpdate::between(&settings, &config);
+                        settings = config;
+                        update
+                    });
+                    if update.as_ref().is_ok_and(SettingsUpdate::is_empty) {
+                        continue;
+                    }
+                    if sender.send(update).is_err() {
+                        return;
+                    }
+                }
+            })
+        };
+
+        Self {
+            updates,
+            running,
+            handle: Some(handle),
+            path,
+        }
+    }
+
+    /// File watched
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Updates from saves since the last call, or why they couldn't be read,
+    /// without blocking
+    pub fn drain(&self) -> Vec<Result<SettingsUpdate, KickbeatsError>> {
+        self.updates.try_iter().collect()
+    }
+}
+
+impl Drop for ConfigWatcher {
+    /// Stop the watcher thread
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Sleep until the next check, returning whether to make it
+fn wait(running: &AtomicBool) -> bool {
+    let mut waited = Duration::ZERO;
+    while waited < POLL {
+        if !running.load(Ordering::SeqCst) {
+            return false;
+        }
+        thread::sleep(WAIT_SLICE);
+        waited += WAIT_SLICE;
+    }
+    running.load(Ordering::SeqCst)
+}
+
+/// When the file at `path` was last modified and how long it is, or `None`
+/// if there's no file
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_update_keeps_only_changed_live_settings() {
+        let old = Config::from_toml_str(
+            "[practice]\ntempo = 100\ncomplexity = \"simple\"\n[midi]\nkick_note = 36\n",
+        )
+        .unwrap();
+        let new = Config::from_toml_str(
+            "[practice]\ntempo = 110\n[midi]\nkick_note = 36\nchannel = 1\n[keys]\nreveal = \"s\"\n",
+        )
+        .unwrap();
+        let update = SettingsUpdate::between(&old, &new);
+        assert_eq!(
+            update,
+            SettingsUpdate {
+                tempo: Some(110),
+                channel: Some(1),
+                ..SettingsUpdate::default()
+            }
+        );
+        assert!(update.remaps());
+        assert!(SettingsUpdate::between(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_watcher_reports_saves() {
+        let path =
+            std::env::temp_dir().join(format!("kickbeats-watch-{}.toml", std::process::id()));
+        std::fs::write(&path, "[practice]\ntempo = 100\n").unwrap();
+        let watcher = ConfigWatcher::start(path.clone());
+
+        let next = |watcher: &ConfigWatcher| {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            loop {
+                if let Some(update) = watcher.drain().pop() {
+                    return update;
+                }
+                assert!(Instant::now() < deadline, "no update from the watcher");
+                thread::sleep(WAIT_SLICE);
+            }
+        };
+
+        std::fs::write(&path, "[practice]\ntempo = 120\ncomplexity = \"complex\"\n").unwrap();
+        let update = next(&watcher).unwrap();
+        assert_eq!(update.tempo, Some(120));
+        assert_eq!(update.complexity, Some(ComplexityLevel::Complex));
+
+        std::fs::write(&path, "[practice]\ntempo = 500\n").unwrap();
+        assert!(next(&watcher)
+            .unwrap_err()
+            .to_string()
+            .contains("practice.tempo"));
+
+        drop(watcher);
+        let _ = std::fs::remove_file(&path);
+    }
+}
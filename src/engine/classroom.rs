@@ -0,0 +1,512 @@
+use crate::error::KickbeatsError;
+use crate::grading::{grade_answer_at, AnswerFormat, AnswerResolution};
+use crate::models::{ComplexityLevel, Pattern};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Port a classroom is hosted on when only a host name is given
+pub const DEFAULT_CLASS_PORT: u16 = 9090;
+
+/// How often the host checks for students joining
+const ACCEPT_POLL: Duration = Duration::from_millis(50);
+
+/// Longest the host waits on a student's connection before dropping them
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Longest line a student may send, in bytes; longer ones drop the student
+const MAX_LINE: u64 = 4096;
+
+/// Most characters of a student's name the teacher is shown
+const MAX_NAME: usize = 40;
+
+/// `addr` with [`DEFAULT_CLASS_PORT`] added if it has no port of its own
+pub fn class_addr(addr: &str) -> String {
+    let has_port = addr.parse::<SocketAddr>().is_ok()
+        || addr
+            .rsplit_once(':')
+            .is_some_and(|(host, port)| !host.contains(':') && port.parse::<u16>().is_ok());
+    if has_port {
+        addr.to_string()
+    } else {
+        format!("{}:{}", addr, DEFAULT_CLASS_PORT)
+    }
+}
+
+/// One line of the classroom protocol: JSON with a `msg` field, sent over TCP
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "msg", rename_all = "snake_case")]
+pub enum ClassMessage {
+    /// Host to students: a new pattern to play (or, in a quiz, just to answer)
+    Pattern {
+        /// Number of the pattern in the host's session
+        number: u32,
+        /// The pattern's fingerprint (e.g., "4/4:x...x.......x...")
+        pattern: String,
+        /// Tempo to play it at
+        tempo: u16,
+        /// Whether students only answer, with the teacher playing it for the room
+        quiz: bool,
+    },
+    /// Host to students: the tempo changed
+    Tempo {
+        /// Tempo now in effect
+        bpm: u16,
+    },
+    /// Host to students: the current pattern was revealed
+    Reveal {
+        /// Number of the pattern revealed
+        number: u32,
+    },
+    /// Host to students: class is over
+    End,
+    /// Student to host, on joining: who they are
+    Hello {
+        /// Name shown to the teacher
+        name: String,
+    },
+    /// Student to host: an answer to a pattern
+    Answer {
+        /// Number of the pattern answered
+        number: u32,
+        /// The answer on the grid, one `x` or `.` per position
+        answer: String,
+        /// Note value each position stands for ("sixteenth" or "eighth")
+        resolution: String,
+    },
+}
+
+impl ClassMessage {
+    /// The message as one line of the protocol
+    pub fn to_line(&self) -> String {
+        let mut line = serde_json::to_string(self).unwrap_or_default();
+        line.push('\n');
+        line
+    }
+}
+
+/// Something a student did, for the teacher to see
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClassEvent {
+    /// A student joined the class
+    Joined {
+        /// Their name
+        name: String,
+    },
+    /// A student left the class
+    Left {
+        /// Their name
+        name: String,
+    },
+    /// A student answered a pattern
+    Answered {
+        /// Their name
+        name: String,
+        /// Number of the pattern answered
+        number: u32,
+        /// Accuracy of the answer, graded by the host (0.0-1.0)
+        accuracy: f32,
+    },
+}
+
+/// What students have been sent, so latecomers can catch up
+#[derive(Debug, Clone, Default)]
+struct Lesson {
+    /// Pattern now current and its number
+    pattern: Option<(u32, String)>,
+    /// Tempo last sent
+    tempo: u16,
+    /// Whether the current pattern has been revealed
+    revealed: bool,
+}
+
+impl Lesson {
+    /// Accuracy of `answer`, at `resolution`, to pattern `number`, if
+    /// that's still the current pattern and the answer reads
+    fn grade(&self, number: u32, answer: &str, resolution: &str) -> Option<f32> {
+        let (current, fingerprint) = self.pattern.as_ref()?;
+        if *current != number {
+            return None;
+        }
+        let pattern = Pattern::from_fingerprint(fingerprint, ComplexityLevel::default()).ok()?;
+        let resolution = resolution.parse::<AnswerResolution>().ok()?;
+        grade_answer_at(&pattern, answer, AnswerFormat::Grid, resolution)
+            .ok()
+            .map(|grade| grade.accuracy)
+    }
+
+    /// Messages that bring a student who just joined up to date
+    fn catch_up(&self, quiz: bool) -> Vec<ClassMessage> {
+        let Some((number, pattern)) = &self.pattern else {
+            return Vec::new();
+        };
+        let mut messages = vec![ClassMessage::Pattern {
+            number: *number,
+            pattern: pattern.clone(),
+            tempo: self.tempo,
+            quiz,
+        }];
+        if self.revealed {
+            messages.push(ClassMessage::Reveal { number: *number });
+        }
+        messages
+    }
+}
+
+/// Students connected to the host
+type Students = Arc<Mutex<Vec<TcpStream>>>;
+
+/// Hosts a class: every student who joins over TCP hears (or, in a quiz,
+/// answers) the teacher's current pattern, and their answers come back
+///
+/// Students join with `kickbeats join`, and their answers are graded here
+/// against the pattern they were sent. The pattern shown is updated with
+/// [`show`](Self::show), as for a [`StreamOverlay`](crate::engine::StreamOverlay),
+/// and sent to every student as it changes; what students do is collected
+/// with [`drain`](Self::drain). Students who go away are dropped without
+/// interrupting the class.
+pub struct ClassroomHost {
+    /// Connected students
+    students: Students,
+    /// What students have been sent
+    lesson: Arc<Mutex<Lesson>>,
+    /// Whether students only answer, without playing the pattern
+    quiz: bool,
+    /// Cleared to stop accepting students
+    running: Arc<AtomicBool>,
+    /// Thread accepting students
+    handle: Option<thread::JoinHandle<()>>,
+    /// What students did, from their reader threads
+    events: Receiver<ClassEvent>,
+    /// Address the class is hosted on
+    addr: SocketAddr,
+}
+
+impl ClassroomHost {
+    /// Host a class on `addr` (e.g. "0.0.0.0", on [`DEFAULT_CLASS_PORT`]
+    /// unless another is given), with students playing each pattern
+    /// themselves unless `quiz`
+    pub fn start(addr: &str, quiz: bool) -> Result<Self, KickbeatsError> {
+        let addr = &class_addr(addr);
+        let failed = |e: std::io::Error| {
+            KickbeatsError::Network(format!("Failed to host a class on {}: {}", addr, e))
+        };
+        let listener = TcpListener::bind(addr)
+            .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+            .map_err(failed)?;
+        let addr = listener.local_addr().map_err(failed)?;
+
+        let students: Students = Arc::new(Mutex::new(Vec::new()));
+        let lesson = Arc::new(Mutex::new(Lesson::default()));
+        let running = Arc::new(AtomicBool::new(true));
+        let (events_tx, events) = mpsc::channel();
+        let handle = {
+            let students = Arc::clone(&students);
+            let lesson = Arc::clone(&lesson);
+            let running = Arc::clone(&running);
+            thread::spawn(move || {
+                while running.load(Ordering::SeqCst) {
+                    while let Ok((stream, _)) = listener.accept() {
+                        welcome(stream, &students, &lesson, quiz, events_tx.clone());
+                    }
+                    thread::sleep(ACCEPT_POLL);
+                }
+            })
+        };
+
+        Ok(Self {
+            students,
+            lesson,
+            quiz,
+            running,
+            handle: Some(handle),
+            events,
+            addr,
+        })
+    }
+
+    /// Address the class is hosted on, with the port picked if asked for port 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Show pattern number `number` to the class at `tempo_bpm`, revealed
+    /// if `revealed`, sending students whatever changed
+    pub fn show(&self, number: u32, pattern: &Pattern, revealed: bool, tempo_bpm: u16) {
+        let Ok(mut lesson) = self.lesson.lock() else {
+            return;
+        };
+        let mut messages = Vec::new();
+        let fingerprint = pattern.fingerprint();
+        let current = Some((number, fingerprint.clone()));
+        if lesson.pattern != current {
+            lesson.pattern = current;
+            lesson.tempo = tempo_bpm;
+            lesson.revealed = false;
+            messages.push(ClassMessage::Pattern {
+                number,
+                pattern: fingerprint,
+                tempo: tempo_bpm,
+                quiz: self.quiz,
+            });
+        } else if lesson.tempo != tempo_bpm {
+            lesson.tempo = tempo_bpm;
+            messages.push(ClassMessage::Tempo { bpm: tempo_bpm });
+        }
+        if revealed && !lesson.revealed {
+            lesson.revealed = true;
+            messages.push(ClassMessage::Reveal { number });
+        }
+        drop(lesson);
+        for message in messages {
+            broadcast(&self.students, &message);
+        }
+    }
+
+    /// What students did since the last call, oldest first
+    pub fn drain(&self) -> Vec<ClassEvent> {
+        self.events.try_iter().collect()
+    }
+}
+
+impl Drop for ClassroomHost {
+    /// Tell students class is over and stop accepting new ones
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        broadcast(&self.students, &ClassMessage::End);
+        if let Ok(students) = self.students.lock() {
+            for student in students.iter() {
+                let _ = student.shutdown(std::net::Shutdown::Both);
+            }
+        }
+    }
+}
+
+/// Bring a student who just connected up to date, and read what they send
+/// on a thread of their own
+fn welcome(
+    stream: TcpStream,
+    students: &Students,
+    lesson: &Arc<Mutex<Lesson>>,
+    quiz: bool,
+    events: Sender<ClassEvent>,
+) {
+    let Ok(reader) = stream
+        .set_nonblocking(false)
+        .and_then(|()| stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+        .and_then(|()| stream.try_clone())
+    else {
+        return;
+    };
+    let catch_up = lesson
+        .lock()
+        .map(|lesson| lesson.catch_up(quiz))
+        .unwrap_or_default();
+    let mut writer = &stream;
+    if catch_up
+        .iter()
+        .any(|message| writer.write_all(message.to_line().as_bytes()).is_err())
+    {
+        return;
+    }
+    if let Ok(mut students) = students.lock() {
+        students.push(stream);
+    }
+    let lesson = Arc::clone(lesson);
+    thread::spawn(move || listen(reader, &lesson, events));
+}
+
+/// Pass on what one student sends until they go away, or send a line
+/// longer than [`MAX_LINE`]
+fn listen(stream: TcpStream, lesson: &Mutex<Lesson>, events: Sender<ClassEvent>) {
+    let mut name = stream
+        .peer_addr()
+        .map_or_else(|_| String::from("A student"), |addr| addr.to_string());
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match (&mut reader).take(MAX_LINE).read_line(&mut line) {
+            Ok(read) if read > 0 && line.ends_with('\n') => {}
+            _ => break,
+        }
+        let event = match serde_json::from_str::<ClassMessage>(&line) {
+            Ok(ClassMessage::Hello { name: given }) => {
+                let given = clean_name(&given);
+                if !given.is_empty() {
+                    name = given;
+                }
+                ClassEvent::Joined { name: name.clone() }
+            }
+            Ok(ClassMessage::Answer {
+                number,
+                answer,
+                resolution,
+            }) => {
+                let graded = lesson
+                    .lock()
+                    .ok()
+                    .and_then(|lesson| lesson.grade(number, &answer, &resolution));
+                // Answers to an earlier pattern, or that don't read, go unreported
+                let Some(accuracy) = graded else {
+                    continue;
+                };
+                ClassEvent::Answered {
+                    name: name.clone(),
+                    number,
+                    accuracy,
+                }
+            }
+            // Students only say hello and answer
+            _ => continue,
+        };
+        if events.send(event).is_err() {
+            return;
+        }
+    }
+    let _ = events.send(ClassEvent::Left { name });
+}
+
+/// `name` as it's safe to show the teacher: without control characters,
+/// which could move the cursor or recolor their terminal, and no longer
+/// than [`MAX_NAME`] characters
+fn clean_name(name: &str) -> String {
+    let name: String = name.chars().filter(|c| !c.is_control()).collect();
+    name.trim().chars().take(MAX_NAME).collect()
+}
+
+/// Send `message` to every student, dropping any that have gone away
+fn broadcast(students: &Students, message: &ClassMessage) {
+    let line = message.to_line();
+    if let Ok(mut students) = students.lock() {
+        students.retain(|mut student| student.write_all(line.as_bytes()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ComplexityLevel;
+    use std::time::Instant;
+
+    /// Wait for the next event from the class, failing after a few seconds
+    fn next_event(host: &ClassroomHost) -> ClassEvent {
+        let started = Instant::now();
+        loop {
+            if let Some(event) = host.drain().into_iter().next() {
+                return event;
+            }
+            assert!(started.elapsed() < Duration::from_secs(5), "no event");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_class_addr_adds_the_default_port() {
+        assert_eq!(class_addr("0.0.0.0"), "0.0.0.0:9090");
+        assert_eq!(class_addr("studio.local"), "studio.local:9090");
+        assert_eq!(class_addr("192.168.1.20:7000"), "192.168.1.20:7000");
+        assert_eq!(class_addr("[::1]:7000"), "[::1]:7000");
+    }
+
+    #[test]
+    fn test_students_get_the_lesson_and_answers_come_back() {
+        let host = ClassroomHost::start("127.0.0.1:0", true).unwrap();
+        let pattern =
+            Pattern::from_fingerprint("4/4:x...x.......x...", ComplexityLevel::Simple).unwrap();
+        host.show(3, &pattern, false, 90);
+
+        let mut student = TcpStream::connect(host.local_addr()).unwrap();
+        student
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut lines = BufReader::new(student.try_clone().unwrap()).lines();
+        let mut next = || serde_json::from_str::<ClassMessage>(&lines.next().unwrap().unwrap());
+
+        // Latecomers catch up on the current pattern
+        assert_eq!(
+            next().unwrap(),
+            ClassMessage::Pattern {
+                number: 3,
+                pattern: "4/4:x...x.......x...".to_string(),
+                tempo: 90,
+                quiz: true,
+            }
+        );
+
+        student
+            .write_all(
+                ClassMessage::Hello { name: "Ana".into() }
+                    .to_line()
+                    .as_bytes(),
+            )
+            .unwrap();
+        assert_eq!(next_event(&host), ClassEvent::Joined { name: "Ana".into() });
+
+        host.show(3, &pattern, true, 100);
+        assert_eq!(next().unwrap(), ClassMessage::Tempo { bpm: 100 });
+        assert_eq!(next().unwrap(), ClassMessage::Reveal { number: 3 });
+
+        // Graded here, against the pattern sent
+        for (number, answer) in [(2, "x...x.......x..."), (3, "x...x...x...x...")] {
+            let answer = ClassMessage::Answer {
+                number,
+                answer: answer.into(),
+                resolution: "sixteenth".into(),
+            };
+            student.write_all(answer.to_line().as_bytes()).unwrap();
+        }
+        assert_eq!(
+            next_event(&host),
+            ClassEvent::Answered {
+                name: "Ana".into(),
+                number: 3,
+                accuracy: 15.0 / 16.0
+            }
+        );
+
+        drop(host);
+        assert_eq!(next().unwrap(), ClassMessage::End);
+    }
+
+    #[test]
+    fn test_names_are_cleaned_and_long_lines_drop_the_student() {
+        let host = ClassroomHost::start("127.0.0.1:0", false).unwrap();
+        let mut student = TcpStream::connect(host.local_addr()).unwrap();
+
+        let hello = ClassMessage::Hello {
+            name: "\u{1b}[2J Ana\u{7}".into(),
+        };
+        student.write_all(hello.to_line().as_bytes()).unwrap();
+        assert_eq!(
+            next_event(&host),
+            ClassEvent::Joined {
+                name: "[2J Ana".into()
+            }
+        );
+
+        student
+            .write_all(&vec![b'x'; MAX_LINE as usize + 1])
+            .unwrap();
+        assert_eq!(
+            next_event(&host),
+            ClassEvent::Left {
+                name: "[2J Ana".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_clean_name_caps_its_length() {
+        assert_eq!(clean_name(&"a".repeat(100)).len(), MAX_NAME);
+        assert_eq!(clean_name(" \t\n"), "");
+    }
+}
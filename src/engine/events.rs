@@ -0,0 +1,216 @@
+use crate::error::KickbeatsError;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Loop drift past which playback warns, in milliseconds
+pub const DRIFT_WARNING_MS: f64 = 10.0;
+
+/// How the event log is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One JSON object per line
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("Invalid log format '{}'. Must be one of: json", s)),
+        }
+    }
+}
+
+/// Something that happened during a session, as written to the event log
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum LogEvent {
+    /// A new pattern was made current
+    PatternGenerated {
+        /// Number of the pattern in the session
+        number: u32,
+        /// The pattern as a shareable fingerprint (see `Pattern::fingerprint`)
+        pattern: String,
+        /// Complexity it was generated at
+        complexity: String,
+        /// Whether it came back from the review queue
+        review: bool,
+    },
+    /// The current pattern was revealed
+    Reveal {
+        /// Number of the pattern in the session
+        number: u32,
+        /// Its kicks as `x`/`.` steps
+        steps: String,
+    },
+    /// A new tempo took effect: at the next loop while playing, or straight
+    /// away while stopped
+    TempoChange {
+        /// Tempo now in effect
+        bpm: u16,
+    },
+    /// Playback started a loop of the pattern
+    LoopStart {
+        /// Loop number since the pattern started (0 = first loop after the count-in)
+        loop_index: u64,
+        /// Tempo of the loop
+        tempo: u16,
+        /// How late the loop started, in milliseconds (none when following
+        /// an external clock)
+        drift_ms: Option<f64>,
+    },
+    /// A loop started later than [`DRIFT_WARNING_MS`]
+    DriftWarning {
+        /// Loop number since the pattern started
+        loop_index: u64,
+        /// How late the loop started, in milliseconds
+        drift_ms: f64,
+        /// Drift past which playback warns, in milliseconds
+        threshold_ms: f64,
+    },
+}
+
+/// One line of the log: the event and when it happened
+#[derive(Serialize)]
+struct Line<'a> {
+    /// Seconds since the log was opened
+    t: f64,
+    /// What happened
+    #[serde(flatten)]
+    event: &'a LogEvent,
+}
+
+/// Log of a session's events as JSON lines, for analyzing or replaying
+/// sessions programmatically
+///
+/// Clones share the same output, so one log can be handed to the engine and
+/// to each playback run. Every line is `{"t": <seconds>, "event": <name>, ...}`
+/// with `t` counted from when the log was opened. Write failures are
+/// ignored so the log never interrupts practice.
+#[derive(Clone)]
+pub struct EventLog {
+    /// When the log was opened
+    start: Instant,
+    /// Where lines are written
+    output: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl EventLog {
+    /// Write events to `output`
+    pub fn new(output: impl Write + Send + 'static) -> Self {
+        Self {
+            start: Instant::now(),
+            output: Arc::new(Mutex::new(Box::new(output))),
+        }
+    }
+
+    /// Write events to stdout
+    pub fn stdout() -> Self {
+        Self::new(io::stdout())
+    }
+
+    /// Append events to the file at `path`, creating it if needed
+    pub fn append_to(path: &Path) -> Result<Self, KickbeatsError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| KickbeatsError::io("open event log", path, e))?;
+        Ok(Self::new(file))
+    }
+
+    /// Write `event` as one line, timed from when the log was opened
+    pub fn record(&self, event: LogEvent) {
+        let line = Line {
+            t: (self.start.elapsed().as_secs_f64() * 1000.0).round() / 1000.0,
+            event: &event,
+        };
+        let Ok(json) = serde_json::to_string(&line) else {
+            return;
+        };
+        if let Ok(mut output) = self.output.lock() {
+            let _ = writeln!(output, "{}", json).and_then(|()| output.flush());
+        }
+    }
+
+    /// Log a loop starting `drift_ms` late, warning if that's past
+    /// [`DRIFT_WARNING_MS`]
+    pub fn loop_start(&self, loop_index: u64, tempo: u16, drift_ms: Option<f64>) {
+        self.record(LogEvent::LoopStart {
+            loop_index,
+            tempo,
+            drift_ms,
+        });
+        if let Some(drift_ms) = drift_ms.filter(|&drift| drift > DRIFT_WARNING_MS) {
+            self.record(LogEvent::DriftWarning {
+                loop_index,
+                drift_ms,
+                threshold_ms: DRIFT_WARNING_MS,
+            });
+        }
+    }
+}
+
+impl std::fmt::Debug for EventLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("EventLog")
+            .field("start", &self.start)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Output shared with the test after the log takes it
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_writes_one_json_object_per_line() {
+        let output = Shared::default();
+        let log = EventLog::new(output.clone());
+        log.record(LogEvent::TempoChange { bpm: 90 });
+        log.loop_start(3, 90, Some(12.5));
+        log.loop_start(4, 90, None);
+
+        let text = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["event"], "tempo_change");
+        assert_eq!(lines[0]["bpm"], 90);
+        assert!(lines[0]["t"].as_f64().unwrap() >= 0.0);
+        assert_eq!(lines[1]["event"], "loop_start");
+        assert_eq!(lines[2]["event"], "drift_warning");
+        assert_eq!(lines[2]["drift_ms"], 12.5);
+        assert_eq!(lines[2]["threshold_ms"], DRIFT_WARNING_MS);
+        assert_eq!(lines[3]["drift_ms"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!("JSON".parse(), Ok(LogFormat::Json));
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+}
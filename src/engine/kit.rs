@@ -0,0 +1,273 @@
+use crate::engine::midi::MidiError;
+use crate::engine::pad::DrumPad;
+use crate::error::KickbeatsError;
+use crate::grading::Limb;
+use midir::{Ignore, MidiInput, MidiInputConnection};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// General MIDI notes kit kick pads send (acoustic bass drum and bass drum)
+const GM_KICKS: [u8; 2] = [35, 36];
+
+/// General MIDI notes kit snare pads send (acoustic and electric snare)
+///
+/// Side stick (37) is left out: it's the default click note.
+const GM_SNARES: [u8; 2] = [38, 40];
+
+/// General MIDI notes kit hi-hats send (closed, pedal, and open)
+const GM_HATS: [u8; 3] = [42, 44, 46];
+
+/// Which of a kit's notes each limb plays
+///
+/// Defaults to the General MIDI drum map; kits that send other notes (e.g.,
+/// separate notes for a hi-hat's bow and edge) are mapped in the `[kit]`
+/// config section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KitMap {
+    /// Notes from the kick pad
+    pub kick: Vec<u8>,
+    /// Notes from the snare pad
+    pub snare: Vec<u8>,
+    /// Notes from the hi-hat
+    pub hat: Vec<u8>,
+}
+
+impl KitMap {
+    /// Map each limb to its notes, checking ranges and that no note is on two limbs
+    pub fn new(kick: Vec<u8>, snare: Vec<u8>, hat: Vec<u8>) -> Result<Self, KickbeatsError> {
+        let limbs = [
+            (Limb::Kick, &kick),
+            (Limb::Snare, &snare),
+            (Limb::HiHat, &hat),
+        ];
+        for (i, &(limb, notes)) in limbs.iter().enumerate() {
+            for &note in notes {
+                if note > 127 {
+                    return Err(KickbeatsError::Config(
+                        "MIDI notes must be between 0 and 127".to_string(),
+                    ));
+                }
+                if let Some((other, _)) = limbs[i + 1..]
+                    .iter()
+                    .find(|(_, notes)| notes.contains(&note))
+                {
+                    return Err(KickbeatsError::Config(format!(
+                        "Kit note {} can't be both the {} and the {}",
+                        note,
+                        limb.name(),
+                        other.name()
+                    )));
+                }
+            }
+        }
+        Ok(Self { kick, snare, hat })
+    }
+
+    /// Limb that plays `note`, if any
+    pub fn limb(&self, note: u8) -> Option<Limb> {
+        [
+            (Limb::Kick, &self.kick),
+            (Limb::Snare, &self.snare),
+            (Limb::HiHat, &self.hat),
+        ]
+        .into_iter()
+        .find(|(_, notes)| notes.contains(&note))
+        .map(|(limb, _)| limb)
+    }
+}
+
+impl Default for KitMap {
+    fn default() -> Self {
+        Self {
+            kick: GM_KICKS.to_vec(),
+            snare: GM_SNARES.to_vec(),
+            hat: GM_HATS.to_vec(),
+        }
+    }
+}
+
+/// One note-on heard from the kit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KitNote {
+    /// When it arrived
+    pub at: Instant,
+    /// Note as played
+    pub note: u8,
+    /// Velocity as played
+    pub velocity: u8,
+    /// Limb that played it, or `None` for pads the map leaves out (e.g., toms)
+    pub limb: Option<Limb>,
+}
+
+/// Notes played on an electronic kit, kept with their arrival times for
+/// timing against the loop
+///
+/// Each note-on is sorted into a limb with a [`KitMap`]; everything else the
+/// kit sends is ignored. Note-ons can be echoed to a [`DrumPad`] as they
+/// arrive, for kits whose own sound module isn't plugged in.
+pub struct KitInput {
+    notes: Arc<Mutex<Vec<KitNote>>>,
+    /// Keeps the input port open; `None` for kits fed by hand in tests
+    _connection: Option<MidiInputConnection<()>>,
+}
+
+impl KitInput {
+    /// Listen on the first input port whose name contains `port_name`,
+    /// telling limbs apart with `map` and playing each note-on through
+    /// `echo`, if given
+    pub fn connect(
+        port_name: &str,
+        map: KitMap,
+        echo: Option<DrumPad>,
+    ) -> Result<Self, KickbeatsError> {
+        let mut midi_in = MidiInput::new("Kickbeats")
+            .map_err(|e| MidiError::new(format!("Failed to initialize MIDI input: {}", e)))?;
+        midi_in.ignore(Ignore::All);
+
+        let ports = midi_in.ports();
+        if ports.is_empty() {
+            return Err(MidiError::new("No MIDI input ports available for the kit").into());
+        }
+        let port = ports
+            .iter()
+            .find(|p| {
+                midi_in
+                    .port_name(p)
+                    .map(|name| name.contains(port_name))
+                    .unwrap_or(false)
+            })
+            .ok_or_else(|| MidiError::new(format!("MIDI input port '{}' not found", port_name)))?;
+
+        let notes = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&notes);
+        let mut echo = echo;
+        let connection = midi_in
+            .connect(
+                port,
+                "kickbeats-kit",
+                move |_, message, _| {
+                    receive(&map, &received, echo.as_mut(), message, Instant::now())
+                },
+                (),
+            )
+            .map_err(|e| MidiError::new(format!("Failed to connect to MIDI input port: {}", e)))?;
+
+        Ok(Self {
+            notes,
+            _connection: Some(connection),
+        })
+    }
+
+    /// Collect the note-ons heard since the last call, oldest first
+    pub fn drain(&self) -> Vec<KitNote> {
+        self.notes
+            .lock()
+            .map(|mut notes| std::mem::take(&mut *notes))
+            .unwrap_or_default()
+    }
+
+    /// A kit fed by hand instead of a port
+    #[cfg(test)]
+    pub(crate) fn detached() -> Self {
+        Self {
+            notes: Arc::new(Mutex::new(Vec::new())),
+            _connection: None,
+        }
+    }
+
+    /// Take `message` as if the kit had just sent it
+    #[cfg(test)]
+    pub(crate) fn feed(&self, map: &KitMap, message: &[u8], at: Instant) {
+        receive(map, &self.notes, None, message, at);
+    }
+}
+
+/// Keep one message from the kit if it's a note-on, echoing it through
+/// `echo` if given
+///
+/// The echo goes out before the note is kept, so it lags the kit as little
+/// as it can.
+fn receive(
+    map: &KitMap,
+    notes: &Mutex<Vec<KitNote>>,
+    echo: Option<&mut DrumPad>,
+    message: &[u8],
+    at: Instant,
+) {
+    let &[status, note, velocity] = message else {
+        return;
+    };
+    if status & 0xF0 != 0x90 || velocity == 0 {
+        return;
+    }
+    if let Some(pad) = echo {
+        let _ = pad.echo(note, velocity);
+    }
+    if let Ok(mut notes) = notes.lock() {
+        notes.push(KitNote {
+            at,
+            note,
+            velocity,
+            limb: map.limb(note),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{MemorySink, MidiMapping};
+
+    #[test]
+    fn test_kit_map_sorts_notes_into_limbs() {
+        let kit = KitInput::detached();
+        let map = KitMap::new(vec![36], vec![38, 40], vec![26, 46]).unwrap();
+        let now = Instant::now();
+
+        // Hi-hat edge, a tom the map leaves out, a rimshot, and from a kit on
+        // channel 10 a note-off, a zero-velocity note-on, and a control change
+        for message in [
+            [0x99, 26, 80],
+            [0x99, 45, 80],
+            [0x99, 40, 100],
+            [0x89, 40, 0],
+            [0x99, 26, 0],
+            [0xB9, 4, 127],
+        ] {
+            kit.feed(&map, &message, now);
+        }
+        let notes = kit.drain();
+        let limbs: Vec<_> = notes.iter().map(|note| note.limb).collect();
+        assert_eq!(limbs, [Some(Limb::HiHat), None, Some(Limb::Snare)]);
+        assert!(notes[2].note == 40 && notes[2].velocity == 100);
+        assert!(kit.drain().is_empty());
+
+        assert_eq!(KitMap::default().limb(35), Some(Limb::Kick));
+        assert_eq!(KitMap::default().limb(44), Some(Limb::HiHat));
+        // The default click note isn't taken for a snare
+        assert_eq!(KitMap::default().limb(37), None);
+        assert!(KitMap::new(vec![36], vec![36], vec![])
+            .unwrap_err()
+            .to_string()
+            .contains("both the kick and the snare"));
+    }
+
+    #[test]
+    fn test_echo_plays_note_ons_as_played() {
+        let sink = MemorySink::new();
+        let mapping = MidiMapping::new(36, 76, 9).unwrap();
+        let mut pad = DrumPad::with_sink(Box::new(sink.clone()), mapping, None);
+        let notes = Mutex::new(Vec::new());
+        let now = Instant::now();
+
+        for message in [[0x99, 38, 90], [0x89, 38, 0], [0x99, 38, 60]] {
+            receive(&KitMap::default(), &notes, Some(&mut pad), &message, now);
+        }
+        // Struck as played on the playback channel, released before the second hit
+        assert_eq!(
+            sink.messages(),
+            [vec![0x99, 38, 90], vec![0x89, 38, 0], vec![0x99, 38, 60]]
+        );
+        assert_eq!(notes.lock().unwrap().len(), 2);
+    }
+}
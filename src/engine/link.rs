@@ -0,0 +1,101 @@
+use rusty_link::{AblLink, SessionState};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Membership in an Ableton Link session on the local network
+///
+/// Peers share one tempo and one beat timeline. Clones refer to the same
+/// session, so the playback thread and the interface can each hold one.
+#[derive(Clone)]
+pub struct LinkSession {
+    link: Arc<AblLink>,
+}
+
+impl LinkSession {
+    /// Join (or start) a Link session, proposing `tempo_bpm` if alone
+    pub fn join(tempo_bpm: u16) -> Self {
+        let link = AblLink::new(f64::from(tempo_bpm));
+        link.enable(true);
+        Self {
+            link: Arc::new(link),
+        }
+    }
+
+    /// Number of other Link apps in the session
+    pub fn peers(&self) -> u64 {
+        self.link.num_peers()
+    }
+
+    /// The session tempo, rounded and limited to 40-300 BPM
+    pub fn tempo(&self) -> u16 {
+        let tempo = self.capture().tempo().round().clamp(40.0, 300.0);
+        tempo as u16
+    }
+
+    /// Change the session tempo for every peer
+    pub fn set_tempo(&self, tempo_bpm: u16) {
+        let mut state = self.capture();
+        state.set_tempo(f64::from(tempo_bpm), self.link.clock_micros());
+        self.link.commit_app_session_state(&state);
+    }
+
+    /// First time at or after `at` where a span of `quantum` beats begins
+    pub fn next_downbeat(&self, at: Instant, quantum: f64) -> Instant {
+        self.downbeat(at, quantum, f64::ceil)
+    }
+
+    /// Time nearest `at` where a span of `quantum` beats begins
+    pub fn nearest_downbeat(&self, at: Instant, quantum: f64) -> Instant {
+        self.downbeat(at, quantum, f64::round)
+    }
+
+    fn downbeat(&self, at: Instant, quantum: f64, snap: fn(f64) -> f64) -> Instant {
+        let state = self.capture();
+        let now = Instant::now();
+        let clock = self.link.clock_micros();
+        let beat = state.beat_at_time(micros_at(at, now, clock), quantum);
+        let downbeat = state.time_at_beat(snap(beat / quantum) * quantum, quantum);
+        instant_at(downbeat, now, clock)
+    }
+
+    fn capture(&self) -> SessionState {
+        let mut state = SessionState::new();
+        self.link.capture_app_session_state(&mut state);
+        state
+    }
+}
+
+/// Link clock reading at `at`, given the reading `clock` taken at `now`
+fn micros_at(at: Instant, now: Instant, clock: i64) -> i64 {
+    match at.checked_duration_since(now) {
+        Some(ahead) => clock + ahead.as_micros() as i64,
+        None => clock - now.duration_since(at).as_micros() as i64,
+    }
+}
+
+/// Instant of Link clock reading `micros`, given the reading `clock` taken at `now`
+fn instant_at(micros: i64, now: Instant, clock: i64) -> Instant {
+    let offset = Duration::from_micros(micros.abs_diff(clock));
+    if micros >= clock {
+        now + offset
+    } else {
+        now.checked_sub(offset).unwrap_or(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_conversion_round_trips() {
+        let now = Instant::now();
+        let later = now + Duration::from_millis(250);
+        assert_eq!(micros_at(later, now, 1_000_000), 1_250_000);
+        assert_eq!(instant_at(1_250_000, now, 1_000_000), later);
+        assert_eq!(
+            instant_at(750_000, now, 1_000_000) + Duration::from_millis(250),
+            now
+        );
+    }
+}
@@ -1,5 +1,12 @@
-use crate::models::{BeatGrid, Pattern};
-use midir::{MidiOutput, MidiOutputConnection};
+use crate::engine::recorder::SessionRecorder;
+use crate::engine::sink::MidiSink;
+use crate::error::KickbeatsError;
+use crate::models::{
+    BeatGrid, ClickDrift, ClickGap, ClickRatio, CountIn, Hemiola, KickDropout, Pattern,
+    TimeSignature,
+};
+use midir::MidiOutput;
+use rand::Rng;
 use std::error::Error;
 use std::fmt;
 
@@ -9,15 +16,140 @@ pub const KICK_NOTE: u8 = 36;
 /// MIDI note number for click/rimshot sound (C#1 in General MIDI percussion map)
 pub const CLICK_NOTE: u8 = 37;
 
+/// MIDI note number for the drum pad's snare (Acoustic Snare in General MIDI percussion map)
+pub const SNARE_NOTE: u8 = 38;
+
+/// MIDI note number for overlay figures (Claves in General MIDI percussion map)
+pub const OVERLAY_NOTE: u8 = 75;
+
+/// MIDI note number for the phrase cue (Hi Wood Block in General MIDI percussion map)
+pub const PHRASE_CUE_NOTE: u8 = 76;
+
+/// MIDI note number for the hemiola trainer's implied pulse (Low Wood Block
+/// in General MIDI percussion map)
+pub const HEMIOLA_NOTE: u8 = 77;
+
+/// Channel mode controller that releases every sounding note on a channel
+pub const ALL_NOTES_OFF: u8 = 123;
+
+/// MIDI timing clock message, sent 24 times per quarter note
+pub const CLOCK_TICK: u8 = 0xF8;
+
+/// MIDI start message: synced gear starts from the top on the next tick
+pub const CLOCK_START: u8 = 0xFA;
+
+/// MIDI continue message: synced gear resumes where it stopped
+pub const CLOCK_CONTINUE: u8 = 0xFB;
+
+/// MIDI stop message
+pub const CLOCK_STOP: u8 = 0xFC;
+
+/// MIDI clock ticks per sixteenth note (24 per quarter)
+pub(crate) const CLOCK_TICKS_PER_SIXTEENTH: usize = 6;
+
 /// Default MIDI velocity for kick drum hits (0-127 range)
 pub const KICK_VELOCITY: u8 = 100;
 
 /// Default MIDI velocity for click track hits (0-127 range)
 pub const CLICK_VELOCITY: u8 = 80;
 
+/// How much one press of a level key raises or lowers a voice's velocity
+pub const VELOCITY_STEP: u8 = 10;
+
+/// MIDI velocity for the accented first click of the count-in
+pub const CLICK_ACCENT_VELOCITY: u8 = 120;
+
+/// MIDI velocity for the eighths between pulses in a subdivided count-in
+pub const COUNT_IN_SUBDIVISION_VELOCITY: u8 = 60;
+
+/// Default MIDI velocity for overlay hits (0-127 range)
+pub const OVERLAY_VELOCITY: u8 = 70;
+
+/// MIDI velocity for the phrase cue, soft enough to stay out of the way
+pub const PHRASE_CUE_VELOCITY: u8 = 60;
+
 /// MIDI channel for percussion (Channel 10, zero-indexed as 9)
 pub const MIDI_CHANNEL: u8 = 9;
 
+/// Notes and channel that playback is sent on
+///
+/// The defaults follow the General MIDI percussion map; other drum samplers
+/// or melodic instruments can use their own notes and channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MidiMapping {
+    /// Note number for kick hits (0-127)
+    pub kick_note: u8,
+    /// Note number for the click track and count-in (0-127)
+    pub click_note: u8,
+    /// MIDI channel, zero-indexed (0-15)
+    pub channel: u8,
+    /// MIDI channel for the click track and count-in, zero-indexed (0-15);
+    /// the same as `channel` unless split off with `with_click_channel`
+    pub click_channel: u8,
+}
+
+impl MidiMapping {
+    /// Create a mapping, checking note and channel ranges
+    pub fn new(kick_note: u8, click_note: u8, channel: u8) -> Result<Self, KickbeatsError> {
+        if kick_note > 127 || click_note > 127 {
+            return Err(KickbeatsError::Config(
+                "MIDI notes must be between 0 and 127".to_string(),
+            ));
+        }
+        if channel > 15 {
+            return Err(KickbeatsError::Config(format!(
+                "MIDI channel {} out of range (1-16)",
+                u16::from(channel) + 1
+            )));
+        }
+
+        Ok(Self {
+            kick_note,
+            click_note,
+            channel,
+            click_channel: channel,
+        })
+    }
+
+    /// Play the click track and count-in on their own channel (zero-indexed),
+    /// so a DAW can pan them or give them another instrument
+    pub fn with_click_channel(mut self, click_channel: u8) -> Result<Self, KickbeatsError> {
+        if click_channel > 15 {
+            return Err(KickbeatsError::Config(format!(
+                "MIDI click channel {} out of range (1-16)",
+                u16::from(click_channel) + 1
+            )));
+        }
+        self.click_channel = click_channel;
+        Ok(self)
+    }
+
+    /// Channel `voice` plays on
+    pub fn channel_for(&self, voice: Voice) -> u8 {
+        match voice {
+            Voice::Click => self.click_channel,
+            _ => self.channel,
+        }
+    }
+}
+
+impl Default for MidiMapping {
+    fn default() -> Self {
+        Self {
+            kick_note: KICK_NOTE,
+            click_note: CLICK_NOTE,
+            channel: MIDI_CHANNEL,
+            click_channel: MIDI_CHANNEL,
+        }
+    }
+}
+
+/// Largest kick timing offset at full humanization, in seconds (±20ms)
+pub const HUMANIZE_MAX_OFFSET: f64 = 0.02;
+
+/// Largest kick velocity change at full humanization
+pub const HUMANIZE_MAX_VELOCITY: i16 = 20;
+
 /// Custom error type for MIDI operations with platform-specific guidance
 #[derive(Debug)]
 pub struct MidiError {
@@ -98,11 +230,28 @@ impl fmt::Display for MidiError {
 
 impl Error for MidiError {}
 
+/// Which part of the playback an event belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Voice {
+    /// The generated kick pattern
+    Kick,
+    /// The click track (metronome and count-in)
+    Click,
+    /// A reference figure (e.g., clave) played alongside the pattern
+    Overlay,
+    /// The marker on the first downbeat of each phrase
+    Cue,
+    /// MIDI clock for synced gear
+    Clock,
+}
+
 /// Represents a scheduled MIDI event
 #[derive(Debug, Clone, Copy)]
 pub struct MidiEvent {
     /// Time offset from start of pattern in seconds
     pub time_offset: f64,
+    /// Voice the event belongs to
+    pub voice: Voice,
     /// MIDI note number
     pub note: u8,
     /// Note velocity (0-127)
@@ -115,6 +264,8 @@ pub struct MidiEvent {
 pub enum MidiEventType {
     NoteOn,
     NoteOff,
+    /// A timing clock tick (note and velocity are unused)
+    Clock,
 }
 
 /// Manages MIDI output and playback
@@ -122,19 +273,26 @@ pub enum MidiEventType {
 /// # Examples
 ///
 /// ```no_run
-/// use kickbeats::engine::midi::MidiEngine;
+/// use kickbeats_cli::engine::midi::MidiEngine;
 ///
 /// let mut engine = MidiEngine::new();
-/// let ports = MidiEngine::list_ports()?;
-/// engine.connect(&ports[0])?;
+/// engine.connect(&MidiEngine::first_port()?)?;
 /// engine.send_note_on(36, 100)?;  // Play kick drum
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
 pub struct MidiEngine {
-    /// Active MIDI output connection
-    connection: Option<MidiOutputConnection>,
-    /// MIDI channel to use (0-15)
-    channel: u8,
+    /// Where messages are sent: a MIDI output port, or an in-memory sink in tests
+    connection: Option<Box<dyn MidiSink>>,
+    /// Where click track messages go instead, if they have a port of their own
+    click_connection: Option<Box<dyn MidiSink>>,
+    /// Notes and channel to play on
+    mapping: MidiMapping,
+    /// Amount of timing and velocity jitter applied to kicks (0-100)
+    humanize: u8,
+    /// Bars, subdivision, and voicing of the count-in
+    count_in: CountIn,
+    /// Session log that every sent message is copied to
+    recorder: Option<SessionRecorder>,
 }
 
 impl MidiEngine {
@@ -142,13 +300,79 @@ impl MidiEngine {
     pub fn new() -> Self {
         Self {
             connection: None,
-            channel: MIDI_CHANNEL,
+            click_connection: None,
+            mapping: MidiMapping::default(),
+            humanize: 0,
+            count_in: CountIn::default(),
+            recorder: None,
         }
     }
 
+    /// Copy every message sent from now on into a session log
+    pub fn set_recorder(&mut self, recorder: Option<SessionRecorder>) {
+        self.recorder = recorder;
+    }
+
+    /// Play on a different set of notes and channel
+    pub fn set_mapping(&mut self, mapping: MidiMapping) {
+        self.mapping = mapping;
+    }
+
+    /// Notes and channel currently played on
+    pub fn mapping(&self) -> MidiMapping {
+        self.mapping
+    }
+
+    /// Count patterns in with `count_in` instead of one bar of clicks
+    pub fn set_count_in(&mut self, count_in: CountIn) {
+        self.count_in = count_in;
+    }
+
+    /// Set how much random timing and velocity variation kicks get (0-100)
+    ///
+    /// Jitter is drawn anew each time events are built; the click is never
+    /// humanized so it stays a steady reference.
+    pub fn set_humanize(&mut self, amount: u8) {
+        self.humanize = amount.min(100);
+    }
+
+    /// Randomize a kick's onset and velocity according to the humanize amount
+    fn humanize_kick(&self, time_offset: f64) -> (f64, u8) {
+        if self.humanize == 0 {
+            return (time_offset, KICK_VELOCITY);
+        }
+
+        let amount = f64::from(self.humanize) / 100.0;
+        let max_offset = HUMANIZE_MAX_OFFSET * amount;
+        let max_velocity = (f64::from(HUMANIZE_MAX_VELOCITY) * amount) as i16;
+
+        let mut rng = rand::thread_rng();
+        let offset = rng.gen_range(-max_offset..=max_offset);
+        let velocity = i16::from(KICK_VELOCITY) + rng.gen_range(-max_velocity..=max_velocity);
+
+        (
+            (time_offset + offset).max(0.0),
+            velocity.clamp(1, 127) as u8,
+        )
+    }
+
     /// Connect to a MIDI output port by name
-    pub fn connect(&mut self, port_name: &str) -> Result<(), Box<dyn Error>> {
-        let midi_out = MidiOutput::new("Kickbeats")?;
+    pub fn connect(&mut self, port_name: &str) -> Result<(), KickbeatsError> {
+        self.connection = Some(Self::open_port(port_name)?);
+        Ok(())
+    }
+
+    /// Send the click track and count-in to the MIDI output port named
+    /// `port_name` instead of the one the kicks play on
+    pub fn connect_click(&mut self, port_name: &str) -> Result<(), KickbeatsError> {
+        self.click_connection = Some(Self::open_port(port_name)?);
+        Ok(())
+    }
+
+    /// Open the MIDI output port whose name contains `port_name`
+    fn open_port(port_name: &str) -> Result<Box<dyn MidiSink>, KickbeatsError> {
+        let midi_out = MidiOutput::new("Kickbeats")
+            .map_err(|e| MidiError::new(format!("Failed to initialize MIDI system: {}", e)))?;
 
         // Find port by name
         let ports = midi_out.ports();
@@ -160,27 +384,68 @@ impl MidiEngine {
                     .map(|name| name.contains(port_name))
                     .unwrap_or(false)
             })
-            .ok_or_else(|| format!("MIDI port '{}' not found", port_name))?;
+            .ok_or_else(|| MidiError::new(format!("MIDI port '{}' not found", port_name)))?;
 
         // Connect to port
-        let connection = midi_out.connect(port, "kickbeats-output")?;
-        self.connection = Some(connection);
+        let connection = midi_out.connect(port, "kickbeats-output").map_err(|e| {
+            MidiError::new(format!(
+                "Failed to connect to MIDI port '{}': {}",
+                port_name, e
+            ))
+        })?;
+        Ok(Box::new(connection))
+    }
 
-        Ok(())
+    /// Create a virtual output port called `name` that synths and DAWs can connect to
+    ///
+    /// Supported on macOS (CoreMIDI) and Linux (ALSA); Windows has no virtual
+    /// ports, so this returns an error suggesting loopMIDI instead.
+    pub fn create_virtual_port(name: &str) -> Result<Box<dyn MidiSink>, KickbeatsError> {
+        #[cfg(unix)]
+        {
+            use midir::os::unix::VirtualOutput;
+
+            let midi_out = MidiOutput::new("Kickbeats")
+                .map_err(|e| MidiError::new(format!("Failed to initialize MIDI system: {}", e)))?;
+            let connection = midi_out.create_virtual(name).map_err(|e| {
+                MidiError::new(format!(
+                    "Failed to create virtual MIDI port '{}': {}",
+                    name, e
+                ))
+            })?;
+            Ok(Box::new(connection))
+        }
+
+        #[cfg(not(unix))]
+        {
+            Err(MidiError::new(format!(
+                "Can't create virtual MIDI port '{}': virtual ports aren't supported on this \
+                 platform (create one in loopMIDI and pass --midi-port instead)",
+                name
+            ))
+            .into())
+        }
+    }
+
+    /// Send to `sink` instead of a MIDI port
+    pub fn connect_sink(&mut self, sink: Box<dyn MidiSink>) {
+        self.connection = Some(sink);
+    }
+
+    /// Send the click track and count-in to `sink` instead of the kicks' port
+    pub fn connect_click_sink(&mut self, sink: Box<dyn MidiSink>) {
+        self.click_connection = Some(sink);
     }
 
     /// List available MIDI output ports with enhanced error reporting
-    pub fn list_ports() -> Result<Vec<String>, Box<dyn Error>> {
-        let midi_out = MidiOutput::new("Kickbeats").map_err(|e| {
-            MidiError::new(format!("Failed to initialize MIDI system: {}", e))
-        })?;
+    pub fn list_ports() -> Result<Vec<String>, KickbeatsError> {
+        let midi_out = MidiOutput::new("Kickbeats")
+            .map_err(|e| MidiError::new(format!("Failed to initialize MIDI system: {}", e)))?;
 
         let ports = midi_out.ports();
 
         if ports.is_empty() {
-            return Err(Box::new(MidiError::new(
-                "No MIDI output ports found on this system"
-            )));
+            return Err(MidiError::new("No MIDI output ports found on this system").into());
         }
 
         let port_names: Vec<String> = ports
@@ -191,49 +456,160 @@ impl MidiEngine {
         Ok(port_names)
     }
 
+    /// Name of the first MIDI output port whose name can be read
+    pub fn first_port() -> Result<String, KickbeatsError> {
+        Self::list_ports()?
+            .into_iter()
+            .next()
+            .ok_or_else(|| MidiError::new("No readable MIDI output ports on this system").into())
+    }
+
     /// Send a note-on message
-    pub fn send_note_on(&mut self, note: u8, velocity: u8) -> Result<(), Box<dyn Error>> {
-        if let Some(conn) = &mut self.connection {
-            let msg = [0x90 | self.channel, note, velocity];
-            conn.send(&msg)?;
-            Ok(())
-        } else {
-            Err("MIDI engine not connected".into())
-        }
+    pub fn send_note_on(&mut self, note: u8, velocity: u8) -> Result<(), KickbeatsError> {
+        self.send_voice_note_on(Voice::Kick, note, velocity)
     }
 
     /// Send a note-off message
-    pub fn send_note_off(&mut self, note: u8) -> Result<(), Box<dyn Error>> {
+    pub fn send_note_off(&mut self, note: u8) -> Result<(), KickbeatsError> {
+        self.send_voice_note_off(Voice::Kick, note)
+    }
+
+    /// Send a note-on message for `voice`, on its channel and port
+    pub fn send_voice_note_on(
+        &mut self,
+        voice: Voice,
+        note: u8,
+        velocity: u8,
+    ) -> Result<(), KickbeatsError> {
+        let channel = self.mapping.channel_for(voice);
+        self.connection_for(voice)?
+            .send(&[0x90 | channel, note, velocity])?;
+        if let Some(recorder) = &self.recorder {
+            recorder.note_on(channel, note, velocity);
+        }
+        Ok(())
+    }
+
+    /// Send a note-off message for `voice`, on its channel and port
+    pub fn send_voice_note_off(&mut self, voice: Voice, note: u8) -> Result<(), KickbeatsError> {
+        let channel = self.mapping.channel_for(voice);
+        self.connection_for(voice)?
+            .send(&[0x80 | channel, note, 0])?;
+        if let Some(recorder) = &self.recorder {
+            recorder.note_off(channel, note);
+        }
+        Ok(())
+    }
+
+    /// Where `voice`'s messages go: the click's own port if it has one,
+    /// otherwise the main connection
+    fn connection_for(&mut self, voice: Voice) -> Result<&mut Box<dyn MidiSink>, KickbeatsError> {
+        let click = match voice {
+            Voice::Click => self.click_connection.as_mut(),
+            _ => None,
+        };
+        click
+            .or(self.connection.as_mut())
+            .ok_or_else(|| MidiError::new("MIDI engine not connected").into())
+    }
+
+    /// Release every note playback may have left sounding
+    ///
+    /// Sends note-offs for each note kickbeats plays, then All Notes Off on
+    /// all sixteen channels for gear that ignores stray note-offs.
+    pub fn silence(&mut self) -> Result<(), KickbeatsError> {
+        if self.connection.is_none() {
+            return Ok(());
+        }
+        let notes = [
+            (Voice::Kick, self.mapping.kick_note),
+            (Voice::Click, self.mapping.click_note),
+            (Voice::Click, HEMIOLA_NOTE),
+            (Voice::Overlay, OVERLAY_NOTE),
+            (Voice::Cue, PHRASE_CUE_NOTE),
+        ];
+        for (voice, note) in notes {
+            self.send_voice_note_off(voice, note)?;
+        }
+        for conn in self.connection.iter_mut().chain(&mut self.click_connection) {
+            for channel in 0..16u8 {
+                conn.send(&[0xB0 | channel, ALL_NOTES_OFF, 0])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Send a one-byte system real-time message (e.g., [`CLOCK_TICK`])
+    ///
+    /// Not copied to the session log, since Standard MIDI Files can't hold them.
+    pub fn send_realtime(&mut self, status: u8) -> Result<(), KickbeatsError> {
         if let Some(conn) = &mut self.connection {
-            let msg = [0x80 | self.channel, note, 0];
-            conn.send(&msg)?;
-            Ok(())
+            conn.send(&[status])
         } else {
-            Err("MIDI engine not connected".into())
+            Err(MidiError::new("MIDI engine not connected").into())
         }
     }
 
-    /// Generate count-in click events (4 beats)
-    pub fn generate_count_in_events(&self, tempo_bpm: u16) -> Vec<MidiEvent> {
+    /// Evenly spaced clock ticks (24 per quarter note) across `sixteenths` sixteenth
+    /// notes lasting `duration_secs`
+    pub fn clock_events(&self, duration_secs: f64, sixteenths: usize) -> Vec<MidiEvent> {
+        let ticks = sixteenths * CLOCK_TICKS_PER_SIXTEENTH;
+        (0..ticks)
+            .map(|tick| MidiEvent {
+                time_offset: duration_secs * tick as f64 / ticks as f64,
+                voice: Voice::Clock,
+                note: 0,
+                velocity: 0,
+                event_type: MidiEventType::Clock,
+            })
+            .collect()
+    }
+
+    /// Generate the count-in clicks, accenting each bar's first
+    ///
+    /// Clicks fall on the meter's pulse: 3 quarters in 3/4, 2 dotted quarters
+    /// in 6/8, 7 eighths in 7/8. A grouped meter clicks on each group's start
+    /// instead: 3 clicks for 7/8 as 2+2+3. The count-in set with
+    /// `set_count_in` decides how many bars there are, whether the last one
+    /// adds softer eighths, and whether its pulses are voiced on notes of
+    /// their own.
+    pub fn generate_count_in_events(
+        &self,
+        time_signature: TimeSignature,
+        tempo_bpm: u16,
+    ) -> Vec<MidiEvent> {
         let mut events = Vec::new();
-        let seconds_per_beat = 60.0 / tempo_bpm as f64;
-        let count_in_beats = 4;
+        let grid = BeatGrid::new(time_signature, 16, 1);
+        let seconds_per_sixteenth = grid.seconds_per_position(tempo_bpm);
 
-        for beat in 0..count_in_beats {
-            let time_offset = beat as f64 * seconds_per_beat;
+        for click in self.count_in.clicks(time_signature) {
+            let time_offset = click.offset as f64 * seconds_per_sixteenth;
+            let velocity = if click.accented {
+                CLICK_ACCENT_VELOCITY
+            } else if click.subdivision {
+                COUNT_IN_SUBDIVISION_VELOCITY
+            } else {
+                CLICK_VELOCITY
+            };
+            let note = click
+                .count
+                .and_then(|count| self.count_in.count_off_note(count))
+                .unwrap_or(self.mapping.click_note);
 
             // Note on
             events.push(MidiEvent {
                 time_offset,
-                note: CLICK_NOTE,
-                velocity: CLICK_VELOCITY,
+                voice: Voice::Click,
+                note,
+                velocity,
                 event_type: MidiEventType::NoteOn,
             });
 
             // Note off (50ms later)
             events.push(MidiEvent {
                 time_offset: time_offset + 0.05,
-                note: CLICK_NOTE,
+                voice: Voice::Click,
+                note,
                 velocity: 0,
                 event_type: MidiEventType::NoteOff,
             });
@@ -242,6 +618,40 @@ impl MidiEngine {
         events
     }
 
+    /// An extra count-in click on the "and" of the last pulse, a pickup
+    /// warning that the pattern coming may sound like a recent one (none
+    /// without a count-in)
+    pub fn count_in_cue_events(
+        &self,
+        time_signature: TimeSignature,
+        tempo_bpm: u16,
+    ) -> Vec<MidiEvent> {
+        if self.count_in.bars == 0 {
+            return Vec::new();
+        }
+        let grid = BeatGrid::new(time_signature, 16, 1);
+        let seconds_per_pulse =
+            time_signature.pulse_sixteenths() as f64 * grid.seconds_per_position(tempo_bpm);
+        let time_offset =
+            self.count_in_duration(time_signature, tempo_bpm) - 0.5 * seconds_per_pulse;
+        vec![
+            MidiEvent {
+                time_offset,
+                voice: Voice::Click,
+                note: self.mapping.click_note,
+                velocity: CLICK_VELOCITY,
+                event_type: MidiEventType::NoteOn,
+            },
+            MidiEvent {
+                time_offset: time_offset + 0.05,
+                voice: Voice::Click,
+                note: self.mapping.click_note,
+                velocity: 0,
+                event_type: MidiEventType::NoteOff,
+            },
+        ]
+    }
+
     /// Convert a pattern to a sequence of MIDI events (without count-in)
     pub fn pattern_to_midi_events(
         &self,
@@ -260,23 +670,26 @@ impl MidiEngine {
 
         let seconds_per_position = grid.seconds_per_position(tempo_bpm);
 
-        // Generate click track events (on every beat)
+        // Generate click track events (on every beat, accenting each bar line
+        // when the pattern changes meter)
         if include_click {
-            for beat_idx in grid.beat_positions() {
+            for (beat_idx, velocity) in Self::click_positions(pattern, &grid) {
                 let time_offset = beat_idx as f64 * seconds_per_position;
 
                 // Note on
                 events.push(MidiEvent {
                     time_offset,
-                    note: CLICK_NOTE,
-                    velocity: CLICK_VELOCITY,
+                    voice: Voice::Click,
+                    note: self.mapping.click_note,
+                    velocity,
                     event_type: MidiEventType::NoteOn,
                 });
 
                 // Note off (50ms later)
                 events.push(MidiEvent {
                     time_offset: time_offset + 0.05,
-                    note: CLICK_NOTE,
+                    voice: Voice::Click,
+                    note: self.mapping.click_note,
                     velocity: 0,
                     event_type: MidiEventType::NoteOff,
                 });
@@ -286,20 +699,22 @@ impl MidiEngine {
         // Generate kick drum events
         for (i, &has_kick) in pattern.steps.iter().enumerate() {
             if has_kick {
-                let time_offset = i as f64 * seconds_per_position;
+                let (time_offset, velocity) = self.humanize_kick(i as f64 * seconds_per_position);
 
                 // Note on
                 events.push(MidiEvent {
                     time_offset,
-                    note: KICK_NOTE,
-                    velocity: KICK_VELOCITY,
+                    voice: Voice::Kick,
+                    note: self.mapping.kick_note,
+                    velocity,
                     event_type: MidiEventType::NoteOn,
                 });
 
                 // Note off (100ms later)
                 events.push(MidiEvent {
                     time_offset: time_offset + 0.1,
-                    note: KICK_NOTE,
+                    voice: Voice::Kick,
+                    note: self.mapping.kick_note,
                     velocity: 0,
                     event_type: MidiEventType::NoteOff,
                 });
@@ -307,14 +722,371 @@ impl MidiEngine {
         }
 
         // Sort events by time
-        events.sort_by(|a, b| a.time_offset.partial_cmp(&b.time_offset).unwrap());
+        events.sort_by(|a, b| a.time_offset.total_cmp(&b.time_offset));
+
+        events
+    }
+
+    /// Click steps and velocities for a pattern
+    ///
+    /// Single-meter patterns click evenly on the beat; patterns with meter
+    /// changes restart the count at each bar line and accent its downbeat.
+    /// Grouped meters (e.g., 7/8 as 2+2+3) click on each group's start, also
+    /// accenting the downbeat, so the uneven groups are heard.
+    fn click_positions(pattern: &Pattern, grid: &BeatGrid) -> Vec<(usize, u8)> {
+        if !pattern.is_multi_measure() {
+            let grouped = pattern.time_signature.grouping.is_some();
+            return grid
+                .beat_positions()
+                .into_iter()
+                .map(|beat_idx| match beat_idx {
+                    0 if grouped => (beat_idx, CLICK_ACCENT_VELOCITY),
+                    _ => (beat_idx, CLICK_VELOCITY),
+                })
+                .collect();
+        }
+
+        let measures = pattern.measures();
+        let mut positions = Vec::new();
+        for (i, &(start, meter)) in measures.iter().enumerate() {
+            let end = measures
+                .get(i + 1)
+                .map_or(pattern.steps.len(), |&(next, _)| next);
+            let measure = BeatGrid::new(meter, pattern.subdivision, 1);
+            for beat_idx in measure.beat_positions() {
+                if start + beat_idx >= end {
+                    break;
+                }
+                let velocity = if beat_idx == 0 {
+                    CLICK_ACCENT_VELOCITY
+                } else {
+                    CLICK_VELOCITY
+                };
+                positions.push((start + beat_idx, velocity));
+            }
+        }
+        positions
+    }
+
+    /// Convert overlay steps to MIDI events timed against a pattern's grid
+    pub fn overlay_to_midi_events(
+        &self,
+        pattern: &Pattern,
+        overlay_steps: &[bool],
+        tempo_bpm: u16,
+    ) -> Vec<MidiEvent> {
+        let grid = BeatGrid::new(
+            pattern.time_signature,
+            pattern.subdivision,
+            pattern.num_measures,
+        );
+        let seconds_per_position = grid.seconds_per_position(tempo_bpm);
+
+        let mut events = Vec::new();
+        for (i, &has_hit) in overlay_steps.iter().enumerate() {
+            if has_hit {
+                let time_offset = i as f64 * seconds_per_position;
+
+                events.push(MidiEvent {
+                    time_offset,
+                    voice: Voice::Overlay,
+                    note: OVERLAY_NOTE,
+                    velocity: OVERLAY_VELOCITY,
+                    event_type: MidiEventType::NoteOn,
+                });
+
+                // Note off (50ms later)
+                events.push(MidiEvent {
+                    time_offset: time_offset + 0.05,
+                    voice: Voice::Overlay,
+                    note: OVERLAY_NOTE,
+                    velocity: 0,
+                    event_type: MidiEventType::NoteOff,
+                });
+            }
+        }
+
+        events
+    }
+
+    /// Pattern events with click track and an optional overlay, sorted by time
+    pub fn loop_events(
+        &self,
+        pattern: &Pattern,
+        tempo_bpm: u16,
+        overlay_steps: Option<&[bool]>,
+    ) -> Vec<MidiEvent> {
+        let mut events = self.pattern_to_midi_events(pattern, tempo_bpm, true);
+        if let Some(steps) = overlay_steps {
+            events.extend(self.overlay_to_midi_events(pattern, steps, tempo_bpm));
+            events.sort_by(|a, b| a.time_offset.total_cmp(&b.time_offset));
+        }
+        events
+    }
+
+    /// Loop events with the click replaced by a tempo-canon click lane
+    ///
+    /// The canon click runs at `ratio` of the pattern tempo; clicks that land
+    /// on a pattern beat are accented so the shared downbeats stand out.
+    pub fn with_canon_click(
+        &self,
+        events: &[MidiEvent],
+        pattern: &Pattern,
+        tempo_bpm: u16,
+        ratio: ClickRatio,
+        loop_index: u64,
+    ) -> Vec<MidiEvent> {
+        let clicks = ratio.clicks_in_loop(loop_index, Self::beats_per_loop(pattern));
+        self.with_click_lane(events, pattern, tempo_bpm, &clicks)
+    }
+
+    /// Loop events for the drifting-click tempo trainer
+    ///
+    /// The click follows `drift` away from the pattern tempo. The kicks only
+    /// sound in the first loop, which sets the reference; after that the
+    /// player carries them against the wandering click.
+    pub fn with_drifting_click(
+        &self,
+        events: &[MidiEvent],
+        pattern: &Pattern,
+        tempo_bpm: u16,
+        drift: ClickDrift,
+        loop_index: u64,
+    ) -> Vec<MidiEvent> {
+        let clicks = drift.clicks_in_loop(tempo_bpm, loop_index, Self::beats_per_loop(pattern));
+        let mut drifting = self.with_click_lane(events, pattern, tempo_bpm, &clicks);
+        if loop_index > 0 {
+            drifting.retain(|event| event.voice != Voice::Kick);
+        }
+        drifting
+    }
+
+    /// Loop events for the hemiola trainer
+    ///
+    /// Through the bars `hemiola` gives the implied pulse, the click is
+    /// replaced by that pulse on [`HEMIOLA_NOTE`], running on from the first
+    /// implied bar across the bar lines, with that first click accented. The
+    /// other bars keep the click as it is.
+    pub fn with_hemiola(
+        &self,
+        events: &[MidiEvent],
+        pattern: &Pattern,
+        tempo_bpm: u16,
+        hemiola: Hemiola,
+        loop_index: u64,
+    ) -> Vec<MidiEvent> {
+        let voices = |voice| voice == Voice::Click;
+        let mut implied =
+            self.without_bars(events, pattern, tempo_bpm, loop_index, voices, |bar| {
+                hemiola.implied_bar(bar).is_none()
+            });
+
+        let grid = BeatGrid::new(
+            pattern.time_signature,
+            pattern.subdivision,
+            pattern.num_measures,
+        );
+        let seconds_per_position = grid.seconds_per_position(tempo_bpm);
+        let measures = pattern.measures();
+        let lens: Vec<usize> = measures
+            .iter()
+            .map(|&(_, meter)| BeatGrid::new(meter, pattern.subdivision, 1).total_positions())
+            .collect();
+        let bars_per_loop = measures.len() as u64;
+        let pulse = (usize::from(hemiola.pulse) * usize::from(pattern.subdivision) / 16).max(1);
+
+        for (i, &(start, _)) in measures.iter().enumerate() {
+            let bar = loop_index * bars_per_loop + i as u64;
+            let Some(into_run) = hemiola.implied_bar(bar) else {
+                continue;
+            };
+            // Positions the pulse has already covered in earlier implied bars
+            let before: usize = (1..=into_run)
+                .map(|back| lens[((bar - back) % bars_per_loop) as usize])
+                .sum();
+            let first = (pulse - before % pulse) % pulse;
+            for position in (first..lens[i]).step_by(pulse) {
+                let time_offset = (start + position) as f64 * seconds_per_position;
+                let velocity = if before + position == 0 {
+                    CLICK_ACCENT_VELOCITY
+                } else {
+                    CLICK_VELOCITY
+                };
+                implied.push(MidiEvent {
+                    time_offset,
+                    voice: Voice::Click,
+                    note: HEMIOLA_NOTE,
+                    velocity,
+                    event_type: MidiEventType::NoteOn,
+                });
+                implied.push(MidiEvent {
+                    time_offset: time_offset + 0.05,
+                    voice: Voice::Click,
+                    note: HEMIOLA_NOTE,
+                    velocity: 0,
+                    event_type: MidiEventType::NoteOff,
+                });
+            }
+        }
+        implied.sort_by(|a, b| a.time_offset.total_cmp(&b.time_offset));
+        implied
+    }
+
+    /// Loop events for the silent-bar trainer
+    ///
+    /// Clicks are left out of the bars `gap` keeps silent, and so are the
+    /// kicks if `gap` mutes them.
+    pub fn with_click_gap(
+        &self,
+        events: &[MidiEvent],
+        pattern: &Pattern,
+        tempo_bpm: u16,
+        gap: ClickGap,
+        loop_index: u64,
+    ) -> Vec<MidiEvent> {
+        let voices = |voice| voice == Voice::Click || (gap.mute_kicks && voice == Voice::Kick);
+        self.without_bars(events, pattern, tempo_bpm, loop_index, voices, |bar| {
+            gap.is_sounding(bar)
+        })
+    }
+
+    /// Loop events for the dropout trainer: the kicks are left out of the
+    /// bars `dropout` keeps silent, while the click plays on
+    pub fn with_kick_dropout(
+        &self,
+        events: &[MidiEvent],
+        pattern: &Pattern,
+        tempo_bpm: u16,
+        dropout: KickDropout,
+        loop_index: u64,
+    ) -> Vec<MidiEvent> {
+        let voices = |voice| voice == Voice::Kick;
+        self.without_bars(events, pattern, tempo_bpm, loop_index, voices, |bar| {
+            dropout.is_playing(bar)
+        })
+    }
+
+    /// Loop events with `voices` left out of every bar `sounding` rejects,
+    /// counting the bars of every loop so far
+    ///
+    /// Only note-ons are dropped, so a note started just before a silent bar
+    /// still ends.
+    fn without_bars(
+        &self,
+        events: &[MidiEvent],
+        pattern: &Pattern,
+        tempo_bpm: u16,
+        loop_index: u64,
+        voices: impl Fn(Voice) -> bool,
+        sounding: impl Fn(u64) -> bool,
+    ) -> Vec<MidiEvent> {
+        let grid = BeatGrid::new(
+            pattern.time_signature,
+            pattern.subdivision,
+            pattern.num_measures,
+        );
+        let seconds_per_position = grid.seconds_per_position(tempo_bpm);
+        let bar_starts: Vec<f64> = pattern
+            .measures()
+            .iter()
+            .map(|&(start, _)| start as f64 * seconds_per_position)
+            .collect();
+        let bars_per_loop = bar_starts.len() as u64;
 
         events
+            .iter()
+            .filter(|event| {
+                if !voices(event.voice) || event.event_type != MidiEventType::NoteOn {
+                    return true;
+                }
+                // Nudge past rounding error so a click on a bar line counts in its bar
+                let bar = bar_starts
+                    .iter()
+                    .rposition(|&start| start <= event.time_offset + 1e-9)
+                    .unwrap_or(0) as u64;
+                sounding(loop_index * bars_per_loop + bar)
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Click beats in one pass through `pattern`
+    fn beats_per_loop(pattern: &Pattern) -> usize {
+        let grid = BeatGrid::new(
+            pattern.time_signature,
+            pattern.subdivision,
+            pattern.num_measures,
+        );
+        Self::click_positions(pattern, &grid).len()
+    }
+
+    /// Loop events with the click replaced by `clicks`, given as offsets in
+    /// pattern beats and whether each is accented
+    fn with_click_lane(
+        &self,
+        events: &[MidiEvent],
+        pattern: &Pattern,
+        tempo_bpm: u16,
+        clicks: &[(f64, bool)],
+    ) -> Vec<MidiEvent> {
+        let beats = Self::beats_per_loop(pattern);
+        let seconds_per_beat = self.pattern_duration(pattern, tempo_bpm) / beats.max(1) as f64;
+
+        let mut canon: Vec<MidiEvent> = events
+            .iter()
+            .filter(|event| event.voice != Voice::Click)
+            .copied()
+            .collect();
+        for &(beat, accented) in clicks {
+            let time_offset = beat * seconds_per_beat;
+            let velocity = if accented {
+                CLICK_ACCENT_VELOCITY
+            } else {
+                CLICK_VELOCITY
+            };
+            canon.push(MidiEvent {
+                time_offset,
+                voice: Voice::Click,
+                note: self.mapping.click_note,
+                velocity,
+                event_type: MidiEventType::NoteOn,
+            });
+            canon.push(MidiEvent {
+                time_offset: time_offset + 0.05,
+                voice: Voice::Click,
+                note: self.mapping.click_note,
+                velocity: 0,
+                event_type: MidiEventType::NoteOff,
+            });
+        }
+        canon.sort_by(|a, b| a.time_offset.partial_cmp(&b.time_offset).unwrap());
+        canon
+    }
+
+    /// One soft, higher-pitched hit on the downbeat marking the start of a phrase
+    pub fn phrase_cue_events(&self) -> Vec<MidiEvent> {
+        vec![
+            MidiEvent {
+                time_offset: 0.0,
+                voice: Voice::Cue,
+                note: PHRASE_CUE_NOTE,
+                velocity: PHRASE_CUE_VELOCITY,
+                event_type: MidiEventType::NoteOn,
+            },
+            MidiEvent {
+                time_offset: 0.05,
+                voice: Voice::Cue,
+                note: PHRASE_CUE_NOTE,
+                velocity: 0,
+                event_type: MidiEventType::NoteOff,
+            },
+        ]
     }
 
     /// Get the duration of the count-in in seconds
-    pub fn count_in_duration(&self, tempo_bpm: u16) -> f64 {
-        4.0 * (60.0 / tempo_bpm as f64)
+    pub fn count_in_duration(&self, time_signature: TimeSignature, tempo_bpm: u16) -> f64 {
+        let grid = BeatGrid::new(time_signature, 16, 1);
+        self.count_in.sixteenths(time_signature) as f64 * grid.seconds_per_position(tempo_bpm)
     }
 
     /// Get the duration of one pattern loop in seconds (without count-in)
@@ -325,6 +1097,15 @@ impl MidiEngine {
             pattern.num_measures,
         );
 
+        if pattern.is_multi_measure() {
+            let positions: usize = pattern
+                .meters
+                .iter()
+                .map(|&meter| BeatGrid::new(meter, pattern.subdivision, 1).total_positions())
+                .sum();
+            return positions as f64 * grid.seconds_per_position(tempo_bpm);
+        }
+
         grid.total_positions() as f64 * grid.seconds_per_position(tempo_bpm)
     }
 }
@@ -338,6 +1119,7 @@ impl Default for MidiEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::engine::sink::MemorySink;
     use crate::models::{ComplexityLevel, TimeSignature};
 
     #[test]
@@ -359,6 +1141,417 @@ mod tests {
         assert!(events.len() >= 4); // At least kicks
         assert!(events.iter().any(|e| e.note == KICK_NOTE));
         assert!(events.iter().any(|e| e.note == CLICK_NOTE));
+
+        // Voices are tagged so playback can mute the click independently
+        assert!(events
+            .iter()
+            .all(|e| (e.voice == Voice::Kick) == (e.note == KICK_NOTE)));
+    }
+
+    #[test]
+    fn test_humanize_bounds() {
+        let mut engine = MidiEngine::new();
+        let pattern = Pattern::new(
+            vec![true; 16],
+            TimeSignature::four_four(),
+            ComplexityLevel::Simple,
+        );
+        let seconds_per_position = 0.125; // sixteenths at 120 BPM
+
+        let kick_ons = |engine: &MidiEngine| -> Vec<MidiEvent> {
+            let mut ons: Vec<MidiEvent> = engine
+                .pattern_to_midi_events(&pattern, 120, false)
+                .into_iter()
+                .filter(|e| e.event_type == MidiEventType::NoteOn)
+                .collect();
+            // Jitter (max 20ms) never reorders sixteenths 125ms apart
+            ons.sort_by(|a, b| a.time_offset.total_cmp(&b.time_offset));
+            ons
+        };
+
+        for (i, event) in kick_ons(&engine).iter().enumerate() {
+            assert_eq!(event.time_offset, i as f64 * seconds_per_position);
+            assert_eq!(event.velocity, KICK_VELOCITY);
+        }
+
+        engine.set_humanize(100);
+        for (i, event) in kick_ons(&engine).iter().enumerate() {
+            let exact = i as f64 * seconds_per_position;
+            assert!((event.time_offset - exact).abs() <= HUMANIZE_MAX_OFFSET + 1e-9);
+            assert!(event.time_offset >= 0.0);
+            assert!(i16::from(event.velocity).abs_diff(i16::from(KICK_VELOCITY)) <= 20);
+        }
+    }
+
+    #[test]
+    fn test_canon_click_replaces_the_click_lane() {
+        let engine = MidiEngine::new();
+        let mut steps = vec![false; 16];
+        steps[0] = true;
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Simple);
+        let events = engine.loop_events(&pattern, 120, None);
+        let ratio = ClickRatio::new(3, 4).unwrap();
+
+        let canon = engine.with_canon_click(&events, &pattern, 120, ratio, 0);
+        let clicks: Vec<(f64, u8)> = canon
+            .iter()
+            .filter(|e| e.voice == Voice::Click && e.event_type == MidiEventType::NoteOn)
+            .map(|e| (e.time_offset, e.velocity))
+            .collect();
+
+        // Three clicks across the 2-second bar, the first on the shared downbeat
+        assert_eq!(clicks.len(), 3);
+        assert_eq!(clicks[0], (0.0, CLICK_ACCENT_VELOCITY));
+        assert!((clicks[1].0 - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(clicks[2].1, CLICK_VELOCITY);
+        assert_eq!(canon.iter().filter(|e| e.voice == Voice::Kick).count(), 2);
+    }
+
+    #[test]
+    fn test_drifting_click_drops_the_kicks_after_the_first_loop() {
+        let engine = MidiEngine::new();
+        let mut steps = vec![false; 16];
+        steps[0] = true;
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Simple);
+        let events = engine.loop_events(&pattern, 100, None);
+        let drift = ClickDrift::new(25, 4);
+        let click_ons = |events: &[MidiEvent]| {
+            events
+                .iter()
+                .filter(|e| e.voice == Voice::Click && e.event_type == MidiEventType::NoteOn)
+                .count()
+        };
+
+        let first = engine.with_drifting_click(&events, &pattern, 100, drift, 0);
+        assert_eq!(click_ons(&first), 4);
+        assert_eq!(first.iter().filter(|e| e.voice == Voice::Kick).count(), 2);
+
+        // Loop 1 rushes to 125 BPM: five clicks in the pattern's four beats, no kicks
+        let second = engine.with_drifting_click(&events, &pattern, 100, drift, 1);
+        assert_eq!(click_ons(&second), 5);
+        assert!(second.iter().all(|e| e.voice != Voice::Kick));
+    }
+
+    #[test]
+    fn test_hemiola_alternates_the_click_with_an_implied_pulse() {
+        let engine = MidiEngine::new();
+        let mut steps = vec![false; 16];
+        steps[0] = true;
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Simple);
+        let events = engine.loop_events(&pattern, 120, None);
+        let hemiola = Hemiola::new(6, 2).unwrap();
+        let clicks = |loop_index, note| -> Vec<f64> {
+            engine
+                .with_hemiola(&events, &pattern, 120, hemiola, loop_index)
+                .iter()
+                .filter(|e| e.note == note && e.event_type == MidiEventType::NoteOn)
+                .map(|e| e.time_offset)
+                .collect()
+        };
+
+        // Two bars of the beat, then the dotted quarters run on across two more
+        assert_eq!(clicks(1, CLICK_NOTE), vec![0.0, 0.5, 1.0, 1.5]);
+        assert!(clicks(1, HEMIOLA_NOTE).is_empty());
+        assert!(clicks(2, CLICK_NOTE).is_empty());
+        assert_eq!(clicks(2, HEMIOLA_NOTE), vec![0.0, 0.75, 1.5]);
+        assert_eq!(clicks(3, HEMIOLA_NOTE), vec![0.25, 1.0, 1.75]);
+        assert_eq!(clicks(4, CLICK_NOTE), vec![0.0, 0.5, 1.0, 1.5]);
+    }
+
+    #[test]
+    fn test_click_gap_silences_whole_bars() {
+        let engine = MidiEngine::new();
+        let bar = |len: usize, time_signature| {
+            let mut steps = vec![false; len];
+            steps[0] = true;
+            Pattern::new(steps, time_signature, ComplexityLevel::Simple)
+        };
+        let pattern = Pattern::concat(&[
+            bar(16, TimeSignature::four_four()),
+            bar(12, TimeSignature::three_four()),
+        ])
+        .unwrap();
+        let events = engine.loop_events(&pattern, 120, None);
+        let ons = |events: &[MidiEvent], voice| -> Vec<f64> {
+            events
+                .iter()
+                .filter(|e| e.voice == voice && e.event_type == MidiEventType::NoteOn)
+                .map(|e| e.time_offset)
+                .collect()
+        };
+
+        // The 4/4 bar sounds; the 3/4 bar (from 2.0s) is silent, kicks and all
+        let gap = ClickGap::new(1, 1).with_muted_kicks();
+        let gapped = engine.with_click_gap(&events, &pattern, 120, gap, 0);
+        assert_eq!(ons(&gapped, Voice::Click), vec![0.0, 0.5, 1.0, 1.5]);
+        assert_eq!(ons(&gapped, Voice::Kick), vec![0.0]);
+        assert_eq!(
+            gapped.iter().filter(|e| e.voice == Voice::Kick).count(),
+            3,
+            "both kicks still end"
+        );
+
+        // Loop 1 holds bars 2 and 3: with 3 on, 1 off, only its 4/4 bar
+        // clicks, and the kicks play on
+        let gap = ClickGap::new(3, 1);
+        let gapped = engine.with_click_gap(&events, &pattern, 120, gap, 1);
+        assert_eq!(ons(&gapped, Voice::Click).len(), 4);
+        assert_eq!(ons(&gapped, Voice::Kick).len(), 2);
+    }
+
+    #[test]
+    fn test_clock_events_run_at_24_ppqn() {
+        let engine = MidiEngine::new();
+        let pattern = Pattern::new(
+            vec![false; 16],
+            TimeSignature::four_four(),
+            ComplexityLevel::Simple,
+        );
+        let duration = engine.pattern_duration(&pattern, 120);
+
+        let ticks = engine.clock_events(duration, pattern.steps.len());
+
+        // 4 quarters at 120 BPM: 96 ticks, one every 1/48 s
+        assert_eq!(ticks.len(), 96);
+        assert!(ticks.iter().all(|e| e.event_type == MidiEventType::Clock));
+        assert_eq!(ticks[0].time_offset, 0.0);
+        assert!((ticks[1].time_offset - 1.0 / 48.0).abs() < 1e-9);
+        assert!(ticks.last().unwrap().time_offset < duration);
+    }
+
+    #[test]
+    fn test_phrase_cue_events() {
+        let events = MidiEngine::new().phrase_cue_events();
+
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .all(|e| e.voice == Voice::Cue && e.note == PHRASE_CUE_NOTE));
+        assert_eq!(events[0].time_offset, 0.0);
+        assert_eq!(events[0].event_type, MidiEventType::NoteOn);
+        assert_eq!(events[1].event_type, MidiEventType::NoteOff);
+    }
+
+    #[test]
+    fn test_loop_events_with_overlay() {
+        let engine = MidiEngine::new();
+        let mut steps = vec![false; 16];
+        steps[0] = true;
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Simple);
+        let clave = crate::models::Overlay::SonClave
+            .steps(TimeSignature::four_four())
+            .unwrap();
+
+        let events = engine.loop_events(&pattern, 120, Some(&clave));
+
+        let overlay_hits: Vec<f64> = events
+            .iter()
+            .filter(|e| e.voice == Voice::Overlay && e.event_type == MidiEventType::NoteOn)
+            .map(|e| e.time_offset)
+            .collect();
+        assert_eq!(overlay_hits.len(), 5);
+        assert!(events
+            .iter()
+            .all(|e| (e.voice == Voice::Overlay) == (e.note == OVERLAY_NOTE)));
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].time_offset <= pair[1].time_offset));
+    }
+
+    #[test]
+    fn test_count_in_follows_meter() {
+        let engine = MidiEngine::new();
+        let clicks = |time_signature| -> Vec<(f64, u8)> {
+            engine
+                .generate_count_in_events(time_signature, 120)
+                .iter()
+                .filter(|e| e.event_type == MidiEventType::NoteOn)
+                .map(|e| (e.time_offset, e.velocity))
+                .collect()
+        };
+
+        assert_eq!(
+            clicks(TimeSignature::four_four()),
+            vec![
+                (0.0, CLICK_ACCENT_VELOCITY),
+                (0.5, CLICK_VELOCITY),
+                (1.0, CLICK_VELOCITY),
+                (1.5, CLICK_VELOCITY)
+            ]
+        );
+        assert_eq!(clicks(TimeSignature::three_four()).len(), 3);
+        let cue = engine.count_in_cue_events(TimeSignature::four_four(), 120);
+        assert_eq!(cue[0].time_offset, 1.75);
+
+        // Dotted quarters: three eighths apart at 120 BPM
+        assert_eq!(
+            clicks(TimeSignature::six_eight()),
+            vec![(0.0, CLICK_ACCENT_VELOCITY), (0.75, CLICK_VELOCITY)]
+        );
+        assert!((engine.count_in_duration(TimeSignature::six_eight(), 120) - 1.5).abs() < 1e-9);
+        assert!((engine.count_in_duration(TimeSignature::four_four(), 120) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_custom_count_in() {
+        let mut engine = MidiEngine::new();
+        let four_four = TimeSignature::four_four();
+        let count_in = CountIn::new(2)
+            .unwrap()
+            .with_subdivision()
+            .with_count_off(60);
+        engine.set_count_in(count_in);
+        let notes: Vec<(f64, u8, u8)> = engine
+            .generate_count_in_events(four_four, 120)
+            .iter()
+            .filter(|e| e.event_type == MidiEventType::NoteOn)
+            .map(|e| (e.time_offset, e.note, e.velocity))
+            .collect();
+        assert_eq!(notes.len(), 12);
+        assert_eq!(notes[3], (1.5, CLICK_NOTE, CLICK_VELOCITY));
+        assert_eq!(notes[4], (2.0, 60, CLICK_ACCENT_VELOCITY));
+        assert_eq!(notes[5], (2.25, CLICK_NOTE, COUNT_IN_SUBDIVISION_VELOCITY));
+        assert_eq!(notes[11], (3.75, CLICK_NOTE, COUNT_IN_SUBDIVISION_VELOCITY));
+        assert_eq!(notes[10], (3.5, 63, CLICK_VELOCITY));
+        assert!((engine.count_in_duration(four_four, 120) - 4.0).abs() < 1e-9);
+        assert_eq!(
+            engine.count_in_cue_events(four_four, 120)[0].time_offset,
+            3.75
+        );
+
+        engine.set_count_in(CountIn::new(0).unwrap());
+        assert!(engine.generate_count_in_events(four_four, 120).is_empty());
+        assert!(engine.count_in_cue_events(four_four, 120).is_empty());
+        assert_eq!(engine.count_in_duration(four_four, 120), 0.0);
+    }
+
+    #[test]
+    fn test_meter_changes_accent_each_bar() {
+        let engine = MidiEngine::new();
+        let pattern = Pattern::concat(&[
+            Pattern::new(
+                vec![false; 16],
+                TimeSignature::four_four(),
+                ComplexityLevel::Simple,
+            ),
+            Pattern::new(
+                vec![false; 12],
+                TimeSignature::three_four(),
+                ComplexityLevel::Simple,
+            ),
+        ])
+        .unwrap();
+
+        let clicks: Vec<(f64, u8)> = engine
+            .pattern_to_midi_events(&pattern, 120, true)
+            .iter()
+            .filter(|e| e.event_type == MidiEventType::NoteOn)
+            .map(|e| (e.time_offset, e.velocity))
+            .collect();
+        assert_eq!(clicks.len(), 7);
+        assert_eq!(clicks[0], (0.0, CLICK_ACCENT_VELOCITY));
+        assert_eq!(clicks[3], (1.5, CLICK_VELOCITY));
+        assert_eq!(clicks[4], (2.0, CLICK_ACCENT_VELOCITY));
+        assert!((engine.pattern_duration(&pattern, 120) - 3.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_grouped_meters_click_each_group() {
+        let engine = MidiEngine::new();
+        let seven_eight = TimeSignature::new(7, 8)
+            .with_grouping("2+2+3".parse().unwrap())
+            .unwrap();
+        let pattern = Pattern::new(vec![false; 14], seven_eight, ComplexityLevel::Simple);
+        let clicks = |events: Vec<MidiEvent>| -> Vec<(f64, u8)> {
+            events
+                .iter()
+                .filter(|e| e.event_type == MidiEventType::NoteOn)
+                .map(|e| (e.time_offset, e.velocity))
+                .collect()
+        };
+
+        // Eighths are 0.25s at 120 BPM: groups start on eighths 0, 2, and 4
+        let expected = vec![
+            (0.0, CLICK_ACCENT_VELOCITY),
+            (0.5, CLICK_VELOCITY),
+            (1.0, CLICK_VELOCITY),
+        ];
+        assert_eq!(
+            clicks(engine.pattern_to_midi_events(&pattern, 120, true)),
+            expected
+        );
+        assert_eq!(
+            clicks(engine.generate_count_in_events(seven_eight, 120)),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_sends_through_sink_and_recorder() {
+        let sink = MemorySink::new();
+        let recorder = SessionRecorder::new();
+        let mut engine = MidiEngine::new();
+        assert!(engine.send_note_on(36, 100).is_err());
+
+        engine.set_mapping(MidiMapping::new(36, 37, 0).unwrap());
+        engine.set_recorder(Some(recorder.clone()));
+        engine.connect_sink(Box::new(sink.clone()));
+        engine.send_note_on(36, 100).unwrap();
+        engine.send_note_off(36).unwrap();
+        engine.send_realtime(CLOCK_TICK).unwrap();
+
+        assert_eq!(
+            sink.messages(),
+            vec![vec![0x90, 36, 100], vec![0x80, 36, 0], vec![CLOCK_TICK]]
+        );
+        // Real-time messages aren't logged
+        assert_eq!(recorder.len(), 2);
+    }
+
+    #[test]
+    fn test_click_gets_its_own_channel_and_port() {
+        let (kicks, clicks) = (MemorySink::new(), MemorySink::new());
+        let mut engine = MidiEngine::new();
+        let mapping = MidiMapping::new(36, 37, 9).unwrap();
+        engine.set_mapping(mapping.with_click_channel(10).unwrap());
+        engine.connect_sink(Box::new(kicks.clone()));
+        engine.send_voice_note_on(Voice::Click, 37, 80).unwrap();
+        engine.send_voice_note_on(Voice::Kick, 36, 100).unwrap();
+        assert_eq!(
+            kicks.messages(),
+            vec![vec![0x9A, 37, 80], vec![0x99, 36, 100]]
+        );
+
+        engine.connect_click_sink(Box::new(clicks.clone()));
+        engine.send_voice_note_off(Voice::Click, 37).unwrap();
+        engine.send_voice_note_on(Voice::Overlay, 75, 70).unwrap();
+        assert_eq!(clicks.messages(), vec![vec![0x8A, 37, 0]]);
+        assert_eq!(kicks.messages().last(), Some(&vec![0x99, 75, 70]));
+
+        assert!(mapping.with_click_channel(16).is_err());
+    }
+
+    #[test]
+    fn test_custom_mapping() {
+        let mut engine = MidiEngine::new();
+        engine.set_mapping(MidiMapping::new(60, 76, 0).unwrap());
+
+        let pattern = Pattern::new(
+            [true, false, false, false].repeat(4),
+            TimeSignature::four_four(),
+            ComplexityLevel::Simple,
+        );
+        let events = engine.pattern_to_midi_events(&pattern, 120, true);
+        assert!(events
+            .iter()
+            .all(|e| e.note == if e.voice == Voice::Kick { 60 } else { 76 }));
+        assert!(engine
+            .generate_count_in_events(TimeSignature::four_four(), 120)
+            .iter()
+            .all(|e| e.note == 76));
+
+        assert!(MidiMapping::new(128, 37, 9).is_err());
+        assert!(MidiMapping::new(36, 37, 16).is_err());
     }
 
     #[test]
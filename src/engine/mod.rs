@@ -1,7 +1,42 @@
 // Engine module
 // MIDI playback engine and timing/synchronization
 
+pub mod classroom;
+pub mod events;
+pub mod kit;
+#[cfg(feature = "link")]
+pub mod link;
 pub mod midi;
+pub mod pad;
 pub mod playback;
+pub mod practice;
+pub mod prefetch;
+pub mod recorder;
+pub mod scheduler;
+pub mod sink;
+#[cfg(feature = "websocket")]
+pub mod socket;
+pub mod stream;
+pub mod sync;
+#[cfg(feature = "wav")]
+pub mod wav;
 
-pub use playback::MidiPlaybackLoop;
+pub use classroom::{class_addr, ClassEvent, ClassMessage, ClassroomHost, DEFAULT_CLASS_PORT};
+pub use events::{EventLog, LogEvent, LogFormat, DRIFT_WARNING_MS};
+pub use kit::{KitInput, KitMap, KitNote};
+#[cfg(feature = "link")]
+pub use link::LinkSession;
+pub use midi::MidiMapping;
+pub use pad::{DrumPad, Pad};
+pub use playback::{DriftSample, MidiPlaybackLoop, PlaybackProbe};
+pub use practice::{AnswerOutcome, NextPattern, Notice, PracticeEngine};
+pub use prefetch::Prefetcher;
+pub use recorder::SessionRecorder;
+pub use scheduler::{JitterStats, Scheduler};
+pub use sink::{MemorySink, MidiSink, SharedSink};
+#[cfg(feature = "websocket")]
+pub use socket::{SocketEvent, SocketServer};
+pub use stream::{OverlayFormat, OverlayFrame, StreamOverlay};
+pub use sync::{ClockState, ClockWait, ExternalClock};
+#[cfg(feature = "wav")]
+pub use wav::{DrumVoice, Sample, WavRenderer};
@@ -0,0 +1,123 @@
+use crate::engine::midi::{MidiEngine, MidiMapping, KICK_VELOCITY, SNARE_NOTE};
+use crate::engine::recorder::SessionRecorder;
+use crate::engine::sink::MidiSink;
+use crate::error::KickbeatsError;
+
+/// A sound the drum pad can trigger
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pad {
+    /// The kick note from the mapping
+    Kick,
+    /// General MIDI acoustic snare
+    Snare,
+}
+
+impl Pad {
+    /// Note this pad plays under a mapping
+    pub fn note(&self, mapping: MidiMapping) -> u8 {
+        match self {
+            Pad::Kick => mapping.kick_note,
+            Pad::Snare => SNARE_NOTE,
+        }
+    }
+}
+
+/// Immediate-send MIDI output for tapping along with the loop
+///
+/// Holds its own connection to the playback port, so hits go out the moment
+/// they're triggered instead of waiting for the playback thread's next
+/// scheduled event.
+pub struct DrumPad {
+    /// Connected engine sending on the playback mapping
+    engine: MidiEngine,
+    /// Notes hit and not yet released
+    sounding: Vec<u8>,
+}
+
+impl DrumPad {
+    /// Connect to the port whose name contains `port`, or the first available port
+    pub fn connect(
+        port: Option<&str>,
+        mapping: MidiMapping,
+        recorder: Option<SessionRecorder>,
+    ) -> Result<Self, KickbeatsError> {
+        let mut engine = MidiEngine::new();
+        engine.set_mapping(mapping);
+        engine.set_recorder(recorder);
+
+        match port {
+            Some(port) => engine.connect(port)?,
+            None => engine.connect(&MidiEngine::first_port()?)?,
+        }
+
+        Ok(Self {
+            engine,
+            sounding: Vec::new(),
+        })
+    }
+
+    /// Send hits to `sink` instead of a MIDI port
+    pub fn with_sink(
+        sink: Box<dyn MidiSink>,
+        mapping: MidiMapping,
+        recorder: Option<SessionRecorder>,
+    ) -> Self {
+        let mut engine = MidiEngine::new();
+        engine.set_mapping(mapping);
+        engine.set_recorder(recorder);
+        engine.connect_sink(sink);
+
+        Self {
+            engine,
+            sounding: Vec::new(),
+        }
+    }
+
+    /// Play a pad now
+    ///
+    /// A note is released just before it is struck again (and when the pad is
+    /// dropped), so samplers that stop on note-off still ring between hits.
+    pub fn hit(&mut self, pad: Pad) -> Result<(), KickbeatsError> {
+        self.strike(pad.note(self.engine.mapping()), KICK_VELOCITY)
+    }
+
+    /// Play back a note just heard from a kit, as it was played
+    ///
+    /// Goes out on the playback channel, released before it's struck again
+    /// like [`hit`](Self::hit).
+    pub fn echo(&mut self, note: u8, velocity: u8) -> Result<(), KickbeatsError> {
+        self.strike(note, velocity)
+    }
+
+    /// Release `note` if it's sounding, then strike it at `velocity`
+    fn strike(&mut self, note: u8, velocity: u8) -> Result<(), KickbeatsError> {
+        if let Some(i) = self.sounding.iter().position(|&n| n == note) {
+            self.sounding.swap_remove(i);
+            let _ = self.engine.send_note_off(note);
+        }
+        self.engine.send_note_on(note, velocity)?;
+        self.sounding.push(note);
+        Ok(())
+    }
+}
+
+impl Drop for DrumPad {
+    fn drop(&mut self) {
+        for note in self.sounding.drain(..) {
+            let _ = self.engine.send_note_off(note);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pad_notes_follow_mapping() {
+        let mapping = MidiMapping::new(48, 76, 0).unwrap();
+        assert_eq!(Pad::Kick.note(mapping), 48);
+        assert_eq!(Pad::Snare.note(mapping), SNARE_NOTE);
+        assert_eq!(Pad::Kick.note(MidiMapping::default()), 36);
+    }
+}
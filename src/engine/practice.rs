@@ -0,0 +1,1945 @@
+#[cfg(feature = "config")]
+use crate::config::{ConfigWatcher, SettingsUpdate};
+use crate::engine::midi::{CLICK_VELOCITY, KICK_VELOCITY, VELOCITY_STEP};
+use crate::engine::prefetch::{lock_generator, Prefetched, SharedGenerator};
+#[cfg(feature = "link")]
+use crate::engine::LinkSession;
+#[cfg(feature = "websocket")]
+use crate::engine::SocketServer;
+use crate::engine::{
+    ClassEvent, ClassroomHost, EventLog, KitInput, KitMap, LogEvent, MidiMapping, MidiPlaybackLoop,
+    Prefetcher, SessionRecorder, SharedSink, StreamOverlay,
+};
+use crate::error::KickbeatsError;
+use crate::generator::{PatternGenerator, WeightedGenerator};
+use crate::grading::{
+    grade_answer_at, AnswerGrade, HitTiming, KitTimings, Limb, LimbScores, TapGrade,
+};
+use crate::models::{
+    Arrangement, ArrangementSpec, ComplexityLevel, CurriculumStage, Medley, Morph, PartSource,
+    Pattern, PlanTransition, PracticeSession, TimeSignature, Transform,
+};
+#[cfg(feature = "history")]
+use crate::storage::replay::{complexity_name, meter_name, pattern_event};
+#[cfg(feature = "history")]
+use crate::storage::{ReplayEvent, SessionRecord, SessionReplay, Storage};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "history")]
+use std::time::Instant;
+use std::time::{Duration, SystemTime};
+
+/// A practice session and its MIDI playback, without any user interface
+///
+/// Wraps a [`PracticeSession`], a [`PatternGenerator`], and a
+/// [`MidiPlaybackLoop`] behind the operations every front end needs, so other
+/// programs can embed kickbeats without the command-line interface. The
+/// scrolling, `--tui`, and `--headless` front ends are built on it too.
+/// Failures come back as a [`KickbeatsError`], whose kind tells a missing
+/// MIDI port apart from, say, an answer that doesn't parse.
+///
+/// ```no_run
+/// use kickbeats_cli::engine::PracticeEngine;
+/// use kickbeats_cli::models::PracticeSession;
+/// use kickbeats_cli::KickbeatsError;
+///
+/// let mut engine = PracticeEngine::new(PracticeSession::default());
+/// engine.generate()?;
+/// match engine.start() {
+///     Err(KickbeatsError::Midi(e)) => eprintln!("Playing without sound: {}", e),
+///     started => started?,
+/// }
+/// // ... let the player listen ...
+/// let grade = engine.grade("x...x...x...x...")?;
+/// println!("{:.0}%", grade.accuracy * 100.0);
+/// engine.stop();
+/// # Ok::<(), KickbeatsError>(())
+/// ```
+pub struct PracticeEngine {
+    /// Current practice session
+    pub(crate) session: PracticeSession,
+    /// MIDI playback engine
+    pub(crate) playback: MidiPlaybackLoop,
+    /// Pattern generator, shared with the prefetcher
+    pub(crate) generator: SharedGenerator,
+    /// Patterns generated ahead of time, once started with `with_prefetch`
+    prefetch: Option<Prefetcher>,
+    /// Where the current pattern came from, for generators that mix sources
+    source: Option<String>,
+    /// Notes and channel playback is sent on
+    pub(crate) midi_mapping: MidiMapping,
+    /// Electronic kit listened to, once connected with `connect_kit`
+    kit: Option<KitInput>,
+    /// Which of the kit's notes each limb plays
+    kit_map: KitMap,
+    /// Grades of the kit's hits on the current pattern
+    kit_scores: LimbScores,
+    /// Timing of the kit's hits on the current pattern, loop by loop
+    kit_timings: KitTimings,
+    /// Whether auto-advance may move on once the current run stops by itself
+    advance_pending: bool,
+    /// Whether playback's loss of its output has been reported
+    output_lost: bool,
+    /// Medley playing instead of the current pattern, and the number of the
+    /// last part announced
+    medley: Option<(Medley, Option<u32>)>,
+    /// Arrangement playing instead of the current pattern, and the index of
+    /// the last section announced
+    arrangement: Option<(Arrangement, Option<usize>)>,
+    /// Log of every MIDI message sent, and the directory it's saved to
+    midi_log: Option<(SessionRecorder, PathBuf)>,
+    /// File showing the exercise on stream
+    stream: Option<StreamOverlay>,
+    /// JSON lines log of patterns, reveals, tempo changes, and loops
+    event_log: Option<EventLog>,
+    /// WebSocket server mirroring the session to companion UIs
+    #[cfg(feature = "websocket")]
+    socket: Option<SocketServer>,
+    /// Class the current pattern is broadcast to, with students' answers coming back
+    classroom: Option<ClassroomHost>,
+    /// Config file whose saved tempo, complexity, and MIDI mapping are taken on live
+    #[cfg(feature = "config")]
+    config_watch: Option<ConfigWatcher>,
+    /// Practice history the session is saved to when it ends
+    #[cfg(feature = "history")]
+    storage: Option<Box<dyn Storage>>,
+    /// Replay being recorded, when recording started, and the directory it's saved to
+    #[cfg(feature = "history")]
+    replay: Option<(SessionReplay, Instant, PathBuf)>,
+}
+
+/// A pattern [`PracticeEngine::next_pattern`] made current
+#[derive(Debug, Clone)]
+pub struct NextPattern {
+    /// The pattern, hidden until revealed
+    pub pattern: Pattern,
+    /// Distance from recent patterns it settled for, when it couldn't be kept
+    /// 3 steps from all of them
+    pub relaxed: Option<u32>,
+    /// Whether it's a pattern missed before, back for review
+    pub review: bool,
+}
+
+/// Something [`PracticeEngine::poll`] picked up, for front ends to report
+#[derive(Debug, Clone)]
+pub enum Notice {
+    /// A hit from the kit was timed against its limb's lane
+    KitHit {
+        /// Limb that played it
+        limb: Limb,
+        /// How close it landed, or `None` if the limb had nothing to play
+        grade: Option<TapGrade>,
+    },
+    /// Auto-advance moved on to a new pattern, now playing
+    Advanced {
+        /// The pattern it replaced, if `reveal_on_advance` revealed it
+        revealed: Option<Pattern>,
+        /// The new pattern
+        next: NextPattern,
+    },
+    /// Auto-advance couldn't move on; the reason why
+    AdvanceFailed(String),
+    /// The session plan moved on to the segment at `index`, whose settings
+    /// are now in effect with a fresh pattern playing
+    PlanSegment {
+        /// Segment now running
+        index: usize,
+        /// Pattern started for it
+        next: NextPattern,
+    },
+    /// The session plan's next segment couldn't start its pattern
+    PlanSegmentFailed(String),
+    /// The session plan's last segment has ended
+    PlanFinished,
+    /// The medley reached the next of the session's patterns
+    MedleyPart {
+        /// The pattern's number in the session
+        number: u32,
+        /// Its place in the medley, counting from 1
+        index: usize,
+        /// Patterns in the medley
+        count: usize,
+    },
+    /// The medley has played every pattern and stopped
+    MedleyFinished,
+    /// The arrangement reached its next section
+    Section {
+        /// Part the section plays (e.g., 'B')
+        label: char,
+        /// Loops the section plays
+        loops: u32,
+        /// Its place in the form, counting from 1
+        index: usize,
+        /// Sections in the form
+        count: usize,
+    },
+    /// The arrangement has played through its form and stopped
+    ArrangementFinished,
+    /// A student in the class joined, left, or answered
+    Class(ClassEvent),
+    /// The watched config file was saved and its changed settings are in
+    /// effect, tempo and mapping from the next loop
+    #[cfg(feature = "config")]
+    ConfigReloaded(SettingsUpdate),
+    /// The watched config file was saved but couldn't be taken on; the reason why
+    #[cfg(feature = "config")]
+    ConfigReloadFailed(String),
+    /// The MIDI output failed mid-run (e.g., the device was unplugged);
+    /// playback is paused until it comes back. The reason why
+    OutputLost(String),
+    /// The lost MIDI output is back and playback has picked up again
+    OutputRestored,
+}
+
+/// What recording an answer changed, for front ends to report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnswerOutcome {
+    /// Click velocity before and after the session's click fade, if it has one
+    pub click_velocity: Option<(u8, u8)>,
+    /// Curriculum stage the answer moved on to, now in effect
+    pub next_stage: Option<CurriculumStage>,
+    /// Whether this answer completed the session goal
+    pub goal_complete: bool,
+}
+
+impl PracticeEngine {
+    /// Create an engine for a session, set up with its ramp, overlay, humanize, phrase, clock, drill, and auto-advance settings
+    pub fn new(session: PracticeSession) -> Self {
+        let mut playback = MidiPlaybackLoop::new();
+        playback.set_tempo_ramp(session.tempo_ramp);
+        playback.set_overlay(session.overlay);
+        playback.set_humanize(session.humanize);
+        playback.set_phrase_loops(session.phrase_bars);
+        playback.set_loop_limit(session.loop_limit());
+        playback.set_click_ratio(session.click_ratio);
+        playback.set_hemiola(session.hemiola);
+        playback.set_click_drift(session.click_drift);
+        playback.set_click_gap(session.click_gap);
+        playback.set_kick_dropout(session.kick_dropout);
+        playback.set_fills(session.fills);
+        playback.set_ghost_steps(session.ghost_steps);
+        playback.set_count_in(session.count_in);
+        if let Some(velocity) = session.kick_velocity {
+            playback.set_kick_velocity(velocity);
+        }
+        if let Some(velocity) = session.click_velocity {
+            playback.set_click_velocity(velocity);
+        }
+        playback.set_send_clock(session.send_clock);
+        playback.set_sync_port(session.sync_port.clone());
+
+        Self {
+            session,
+            playback,
+            generator: Arc::new(Mutex::new(Box::new(WeightedGenerator::new()))),
+            prefetch: None,
+            source: None,
+            midi_mapping: MidiMapping::default(),
+            kit: None,
+            kit_map: KitMap::default(),
+            kit_scores: LimbScores::new(),
+            kit_timings: KitTimings::new(),
+            advance_pending: false,
+            output_lost: false,
+            medley: None,
+            arrangement: None,
+            midi_log: None,
+            stream: None,
+            event_log: None,
+            #[cfg(feature = "websocket")]
+            socket: None,
+            classroom: None,
+            #[cfg(feature = "config")]
+            config_watch: None,
+            #[cfg(feature = "history")]
+            storage: None,
+            #[cfg(feature = "history")]
+            replay: None,
+        }
+    }
+
+    /// Use a different or preconfigured generator (e.g., Euclidean, custom weights)
+    pub fn with_generator(mut self, generator: Box<dyn PatternGenerator>) -> Self {
+        self.generator = Arc::new(Mutex::new(generator));
+        if let Some(prefetch) = self.prefetch.take() {
+            self = self.with_prefetch(prefetch.depth());
+        }
+        self
+    }
+
+    /// Keep `depth` patterns generated ahead of time on a background thread,
+    /// so [`next_pattern`](Self::next_pattern) doesn't wait on the generator
+    pub fn with_prefetch(mut self, depth: usize) -> Self {
+        self.prefetch = (depth > 0).then(|| {
+            Prefetcher::start(
+                Arc::clone(&self.generator),
+                depth,
+                self.session.time_signature,
+                self.session.complexity_level,
+                self.session.pattern_history.patterns(),
+            )
+        });
+        self
+    }
+
+    /// Play into a sink that discards every message instead of a MIDI port
+    pub fn with_null_output(mut self) -> Self {
+        self.playback.set_null_output(true);
+        self
+    }
+
+    /// Play on custom notes and channel instead of the General MIDI drum map
+    pub fn with_midi_mapping(mut self, mapping: MidiMapping) -> Self {
+        self.playback.set_mapping(mapping);
+        self.midi_mapping = mapping;
+        self
+    }
+
+    /// Tell the kit's limbs apart by `map` instead of the General MIDI drum map
+    pub fn with_kit_map(mut self, map: KitMap) -> Self {
+        self.kit_map = map;
+        self
+    }
+
+    /// Play on the MIDI output port whose name contains `port` instead of the first one
+    pub fn with_midi_port(mut self, port: String) -> Self {
+        self.playback.set_port(Some(port));
+        self
+    }
+
+    /// Send the click track and count-in to the MIDI output port whose name
+    /// contains `port`, apart from the kicks
+    pub fn with_click_port(mut self, port: String) -> Self {
+        self.playback.set_click_port(Some(port));
+        self
+    }
+
+    /// Play (and drum) on `sink`, e.g. a virtual port, instead of a named port
+    pub fn with_midi_sink(mut self, sink: SharedSink) -> Self {
+        self.playback.set_sink(Some(sink));
+        self
+    }
+
+    /// Stop playback, silencing every note, once `running` is cleared (e.g.,
+    /// by a Ctrl-C handler)
+    pub fn with_interrupt(mut self, running: Arc<AtomicBool>) -> Self {
+        self.playback.set_interrupt(Some(running));
+        self
+    }
+
+    /// Copy every MIDI message played into `recorder`
+    pub fn with_recorder(mut self, recorder: SessionRecorder) -> Self {
+        self.playback.set_recorder(Some(recorder));
+        self
+    }
+
+    /// Join an Ableton Link session, proposing the session's tempo
+    ///
+    /// Peers' tempo changes show up in [`tempo`](Self::tempo) while playing.
+    #[cfg(feature = "link")]
+    pub fn with_link(mut self) -> Self {
+        self.playback
+            .set_link(Some(LinkSession::join(self.session.tempo_bpm)));
+        self
+    }
+
+    /// Keep a practice history, saved to with [`save_session`](Self::save_session)
+    #[cfg(feature = "history")]
+    pub fn with_storage(mut self, storage: Box<dyn Storage>) -> Self {
+        self.storage = Some(storage);
+        self
+    }
+
+    /// Keep every MIDI message played, to save as a Standard MIDI File in
+    /// `dir` with [`save_midi_log`](Self::save_midi_log)
+    pub fn with_midi_log(mut self, dir: PathBuf) -> Self {
+        let recorder = SessionRecorder::new();
+        self.playback.set_recorder(Some(recorder.clone()));
+        self.midi_log = Some((recorder, dir));
+        self
+    }
+
+    /// Write patterns, reveals, tempo changes, loop starts, and drift
+    /// warnings to `log` as they happen
+    pub fn with_event_log(mut self, log: EventLog) -> Self {
+        self.playback.set_event_log(Some(log.clone()));
+        self.event_log = Some(log);
+        self.log_pattern(false);
+        self
+    }
+
+    /// Keep `stream` showing the current pattern, tempo, and beat, e.g. for OBS
+    ///
+    /// Start it on this engine's [`playback`](Self::playback) probe.
+    pub fn with_stream(mut self, stream: StreamOverlay) -> Self {
+        self.stream = Some(stream);
+        self.update_stream();
+        self
+    }
+
+    /// Mirror the current pattern, tempo, and every step played to the
+    /// clients of `socket`, e.g. a browser-based visual metronome
+    ///
+    /// Start it on this engine's [`playback`](Self::playback) probe.
+    #[cfg(feature = "websocket")]
+    pub fn with_socket(mut self, socket: SocketServer) -> Self {
+        self.socket = Some(socket);
+        self.update_stream();
+        self
+    }
+
+    /// Broadcast the current pattern, tempo, and reveal to the students of
+    /// `classroom`, reporting what they do as [`Notice::Class`]
+    pub fn with_classroom(mut self, classroom: ClassroomHost) -> Self {
+        self.classroom = Some(classroom);
+        self.update_stream();
+        self
+    }
+
+    /// Take on the tempo, complexity, and MIDI mapping saved to the file
+    /// `watcher` watches, reporting each reload as [`Notice::ConfigReloaded`]
+    #[cfg(feature = "config")]
+    pub fn with_config_watch(mut self, watcher: ConfigWatcher) -> Self {
+        self.config_watch = Some(watcher);
+        self
+    }
+
+    /// Record every pattern, command, answer, and tempo change, to save as a
+    /// `.kbreplay` file in `dir` with [`save_replay`](Self::save_replay)
+    #[cfg(feature = "history")]
+    pub fn with_replay(mut self, dir: PathBuf) -> Self {
+        let mut replay = SessionReplay::new(&self.session);
+        if let Some(pattern) = &self.session.current_pattern {
+            replay.record(Duration::ZERO, pattern_event(pattern, false));
+        }
+        self.replay = Some((replay, Instant::now(), dir));
+        self
+    }
+
+    /// The session, with the tempo as of the last engine call
+    pub fn session(&self) -> &PracticeSession {
+        &self.session
+    }
+
+    /// The playback loop, for position, drift, and click details
+    pub fn playback(&self) -> &MidiPlaybackLoop {
+        &self.playback
+    }
+
+    /// Notes and channel playback is sent on
+    pub fn midi_mapping(&self) -> MidiMapping {
+        self.midi_mapping
+    }
+
+    /// Grades of the kit's hits on the current pattern, once it's connected
+    pub fn kit_scores(&self) -> Option<LimbScores> {
+        self.kit.as_ref().map(|_| self.kit_scores)
+    }
+
+    /// How early or late each of the kit's graded hits on the current
+    /// pattern landed, loop by loop, once it's connected
+    pub fn kit_timings(&self) -> Option<&KitTimings> {
+        self.kit.as_ref().map(|_| &self.kit_timings)
+    }
+
+    /// Pattern now selected (playing, if playback is running)
+    pub fn current_pattern(&self) -> Option<&Pattern> {
+        self.session.current_pattern.as_ref()
+    }
+
+    /// Where the current pattern came from, for generators that mix sources
+    /// (e.g., `--mystery`)
+    pub fn pattern_source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Generate a new unique pattern and make it current, hidden until revealed
+    ///
+    /// Any running loop plays on; call [`start`](Self::start) to hear the new one.
+    pub fn generate(&mut self) -> Result<&Pattern, KickbeatsError> {
+        let next = self.next_pattern()?;
+        Ok(self.session.current_pattern.insert(next.pattern))
+    }
+
+    /// Generate a new unique pattern and make it current, hidden until
+    /// revealed, noting whether it had to settle for less than 3 steps from
+    /// recent patterns
+    ///
+    /// Any running loop plays on; call [`start`](Self::start) to hear the new one.
+    pub fn next_pattern(&mut self) -> Result<NextPattern, KickbeatsError> {
+        // Keep any ramped tempo for the next pattern
+        self.sync_tempo_from_playback();
+        // A missed pattern due for review comes back before a fresh one
+        let review = self.session.next_review();
+        let reviewing = review.is_some();
+        let (pattern, distance) = match review {
+            Some(pattern) => (pattern, 3),
+            None => {
+                let next = self.generate_next()?;
+                self.source = next.source;
+                (next.pattern, next.distance)
+            }
+        };
+
+        // A tempo range gives each new pattern a tempo of its own
+        if let Some(range) = self.session.tempo_range {
+            self.set_tempo(range.pick(&mut rand::thread_rng()))?;
+        }
+        self.session.patterns_generated += 1;
+        self.session.add_to_history(pattern.clone());
+        self.refill_prefetch();
+        self.session.current_pattern = Some(pattern.clone());
+        self.session.pattern_revealed = false;
+        self.kit_scores.clear();
+        self.kit_timings.clear();
+        self.session.update_activity();
+        #[cfg(feature = "history")]
+        self.log(pattern_event(&pattern, reviewing));
+        self.log_pattern(reviewing);
+        self.update_stream();
+        // Mixed sources stay secret, and their review patterns repeat on purpose
+        let relaxed = (distance < 3 && self.source.is_none()).then_some(distance);
+        self.session.record_relaxed(relaxed);
+        Ok(NextPattern {
+            pattern,
+            relaxed,
+            review: reviewing,
+        })
+    }
+
+    /// Loop the current pattern, after the session's listen delay and a count-in
+    ///
+    /// With neither of those (nor a repeat cue) to play first, a running loop
+    /// takes it over at its next bar line instead, at the session tempo.
+    pub fn start(&mut self) -> Result<(), KickbeatsError> {
+        let pattern = self.session.current_pattern.clone().ok_or_else(|| {
+            KickbeatsError::Playback(String::from("No pattern to play; generate one first"))
+        })?;
+        let started = if !self.has_preroll() && self.queue(pattern.clone()) {
+            self.playback.set_tempo(self.session.tempo_bpm);
+            Ok(())
+        } else {
+            self.end_sequence();
+            self.playback.stop();
+            if self.session.repeat_cue && self.session.current_relaxed.is_some() {
+                self.playback.cue_count_in();
+            }
+            self.playback.start_with_preroll(
+                pattern,
+                self.session.tempo_bpm,
+                self.session.click_enabled,
+                Duration::from_secs(u64::from(self.session.listen_delay_secs)),
+            )
+        };
+        self.advance_pending = started.is_ok();
+        #[cfg(feature = "history")]
+        if started.is_ok() {
+            self.log(ReplayEvent::Play {
+                bpm: self.session.tempo_bpm,
+            });
+        }
+        started
+    }
+
+    /// Loop `pattern` straight away, making it current
+    ///
+    /// For returning to a pattern from history; it keeps its revealed state.
+    /// A running loop takes it over at its next bar line, keeping time
+    /// without a gap; otherwise playback starts after a count-in.
+    pub fn replay(&mut self, pattern: Pattern) -> Result<(), KickbeatsError> {
+        self.sync_tempo_from_playback();
+        let queued = self.queue(pattern.clone());
+        if !queued {
+            self.playback.stop();
+        }
+        self.session.current_pattern = Some(pattern.clone());
+        self.session.current_relaxed = None;
+        self.kit_scores.clear();
+        self.kit_timings.clear();
+        self.session.update_activity();
+        #[cfg(feature = "history")]
+        self.log(ReplayEvent::Revisit {
+            pattern: pattern.fingerprint(),
+        });
+        self.update_stream();
+        let started = if queued {
+            Ok(())
+        } else {
+            self.playback
+                .start(pattern, self.session.tempo_bpm, self.session.click_enabled)
+        };
+        self.advance_pending = started.is_ok();
+        #[cfg(feature = "history")]
+        if started.is_ok() {
+            self.log(ReplayEvent::Play {
+                bpm: self.session.tempo_bpm,
+            });
+        }
+        started
+    }
+
+    /// Whether the current pattern starts after a listen delay, a count-in,
+    /// or a repeat cue, which only a fresh start plays
+    fn has_preroll(&self) -> bool {
+        self.session.listen_delay_secs > 0
+            || self.session.count_in.bars > 0
+            || (self.session.repeat_cue && self.session.current_relaxed.is_some())
+    }
+
+    /// End any medley or arrangement, then hand `pattern` to the running
+    /// loop for its next bar line, returning whether it took it
+    ///
+    /// A medley or arrangement only reaches a bar line of its own at the end
+    /// of the whole sequence, so it's never handed one.
+    fn queue(&mut self, pattern: Pattern) -> bool {
+        let sequenced = self.medley.is_some() || self.arrangement.is_some();
+        self.end_sequence();
+        !sequenced && self.playback.queue_pattern(pattern)
+    }
+
+    /// Play the current pattern turning into a new one, one step every
+    /// `morph_bars` loops, making the new one current
+    ///
+    /// Returns how many steps change on the way.
+    pub fn morph(&mut self) -> Result<usize, KickbeatsError> {
+        let from = self.session.current_pattern.clone().ok_or_else(|| {
+            KickbeatsError::Playback(String::from("No pattern to morph from; generate one first"))
+        })?;
+        self.sync_tempo_from_playback();
+        let next = self.generate_next()?;
+        let to = next.pattern;
+        let morph = Morph::new(&from, &to, self.session.morph_bars)?;
+        let steps = morph.stages.len() - 1;
+
+        self.source = next.source;
+        self.end_sequence();
+        self.playback.stop();
+        self.session.patterns_generated += 1;
+        self.session.add_to_history(to.clone());
+        self.refill_prefetch();
+        #[cfg(feature = "history")]
+        self.log(ReplayEvent::Morph {
+            pattern: to.fingerprint(),
+            loops_per_stage: morph.loops_per_stage,
+            bpm: self.session.tempo_bpm,
+        });
+        self.session.current_pattern = Some(to);
+        self.session.pattern_revealed = false;
+        self.kit_scores.clear();
+        self.kit_timings.clear();
+        self.session.update_activity();
+        self.update_stream();
+        let started =
+            self.playback
+                .start_morph(morph, self.session.tempo_bpm, self.session.click_enabled);
+        self.advance_pending = started.is_ok();
+        started.map(|()| steps)
+    }
+
+    /// Play a variation of the current pattern straight away, making it
+    /// current and revealed
+    ///
+    /// The variation joins the history after the pattern it came from, so
+    /// going back returns to the original.
+    pub fn transform(&mut self, transform: Transform) -> Result<(), KickbeatsError> {
+        let from = self.session.current_pattern.as_ref().ok_or_else(|| {
+            KickbeatsError::Playback(String::from("No pattern to vary; generate one first"))
+        })?;
+        let varied = transform.apply(from)?;
+        self.session.patterns_generated += 1;
+        self.session.add_to_history(varied.clone());
+        self.replay(varied)?;
+        self.session.mark_revealed();
+        Ok(())
+    }
+
+    /// Play each pattern in the history once, oldest first, with a bar of
+    /// click between them (see [`Medley`])
+    ///
+    /// The current pattern stays current; [`poll`](Self::poll) announces each
+    /// part as it comes up and when the medley is over. Returns the medley.
+    pub fn start_medley(&mut self) -> Result<&Medley, KickbeatsError> {
+        let parts: Vec<(u32, Pattern)> = self
+            .session
+            .pattern_history
+            .iter_recent()
+            .rev()
+            .map(|entry| (entry.number, entry.pattern.clone()))
+            .collect();
+        let medley = Medley::new(&parts).ok_or_else(|| {
+            KickbeatsError::Playback(String::from("No patterns in this session yet"))
+        })?;
+        self.stop();
+        self.playback.set_loop_limit(Some(1));
+        if let Err(e) = self.playback.start(
+            medley.pattern.clone(),
+            self.session.tempo_bpm,
+            self.session.click_enabled,
+        ) {
+            self.playback.set_loop_limit(self.session.loop_limit());
+            return Err(e);
+        }
+        self.session.update_activity();
+        Ok(&self.medley.insert((medley, None)).0)
+    }
+
+    /// Whether a medley is playing
+    pub fn is_medley_playing(&self) -> bool {
+        self.medley.is_some()
+    }
+
+    /// Play the song form `spec` through once, each section's pattern
+    /// looping for its count and straight into the next (see [`Arrangement`])
+    ///
+    /// Parts come from the session's patterns by number, from noted patterns
+    /// by tag (this session's first, then the saved history's), or from
+    /// pattern notation. The current pattern stays current;
+    /// [`poll`](Self::poll) announces each section as it comes up and when
+    /// the form is over. Returns the arrangement.
+    pub fn start_arrangement(
+        &mut self,
+        spec: &ArrangementSpec,
+    ) -> Result<&Arrangement, KickbeatsError> {
+        let parts = spec
+            .parts
+            .iter()
+            .filter(|(label, _)| spec.form.iter().any(|section| section.label == *label))
+            .map(|(label, source)| Ok((*label, self.part_pattern(source)?)))
+            .collect::<Result<Vec<_>, KickbeatsError>>()?;
+        let arrangement = Arrangement::new(&spec.form, &parts)?;
+        self.stop();
+        self.playback.set_loop_limit(Some(1));
+        if let Err(e) = self.playback.start(
+            arrangement.pattern.clone(),
+            self.session.tempo_bpm,
+            self.session.click_enabled,
+        ) {
+            self.playback.set_loop_limit(self.session.loop_limit());
+            return Err(e);
+        }
+        self.session.update_activity();
+        Ok(&self.arrangement.insert((arrangement, None)).0)
+    }
+
+    /// Whether an arrangement is playing
+    pub fn is_arrangement_playing(&self) -> bool {
+        self.arrangement.is_some()
+    }
+
+    /// The pattern an arrangement's part plays
+    fn part_pattern(&self, source: &PartSource) -> Result<Pattern, KickbeatsError> {
+        let session = &self.session;
+        match source {
+            PartSource::Number(number) => session
+                .pattern_history
+                .iter_recent()
+                .find(|entry| entry.number == *number)
+                .map(|entry| entry.pattern.clone())
+                .or_else(|| {
+                    session
+                        .pattern_notes
+                        .iter()
+                        .find(|noted| noted.number == *number)
+                        .map(|noted| noted.pattern.clone())
+                })
+                .ok_or_else(|| {
+                    KickbeatsError::Config(format!(
+                        "Pattern #{} isn't in this session's history",
+                        number
+                    ))
+                }),
+            PartSource::Tag(tag) => session
+                .pattern_notes
+                .iter()
+                .rev()
+                .find(|noted| noted.note.has_tag(tag))
+                .map(|noted| Ok(noted.pattern.clone()))
+                .or_else(|| self.saved_tagged(tag))
+                .unwrap_or_else(|| {
+                    Err(KickbeatsError::Config(format!(
+                        "No pattern is tagged #{}",
+                        tag
+                    )))
+                }),
+            PartSource::Written(text) => {
+                Pattern::from_pattern_string(text, session.complexity_level)
+                    .map_err(KickbeatsError::from)
+            }
+        }
+    }
+
+    /// The most recent pattern tagged `tag` in the saved practice history
+    #[cfg(feature = "history")]
+    fn saved_tagged(&self, tag: &str) -> Option<Result<Pattern, KickbeatsError>> {
+        let sessions = match self.storage.as_ref()?.sessions() {
+            Ok(sessions) => sessions,
+            Err(e) => return Some(Err(e)),
+        };
+        let noted = sessions
+            .iter()
+            .rev()
+            .flat_map(|record| record.notes.iter().rev())
+            .find(|noted| noted.note().has_tag(tag))?;
+        Some(
+            Pattern::from_pattern_string(&noted.pattern, self.session.complexity_level)
+                .map_err(|e| KickbeatsError::Storage(e.to_string())),
+        )
+    }
+
+    /// Without a practice history, only this session's notes have tags
+    #[cfg(not(feature = "history"))]
+    fn saved_tagged(&self, _tag: &str) -> Option<Result<Pattern, KickbeatsError>> {
+        None
+    }
+
+    /// Listen to the electronic kit on the session's `kit_port`, if it has one,
+    /// echoing its hits to the playback port if the session's `kit_echo` is set
+    ///
+    /// Practice can carry on without the kit if this fails.
+    pub fn connect_kit(&mut self) -> Option<Result<(), KickbeatsError>> {
+        let port = self.session.kit_port.as_deref()?;
+        let connected = self
+            .session
+            .kit_echo
+            .then(|| self.playback.drum_pad())
+            .transpose()
+            .and_then(|echo| KitInput::connect(port, self.kit_map.clone(), echo));
+        Some(connected.map(|kit| self.kit = Some(kit)))
+    }
+
+    /// Turn auto-advance on or off; a running loop picks it up at its next loop
+    ///
+    /// Timed drills already stop after their own loops, so it can't be turned
+    /// on during one.
+    pub fn set_auto_advance(&mut self, enabled: bool) -> Result<(), KickbeatsError> {
+        if enabled && self.session.drill.is_some() {
+            return Err(KickbeatsError::Playback(String::from(
+                "Auto-advance isn't available during a timed drill",
+            )));
+        }
+        self.session.auto_advance = enabled;
+        self.playback.set_loop_limit(self.session.loop_limit());
+        self.session.update_activity();
+        #[cfg(feature = "history")]
+        self.log(ReplayEvent::AutoAdvance { enabled });
+        Ok(())
+    }
+
+    /// Catch up on input and playback since the last call, returning what
+    /// front ends may want to report
+    ///
+    /// Times each hit from the kit against its limb's lane (see
+    /// [`Limb::lane`]) and adds it to [`kit_scores`](Self::kit_scores) and
+    /// [`kit_timings`](Self::kit_timings); hits
+    /// from pads no limb plays, or while nothing loops, are dropped. Once
+    /// auto-advance has played the current pattern enough times, moves on to
+    /// a new one. Call it every tick of the front end's loop.
+    pub fn poll(&mut self) -> Vec<Notice> {
+        let mut notices = self.poll_kit();
+        notices.extend(self.poll_output());
+        notices.extend(self.poll_plan());
+        notices.extend(self.poll_advance());
+        notices.extend(self.poll_medley());
+        notices.extend(self.poll_arrangement());
+        if let Some(classroom) = &self.classroom {
+            notices.extend(classroom.drain().into_iter().map(Notice::Class));
+        }
+        #[cfg(feature = "config")]
+        notices.extend(self.poll_config());
+        self.update_stream();
+        notices
+    }
+
+    /// Report playback losing its output, and getting it back
+    fn poll_output(&mut self) -> Option<Notice> {
+        let lost = self.playback.output_lost();
+        if lost.is_some() == self.output_lost {
+            return None;
+        }
+        self.output_lost = lost.is_some();
+        match lost {
+            Some(e) => Some(Notice::OutputLost(e)),
+            // A run stopped while waiting never got its output back
+            None => self.playback.is_playing().then_some(Notice::OutputRestored),
+        }
+    }
+
+    /// Take on settings saved to the watched config file since the last call
+    #[cfg(feature = "config")]
+    fn poll_config(&mut self) -> Vec<Notice> {
+        let Some(watch) = &self.config_watch else {
+            return Vec::new();
+        };
+        watch
+            .drain()
+            .into_iter()
+            .map(
+                |update| match update.and_then(|update| self.apply_settings(update)) {
+                    Ok(update) => Notice::ConfigReloaded(update),
+                    Err(e) => Notice::ConfigReloadFailed(e.to_string()),
+                },
+            )
+            .collect()
+    }
+
+    /// Grade the kit's hits since the last call
+    fn poll_kit(&mut self) -> Vec<Notice> {
+        let Some(kit) = &self.kit else {
+            return Vec::new();
+        };
+        let mut notices = Vec::new();
+        for note in kit.drain() {
+            let (Some(limb), Some(pattern)) = (note.limb, &self.session.current_pattern) else {
+                continue;
+            };
+            let Some((loop_index, position, step_secs)) = self.playback.position_at(note.at) else {
+                continue;
+            };
+            let timing = limb
+                .lane(pattern, self.session.overlay)
+                .and_then(|lane| HitTiming::on_lane(limb, &lane, position, step_secs));
+            let grade = timing.map(|timing| timing.grade());
+            if let Some(timing) = timing {
+                self.kit_timings.record(loop_index, timing);
+            }
+            self.kit_scores.record(limb, grade);
+            notices.push(Notice::KitHit { limb, grade });
+        }
+        notices
+    }
+
+    /// Switch to the plan's next segment once its time comes, starting a fresh
+    /// pattern with its settings
+    fn poll_plan(&mut self) -> Option<Notice> {
+        let elapsed = self.session.elapsed_secs();
+        let transition = self.session.plan.as_mut()?.update(elapsed)?;
+        let PlanTransition::Segment(index) = transition else {
+            return Some(Notice::PlanFinished);
+        };
+
+        self.sync_tempo_from_playback();
+        self.session.apply_plan_segment();
+        self.playback.set_tempo(self.session.tempo_bpm);
+        #[cfg(feature = "history")]
+        self.log_settings();
+        let next = match self.next_pattern() {
+            Ok(next) => next,
+            Err(e) => return Some(Notice::PlanSegmentFailed(e.to_string())),
+        };
+        Some(match self.start() {
+            Ok(()) => Notice::PlanSegment { index, next },
+            Err(e) => Notice::PlanSegmentFailed(e.to_string()),
+        })
+    }
+
+    /// Generate and start the next pattern once the loop limit has stopped
+    /// the current one, or during its last loop when the next one can take
+    /// over at the bar line without a count-in; reveals the current one
+    /// first if asked to
+    fn poll_advance(&mut self) -> Option<Notice> {
+        if !self.advance_pending || !self.session.auto_advance {
+            return None;
+        }
+        if self.playback.is_playing() {
+            let last = u64::from(self.session.advance_loops).saturating_sub(1);
+            let seamless = self.session.drill.is_none()
+                && self.session.listen_delay_secs == 0
+                && self.session.count_in.bars == 0
+                && !self.session.repeat_cue
+                && self.medley.is_none()
+                && self.arrangement.is_none()
+                && self.playback.can_queue()
+                && !self.playback.is_pattern_queued();
+            if !seamless
+                || self
+                    .playback
+                    .current_loop()
+                    .is_none_or(|index| index < last)
+            {
+                return None;
+            }
+        }
+        self.advance_pending = false;
+
+        let revealed = if self.session.reveal_on_advance && !self.session.pattern_revealed {
+            self.reveal().cloned()
+        } else {
+            None
+        };
+        let next = match self.next_pattern() {
+            Ok(next) => next,
+            Err(e) => return Some(Notice::AdvanceFailed(e.to_string())),
+        };
+        Some(match self.start() {
+            Ok(()) => Notice::Advanced { revealed, next },
+            Err(e) => Notice::AdvanceFailed(e.to_string()),
+        })
+    }
+
+    /// Announce the medley's next part as it comes up, and its end
+    fn poll_medley(&mut self) -> Option<Notice> {
+        let (medley, announced) = self.medley.as_mut()?;
+        if !self.playback.is_playing() {
+            self.end_sequence();
+            return Some(Notice::MedleyFinished);
+        }
+        let number = medley.number_at(self.playback.current_step()?)?;
+        if *announced == Some(number) {
+            return None;
+        }
+        *announced = Some(number);
+        let index = medley.parts.iter().position(|&(n, _, _)| n == number)?;
+        Some(Notice::MedleyPart {
+            number,
+            index: index + 1,
+            count: medley.len(),
+        })
+    }
+
+    /// Announce the arrangement's next section as it comes up, and its end
+    fn poll_arrangement(&mut self) -> Option<Notice> {
+        let (arrangement, announced) = self.arrangement.as_mut()?;
+        if !self.playback.is_playing() {
+            self.end_sequence();
+            return Some(Notice::ArrangementFinished);
+        }
+        let index = arrangement.section_at(self.playback.current_step()?)?;
+        if *announced == Some(index) {
+            return None;
+        }
+        *announced = Some(index);
+        let section = arrangement.sections[index];
+        Some(Notice::Section {
+            label: section.label,
+            loops: section.loops,
+            index: index + 1,
+            count: arrangement.len(),
+        })
+    }
+
+    /// Forget any medley or arrangement, putting back the session's own loop limit
+    fn end_sequence(&mut self) {
+        let medley = self.medley.take();
+        let arrangement = self.arrangement.take();
+        if medley.is_some() || arrangement.is_some() {
+            self.playback.set_loop_limit(self.session.loop_limit());
+        }
+    }
+
+    /// Stop playback, keeping any ramped tempo
+    pub fn stop(&mut self) {
+        self.sync_tempo_from_playback();
+        self.end_sequence();
+        self.advance_pending = false;
+        #[cfg(feature = "history")]
+        if self.playback.is_playing() {
+            self.log(ReplayEvent::Stop);
+        }
+        self.playback.stop();
+    }
+
+    /// Whether a pattern is looping (including its pre-roll and count-in)
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_playing()
+    }
+
+    /// Mark the current pattern revealed and return it
+    pub fn reveal(&mut self) -> Option<&Pattern> {
+        self.session.current_pattern.as_ref()?;
+        self.session.mark_revealed();
+        self.session.update_activity();
+        #[cfg(feature = "history")]
+        self.log(ReplayEvent::Reveal);
+        if let (Some(log), Some(pattern)) = (&self.event_log, &self.session.current_pattern) {
+            log.record(LogEvent::Reveal {
+                number: self.session.patterns_generated,
+                steps: pattern
+                    .steps
+                    .iter()
+                    .map(|&kick| if kick { 'x' } else { '.' })
+                    .collect(),
+            });
+        }
+        self.update_stream();
+        self.session.current_pattern.as_ref()
+    }
+
+    /// Grade an answer in the session's answer format and record its accuracy
+    /// (see [`record_result`](Self::record_result))
+    pub fn grade(&mut self, input: &str) -> Result<AnswerGrade, KickbeatsError> {
+        let pattern = self
+            .session
+            .current_pattern
+            .as_ref()
+            .ok_or_else(|| KickbeatsError::Playback(String::from("No pattern to answer")))?;
+        let grade = grade_answer_at(
+            pattern,
+            input,
+            self.session.answer_format,
+            self.session.answer_resolution,
+        )?;
+        #[cfg(feature = "history")]
+        self.log(ReplayEvent::Answer {
+            answer: Some(input.to_string()),
+            accuracy: grade.accuracy,
+        });
+        self.apply_result(grade.accuracy);
+        Ok(grade)
+    }
+
+    /// Record an answer's accuracy for the current pattern and reveal it
+    ///
+    /// Tells the generator how it went, counts the answer toward the
+    /// curriculum (moving to its next stage's complexity and tempo when
+    /// passed), and applies the session's click fade, if any, for the next
+    /// loops.
+    pub fn record_result(&mut self, accuracy: f32) -> AnswerOutcome {
+        #[cfg(feature = "history")]
+        self.log(ReplayEvent::Answer {
+            answer: None,
+            accuracy,
+        });
+        self.apply_result(accuracy)
+    }
+
+    /// [`record_result`](Self::record_result), once the answer is logged
+    fn apply_result(&mut self, accuracy: f32) -> AnswerOutcome {
+        if let Some(pattern) = &self.session.current_pattern {
+            lock_generator(&self.generator).record_result(pattern, accuracy);
+        }
+        self.sync_tempo_from_playback();
+        // Goal tempos count the ramped tempo actually heard
+        let goal_complete = self.session.record_answer(accuracy);
+        self.session.mark_revealed();
+        self.session.update_activity();
+        self.update_stream();
+
+        let next_stage = self.session.advance_curriculum(accuracy);
+        if next_stage.is_some() {
+            self.playback.set_tempo(self.session.tempo_bpm);
+            self.refill_prefetch();
+            #[cfg(feature = "history")]
+            self.log_settings();
+        }
+        let click_velocity = self.session.click_fade.map(|fade| {
+            self.playback
+                .fade_click(fade, accuracy, self.click_velocity())
+        });
+        AnswerOutcome {
+            click_velocity,
+            next_stage,
+            goal_complete,
+        }
+    }
+
+    /// Current tempo, including any ramp steps applied so far
+    pub fn tempo(&self) -> u16 {
+        if self.playback.is_playing() {
+            self.playback.current_tempo()
+        } else {
+            self.session.tempo_bpm
+        }
+    }
+
+    /// Change tempo (40-300 BPM); a running loop picks it up at its next loop
+    ///
+    /// With Link the change goes to the session's peers even while stopped.
+    pub fn set_tempo(&mut self, tempo_bpm: u16) -> Result<(), KickbeatsError> {
+        if !(40..=300).contains(&tempo_bpm) {
+            return Err(KickbeatsError::Config(format!(
+                "Tempo {} is out of range (40-300 BPM)",
+                tempo_bpm
+            )));
+        }
+        self.session.tempo_bpm = tempo_bpm;
+        self.session.update_activity();
+        self.playback.set_tempo(tempo_bpm);
+        // A running loop logs the change once it takes effect
+        if let (Some(log), false) = (&self.event_log, self.playback.is_playing()) {
+            log.record(LogEvent::TempoChange { bpm: tempo_bpm });
+        }
+        #[cfg(feature = "history")]
+        self.log(ReplayEvent::Tempo { bpm: tempo_bpm });
+        self.update_stream();
+        Ok(())
+    }
+
+    /// Take on settings changed in the config file: a running loop switches
+    /// tempo and notes at its next boundary, and complexity applies to the
+    /// next pattern generated
+    #[cfg(feature = "config")]
+    pub fn apply_settings(
+        &mut self,
+        update: SettingsUpdate,
+    ) -> Result<SettingsUpdate, KickbeatsError> {
+        if update.remaps() {
+            let current = self.midi_mapping;
+            let mapping = MidiMapping::new(
+                update.kick_note.unwrap_or(current.kick_note),
+                update.click_note.unwrap_or(current.click_note),
+                update
+                    .channel
+                    .map_or(current.channel, |channel| channel.saturating_sub(1)),
+            )?;
+            // A click split onto its own channel stays there
+            let mapping = if current.click_channel == current.channel {
+                mapping
+            } else {
+                mapping.with_click_channel(current.click_channel)?
+            };
+            self.playback.remap(mapping);
+            self.midi_mapping = mapping;
+        }
+        if let Some(tempo) = update.tempo {
+            self.set_tempo(tempo)?;
+        }
+        if let Some(level) = update.complexity {
+            self.set_complexity(level);
+        }
+        Ok(update)
+    }
+
+    /// Complexity of patterns generated from now on
+    pub fn set_complexity(&mut self, level: ComplexityLevel) {
+        self.session.set_complexity(level);
+        self.refill_prefetch();
+        self.session.update_activity();
+        #[cfg(feature = "history")]
+        self.log(ReplayEvent::Complexity {
+            level: complexity_name(level),
+        });
+    }
+
+    /// Time signature of patterns generated from now on
+    pub fn set_time_signature(&mut self, time_signature: TimeSignature) {
+        self.session.time_signature = time_signature;
+        self.refill_prefetch();
+        self.session.update_activity();
+        #[cfg(feature = "history")]
+        self.log(ReplayEvent::TimeSignature {
+            meter: meter_name(time_signature),
+        });
+    }
+
+    /// Mute or unmute the click while the pattern keeps playing
+    pub fn set_click_enabled(&mut self, enabled: bool) {
+        self.session.click_enabled = enabled;
+        self.playback.set_click_enabled(enabled);
+        self.session.update_activity();
+        #[cfg(feature = "history")]
+        self.log(ReplayEvent::Click { enabled });
+    }
+
+    /// Velocity looped kicks play at (0 when they're silenced)
+    pub fn kick_velocity(&self) -> u8 {
+        self.session.kick_velocity.unwrap_or(KICK_VELOCITY)
+    }
+
+    /// Velocity looped clicks play at before any `--click-fade` (0 when
+    /// they're silenced)
+    pub fn click_velocity(&self) -> u8 {
+        self.session.click_velocity.unwrap_or(CLICK_VELOCITY)
+    }
+
+    /// Set how loud the kicks play, from the next kick on, for the rest of
+    /// the session (0 silences them, up to 127)
+    pub fn set_kick_velocity(&mut self, velocity: u8) {
+        let velocity = velocity.min(127);
+        self.session.kick_velocity = Some(velocity);
+        self.playback.set_kick_velocity(velocity);
+        self.session.update_activity();
+    }
+
+    /// Set how loud the click plays, from the next click on, for the rest
+    /// of the session (0 silences it, up to 127); a click fade starts over
+    /// from here
+    pub fn set_click_velocity(&mut self, velocity: u8) {
+        let velocity = velocity.min(127);
+        self.session.click_velocity = Some(velocity);
+        self.playback.set_click_velocity(velocity);
+        self.session.update_activity();
+    }
+
+    /// Make the kicks a step louder or softer, returning their new velocity
+    pub fn step_kick_velocity(&mut self, louder: bool) -> u8 {
+        let velocity = stepped(self.kick_velocity(), louder);
+        self.set_kick_velocity(velocity);
+        velocity
+    }
+
+    /// Make the click a step louder or softer, returning its new velocity
+    pub fn step_click_velocity(&mut self, louder: bool) -> u8 {
+        let velocity = stepped(self.click_velocity(), louder);
+        self.set_click_velocity(velocity);
+        velocity
+    }
+
+    /// Save the MIDI log asked for with [`with_midi_log`](Self::with_midi_log),
+    /// named after when the session started, returning where it went
+    pub fn save_midi_log(&self) -> Option<Result<PathBuf, KickbeatsError>> {
+        let (recorder, dir) = self.midi_log.as_ref()?;
+        Some(save_midi_log(recorder, dir, self.session.session_start))
+    }
+
+    /// Save the replay asked for with [`with_replay`](Self::with_replay),
+    /// named after when the session started, returning where it went
+    #[cfg(feature = "history")]
+    pub fn save_replay(&self) -> Option<Result<PathBuf, KickbeatsError>> {
+        let (replay, _, dir) = self.replay.as_ref()?;
+        Some(replay.save(dir))
+    }
+
+    /// Show the current pattern, and whether it's revealed, on the stream
+    /// overlay, WebSocket server, and classroom, if there are any
+    fn update_stream(&self) {
+        let Some(pattern) = &self.session.current_pattern else {
+            return;
+        };
+        let number = self.session.patterns_generated;
+        let revealed = self.session.pattern_revealed;
+        if let Some(stream) = &self.stream {
+            stream.show(number, pattern, revealed, self.session.tempo_bpm);
+        }
+        #[cfg(feature = "websocket")]
+        if let Some(socket) = &self.socket {
+            socket.show(number, pattern, revealed, self.session.tempo_bpm);
+        }
+        if let Some(classroom) = &self.classroom {
+            classroom.show(number, pattern, revealed, self.session.tempo_bpm);
+        }
+    }
+
+    /// Write the current pattern to the event log, if there is one
+    fn log_pattern(&self, review: bool) {
+        if let (Some(log), Some(pattern)) = (&self.event_log, &self.session.current_pattern) {
+            log.record(LogEvent::PatternGenerated {
+                number: self.session.patterns_generated,
+                pattern: pattern.fingerprint(),
+                complexity: format!("{:?}", pattern.complexity_level).to_lowercase(),
+                review,
+            });
+        }
+    }
+
+    /// Add an event to the replay being recorded, if there is one
+    #[cfg(feature = "history")]
+    fn log(&mut self, event: ReplayEvent) {
+        if let Some((replay, start, _)) = &mut self.replay {
+            replay.record(start.elapsed(), event);
+        }
+    }
+
+    /// Log the tempo, complexity, and meter now in effect, after a curriculum
+    /// stage or plan segment changed them
+    #[cfg(feature = "history")]
+    fn log_settings(&mut self) {
+        self.log(ReplayEvent::Tempo {
+            bpm: self.session.tempo_bpm,
+        });
+        self.log(ReplayEvent::Complexity {
+            level: complexity_name(self.session.complexity_level),
+        });
+        self.log(ReplayEvent::TimeSignature {
+            meter: meter_name(self.session.time_signature),
+        });
+    }
+
+    /// Save a record of the session, and the review queue it graded, to the
+    /// practice history given with [`with_storage`](Self::with_storage)
+    #[cfg(feature = "history")]
+    pub fn save_session(&mut self) -> Option<Result<(), KickbeatsError>> {
+        let storage = self.storage.as_ref()?;
+        let saved = storage.save_session(&SessionRecord::from_session(&self.session));
+        // Taps on the last pattern count toward its review too
+        self.session.review_taps();
+        let reviewed = match &self.session.review {
+            Some(review) => storage.save_review_queue(review),
+            None => Ok(()),
+        };
+        Some(saved.and(reviewed))
+    }
+
+    /// Stop playback and hand back the session, e.g. for a summary
+    pub fn finish(mut self) -> PracticeSession {
+        self.stop();
+        std::mem::take(&mut self.session)
+    }
+
+    /// A new pattern for the session's settings: a prefetched one if one is
+    /// ready, otherwise one generated now
+    fn generate_next(&mut self) -> Result<Prefetched, KickbeatsError> {
+        let (time_signature, complexity) =
+            (self.session.time_signature, self.session.complexity_level);
+        let history = self.session.pattern_history.patterns();
+        let prefetched = self
+            .prefetch
+            .as_ref()
+            .and_then(|prefetch| prefetch.take(time_signature, complexity, &history));
+        match prefetched {
+            Some(next) => Ok(next),
+            None => Prefetched::generate(
+                lock_generator(&self.generator).as_mut(),
+                time_signature,
+                complexity,
+                &history,
+            ),
+        }
+    }
+
+    /// Point the prefetcher at the session's current settings and history
+    fn refill_prefetch(&self) {
+        if let Some(prefetch) = &self.prefetch {
+            prefetch.refill(
+                self.session.time_signature,
+                self.session.complexity_level,
+                self.session.pattern_history.patterns(),
+            );
+        }
+    }
+
+    /// Copy the playback tempo (which a tempo ramp may have raised) into the session
+    pub(crate) fn sync_tempo_from_playback(&mut self) {
+        if self.playback.is_playing() {
+            self.session.tempo_bpm = self.playback.current_tempo();
+        }
+    }
+}
+
+impl Drop for PracticeEngine {
+    fn drop(&mut self) {
+        self.playback.stop();
+    }
+}
+
+/// Write a session's MIDI log to `dir`, named after when the session started
+fn save_midi_log(
+    recorder: &SessionRecorder,
+    dir: &Path,
+    session_start: SystemTime,
+) -> Result<PathBuf, KickbeatsError> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| KickbeatsError::io("create MIDI log directory", dir, e))?;
+    let stamp = session_start
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = dir.join(format!("kickbeats-session-{}.mid", stamp));
+    recorder.write_smf(&path)?;
+    Ok(path)
+}
+
+/// `velocity` a level key's step louder or softer, within 0-127
+fn stepped(velocity: u8, louder: bool) -> u8 {
+    if louder {
+        velocity.saturating_add(VELOCITY_STEP).min(127)
+    } else {
+        velocity.saturating_sub(VELOCITY_STEP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::midi::CLICK_NOTE;
+    use crate::engine::MemorySink;
+    use crate::models::{CountIn, Curriculum, Overlay, Progression, TempoRange};
+    #[cfg(feature = "config")]
+    use crate::models::{PlanProgress, SessionPlan};
+    use std::thread;
+    use std::time::Instant;
+
+    #[test]
+    fn test_generate_makes_a_hidden_current_pattern() {
+        let mut engine = PracticeEngine::new(PracticeSession::default());
+        assert!(engine.current_pattern().is_none());
+        assert!(engine.reveal().is_none());
+
+        let steps = engine.generate().unwrap().steps.len();
+        assert_eq!(steps, 16);
+        assert_eq!(engine.session().patterns_generated, 1);
+        assert!(!engine.session().pattern_revealed);
+
+        assert!(engine.reveal().is_some());
+        assert!(engine.session().pattern_revealed);
+    }
+
+    #[test]
+    fn test_tempo_range_picks_a_tempo_per_pattern() {
+        let mut engine = PracticeEngine::new(PracticeSession {
+            tempo_range: Some(TempoRange::new(90, 140).unwrap()),
+            ..Default::default()
+        });
+
+        let tempos: Vec<u16> = (0..20)
+            .map(|_| {
+                engine.generate().unwrap();
+                engine.tempo()
+            })
+            .collect();
+        assert!(tempos.iter().all(|tempo| (90..=140).contains(tempo)));
+        assert!(tempos.iter().any(|&tempo| tempo != tempos[0]));
+    }
+
+    #[test]
+    fn test_grade_records_accuracy() {
+        let mut engine = PracticeEngine::new(PracticeSession::default());
+        assert!(engine.grade("x...").is_err());
+
+        engine.generate().unwrap();
+        let answer: String = engine
+            .current_pattern()
+            .unwrap()
+            .steps
+            .iter()
+            .map(|&kick| if kick { 'x' } else { '.' })
+            .collect();
+        let grade = engine.grade(&answer).unwrap();
+
+        assert!(grade.is_perfect());
+        assert_eq!(engine.session().answer_accuracies, vec![1.0]);
+    }
+
+    #[cfg(feature = "history")]
+    #[test]
+    fn test_replay_logs_patterns_commands_and_answers() {
+        let dir = std::env::temp_dir().join(format!("kickbeats-replay-{}", std::process::id()));
+        let mut engine = PracticeEngine::new(PracticeSession::default()).with_replay(dir.clone());
+        engine.generate().unwrap();
+        engine.set_tempo(130).unwrap();
+        engine.set_click_enabled(false);
+        engine.record_result(0.5);
+
+        let path = engine.save_replay().unwrap().unwrap();
+        let replay = SessionReplay::load(&path).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let events: Vec<_> = replay.events.into_iter().map(|entry| entry.event).collect();
+        assert!(matches!(
+            events[0],
+            ReplayEvent::Pattern { review: false, .. }
+        ));
+        assert_eq!(
+            events[1..],
+            [
+                ReplayEvent::Tempo { bpm: 130 },
+                ReplayEvent::Click { enabled: false },
+                ReplayEvent::Answer {
+                    answer: None,
+                    accuracy: 0.5
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_result_advances_curriculum() {
+        let stage = |name: &str, tempo, progression| CurriculumStage {
+            name: name.to_string(),
+            complexity: ComplexityLevel::Simple,
+            tempo: Some(tempo),
+            progression,
+        };
+        let curriculum = Curriculum::new(vec![
+            stage(
+                "one",
+                80,
+                Some(Progression {
+                    accuracy_percent: 90,
+                    answers: 1,
+                }),
+            ),
+            stage("two", 100, None),
+        ])
+        .unwrap();
+        let mut session = PracticeSession::default();
+        session.start_curriculum(curriculum);
+        let mut engine = PracticeEngine::new(session);
+        engine.generate().unwrap();
+
+        assert_eq!(engine.record_result(0.5).next_stage, None);
+        let outcome = engine.record_result(1.0);
+        assert_eq!(outcome.next_stage.unwrap().name, "two");
+        assert_eq!(outcome.click_velocity, None);
+        assert_eq!(engine.tempo(), 100);
+        assert!(engine.session().pattern_revealed);
+    }
+
+    #[test]
+    fn test_settings() {
+        let mut engine = PracticeEngine::new(PracticeSession::default());
+
+        assert!(matches!(
+            engine.set_tempo(500),
+            Err(KickbeatsError::Config(_))
+        ));
+        engine.set_tempo(90).unwrap();
+        assert_eq!(engine.tempo(), 90);
+
+        engine.set_complexity(ComplexityLevel::Simple);
+        engine.set_time_signature(TimeSignature::three_four());
+        assert_eq!(engine.generate().unwrap().steps.len(), 12);
+
+        engine.set_click_enabled(false);
+        assert!(!engine.playback().is_click_enabled());
+        assert_eq!(engine.finish().tempo_bpm, 90);
+    }
+
+    #[test]
+    fn test_voice_levels_step_and_stay_in_range() {
+        let mut engine = PracticeEngine::new(PracticeSession {
+            kick_velocity: Some(120),
+            click_velocity: Some(10),
+            ..Default::default()
+        });
+        assert_eq!(engine.playback().kick_velocity(), 120);
+        assert_eq!(engine.playback().click_velocity(), 10);
+
+        assert_eq!(engine.step_kick_velocity(true), 127);
+        assert_eq!(engine.step_click_velocity(false), 0);
+        assert_eq!(engine.step_click_velocity(false), 0);
+        assert_eq!(engine.step_click_velocity(true), 10);
+        assert_eq!(engine.playback().click_velocity(), 10);
+        assert_eq!(engine.session().kick_velocity, Some(127));
+
+        let mut faded = PracticeEngine::new(PracticeSession {
+            click_fade: Some("90%/20".parse().unwrap()),
+            click_velocity: Some(50),
+            ..Default::default()
+        });
+        faded.generate().unwrap();
+        assert_eq!(faded.record_result(1.0).click_velocity, Some((50, 30)));
+        assert_eq!(faded.record_result(0.0).click_velocity, Some((30, 50)));
+        assert_eq!(faded.record_result(0.0).click_velocity, Some((50, 50)));
+    }
+
+    #[test]
+    fn test_prefetched_patterns_follow_settings() {
+        let mut engine = PracticeEngine::new(PracticeSession::default()).with_prefetch(2);
+        for _ in 0..5 {
+            let next = engine.next_pattern().unwrap();
+            assert_eq!(next.pattern.steps.len(), 16);
+            assert!(next.pattern.validate_steps().is_ok());
+        }
+
+        // Patterns queued for 4/4 are thrown out
+        engine.set_time_signature(TimeSignature::three_four());
+        for _ in 0..3 {
+            assert_eq!(engine.next_pattern().unwrap().pattern.steps.len(), 12);
+        }
+        assert_eq!(engine.session().patterns_generated, 8);
+    }
+
+    #[test]
+    fn test_poll_grades_each_limb_on_its_lane() {
+        let session = PracticeSession {
+            overlay: Some(Overlay::SonClave),
+            ..Default::default()
+        };
+        let mut engine = PracticeEngine::new(session);
+        engine.playback.set_sink(Some(MemorySink::new()));
+        engine.kit = Some(KitInput::detached());
+        let kick_on_one = (0..16).map(|i| i == 0).collect();
+        let pattern = Pattern::new(
+            kick_on_one,
+            TimeSignature::four_four(),
+            ComplexityLevel::Simple,
+        );
+
+        // 300 BPM: a 0.8s count-in, then 0.8s loops of 50ms steps
+        engine.session.tempo_bpm = 300;
+        let started = Instant::now();
+        engine.replay(pattern).unwrap();
+        thread::sleep(Duration::from_millis(1000));
+
+        // Kick just after 1, snare just after the clave's second note, a
+        // hi-hat, and a tom
+        let at = |ms: u64| started + Duration::from_millis(800 + ms);
+        let kit = engine.kit.as_ref().unwrap();
+        for (note, ms) in [(36, 20), (38, 160), (42, 100), (45, 50)] {
+            kit.feed(&KitMap::default(), &[0x99, note, 100], at(ms));
+        }
+
+        let notices = engine.poll();
+        assert_eq!(notices.len(), 3);
+        // No eighths overlay, so the hi-hat has nothing to play
+        assert!(matches!(
+            notices[2],
+            Notice::KitHit {
+                limb: Limb::HiHat,
+                grade: None
+            }
+        ));
+        let scores = engine.kit_scores().unwrap();
+        assert_eq!(scores.counts(Limb::Kick).on_time(), 1);
+        assert_eq!(scores.counts(Limb::Snare).on_time(), 1);
+        assert_eq!(scores.unscored, 1);
+        let timings = engine.kit_timings().unwrap();
+        let hits = timings.hits(0);
+        assert_eq!(hits.len(), 2);
+        assert!(hits
+            .iter()
+            .all(|hit| hit.offset_secs > 0.0 && hit.verdict().is_none()));
+        assert_eq!(hits[1].step, 3);
+        assert!(engine.poll().is_empty());
+        engine.stop();
+    }
+
+    #[test]
+    fn test_morph_makes_its_target_current() {
+        let mut engine = PracticeEngine::new(PracticeSession::default());
+        engine.playback.set_sink(Some(MemorySink::new()));
+        assert!(matches!(engine.morph(), Err(KickbeatsError::Playback(_))));
+
+        let first = engine.generate().unwrap().clone();
+        let steps = engine.morph().unwrap();
+        let target = engine.current_pattern().unwrap();
+        assert_ne!(target.id, first.id);
+        assert_eq!(engine.session().patterns_generated, 2);
+        assert_eq!(engine.playback().morph_stage(), Some((0, steps + 1)));
+        engine.stop();
+    }
+
+    #[test]
+    fn test_transform_plays_a_revealed_variation() {
+        let mut engine = PracticeEngine::new(PracticeSession::default());
+        engine.playback.set_sink(Some(MemorySink::new()));
+        assert!(engine.transform(Transform::Reverse).is_err());
+
+        let first = engine.generate().unwrap().clone();
+        engine.transform(Transform::Reverse).unwrap();
+        let varied = engine.current_pattern().unwrap();
+        assert_ne!(varied.id, first.id);
+        assert_eq!(varied.steps[0], first.steps[0]);
+        assert!(engine.session().pattern_revealed);
+        assert!(engine.is_playing());
+        let previous = engine.session().pattern_history.previous(varied.id);
+        assert_eq!(previous.map(|entry| entry.pattern.id), Some(first.id));
+        engine.stop();
+    }
+
+    #[test]
+    #[cfg(feature = "config")]
+    fn test_plan_switches_segments_with_a_fresh_pattern() {
+        let plan = SessionPlan::from_toml_str(
+            "[[segment]]\nminutes = 1\n\n[[segment]]\nminutes = 1\ncomplexity = \"complex\"\ntempo = 90\n",
+        )
+        .unwrap();
+        let mut engine = PracticeEngine::new(PracticeSession {
+            plan: Some(PlanProgress::new(plan)),
+            ..Default::default()
+        });
+        engine.playback.set_sink(Some(MemorySink::new()));
+        let first = engine.generate().unwrap().clone();
+        engine.start().unwrap();
+        assert!(engine.poll().is_empty());
+
+        engine.session.session_start -= Duration::from_secs(61);
+        match &engine.poll()[..] {
+            [Notice::PlanSegment { index: 1, next }] => {
+                assert_ne!(next.pattern.id, first.id);
+                assert_eq!(next.pattern.complexity_level, ComplexityLevel::Complex);
+            }
+            other => panic!("expected the second segment, got {:?}", other),
+        }
+        assert_eq!(engine.session().tempo_bpm, 90);
+        assert!(engine.is_playing());
+
+        engine.session.session_start -= Duration::from_secs(60);
+        assert!(matches!(&engine.poll()[..], [Notice::PlanFinished]));
+        assert!(engine.poll().is_empty());
+        engine.stop();
+    }
+
+    #[test]
+    fn test_next_pattern_while_playing_switches_without_a_count_in() {
+        let session = PracticeSession {
+            tempo_bpm: 300,
+            click_enabled: false,
+            count_in: CountIn::new(0).unwrap(),
+            ..Default::default()
+        };
+        let mut engine = PracticeEngine::new(session);
+        let sink = MemorySink::new();
+        engine.playback.set_sink(Some(sink.clone()));
+        engine.generate().unwrap();
+        engine.start().unwrap();
+
+        // 300 BPM: a 0.8s count-in, then 0.8s loops
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while engine.playback().current_loop().is_none() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert!(engine.playback().current_loop().is_some());
+        let sent = sink.len();
+
+        let next = engine.next_pattern().unwrap().pattern.id;
+        engine.start().unwrap();
+        assert!(engine.playback().is_pattern_queued());
+        thread::sleep(Duration::from_millis(1200));
+        assert!(engine.is_playing());
+        assert!(!engine.playback().is_pattern_queued());
+        assert_eq!(engine.current_pattern().map(|p| p.id), Some(next));
+
+        // With the click off, any click after the switch would be a count-in
+        let after = &sink.messages()[sent..];
+        assert!(!after.is_empty());
+        assert!(!after
+            .iter()
+            .any(|message| message[0] == 0x99 && message[1] == CLICK_NOTE));
+        engine.stop();
+    }
+
+    #[test]
+    fn test_next_pattern_while_playing_keeps_its_count_in() {
+        let session = PracticeSession {
+            tempo_bpm: 300,
+            click_enabled: false,
+            ..Default::default()
+        };
+        let mut engine = PracticeEngine::new(session);
+        let sink = MemorySink::new();
+        engine.playback.set_sink(Some(sink.clone()));
+        engine.generate().unwrap();
+        engine.start().unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while engine.playback().current_loop().is_none() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert!(engine.playback().current_loop().is_some());
+        let sent = sink.len();
+
+        engine.next_pattern().unwrap();
+        engine.start().unwrap();
+        assert!(!engine.playback().is_pattern_queued());
+        thread::sleep(Duration::from_millis(400));
+
+        // The click is off, so these clicks are the new pattern's count-in
+        assert!(sink.messages()[sent..]
+            .iter()
+            .any(|message| message[0] == 0x99 && message[1] == CLICK_NOTE));
+        engine.stop();
+    }
+
+    #[test]
+    fn test_auto_advance_moves_on_after_its_loops() {
+        let session = PracticeSession {
+            tempo_bpm: 300,
+            auto_advance: true,
+            advance_loops: 1,
+            reveal_on_advance: true,
+            ..Default::default()
+        };
+        let mut engine = PracticeEngine::new(session);
+        engine.playback.set_sink(Some(MemorySink::new()));
+        let first = engine.generate().unwrap().clone();
+        engine.start().unwrap();
+        assert!(engine.poll().is_empty());
+
+        // 300 BPM: a 0.8s count-in, then one 0.8s loop
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let notices = loop {
+            let notices = engine.poll();
+            if !notices.is_empty() || Instant::now() > deadline {
+                break notices;
+            }
+            thread::sleep(Duration::from_millis(20));
+        };
+        match &notices[..] {
+            [Notice::Advanced { revealed, next }] => {
+                assert_eq!(revealed.as_ref().map(|p| p.id), Some(first.id));
+                assert_eq!(
+                    engine.current_pattern().map(|p| p.id),
+                    Some(next.pattern.id)
+                );
+            }
+            other => panic!("expected an advance, got {:?}", other),
+        }
+        assert_eq!(engine.session().patterns_generated, 2);
+        assert!(engine.is_playing());
+
+        engine.set_auto_advance(false).unwrap();
+        assert_eq!(engine.session().loop_limit(), None);
+        engine.session.drill = Some("4loops/20s".parse().unwrap());
+        assert!(engine.set_auto_advance(true).is_err());
+        engine.stop();
+    }
+
+    #[test]
+    fn test_arrangement_announces_each_section() {
+        let session = PracticeSession {
+            tempo_bpm: 300,
+            ..Default::default()
+        };
+        let mut engine = PracticeEngine::new(session);
+        engine.playback.set_sink(Some(MemorySink::new()));
+        let current = engine.generate().unwrap().id;
+        engine.session.annotate("#bridge".parse().unwrap()).unwrap();
+
+        let missing: ArrangementSpec = "AB A=1 B=#chorus".parse().unwrap();
+        assert!(engine.start_arrangement(&missing).is_err());
+        let spec: ArrangementSpec = "A B2 A A=1 B=#bridge".parse().unwrap();
+        assert_eq!(engine.start_arrangement(&spec).unwrap().form_label(), "ABA");
+        assert!(engine.is_arrangement_playing());
+
+        // 300 BPM: a 0.8s count-in, then four 0.8s loops
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut notices = Vec::new();
+        while engine.is_arrangement_playing() && Instant::now() < deadline {
+            notices.extend(engine.poll());
+            thread::sleep(Duration::from_millis(20));
+        }
+        let sections: Vec<(char, u32, usize)> = notices
+            .iter()
+            .filter_map(|notice| match notice {
+                Notice::Section {
+                    label,
+                    loops,
+                    index,
+                    ..
+                } => Some((*label, *loops, *index)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(sections, vec![('A', 1, 1), ('B', 2, 2), ('A', 1, 3)]);
+        assert!(matches!(notices.last(), Some(Notice::ArrangementFinished)));
+        assert!(!engine.is_playing());
+        assert_eq!(engine.current_pattern().map(|p| p.id), Some(current));
+    }
+
+    #[test]
+    fn test_medley_plays_each_pattern_once() {
+        let session = PracticeSession {
+            tempo_bpm: 300,
+            ..Default::default()
+        };
+        let mut engine = PracticeEngine::new(session);
+        engine.playback.set_sink(Some(MemorySink::new()));
+        assert!(engine.start_medley().is_err());
+        engine.generate().unwrap();
+        let current = engine.generate().unwrap().id;
+
+        assert_eq!(engine.start_medley().unwrap().len(), 2);
+        assert!(engine.is_medley_playing());
+
+        // 300 BPM: a 0.8s count-in, then two 0.8s patterns a 0.8s bar apart
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut notices = Vec::new();
+        while engine.is_medley_playing() && Instant::now() < deadline {
+            notices.extend(engine.poll());
+            thread::sleep(Duration::from_millis(20));
+        }
+        let parts: Vec<(u32, usize)> = notices
+            .iter()
+            .filter_map(|notice| match notice {
+                Notice::MedleyPart { number, index, .. } => Some((*number, *index)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(parts, vec![(1, 1), (2, 2)]);
+        assert!(matches!(notices.last(), Some(Notice::MedleyFinished)));
+        assert!(!engine.is_playing());
+        assert_eq!(engine.current_pattern().map(|p| p.id), Some(current));
+    }
+}
@@ -0,0 +1,331 @@
+use crate::error::KickbeatsError;
+use crate::generator::{is_pattern_unique, PatternGenerator};
+use crate::models::{ComplexityLevel, Pattern, TimeSignature};
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+/// A pattern generator shared between a session and its [`Prefetcher`]
+pub type SharedGenerator = Arc<Mutex<Box<dyn PatternGenerator>>>;
+
+/// Lock a shared generator, carrying on if a holder panicked
+pub fn lock_generator(generator: &SharedGenerator) -> MutexGuard<'_, Box<dyn PatternGenerator>> {
+    generator.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A pattern generated ahead of time, or on demand when none was waiting
+#[derive(Debug, Clone)]
+pub struct Prefetched {
+    /// The pattern
+    pub pattern: Pattern,
+    /// Least distance from recent patterns it was generated to
+    pub distance: u32,
+    /// Where it came from, for generators that mix sources (e.g., "euclidean")
+    pub source: Option<String>,
+}
+
+impl Prefetched {
+    /// Generate a pattern with `generator` straight away
+    pub fn generate(
+        generator: &mut dyn PatternGenerator,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Result<Self, KickbeatsError> {
+        let (pattern, distance) = generator.generate_unique(time_signature, complexity, history)?;
+        Ok(Self {
+            pattern,
+            distance,
+            source: generator.last_source().map(String::from),
+        })
+    }
+}
+
+/// What the worker generates for, and what it has ready
+struct Queue {
+    /// Time signature patterns are generated in
+    time_signature: TimeSignature,
+    /// Complexity patterns are generated at
+    complexity: ComplexityLevel,
+    /// Recent patterns the queued ones must differ from
+    history: VecDeque<Pattern>,
+    /// Patterns ready to hand out, oldest first
+    ready: VecDeque<Prefetched>,
+    /// Bumped when the settings change, so a pattern generated for the old
+    /// ones is thrown out
+    epoch: u64,
+    /// Whether the generator failed at these settings and history; the
+    /// worker waits for new ones rather than retrying
+    stalled: bool,
+    /// Cleared to stop the worker
+    running: bool,
+}
+
+/// Keeps a few validated, unique patterns generated ahead of time, so moving
+/// on to the next pattern never waits on the generator
+///
+/// A background thread fills the queue up to its depth with the session's
+/// own generator, each pattern unique against the recent history and the
+/// patterns queued before it. Changing the time signature or complexity
+/// (see [`refill`](Self::refill)) throws out what was queued.
+pub struct Prefetcher {
+    /// Queue, shared with the worker, and the signal that wakes it
+    shared: Arc<(Mutex<Queue>, Condvar)>,
+    /// Worker thread
+    handle: Option<thread::JoinHandle<()>>,
+    /// Patterns kept ready
+    depth: usize,
+}
+
+impl Prefetcher {
+    /// Start keeping `depth` patterns ready from `generator`, in
+    /// `time_signature` at `complexity` and unique against `history`
+    pub fn start(
+        generator: SharedGenerator,
+        depth: usize,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: VecDeque<Pattern>,
+    ) -> Self {
+        let shared = Arc::new((
+            Mutex::new(Queue {
+                time_signature,
+                complexity,
+                history,
+                ready: VecDeque::new(),
+                epoch: 0,
+                stalled: false,
+                running: true,
+            }),
+            Condvar::new(),
+        ));
+        let handle = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || fill(&shared, &generator, depth))
+        };
+        Self {
+            shared,
+            handle: Some(handle),
+            depth,
+        }
+    }
+
+    /// Patterns kept ready
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Number of patterns ready
+    pub fn len(&self) -> usize {
+        self.lock().ready.len()
+    }
+
+    /// Whether no pattern is ready
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Hand out the oldest ready pattern for these settings that's still
+    /// unique against `history`
+    ///
+    /// `None` when nothing fits, e.g. just after the settings changed; the
+    /// caller generates one itself then.
+    pub fn take(
+        &self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: &VecDeque<Pattern>,
+    ) -> Option<Prefetched> {
+        let mut queue = self.lock();
+        if (queue.time_signature, queue.complexity) != (time_signature, complexity) {
+            return None;
+        }
+        // Patterns added to the history since (e.g., a review) may be too close
+        let next = std::iter::from_fn(|| queue.ready.pop_front())
+            .find(|next| is_pattern_unique(&next.pattern, history, next.distance));
+        self.shared.1.notify_all();
+        next
+    }
+
+    /// Generate for these settings and recent patterns from now on, throwing
+    /// out the queue if the settings changed, and topping it back up
+    pub fn refill(
+        &self,
+        time_signature: TimeSignature,
+        complexity: ComplexityLevel,
+        history: VecDeque<Pattern>,
+    ) {
+        let mut queue = self.lock();
+        if (queue.time_signature, queue.complexity) != (time_signature, complexity) {
+            queue.time_signature = time_signature;
+            queue.complexity = complexity;
+            queue.ready.clear();
+            queue.epoch += 1;
+        }
+        // A new history may leave room for patterns the old one didn't
+        queue.history = history;
+        queue.stalled = false;
+        self.shared.1.notify_all();
+    }
+
+    /// Lock the queue, carrying on if the worker panicked
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.shared.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Drop for Prefetcher {
+    /// Stop the worker, waiting for any pattern it's generating
+    fn drop(&mut self) {
+        self.lock().running = false;
+        self.shared.1.notify_all();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Worker loop: generate whenever the queue is short of `depth`
+fn fill(shared: &(Mutex<Queue>, Condvar), generator: &SharedGenerator, depth: usize) {
+    let (lock, wake) = shared;
+    loop {
+        let (time_signature, complexity, history, epoch) = {
+            let mut queue = lock.lock().unwrap_or_else(PoisonError::into_inner);
+            while queue.running && (queue.stalled || queue.ready.len() >= depth) {
+                queue = wake.wait(queue).unwrap_or_else(PoisonError::into_inner);
+            }
+            if !queue.running {
+                return;
+            }
+            // Queued patterns must differ from each other too
+            let mut history = queue.history.clone();
+            history.extend(queue.ready.iter().map(|next| next.pattern.clone()));
+            (queue.time_signature, queue.complexity, history, queue.epoch)
+        };
+
+        let generated = Prefetched::generate(
+            lock_generator(generator).as_mut(),
+            time_signature,
+            complexity,
+            &history,
+        );
+
+        let mut queue = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        if queue.epoch != epoch {
+            continue;
+        }
+        match generated {
+            Ok(next) => queue.ready.push_back(next),
+            // Surfaced when the session generates the pattern itself
+            Err(_) => queue.stalled = true,
+        }
+        wake.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::WeightedGenerator;
+    use crate::models::ComplexityParams;
+    use std::time::{Duration, Instant};
+
+    fn shared(generator: impl PatternGenerator + 'static) -> SharedGenerator {
+        Arc::new(Mutex::new(Box::new(generator)))
+    }
+
+    /// Wait for the worker to have `len` patterns ready
+    fn wait_for(prefetcher: &Prefetcher, len: usize) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while prefetcher.len() < len {
+            assert!(Instant::now() < deadline, "only {} ready", prefetcher.len());
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_keeps_unique_patterns_ready() {
+        let prefetcher = Prefetcher::start(
+            shared(WeightedGenerator::new()),
+            3,
+            TimeSignature::four_four(),
+            ComplexityLevel::Complex,
+            VecDeque::new(),
+        );
+        wait_for(&prefetcher, 3);
+
+        let mut history = VecDeque::new();
+        for _ in 0..3 {
+            let next = prefetcher
+                .take(
+                    TimeSignature::four_four(),
+                    ComplexityLevel::Complex,
+                    &history,
+                )
+                .unwrap();
+            assert!(next.pattern.validate_steps().is_ok());
+            assert!(is_pattern_unique(&next.pattern, &history, next.distance));
+            history.push_back(next.pattern);
+        }
+        // Taking wakes the worker to top the queue back up
+        prefetcher.refill(
+            TimeSignature::four_four(),
+            ComplexityLevel::Complex,
+            history,
+        );
+        wait_for(&prefetcher, 3);
+    }
+
+    #[test]
+    fn test_new_settings_throw_out_the_queue() {
+        let prefetcher = Prefetcher::start(
+            shared(WeightedGenerator::new()),
+            2,
+            TimeSignature::four_four(),
+            ComplexityLevel::Simple,
+            VecDeque::new(),
+        );
+        wait_for(&prefetcher, 2);
+        assert!(prefetcher
+            .take(
+                TimeSignature::three_four(),
+                ComplexityLevel::Simple,
+                &VecDeque::new(),
+            )
+            .is_none());
+
+        prefetcher.refill(
+            TimeSignature::three_four(),
+            ComplexityLevel::Simple,
+            VecDeque::new(),
+        );
+        wait_for(&prefetcher, 2);
+        let next = prefetcher
+            .take(
+                TimeSignature::three_four(),
+                ComplexityLevel::Simple,
+                &VecDeque::new(),
+            )
+            .unwrap();
+        assert_eq!(next.pattern.time_signature, TimeSignature::three_four());
+    }
+
+    #[test]
+    fn test_stops_when_the_generator_fails() {
+        // Nine kicks can't fit in 16 steps with none adjacent
+        let params = ComplexityParams::new(9, 9, 0.5, 1).unwrap();
+        let prefetcher = Prefetcher::start(
+            shared(WeightedGenerator::new().with_params(params)),
+            3,
+            TimeSignature::four_four(),
+            ComplexityLevel::Medium,
+            VecDeque::new(),
+        );
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !prefetcher.lock().stalled {
+            assert!(Instant::now() < deadline);
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(prefetcher.is_empty());
+    }
+}
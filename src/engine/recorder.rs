@@ -0,0 +1,294 @@
+use crate::error::KickbeatsError;
+use crate::models::TimeSignature;
+use crate::storage::write_atomic;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Ticks per quarter note in written MIDI files
+const TICKS_PER_QUARTER: u16 = 480;
+
+/// Tempo assumed before the first tempo event (the SMF default)
+const DEFAULT_TEMPO_BPM: u16 = 120;
+
+/// One event that was actually sent, or a tempo or meter change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Recorded {
+    NoteOn { channel: u8, note: u8, velocity: u8 },
+    NoteOff { channel: u8, note: u8 },
+    Tempo(u16),
+    Meter(TimeSignature),
+}
+
+/// Log of every MIDI message emitted during a session, for writing to a
+/// Standard MIDI File
+///
+/// Clones share the same log, so one recorder can be handed to each
+/// playback run. Events are timestamped on arrival relative to when the
+/// recorder was created, so silences between patterns are kept.
+#[derive(Debug, Clone)]
+pub struct SessionRecorder {
+    /// When the session (and the file's time zero) began
+    start: Instant,
+    /// Events with their offsets from `start`
+    events: Arc<Mutex<Vec<(Duration, Recorded)>>>,
+}
+
+impl SessionRecorder {
+    /// Create an empty recorder starting now
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            events: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Log a note-on that was just sent
+    pub fn note_on(&self, channel: u8, note: u8, velocity: u8) {
+        self.record(Recorded::NoteOn {
+            channel,
+            note,
+            velocity,
+        });
+    }
+
+    /// Log a note-off that was just sent
+    pub fn note_off(&self, channel: u8, note: u8) {
+        self.record(Recorded::NoteOff { channel, note });
+    }
+
+    /// Log the tempo playback is running at from now on
+    pub fn tempo(&self, tempo_bpm: u16) {
+        self.record(Recorded::Tempo(tempo_bpm));
+    }
+
+    /// Log the meter of the measure starting now
+    pub fn time_signature(&self, time_signature: TimeSignature) {
+        self.record(Recorded::Meter(time_signature));
+    }
+
+    /// Log the meter of a measure scheduled to start at `at`
+    pub fn time_signature_at(&self, at: Instant, time_signature: TimeSignature) {
+        self.record_at(
+            at.saturating_duration_since(self.start),
+            Recorded::Meter(time_signature),
+        );
+    }
+
+    /// Number of events logged so far
+    pub fn len(&self) -> usize {
+        self.events.lock().map(|events| events.len()).unwrap_or(0)
+    }
+
+    /// Whether nothing has been logged yet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn record(&self, event: Recorded) {
+        self.record_at(self.start.elapsed(), event);
+    }
+
+    fn record_at(&self, at: Duration, event: Recorded) {
+        if let Ok(mut events) = self.events.lock() {
+            events.push((at, event));
+        }
+    }
+
+    /// Encode the log as a format 0 Standard MIDI File
+    ///
+    /// Wall-clock offsets are converted to ticks through the logged tempo
+    /// changes, so the file plays back with the session's real timing.
+    pub fn to_smf(&self) -> Vec<u8> {
+        let mut events = self
+            .events
+            .lock()
+            .map(|events| events.clone())
+            .unwrap_or_default();
+        events.sort_by_key(|&(at, _)| at);
+
+        let mut track = Vec::new();
+        write_meta(&mut track, 0, 0x03, b"kickbeats session");
+
+        // Ticks advance at the tempo in force since the last tempo change
+        let mut tempo_bpm = DEFAULT_TEMPO_BPM;
+        let mut tempo_at = Duration::ZERO;
+        let mut tempo_tick = 0.0;
+        let mut last_tick = 0u64;
+
+        for (at, event) in events {
+            let elapsed = at.saturating_sub(tempo_at).as_secs_f64();
+            let tick_f =
+                tempo_tick + elapsed * f64::from(tempo_bpm) / 60.0 * f64::from(TICKS_PER_QUARTER);
+            let tick = (tick_f.round() as u64).max(last_tick);
+            let delta = (tick - last_tick) as u32;
+            last_tick = tick;
+
+            match event {
+                Recorded::NoteOn {
+                    channel,
+                    note,
+                    velocity,
+                } => {
+                    write_varlen(&mut track, delta);
+                    track.extend_from_slice(&[0x90 | channel, note, velocity]);
+                }
+                Recorded::NoteOff { channel, note } => {
+                    write_varlen(&mut track, delta);
+                    track.extend_from_slice(&[0x80 | channel, note, 0]);
+                }
+                Recorded::Tempo(bpm) => {
+                    let micros = 60_000_000 / u32::from(bpm.max(1));
+                    write_meta(&mut track, delta, 0x51, &micros.to_be_bytes()[1..]);
+                    tempo_bpm = bpm.max(1);
+                    tempo_at = at;
+                    tempo_tick = tick_f;
+                }
+                Recorded::Meter(meter) => {
+                    // nn, log2(dd), clocks per click, 32nds per quarter
+                    let dd = meter.denominator.max(1).trailing_zeros() as u8;
+                    write_meta(&mut track, delta, 0x58, &[meter.numerator, dd, 24, 8]);
+                }
+            }
+        }
+        write_meta(&mut track, 0, 0x2F, &[]);
+
+        let mut smf = Vec::with_capacity(track.len() + 22);
+        smf.extend_from_slice(b"MThd");
+        smf.extend_from_slice(&6u32.to_be_bytes());
+        smf.extend_from_slice(&0u16.to_be_bytes()); // format 0
+        smf.extend_from_slice(&1u16.to_be_bytes()); // one track
+        smf.extend_from_slice(&TICKS_PER_QUARTER.to_be_bytes());
+        smf.extend_from_slice(b"MTrk");
+        smf.extend_from_slice(&(track.len() as u32).to_be_bytes());
+        smf.extend_from_slice(&track);
+        smf
+    }
+
+    /// Write the log to a Standard MIDI File
+    pub fn write_smf(&self, path: &Path) -> Result<(), KickbeatsError> {
+        write_atomic(path, self.to_smf()).map_err(|e| KickbeatsError::io("write MIDI log", path, e))
+    }
+}
+
+impl Default for SessionRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Append a variable-length quantity (7 bits per byte, high bit = more)
+fn write_varlen(out: &mut Vec<u8>, mut value: u32) {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    value >>= 7;
+    while value > 0 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.extend(bytes.iter().rev());
+}
+
+/// Append a meta event (`FF type len data`)
+fn write_meta(out: &mut Vec<u8>, delta: u32, kind: u8, data: &[u8]) {
+    write_varlen(out, delta);
+    out.extend_from_slice(&[0xFF, kind]);
+    write_varlen(out, data.len() as u32);
+    out.extend_from_slice(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_varlen() {
+        let encode = |value| {
+            let mut out = Vec::new();
+            write_varlen(&mut out, value);
+            out
+        };
+        assert_eq!(encode(0), vec![0x00]);
+        assert_eq!(encode(0x7F), vec![0x7F]);
+        assert_eq!(encode(0x80), vec![0x81, 0x00]);
+        assert_eq!(encode(480), vec![0x83, 0x60]);
+    }
+
+    #[test]
+    fn test_smf_layout_and_tempo_timing() {
+        let recorder = SessionRecorder::new();
+        recorder.record_at(Duration::ZERO, Recorded::Tempo(120));
+        recorder.record_at(
+            Duration::ZERO,
+            Recorded::NoteOn {
+                channel: 9,
+                note: 36,
+                velocity: 100,
+            },
+        );
+        // One quarter note later at 120 BPM
+        recorder.record_at(
+            Duration::from_millis(500),
+            Recorded::NoteOff {
+                channel: 9,
+                note: 36,
+            },
+        );
+        // Switch to 60 BPM: another half second is only half a quarter
+        recorder.record_at(Duration::from_millis(500), Recorded::Tempo(60));
+        recorder.record_at(
+            Duration::from_millis(1000),
+            Recorded::NoteOn {
+                channel: 9,
+                note: 37,
+                velocity: 80,
+            },
+        );
+        assert_eq!(recorder.len(), 5);
+
+        let smf = recorder.to_smf();
+        assert_eq!(&smf[0..4], b"MThd");
+        assert_eq!(&smf[8..14], &[0, 0, 0, 1, 0x01, 0xE0]);
+        assert_eq!(&smf[14..18], b"MTrk");
+        let track_len = u32::from_be_bytes(smf[18..22].try_into().unwrap()) as usize;
+        assert_eq!(smf.len(), 22 + track_len);
+
+        let track = &smf[22..];
+        let name_len = 4 + b"kickbeats session".len();
+        let events = &track[name_len..];
+        assert_eq!(
+            events,
+            &[
+                0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20, // tempo 120
+                0x00, 0x99, 36, 100, // kick on
+                0x83, 0x60, 0x89, 36, 0, // 480 ticks later: kick off
+                0x00, 0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40, // tempo 60
+                0x81, 0x70, 0x99, 37, 80, // 240 ticks later: click on
+                0x00, 0xFF, 0x2F, 0x00, // end of track
+            ][..]
+        );
+    }
+
+    #[test]
+    fn test_smf_meter_changes() {
+        let recorder = SessionRecorder::new();
+        recorder.record_at(Duration::ZERO, Recorded::Tempo(120));
+        recorder.record_at(Duration::ZERO, Recorded::Meter(TimeSignature::four_four()));
+        // Two seconds is one 4/4 bar at 120 BPM
+        recorder.time_signature_at(
+            recorder.start + Duration::from_secs(2),
+            TimeSignature::six_eight(),
+        );
+
+        let smf = recorder.to_smf();
+        let name_len = 4 + b"kickbeats session".len();
+        let events = &smf[22 + name_len..];
+        assert_eq!(
+            &events[7..],
+            &[
+                0x00, 0xFF, 0x58, 0x04, 4, 2, 24, 8, // 4/4
+                0x8F, 0x00, 0xFF, 0x58, 0x04, 6, 3, 24, 8, // 1920 ticks later: 6/8
+                0x00, 0xFF, 0x2F, 0x00, // end of track
+            ][..]
+        );
+    }
+}
@@ -0,0 +1,389 @@
+use crate::error::KickbeatsError;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long before a deadline the scheduler stops sleeping and spins
+///
+/// OS sleeps routinely overshoot by up to a millisecond; spinning through the
+/// last stretch keeps dispatch well under that.
+pub const SPIN_MARGIN: Duration = Duration::from_micros(1500);
+
+/// Events the playback ring holds (one dense loop with clock ticks is a few hundred)
+pub const RING_CAPACITY: usize = 2048;
+
+/// Source of time for the scheduler, so tests can run on a mock clock
+pub trait Clock {
+    /// The current instant
+    fn now(&self) -> Instant;
+
+    /// Block for about `duration` (the OS may overshoot)
+    fn sleep(&self, duration: Duration);
+
+    /// Busy-wait briefly before checking the time again
+    fn spin(&self) {
+        std::hint::spin_loop();
+    }
+}
+
+/// The wall clock, sleeping the thread
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+
+    fn spin(&self) {
+        // Give up the core without sleeping so other threads aren't starved
+        thread::yield_now();
+    }
+}
+
+/// How late dispatched events were
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct JitterStats {
+    /// Events dispatched
+    pub events: u64,
+    /// Latest any event went out
+    pub max: Duration,
+    /// Lateness summed over all events
+    total: Duration,
+}
+
+impl JitterStats {
+    /// Count one event that went out `lateness` after its deadline
+    pub fn record(&mut self, lateness: Duration) {
+        self.events += 1;
+        self.max = self.max.max(lateness);
+        self.total += lateness;
+    }
+
+    /// Average lateness, or zero before any events
+    pub fn mean(&self) -> Duration {
+        match u32::try_from(self.events) {
+            Ok(0) => Duration::ZERO,
+            Ok(events) => self.total / events,
+            Err(_) => Duration::from_secs_f64(self.total.as_secs_f64() / self.events as f64),
+        }
+    }
+}
+
+/// Fixed-capacity ring buffer of pre-rendered events with absolute deadlines
+///
+/// The slots are allocated once and reused, so queueing and dispatching
+/// events don't allocate. This keeps the wait loop cheap; it doesn't make
+/// playback real-time safe, since the playback thread still builds each
+/// loop's events and shares state with the interface through locks.
+#[derive(Debug)]
+pub struct EventRing<T> {
+    slots: Vec<Option<(Instant, T)>>,
+    head: usize,
+    len: usize,
+}
+
+impl<T> EventRing<T> {
+    /// Create an empty ring holding up to `capacity` events
+    pub fn new(capacity: usize) -> Self {
+        let mut slots = Vec::with_capacity(capacity.max(1));
+        slots.resize_with(capacity.max(1), || None);
+        Self {
+            slots,
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Queue an event behind those already waiting; hands it back when full
+    pub fn push(&mut self, deadline: Instant, event: T) -> Result<(), (Instant, T)> {
+        if self.len == self.slots.len() {
+            return Err((deadline, event));
+        }
+        let tail = (self.head + self.len) % self.slots.len();
+        self.slots[tail] = Some((deadline, event));
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Take the oldest queued event
+    pub fn pop(&mut self) -> Option<(Instant, T)> {
+        if self.len == 0 {
+            return None;
+        }
+        let slot = self.slots[self.head].take();
+        self.head = (self.head + 1) % self.slots.len();
+        self.len -= 1;
+        slot
+    }
+
+    /// Deadline of the oldest queued event
+    pub fn peek_deadline(&self) -> Option<Instant> {
+        if self.len == 0 {
+            return None;
+        }
+        self.slots[self.head]
+            .as_ref()
+            .map(|&(deadline, _)| deadline)
+    }
+
+    /// Drop every queued event
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Number of queued events
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether nothing is queued
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Most events the ring can hold
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+}
+
+/// Dispatches queued events at their deadlines with a sleep-then-spin wait
+///
+/// Each wait sleeps until [`SPIN_MARGIN`] before the deadline, then spins the
+/// rest of the way, and records how late the event actually went out.
+#[derive(Debug)]
+pub struct Scheduler<T, C: Clock = SystemClock> {
+    clock: C,
+    queue: EventRing<T>,
+    jitter: JitterStats,
+}
+
+impl<T> Scheduler<T> {
+    /// Scheduler on the wall clock with a [`RING_CAPACITY`] event ring
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock, RING_CAPACITY)
+    }
+}
+
+impl<T> Default for Scheduler<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, C: Clock> Scheduler<T, C> {
+    /// Scheduler on `clock` with room for `capacity` queued events
+    pub fn with_clock(clock: C, capacity: usize) -> Self {
+        Self {
+            clock,
+            queue: EventRing::new(capacity),
+            jitter: JitterStats::default(),
+        }
+    }
+
+    /// The scheduler's clock
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Queue an event for `deadline`; events must be queued in time order
+    pub fn schedule(&mut self, deadline: Instant, event: T) -> Result<(), KickbeatsError> {
+        self.queue.push(deadline, event).map_err(|_| {
+            KickbeatsError::Playback(format!(
+                "Scheduler queue full ({} events)",
+                self.queue.capacity()
+            ))
+        })
+    }
+
+    /// Queue events from `events` until the ring is full or they run out, so a
+    /// loop longer than the ring plays out as it goes instead of overflowing
+    pub fn refill(&mut self, events: &mut impl Iterator<Item = (Instant, T)>) {
+        while self.queue.len() < self.queue.capacity() {
+            let Some((deadline, event)) = events.next() else {
+                break;
+            };
+            if self.queue.push(deadline, event).is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Wait for the next queued event's deadline and hand it over
+    pub fn next_due(&mut self) -> Option<T> {
+        let deadline = self.queue.peek_deadline()?;
+        let lateness = self.wait_until(deadline);
+        self.jitter.record(lateness);
+        self.queue.pop().map(|(_, event)| event)
+    }
+
+    /// Block until `deadline`, returning how late the wait ended
+    pub fn wait_until(&self, deadline: Instant) -> Duration {
+        loop {
+            let now = self.clock.now();
+            if now >= deadline {
+                return now - deadline;
+            }
+            let remaining = deadline - now;
+            if remaining > SPIN_MARGIN {
+                self.clock.sleep(remaining - SPIN_MARGIN);
+            } else {
+                self.clock.spin();
+            }
+        }
+    }
+
+    /// Drop every queued event (e.g., when playback stops mid-loop)
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Number of events still waiting
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Lateness of events dispatched since the last `take_jitter`
+    pub fn jitter(&self) -> JitterStats {
+        self.jitter
+    }
+
+    /// Return the jitter gathered so far and start measuring afresh
+    pub fn take_jitter(&mut self) -> JitterStats {
+        std::mem::take(&mut self.jitter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Clock that advances only when slept or spun, overshooting every sleep
+    struct MockClock {
+        now: Cell<Instant>,
+        oversleep: Duration,
+        spin_step: Duration,
+        sleeps: Cell<u32>,
+    }
+
+    impl MockClock {
+        fn new(oversleep: Duration) -> Self {
+            Self {
+                now: Cell::new(Instant::now()),
+                oversleep,
+                spin_step: Duration::from_micros(20),
+                sleeps: Cell::new(0),
+            }
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            self.now.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.sleeps.set(self.sleeps.get() + 1);
+            self.now.set(self.now.get() + duration + self.oversleep);
+        }
+
+        fn spin(&self) {
+            self.now.set(self.now.get() + self.spin_step);
+        }
+    }
+
+    #[test]
+    fn test_ring_wraps_and_reports_full() {
+        let start = Instant::now();
+        let mut ring = EventRing::new(3);
+        for i in 0..3 {
+            ring.push(start + Duration::from_millis(i), i).unwrap();
+        }
+        assert_eq!(ring.push(start, 3), Err((start, 3)));
+        assert_eq!(ring.pop().map(|(_, e)| e), Some(0));
+
+        // The freed slot is reused at the front of the buffer
+        ring.push(start + Duration::from_millis(9), 3).unwrap();
+        let drained: Vec<u64> = std::iter::from_fn(|| ring.pop().map(|(_, e)| e)).collect();
+        assert_eq!(drained, vec![1, 2, 3]);
+        assert!(ring.is_empty());
+        assert_eq!(ring.peek_deadline(), None);
+    }
+
+    #[test]
+    fn test_hybrid_wait_absorbs_oversleep() {
+        // The OS oversleeps every sleep by a millisecond, just inside the spin margin
+        let clock = MockClock::new(Duration::from_millis(1));
+        let start = clock.now();
+        let mut scheduler = Scheduler::with_clock(clock, 64);
+
+        // A dense 300 BPM loop: a sixteenth every 50ms
+        for i in 0..32u32 {
+            scheduler
+                .schedule(start + Duration::from_millis(50) * i, i)
+                .unwrap();
+        }
+        let dispatched: Vec<u32> = std::iter::from_fn(|| scheduler.next_due()).collect();
+        assert_eq!(dispatched, (0..32).collect::<Vec<_>>());
+
+        let jitter = scheduler.take_jitter();
+        assert_eq!(jitter.events, 32);
+        assert!(
+            jitter.max < Duration::from_micros(100),
+            "max {:?}",
+            jitter.max
+        );
+        assert!(jitter.mean() <= jitter.max);
+        assert_eq!(scheduler.jitter(), JitterStats::default());
+
+        // One sleep per event (none for the first, already due); spins cover the rest
+        assert_eq!(scheduler.clock().sleeps.get(), 31);
+    }
+
+    #[test]
+    fn test_overdue_events_go_out_immediately() {
+        let clock = MockClock::new(Duration::ZERO);
+        let start = clock.now();
+        clock.now.set(start + Duration::from_millis(5));
+        let mut scheduler = Scheduler::with_clock(clock, 4);
+
+        scheduler.schedule(start, "late").unwrap();
+        assert_eq!(scheduler.next_due(), Some("late"));
+        assert_eq!(scheduler.jitter().max, Duration::from_millis(5));
+        assert_eq!(scheduler.clock().sleeps.get(), 0);
+        assert_eq!(scheduler.next_due(), None);
+    }
+
+    #[test]
+    fn test_schedule_rejects_overflow() {
+        let mut scheduler = Scheduler::with_clock(MockClock::new(Duration::ZERO), 1);
+        let now = scheduler.clock().now();
+        scheduler.schedule(now, 1).unwrap();
+        assert!(scheduler.schedule(now, 2).is_err());
+        scheduler.clear();
+        assert_eq!(scheduler.pending(), 0);
+    }
+
+    #[test]
+    fn test_refill_plays_out_more_events_than_fit() {
+        let mut scheduler = Scheduler::with_clock(MockClock::new(Duration::ZERO), 4);
+        let start = scheduler.clock().now();
+        let mut events = (0..10u64).map(|i| (start + Duration::from_millis(i), i));
+
+        let mut played = Vec::new();
+        loop {
+            scheduler.refill(&mut events);
+            assert!(scheduler.pending() <= 4);
+            let Some(event) = scheduler.next_due() else {
+                break;
+            };
+            played.push(event);
+        }
+        assert_eq!(played, (0..10).collect::<Vec<_>>());
+    }
+}
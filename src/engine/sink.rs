@@ -0,0 +1,138 @@
+use crate::engine::midi::MidiError;
+use crate::error::KickbeatsError;
+use midir::MidiOutputConnection;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Destination for raw MIDI messages
+///
+/// Implemented by the midir output connection and by [`MemorySink`], so
+/// playback can be exercised without a synth or virtual port.
+pub trait MidiSink: Send {
+    /// Send one complete message (status byte first)
+    fn send(&mut self, message: &[u8]) -> Result<(), KickbeatsError>;
+}
+
+impl MidiSink for MidiOutputConnection {
+    fn send(&mut self, message: &[u8]) -> Result<(), KickbeatsError> {
+        MidiOutputConnection::send(self, message)
+            .map_err(|e| MidiError::new(format!("Failed to send MIDI message: {}", e)).into())
+    }
+}
+
+/// One sink shared by every sender in the session
+///
+/// Playback and the drum pad each open their own connection to a named port;
+/// a virtual port has to be created once and written to by both.
+#[derive(Clone)]
+pub struct SharedSink {
+    sink: Arc<Mutex<Box<dyn MidiSink>>>,
+}
+
+impl SharedSink {
+    /// Share `sink` between clones of the returned handle
+    pub fn new(sink: Box<dyn MidiSink>) -> Self {
+        Self {
+            sink: Arc::new(Mutex::new(sink)),
+        }
+    }
+}
+
+impl MidiSink for SharedSink {
+    fn send(&mut self, message: &[u8]) -> Result<(), KickbeatsError> {
+        self.sink
+            .lock()
+            .map_err(|_| MidiError::new("MIDI output poisoned"))?
+            .send(message)
+    }
+}
+
+/// A message with the time it was sent
+type Timed = (Instant, Vec<u8>);
+
+/// In-memory sink that keeps every message with the time it was sent
+///
+/// Clones share the same log, so a test can hold one handle while playback
+/// sends through another.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    messages: Arc<Mutex<Vec<Timed>>>,
+}
+
+impl MemorySink {
+    /// Create an empty sink
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Messages sent so far, oldest first
+    pub fn messages(&self) -> Vec<Vec<u8>> {
+        self.timed()
+            .into_iter()
+            .map(|(_, message)| message)
+            .collect()
+    }
+
+    /// Messages sent so far with when each arrived
+    pub fn timed(&self) -> Vec<Timed> {
+        self.messages
+            .lock()
+            .map(|messages| messages.clone())
+            .unwrap_or_default()
+    }
+
+    /// Number of messages sent so far
+    pub fn len(&self) -> usize {
+        self.messages
+            .lock()
+            .map(|messages| messages.len())
+            .unwrap_or(0)
+    }
+
+    /// Whether nothing has been sent yet
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl MidiSink for MemorySink {
+    fn send(&mut self, message: &[u8]) -> Result<(), KickbeatsError> {
+        self.messages
+            .lock()
+            .map_err(|_| MidiError::new("MIDI log poisoned"))?
+            .push((Instant::now(), message.to_vec()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_sink_clones_share_the_log() {
+        let sink = MemorySink::new();
+        let mut sender = sink.clone();
+        sender.send(&[0x99, 36, 100]).unwrap();
+        sender.send(&[0xF8]).unwrap();
+
+        assert_eq!(sink.messages(), vec![vec![0x99, 36, 100], vec![0xF8]]);
+        assert_eq!(sink.len(), 2);
+        let timed = sink.timed();
+        assert!(timed[0].0 <= timed[1].0);
+    }
+
+    #[test]
+    fn test_shared_sink_clones_write_to_one_sink() {
+        let log = MemorySink::new();
+        let mut playback = SharedSink::new(Box::new(log.clone()));
+        let mut pad = playback.clone();
+        playback.send(&[0x99, 36, 100]).unwrap();
+        pad.send(&[0x99, 38, 100]).unwrap();
+
+        assert_eq!(
+            log.messages(),
+            vec![vec![0x99, 36, 100], vec![0x99, 38, 100]]
+        );
+    }
+}
@@ -0,0 +1,386 @@
+use crate::engine::stream::Shown;
+use crate::engine::{OverlayFrame, PlaybackProbe};
+use crate::error::KickbeatsError;
+use crate::models::Pattern;
+use serde::Serialize;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::{Message, WebSocket};
+
+/// How often the server checks whether playback has moved on
+const REFRESH: Duration = Duration::from_millis(10);
+
+/// Longest a connecting client may take over its handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Most handshakes in progress at once; later connections are turned away
+const MAX_HANDSHAKES: usize = 8;
+
+/// Most bytes queued for a client that isn't reading before it's dropped
+const MAX_BACKLOG: usize = 64 * 1024;
+
+/// One message to companion UIs, sent as a JSON object with a `type` field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SocketEvent {
+    /// The session as the stream overlay shows it: sent on connecting, and
+    /// whenever the pattern, tempo, meter, reveal, or playing changes
+    State(OverlayFrame),
+    /// Playback moved on to another step
+    Tick {
+        /// Number of the pattern in the session
+        pattern: u32,
+        /// Step now sounding, from 0
+        step: usize,
+        /// Beat of the bar now sounding, from 1
+        beat: usize,
+        /// Tempo now in effect
+        tempo: u16,
+    },
+}
+
+/// Turns successive looks at playback into the events that report what changed
+#[derive(Debug, Default)]
+struct Ticker {
+    /// Last state sent, without its step and beat
+    state: Option<OverlayFrame>,
+    /// Last step ticked
+    step: Option<usize>,
+}
+
+impl Ticker {
+    /// Events for `shown` at playback's current position, if anything changed
+    fn advance(
+        &mut self,
+        shown: &Shown,
+        playing: bool,
+        tempo: u16,
+        step: Option<usize>,
+    ) -> Vec<SocketEvent> {
+        let step = step.filter(|_| playing);
+        let frame = OverlayFrame::new(shown, playing, tempo, step);
+        let mut events = Vec::new();
+        let state = OverlayFrame {
+            step: None,
+            beat: None,
+            ..frame.clone()
+        };
+        if self.state.as_ref() != Some(&state) {
+            self.state = Some(state);
+            events.push(SocketEvent::State(frame.clone()));
+        }
+        if step != self.step {
+            self.step = step;
+            if let (Some(step), Some(beat)) = (frame.step, frame.beat) {
+                events.push(SocketEvent::Tick {
+                    pattern: frame.pattern,
+                    step,
+                    beat,
+                    tempo: frame.tempo,
+                });
+            }
+        }
+        events
+    }
+}
+
+/// Broadcasts the session to browser-based companion UIs over WebSocket: a
+/// visual metronome, say, or a teacher's dashboard mirroring the session
+///
+/// Every client gets a [`SocketEvent::State`] on connecting and whenever
+/// the session changes, and a [`SocketEvent::Tick`] for every step played.
+/// Messages from clients are read and ignored, and a client that falls too
+/// far behind is dropped. A background thread accepts clients (handshaking
+/// each on a thread of its own) and follows playback through a
+/// [`PlaybackProbe`]; the pattern
+/// shown is updated with [`show`](Self::show), as for a
+/// [`StreamOverlay`](crate::engine::StreamOverlay).
+pub struct SocketServer {
+    /// Pattern on show, shared with the server thread
+    shown: Arc<Mutex<Option<Shown>>>,
+    /// Cleared to stop the server thread
+    running: Arc<AtomicBool>,
+    /// Server thread
+    handle: Option<thread::JoinHandle<()>>,
+    /// Address the server listens on
+    addr: SocketAddr,
+}
+
+impl SocketServer {
+    /// Start listening on `addr` (e.g. "127.0.0.1:9001") for clients to
+    /// follow playback through `probe`
+    ///
+    /// Fails if the address can't be listened on; clients that fail or go
+    /// away later are dropped so they never interrupt practice.
+    pub fn start(addr: &str, probe: PlaybackProbe) -> Result<Self, KickbeatsError> {
+        let failed = |e: std::io::Error| {
+            KickbeatsError::Network(format!(
+                "Failed to start WebSocket server on {}: {}",
+                addr, e
+            ))
+        };
+        let listener = TcpListener::bind(addr)
+            .and_then(|listener| listener.set_nonblocking(true).map(|()| listener))
+            .map_err(failed)?;
+        let addr = listener.local_addr().map_err(failed)?;
+
+        let shown: Arc<Mutex<Option<Shown>>> = Arc::new(Mutex::new(None));
+        let running = Arc::new(AtomicBool::new(true));
+        let handle = {
+            let shown = Arc::clone(&shown);
+            let running = Arc::clone(&running);
+            thread::spawn(move || {
+                let mut clients: Vec<WebSocket<TcpStream>> = Vec::new();
+                let mut ticker = Ticker::default();
+                let (connected_tx, connected) = mpsc::channel();
+                let handshaking = Arc::new(AtomicUsize::new(0));
+                while running.load(Ordering::SeqCst) {
+                    let current = shown.lock().ok().and_then(|shown| shown.clone());
+                    let events = match &current {
+                        Some(current) => ticker.advance(
+                            current,
+                            probe.is_playing(),
+                            probe.current_tempo(),
+                            probe.current_step(),
+                        ),
+                        None => Vec::new(),
+                    };
+                    for event in &events {
+                        broadcast(&mut clients, event);
+                    }
+
+                    while let Ok((stream, _)) = listener.accept() {
+                        handshake(stream, &handshaking, &connected_tx);
+                    }
+                    while let Ok(mut client) = connected.try_recv() {
+                        let welcome = current.as_ref().map(|shown| {
+                            SocketEvent::State(OverlayFrame::new(
+                                shown,
+                                probe.is_playing(),
+                                probe.current_tempo(),
+                                ticker.step,
+                            ))
+                        });
+                        if welcome.is_none_or(|welcome| send(&mut client, &welcome)) {
+                            clients.push(client);
+                        }
+                    }
+                    clients.retain_mut(drain);
+                    thread::sleep(REFRESH);
+                }
+                for mut client in clients {
+                    let _ = client.close(None);
+                    let _ = client.flush();
+                }
+            })
+        };
+
+        Ok(Self {
+            shown,
+            running,
+            handle: Some(handle),
+            addr,
+        })
+    }
+
+    /// Address the server listens on, with the port picked if asked for port 0
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Show pattern number `number`, with its kicks if `revealed`, at
+    /// `tempo_bpm` while stopped
+    pub fn show(&self, number: u32, pattern: &Pattern, revealed: bool, tempo_bpm: u16) {
+        let Ok(mut shown) = self.shown.lock() else {
+            return;
+        };
+        if !shown
+            .as_ref()
+            .is_some_and(|shown| shown.shows(number, pattern, revealed, tempo_bpm))
+        {
+            *shown = Some(Shown {
+                number,
+                pattern: pattern.clone(),
+                revealed,
+                tempo_bpm,
+            });
+        }
+    }
+}
+
+impl Drop for SocketServer {
+    /// Stop the server thread, closing every client's connection
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Complete a connecting client's WebSocket handshake on a thread of its
+/// own, handing the client to `connected`, or give up on it
+///
+/// A slow client holds up only its own thread; past [`MAX_HANDSHAKES`] at
+/// once, new connections are closed straight away.
+fn handshake(
+    stream: TcpStream,
+    handshaking: &Arc<AtomicUsize>,
+    connected: &Sender<WebSocket<TcpStream>>,
+) {
+    if handshaking.fetch_add(1, Ordering::SeqCst) >= MAX_HANDSHAKES {
+        handshaking.fetch_sub(1, Ordering::SeqCst);
+        return;
+    }
+    let handshaking = Arc::clone(handshaking);
+    let connected = connected.clone();
+    thread::spawn(move || {
+        if let Some(client) = accept(stream) {
+            let _ = connected.send(client);
+        }
+        handshaking.fetch_sub(1, Ordering::SeqCst);
+    });
+}
+
+/// Accept `stream` as a WebSocket client that can't queue more than
+/// [`MAX_BACKLOG`] bytes
+fn accept(stream: TcpStream) -> Option<WebSocket<TcpStream>> {
+    stream.set_nonblocking(false).ok()?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).ok()?;
+    let config = WebSocketConfig {
+        write_buffer_size: 0,
+        max_write_buffer_size: MAX_BACKLOG,
+        ..WebSocketConfig::default()
+    };
+    let client = tungstenite::accept_with_config(stream, Some(config)).ok()?;
+    client.get_ref().set_nonblocking(true).ok()?;
+    Some(client)
+}
+
+/// Send `event` to every client, dropping any that have gone away
+fn broadcast(clients: &mut Vec<WebSocket<TcpStream>>, event: &SocketEvent) {
+    clients.retain_mut(|client| send(client, event));
+}
+
+/// Send `event` to `client`, returning whether it's still connected
+fn send(client: &mut WebSocket<TcpStream>, event: &SocketEvent) -> bool {
+    let Ok(json) = serde_json::to_string(event) else {
+        return true;
+    };
+    match client.send(Message::Text(json)) {
+        Ok(()) => true,
+        // Queued until the client catches up, up to MAX_BACKLOG
+        Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => true,
+        // Too far behind (WriteBufferFull), or gone
+        Err(_) => false,
+    }
+}
+
+/// Read and ignore whatever `client` sent (answering pings), returning
+/// whether it's still connected
+fn drain(client: &mut WebSocket<TcpStream>) -> bool {
+    loop {
+        match client.read() {
+            Ok(_) => continue,
+            Err(tungstenite::Error::Io(e)) if e.kind() == ErrorKind::WouldBlock => return true,
+            Err(_) => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::MidiPlaybackLoop;
+    use crate::models::ComplexityLevel;
+    use std::time::Instant;
+
+    fn shown(revealed: bool) -> Shown {
+        Shown {
+            number: 2,
+            pattern: Pattern::from_fingerprint("4/4:x...x.....x....x", ComplexityLevel::Medium)
+                .unwrap(),
+            revealed,
+            tempo_bpm: 100,
+        }
+    }
+
+    #[test]
+    fn test_ticks_each_step_and_sends_state_on_changes() {
+        let mut ticker = Ticker::default();
+        let events = ticker.advance(&shown(false), true, 120, Some(4));
+        assert!(
+            matches!(&events[0], SocketEvent::State(frame) if frame.playing && frame.steps.is_none())
+        );
+        assert_eq!(
+            events[1],
+            SocketEvent::Tick {
+                pattern: 2,
+                step: 4,
+                beat: 2,
+                tempo: 120
+            }
+        );
+        assert!(ticker.advance(&shown(false), true, 120, Some(4)).is_empty());
+        assert_eq!(ticker.advance(&shown(false), true, 120, Some(5)).len(), 1);
+
+        let events = ticker.advance(&shown(true), false, 120, Some(5));
+        assert_eq!(events.len(), 1);
+        assert!(
+            matches!(&events[0], SocketEvent::State(frame) if frame.revealed && !frame.playing)
+        );
+
+        let json = serde_json::to_string(&SocketEvent::Tick {
+            pattern: 2,
+            step: 4,
+            beat: 2,
+            tempo: 120,
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            r#"{"type":"tick","pattern":2,"step":4,"beat":2,"tempo":120}"#
+        );
+    }
+
+    #[test]
+    fn test_clients_get_the_state_on_connecting() {
+        let playback = MidiPlaybackLoop::new();
+        let server = SocketServer::start("127.0.0.1:0", playback.probe()).unwrap();
+        let shown = shown(true);
+        server.show(shown.number, &shown.pattern, true, shown.tempo_bpm);
+
+        // A client that never finishes its handshake holds up no one else
+        let _stalled = TcpStream::connect(server.local_addr()).unwrap();
+
+        // Wait for the server to pick the pattern up, then connect
+        thread::sleep(REFRESH * 5);
+        let url = format!("ws://{}", server.local_addr());
+        let started = Instant::now();
+        let stream = TcpStream::connect(server.local_addr()).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let (mut client, _) = tungstenite::client(url.as_str(), stream).unwrap();
+
+        let message = loop {
+            match client.read() {
+                Ok(Message::Text(text)) => break text,
+                Ok(_) => {}
+                Err(e) => panic!("no state after {:?}: {}", started.elapsed(), e),
+            }
+        };
+        assert!(started.elapsed() < HANDSHAKE_TIMEOUT / 2);
+        let state: serde_json::Value = serde_json::from_str(&message).unwrap();
+        assert_eq!(state["type"], "state");
+        assert_eq!(state["pattern"], 2);
+        assert_eq!(state["steps"], "x...x.....x....x");
+        assert_eq!(state["playing"], false);
+    }
+}
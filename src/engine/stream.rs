@@ -0,0 +1,348 @@
+use crate::engine::PlaybackProbe;
+use crate::error::KickbeatsError;
+use crate::models::Pattern;
+use crate::storage::replace_file;
+use crate::visualizer::ascii::{count_row, kick_row, step_column};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// How often the overlay checks whether playback has moved on
+const REFRESH: Duration = Duration::from_millis(10);
+
+/// How an overlay file is written, picked from its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayFormat {
+    /// Plain text for an OBS text source's "Read from file"
+    Text,
+    /// One JSON object, for a browser source or script to poll
+    Json,
+}
+
+impl OverlayFormat {
+    /// JSON for `.json` files, text for anything else
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => OverlayFormat::Json,
+            _ => OverlayFormat::Text,
+        }
+    }
+}
+
+/// The pattern on show and what the overlay may say about it
+#[derive(Debug, Clone)]
+pub(crate) struct Shown {
+    /// Number of the pattern in the session
+    pub(crate) number: u32,
+    /// The pattern
+    pub(crate) pattern: Pattern,
+    /// Whether its kicks may be shown
+    pub(crate) revealed: bool,
+    /// Tempo shown while nothing plays
+    pub(crate) tempo_bpm: u16,
+}
+
+impl Shown {
+    /// Whether this is already showing pattern `number` as asked
+    pub(crate) fn shows(
+        &self,
+        number: u32,
+        pattern: &Pattern,
+        revealed: bool,
+        tempo_bpm: u16,
+    ) -> bool {
+        self.number == number
+            && self.revealed == revealed
+            && self.tempo_bpm == tempo_bpm
+            && self.pattern.id == pattern.id
+            && self.pattern.steps == pattern.steps
+    }
+}
+
+/// One moment of the exercise, as written to the overlay file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OverlayFrame {
+    /// Number of the pattern in the session
+    pub pattern: u32,
+    /// Tempo now in effect
+    pub tempo: u16,
+    /// Time signature of the bar now sounding (e.g., "4/4")
+    pub time_signature: String,
+    /// Whether the pattern is looping (including its count-in)
+    pub playing: bool,
+    /// Whether the pattern has been revealed
+    pub revealed: bool,
+    /// Kicks as `x`/`.` steps, once revealed
+    pub steps: Option<String>,
+    /// Step now sounding, from 0
+    pub step: Option<usize>,
+    /// Beat of the bar now sounding, from 1
+    pub beat: Option<usize>,
+    /// Beats in the bar
+    pub beats: usize,
+    /// Count and kick rows of the grid, with `?` for hidden kicks
+    pub grid: String,
+}
+
+impl OverlayFrame {
+    /// Frame for `shown` with playback at `step`
+    pub(crate) fn new(shown: &Shown, playing: bool, tempo: u16, step: Option<usize>) -> Self {
+        let pattern = &shown.pattern;
+        let visible = if shown.revealed {
+            pattern.steps.len()
+        } else {
+            0
+        };
+        let (bar_start, meter) = pattern
+            .measures()
+            .into_iter()
+            .take_while(|&(start, _)| step.is_none_or(|step| start <= step))
+            .last()
+            .unwrap_or((0, pattern.time_signature));
+        let pulse = meter.pulse_sixteenths().max(1);
+        let beats = meter.sixteenths_per_measure().div_ceil(pulse);
+
+        Self {
+            pattern: shown.number,
+            tempo: if playing { tempo } else { shown.tempo_bpm },
+            time_signature: format!("{}/{}", meter.numerator, meter.denominator),
+            playing,
+            revealed: shown.revealed,
+            steps: shown.revealed.then(|| {
+                pattern
+                    .steps
+                    .iter()
+                    .map(|&kick| if kick { 'x' } else { '.' })
+                    .collect()
+            }),
+            step,
+            beat: step.map(|step| (step - bar_start) / pulse + 1),
+            beats,
+            grid: format!("{}{}", count_row(pattern), kick_row(pattern, visible)),
+        }
+    }
+
+    /// The frame as text for an OBS text source
+    ///
+    /// ```text
+    /// Pattern #3 · 120 BPM · 4/4
+    /// |1 e + a |2 e + a |3 e + a |4 e + a |
+    /// |? ? ? ? |? ? ? ? |? ? ? ? |? ? ? ? |
+    ///           ^
+    /// ○ ● ○ ○
+    /// ```
+    pub fn to_text(&self, pattern: &Pattern) -> String {
+        let mut text = format!(
+            "Pattern #{} · {} BPM · {}\n{}",
+            self.pattern, self.tempo, self.time_signature, self.grid
+        );
+        // The playhead line stays (blank) while stopped so the layout doesn't jump
+        if let Some(step) = self.step {
+            text.push_str(&" ".repeat(step_column(pattern, step)));
+            text.push('^');
+        }
+        text.push('\n');
+        let beats: Vec<&str> = (1..=self.beats)
+            .map(|beat| {
+                if self.beat == Some(beat) {
+                    "●"
+                } else {
+                    "○"
+                }
+            })
+            .collect();
+        text.push_str(&beats.join(" "));
+        text.push('\n');
+        text
+    }
+}
+
+/// Keeps a file showing the exercise on stream: the current pattern's grid
+/// (hidden until revealed), the tempo, and a playhead and beat indicator that
+/// follow playback
+///
+/// The file is rewritten atomically whenever any of it changes, so an OBS
+/// text source (or a browser source polling the JSON) never reads half a
+/// frame. A background thread follows playback through a
+/// [`PlaybackProbe`]; the pattern shown is updated with [`show`](Self::show).
+pub struct StreamOverlay {
+    /// Pattern on show, shared with the writer thread
+    shown: Arc<Mutex<Option<Shown>>>,
+    /// Cleared to stop the writer thread
+    running: Arc<AtomicBool>,
+    /// Writer thread
+    handle: Option<thread::JoinHandle<()>>,
+    /// Follows playback for the final frame
+    probe: PlaybackProbe,
+    /// File written to
+    path: PathBuf,
+    /// How the file is written
+    format: OverlayFormat,
+}
+
+impl StreamOverlay {
+    /// Start keeping `path` up to date with playback followed by `probe`
+    ///
+    /// Fails if the file can't be written; later write failures are ignored
+    /// so the stream never interrupts practice.
+    pub fn start(path: PathBuf, probe: PlaybackProbe) -> Result<Self, KickbeatsError> {
+        let format = OverlayFormat::for_path(&path);
+        replace_file(&path, render(None, &probe, format))
+            .map_err(|e| KickbeatsError::io("write stream overlay", &path, e))?;
+
+        let shown = Arc::new(Mutex::new(None));
+        let running = Arc::new(AtomicBool::new(true));
+        let handle = {
+            let shown = Arc::clone(&shown);
+            let running = Arc::clone(&running);
+            let probe = probe.clone();
+            let path = path.clone();
+            thread::spawn(move || {
+                let mut written = String::new();
+                while running.load(Ordering::SeqCst) {
+                    let current = shown.lock().ok().and_then(|shown| shown.clone());
+                    let contents = render(current.as_ref(), &probe, format);
+                    if contents != written && replace_file(&path, &contents).is_ok() {
+                        written = contents;
+                    }
+                    thread::sleep(REFRESH);
+                }
+            })
+        };
+
+        Ok(Self {
+            shown,
+            running,
+            handle: Some(handle),
+            probe,
+            path,
+            format,
+        })
+    }
+
+    /// File the overlay is written to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Show pattern number `number`, with its kicks if `revealed`, at
+    /// `tempo_bpm` while stopped
+    pub fn show(&self, number: u32, pattern: &Pattern, revealed: bool, tempo_bpm: u16) {
+        let Ok(mut shown) = self.shown.lock() else {
+            return;
+        };
+        if !shown
+            .as_ref()
+            .is_some_and(|shown| shown.shows(number, pattern, revealed, tempo_bpm))
+        {
+            *shown = Some(Shown {
+                number,
+                pattern: pattern.clone(),
+                revealed,
+                tempo_bpm,
+            });
+        }
+    }
+}
+
+impl Drop for StreamOverlay {
+    /// Stop the writer thread, leaving a final frame with playback stopped
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        let shown = self.shown.lock().ok().and_then(|shown| shown.clone());
+        let _ = replace_file(&self.path, render(shown.as_ref(), &self.probe, self.format));
+    }
+}
+
+/// Overlay file contents for `shown` at playback's current position
+fn render(shown: Option<&Shown>, probe: &PlaybackProbe, format: OverlayFormat) -> String {
+    let Some(shown) = shown else {
+        return match format {
+            OverlayFormat::Text => String::new(),
+            OverlayFormat::Json => String::from("null"),
+        };
+    };
+    let playing = probe.is_playing();
+    let frame = OverlayFrame::new(shown, playing, probe.current_tempo(), probe.current_step());
+    match format {
+        OverlayFormat::Text => frame.to_text(&shown.pattern),
+        OverlayFormat::Json => serde_json::to_string(&frame).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ComplexityLevel;
+
+    fn shown(revealed: bool) -> Shown {
+        Shown {
+            number: 3,
+            pattern: Pattern::from_fingerprint("4/4:x...x.....x....x", ComplexityLevel::Medium)
+                .unwrap(),
+            revealed,
+            tempo_bpm: 100,
+        }
+    }
+
+    #[test]
+    fn test_picks_format_from_extension() {
+        assert_eq!(
+            OverlayFormat::for_path(Path::new("overlay.JSON")),
+            OverlayFormat::Json
+        );
+        assert_eq!(
+            OverlayFormat::for_path(Path::new("overlay.txt")),
+            OverlayFormat::Text
+        );
+    }
+
+    #[test]
+    fn test_hides_kicks_until_revealed() {
+        let shown = shown(false);
+        let frame = OverlayFrame::new(&shown, true, 120, Some(5));
+        assert_eq!(frame.steps, None);
+        assert_eq!(frame.beat, Some(2));
+        assert_eq!(frame.beats, 4);
+
+        let text = frame.to_text(&shown.pattern);
+        assert_eq!(
+            text,
+            "Pattern #3 · 120 BPM · 4/4\n\
+             |1 e + a |2 e + a |3 e + a |4 e + a |\n\
+             |? ? ? ? |? ? ? ? |? ? ? ? |? ? ? ? |\n\
+             \x20           ^\n\
+             ○ ● ○ ○\n"
+        );
+    }
+
+    #[test]
+    fn test_shows_revealed_kicks_while_stopped() {
+        let shown = shown(true);
+        let frame = OverlayFrame::new(&shown, false, 0, None);
+        assert_eq!(frame.tempo, 100);
+        assert_eq!(frame.steps.as_deref(), Some("x...x.....x....x"));
+
+        let text = frame.to_text(&shown.pattern);
+        assert!(text.contains("|X . . . |X . . . |. . X . |. . . X |\n\n○ ○ ○ ○\n"));
+    }
+
+    #[test]
+    fn test_counts_beats_of_the_bar_playing() {
+        let mut shown = shown(true);
+        shown.pattern = Pattern::from_fingerprint(
+            "4/4+6/8:x...x...x...x...x.....x.....",
+            ComplexityLevel::Medium,
+        )
+        .unwrap();
+        let frame = OverlayFrame::new(&shown, true, 100, Some(22));
+        assert_eq!(frame.time_signature, "6/8");
+        assert_eq!((frame.beat, frame.beats), (Some(2), 2));
+    }
+}
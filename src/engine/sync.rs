@@ -0,0 +1,292 @@
+use crate::engine::midi::{MidiError, CLOCK_CONTINUE, CLOCK_START, CLOCK_STOP, CLOCK_TICK};
+use crate::error::KickbeatsError;
+use midir::{Ignore, MidiInput, MidiInputConnection};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Clock ticks per quarter note
+const TICKS_PER_QUARTER: f64 = 24.0;
+
+/// Longest gap between ticks still counted toward the tempo (a quarter at 10 BPM)
+const MAX_TICK_SECS: f64 = 0.25;
+
+/// Weight of each new tick interval in the smoothed tempo
+const SMOOTHING: f64 = 0.1;
+
+/// How often waits wake up to check whether playback was stopped
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Transport and tempo as heard from an external MIDI clock
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ClockState {
+    /// Whether the external transport is running
+    pub running: bool,
+    /// Ticks received while running since the last start message
+    pub ticks: u64,
+    /// Start messages received, telling a restart from the top apart from a continue
+    pub starts: u64,
+    /// Smoothed seconds between ticks, once two have arrived
+    tick_secs: Option<f64>,
+    /// When the last tick arrived
+    last_tick: Option<Instant>,
+}
+
+impl ClockState {
+    /// Update the transport for one incoming message; anything but clock,
+    /// start, continue, and stop is ignored
+    pub fn handle(&mut self, message: &[u8], now: Instant) {
+        match message.first().copied() {
+            Some(CLOCK_TICK) => {
+                if let Some(last) = self.last_tick {
+                    let interval = now.duration_since(last).as_secs_f64();
+                    // A long gap means the sender paused, not that the tempo dropped
+                    if interval < MAX_TICK_SECS {
+                        self.tick_secs = Some(match self.tick_secs {
+                            Some(average) => average + (interval - average) * SMOOTHING,
+                            None => interval,
+                        });
+                    }
+                }
+                self.last_tick = Some(now);
+                if self.running {
+                    self.ticks += 1;
+                }
+            }
+            Some(CLOCK_START) => {
+                self.running = true;
+                self.ticks = 0;
+                self.starts += 1;
+            }
+            Some(CLOCK_CONTINUE) => self.running = true,
+            Some(CLOCK_STOP) => self.running = false,
+            _ => {}
+        }
+    }
+
+    /// Seconds between ticks, once the clock has been heard long enough to tell
+    pub fn tick_secs(&self) -> Option<f64> {
+        self.tick_secs
+    }
+
+    /// Tempo of the external clock in quarter notes per minute
+    pub fn tempo_bpm(&self) -> Option<f64> {
+        self.tick_secs.map(|secs| 60.0 / (secs * TICKS_PER_QUARTER))
+    }
+}
+
+/// What ended a wait on the external clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockWait {
+    /// The awaited tick arrived
+    Reached,
+    /// The transport stopped or restarted from the top first
+    Interrupted,
+    /// Playback was stopped locally
+    Cancelled,
+}
+
+/// Clock, start, continue, and stop messages received on a MIDI input port
+///
+/// Per the MIDI spec, the first tick after a start message is the downbeat,
+/// so tick `n` (counting from 0) has arrived once `ticks > n`.
+pub struct ExternalClock {
+    shared: Arc<(Mutex<ClockState>, Condvar)>,
+    /// Keeps the input port open; `None` for clocks fed by hand in tests
+    _connection: Option<MidiInputConnection<()>>,
+}
+
+impl ExternalClock {
+    /// List available MIDI input ports
+    pub fn list_ports() -> Result<Vec<String>, KickbeatsError> {
+        let midi_in = MidiInput::new("Kickbeats")
+            .map_err(|e| MidiError::new(format!("Failed to initialize MIDI input: {}", e)))?;
+        Ok(midi_in
+            .ports()
+            .iter()
+            .filter_map(|p| midi_in.port_name(p).ok())
+            .collect())
+    }
+
+    /// Listen on the first input port whose name contains `port_name`
+    pub fn connect(port_name: &str) -> Result<Self, KickbeatsError> {
+        let mut midi_in = MidiInput::new("Kickbeats")
+            .map_err(|e| MidiError::new(format!("Failed to initialize MIDI input: {}", e)))?;
+        // Timing messages are filtered out unless asked for
+        midi_in.ignore(Ignore::SysexAndActiveSense);
+
+        let ports = midi_in.ports();
+        if ports.is_empty() {
+            return Err(MidiError::new("No MIDI input ports available to sync to").into());
+        }
+        let port = ports
+            .iter()
+            .find(|p| {
+                midi_in
+                    .port_name(p)
+                    .map(|name| name.contains(port_name))
+                    .unwrap_or(false)
+            })
+            .ok_or_else(|| MidiError::new(format!("MIDI input port '{}' not found", port_name)))?;
+
+        let shared = Arc::new((Mutex::new(ClockState::default()), Condvar::new()));
+        let receiver = Arc::clone(&shared);
+        let connection = midi_in
+            .connect(
+                port,
+                "kickbeats-sync",
+                move |_, message, _| receive(&receiver, message, Instant::now()),
+                (),
+            )
+            .map_err(|e| MidiError::new(format!("Failed to connect to MIDI input port: {}", e)))?;
+
+        Ok(Self {
+            shared,
+            _connection: Some(connection),
+        })
+    }
+
+    /// Transport and tempo as of the last message received
+    pub fn state(&self) -> ClockState {
+        self.shared.0.lock().map(|state| *state).unwrap_or_default()
+    }
+
+    /// Block until the transport is running, or until `is_playing` goes false
+    pub fn wait_for_running(&self, is_playing: &AtomicBool) -> bool {
+        let (state, ticked) = &*self.shared;
+        let Ok(mut guard) = state.lock() else {
+            return false;
+        };
+        while !guard.running {
+            if !is_playing.load(Ordering::SeqCst) {
+                return false;
+            }
+            guard = match ticked.wait_timeout(guard, POLL_INTERVAL) {
+                Ok((guard, _)) => guard,
+                Err(_) => return false,
+            };
+        }
+        true
+    }
+
+    /// Block until tick `tick` of the run begun by start message number `starts` arrives
+    pub fn wait_for_tick(&self, tick: u64, starts: u64, is_playing: &AtomicBool) -> ClockWait {
+        let (state, ticked) = &*self.shared;
+        let Ok(mut guard) = state.lock() else {
+            return ClockWait::Cancelled;
+        };
+        loop {
+            if !guard.running || guard.starts != starts {
+                return ClockWait::Interrupted;
+            }
+            if guard.ticks > tick {
+                return ClockWait::Reached;
+            }
+            if !is_playing.load(Ordering::SeqCst) {
+                return ClockWait::Cancelled;
+            }
+            guard = match ticked.wait_timeout(guard, POLL_INTERVAL) {
+                Ok((guard, _)) => guard,
+                Err(_) => return ClockWait::Cancelled,
+            };
+        }
+    }
+
+    /// A clock fed by hand instead of a port
+    #[cfg(test)]
+    fn detached() -> Self {
+        Self {
+            shared: Arc::new((Mutex::new(ClockState::default()), Condvar::new())),
+            _connection: None,
+        }
+    }
+}
+
+/// Apply one message and wake anyone waiting on the clock
+fn receive(shared: &(Mutex<ClockState>, Condvar), message: &[u8], now: Instant) {
+    if let Ok(mut state) = shared.0.lock() {
+        state.handle(message, now);
+    }
+    shared.1.notify_all();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_transport_messages() {
+        let now = Instant::now();
+        let mut state = ClockState::default();
+
+        // Ticks before a start set the tempo but aren't counted
+        state.handle(&[CLOCK_TICK], now);
+        assert_eq!(state.ticks, 0);
+
+        state.handle(&[CLOCK_START], now);
+        state.handle(&[CLOCK_TICK], now);
+        state.handle(&[CLOCK_TICK], now);
+        assert!(state.running);
+        assert_eq!((state.ticks, state.starts), (2, 1));
+
+        state.handle(&[CLOCK_STOP], now);
+        state.handle(&[CLOCK_TICK], now);
+        assert!(!state.running);
+        assert_eq!(state.ticks, 2);
+
+        state.handle(&[CLOCK_CONTINUE], now);
+        state.handle(&[CLOCK_TICK], now);
+        assert_eq!((state.ticks, state.starts), (3, 1));
+
+        state.handle(&[0x90, 36, 100], now);
+        assert_eq!(state.ticks, 3);
+    }
+
+    #[test]
+    fn test_tempo_from_tick_spacing() {
+        let start = Instant::now();
+        let mut state = ClockState::default();
+        assert_eq!(state.tempo_bpm(), None);
+
+        // 120 BPM: 48 ticks a second
+        for tick in 0..48 {
+            state.handle(
+                &[CLOCK_TICK],
+                start + Duration::from_secs_f64(tick as f64 / 48.0),
+            );
+        }
+        assert!((state.tempo_bpm().unwrap() - 120.0).abs() < 0.01);
+
+        // A pause isn't read as a tempo change
+        state.handle(&[CLOCK_TICK], start + Duration::from_secs(5));
+        assert!((state.tempo_bpm().unwrap() - 120.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_wait_for_tick() {
+        let clock = ExternalClock::detached();
+        let is_playing = AtomicBool::new(true);
+        receive(&clock.shared, &[CLOCK_START], Instant::now());
+        assert!(clock.wait_for_running(&is_playing));
+
+        let sender = Arc::clone(&clock.shared);
+        let ticks = thread::spawn(move || {
+            for _ in 0..3 {
+                thread::sleep(Duration::from_millis(5));
+                receive(&sender, &[CLOCK_TICK], Instant::now());
+            }
+        });
+        assert_eq!(clock.wait_for_tick(2, 1, &is_playing), ClockWait::Reached);
+        ticks.join().unwrap();
+
+        receive(&clock.shared, &[CLOCK_START], Instant::now());
+        assert_eq!(
+            clock.wait_for_tick(5, 1, &is_playing),
+            ClockWait::Interrupted
+        );
+
+        is_playing.store(false, Ordering::SeqCst);
+        assert_eq!(clock.wait_for_tick(5, 2, &is_playing), ClockWait::Cancelled);
+    }
+}
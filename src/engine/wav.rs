@@ -0,0 +1,609 @@
+use crate::engine::midi::{MidiEngine, MidiEvent, MidiEventType, Voice, CLICK_ACCENT_VELOCITY};
+use crate::error::KickbeatsError;
+use crate::models::{Overlay, Pattern};
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+use std::f64::consts::TAU;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+/// Sample rate files are rendered at unless another is asked for
+pub const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+
+/// Highest gain a voice can be turned up to
+pub const MAX_GAIN: f32 = 2.0;
+
+/// Peak level a mix that would clip is scaled down to
+const HEADROOM: f32 = 0.95;
+
+/// A sound the renderer plays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrumVoice {
+    /// The pattern's kicks
+    Kick,
+    /// The click track and count-in
+    Click,
+    /// Clave and tresillo overlays
+    Snare,
+    /// The eighths overlay
+    Hat,
+}
+
+impl DrumVoice {
+    /// Every voice, in the order they're configured
+    pub const ALL: [DrumVoice; 4] = [
+        DrumVoice::Kick,
+        DrumVoice::Click,
+        DrumVoice::Snare,
+        DrumVoice::Hat,
+    ];
+
+    /// Name as written in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            DrumVoice::Kick => "kick",
+            DrumVoice::Click => "click",
+            DrumVoice::Snare => "snare",
+            DrumVoice::Hat => "hat",
+        }
+    }
+
+    /// Voice an overlay figure is played on: eighths on the hi-hat, claves
+    /// and tresillo on the snare, as an electronic kit plays them
+    pub fn for_overlay(overlay: Overlay) -> Self {
+        match overlay {
+            Overlay::Eighths => DrumVoice::Hat,
+            Overlay::SonClave | Overlay::RumbaClave | Overlay::Tresillo => DrumVoice::Snare,
+        }
+    }
+}
+
+/// One drum sound as mono samples, struck at full velocity
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    /// Mono samples
+    frames: Vec<f32>,
+    /// Samples per second they were recorded at
+    rate: u32,
+}
+
+impl Sample {
+    /// Built-in kick: a sine dropping from 150Hz to 50Hz over 50ms, fading
+    /// out over 150ms (the web app's kick)
+    pub fn kick() -> Self {
+        Self::synthesize(0.15, |t, _| {
+            let sweep = (50.0_f64 / 150.0).powf((t / 0.05).min(1.0));
+            Tone::Sine(150.0 * sweep)
+        })
+    }
+
+    /// Built-in click: a 10ms 2kHz blip (the web app's click)
+    pub fn click() -> Self {
+        Self::synthesize(0.01, |_, _| Tone::Sine(2000.0))
+    }
+
+    /// Built-in accented click, a 3kHz blip for downbeats and group starts
+    pub fn accent() -> Self {
+        Self::synthesize(0.01, |_, _| Tone::Sine(3000.0))
+    }
+
+    /// Built-in snare: a 180Hz body under a burst of noise, fading over 120ms
+    pub fn snare() -> Self {
+        Self::synthesize(0.12, |_, noise| Tone::Mix(180.0, noise))
+    }
+
+    /// Built-in hi-hat: a 40ms burst of bright noise
+    pub fn hat() -> Self {
+        Self::synthesize(0.04, |_, noise| Tone::Noise(noise))
+    }
+
+    /// Built-in sound for a voice (the plain click for [`DrumVoice::Click`])
+    pub fn built_in(voice: DrumVoice) -> Self {
+        match voice {
+            DrumVoice::Kick => Self::kick(),
+            DrumVoice::Click => Self::click(),
+            DrumVoice::Snare => Self::snare(),
+            DrumVoice::Hat => Self::hat(),
+        }
+    }
+
+    /// A sound following `tone` (given the time in seconds and a fresh noise
+    /// value), decaying exponentially to 1% over `length_secs`
+    fn synthesize(length_secs: f64, tone: impl Fn(f64, f64) -> Tone) -> Self {
+        let rate = f64::from(DEFAULT_SAMPLE_RATE);
+        let count = (length_secs * rate) as usize;
+        let mut phase = 0.0;
+        let mut noise = Noise(0x2545_f491);
+        let mut last_noise = 0.0;
+        let frames = (0..count)
+            .map(|i| {
+                let t = i as f64 / rate;
+                let level = 0.01_f64.powf(t / length_secs);
+                let frame = match tone(t, noise.next()) {
+                    Tone::Sine(hz) => {
+                        let frame = (phase * TAU).sin();
+                        phase += hz / rate;
+                        frame
+                    }
+                    Tone::Mix(hz, white) => {
+                        let frame = 0.5 * (phase * TAU).sin() + 0.5 * white;
+                        phase += hz / rate;
+                        frame
+                    }
+                    // Differencing tilts white noise towards the highs
+                    Tone::Noise(white) => {
+                        let bright = (white - last_noise) / 2.0;
+                        last_noise = white;
+                        bright
+                    }
+                };
+                (frame * level) as f32
+            })
+            .collect();
+        Self {
+            frames,
+            rate: DEFAULT_SAMPLE_RATE,
+        }
+    }
+
+    /// Load a WAV file, mixing its channels down to mono
+    ///
+    /// A file that isn't a WAV with audio in it is a config error.
+    pub fn load(path: &Path) -> Result<Self, KickbeatsError> {
+        let invalid = |e: String| {
+            KickbeatsError::Config(format!("Failed to read sample {}: {}", path.display(), e))
+        };
+        let reader = WavReader::open(path).map_err(|e| match e {
+            hound::Error::IoError(e) => KickbeatsError::io("read sample", path, e),
+            e => invalid(e.to_string()),
+        })?;
+        Self::read(reader).map_err(invalid)
+    }
+
+    /// Decode a WAV file's samples, whatever their format and channel count
+    fn read<R: Read>(reader: WavReader<R>) -> Result<Self, String> {
+        let spec = reader.spec();
+        let channels = usize::from(spec.channels.max(1));
+        let interleaved: Vec<f32> = match spec.sample_format {
+            SampleFormat::Float => reader
+                .into_samples::<f32>()
+                .collect::<Result<_, _>>()
+                .map_err(|e| e.to_string())?,
+            SampleFormat::Int => {
+                let scale = (1_i64 << (spec.bits_per_sample.max(1) - 1)) as f32;
+                reader
+                    .into_samples::<i32>()
+                    .map(|sample| sample.map(|s| s as f32 / scale))
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| e.to_string())?
+            }
+        };
+        if interleaved.is_empty() || spec.sample_rate == 0 {
+            return Err("it has no audio".to_string());
+        }
+        let frames = interleaved
+            .chunks(channels)
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            .collect();
+        Ok(Self {
+            frames,
+            rate: spec.sample_rate,
+        })
+    }
+
+    /// Length in seconds
+    pub fn duration_secs(&self) -> f64 {
+        self.frames.len() as f64 / f64::from(self.rate)
+    }
+
+    /// Samples at `rate`, linearly interpolated if it isn't the sample's own
+    fn at_rate(&self, rate: u32) -> Vec<f32> {
+        if rate == self.rate {
+            return self.frames.clone();
+        }
+        let step = f64::from(self.rate) / f64::from(rate);
+        let count = (self.frames.len() as f64 / step) as usize;
+        (0..count)
+            .map(|i| {
+                let position = i as f64 * step;
+                let index = position as usize;
+                let next = self.frames.get(index + 1).copied().unwrap_or(0.0);
+                let fraction = (position - index as f64) as f32;
+                self.frames[index] * (1.0 - fraction) + next * fraction
+            })
+            .collect()
+    }
+}
+
+/// What a built-in sound is made of at one instant
+enum Tone {
+    /// A sine at this frequency
+    Sine(f64),
+    /// A sine at this frequency mixed evenly with this noise value
+    Mix(f64, f64),
+    /// Brightened noise from this value
+    Noise(f64),
+}
+
+/// Repeatable white noise (xorshift), so built-in sounds are the same every run
+struct Noise(u32);
+
+impl Noise {
+    /// Next value, between -1 and 1
+    fn next(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        f64::from(self.0) / f64::from(u32::MAX) * 2.0 - 1.0
+    }
+}
+
+/// Offline renderer that mixes a pattern's kicks and click into a WAV file,
+/// for practicing away from a MIDI setup (e.g., on a phone)
+///
+/// The hits are timed exactly as playback sends them over MIDI: a bar of
+/// count-in, then the pattern looped, with the click's accents on the same
+/// beats and any overlay on the snare or hi-hat. Each hit plays its voice's
+/// sample scaled by its velocity and the voice's gain. Built-in sounds are
+/// used unless samples are supplied, and every sample is resampled to the
+/// output rate.
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_cli::engine::WavRenderer;
+/// use kickbeats_cli::models::{ComplexityLevel, Pattern, TimeSignature};
+///
+/// let steps = (0..16).map(|i| i % 4 == 0).collect();
+/// let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Simple);
+/// let wav = WavRenderer::new().to_wav(&pattern, 120, 4).unwrap();
+/// assert_eq!(&wav[..4], b"RIFF");
+/// ```
+pub struct WavRenderer {
+    /// Times hits the way playback does
+    engine: MidiEngine,
+    /// Sound of each voice, in [`DrumVoice::ALL`] order
+    samples: [Sample; 4],
+    /// Sound of accented clicks, while the click is the built-in one
+    accent: Option<Sample>,
+    /// Level of each voice, in [`DrumVoice::ALL`] order
+    gains: [f32; 4],
+    /// Whether the click plays during the loops (the count-in always clicks)
+    click_enabled: bool,
+    /// Figure played alongside the pattern, if any
+    overlay: Option<Overlay>,
+    /// Samples per second of the output
+    sample_rate: u32,
+}
+
+impl WavRenderer {
+    /// Render with the built-in sounds at full gain and the click on
+    pub fn new() -> Self {
+        Self {
+            engine: MidiEngine::new(),
+            samples: DrumVoice::ALL.map(Sample::built_in),
+            accent: Some(Sample::accent()),
+            gains: [1.0; 4],
+            click_enabled: true,
+            overlay: None,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+        }
+    }
+
+    /// Play `sample` for each hit on `voice`
+    ///
+    /// A click sample plays accents too, just louder.
+    pub fn with_sample(mut self, voice: DrumVoice, sample: Sample) -> Self {
+        if voice == DrumVoice::Click {
+            self.accent = None;
+        }
+        self.samples[voice as usize] = sample;
+        self
+    }
+
+    /// Scale `voice` by `gain` (0 silences it, 1 leaves it as recorded)
+    pub fn with_gain(mut self, voice: DrumVoice, gain: f32) -> Result<Self, KickbeatsError> {
+        if !(0.0..=MAX_GAIN).contains(&gain) {
+            return Err(KickbeatsError::Config(format!(
+                "The {} gain must be between 0 and {}",
+                voice.name(),
+                MAX_GAIN
+            )));
+        }
+        self.gains[voice as usize] = gain;
+        Ok(self)
+    }
+
+    /// Jitter each loop's kicks as playback does (see [`MidiEngine::set_humanize`])
+    pub fn with_humanize(mut self, amount: u8) -> Self {
+        self.engine.set_humanize(amount);
+        self
+    }
+
+    /// Play `overlay` alongside the pattern, on the voice it belongs to (see
+    /// [`DrumVoice::for_overlay`])
+    pub fn with_overlay(mut self, overlay: Overlay) -> Self {
+        self.overlay = Some(overlay);
+        self
+    }
+
+    /// Write at `rate` samples per second instead of [`DEFAULT_SAMPLE_RATE`]
+    pub fn with_sample_rate(mut self, rate: u32) -> Self {
+        self.sample_rate = rate.max(1);
+        self
+    }
+
+    /// Leave the click out of the loops, keeping it for the count-in
+    pub fn without_click(mut self) -> Self {
+        self.click_enabled = false;
+        self
+    }
+
+    /// Mono samples of a count-in and `loops` loops of `pattern` at
+    /// `tempo_bpm`, with room for the last hit to ring out
+    ///
+    /// Fails if the overlay doesn't fit the pattern's meter.
+    pub fn render(
+        &self,
+        pattern: &Pattern,
+        tempo_bpm: u16,
+        loops: u32,
+    ) -> Result<Vec<f32>, KickbeatsError> {
+        let overlay = match self.overlay {
+            Some(overlay) => Some((overlay.steps(pattern.time_signature)?, overlay)),
+            None => None,
+        };
+        let count_in = self
+            .engine
+            .count_in_duration(pattern.time_signature, tempo_bpm);
+        let loop_secs = self.engine.pattern_duration(pattern, tempo_bpm);
+        let mut hits: Vec<MidiEvent> = self
+            .engine
+            .generate_count_in_events(pattern.time_signature, tempo_bpm);
+        for index in 0..loops {
+            let start = count_in + f64::from(index) * loop_secs;
+            let mut events =
+                self.engine
+                    .pattern_to_midi_events(pattern, tempo_bpm, self.click_enabled);
+            if let Some((steps, _)) = &overlay {
+                events.extend(
+                    self.engine
+                        .overlay_to_midi_events(pattern, steps, tempo_bpm),
+                );
+            }
+            hits.extend(events.iter().map(|event| MidiEvent {
+                time_offset: event.time_offset + start,
+                ..*event
+            }));
+        }
+
+        let rate = f64::from(self.sample_rate);
+        let voices = self
+            .samples
+            .each_ref()
+            .map(|sample| sample.at_rate(self.sample_rate));
+        let accent = self
+            .accent
+            .as_ref()
+            .map(|sample| sample.at_rate(self.sample_rate));
+        // Room for the longest sound that plays to ring out: snare and hat
+        // only play an overlay
+        let overlay_voice = self.overlay.map(DrumVoice::for_overlay);
+        let ring = DrumVoice::ALL
+            .into_iter()
+            .filter(|&voice| {
+                matches!(voice, DrumVoice::Kick | DrumVoice::Click) || Some(voice) == overlay_voice
+            })
+            .map(|voice| voices[voice as usize].len())
+            .chain(accent.as_ref().map(Vec::len))
+            .max()
+            .unwrap_or(0);
+        let length = count_in + f64::from(loops) * loop_secs;
+        let mut mix = vec![0.0_f32; (length * rate) as usize + ring];
+        for hit in hits
+            .iter()
+            .filter(|event| event.event_type == MidiEventType::NoteOn)
+        {
+            let voice = match (hit.voice, &overlay) {
+                (Voice::Kick, _) => DrumVoice::Kick,
+                (Voice::Click, _) => DrumVoice::Click,
+                (Voice::Overlay, Some((_, overlay))) => DrumVoice::for_overlay(*overlay),
+                (Voice::Overlay | Voice::Cue | Voice::Clock, _) => continue,
+            };
+            let frames = match &accent {
+                Some(accent)
+                    if voice == DrumVoice::Click && hit.velocity >= CLICK_ACCENT_VELOCITY =>
+                {
+                    accent
+                }
+                _ => &voices[voice as usize],
+            };
+            let gain = f32::from(hit.velocity) / 127.0 * self.gains[voice as usize];
+            let start = (hit.time_offset.max(0.0) * rate).round() as usize;
+            for (out, frame) in mix.iter_mut().skip(start).zip(frames) {
+                *out += frame * gain;
+            }
+        }
+
+        let peak = mix
+            .iter()
+            .fold(0.0_f32, |peak, frame| peak.max(frame.abs()));
+        if peak > 1.0 {
+            let scale = HEADROOM / peak;
+            mix.iter_mut().for_each(|frame| *frame *= scale);
+        }
+        Ok(mix)
+    }
+
+    /// The render as a 16-bit mono WAV file
+    pub fn to_wav(
+        &self,
+        pattern: &Pattern,
+        tempo_bpm: u16,
+        loops: u32,
+    ) -> Result<Vec<u8>, KickbeatsError> {
+        let encode =
+            |e: hound::Error| KickbeatsError::Playback(format!("Failed to encode WAV: {}", e));
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut wav = Cursor::new(Vec::new());
+        let mut writer = WavWriter::new(&mut wav, spec).map_err(encode)?;
+        for frame in self.render(pattern, tempo_bpm, loops)? {
+            let frame = (frame.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+            writer.write_sample(frame).map_err(encode)?;
+        }
+        writer.finalize().map_err(encode)?;
+        Ok(wav.into_inner())
+    }
+}
+
+impl Default for WavRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, TimeSignature};
+
+    fn pattern() -> Pattern {
+        let steps = (0..16).map(|i| i == 0 || i == 6).collect();
+        Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Simple)
+    }
+
+    /// A flat sample `len` frames long at the default rate
+    fn flat(level: f32, len: usize) -> Sample {
+        Sample {
+            frames: vec![level; len],
+            rate: DEFAULT_SAMPLE_RATE,
+        }
+    }
+
+    /// Sample index of each hit's onset: where a silent stretch ends
+    fn onsets(mix: &[f32]) -> Vec<usize> {
+        let mut onsets = Vec::new();
+        let mut quiet = usize::MAX;
+        for (i, frame) in mix.iter().enumerate() {
+            if frame.abs() > 1e-6 {
+                if quiet > 200 {
+                    onsets.push(i);
+                }
+                quiet = 0;
+            } else {
+                quiet = quiet.saturating_add(1);
+            }
+        }
+        onsets
+    }
+
+    #[test]
+    fn test_hits_land_where_playback_sends_them() {
+        // 120 BPM: a 2s count-in, then 2s loops of 125ms steps
+        let renderer = WavRenderer::new()
+            .with_sample(DrumVoice::Kick, flat(0.5, 10))
+            .with_sample(DrumVoice::Click, flat(0.25, 10));
+        let mix = renderer.render(&pattern(), 120, 2).unwrap();
+        assert_eq!(mix.len(), 6 * DEFAULT_SAMPLE_RATE as usize + 10);
+
+        // Four count-in clicks, then each loop's kick-and-click on 1, clicks
+        // on 2, 3, and 4, and the kick on 2+
+        let second = DEFAULT_SAMPLE_RATE as usize;
+        let beat = second / 2;
+        let mut expected: Vec<usize> = (0..4).map(|i| i * beat).collect();
+        for start in [2 * second, 4 * second] {
+            expected.extend([start, start + beat, start + 3 * beat / 2, start + 2 * beat]);
+            expected.push(start + 3 * beat);
+        }
+        assert_eq!(onsets(&mix), expected);
+
+        // Kick and click together on the downbeat, scaled by velocity
+        let downbeat = mix[2 * second];
+        assert!((downbeat - (0.5 * 100.0 + 0.25 * 80.0) / 127.0).abs() < 1e-6);
+
+        // Without the click, only the count-in clicks
+        let quiet = renderer.without_click().render(&pattern(), 120, 2).unwrap();
+        assert_eq!(onsets(&quiet).len(), 4 + 2 * 2);
+    }
+
+    #[test]
+    fn test_each_voice_has_its_own_gain() {
+        let renderer = WavRenderer::new()
+            .with_sample(DrumVoice::Kick, flat(0.5, 10))
+            .with_sample(DrumVoice::Click, flat(0.5, 10))
+            .with_sample(DrumVoice::Hat, flat(0.5, 10))
+            .with_gain(DrumVoice::Kick, 0.5)
+            .unwrap()
+            .with_gain(DrumVoice::Click, 0.0)
+            .unwrap()
+            .with_overlay(Overlay::Eighths);
+        let mix = renderer.render(&pattern(), 120, 1).unwrap();
+
+        // The downbeat's kick at half gain, its hi-hat, and no click
+        let second = DEFAULT_SAMPLE_RATE as usize;
+        let hat = 0.5 * 70.0 / 127.0;
+        assert!((mix[2 * second] - (0.25 * 100.0 / 127.0 + hat)).abs() < 1e-6);
+        // A hi-hat on each eighth, and nothing during the count-in
+        assert_eq!(onsets(&mix).len(), 8);
+        assert!((mix[2 * second + second / 4] - hat).abs() < 1e-6);
+
+        assert!(WavRenderer::new().with_gain(DrumVoice::Snare, 2.5).is_err());
+        let clave = WavRenderer::new().with_overlay(Overlay::SonClave);
+        let three_four = Pattern::new(
+            vec![true; 12],
+            TimeSignature::three_four(),
+            ComplexityLevel::Simple,
+        );
+        assert!(clave.render(&three_four, 120, 1).is_err());
+    }
+
+    #[test]
+    fn test_loud_mixes_are_scaled_under_full_scale() {
+        let renderer = WavRenderer::new().with_sample(DrumVoice::Kick, flat(1.0, 10));
+        let steps = vec![true; 16];
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Simple);
+        let mix = renderer.render(&pattern, 120, 1).unwrap();
+        let peak = mix
+            .iter()
+            .fold(0.0_f32, |peak, frame| peak.max(frame.abs()));
+        assert!((peak - HEADROOM).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_samples_are_resampled_to_the_output_rate() {
+        // A stereo 22.05kHz file: left at half scale, right silent
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: DEFAULT_SAMPLE_RATE / 2,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut file = Cursor::new(Vec::new());
+        let mut writer = WavWriter::new(&mut file, spec).unwrap();
+        for _ in 0..100 {
+            writer.write_sample(i16::MAX / 2).unwrap();
+            writer.write_sample(0_i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let sample = Sample::read(WavReader::new(Cursor::new(file.into_inner())).unwrap()).unwrap();
+        assert_eq!(sample.frames.len(), 100);
+        assert!((sample.frames[0] - 0.25).abs() < 1e-3);
+        assert!((sample.duration_secs() - 100.0 / 22_050.0).abs() < 1e-9);
+        assert_eq!(sample.at_rate(DEFAULT_SAMPLE_RATE).len(), 200);
+        assert_eq!(sample.at_rate(48_000).len(), 217);
+
+        // Built-in kick (150ms) rings out past 4s of count-in and loop
+        let wav = WavRenderer::new()
+            .with_sample_rate(48_000)
+            .to_wav(&pattern(), 120, 1)
+            .unwrap();
+        let reader = WavReader::new(Cursor::new(wav)).unwrap();
+        assert_eq!(reader.spec().sample_rate, 48_000);
+        assert_eq!(reader.duration() as usize, 4 * 48_000 + 7200);
+    }
+}
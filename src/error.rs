@@ -0,0 +1,95 @@
+// Error module
+// The one error type the library API returns, so callers can tell failures apart
+
+use kickbeats_core::CoreError;
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[cfg(feature = "midi")]
+use crate::engine::midi::MidiError;
+
+/// Why a library call failed
+///
+/// Every kind carries a message ready to show the player; match on the kind
+/// to handle some failures (say, a missing MIDI port) differently.
+#[derive(Debug, Error)]
+pub enum KickbeatsError {
+    /// A MIDI port couldn't be listed, opened, or sent to (the message
+    /// includes setup steps for the platform where it helps)
+    #[cfg(feature = "midi")]
+    #[error(transparent)]
+    Midi(#[from] MidiError),
+    /// No pattern could be generated or derived from the current one
+    #[error("{0}")]
+    Generation(String),
+    /// Playback couldn't start or change in the session's current state
+    #[error("{0}")]
+    Playback(String),
+    /// A setting, or a config file's contents, was invalid
+    #[error("{0}")]
+    Config(String),
+    /// Practice history or a session file couldn't be read or saved
+    #[error("{0}")]
+    Storage(String),
+    /// A server for other apps or students couldn't listen on its address
+    #[error("{0}")]
+    Network(String),
+    /// A file couldn't be read or written
+    #[error("Failed to {action} {}: {source}", path.display())]
+    Io {
+        /// What was being done, e.g. "read config file"
+        action: &'static str,
+        /// File that couldn't be read or written
+        path: PathBuf,
+        /// What went wrong
+        source: io::Error,
+    },
+}
+
+impl KickbeatsError {
+    /// Error for a failure to `action` (e.g., "read config file") `path`
+    pub fn io(action: &'static str, path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Self::Io {
+            action,
+            path: path.into(),
+            source,
+        }
+    }
+}
+
+/// Keeps the kind a core call failed with
+impl From<CoreError> for KickbeatsError {
+    fn from(error: CoreError) -> Self {
+        match error {
+            CoreError::Generation(message) => Self::Generation(message),
+            CoreError::Config(message) => Self::Config(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_error_names_the_file() {
+        let error = KickbeatsError::io(
+            "read config file",
+            "/tmp/kickbeats.toml",
+            io::Error::from(io::ErrorKind::NotFound),
+        );
+        assert!(error
+            .to_string()
+            .starts_with("Failed to read config file /tmp/kickbeats.toml: "));
+        assert!(std::error::Error::source(&error).is_some());
+    }
+
+    #[test]
+    fn test_core_errors_keep_their_kind() {
+        let error = KickbeatsError::from(CoreError::Generation(String::from("stuck")));
+        assert!(matches!(error, KickbeatsError::Generation(ref message) if message == "stuck"));
+        let error = KickbeatsError::from(CoreError::Config(String::from("bad")));
+        assert!(matches!(error, KickbeatsError::Config(_)));
+    }
+}
@@ -1,7 +1,25 @@
-// Kickbeats - Rhythm Practice Tool Library
+// Kickbeats CLI - Rhythm Practice Tool Library
+//
+// The MIDI engine, terminal interfaces, config files, and practice history. The
+// pattern core (models, generator, grading, visualizer) lives in `kickbeats-core`
+// and is re-exported here so both crates share one set of paths.
 
+#[cfg(all(feature = "cli", feature = "midi"))]
 pub mod cli;
+#[cfg(feature = "cli")]
+pub mod color;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "midi")]
 pub mod engine;
-pub mod generator;
-pub mod models;
-pub mod visualizer;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub mod storage;
+
+pub use kickbeats_core::{generator, grading, models, visualizer, CoreError};
+
+#[cfg(feature = "midi")]
+pub use engine::PracticeEngine;
+#[cfg(feature = "std")]
+pub use error::KickbeatsError;
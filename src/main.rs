@@ -1,17 +1,77 @@
-mod cli;
-mod engine;
-mod generator;
-mod models;
-mod visualizer;
-
-use clap::Parser;
-use cli::CommandLoop;
-use generator::WeightedGenerator;
-use models::{ComplexityLevel, PracticeSession, TimeSignature};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+#[cfg(all(feature = "webhooks", feature = "midi"))]
+use kickbeats_cli::cli::Webhook;
+#[cfg(feature = "midi")]
+use kickbeats_cli::cli::{
+    ClassroomStudent, CommandLoop, DemoTour, HeadlessLoop, ReplayPlayer, SessionOptions, TuiApp,
+};
+use kickbeats_cli::color::{stdout_supports_color, Palette};
+#[cfg(feature = "midi")]
+use kickbeats_cli::config::KeyBindings;
+use kickbeats_cli::config::{
+    Config, PracticePack, PracticeSection, SamplesSection, TeacherKey, Theme,
+};
+#[cfg(feature = "midi")]
+use kickbeats_cli::config::{ConfigWatcher, KitSection, MidiSection};
+#[cfg(feature = "midi")]
+use kickbeats_cli::engine::midi::MidiEngine;
+#[cfg(feature = "websocket")]
+use kickbeats_cli::engine::SocketServer;
+#[cfg(feature = "midi")]
+use kickbeats_cli::engine::{
+    ClassroomHost, EventLog, KitMap, LogFormat, MidiMapping, PracticeEngine, SharedSink,
+    StreamOverlay,
+};
+#[cfg(feature = "wav")]
+use kickbeats_cli::engine::{DrumVoice, Sample, WavRenderer};
+use kickbeats_cli::error::KickbeatsError;
+use kickbeats_cli::generator::{
+    DifficultyTarget, EuclideanGenerator, GrooveGenerator, MeterChanges, MysteryBag,
+    PatternGenerator, PlaylistGenerator, Style, StyleGenerator, WeightProfiles, WeightedGenerator,
+    GROOVE_SOURCE,
+};
+use kickbeats_cli::grading::{AnswerFormat, AnswerResolution};
+use kickbeats_cli::models::{
+    today, AnswerDrill, ClickDrift, ClickFade, ClickGap, ClickRatio, ComplexityLevel,
+    ComplexityParams, ComplexityScale, DifficultyRange, GhostSteps, Grouping, Overlay, Pattern,
+    PhraseFill, TempoRamp, TempoRange, TimeSignature,
+};
+#[cfg(feature = "midi")]
+use kickbeats_cli::models::{
+    CountIn, GoalProgress, Hemiola, KickDropout, PlanProgress, PracticeSession, SessionGoal,
+    SessionPlan,
+};
+#[cfg(feature = "midi")]
+use kickbeats_cli::storage::SessionReplay;
+use kickbeats_cli::storage::{format_day, write_atomic, PracticeStats, DAY_SECS};
+use kickbeats_cli::visualizer::{format_reveal, NotationFormat, RevealStyle};
+use kickbeats_cli::CoreError;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use std::collections::VecDeque;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
+/// Patterns kept generated ahead of time during practice, so `n` never waits
+#[cfg(feature = "midi")]
+const PREFETCH_DEPTH: usize = 3;
+
+/// Tempo the guided tour starts at, slow enough to follow a first pattern
+#[cfg(feature = "midi")]
+const DEMO_TEMPO: u16 = 90;
+
+/// Flags that only change how patterns sound, which `generate` never plays
+const PLAY_ONLY_FLAGS: [(&str, &str); 4] = [
+    ("overlay", "--overlay"),
+    ("click", "--click"),
+    ("no_click", "--no-click"),
+    ("humanize", "--humanize"),
+];
+
 /// Kickbeats - Rhythm Practice Tool
 ///
 /// A command-line tool to help musicians practice identifying rhythmic patterns by ear.
@@ -19,69 +79,515 @@ use std::sync::Arc;
 #[command(name = "kickbeats")]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Tempo in beats per minute (40-300)
-    #[arg(short, long, default_value_t = 120, value_parser = clap::value_parser!(u16).range(40..=300))]
+    #[arg(short, long, global = true, default_value_t = 120, value_parser = clap::value_parser!(u16).range(40..=300))]
     tempo: u16,
 
     /// Complexity level: simple, medium, or complex
-    #[arg(short, long, default_value = "medium", value_parser = parse_complexity)]
+    #[arg(short, long, global = true, default_value = "medium", value_parser = str::parse::<ComplexityLevel>)]
     complexity: ComplexityLevel,
 
+    /// Complexity on a finer 1-10 scale instead (simple is 2, medium 5, complex 8)
+    #[arg(long, global = true, value_name = "1-10", value_parser = str::parse::<ComplexityScale>, conflicts_with_all = ["complexity", "difficulty", "style", "mystery"])]
+    level: Option<ComplexityScale>,
+
+    /// Generate patterns with a difficulty score (0-100) in this range instead of at --complexity (e.g., 30-50)
+    #[arg(long, global = true, value_name = "MIN-MAX", value_parser = str::parse::<DifficultyRange>, conflicts_with_all = ["complexity", "kicks_min", "kicks_max", "syncopation", "allow_consecutive"])]
+    difficulty: Option<DifficultyRange>,
+
     /// Time signature (e.g., 4/4, 3/4, 6/8, 5/4, 7/8)
-    #[arg(long, default_value = "4/4", value_parser = parse_time_signature)]
+    #[arg(long, global = true, default_value = "4/4", value_parser = str::parse::<TimeSignature>)]
     time_signature: TimeSignature,
-}
 
-/// Parse complexity level from string
-fn parse_complexity(s: &str) -> Result<ComplexityLevel, String> {
-    match s.to_lowercase().as_str() {
-        "simple" | "s" | "1" => Ok(ComplexityLevel::Simple),
-        "medium" | "m" | "2" => Ok(ComplexityLevel::Medium),
-        "complex" | "c" | "3" => Ok(ComplexityLevel::Complex),
-        _ => Err(format!(
-            "Invalid complexity '{}'. Use: simple, medium, or complex",
-            s
-        )),
-    }
-}
+    /// Group the time signature's beats for accents, clicks, and bar lines (e.g., 2+2+3 in 7/8)
+    #[arg(long, global = true, value_name = "GROUPS", value_parser = str::parse::<Grouping>, conflicts_with = "meters")]
+    grouping: Option<Grouping>,
 
-/// Parse time signature from string (e.g., "4/4", "3/4", "6/8")
-fn parse_time_signature(s: &str) -> Result<TimeSignature, String> {
-    let parts: Vec<&str> = s.split('/').collect();
-    if parts.len() != 2 {
-        return Err(format!(
-            "Invalid time signature '{}'. Format should be numerator/denominator (e.g., 4/4, 3/4, 6/8)",
-            s
-        ));
-    }
+    /// How revealed patterns are shown: grid, notes, tab, build, ioi, rhythmic (comma-separated for several)
+    #[arg(long, visible_alias = "notation", global = true, value_name = "STYLES", value_delimiter = ',', default_value = "grid", value_parser = str::parse::<RevealStyle>)]
+    reveal: Vec<RevealStyle>,
 
-    let numerator = parts[0].parse::<u8>().map_err(|_| {
-        format!(
-            "Invalid numerator '{}' in time signature. Must be a positive number",
-            parts[0]
-        )
-    })?;
+    /// When to print in color: auto (a terminal, unless NO_COLOR is set), always, or never
+    #[arg(long, global = true, value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
 
-    let denominator = parts[1].parse::<u8>().map_err(|_| {
-        format!(
-            "Invalid denominator '{}' in time signature. Must be a positive number",
-            parts[1]
-        )
-    })?;
+    /// Pattern generation algorithm
+    #[arg(long, global = true, value_enum, default_value_t = GeneratorKind::Weighted)]
+    generator: GeneratorKind,
 
-    // Validate denominator is a power of 2 (common in music)
-    if ![1, 2, 4, 8, 16].contains(&denominator) {
-        return Err(format!(
-            "Denominator {} is not standard. Use 1, 2, 4, 8, or 16",
-            denominator
-        ));
-    }
+    /// Generate idiomatic 4/4 patterns: rock, funk, latin, dnb, or four-on-floor
+    #[arg(long, global = true, value_parser = str::parse::<Style>, conflicts_with_all = ["generator", "hits", "weights", "kicks_min", "kicks_max", "syncopation", "allow_consecutive"])]
+    style: Option<Style>,
 
-    if numerator == 0 {
-        return Err("Numerator must be at least 1".to_string());
-    }
+    /// Draw each pattern from a random source (generators, styles, missed patterns), revealed after answering
+    #[arg(long, global = true, conflicts_with_all = ["generator", "style", "hits", "weights", "kicks_min", "kicks_max", "syncopation", "allow_consecutive"])]
+    mystery: bool,
+
+    /// Start with this pattern, shared as a pattern string (e.g., 4/4:16:x...x..x..x...x.)
+    #[arg(long, global = true, value_name = "STRING", value_parser = str::parse::<Pattern>)]
+    pattern: Option<Pattern>,
+
+    /// Seed the pattern generator so the same seed always yields the same pattern sequence
+    #[arg(long, global = true, value_name = "N", conflicts_with = "mystery")]
+    seed: Option<u64>,
+
+    /// Folder of MIDI files whose kick drum parts (channel 10) join the --mystery sources
+    #[arg(long, global = true, value_name = "DIR", requires = "mystery")]
+    grooves: Option<PathBuf>,
+    /// Change meter bar by bar within each pattern, 2-8 bars (e.g., 4/4,3/4 or 7/8,7/8,6/8)
+    #[arg(long, global = true, value_name = "METERS", value_delimiter = ',', value_parser = str::parse::<TimeSignature>, conflicts_with = "time_signature")]
+    meters: Option<Vec<TimeSignature>>,
+
+    /// Kicks per measure for the Euclidean generator (default: based on complexity)
+    #[arg(long, global = true, value_parser = clap::value_parser!(u8).range(1..))]
+    hits: Option<u8>,
+
+    /// Fewest kicks per measure for the weighted generator (default: based on complexity)
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u8).range(1..=32))]
+    kicks_min: Option<u8>,
+
+    /// Most kicks per measure for the weighted generator (default: based on complexity)
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u8).range(1..=32))]
+    kicks_max: Option<u8>,
+
+    /// How strongly the weighted generator favors off-beats, 0.0-1.0 (default: based on complexity)
+    #[arg(long, global = true, value_name = "AMOUNT")]
+    syncopation: Option<f32>,
+
+    /// Longest run of back-to-back sixteenth kicks the weighted generator allows [default: 2]
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u8).range(1..=16))]
+    allow_consecutive: Option<u8>,
+
+    /// TOML configuration file [default: ~/.config/kickbeats/config.toml]
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// TOML file of per-time-signature weight tables (e.g., "4/4" = [1.0, 0.2, ...])
+    #[arg(long, global = true, value_name = "FILE")]
+    weights: Option<PathBuf>,
+
+    /// Practice with an installed pack's settings, weights, playlist, and curriculum
+    #[arg(long, global = true, value_name = "NAME")]
+    pack: Option<String>,
+
+    /// Gradually raise the tempo while a pattern loops (e.g., 5bpm/4bars)
+    #[arg(long, value_parser = str::parse::<TempoRamp>)]
+    ramp: Option<TempoRamp>,
+
+    /// Give each new pattern a random tempo from this range, so rhythms are learned apart from their speed (e.g., 90-140)
+    #[arg(long, value_name = "MIN-MAX", value_parser = str::parse::<TempoRange>, conflicts_with_all = ["tempo", "sync"])]
+    #[cfg_attr(feature = "midi", arg(conflicts_with = "plan"))]
+    tempo_range: Option<TempoRange>,
+
+    /// Send MIDI nowhere: patterns keep time without a MIDI device (for scripts and tests)
+    #[arg(long)]
+    no_midi: bool,
+
+    /// Reference figure played alongside the kicks: son-clave, rumba-clave, tresillo, eighths
+    #[arg(long, global = true, value_parser = str::parse::<Overlay>, conflicts_with = "meters")]
+    overlay: Option<Overlay>,
+
+    /// POST a JSON session summary to this URL when practice starts and ends (overrides [webhooks] url)
+    #[cfg(all(feature = "webhooks", feature = "midi"))]
+    #[arg(long, value_name = "URL")]
+    webhook: Option<String>,
+
+    /// Full-screen interface with panes for settings, playback position, history, and the pattern
+    #[cfg(feature = "midi")]
+    #[arg(long, conflicts_with = "drill")]
+    tui: bool,
+
+    /// Read JSON commands on stdin and write JSON events on stdout instead of using the terminal
+    #[cfg(feature = "midi")]
+    #[arg(long, conflicts_with_all = ["drill", "tui"])]
+    headless: bool,
+
+    /// Save every MIDI message sent (count-ins, clicks, kicks, tempo changes) to a .mid file in DIR on quit
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "DIR")]
+    log_midi: Option<PathBuf>,
+
+    /// Save a .kbreplay file of every pattern, command, answer, and tempo change to DIR on quit
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "DIR")]
+    save_replay: Option<PathBuf>,
+
+    /// Keep FILE updated with the pattern grid, tempo, and beat for an OBS text source (JSON if FILE ends in .json)
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "FILE")]
+    stream_file: Option<PathBuf>,
+
+    /// Log patterns, reveals, tempo changes, loop starts, and drift warnings as they happen, in FORMAT: json (one object per line)
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "FORMAT", value_parser = str::parse::<LogFormat>)]
+    log_format: Option<LogFormat>,
+
+    /// Append the --log-format events to FILE instead of stdout
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "FILE", requires = "log_format")]
+    log_file: Option<PathBuf>,
+
+    /// Broadcast session state and every step played as JSON to WebSocket clients on ADDR (e.g., 127.0.0.1:9001)
+    #[cfg(feature = "websocket")]
+    #[arg(long, value_name = "ADDR")]
+    websocket: Option<String>,
+
+    /// Host a class on ADDR (e.g., 0.0.0.0, port 9090 unless given): students who `kickbeats join` hear each pattern and send their answers back
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "ADDR")]
+    host: Option<String>,
+
+    /// With --host, students only see each pattern and answer, while the teacher plays it for the room
+    #[cfg(feature = "midi")]
+    #[arg(long, requires = "host")]
+    quiz_only: bool,
+
+    /// How answers are typed: grid (x...x...) or ioi (3-3-2-4-4, grid answers still work)
+    #[arg(long, value_name = "FORMAT", default_value = "grid", value_parser = str::parse::<AnswerFormat>)]
+    answer_format: AnswerFormat,
+
+    /// Grid answers are typed at: 16th, or 8th to answer sixteenth patterns one eighth at a time
+    #[arg(long, value_name = "RES", default_value = "16th", value_parser = str::parse::<AnswerResolution>)]
+    answer_resolution: AnswerResolution,
+
+    /// Timed-answer drill: stop after N loops, then answer within T seconds (e.g., 4loops/20s)
+    #[arg(long, value_name = "LOOPS/SECS", value_parser = str::parse::<AnswerDrill>)]
+    drill: Option<AnswerDrill>,
+
+    /// Session goal: warmup, steady, mixed, gauntlet, or e.g. "10 medium @ 100+ BPM, 80%"
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "GOAL", value_parser = str::parse::<SessionGoal>)]
+    goal: Option<SessionGoal>,
+
+    /// TOML session plan of timed segments that switch complexity, tempo, and meter
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "FILE", conflicts_with = "headless")]
+    plan: Option<PathBuf>,
+
+    /// Don't track missed patterns or bring them back for spaced-repetition review
+    #[cfg(feature = "midi")]
+    #[arg(long)]
+    no_review: bool,
+
+    /// Auto-advance: play each pattern N times, then move on to a new one (press a to toggle)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..=64), conflicts_with = "drill")]
+    #[cfg_attr(feature = "midi", arg(conflicts_with = "headless"))]
+    loops: Option<u32>,
+
+    /// Reveal each pattern before auto-advance moves on from it
+    #[arg(long, conflicts_with = "drill")]
+    #[cfg_attr(feature = "midi", arg(conflicts_with = "headless"))]
+    reveal_on_advance: bool,
+
+    /// Loops each step of a morph holds before the next step changes (press o to morph)
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=16))]
+    morph_bars: u32,
+
+    /// MIDI output port to play on (any part of its name) [default: the first port, or midi.port]
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "NAME")]
+    midi_port: Option<String>,
+
+    /// Share tempo and bar lines with other Ableton Link apps on the network
+    #[cfg(feature = "link")]
+    #[arg(long)]
+    link: bool,
+
+    /// Send the click and count-in to this MIDI output port instead (any part of its name), e.g. another instrument in a DAW
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "NAME", conflicts_with = "virtual_port")]
+    click_port: Option<String>,
+
+    /// Create a virtual MIDI output port with this name and play on it (macOS and Linux)
+    #[cfg(feature = "midi")]
+    #[arg(long, value_name = "NAME", conflicts_with = "midi_port")]
+    virtual_port: Option<String>,
+
+    /// Start with the click track on, even if the config file turns it off
+    #[arg(long, global = true, overrides_with = "no_click")]
+    click: bool,
+
+    /// Start with the click track muted (press m to toggle it)
+    #[arg(long, global = true, overrides_with = "click")]
+    no_click: bool,
+
+    /// How hard the kicks play, from 0 (silent) to 127 [default: 100] (change it live with ] and [)
+    #[arg(long, value_name = "VELOCITY", value_parser = clap::value_parser!(u8).range(0..=127))]
+    kick_velocity: Option<u8>,
+
+    /// How hard the click plays, from 0 (silent) to 127 [default: 80] (change it live with + and -)
+    #[arg(long, value_name = "VELOCITY", value_parser = clap::value_parser!(u8).range(0..=127))]
+    click_velocity: Option<u8>,
+
+    /// Make the click quieter by STEP velocity after each answer at THRESHOLD or better, louder after misses (e.g., 90%/10)
+    #[arg(long, value_name = "THRESHOLD/STEP", value_parser = str::parse::<ClickFade>)]
+    click_fade: Option<ClickFade>,
+
+    /// Tempo canon: the click plays CLICKS evenly spaced beats for every BEATS of the pattern (e.g., 3:4)
+    #[arg(long, value_name = "CLICKS:BEATS", value_parser = str::parse::<ClickRatio>)]
+    click_ratio: Option<ClickRatio>,
+
+    /// Tempo trainer: the click drifts up to BPM sharp and flat over every BARS bars while you hold the pattern's tempo (e.g., 4bpm/16bars)
+    #[arg(long, value_name = "BPM/BARS", value_parser = str::parse::<ClickDrift>, conflicts_with_all = ["click_ratio", "sync"])]
+    click_drift: Option<ClickDrift>,
+
+    /// Metric modulation trainer: the click switches to an implied pulse on its own note every BARS bars (e.g., pulse=dotted-quarter bars=2)
+    #[arg(long, value_name = "pulse=NOTE bars=N", num_args = 1..=2, conflicts_with_all = ["click_ratio", "click_drift"])]
+    hemiola: Option<Vec<String>>,
+
+    /// Internal-time trainer: the click sounds for ON bars, then drops out for OFF bars, over and over (e.g., 2on/2off)
+    #[arg(long, value_name = "ON/OFF", value_parser = str::parse::<ClickGap>, conflicts_with = "sync")]
+    click_gap: Option<ClickGap>,
+
+    /// Silence the kicks too during --click-gap's silent bars
+    #[arg(long, requires = "click_gap")]
+    gap_kicks: bool,
 
-    Ok(TimeSignature::new(numerator, denominator))
+    /// Dropout trainer: the kicks go silent for the last BARS of every EVERY bars while the click plays on (e.g., bars=2 every=4)
+    #[arg(long, value_name = "bars=N every=N", num_args = 1..=2, conflicts_with = "sync")]
+    dropout: Option<Vec<String>>,
+
+    /// Fill trainer: the last bar of every N plays a busier variation of the pattern (e.g., every=4)
+    #[arg(long, value_name = "every=N", value_parser = str::parse::<PhraseFill>, conflicts_with_all = ["sync", "meters"])]
+    fills: Option<PhraseFill>,
+
+    /// Variation trainer: off-beat kicks sound only some loops, rolled fresh each time (e.g., chance=60)
+    #[arg(long, value_name = "chance=PERCENT", value_parser = str::parse::<GhostSteps>)]
+    ghosts: Option<GhostSteps>,
+
+    /// Sound a cue and count bars on the status line every BARS bars (e.g., 4 or 8)
+    #[arg(long, value_name = "BARS", value_parser = clap::value_parser!(u32).range(2..=64), conflicts_with = "meters")]
+    phrase: Option<u32>,
+
+    /// Send MIDI clock (24 PPQN), start, and stop so drum machines and DAWs can follow the tempo
+    #[arg(long)]
+    send_clock: bool,
+
+    /// Follow MIDI clock, start, and stop from an input port (any part of its name) [default: first port]
+    #[arg(long, value_name = "PORT", num_args = 0..=1, require_equals = true, default_missing_value = "", conflicts_with = "send_clock")]
+    sync: Option<String>,
+
+    /// Grade an electronic kit on an input port (any part of its name), each limb against its own lane [default: first port]
+    #[arg(long, value_name = "PORT", num_args = 0..=1, require_equals = true, default_missing_value = "")]
+    #[cfg_attr(feature = "midi", arg(conflicts_with = "headless"))]
+    kit_input: Option<String>,
+
+    /// Play the kit's hits back through the MIDI output as they arrive, for kits without their own sound
+    #[arg(long, requires = "kit_input")]
+    kit_echo: bool,
+
+    /// MIDI note for kicks [default: 36, or the config file's midi.kick_note]
+    #[arg(long, value_name = "NOTE", value_parser = clap::value_parser!(u8).range(0..=127))]
+    kick_note: Option<u8>,
+
+    /// MIDI note for the click and count-in [default: 37, or midi.click_note]
+    #[arg(long, value_name = "NOTE", value_parser = clap::value_parser!(u8).range(0..=127))]
+    click_note: Option<u8>,
+
+    /// MIDI channel to play on, 1-16 [default: 10, or midi.channel]
+    #[arg(long, value_name = "CHANNEL", value_parser = clap::value_parser!(u8).range(1..=16))]
+    midi_channel: Option<u8>,
+
+    /// MIDI channel for the click and count-in, 1-16, so they can be panned or voiced apart from the kicks [default: --midi-channel]
+    #[arg(long, value_name = "CHANNEL", value_parser = clap::value_parser!(u8).range(1..=16))]
+    click_channel: Option<u8>,
+
+    /// Random kick timing and velocity variation, from 0 (machine-exact) to 100
+    #[arg(long, global = true, value_name = "AMOUNT", default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=100))]
+    humanize: u8,
+
+    /// Seconds of silence (with a countdown) before each new pattern's count-in
+    #[arg(long, value_name = "SECONDS", default_value_t = 0, value_parser = clap::value_parser!(u32).range(0..=60))]
+    listen_delay: u32,
+
+    /// Sound an extra count-in click before a pattern that may sound like a recent one
+    #[arg(long)]
+    repeat_cue: bool,
+
+    /// Bars of count-in before each new pattern, 0 to start right away
+    #[arg(long, value_name = "BARS", default_value_t = 1, value_parser = clap::value_parser!(u32).range(0..=4))]
+    count_in: u32,
+
+    /// Click the eighths between the pulses in the count-in's last bar
+    #[arg(long)]
+    count_in_subdivide: bool,
+
+    /// Voice the count-in's last bar on consecutive notes from NOTE up, one per pulse (e.g., spoken "one, two, ..." samples)
+    #[arg(long, value_name = "NOTE", value_parser = clap::value_parser!(u8).range(0..=127))]
+    count_off: Option<u8>,
+}
+
+/// Available pattern generation algorithms
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum GeneratorKind {
+    /// Weighted random kicks following the metrical hierarchy
+    Weighted,
+    /// Evenly spread kicks (Bjorklund), e.g. E(5,16)
+    Euclidean,
+}
+
+/// When output is colored
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ColorChoice {
+    /// Color a terminal, unless NO_COLOR is set or TERM is dumb
+    Auto,
+    /// Always color, even when piped or with NO_COLOR set
+    Always,
+    /// Never color
+    Never,
+}
+
+/// Image formats `lilypond` can engrave notation to
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum RenderFormat {
+    /// PNG image
+    Png,
+    /// PDF document
+    Pdf,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Practice interactively with MIDI playback (the default)
+    Play,
+    /// Print generated patterns without playing them (e.g., for worksheets)
+    Generate {
+        /// Number of patterns to print
+        #[arg(short = 'n', long, default_value_t = 1)]
+        count: u32,
+    },
+    /// Write a pattern as LilyPond or ABC notation source, optionally engraved to PNG or PDF
+    Notation {
+        /// Notation to write: lilypond or abc
+        #[arg(short, long, default_value = "lilypond", value_parser = str::parse::<NotationFormat>)]
+        format: NotationFormat,
+        /// File to write the source to instead of printing it (e.g., groove.ly)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Engrave the LilyPond source with the `lilypond` program (needs --output)
+        #[arg(long, value_enum, requires = "output")]
+        render: Option<RenderFormat>,
+    },
+    /// Render a pattern's kicks and click to a WAV file, for practicing away from MIDI (e.g., on a phone)
+    Audio {
+        /// WAV file to write (e.g., groove.wav)
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Number of times the pattern loops, after a bar of count-in
+        #[arg(short = 'n', long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..=512))]
+        loops: u32,
+        /// WAV file to play for each kick [default: the config file's samples.kick, or the built-in kick]
+        #[arg(long, value_name = "WAV")]
+        kick_sample: Option<PathBuf>,
+        /// WAV file to play for each click [default: the config file's samples.click, or the built-in click]
+        #[arg(long, value_name = "WAV")]
+        click_sample: Option<PathBuf>,
+        /// Samples per second to write, e.g. 48000 to match a phone or interface
+        #[arg(long, value_name = "HZ", default_value_t = 44_100, value_parser = clap::value_parser!(u32).range(8_000..=192_000))]
+        sample_rate: u32,
+    },
+    /// Install, list, or export practice packs (shareable bundles of settings and weights)
+    Pack {
+        #[command(subcommand)]
+        action: PackAction,
+    },
+    /// Show practice trends from the saved history (streaks, accuracy, time by complexity)
+    Stats {
+        /// Number of recent days to list
+        #[arg(long, default_value_t = 7, value_parser = clap::value_parser!(u32).range(1..=366))]
+        days: u32,
+        /// Number of recent weeks to list
+        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..=104))]
+        weeks: u32,
+    },
+    /// List patterns noted or tagged with the # command in saved sessions, newest first
+    Notes {
+        /// Only list patterns with this tag (e.g., tricky or #tricky)
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Play back a session saved with --save-replay or logged with --log-format json: its patterns, commands, answers, and tempo changes
+    Replay {
+        /// Replay file or event log (e.g., kickbeats-session-1760600000.kbreplay, session.jsonl)
+        file: PathBuf,
+        /// List everything that happened straight away, without pacing or sound
+        #[arg(long)]
+        summary: bool,
+        /// Pace events this many times as fast as they happened, 0.25-16 (patterns keep their tempo)
+        #[arg(
+            long,
+            value_name = "FACTOR",
+            default_value_t = 1.0,
+            conflicts_with = "summary"
+        )]
+        speed: f64,
+    },
+    /// Join a class hosted with --host: hear each of the teacher's patterns and send typed answers back
+    Join {
+        /// Teacher's machine, with its port if not 9090 (e.g., 192.168.1.20)
+        addr: String,
+        /// Name your teacher sees [default: your user name]
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Take a narrated two-minute tour: hear, reveal, and change patterns as each step is explained
+    Demo,
+    /// Print a shell completion script (e.g., `kickbeats completions zsh > _kickbeats`)
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the man page, or write pages for every subcommand to a directory
+    Man {
+        /// Directory to write kickbeats.1 and subcommand pages into
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PackAction {
+    /// Verify a pack file and install it for use with --pack
+    Install {
+        /// Pack file (e.g., funk101.kbpack)
+        file: PathBuf,
+        /// Replace an installed copy even if it is a newer version or signed by a different key
+        #[arg(long)]
+        force: bool,
+    },
+    /// List installed packs
+    List,
+    /// Bundle your config file, --weights, a playlist, and a curriculum into a pack file
+    Export {
+        /// Pack file to write (e.g., funk101.kbpack)
+        file: PathBuf,
+        /// Name students select the pack by (lowercase letters, digits, '-', '_')
+        #[arg(long)]
+        name: String,
+        /// Pack release, as dotted numbers
+        #[arg(long = "pack-version", value_name = "VERSION", default_value = "1.0.0")]
+        version: String,
+        /// Who made the pack
+        #[arg(long)]
+        author: Option<String>,
+        /// One-line summary shown by `kickbeats pack list`
+        #[arg(long)]
+        description: Option<String>,
+        /// Patterns to play in order instead of generating them, one per line
+        #[arg(long, value_name = "FILE")]
+        playlist: Option<PathBuf>,
+        /// Stages to work through, as `[[curriculum]]` tables
+        #[arg(long, value_name = "FILE")]
+        curriculum: Option<PathBuf>,
+        /// Teacher key to sign the pack with, created if missing [default: ~/.config/kickbeats/teacher.key]
+        #[arg(long, value_name = "FILE")]
+        key: Option<PathBuf>,
+    },
 }
 
 fn main() {
@@ -95,35 +601,1095 @@ fn main() {
     .expect("Error setting Ctrl-C handler");
 
     // Run the application
-    if let Err(e) = run() {
+    if let Err(e) = run(&running) {
         eprintln!("\nError: {}", e);
+        if let Some(hint) = e.downcast_ref::<KickbeatsError>().and_then(error_hint) {
+            eprintln!("{}", hint);
+        }
         std::process::exit(1);
     }
 }
 
-fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// What the player can do about a library error, shown below its message
+fn error_hint(error: &KickbeatsError) -> Option<&'static str> {
+    match error {
+        #[cfg(feature = "midi")]
+        KickbeatsError::Midi(_) => Some(
+            "Start your synth or connect a MIDI device, then choose it with --midi-port \
+             (or create one with --virtual-port, or play silently with --no-midi).",
+        ),
+        KickbeatsError::Config(_) => {
+            Some("Check the value against `kickbeats --help`, or the config file it came from.")
+        }
+        KickbeatsError::Network(_) => {
+            Some("Another program may already be using that port; pick another for --websocket or --host.")
+        }
+        KickbeatsError::Io { source, .. } if source.kind() == io::ErrorKind::PermissionDenied => {
+            Some("Check the file's permissions, or point kickbeats at another location.")
+        }
+        _ => None,
+    }
+}
+
+fn run(running: &Arc<AtomicBool>) -> Result<(), Box<dyn std::error::Error>> {
     // Parse command-line arguments
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    match args.command {
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(shell, &mut Args::command(), "kickbeats", &mut io::stdout());
+            return Ok(());
+        }
+        Some(Command::Man { ref out_dir }) => return run_man(out_dir.clone()),
+        _ => {}
+    }
+
+    // Pack values, then config file values, fill in whatever wasn't given on the command line
+    let pack = args
+        .pack
+        .as_deref()
+        .map(PracticePack::load_installed)
+        .transpose()?;
+    let mut config = load_config(&args)?;
+    if let Some(pack) = &pack {
+        config = pack.config.clone().or(config);
+    }
+    apply_practice_config(&mut args, &matches, &config.practice);
+    if let Some(level) = args.level {
+        args.complexity = level.level();
+    }
+    if let Some(meters) = &args.meters {
+        args.time_signature = meters[0];
+    }
+    // Patterns after a shared one follow its meter unless another was asked for
+    if let Some(pattern) = &mut args.pattern {
+        pattern.complexity_level = args.complexity;
+        if is_default(&matches, "time_signature") && args.meters.is_none() {
+            args.time_signature = pattern.time_signature;
+        }
+    }
+    if let Some(grouping) = args.grouping {
+        args.time_signature = args.time_signature.with_grouping(grouping)?;
+        if let Some(pattern) = &mut args.pattern {
+            pattern.regroup(args.time_signature);
+        }
+    }
+
+    match args.command {
+        Some(Command::Generate { count }) => {
+            if let Some((_, flag)) = PLAY_ONLY_FLAGS
+                .iter()
+                .find(|(id, _)| !is_default(&matches, id))
+            {
+                return Err(
+                    format!("{} only applies when patterns play, not to generate", flag).into(),
+                );
+            }
+            run_generate(&args, count, pack.as_ref(), palette(&args, &config))
+        }
+        Some(Command::Notation {
+            format,
+            ref output,
+            render,
+        }) => run_notation(&args, format, output.as_deref(), render, pack.as_ref()),
+        Some(Command::Audio {
+            ref output,
+            loops,
+            ref kick_sample,
+            ref click_sample,
+            sample_rate,
+        }) => {
+            let samples = SamplesSection {
+                kick: kick_sample.clone(),
+                click: click_sample.clone(),
+                ..SamplesSection::default()
+            };
+            let config = Config {
+                samples,
+                ..Config::default()
+            }
+            .or(config);
+            run_audio(&args, &config, output, loops, sample_rate, pack.as_ref())
+        }
+        Some(Command::Pack { ref action }) => run_pack(action, &args, config, pack.as_ref()),
+        Some(Command::Stats { days, weeks }) => run_stats(&config, days, weeks),
+        Some(Command::Notes { ref tag }) => run_notes(&config, tag.as_deref()),
+        Some(Command::Replay {
+            ref file,
+            summary,
+            speed,
+        }) => run_replay(&args, &config, file, summary, speed, running),
+        Some(Command::Join { ref addr, ref name }) => {
+            run_join(&args, &config, addr, name.as_deref(), running)
+        }
+        Some(Command::Demo) => run_demo(&args, &config, running),
+        _ => run_play(&args, &config, pack.as_ref(), running),
+    }
+}
+
+/// Install, list, or export practice packs
+fn run_pack(
+    action: &PackAction,
+    args: &Args,
+    mut config: Config,
+    active: Option<&PracticePack>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        PackAction::Install { file, force } => {
+            let (pack, path) = PracticePack::install(file, *force)?;
+            println!(
+                "Installed {} {} to {}",
+                pack.info.name,
+                pack.info.version,
+                path.display()
+            );
+            println!("Signed with teacher key {}", pack.info.fingerprint());
+            println!("Practice with it: kickbeats --pack {}", pack.info.name);
+        }
+        PackAction::List => {
+            let packs = PracticePack::installed();
+            if packs.is_empty() {
+                println!("No practice packs installed (kickbeats pack install <FILE>)");
+            }
+            for pack in packs {
+                let info = pack.info;
+                match info.description {
+                    Some(description) => {
+                        println!("{} {} - {}", info.name, info.version, description)
+                    }
+                    None => println!("{} {}", info.name, info.version),
+                }
+            }
+        }
+        PackAction::Export {
+            file,
+            name,
+            version,
+            author,
+            description,
+            playlist,
+            curriculum,
+            key,
+        } => {
+            // Ports, kits, samples, trackers, history locations, and colors differ between students, so they stay in each one's own config
+            config.midi.port = None;
+            config.webhooks = Default::default();
+            config.kit = Default::default();
+            config.samples = Default::default();
+            config.storage = Default::default();
+            config.theme = Default::default();
+            let weights = match &args.weights {
+                Some(path) => WeightProfiles::load(path)?,
+                None => active.map(|pack| pack.weights.clone()).unwrap_or_default(),
+            };
+            let playlist = match playlist {
+                Some(path) => {
+                    let text = std::fs::read_to_string(path)
+                        .map_err(|e| KickbeatsError::io("read playlist", path, e))?;
+                    let meter = config
+                        .practice
+                        .time_signature
+                        .unwrap_or_else(TimeSignature::four_four);
+                    PracticePack::parse_playlist(&text, meter)?
+                }
+                None => active.map(|pack| pack.playlist.clone()).unwrap_or_default(),
+            };
+            let curriculum = match curriculum {
+                Some(path) => {
+                    let text = std::fs::read_to_string(path)
+                        .map_err(|e| KickbeatsError::io("read curriculum", path, e))?;
+                    Some(PracticePack::parse_curriculum(&text)?)
+                }
+                None => active.and_then(|pack| pack.curriculum.clone()),
+            };
+
+            let mut pack = PracticePack::new(name, version, config, weights)?;
+            if let Some(author) = author {
+                pack = pack.with_author(author);
+            }
+            if let Some(description) = description {
+                pack = pack.with_description(description);
+            }
+            if !playlist.is_empty() {
+                pack = pack.with_playlist(playlist);
+            }
+            if let Some(curriculum) = curriculum {
+                pack = pack.with_curriculum(curriculum);
+            }
+
+            let key_path = match key {
+                Some(path) => path.clone(),
+                None => TeacherKey::default_path().ok_or_else(|| {
+                    KickbeatsError::Config(
+                        "Can't find a config directory for the teacher key; pass --key".to_string(),
+                    )
+                })?,
+            };
+            let (key, created) = TeacherKey::load_or_create(&key_path)?;
+            if created {
+                println!(
+                    "Created teacher key {} at {}; back it up, since students can only update \
+                     packs signed with the same key",
+                    key.fingerprint(),
+                    key_path.display()
+                );
+            }
+            let pack = pack.signed(&key);
+            write_atomic(file, pack.to_toml_string())
+                .map_err(|e| KickbeatsError::io("write practice pack", file, e))?;
+            println!(
+                "Wrote {} {} to {}, signed with teacher key {}",
+                name,
+                version,
+                file.display(),
+                key.fingerprint()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Whether an argument kept its default because it wasn't given on the command line
+fn is_default(matches: &ArgMatches, id: &str) -> bool {
+    matches!(
+        matches.value_source(id),
+        Some(ValueSource::DefaultValue) | None
+    )
+}
+
+/// Replace default tempo, complexity, and time signature with config file values
+fn apply_practice_config(args: &mut Args, matches: &ArgMatches, practice: &PracticeSection) {
+    if let Some(tempo) = practice.tempo.filter(|_| is_default(matches, "tempo")) {
+        args.tempo = tempo;
+    }
+    if let Some(complexity) = practice
+        .complexity
+        .filter(|_| is_default(matches, "complexity"))
+    {
+        args.complexity = complexity;
+    }
+    if let Some(time_signature) = practice
+        .time_signature
+        .filter(|_| is_default(matches, "time_signature"))
+    {
+        args.time_signature = time_signature;
+    }
+}
+
+/// Build the selected pattern generator, applying `--mystery`, `--style`, `--weights`, or `--hits`,
+/// joining its bars into `--meters` phrases, and keeping patterns in the `--difficulty` range
+fn build_generator(
+    args: &Args,
+    pack: Option<&PracticePack>,
+) -> Result<Box<dyn PatternGenerator>, String> {
+    let mut generator = base_generator(args, pack)?;
+    if let Some(meters) = &args.meters {
+        generator = Box::new(MeterChanges::new(generator, meters.clone())?);
+    }
+    match args.difficulty {
+        Some(range) => Ok(Box::new(DifficultyTarget::new(generator, range))),
+        None => Ok(generator),
+    }
+}
+
+/// The single-bar generator selected by `--mystery`, `--style`, or `--generator`
+///
+/// A `--pack` playlist replaces the weighted generator unless `--weights` is given;
+/// otherwise the pack's weights apply to it.
+fn base_generator(
+    args: &Args,
+    pack: Option<&PracticePack>,
+) -> Result<Box<dyn PatternGenerator>, String> {
+    if args.mystery {
+        let mut bag = MysteryBag::standard();
+        if let Some(dir) = &args.grooves {
+            let grooves = GrooveGenerator::load_dir(dir)?;
+            bag = bag.with_source(GROOVE_SOURCE, Box::new(grooves), 2.0);
+        }
+        return Ok(Box::new(bag));
+    }
+    if let Some(style) = args.style {
+        return Ok(Box::new(StyleGenerator::with_rng(
+            style,
+            generator_rng(args),
+        )));
+    }
+
+    match args.generator {
+        GeneratorKind::Weighted => {
+            if args.hits.is_some() {
+                return Err("--hits only applies to --generator euclidean".to_string());
+            }
+            let weighted = WeightedGenerator::with_rng(generator_rng(args));
+            let weighted = match complexity_params(args)? {
+                Some(params) => weighted.with_params(params),
+                None => weighted,
+            };
+            if let Some(path) = &args.weights {
+                let profiles = WeightProfiles::load(path)?;
+                return Ok(Box::new(weighted.with_profiles(profiles)));
+            }
+            match pack {
+                Some(pack) if !pack.playlist.is_empty() => {
+                    let patterns = pack
+                        .playlist
+                        .iter()
+                        .map(|entry| (entry.steps.clone(), entry.time_signature))
+                        .collect();
+                    Ok(Box::new(PlaylistGenerator::new(patterns)?))
+                }
+                Some(pack) if !pack.weights.is_empty() => {
+                    Ok(Box::new(weighted.with_profiles(pack.weights.clone())))
+                }
+                _ => Ok(Box::new(weighted)),
+            }
+        }
+        GeneratorKind::Euclidean => {
+            if args.weights.is_some() {
+                return Err("--weights only applies to --generator weighted".to_string());
+            }
+            if complexity_params(args)?.is_some() {
+                return Err(
+                    "--level, --kicks-min, --kicks-max, --syncopation, and --allow-consecutive only apply to --generator weighted"
+                        .to_string(),
+                );
+            }
+            let generator = EuclideanGenerator::with_rng(generator_rng(args));
+            match args.hits {
+                Some(hits) => Ok(Box::new(generator.with_hits(usize::from(hits)))),
+                None => Ok(Box::new(generator)),
+            }
+        }
+    }
+}
+
+/// Random source for the generator: seeded from `--seed` so the pattern sequence
+/// repeats (a teacher and student hear the same patterns), otherwise from entropy
+fn generator_rng(args: &Args) -> StdRng {
+    match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// Custom generation parameters from `--level`, `--kicks-min`, `--kicks-max`,
+/// `--syncopation`, and `--allow-consecutive`, filling in the rest from the
+/// `--level` point or the `--complexity` preset
+///
+/// `None` when none of them are given.
+fn complexity_params(args: &Args) -> Result<Option<ComplexityParams>, CoreError> {
+    if args.level.is_none()
+        && args.kicks_min.is_none()
+        && args.kicks_max.is_none()
+        && args.syncopation.is_none()
+        && args.allow_consecutive.is_none()
+    {
+        return Ok(None);
+    }
+    let preset = match args.level {
+        Some(level) => level.params(),
+        None => args.complexity.params(),
+    };
+    let kicks_min = args.kicks_min.map(usize::from);
+    let kicks_max = args.kicks_max.map(usize::from);
+    // A bound given on its own moves the preset's other bound out of its way
+    let min_kicks = kicks_min.unwrap_or(preset.min_kicks.min(kicks_max.unwrap_or(usize::MAX)));
+    let max_kicks = kicks_max.unwrap_or(preset.max_kicks.max(min_kicks));
+    ComplexityParams::new(
+        min_kicks,
+        max_kicks,
+        args.syncopation.unwrap_or(preset.syncopation),
+        args.allow_consecutive
+            .map_or(preset.max_consecutive, usize::from),
+    )
+    .map(Some)
+}
+
+/// Colors for output, in the config file's theme, as `--color` asks
+fn palette(args: &Args, config: &Config) -> Palette {
+    let enabled = match args.color {
+        ColorChoice::Auto => stdout_supports_color(),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+    if enabled {
+        Palette::new(Theme::from_section(&config.theme))
+    } else {
+        Palette::plain()
+    }
+}
+
+/// Load the `--config` file, or the default config file if there is one
+fn load_config(args: &Args) -> Result<Config, KickbeatsError> {
+    match &args.config {
+        Some(path) => Config::load(path),
+        None => Config::load_default(),
+    }
+}
+
+/// The config file `--config` names, or the default one
+#[cfg(feature = "midi")]
+fn config_path(args: &Args) -> Option<PathBuf> {
+    args.config.clone().or_else(Config::default_path)
+}
+
+/// Note and channel mapping from flags, falling back to the config file and
+/// then the General MIDI defaults
+#[cfg(feature = "midi")]
+fn midi_mapping(args: &Args, midi: &MidiSection) -> Result<MidiMapping, KickbeatsError> {
+    let defaults = MidiMapping::default();
+    let mapping = MidiMapping::new(
+        args.kick_note
+            .or(midi.kick_note)
+            .unwrap_or(defaults.kick_note),
+        args.click_note
+            .or(midi.click_note)
+            .unwrap_or(defaults.click_note),
+        args.midi_channel
+            .or(midi.channel)
+            .map_or(defaults.channel, |channel| channel - 1),
+    )?;
+    match args.click_channel {
+        Some(channel) => mapping.with_click_channel(channel - 1),
+        None => Ok(mapping),
+    }
+}
+
+/// Kit notes for each limb from the config file, falling back to the General
+/// MIDI drum map
+#[cfg(feature = "midi")]
+fn kit_map(kit: &KitSection) -> Result<KitMap, KickbeatsError> {
+    let defaults = KitMap::default();
+    KitMap::new(
+        kit.kick.clone().unwrap_or(defaults.kick),
+        kit.snare.clone().unwrap_or(defaults.snare),
+        kit.hat.clone().unwrap_or(defaults.hat),
+    )
+}
+
+/// The first pattern of a session: the `--pattern` given, or else a fresh one
+fn first_pattern(
+    args: &Args,
+    generator: &mut dyn PatternGenerator,
+    time_signature: TimeSignature,
+    complexity: ComplexityLevel,
+) -> Result<Pattern, CoreError> {
+    match &args.pattern {
+        Some(pattern) => Ok(pattern.clone()),
+        None => generator.generate(time_signature, complexity, &VecDeque::new()),
+    }
+}
+
+/// Print practice trends from the saved session history
+fn run_stats(config: &Config, days: u32, weeks: u32) -> Result<(), Box<dyn std::error::Error>> {
+    let backend = config.storage.backend.unwrap_or_default();
+    let storage = backend.open(config.storage.dir.clone())?;
+    let records = storage.sessions()?;
+    if records.is_empty() {
+        println!("No practice sessions saved yet. Play a session and check back!");
+        return Ok(());
+    }
+    let today = today();
+    print!("{}", PracticeStats::new(&records, today, days, weeks));
+    let review = storage.review_queue()?;
+    if !review.is_empty() {
+        println!(
+            "\nReview queue: {} missed pattern{}, {} due today",
+            review.len(),
+            if review.len() == 1 { "" } else { "s" },
+            review.due_count(today)
+        );
+    }
+    Ok(())
+}
+
+/// List the patterns noted in the saved history, only those tagged `tag` if given
+fn run_notes(config: &Config, tag: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let backend = config.storage.backend.unwrap_or_default();
+    let storage = backend.open(config.storage.dir.clone())?;
+    let mut shown = 0;
+    for record in storage.sessions()?.iter().rev() {
+        for noted in record.notes.iter().rev() {
+            let note = noted.note();
+            if tag.is_some_and(|tag| !note.has_tag(tag)) {
+                continue;
+            }
+            println!(
+                "{}  #{:<3}  {}  {}",
+                format_day(record.started_at / DAY_SECS),
+                noted.number,
+                noted.pattern,
+                note
+            );
+            shown += 1;
+        }
+    }
+    if shown == 0 {
+        match tag {
+            Some(tag) => println!("No saved patterns tagged #{}.", tag.trim_start_matches('#')),
+            None => println!("No noted patterns saved yet. Press # during practice to note one."),
+        }
+    }
+    Ok(())
+}
+
+/// Print `count` unique patterns with their metadata
+fn run_generate(
+    args: &Args,
+    count: u32,
+    pack: Option<&PracticePack>,
+    palette: Palette,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut generator = build_generator(args, pack)?;
+    let mut history = VecDeque::new();
+
+    for i in 0..count {
+        let pattern = if i == 0 {
+            first_pattern(
+                args,
+                generator.as_mut(),
+                args.time_signature,
+                args.complexity,
+            )?
+        } else {
+            generator
+                .generate_unique(args.time_signature, args.complexity, &history)?
+                .0
+        };
+
+        if i > 0 {
+            println!();
+        }
+        print!(
+            "{}",
+            palette.reveal(&format_reveal(&pattern, args.tempo, &args.reveal))
+        );
+
+        if history.len() >= 20 {
+            history.pop_front();
+        }
+        history.push_back(pattern);
+    }
+
+    Ok(())
+}
+
+/// Write the first pattern (--pattern, or a generated one) as notation
+/// source, engraving it with `lilypond` if asked
+fn run_notation(
+    args: &Args,
+    format: NotationFormat,
+    output: Option<&Path>,
+    render: Option<RenderFormat>,
+    pack: Option<&PracticePack>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if render.is_some() && format != NotationFormat::Lilypond {
+        return Err(
+            "--render engraves with LilyPond. Use --format lilypond, or open the ABC in an ABC editor"
+                .into(),
+        );
+    }
+    let mut generator = build_generator(args, pack)?;
+    let pattern = first_pattern(
+        args,
+        generator.as_mut(),
+        args.time_signature,
+        args.complexity,
+    )?;
+    let source = format.render(&pattern, args.tempo);
+
+    let Some(path) = output else {
+        print!("{}", source);
+        return Ok(());
+    };
+    write_atomic(path, &source).map_err(|e| KickbeatsError::io("write", path, e))?;
+    println!("Wrote {}", path.display());
+
+    if let Some(render) = render {
+        println!("Engraved {}", engrave(path, render)?.display());
+    }
+    Ok(())
+}
+
+/// Render the first pattern to a WAV file with its count-in and `loops` loops,
+/// in the config file's samples and gains
+#[cfg(feature = "wav")]
+fn run_audio(
+    args: &Args,
+    config: &Config,
+    output: &Path,
+    loops: u32,
+    sample_rate: u32,
+    pack: Option<&PracticePack>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut renderer = WavRenderer::new()
+        .with_humanize(args.humanize)
+        .with_sample_rate(sample_rate);
+    for (voice, (_, path, gain)) in DrumVoice::ALL.into_iter().zip(config.samples.voices()) {
+        if let Some(path) = path {
+            renderer = renderer.with_sample(voice, Sample::load(path)?);
+        }
+        if let Some(gain) = gain {
+            renderer = renderer.with_gain(voice, gain)?;
+        }
+    }
+    if let Some(overlay) = args.overlay {
+        renderer = renderer.with_overlay(overlay);
+    }
+    let click = if args.click || args.no_click {
+        args.click
+    } else {
+        config.click.enabled.unwrap_or(true)
+    };
+    if !click {
+        renderer = renderer.without_click();
+    }
+
+    let mut generator = build_generator(args, pack)?;
+    let pattern = first_pattern(
+        args,
+        generator.as_mut(),
+        args.time_signature,
+        args.complexity,
+    )?;
+    let wav = renderer.to_wav(&pattern, args.tempo, loops)?;
+    write_atomic(output, wav).map_err(|e| KickbeatsError::io("write", output, e))?;
+    println!(
+        "Wrote {}: {} loop{} at {} BPM, after a bar of count-in",
+        output.display(),
+        loops,
+        if loops == 1 { "" } else { "s" },
+        args.tempo
+    );
+    Ok(())
+}
 
-    let tempo_bpm = args.tempo;
-    let complexity = args.complexity;
-    let time_signature = args.time_signature;
+#[cfg(not(feature = "wav"))]
+fn run_audio(
+    _args: &Args,
+    _config: &Config,
+    _output: &Path,
+    _loops: u32,
+    _sample_rate: u32,
+    _pack: Option<&PracticePack>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err("This build of kickbeats can't write audio (built without the `wav` feature).".into())
+}
+
+/// Run `lilypond` on the source at `path`, returning the image it wrote
+/// next to it
+fn engrave(path: &Path, render: RenderFormat) -> Result<PathBuf, String> {
+    let (flag, extension) = match render {
+        RenderFormat::Png => ("--png", "png"),
+        RenderFormat::Pdf => ("--pdf", "pdf"),
+    };
+    let stem = path.with_extension("");
+    let status = std::process::Command::new("lilypond")
+        .arg(flag)
+        .arg("--loglevel=ERROR")
+        .arg("-o")
+        .arg(&stem)
+        .arg(path)
+        .status()
+        .map_err(|e| {
+            format!(
+                "Failed to run lilypond: {}. Install LilyPond (https://lilypond.org) or leave off --render",
+                e
+            )
+        })?;
+    if !status.success() {
+        return Err(format!("lilypond failed to engrave {}", path.display()));
+    }
+    Ok(stem.with_extension(extension))
+}
+
+/// Render man pages with clap_mangen
+fn run_man(out_dir: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let command = Args::command();
+    match out_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(&dir)?;
+            clap_mangen::generate_to(command, &dir)?;
+            println!("Man pages written to {}", dir.display());
+        }
+        None => clap_mangen::Man::new(command).render(&mut io::stdout())?,
+    }
+    Ok(())
+}
+
+#[cfg(feature = "midi")]
+fn run_play(
+    args: &Args,
+    config: &Config,
+    pack: Option<&PracticePack>,
+    running: &Arc<AtomicBool>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mapping = midi_mapping(args, &config.midi)?;
+    let keys = KeyBindings::from_section(&config.keys)?;
 
     // Create practice session
-    let mut session = PracticeSession::new(tempo_bpm, complexity, time_signature);
+    let mut session = PracticeSession::new(args.tempo, args.complexity, args.time_signature);
+    session.tempo_ramp = args.ramp;
+    session.tempo_range = args.tempo_range;
+    session.listen_delay_secs = args.listen_delay;
+    session.humanize = args.humanize;
+    session.repeat_cue = args.repeat_cue;
+    session.count_in = CountIn::new(args.count_in)?;
+    if args.count_in_subdivide {
+        session.count_in = session.count_in.with_subdivision();
+    }
+    if let Some(note) = args.count_off {
+        session.count_in = session.count_in.with_count_off(note);
+    }
+    session.drill = args.drill;
+    session.goal = args.goal.clone().map(GoalProgress::new);
+    if let Some(path) = &args.plan {
+        session.plan = Some(PlanProgress::new(SessionPlan::load(path)?));
+        session.apply_plan_segment();
+    }
+    if let Some(loops) = args.loops {
+        session.auto_advance = true;
+        session.advance_loops = loops;
+    }
+    session.reveal_on_advance = args.reveal_on_advance;
+    session.morph_bars = args.morph_bars;
+    session.answer_format = args.answer_format;
+    session.answer_resolution = args.answer_resolution;
+    session.click_enabled = if args.click || args.no_click {
+        args.click
+    } else {
+        config.click.enabled.unwrap_or(true)
+    };
+    session.kick_velocity = args.kick_velocity;
+    session.click_velocity = args.click_velocity;
+    session.click_fade = args.click_fade;
+    session.click_ratio = args.click_ratio;
+    session.click_drift = args.click_drift;
+    if let Some(settings) = &args.hemiola {
+        let hemiola = settings.join(" ").parse::<Hemiola>()?;
+        hemiola.check_meter(args.time_signature)?;
+        session.hemiola = Some(hemiola);
+    }
+    session.kick_dropout = args
+        .dropout
+        .as_ref()
+        .map(|settings| settings.join(" ").parse::<KickDropout>())
+        .transpose()?;
+    session.click_gap = args.click_gap.map(|gap| {
+        if args.gap_kicks {
+            gap.with_muted_kicks()
+        } else {
+            gap
+        }
+    });
+    session.fills = args.fills;
+    session.ghost_steps = args.ghosts;
+    session.phrase_bars = args.phrase;
+    session.send_clock = args.send_clock;
+    session.sync_port = args.sync.clone();
+    session.kit_port = args.kit_input.clone();
+    session.kit_echo = args.kit_echo;
+    session.reveal_styles = args.reveal.clone();
+    if let Some(overlay) = args.overlay {
+        overlay.steps(args.time_signature)?;
+        session.overlay = Some(overlay);
+    }
+    if let Some(curriculum) = pack.and_then(|pack| pack.curriculum.clone()) {
+        session.start_curriculum(curriculum);
+    }
 
-    // Generate first pattern
-    let mut generator = WeightedGenerator::new();
-    let pattern = generator.generate(time_signature, complexity, &VecDeque::new())?;
+    // Start with --pattern, or generate one at the curriculum's first stage if there is one
+    let mut generator = build_generator(args, pack)?;
+    let pattern = first_pattern(
+        args,
+        generator.as_mut(),
+        session.time_signature,
+        session.complexity_level,
+    )?;
 
     // Set as current pattern and add to history
     session.patterns_generated = 1;
     session.add_to_history(pattern.clone());
     session.current_pattern = Some(pattern);
 
-    // Create command loop and run
-    let mut cmd_loop = CommandLoop::new(session);
-    cmd_loop.run()?;
+    let port = args.midi_port.clone().or_else(|| config.midi.port.clone());
+    // Where virtual ports aren't available (Windows), carry on with the usual port
+    let virtual_sink =
+        args.virtual_port
+            .as_deref()
+            .and_then(|name| match MidiEngine::create_virtual_port(name) {
+                Ok(sink) => Some(SharedSink::new(sink)),
+                Err(e) => {
+                    let fallback = port.as_deref().unwrap_or("the first MIDI port");
+                    eprintln!("⚠  {}\n   Falling back to {}.", e, fallback);
+                    None
+                }
+            });
+    #[cfg(feature = "webhooks")]
+    let webhook = args
+        .webhook
+        .as_ref()
+        .or(config.webhooks.url.as_ref())
+        .map(|url| Webhook::new(url))
+        .transpose()?;
+    // Practice still works without a history, e.g. when there's no home directory
+    let backend = config.storage.backend.unwrap_or_default();
+    let storage = match backend.open(config.storage.dir.clone()) {
+        Ok(storage) => Some(storage),
+        Err(e) => {
+            eprintln!("⚠  {}", e);
+            None
+        }
+    };
+    // Missed patterns from earlier sessions come back for review (headless
+    // sessions can't be answered, so they leave the queue alone)
+    if let (Some(storage), false) = (&storage, args.headless || args.no_review) {
+        match storage.review_queue() {
+            Ok(queue) => session.review = Some(queue),
+            Err(e) => eprintln!("⚠  {}", e),
+        }
+    }
 
-    Ok(())
+    let mut engine = PracticeEngine::new(session)
+        .with_generator(generator)
+        .with_prefetch(PREFETCH_DEPTH)
+        .with_midi_mapping(mapping)
+        .with_kit_map(kit_map(&config.kit)?)
+        .with_interrupt(Arc::clone(running));
+    if args.no_midi {
+        engine = engine.with_null_output();
+    }
+    if let Some(port) = port {
+        engine = engine.with_midi_port(port);
+    }
+    if let Some(port) = args.click_port.clone() {
+        engine = engine.with_click_port(port);
+    }
+    if let Some(sink) = virtual_sink {
+        engine = engine.with_midi_sink(sink);
+    }
+    if let Some(dir) = &args.log_midi {
+        engine = engine.with_midi_log(dir.clone());
+    }
+    if let Some(dir) = &args.save_replay {
+        engine = engine.with_replay(dir.clone());
+    }
+    if let Some(path) = &args.stream_file {
+        let stream = StreamOverlay::start(path.clone(), engine.playback().probe())?;
+        engine = engine.with_stream(stream);
+    }
+    if let Some(LogFormat::Json) = args.log_format {
+        // The interactive display needs stdout to itself; headless events can share it
+        let log = match &args.log_file {
+            Some(path) => EventLog::append_to(path)?,
+            None if args.headless => EventLog::stdout(),
+            None => {
+                return Err(
+                    "--log-format writes to stdout only with --headless; add --log-file FILE"
+                        .into(),
+                )
+            }
+        };
+        engine = engine.with_event_log(log);
+    }
+    #[cfg(feature = "websocket")]
+    if let Some(addr) = &args.websocket {
+        let socket = SocketServer::start(addr, engine.playback().probe())?;
+        engine = engine.with_socket(socket);
+    }
+    if let Some(addr) = &args.host {
+        let classroom = ClassroomHost::start(addr, args.quiz_only)?;
+        // Headless stdout is for JSON events only
+        if !args.headless {
+            println!("🎓 Hosting a class on {}", classroom.local_addr());
+        }
+        engine = engine.with_classroom(classroom);
+    }
+    if let Some(storage) = storage {
+        engine = engine.with_storage(storage);
+    }
+    // Headless sessions take their settings as commands instead
+    if let (Some(path), false) = (config_path(args), args.headless) {
+        engine = engine.with_config_watch(ConfigWatcher::start(path));
+    }
+    #[cfg(feature = "link")]
+    if args.link {
+        engine = engine.with_link();
+    }
+
+    let options = SessionOptions::new()
+        .with_key_bindings(keys)
+        .with_palette(palette(args, config));
+    #[cfg(feature = "webhooks")]
+    let options = match webhook {
+        Some(webhook) => options.with_webhook(webhook),
+        None => options,
+    };
+
+    if args.headless {
+        return HeadlessLoop::new(engine, options).run(running);
+    }
+    if args.tui {
+        return TuiApp::new(engine, options).run(running);
+    }
+    CommandLoop::new(engine, options).run(running)
+}
+
+/// Play back a saved session, or with `summary` list it without pacing or sound
+#[cfg(feature = "midi")]
+fn run_replay(
+    args: &Args,
+    config: &Config,
+    file: &Path,
+    summary: bool,
+    speed: f64,
+    running: &AtomicBool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let replay = SessionReplay::load(file)?;
+    let mut player = ReplayPlayer::new(replay.clone())
+        .with_reveal_styles(args.reveal.clone())
+        .with_speed(speed)?;
+    if !summary {
+        let mut session = PracticeSession::new(
+            replay.tempo_bpm,
+            replay.complexity_level()?,
+            replay.meter()?,
+        );
+        session.click_enabled = replay.click;
+        let mut engine =
+            PracticeEngine::new(session).with_midi_mapping(midi_mapping(args, &config.midi)?);
+        if args.no_midi {
+            engine = engine.with_null_output();
+        }
+        if let Some(port) = args.midi_port.clone().or_else(|| config.midi.port.clone()) {
+            engine = engine.with_midi_port(port);
+        }
+        if let Some(port) = args.click_port.clone() {
+            engine = engine.with_click_port(port);
+        }
+        player = player.with_engine(engine);
+    }
+    player.run(running)
+}
+
+/// Join the class at `addr` as `name`, playing the teacher's patterns
+#[cfg(feature = "midi")]
+fn run_join(
+    args: &Args,
+    config: &Config,
+    addr: &str,
+    name: Option<&str>,
+    running: &AtomicBool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let name = name
+        .map(String::from)
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_else(|| String::from("A student"));
+    let session = PracticeSession::new(args.tempo, args.complexity, args.time_signature);
+    let mut engine =
+        PracticeEngine::new(session).with_midi_mapping(midi_mapping(args, &config.midi)?);
+    if args.no_midi {
+        engine = engine.with_null_output();
+    }
+    if let Some(port) = args.midi_port.clone().or_else(|| config.midi.port.clone()) {
+        engine = engine.with_midi_port(port);
+    }
+    if let Some(port) = args.click_port.clone() {
+        engine = engine.with_click_port(port);
+    }
+    ClassroomStudent::new(name)
+        .with_engine(engine)
+        .with_reveal_styles(args.reveal.clone())
+        .with_answer_format(args.answer_format, args.answer_resolution)
+        .run(addr, running)
+}
+
+/// Give the guided tour on a fresh session at a fixed tempo and complexity,
+/// leaving the practice history alone
+#[cfg(feature = "midi")]
+fn run_demo(
+    args: &Args,
+    config: &Config,
+    running: &AtomicBool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let session = PracticeSession::new(
+        DEMO_TEMPO,
+        ComplexityLevel::Simple,
+        TimeSignature::four_four(),
+    );
+    let mut engine =
+        PracticeEngine::new(session).with_midi_mapping(midi_mapping(args, &config.midi)?);
+    if args.no_midi {
+        engine = engine.with_null_output();
+    }
+    if let Some(port) = args.midi_port.clone().or_else(|| config.midi.port.clone()) {
+        engine = engine.with_midi_port(port);
+    }
+    if let Some(port) = args.click_port.clone() {
+        engine = engine.with_click_port(port);
+    }
+    engine.generate()?;
+
+    let options = SessionOptions::new()
+        .with_key_bindings(KeyBindings::from_section(&config.keys)?)
+        .with_palette(palette(args, config));
+    DemoTour::new(engine, options).run(running)
+}
+
+#[cfg(not(feature = "midi"))]
+fn run_demo(
+    _args: &Args,
+    _config: &Config,
+    _running: &AtomicBool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err("This build of kickbeats has no MIDI support (built without the `midi` feature).".into())
+}
+
+#[cfg(not(feature = "midi"))]
+fn run_join(
+    _args: &Args,
+    _config: &Config,
+    _addr: &str,
+    _name: Option<&str>,
+    _running: &AtomicBool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err("This build of kickbeats has no MIDI support (built without the `midi` feature).".into())
+}
+
+#[cfg(not(feature = "midi"))]
+fn run_replay(
+    _args: &Args,
+    _config: &Config,
+    _file: &Path,
+    _summary: bool,
+    _speed: f64,
+    _running: &AtomicBool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err("This build of kickbeats has no MIDI support (built without the `midi` feature).".into())
+}
+
+#[cfg(not(feature = "midi"))]
+fn run_play(
+    _args: &Args,
+    _config: &Config,
+    _pack: Option<&PracticePack>,
+    _running: &Arc<AtomicBool>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err(
+        "This build of kickbeats has no MIDI support (built without the `midi` feature).\n\
+         Use `kickbeats generate` to print patterns instead."
+            .into(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args_are_consistent() {
+        // Catches conflicts and requirements naming flags this build leaves out
+        Args::command().debug_assert();
+    }
 }
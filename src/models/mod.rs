@@ -1,15 +0,0 @@
-// Models module
-// Core data structures for the rhythm practice tool
-
-pub mod beat_grid;
-pub mod complexity;
-pub mod pattern;
-pub mod session;
-pub mod time_signature;
-
-// Re-export main types for convenience
-pub use beat_grid::BeatGrid;
-pub use complexity::ComplexityLevel;
-pub use pattern::Pattern;
-pub use session::PracticeSession;
-pub use time_signature::TimeSignature;
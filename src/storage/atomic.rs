@@ -0,0 +1,216 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Lock file [`DirLock`] takes in a kickbeats data directory
+pub const LOCK_FILE_NAME: &str = ".kickbeats.lock";
+
+/// Numbers this process's temporary files
+static NEXT_TEMP: AtomicU64 = AtomicU64::new(0);
+
+/// Replace the file at `path` with `contents` so it is never seen half-written
+///
+/// The contents go to a temporary file in the same directory, are flushed to
+/// disk, and are then renamed over `path`, so a crash mid-save leaves either
+/// the old file or the new one. Missing parent directories are created.
+///
+/// Nothing is locked, so this is safe for any destination the user picks.
+/// Saves into kickbeats' own directories that read before they write should
+/// hold a [`DirLock`] across both.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    replace(path, contents.as_ref(), true)
+}
+
+/// Replace the file at `path` with `contents` so readers never see it
+/// half-written, without flushing it to disk
+///
+/// For files rewritten several times a second that a crash can't hurt (e.g.,
+/// the stream overlay), where [`write_atomic`]'s syncs would only add disk wear.
+pub fn replace_file(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    replace(path, contents.as_ref(), false)
+}
+
+/// Write `contents` to a temporary file beside `path` and rename it over
+/// `path`, flushing the file and the rename to disk if `durable`
+fn replace(path: &Path, contents: &[u8], durable: bool) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    fs::create_dir_all(dir)?;
+
+    // Unique per process and save, so concurrent writers never share a temp file
+    let temp = dir.join(format!(
+        ".{}.{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        NEXT_TEMP.fetch_add(1, Ordering::Relaxed)
+    ));
+    let result = write_temp(&temp, contents, durable).and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result?;
+
+    if durable {
+        sync_dir(dir);
+    }
+    Ok(())
+}
+
+/// Exclusive hold on a kickbeats data directory (installed packs, history)
+///
+/// Concurrent kickbeats instances take turns: `acquire` blocks until no one
+/// else holds the directory. Hold it across a read-modify-write so another
+/// instance can't save in between. Released on drop, or when the process dies.
+#[derive(Debug)]
+pub struct DirLock {
+    _file: File,
+}
+
+impl DirLock {
+    /// Wait for and take the lock on `dir`, creating the directory if needed
+    pub fn acquire(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join(LOCK_FILE_NAME))?;
+        file.lock()?;
+        Ok(Self { _file: file })
+    }
+}
+
+fn write_temp(path: &Path, contents: &[u8], sync: bool) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    if sync {
+        file.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Persist the rename itself; best effort, since not every platform can open directories
+fn sync_dir(dir: &Path) {
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("kickbeats-storage-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_write_creates_and_replaces() {
+        let dir = scratch_dir("replace");
+        let path = dir.join("nested").join("session.mid");
+
+        write_atomic(&path, "first").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+        write_atomic(&path, "second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+
+        // No lock or temporary files are left beside the user's file
+        assert_eq!(file_names(path.parent().unwrap()), vec!["session.mid"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_replace_file_leaves_no_temp_files() {
+        let dir = scratch_dir("overlay");
+        let path = dir.join("overlay.txt");
+
+        replace_file(&path, "beat 1").unwrap();
+        replace_file(&path, "beat 2").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "beat 2");
+        assert_eq!(file_names(&dir), vec!["overlay.txt"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_writers_never_interleave() {
+        let dir = scratch_dir("concurrent");
+        let path = Arc::new(dir.join("stats.txt"));
+
+        let writers: Vec<_> = (0..8u8)
+            .map(|i| {
+                let path = Arc::clone(&path);
+                thread::spawn(move || {
+                    let contents = vec![b'a' + i; 64 * 1024];
+                    for _ in 0..5 {
+                        write_atomic(&path, &contents).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let contents = fs::read(&*path).unwrap();
+        assert_eq!(contents.len(), 64 * 1024);
+        assert!(contents.iter().all(|&b| b == contents[0]));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_lock_serializes_read_modify_write() {
+        let dir = scratch_dir("counter");
+        let path = dir.join("count.txt");
+        write_atomic(&path, "0").unwrap();
+
+        let writers: Vec<_> = (0..8)
+            .map(|_| {
+                let (dir, path) = (dir.clone(), path.clone());
+                thread::spawn(move || {
+                    for _ in 0..10 {
+                        let _lock = DirLock::acquire(&dir).unwrap();
+                        let count: u32 = fs::read_to_string(&path).unwrap().parse().unwrap();
+                        write_atomic(&path, (count + 1).to_string()).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "80");
+        assert_eq!(file_names(&dir), vec![LOCK_FILE_NAME, "count.txt"]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_paths_without_file_names() {
+        assert!(write_atomic(Path::new("/"), "x").is_err());
+    }
+}
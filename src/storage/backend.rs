@@ -0,0 +1,103 @@
+#[cfg(feature = "sqlite")]
+use super::SqliteStorage;
+#[cfg(feature = "history")]
+use super::{JsonStorage, SessionRecord};
+#[cfg(feature = "history")]
+use crate::error::KickbeatsError;
+#[cfg(feature = "history")]
+use crate::models::ReviewQueue;
+#[cfg(feature = "history")]
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Where finished practice sessions are kept between runs
+#[cfg(feature = "history")]
+pub trait Storage {
+    /// Add a finished session to the history
+    fn save_session(&self, record: &SessionRecord) -> Result<(), KickbeatsError>;
+
+    /// Every saved session, oldest first
+    fn sessions(&self) -> Result<Vec<SessionRecord>, KickbeatsError>;
+
+    /// The saved spaced-repetition review queue (empty before the first save)
+    fn review_queue(&self) -> Result<ReviewQueue, KickbeatsError>;
+
+    /// Replace the saved review queue
+    fn save_review_queue(&self, queue: &ReviewQueue) -> Result<(), KickbeatsError>;
+}
+
+/// Practice history formats selectable with `storage.backend` in the config file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StorageBackend {
+    /// One human-readable JSON file per session
+    #[default]
+    Json,
+    /// A single SQLite database, quicker to load for years of history
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl StorageBackend {
+    /// Open this backend in `dir`, or in its default directory
+    #[cfg(feature = "history")]
+    pub fn open(self, dir: Option<PathBuf>) -> Result<Box<dyn Storage>, KickbeatsError> {
+        let dir = dir.or_else(JsonStorage::default_dir).ok_or_else(|| {
+            KickbeatsError::Storage(String::from(
+                "Can't find a data directory for practice history; set storage.dir",
+            ))
+        })?;
+        match self {
+            StorageBackend::Json => Ok(Box::new(JsonStorage::new(dir))),
+            #[cfg(feature = "sqlite")]
+            StorageBackend::Sqlite => Ok(Box::new(SqliteStorage::new(dir))),
+        }
+    }
+}
+
+/// Backend names this build accepts, for error messages
+#[cfg(feature = "sqlite")]
+const BACKEND_NAMES: &str = "json, sqlite";
+#[cfg(not(feature = "sqlite"))]
+const BACKEND_NAMES: &str = "json";
+
+impl FromStr for StorageBackend {
+    type Err = String;
+
+    /// Parse a backend name ("json" or, built with the `sqlite` feature, "sqlite")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(StorageBackend::Json),
+            #[cfg(feature = "sqlite")]
+            "sqlite" => Ok(StorageBackend::Sqlite),
+            #[cfg(not(feature = "sqlite"))]
+            "sqlite" => Err(
+                "SQLite storage isn't available in this build (enable the `sqlite` feature). \
+                 Use: json"
+                    .to_string(),
+            ),
+            _ => Err(format!(
+                "Invalid storage backend '{}'. Use: {}",
+                s, BACKEND_NAMES
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_backend() {
+        assert_eq!("json".parse(), Ok(StorageBackend::Json));
+        assert_eq!("JSON".parse(), Ok(StorageBackend::Json));
+        #[cfg(feature = "sqlite")]
+        assert_eq!("SQLite".parse(), Ok(StorageBackend::Sqlite));
+        #[cfg(not(feature = "sqlite"))]
+        assert!("sqlite"
+            .parse::<StorageBackend>()
+            .unwrap_err()
+            .contains("isn't available"));
+        assert!("csv".parse::<StorageBackend>().is_err());
+    }
+}
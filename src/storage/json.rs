@@ -0,0 +1,202 @@
+use super::{write_atomic, DirLock, ReviewFile, SessionRecord, Storage, REVIEW_FILE_NAME};
+use crate::error::KickbeatsError;
+use crate::models::ReviewQueue;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Practice history kept as one pretty-printed JSON file per session
+///
+/// Files are named `<started_at>-<session id prefix>.json`, so they sort by
+/// date and can be read, grepped, or backed up with ordinary tools.
+#[derive(Debug, Clone)]
+pub struct JsonStorage {
+    dir: PathBuf,
+}
+
+impl JsonStorage {
+    /// Keep sessions in `dir`, created on the first save
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Where sessions are kept unless `storage.dir` says otherwise
+    ///
+    /// `$XDG_DATA_HOME/kickbeats/sessions`, falling back to
+    /// `~/.local/share/kickbeats/sessions`.
+    pub fn default_dir() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("share"))
+            })?;
+        Some(base.join("kickbeats").join("sessions"))
+    }
+
+    /// Directory the session files are in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// File a session is saved to
+    fn path_for(&self, record: &SessionRecord) -> PathBuf {
+        let id: String = record.session_id.chars().take(8).collect();
+        self.dir.join(format!("{}-{}.json", record.started_at, id))
+    }
+}
+
+impl Storage for JsonStorage {
+    fn save_session(&self, record: &SessionRecord) -> Result<(), KickbeatsError> {
+        let path = self.path_for(record);
+        let json = serde_json::to_string_pretty(record).map_err(|e| {
+            KickbeatsError::Storage(format!("Failed to encode practice history: {}", e))
+        })?;
+        let _lock = DirLock::acquire(&self.dir)
+            .map_err(|e| KickbeatsError::io("lock practice history in", &self.dir, e))?;
+        write_atomic(&path, json + "\n")
+            .map_err(|e| KickbeatsError::io("save practice history to", path, e))
+    }
+
+    fn sessions(&self) -> Result<Vec<SessionRecord>, KickbeatsError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(KickbeatsError::io("read practice history in", &self.dir, e)),
+        };
+
+        let mut records = Vec::new();
+        for path in entries.filter_map(Result::ok).map(|entry| entry.path()) {
+            // Skips the lock file, temporary files from saves in progress, and the review queue
+            let is_session = path.extension().is_some_and(|ext| ext == "json")
+                && !path.file_name().is_some_and(|name| {
+                    let name = name.to_string_lossy();
+                    name.starts_with('.') || name == REVIEW_FILE_NAME
+                });
+            if !is_session {
+                continue;
+            }
+            let contents =
+                fs::read_to_string(&path).map_err(|e| KickbeatsError::io("read", &path, e))?;
+            let record = serde_json::from_str(&contents).map_err(|e| {
+                KickbeatsError::Storage(format!(
+                    "Invalid practice history file {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            records.push(record);
+        }
+        records.sort_by(|a: &SessionRecord, b| {
+            (a.started_at, &a.session_id).cmp(&(b.started_at, &b.session_id))
+        });
+        Ok(records)
+    }
+
+    fn review_queue(&self) -> Result<ReviewQueue, KickbeatsError> {
+        let path = self.dir.join(REVIEW_FILE_NAME);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(ReviewQueue::new()),
+            Err(e) => return Err(KickbeatsError::io("read", path, e)),
+        };
+        let invalid = |e: String| {
+            KickbeatsError::Storage(format!(
+                "Invalid review queue file {}: {}",
+                path.display(),
+                e
+            ))
+        };
+        let file: ReviewFile =
+            serde_json::from_str(&contents).map_err(|e| invalid(e.to_string()))?;
+        file.to_queue().map_err(|e| invalid(e.to_string()))
+    }
+
+    fn save_review_queue(&self, queue: &ReviewQueue) -> Result<(), KickbeatsError> {
+        let path = self.dir.join(REVIEW_FILE_NAME);
+        // Another session may have saved the queue since this one loaded it
+        let _lock = DirLock::acquire(&self.dir)
+            .map_err(|e| KickbeatsError::io("lock review queue in", &self.dir, e))?;
+        let queue = queue.merged_onto(self.review_queue()?);
+        let json = serde_json::to_string_pretty(&ReviewFile::from_queue(&queue)).map_err(|e| {
+            KickbeatsError::Storage(format!("Failed to encode review queue: {}", e))
+        })?;
+        write_atomic(&path, json + "\n")
+            .map_err(|e| KickbeatsError::io("save review queue to", path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, Pattern, TimeSignature};
+
+    fn record(id: &str, started_at: u64) -> SessionRecord {
+        SessionRecord {
+            session_id: id.to_string(),
+            started_at,
+            duration_secs: 60,
+            tempo_bpm: 120,
+            complexity: "medium".to_string(),
+            time_signature: "4/4".to_string(),
+            patterns_generated: 2,
+            answer_accuracies: vec![0.75],
+            goal: None,
+            goal_met: false,
+            reveals: 1,
+            taps: 0,
+            taps_on_time: 0,
+            complexity_secs: Default::default(),
+            relaxed_patterns: Default::default(),
+            notes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_saves_and_lists_sessions_oldest_first() {
+        let dir = std::env::temp_dir().join(format!("kickbeats-json-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let storage = JsonStorage::new(dir.clone());
+        assert_eq!(storage.sessions().unwrap(), Vec::new());
+
+        let later = record("bbbbbbbb-2222", 2_000);
+        let earlier = record("aaaaaaaa-1111", 1_000);
+        storage.save_session(&later).unwrap();
+        storage.save_session(&earlier).unwrap();
+
+        assert_eq!(storage.sessions().unwrap(), vec![earlier, later.clone()]);
+        let file = fs::read_to_string(dir.join("2000-bbbbbbbb.json")).unwrap();
+        assert!(file.contains("\"tempo_bpm\": 120"));
+
+        fs::write(dir.join("broken.json"), "{").unwrap();
+        assert!(storage
+            .sessions()
+            .unwrap_err()
+            .to_string()
+            .contains("broken.json"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_review_queue_lives_beside_the_sessions() {
+        let dir = std::env::temp_dir().join(format!("kickbeats-review-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let storage = JsonStorage::new(dir.clone());
+        assert!(storage.review_queue().unwrap().is_empty());
+
+        let steps = (0..16).map(|i| i % 6 == 0).collect();
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium);
+        let mut queue = ReviewQueue::new();
+        queue.record(&pattern, 0, 20_000);
+        storage.save_review_queue(&queue).unwrap();
+        storage
+            .save_session(&record("aaaaaaaa-1111", 1_000))
+            .unwrap();
+
+        assert_eq!(storage.review_queue().unwrap().cards(), queue.cards());
+        assert_eq!(storage.sessions().unwrap().len(), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
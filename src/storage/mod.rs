@@ -0,0 +1,34 @@
+// Storage module
+// Crash-safe file writes and the practice history kept between sessions
+
+pub mod atomic;
+pub mod backend;
+#[cfg(feature = "history")]
+pub mod json;
+#[cfg(feature = "history")]
+pub mod record;
+#[cfg(feature = "history")]
+pub mod replay;
+#[cfg(feature = "history")]
+pub mod review;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "history")]
+pub mod stats;
+
+pub use atomic::{replace_file, write_atomic, DirLock, LOCK_FILE_NAME};
+#[cfg(feature = "history")]
+pub use backend::Storage;
+pub use backend::StorageBackend;
+#[cfg(feature = "history")]
+pub use json::JsonStorage;
+#[cfg(feature = "history")]
+pub use record::{NoteRecord, SessionRecord};
+#[cfg(feature = "history")]
+pub use replay::{ReplayEntry, ReplayEvent, SessionReplay, REPLAY_EXTENSION, REPLAY_FORMAT};
+#[cfg(feature = "history")]
+pub use review::{ReviewFile, ReviewRecord, REVIEW_FILE_NAME};
+#[cfg(feature = "sqlite")]
+pub use sqlite::{SqliteStorage, SQLITE_FILE_NAME};
+#[cfg(feature = "history")]
+pub use stats::{format_day, PeriodStats, PracticeStats, DAY_SECS};
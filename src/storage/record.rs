@@ -0,0 +1,269 @@
+use crate::models::{PatternNote, PracticeSession};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::UNIX_EPOCH;
+
+/// What a finished practice session leaves in the history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    /// Unique session identifier
+    pub session_id: String,
+    /// When the session began, in seconds since the Unix epoch
+    pub started_at: u64,
+    /// Seconds from the start to the last interaction
+    pub duration_secs: u64,
+    /// Tempo at the end of the session
+    pub tempo_bpm: u16,
+    /// Complexity level ("simple", "medium", or "complex")
+    pub complexity: String,
+    /// Time signature (e.g., "6/8")
+    pub time_signature: String,
+    /// Patterns created during the session
+    pub patterns_generated: u32,
+    /// Accuracy (0.0-1.0) of each answer checked
+    pub answer_accuracies: Vec<f32>,
+    /// Session goal, if one was set (e.g., "10 medium @ 100+ BPM, 80%")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal: Option<String>,
+    /// Whether the session goal was reached
+    #[serde(default)]
+    pub goal_met: bool,
+    /// Patterns revealed
+    #[serde(default)]
+    pub reveals: u32,
+    /// Drum pad kick taps
+    #[serde(default)]
+    pub taps: u32,
+    /// Drum pad kick taps graded perfect or good
+    #[serde(default)]
+    pub taps_on_time: u32,
+    /// Seconds spent at each complexity ("simple", "medium", "complex")
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub complexity_secs: BTreeMap<String, u64>,
+    /// Patterns that settled for less than 3 steps from recent ones, by the
+    /// distance they settled for
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub relaxed_patterns: BTreeMap<u32, u32>,
+    /// Patterns annotated during the session
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<NoteRecord>,
+}
+
+/// A pattern annotated during a session, as kept in the history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteRecord {
+    /// Position of the pattern in the session's generation order
+    pub number: u32,
+    /// The pattern as a shared `--pattern` string (e.g., "4/4:16:x...x..x..x...x.")
+    pub pattern: String,
+    /// Free text noted about it
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub text: String,
+    /// Tags, lowercase and without the `#`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl NoteRecord {
+    /// The note as typed, text then tags
+    pub fn note(&self) -> PatternNote {
+        PatternNote {
+            text: self.text.clone(),
+            tags: self.tags.clone(),
+        }
+    }
+}
+
+impl SessionRecord {
+    /// Summarize a session for the history
+    pub fn from_session(session: &PracticeSession) -> Self {
+        Self {
+            session_id: session.session_id.to_string(),
+            started_at: session
+                .session_start
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            duration_secs: session
+                .last_activity
+                .duration_since(session.session_start)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            tempo_bpm: session.tempo_bpm,
+            complexity: format!("{:?}", session.complexity_level).to_lowercase(),
+            time_signature: format!(
+                "{}/{}",
+                session.time_signature.numerator, session.time_signature.denominator
+            ),
+            patterns_generated: session.patterns_generated,
+            answer_accuracies: session.answer_accuracies.clone(),
+            goal: session.goal.as_ref().map(|goal| goal.goal.to_string()),
+            goal_met: session.goal.as_ref().is_some_and(|goal| goal.is_met()),
+            reveals: session.reveals,
+            taps: session.taps.total() as u32,
+            taps_on_time: session.taps.on_time() as u32,
+            complexity_secs: session
+                .time_at_complexity()
+                .into_iter()
+                .map(|(level, time)| (format!("{:?}", level).to_lowercase(), time.as_secs()))
+                .collect(),
+            relaxed_patterns: session.relaxed_patterns.clone(),
+            notes: session
+                .pattern_notes
+                .iter()
+                .map(|noted| NoteRecord {
+                    number: noted.number,
+                    pattern: noted.pattern.to_pattern_string(),
+                    text: noted.note.text.clone(),
+                    tags: noted.note.tags.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Mean accuracy over the session's answers, if any were checked
+    pub fn average_accuracy(&self) -> Option<f32> {
+        if self.answer_accuracies.is_empty() {
+            return None;
+        }
+        let total: f32 = self.answer_accuracies.iter().sum();
+        Some(total / self.answer_accuracies.len() as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grading::TapGrade;
+    use crate::models::{ComplexityLevel, GoalProgress, Pattern, TimeSignature};
+
+    #[test]
+    fn test_from_session() {
+        let mut session =
+            PracticeSession::new(96, ComplexityLevel::Complex, TimeSignature::six_eight());
+        session.patterns_generated = 3;
+        session.record_answer(1.0);
+        session.record_answer(0.5);
+        session.record_relaxed(Some(2));
+        session.record_relaxed(None);
+
+        let record = SessionRecord::from_session(&session);
+
+        assert_eq!(record.session_id, session.session_id.to_string());
+        assert_eq!(record.tempo_bpm, 96);
+        assert_eq!(record.complexity, "complex");
+        assert_eq!(record.time_signature, "6/8");
+        assert_eq!(record.patterns_generated, 3);
+        assert_eq!(record.average_accuracy(), Some(0.75));
+        assert_eq!(record.goal, None);
+        assert_eq!(record.reveals, 0);
+        assert_eq!(record.taps, 0);
+        assert!(record.complexity_secs.contains_key("complex"));
+        assert_eq!(record.relaxed_patterns, [(2, 1)].into_iter().collect());
+    }
+
+    #[test]
+    fn test_records_reveals_taps_and_complexity_time() {
+        let mut session =
+            PracticeSession::new(120, ComplexityLevel::Simple, TimeSignature::four_four());
+        session.current_pattern = Some(Pattern::new(
+            vec![true; 16],
+            TimeSignature::four_four(),
+            ComplexityLevel::Simple,
+        ));
+        session.mark_revealed();
+        session.mark_revealed();
+        session.record_tap(TapGrade::Perfect);
+        session.record_tap(TapGrade::Late);
+        session.set_complexity(ComplexityLevel::Medium);
+        session.update_activity();
+
+        let record = SessionRecord::from_session(&session);
+        assert_eq!(record.reveals, 1);
+        assert_eq!((record.taps, record.taps_on_time), (2, 1));
+        let levels: Vec<&str> = record.complexity_secs.keys().map(String::as_str).collect();
+        assert_eq!(levels, vec!["medium", "simple"]);
+
+        // Records saved before these stats existed still load
+        let mut json: serde_json::Value = serde_json::to_value(&record).unwrap();
+        for key in ["reveals", "taps", "taps_on_time", "complexity_secs"] {
+            json.as_object_mut().unwrap().remove(key);
+        }
+        let old: SessionRecord = serde_json::from_value(json).unwrap();
+        assert_eq!((old.reveals, old.taps), (0, 0));
+        assert!(old.complexity_secs.is_empty());
+    }
+
+    #[test]
+    fn test_records_goal_outcome() {
+        let mut session =
+            PracticeSession::new(120, ComplexityLevel::Medium, TimeSignature::four_four());
+        session.goal = Some(GoalProgress::new("1 medium".parse().unwrap()));
+        session.current_pattern = Some(Pattern::new(
+            vec![true; 16],
+            TimeSignature::four_four(),
+            ComplexityLevel::Medium,
+        ));
+        assert!(!SessionRecord::from_session(&session).goal_met);
+
+        assert!(session.record_answer(1.0));
+        let record = SessionRecord::from_session(&session);
+        assert_eq!(record.goal.as_deref(), Some("1 medium, 100%"));
+        assert!(record.goal_met);
+
+        // Records saved before goals existed still load
+        let json = serde_json::to_string(&record)
+            .unwrap()
+            .replace(",\"goal\":\"1 medium, 100%\"", "");
+        let old: SessionRecord =
+            serde_json::from_str(&json.replace(",\"goal_met\":true", "")).unwrap();
+        assert_eq!(old.goal, None);
+        assert!(!old.goal_met);
+    }
+
+    #[test]
+    fn test_records_notes_on_the_patterns_annotated() {
+        let mut session =
+            PracticeSession::new(120, ComplexityLevel::Simple, TimeSignature::four_four());
+        assert_eq!(session.annotate("#tricky".parse().unwrap()), None);
+
+        let first =
+            Pattern::from_fingerprint("4/4:x...x.......x...", ComplexityLevel::Simple).unwrap();
+        session.patterns_generated = 1;
+        session.add_to_history(first.clone());
+        session.current_pattern = Some(first.clone());
+        assert_eq!(session.annotate("#tricky".parse().unwrap()), Some(1));
+
+        session.patterns_generated = 2;
+        session.add_to_history(Pattern::new(
+            vec![true; 16],
+            TimeSignature::four_four(),
+            ComplexityLevel::Simple,
+        ));
+        // Back on the first pattern, a new note replaces the old one
+        assert_eq!(
+            session.annotate("push on 3 #Samba-ish".parse().unwrap()),
+            Some(1)
+        );
+        assert!(session.note_for(1).unwrap().has_tag("samba-ish"));
+        assert!(session.note_for(2).is_none());
+
+        let record = SessionRecord::from_session(&session);
+        assert_eq!(
+            record.notes,
+            vec![NoteRecord {
+                number: 1,
+                pattern: first.to_pattern_string(),
+                text: "push on 3".to_string(),
+                tags: vec!["samba-ish".to_string()],
+            }]
+        );
+        assert_eq!(record.notes[0].note().to_string(), "push on 3 #samba-ish");
+
+        // Records saved before notes existed still load
+        let mut json: serde_json::Value = serde_json::to_value(&record).unwrap();
+        json.as_object_mut().unwrap().remove("notes");
+        let old: SessionRecord = serde_json::from_value(json).unwrap();
+        assert!(old.notes.is_empty());
+    }
+}
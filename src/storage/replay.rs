@@ -0,0 +1,461 @@
+use crate::error::KickbeatsError;
+use crate::models::{ComplexityLevel, Pattern, PracticeSession, TimeSignature};
+use crate::storage::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Newest replay format this version of kickbeats reads and writes
+pub const REPLAY_FORMAT: u32 = 1;
+
+/// File extension for session replays
+pub const REPLAY_EXTENSION: &str = "kbreplay";
+
+/// Something that happened during a session, as saved in a replay
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum ReplayEvent {
+    /// A new pattern became current, hidden until revealed
+    Pattern {
+        /// The pattern's fingerprint (e.g., "4/4:x..x......x.....")
+        pattern: String,
+        /// Complexity level it was generated at
+        complexity: String,
+        /// Whether it was a missed pattern back for review
+        #[serde(default)]
+        review: bool,
+    },
+    /// An earlier pattern from the session became current again
+    Revisit {
+        /// The pattern's fingerprint
+        pattern: String,
+    },
+    /// The current pattern morphed into a new one, which became current
+    Morph {
+        /// The new pattern's fingerprint
+        pattern: String,
+        /// Loops each step of the morph held
+        loops_per_stage: u32,
+        /// Tempo the morph played at
+        bpm: u16,
+    },
+    /// The current pattern started looping
+    Play {
+        /// Tempo it started at
+        bpm: u16,
+    },
+    /// Playback stopped
+    Stop,
+    /// Tempo changed
+    Tempo { bpm: u16 },
+    /// Complexity of patterns generated from then on
+    Complexity { level: String },
+    /// Time signature of patterns generated from then on
+    TimeSignature { meter: String },
+    /// Click muted or unmuted
+    Click { enabled: bool },
+    /// Auto-advance turned on or off
+    AutoAdvance { enabled: bool },
+    /// The current pattern was shown
+    Reveal,
+    /// An answer was checked against the current pattern
+    Answer {
+        /// What was typed, when the answer was typed rather than scored elsewhere
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        answer: Option<String>,
+        /// Accuracy (0.0-1.0)
+        accuracy: f32,
+    },
+}
+
+/// One line of a `--log-format json` event log, as far as replays need it
+#[derive(Debug, Deserialize)]
+struct LoggedLine {
+    /// Seconds since the session started
+    t: f64,
+    /// What happened
+    #[serde(flatten)]
+    event: LoggedEvent,
+}
+
+/// Events of a `--log-format json` event log that replays play back
+#[derive(Debug, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum LoggedEvent {
+    PatternGenerated {
+        pattern: String,
+        complexity: String,
+        #[serde(default)]
+        review: bool,
+    },
+    Reveal,
+    TempoChange {
+        bpm: u16,
+    },
+    LoopStart {
+        loop_index: u64,
+        tempo: u16,
+    },
+    /// Drift warnings and anything newer than this version knows
+    #[serde(other)]
+    Other,
+}
+
+impl LoggedEvent {
+    /// The replay event this stands for, if any; the first loop of a
+    /// pattern is when it started playing
+    fn to_replay(&self) -> Option<ReplayEvent> {
+        match self {
+            LoggedEvent::PatternGenerated {
+                pattern,
+                complexity,
+                review,
+            } => Some(ReplayEvent::Pattern {
+                pattern: pattern.clone(),
+                complexity: complexity.clone(),
+                review: *review,
+            }),
+            LoggedEvent::Reveal => Some(ReplayEvent::Reveal),
+            LoggedEvent::TempoChange { bpm } => Some(ReplayEvent::Tempo { bpm: *bpm }),
+            LoggedEvent::LoopStart {
+                loop_index: 0,
+                tempo,
+            } => Some(ReplayEvent::Play { bpm: *tempo }),
+            LoggedEvent::LoopStart { .. } | LoggedEvent::Other => None,
+        }
+    }
+}
+
+/// One event and when it happened
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayEntry {
+    /// Milliseconds since the session started
+    pub at_ms: u64,
+    /// What happened
+    #[serde(flatten)]
+    pub event: ReplayEvent,
+}
+
+impl ReplayEntry {
+    /// Time since the session started
+    pub fn at(&self) -> Duration {
+        Duration::from_millis(self.at_ms)
+    }
+}
+
+/// A whole session saved for later playback: every pattern, command, answer,
+/// and tempo change with when it happened
+///
+/// Saved as JSON in a `.kbreplay` file:
+///
+/// ```json
+/// {
+///   "format": 1,
+///   "session_id": "...",
+///   "started_at": 1760600000,
+///   "tempo_bpm": 100,
+///   "complexity": "medium",
+///   "time_signature": "4/4",
+///   "click": true,
+///   "events": [
+///     { "at_ms": 0, "event": "pattern", "pattern": "4/4:x...x.......x...", "complexity": "medium" },
+///     { "at_ms": 2, "event": "play", "bpm": 100 },
+///     { "at_ms": 9120, "event": "answer", "answer": "x...x.....x.....", "accuracy": 0.94 }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionReplay {
+    /// Replay format version; replays newer than [`REPLAY_FORMAT`] are refused
+    pub format: u32,
+    /// Session the replay was recorded from
+    pub session_id: String,
+    /// When the session began, in seconds since the Unix epoch
+    pub started_at: u64,
+    /// Tempo the session started at
+    pub tempo_bpm: u16,
+    /// Complexity level the session started at
+    pub complexity: String,
+    /// Time signature the session started in (e.g., "6/8")
+    pub time_signature: String,
+    /// Whether the click started out enabled
+    pub click: bool,
+    /// What happened, in order
+    pub events: Vec<ReplayEntry>,
+}
+
+impl SessionReplay {
+    /// Start an empty replay of `session`, with its opening settings
+    pub fn new(session: &PracticeSession) -> Self {
+        Self {
+            format: REPLAY_FORMAT,
+            session_id: session.session_id.to_string(),
+            started_at: session
+                .session_start
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            tempo_bpm: session.tempo_bpm,
+            complexity: complexity_name(session.complexity_level),
+            time_signature: meter_name(session.time_signature),
+            click: session.click_enabled,
+            events: Vec::new(),
+        }
+    }
+
+    /// Add an event that happened `at` into the session
+    pub fn record(&mut self, at: Duration, event: ReplayEvent) {
+        self.events.push(ReplayEntry {
+            at_ms: at.as_millis() as u64,
+            event,
+        });
+    }
+
+    /// Complexity level the session started at
+    pub fn complexity_level(&self) -> Result<ComplexityLevel, KickbeatsError> {
+        self.complexity.parse().map_err(KickbeatsError::Storage)
+    }
+
+    /// Time signature the session started in
+    pub fn meter(&self) -> Result<TimeSignature, KickbeatsError> {
+        self.time_signature.parse().map_err(KickbeatsError::Storage)
+    }
+
+    /// Time from the start of the session to its last event
+    pub fn duration(&self) -> Duration {
+        self.events.last().map_or(Duration::ZERO, ReplayEntry::at)
+    }
+
+    /// Accuracy of every answer checked, in order
+    pub fn accuracies(&self) -> Vec<f32> {
+        self.events
+            .iter()
+            .filter_map(|entry| match entry.event {
+                ReplayEvent::Answer { accuracy, .. } => Some(accuracy),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Read a replay file, or a `--log-format json` event log (see
+    /// [`from_event_log`](Self::from_event_log)), refusing replay formats
+    /// newer than this version understands
+    pub fn load(path: &Path) -> Result<Self, KickbeatsError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| KickbeatsError::io("read replay", path, e))?;
+        let is_log = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("jsonl"));
+        let replay = if is_log {
+            Self::from_event_log(&text)
+        } else {
+            Self::parse(&text).or_else(|e| Self::from_event_log(&text).map_err(|_| e))
+        };
+        replay.map_err(|e| {
+            KickbeatsError::Storage(format!("Invalid replay {}: {}", path.display(), e))
+        })
+    }
+
+    /// Rebuild a replay from a `--log-format json` event log: its patterns,
+    /// reveals, tempo changes, and when each pattern started playing
+    ///
+    /// The log doesn't say how the session was set up, so the opening
+    /// tempo, complexity, and meter are taken from its first events, and the
+    /// click is taken to be on.
+    pub fn from_event_log(text: &str) -> Result<Self, KickbeatsError> {
+        let mut events = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let logged: LoggedLine = serde_json::from_str(line).map_err(|e| {
+                KickbeatsError::Storage(format!("line {} of the event log: {}", number + 1, e))
+            })?;
+            if let Some(event) = logged.event.to_replay() {
+                events.push(ReplayEntry {
+                    at_ms: (logged.t.max(0.0) * 1000.0).round() as u64,
+                    event,
+                });
+            }
+        }
+
+        let first_pattern = events.iter().find_map(|entry| match &entry.event {
+            ReplayEvent::Pattern {
+                pattern,
+                complexity,
+                ..
+            } => Some((pattern, complexity)),
+            _ => None,
+        });
+        let Some((pattern, complexity)) = first_pattern else {
+            return Err(KickbeatsError::Storage(
+                "the event log has no patterns".to_string(),
+            ));
+        };
+        let meter = pattern
+            .split([':', '+'])
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let tempo_bpm = events
+            .iter()
+            .find_map(|entry| match entry.event {
+                ReplayEvent::Play { bpm } | ReplayEvent::Tempo { bpm } => Some(bpm),
+                _ => None,
+            })
+            .unwrap_or(120);
+
+        Ok(Self {
+            format: REPLAY_FORMAT,
+            session_id: "from event log".to_string(),
+            started_at: 0,
+            tempo_bpm,
+            complexity: complexity.clone(),
+            time_signature: meter,
+            click: true,
+            events,
+        })
+    }
+
+    /// Read a replay from its JSON text
+    pub fn parse(text: &str) -> Result<Self, KickbeatsError> {
+        let replay: Self =
+            serde_json::from_str(text).map_err(|e| KickbeatsError::Storage(e.to_string()))?;
+        if replay.format > REPLAY_FORMAT {
+            return Err(KickbeatsError::Storage(format!(
+                "replay format {} is newer than this kickbeats understands ({}). Upgrade kickbeats to play it",
+                replay.format, REPLAY_FORMAT
+            )));
+        }
+        Ok(replay)
+    }
+
+    /// Write the replay to `dir`, named after when the session started,
+    /// returning where it went
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, KickbeatsError> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| KickbeatsError::io("create replay directory", dir, e))?;
+        let path = dir.join(format!(
+            "kickbeats-session-{}.{}",
+            self.started_at, REPLAY_EXTENSION
+        ));
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| KickbeatsError::Storage(format!("Failed to encode replay: {}", e)))?;
+        write_atomic(&path, json).map_err(|e| KickbeatsError::io("write replay", &path, e))?;
+        Ok(path)
+    }
+}
+
+/// Name a complexity level the way replays save it ("simple", "medium", or "complex")
+pub fn complexity_name(level: ComplexityLevel) -> String {
+    format!("{:?}", level).to_lowercase()
+}
+
+/// Name a time signature the way replays save it (e.g., "7/8")
+pub fn meter_name(meter: TimeSignature) -> String {
+    format!("{}/{}", meter.numerator, meter.denominator)
+}
+
+/// Event for `pattern` becoming current
+pub fn pattern_event(pattern: &Pattern, review: bool) -> ReplayEvent {
+    ReplayEvent::Pattern {
+        pattern: pattern.fingerprint(),
+        complexity: complexity_name(pattern.complexity_level),
+        review,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> SessionReplay {
+        let session =
+            PracticeSession::new(100, ComplexityLevel::Simple, TimeSignature::three_four());
+        let mut replay = SessionReplay::new(&session);
+        replay.record(
+            Duration::ZERO,
+            ReplayEvent::Pattern {
+                pattern: "3/4:x...x.......".to_string(),
+                complexity: "simple".to_string(),
+                review: false,
+            },
+        );
+        replay.record(Duration::from_millis(5), ReplayEvent::Play { bpm: 100 });
+        replay.record(
+            Duration::from_millis(8_250),
+            ReplayEvent::Answer {
+                answer: Some("x...x.......".to_string()),
+                accuracy: 1.0,
+            },
+        );
+        replay
+    }
+
+    #[test]
+    fn test_round_trips_through_json() {
+        let replay = replay();
+        let json = serde_json::to_string(&replay).unwrap();
+        assert!(json.contains(r#""event":"play","bpm":100"#));
+        assert_eq!(SessionReplay::parse(&json).unwrap(), replay);
+    }
+
+    #[test]
+    fn test_summarizes_answers_and_duration() {
+        let replay = replay();
+        assert_eq!(replay.accuracies(), vec![1.0]);
+        assert_eq!(replay.duration(), Duration::from_millis(8_250));
+        assert_eq!(replay.meter().unwrap(), TimeSignature::three_four());
+        assert_eq!(replay.complexity_level().unwrap(), ComplexityLevel::Simple);
+    }
+
+    #[test]
+    fn test_rebuilds_a_replay_from_an_event_log() {
+        let log = r#"{"t":0.0,"event":"pattern_generated","number":1,"pattern":"6/8:x.....x.....","complexity":"simple","review":false}
+{"t":2.504,"event":"loop_start","loop_index":0,"tempo":90,"drift_ms":0.1}
+{"t":6.504,"event":"loop_start","loop_index":1,"tempo":90,"drift_ms":12.0}
+{"t":6.504,"event":"drift_warning","loop_index":1,"drift_ms":12.0,"threshold_ms":10.0}
+{"t":7.1,"event":"reveal","number":1,"steps":"x.....x....."}
+{"t":9.0,"event":"tempo_change","bpm":100}
+"#;
+        let replay = SessionReplay::from_event_log(log).unwrap();
+        assert_eq!(replay.tempo_bpm, 90);
+        assert_eq!(replay.meter().unwrap(), TimeSignature::six_eight());
+        assert_eq!(replay.complexity_level().unwrap(), ComplexityLevel::Simple);
+        let events: Vec<(u64, ReplayEvent)> = replay
+            .events
+            .into_iter()
+            .map(|entry| (entry.at_ms, entry.event))
+            .collect();
+        assert_eq!(
+            events[1..],
+            [
+                (2_504, ReplayEvent::Play { bpm: 90 }),
+                (7_100, ReplayEvent::Reveal),
+                (9_000, ReplayEvent::Tempo { bpm: 100 }),
+            ]
+        );
+
+        assert!(
+            SessionReplay::from_event_log("{\"t\":1.0,\"event\":\"reveal\"}")
+                .unwrap_err()
+                .to_string()
+                .contains("no patterns")
+        );
+        assert!(SessionReplay::from_event_log("not json")
+            .unwrap_err()
+            .to_string()
+            .contains("line 1"));
+    }
+
+    #[test]
+    fn test_refuses_newer_formats() {
+        let mut replay = replay();
+        replay.format = REPLAY_FORMAT + 1;
+        let json = serde_json::to_string(&replay).unwrap();
+        assert!(matches!(
+            SessionReplay::parse(&json),
+            Err(KickbeatsError::Storage(message)) if message.contains("newer")
+        ));
+    }
+}
@@ -0,0 +1,101 @@
+use crate::error::KickbeatsError;
+use crate::models::{ReviewCard, ReviewQueue};
+use serde::{Deserialize, Serialize};
+
+/// Name of the review queue file kept alongside the session files
+pub const REVIEW_FILE_NAME: &str = "review.json";
+
+/// One review card as saved in the review queue file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewRecord {
+    /// The pattern's fingerprint (e.g., "4/4:x..x......x.....")
+    pub pattern: String,
+    /// Complexity level ("simple", "medium", or "complex")
+    pub complexity: String,
+    /// SM-2 easiness factor
+    pub easiness: f32,
+    /// Reviews passed in a row
+    pub repetitions: u32,
+    /// Days between the last review and the next
+    pub interval_days: u32,
+    /// Day number (days since 1970-01-01) the pattern is next due
+    pub due_day: u64,
+    /// Times the pattern was missed
+    pub lapses: u32,
+}
+
+impl ReviewRecord {
+    /// Save a card
+    pub fn from_card(card: &ReviewCard) -> Self {
+        Self {
+            pattern: card.fingerprint.clone(),
+            complexity: format!("{:?}", card.complexity).to_lowercase(),
+            easiness: card.easiness,
+            repetitions: card.repetitions,
+            interval_days: card.interval_days,
+            due_day: card.due_day,
+            lapses: card.lapses,
+        }
+    }
+
+    /// Restore the saved card
+    pub fn to_card(&self) -> Result<ReviewCard, KickbeatsError> {
+        Ok(ReviewCard {
+            fingerprint: self.pattern.clone(),
+            complexity: self.complexity.parse().map_err(KickbeatsError::Storage)?,
+            easiness: self.easiness,
+            repetitions: self.repetitions,
+            interval_days: self.interval_days,
+            due_day: self.due_day,
+            lapses: self.lapses,
+        })
+    }
+}
+
+/// The saved review queue
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReviewFile {
+    /// Every queued pattern
+    pub cards: Vec<ReviewRecord>,
+}
+
+impl ReviewFile {
+    /// Save a queue
+    pub fn from_queue(queue: &ReviewQueue) -> Self {
+        Self {
+            cards: queue.cards().iter().map(ReviewRecord::from_card).collect(),
+        }
+    }
+
+    /// Restore the saved queue
+    pub fn to_queue(&self) -> Result<ReviewQueue, KickbeatsError> {
+        let cards = self
+            .cards
+            .iter()
+            .map(ReviewRecord::to_card)
+            .collect::<Result<_, _>>()?;
+        Ok(ReviewQueue::from_cards(cards))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, Pattern, TimeSignature};
+
+    #[test]
+    fn test_queue_round_trips_through_json() {
+        let steps = (0..16).map(|i| i % 6 == 0).collect();
+        let pattern = Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Complex);
+        let mut queue = ReviewQueue::new();
+        queue.record(&pattern, 1, 20_000);
+
+        let json = serde_json::to_string(&ReviewFile::from_queue(&queue)).unwrap();
+        assert!(json.contains("\"pattern\":\"4/4:x.....x.....x...\""));
+        assert!(json.contains("\"complexity\":\"complex\""));
+
+        let file: ReviewFile = serde_json::from_str(&json).unwrap();
+        let restored = file.to_queue().unwrap();
+        assert_eq!(restored.cards(), queue.cards());
+    }
+}
@@ -0,0 +1,288 @@
+use super::{ReviewRecord, SessionRecord, Storage};
+use crate::error::KickbeatsError;
+use crate::models::ReviewQueue;
+use rusqlite::{params, Connection, OpenFlags};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// File the SQLite history is kept in, inside the storage directory
+pub const SQLITE_FILE_NAME: &str = "history.sqlite3";
+
+/// How long a save waits for another kickbeats process to finish writing
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Tables created when the database is first written to
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+    session_id TEXT PRIMARY KEY,
+    started_at INTEGER NOT NULL,
+    record TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS sessions_by_start ON sessions (started_at, session_id);
+CREATE TABLE IF NOT EXISTS review_cards (
+    fingerprint TEXT PRIMARY KEY,
+    record TEXT NOT NULL
+);
+";
+
+/// Practice history kept in a single SQLite database
+///
+/// Each session is a row keyed by its id and indexed by start time, holding
+/// the same JSON summary the `json` backend writes, so years of history load
+/// in one query instead of one file read per session. Review cards get a row
+/// each too, keyed by pattern. The database is only
+/// created on the first save; reading a history that doesn't exist yet
+/// finds nothing.
+#[derive(Debug, Clone)]
+pub struct SqliteStorage {
+    path: PathBuf,
+}
+
+impl SqliteStorage {
+    /// Keep sessions in `dir`, in a database created on the first save
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            path: dir.join(SQLITE_FILE_NAME),
+        }
+    }
+
+    /// The database file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Open the database to read, or `None` if nothing has been saved yet
+    fn read(&self) -> Result<Option<Connection>, KickbeatsError> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        let connection = Connection::open_with_flags(&self.path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .and_then(|connection| {
+                connection.busy_timeout(BUSY_TIMEOUT)?;
+                Ok(connection)
+            })
+            .map_err(|e| self.error("Failed to open practice history", e))?;
+        Ok(Some(connection))
+    }
+
+    /// Open the database to write, creating it and its tables if needed
+    fn write(&self) -> Result<Connection, KickbeatsError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| KickbeatsError::io("create practice history directory", dir, e))?;
+        }
+        Connection::open(&self.path)
+            .and_then(|connection| {
+                connection.busy_timeout(BUSY_TIMEOUT)?;
+                connection.execute_batch(SCHEMA)?;
+                Ok(connection)
+            })
+            .map_err(|e| self.error("Failed to open practice history", e))
+    }
+
+    /// A database error, naming the file
+    fn error(&self, context: &str, e: rusqlite::Error) -> KickbeatsError {
+        KickbeatsError::Storage(format!("{} {}: {}", context, self.path.display(), e))
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn save_session(&self, record: &SessionRecord) -> Result<(), KickbeatsError> {
+        let json = serde_json::to_string(record).map_err(|e| {
+            KickbeatsError::Storage(format!("Failed to encode practice history: {}", e))
+        })?;
+        self.write()?
+            .execute(
+                "INSERT OR REPLACE INTO sessions (session_id, started_at, record)
+                 VALUES (?1, ?2, ?3)",
+                params![record.session_id, record.started_at as i64, json],
+            )
+            .map_err(|e| self.error("Failed to save practice history to", e))?;
+        Ok(())
+    }
+
+    fn sessions(&self) -> Result<Vec<SessionRecord>, KickbeatsError> {
+        let Some(connection) = self.read()? else {
+            return Ok(Vec::new());
+        };
+        let rows = connection
+            .prepare("SELECT session_id, record FROM sessions ORDER BY started_at, session_id")
+            .and_then(|mut statement| {
+                statement
+                    .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<Result<Vec<(String, String)>, _>>()
+            })
+            .map_err(|e| self.error("Failed to read practice history in", e))?;
+        rows.into_iter()
+            .map(|(id, json)| {
+                serde_json::from_str(&json).map_err(|e| {
+                    KickbeatsError::Storage(format!(
+                        "Invalid practice history for session {} in {}: {}",
+                        id,
+                        self.path.display(),
+                        e
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    fn review_queue(&self) -> Result<ReviewQueue, KickbeatsError> {
+        let Some(connection) = self.read()? else {
+            return Ok(ReviewQueue::new());
+        };
+        // Databases saved before review cards existed don't have the table yet
+        let rows = connection
+            .prepare("SELECT record FROM review_cards ORDER BY rowid")
+            .and_then(|mut statement| {
+                statement
+                    .query_map([], |row| row.get(0))?
+                    .collect::<Result<Vec<String>, _>>()
+            });
+        let rows = match rows {
+            Ok(rows) => rows,
+            Err(e) if e.to_string().contains("no such table") => return Ok(ReviewQueue::new()),
+            Err(e) => return Err(self.error("Failed to read review queue in", e)),
+        };
+        let cards = rows
+            .iter()
+            .map(|json| {
+                serde_json::from_str::<ReviewRecord>(json)
+                    .map_err(|e| e.to_string())
+                    .and_then(|record| record.to_card().map_err(|e| e.to_string()))
+                    .map_err(|e| {
+                        KickbeatsError::Storage(format!(
+                            "Invalid review card in {}: {}",
+                            self.path.display(),
+                            e
+                        ))
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ReviewQueue::from_cards(cards))
+    }
+
+    fn save_review_queue(&self, queue: &ReviewQueue) -> Result<(), KickbeatsError> {
+        // Only this session's grades are written, so cards another session
+        // saved in the meantime are left alone
+        let mut connection = self.write()?;
+        let transaction = connection
+            .transaction()
+            .map_err(|e| self.error("Failed to save review queue to", e))?;
+        for card in queue.graded_cards() {
+            let json = serde_json::to_string(&ReviewRecord::from_card(card)).map_err(|e| {
+                KickbeatsError::Storage(format!("Failed to encode review queue: {}", e))
+            })?;
+            transaction
+                .execute(
+                    "INSERT INTO review_cards (fingerprint, record) VALUES (?1, ?2)
+                     ON CONFLICT (fingerprint) DO UPDATE SET record = excluded.record",
+                    params![card.fingerprint, json],
+                )
+                .map_err(|e| self.error("Failed to save review queue to", e))?;
+        }
+        transaction
+            .commit()
+            .map_err(|e| self.error("Failed to save review queue to", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ComplexityLevel, Pattern, TimeSignature};
+
+    fn record(id: &str, started_at: u64) -> SessionRecord {
+        SessionRecord {
+            session_id: id.to_string(),
+            started_at,
+            duration_secs: 60,
+            tempo_bpm: 120,
+            complexity: "medium".to_string(),
+            time_signature: "4/4".to_string(),
+            patterns_generated: 2,
+            answer_accuracies: vec![0.75],
+            goal: None,
+            goal_met: false,
+            reveals: 1,
+            taps: 0,
+            taps_on_time: 0,
+            complexity_secs: Default::default(),
+            relaxed_patterns: Default::default(),
+            notes: Vec::new(),
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("kickbeats-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_saves_and_lists_sessions_oldest_first() {
+        let dir = temp_dir("sqlite");
+        let storage = SqliteStorage::new(dir.clone());
+        assert_eq!(storage.sessions().unwrap(), Vec::new());
+        assert!(!storage.path().exists());
+
+        let later = record("bbbbbbbb-2222", 2_000);
+        let earlier = record("aaaaaaaa-1111", 1_000);
+        storage.save_session(&later).unwrap();
+        storage.save_session(&earlier).unwrap();
+        // Saving the same session again replaces it
+        storage.save_session(&later).unwrap();
+
+        assert_eq!(storage.sessions().unwrap(), vec![earlier, later]);
+        assert!(dir.join(SQLITE_FILE_NAME).exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_review_cards_are_saved_one_row_each() {
+        let dir = temp_dir("sqlite-review");
+        let storage = SqliteStorage::new(dir.clone());
+        assert!(storage.review_queue().unwrap().is_empty());
+        storage
+            .save_session(&record("aaaaaaaa-1111", 1_000))
+            .unwrap();
+        assert!(storage.review_queue().unwrap().is_empty());
+
+        let pattern = |kicks: &[usize]| {
+            let steps = (0..16).map(|i| kicks.contains(&i)).collect();
+            Pattern::new(steps, TimeSignature::four_four(), ComplexityLevel::Medium)
+        };
+        let mut first = ReviewQueue::new();
+        first.record(&pattern(&[0, 3, 10]), 1, 20_000);
+        storage.save_review_queue(&first).unwrap();
+        // A session that loaded before the first saved doesn't drop its card
+        let mut second = ReviewQueue::new();
+        second.record(&pattern(&[0, 6, 12]), 1, 20_000);
+        storage.save_review_queue(&second).unwrap();
+
+        let saved = storage.review_queue().unwrap();
+        assert_eq!(saved.cards(), [first.cards(), second.cards()].concat());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_reports_a_corrupt_row() {
+        let dir = temp_dir("sqlite-corrupt");
+        let storage = SqliteStorage::new(dir.clone());
+        storage
+            .save_session(&record("aaaaaaaa-1111", 1_000))
+            .unwrap();
+        Connection::open(storage.path())
+            .unwrap()
+            .execute("UPDATE sessions SET record = '{'", [])
+            .unwrap();
+
+        let err = storage.sessions().unwrap_err().to_string();
+        assert!(err.contains("aaaaaaaa-1111"), "{}", err);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
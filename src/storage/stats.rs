@@ -0,0 +1,357 @@
+use super::SessionRecord;
+use crate::models::relaxed_summary;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Seconds in a day; practice days are counted in UTC
+pub const DAY_SECS: u64 = 86_400;
+
+/// Totals over a set of sessions
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeriodStats {
+    /// Sessions practiced
+    pub sessions: u32,
+    /// Time practiced
+    pub secs: u64,
+    /// Patterns heard
+    pub patterns: u32,
+    /// Patterns revealed
+    pub reveals: u32,
+    /// Answers checked
+    pub answers: u32,
+    /// Accuracy of those answers, summed
+    accuracy_total: f32,
+    /// Drum pad kick taps
+    pub taps: u32,
+    /// Taps graded perfect or good
+    pub taps_on_time: u32,
+}
+
+impl PeriodStats {
+    /// Add a session to the totals
+    pub fn add(&mut self, record: &SessionRecord) {
+        self.sessions += 1;
+        self.secs += record.duration_secs;
+        self.patterns += record.patterns_generated;
+        self.reveals += record.reveals;
+        self.answers += record.answer_accuracies.len() as u32;
+        self.accuracy_total += record.answer_accuracies.iter().sum::<f32>();
+        self.taps += record.taps;
+        self.taps_on_time += record.taps_on_time;
+    }
+
+    /// Mean answer accuracy (0.0-1.0), if any answers were checked
+    pub fn average_accuracy(&self) -> Option<f32> {
+        (self.answers > 0).then(|| self.accuracy_total / self.answers as f32)
+    }
+
+    /// Share of taps on time (0.0-1.0), if there were any
+    pub fn tap_accuracy(&self) -> Option<f32> {
+        (self.taps > 0).then(|| self.taps_on_time as f32 / self.taps as f32)
+    }
+}
+
+/// Practice trends across the saved history, for `kickbeats stats`
+///
+/// # Examples
+///
+/// ```
+/// use kickbeats_cli::storage::{PracticeStats, SessionRecord};
+///
+/// let stats = PracticeStats::new(&[], 20_000, 7, 4);
+/// assert_eq!(stats.total.sessions, 0);
+/// assert_eq!(stats.current_streak, 0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PracticeStats {
+    /// Everything in the history
+    pub total: PeriodStats,
+    /// The last few days, oldest first, by day number (days since 1970-01-01)
+    pub days: Vec<(u64, PeriodStats)>,
+    /// The last few weeks, oldest first, by the day number of their Monday
+    pub weeks: Vec<(u64, PeriodStats)>,
+    /// Days in a row practiced, ending today (or yesterday, if today isn't over)
+    pub current_streak: u32,
+    /// Most days in a row ever practiced
+    pub longest_streak: u32,
+    /// Seconds at each complexity over the whole history
+    pub complexity_secs: BTreeMap<String, u64>,
+    /// Patterns over the whole history that settled for less than 3 steps
+    /// from recent ones, by the distance they settled for
+    pub relaxed_patterns: BTreeMap<u32, u32>,
+}
+
+impl PracticeStats {
+    /// Gather stats from `records` as of day number `today`, covering the
+    /// last `days` days and `weeks` weeks in detail
+    pub fn new(records: &[SessionRecord], today: u64, days: u32, weeks: u32) -> Self {
+        let mut total = PeriodStats::default();
+        let mut by_day: BTreeMap<u64, PeriodStats> = BTreeMap::new();
+        let mut complexity_secs = BTreeMap::new();
+        let mut relaxed_patterns = BTreeMap::new();
+        for record in records {
+            total.add(record);
+            by_day
+                .entry(record.started_at / DAY_SECS)
+                .or_default()
+                .add(record);
+            for (level, secs) in &record.complexity_secs {
+                *complexity_secs.entry(level.clone()).or_insert(0) += secs;
+            }
+            for (distance, count) in &record.relaxed_patterns {
+                *relaxed_patterns.entry(*distance).or_insert(0) += count;
+            }
+        }
+
+        let first_day = today.saturating_sub(u64::from(days.max(1)) - 1);
+        let days = (first_day..=today)
+            .map(|day| (day, by_day.get(&day).cloned().unwrap_or_default()))
+            .collect();
+
+        let this_week = week_start(today);
+        let first_week = this_week.saturating_sub(7 * (u64::from(weeks.max(1)) - 1));
+        let weeks = (first_week..=this_week)
+            .step_by(7)
+            .map(|monday| {
+                let mut week = PeriodStats::default();
+                for record in records {
+                    if week_start(record.started_at / DAY_SECS) == monday {
+                        week.add(record);
+                    }
+                }
+                (monday, week)
+            })
+            .collect();
+
+        let practiced: BTreeSet<u64> = by_day.keys().copied().collect();
+        let (current_streak, longest_streak) = streaks(&practiced, today);
+
+        Self {
+            total,
+            days,
+            weeks,
+            current_streak,
+            longest_streak,
+            complexity_secs,
+            relaxed_patterns,
+        }
+    }
+}
+
+/// Current and longest runs of consecutive practice days
+fn streaks(practiced: &BTreeSet<u64>, today: u64) -> (u32, u32) {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<u64> = None;
+    for &day in practiced {
+        run = if previous.is_some_and(|p| p + 1 == day) {
+            run + 1
+        } else {
+            1
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    // A streak that reached yesterday is still alive until today ends
+    let mut current = 0;
+    let mut day = if practiced.contains(&today) {
+        today
+    } else {
+        today.wrapping_sub(1)
+    };
+    while practiced.contains(&day) {
+        current += 1;
+        day = day.wrapping_sub(1);
+    }
+    (current, longest)
+}
+
+/// Day number of the Monday starting the week `day` falls in (1970-01-01 was a Thursday)
+fn week_start(day: u64) -> u64 {
+    day - (day + 3) % 7
+}
+
+/// Calendar date of a day number, as YYYY-MM-DD (UTC)
+pub fn format_day(day: u64) -> String {
+    // Civil-from-days, after Howard Hinnant's date algorithms
+    let z = day as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+/// A duration as "1h 05m" or "12m"
+fn format_duration(secs: u64) -> String {
+    let minutes = secs / 60;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// "84.3%", or "—" when there's nothing to average
+fn format_percent(value: Option<f32>) -> String {
+    value.map_or_else(|| "—".to_string(), |v| format!("{:.1}%", v * 100.0))
+}
+
+impl fmt::Display for PracticeStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let total = &self.total;
+        writeln!(
+            f,
+            "Practice stats: {} session{}, {}",
+            total.sessions,
+            if total.sessions == 1 { "" } else { "s" },
+            format_duration(total.secs)
+        )?;
+        writeln!(
+            f,
+            "Streak: {} day{} (longest {})",
+            self.current_streak,
+            if self.current_streak == 1 { "" } else { "s" },
+            self.longest_streak
+        )?;
+        writeln!(
+            f,
+            "Patterns heard: {} · revealed: {} · answers: {} (average {})",
+            total.patterns,
+            total.reveals,
+            total.answers,
+            format_percent(total.average_accuracy())
+        )?;
+        if total.taps > 0 {
+            writeln!(
+                f,
+                "Drum pad taps: {} ({} on time)",
+                total.taps,
+                format_percent(total.tap_accuracy())
+            )?;
+        }
+        if !self.complexity_secs.is_empty() {
+            let levels: Vec<String> = ["simple", "medium", "complex"]
+                .iter()
+                .filter_map(|level| {
+                    let secs = self.complexity_secs.get(*level)?;
+                    Some(format!("{} {}", level, format_duration(*secs)))
+                })
+                .collect();
+            writeln!(f, "Time by complexity: {}", levels.join(" · "))?;
+        }
+        if let Some(relaxed) = relaxed_summary(&self.relaxed_patterns) {
+            writeln!(f, "Relaxed uniqueness: {}", relaxed)?;
+        }
+
+        writeln!(f, "\nBy day:")?;
+        for (day, stats) in &self.days {
+            write_period(f, &format_day(*day), stats)?;
+        }
+        writeln!(f, "\nBy week:")?;
+        for (monday, stats) in &self.weeks {
+            write_period(f, &format!("Week of {}", format_day(*monday)), stats)?;
+        }
+        Ok(())
+    }
+}
+
+/// One row of the day or week table
+fn write_period(f: &mut fmt::Formatter, label: &str, stats: &PeriodStats) -> fmt::Result {
+    if stats.sessions == 0 {
+        return writeln!(f, "  {:<20} —", label);
+    }
+    writeln!(
+        f,
+        "  {:<20} {:>2} session{} {:>8} {:>4} patterns   avg {}",
+        label,
+        stats.sessions,
+        if stats.sessions == 1 { " " } else { "s" },
+        format_duration(stats.secs),
+        stats.patterns,
+        format_percent(stats.average_accuracy())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(day: u64, accuracies: Vec<f32>) -> SessionRecord {
+        SessionRecord {
+            session_id: format!("session-{}", day),
+            started_at: day * DAY_SECS + 3600,
+            duration_secs: 600,
+            tempo_bpm: 120,
+            complexity: "medium".to_string(),
+            time_signature: "4/4".to_string(),
+            patterns_generated: 5,
+            answer_accuracies: accuracies,
+            goal: None,
+            goal_met: false,
+            reveals: 2,
+            taps: 4,
+            taps_on_time: 3,
+            complexity_secs: [("medium".to_string(), 600)].into_iter().collect(),
+            relaxed_patterns: BTreeMap::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_format_day() {
+        assert_eq!(format_day(0), "1970-01-01");
+        assert_eq!(format_day(20_741), "2026-10-15");
+        assert_eq!(format_day(11_016), "2000-02-29");
+        assert_eq!(week_start(20_741), 20_738);
+        assert_eq!(format_day(20_738), "2026-10-12");
+    }
+
+    #[test]
+    fn test_streaks() {
+        let days = |list: &[u64]| list.iter().copied().collect::<BTreeSet<u64>>();
+        assert_eq!(streaks(&days(&[1, 2, 3, 7, 8]), 8), (2, 3));
+        // Yesterday still counts until today is over
+        assert_eq!(streaks(&days(&[6, 7]), 8), (2, 2));
+        assert_eq!(streaks(&days(&[5, 6]), 8), (0, 2));
+        assert_eq!(streaks(&days(&[]), 8), (0, 0));
+    }
+
+    #[test]
+    fn test_totals_days_and_weeks() {
+        let today = 20_741;
+        let mut records = vec![
+            record(today - 8, vec![0.5]),
+            record(today - 1, vec![1.0, 0.5]),
+            record(today, vec![]),
+            record(today, vec![1.0]),
+        ];
+        records[1].relaxed_patterns = [(2, 2), (1, 1)].into_iter().collect();
+        records[3].relaxed_patterns = [(2, 1)].into_iter().collect();
+        let stats = PracticeStats::new(&records, today, 3, 2);
+
+        assert_eq!(stats.total.sessions, 4);
+        assert_eq!(stats.total.answers, 4);
+        assert_eq!(stats.total.average_accuracy(), Some(0.75));
+        assert_eq!(stats.total.tap_accuracy(), Some(0.75));
+        assert_eq!(stats.complexity_secs["medium"], 2400);
+        assert_eq!((stats.current_streak, stats.longest_streak), (2, 2));
+
+        let sessions: Vec<(u64, u32)> = stats.days.iter().map(|(d, s)| (*d, s.sessions)).collect();
+        assert_eq!(sessions, vec![(today - 2, 0), (today - 1, 1), (today, 2)]);
+        let weeks: Vec<u32> = stats.weeks.iter().map(|(_, s)| s.sessions).collect();
+        assert_eq!(weeks, vec![1, 3]);
+
+        let text = stats.to_string();
+        assert!(text.starts_with("Practice stats: 4 sessions, 40m\nStreak: 2 days (longest 2)\n"));
+        assert!(text.contains("Time by complexity: medium 40m"));
+        assert!(text.contains("Relaxed uniqueness: 4 patterns (3 at distance 2, 1 at distance 1)"));
+        assert!(text.contains("  2026-10-13           —"));
+        assert!(text.contains("Week of 2026-10-12"));
+    }
+}
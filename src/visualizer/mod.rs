@@ -1,6 +0,0 @@
-// Visualizer module
-// ASCII art rendering for pattern display
-
-pub mod ascii;
-
-pub use ascii::format_pattern_with_metadata;
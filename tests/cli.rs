@@ -0,0 +1,1210 @@
+// End-to-end tests running the compiled binary.
+
+mod common;
+
+use common::Kickbeats;
+
+#[test]
+fn help_lists_options() {
+    let run = Kickbeats::new().args(&["--help"]).run();
+
+    assert_eq!(run.status, Some(0));
+    for flag in [
+        "--tempo",
+        "--complexity",
+        "--time-signature",
+        "--ramp",
+        "--no-midi",
+        "--listen-delay",
+        "--weights",
+        "--generator",
+        "--style",
+        "--overlay",
+        "--send-clock",
+        #[cfg(feature = "midi")]
+        "--log-midi",
+    ] {
+        assert!(
+            run.stdout.contains(flag),
+            "missing {} in:\n{}",
+            flag,
+            run.stdout
+        );
+    }
+}
+
+/// Invalid flags and values: the arguments, the exit status (2 for usage
+/// errors clap catches, 1 for ones found after parsing), and part of the message
+const REJECTED: &[(&[&str], i32, &str)] = &[
+    (&["--tempo", "500"], 2, "--tempo"),
+    (&["--tempo", "39"], 2, "--tempo"),
+    (&["--seed", "abc"], 2, "--seed"),
+    (
+        &["--pattern", "4/4:16:x..."],
+        2,
+        "has 4 steps, but its meter needs 16",
+    ),
+    (&["--mystery", "--seed", "7"], 2, "--seed"),
+    (&["--complexity", "extreme"], 2, "--complexity"),
+    (&["--level", "11"], 2, "Complexity 11 is out of range"),
+    (
+        &["--level", "3", "--complexity", "simple"],
+        2,
+        "cannot be used with",
+    ),
+    (
+        &["--time-signature", "4/3"],
+        2,
+        "Denominator 3 is not standard",
+    ),
+    (&["--ramp", "5bpm"], 2, "Invalid ramp"),
+    (&["--no-midi=yes"], 2, "--no-midi"),
+    (&["--listen-delay", "90"], 2, "--listen-delay"),
+    (
+        &["generate", "--style", "funk", "--time-signature", "3/4"],
+        1,
+        "only available in 4/4",
+    ),
+    (&["generate", "--hits", "5"], 1, "--hits only applies"),
+    (
+        &["generate", "--humanize", "20"],
+        1,
+        "--humanize only applies when patterns play",
+    ),
+    (&["generate", "--no-click"], 1, "--no-click only applies"),
+    (&["notation", "--render", "png"], 2, "--output"),
+    (&["audio", "-o", "groove.wav", "-n", "0"], 2, "--loops"),
+    (
+        &["audio", "-o", "groove.wav", "--sample-rate", "4000"],
+        2,
+        "--sample-rate",
+    ),
+    (
+        &[
+            "notation",
+            "-f",
+            "abc",
+            "-o",
+            "groove.abc",
+            "--render",
+            "pdf",
+        ],
+        1,
+        "--render engraves with LilyPond",
+    ),
+    (
+        &["generate", "--kicks-min", "6", "--kicks-max", "4"],
+        1,
+        "is backwards",
+    ),
+    (&["generate", "--syncopation", "1.5"], 1, "Syncopation 1.5"),
+    (
+        &[
+            "generate",
+            "--kicks-min",
+            "9",
+            "--kicks-max",
+            "9",
+            "--allow-consecutive",
+            "1",
+        ],
+        1,
+        "--allow-consecutive 2",
+    ),
+    (
+        &[
+            "generate",
+            "--generator",
+            "euclidean",
+            "--syncopation",
+            "0.8",
+        ],
+        1,
+        "only apply to --generator weighted",
+    ),
+    (
+        &["generate", "--weights", "does-not-exist.toml"],
+        1,
+        "Failed to read weights file",
+    ),
+    (&["--humanize", "150"], 2, "--humanize"),
+    (
+        &["generate", "--mystery", "--style", "rock"],
+        2,
+        "cannot be used with",
+    ),
+    (&["generate", "--grooves", "grooves"], 2, "--mystery"),
+    (
+        &["generate", "--mystery", "--grooves", "does-not-exist"],
+        1,
+        "Failed to read grooves directory",
+    ),
+    (&["--drill", "0loops/20s"], 2, "at least 1 loop"),
+    (
+        &["--config", "does-not-exist.toml"],
+        1,
+        "Failed to read config file",
+    ),
+    (&["--midi-channel", "17"], 2, "--midi-channel"),
+    (
+        &["--answer-format", "staff"],
+        2,
+        "Must be one of: grid, ioi",
+    ),
+    (
+        &["--click-fade", "90%/0"],
+        2,
+        "Click fade step must be between 1 and 127",
+    ),
+    (&["--phrase", "1"], 2, "--phrase"),
+    (&["--sync=IAC", "--send-clock"], 2, "cannot be used with"),
+    (&["--kit-echo"], 2, "--kit-input"),
+    (
+        &["--loops", "4", "--drill", "4loops/20s"],
+        2,
+        "cannot be used with",
+    ),
+    (
+        &["--pack", "funk101", "generate"],
+        1,
+        "No practice pack named funk101 is installed",
+    ),
+    (&["generate", "--color", "sometimes"], 2, "--color"),
+    (
+        &["generate", "--time-signature", "7/8", "--grouping", "2+2+2"],
+        1,
+        "adds up to 6 beats, but 7/8 has 7",
+    ),
+    (&["generate", "--grouping", "2+0+2"], 2, "Invalid grouping"),
+    (&["--click-gap", "2on"], 2, "Invalid click gap"),
+    (&["--gap-kicks"], 2, "--click-gap"),
+    (
+        &["--fills", "every=1"],
+        2,
+        "Fills must come every 2 to 32 bars",
+    ),
+    (
+        &["--fills", "every=4", "--meters", "4/4,3/4"],
+        2,
+        "--meters",
+    ),
+    (
+        &["--hemiola", "pulse=6", "--click-ratio", "3:4"],
+        2,
+        "--click-ratio",
+    ),
+    (
+        &["--tempo-range", "140-90"],
+        2,
+        "needs a slower tempo first",
+    ),
+    (&["--tempo-range", "90-140", "--tempo", "100"], 2, "--tempo"),
+    (&["--kick-velocity", "128"], 2, "0..=127"),
+    (&["--click-channel", "17"], 2, "1..=16"),
+    (&["--count-in", "5"], 2, "0..=4"),
+    (
+        &["--ghosts", "chance=100"],
+        2,
+        "Ghost steps must sound between 1% and 99% of the time",
+    ),
+];
+
+/// Rejections only a build with MIDI playback gets as far as
+#[cfg(feature = "midi")]
+const REJECTED_WITH_MIDI: &[(&[&str], i32, &str)] = &[
+    (
+        &[
+            "--overlay",
+            "son-clave",
+            "--time-signature",
+            "3/4",
+            "--no-midi",
+        ],
+        1,
+        "does not fit 3/4",
+    ),
+    (
+        &["--kit-input=TD-17", "--headless"],
+        2,
+        "cannot be used with",
+    ),
+    (
+        &["replay", "does-not-exist.kbreplay"],
+        1,
+        "Failed to read replay",
+    ),
+    (
+        &["--dropout", "bars=4", "every=4", "--no-midi"],
+        1,
+        "at least one of every 4 bars",
+    ),
+    (
+        &[
+            "--hemiola",
+            "pulse=dotted-quarter",
+            "--time-signature",
+            "6/8",
+            "--no-midi",
+        ],
+        1,
+        "already the beat of 6/8",
+    ),
+    (&["--quiz-only"], 2, "--host"),
+    (
+        &["--no-midi", "join", "127.0.0.1:1"],
+        1,
+        "Failed to join the class at 127.0.0.1:1",
+    ),
+];
+
+#[test]
+fn rejects_invalid_arguments() {
+    #[cfg(feature = "midi")]
+    let rejected = REJECTED.iter().chain(REJECTED_WITH_MIDI);
+    #[cfg(not(feature = "midi"))]
+    let rejected = REJECTED.iter();
+    for (args, status, message) in rejected {
+        let run = Kickbeats::new().args(args).run();
+        assert_eq!(run.status, Some(*status), "{:?}: {}", args, run.stderr);
+        assert!(run.stderr.contains(message), "{:?}: {}", args, run.stderr);
+    }
+}
+
+#[test]
+#[cfg(feature = "midi")]
+fn interactive_mode_requires_a_terminal() {
+    let run = Kickbeats::new().args(&["--no-midi"]).line("q").run();
+
+    assert_eq!(run.status, Some(1));
+    assert!(run.stderr.contains("not connected to a terminal"));
+}
+
+#[test]
+#[cfg(feature = "midi")]
+fn headless_mode_speaks_json_lines() {
+    let run = Kickbeats::new()
+        .args(&["--headless", "--no-midi", "--tempo", "90"])
+        .line(r#"{"cmd":"reveal"}"#)
+        .line(r#"{"cmd":"set-tempo","bpm":500}"#)
+        .line("not json")
+        .line(r#"{"cmd":"quit"}"#)
+        .run();
+
+    assert_eq!(run.status, Some(0));
+    let lines: Vec<&str> = run.stdout.lines().collect();
+    assert!(lines[0].starts_with(r#"{"event":"ready","tempo":90"#));
+    assert!(lines
+        .iter()
+        .any(|l| l.starts_with(r#"{"event":"revealed","steps":""#)));
+    assert!(run.stdout.contains("Tempo 500 is out of range"));
+    assert!(run.stdout.contains("Invalid command 'not json'"));
+    assert!(lines
+        .last()
+        .unwrap()
+        .starts_with(r#"{"event":"summary","patterns":1"#));
+}
+
+#[test]
+#[cfg(feature = "midi")]
+fn sessions_are_saved_to_the_configured_history() {
+    let home = concat!(env!("CARGO_TARGET_TMPDIR"), "/history");
+    let history = format!("{}/sessions", home);
+    let _ = std::fs::remove_dir_all(home);
+    std::fs::create_dir_all(format!("{}/kickbeats", home)).unwrap();
+    std::fs::write(
+        format!("{}/kickbeats/config.toml", home),
+        format!("[storage]\nbackend = \"json\"\ndir = \"{}\"\n", history),
+    )
+    .unwrap();
+
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["--headless", "--no-midi", "--tempo", "90"])
+        .line(r#"{"cmd":"quit"}"#)
+        .run();
+
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    let saved: Vec<_> = std::fs::read_dir(&history)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    assert_eq!(saved.len(), 1);
+    assert!(std::fs::read_to_string(&saved[0])
+        .unwrap()
+        .contains("\"tempo_bpm\": 90"));
+}
+
+#[test]
+#[cfg(feature = "midi")]
+fn headless_session_plays_without_a_device() {
+    let run = Kickbeats::new()
+        .args(&["--headless", "--no-midi", "--tempo", "100"])
+        .line(r#"{"cmd":"new"}"#)
+        .line(r#"{"cmd":"reveal"}"#)
+        .line(r#"{"cmd":"set-tempo","bpm":120}"#)
+        .line(r#"{"cmd":"quit"}"#)
+        .run();
+
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(!run.stdout.contains(r#""event":"error""#), "{}", run.stdout);
+    let lines: Vec<&str> = run.stdout.lines().collect();
+    assert!(lines[0].starts_with(r#"{"event":"ready","tempo":100"#));
+    assert!(lines
+        .iter()
+        .any(|l| l.starts_with(r#"{"event":"pattern","number":2"#)));
+    assert!(lines
+        .iter()
+        .any(|l| l.starts_with(r#"{"event":"revealed","steps":""#)));
+    assert!(lines.contains(&r#"{"event":"tempo","bpm":120}"#));
+    assert!(lines
+        .last()
+        .unwrap()
+        .starts_with(r#"{"event":"summary","patterns":2"#));
+}
+
+#[test]
+#[cfg(feature = "midi")]
+fn saved_replays_list_the_session() {
+    let dir = concat!(env!("CARGO_TARGET_TMPDIR"), "/replays");
+    let _ = std::fs::remove_dir_all(dir);
+
+    let run = Kickbeats::new()
+        .args(&["--headless", "--no-midi", "--save-replay", dir])
+        .line(r#"{"cmd":"new"}"#)
+        .line(r#"{"cmd":"set-tempo","bpm":140}"#)
+        .line(r#"{"cmd":"reveal"}"#)
+        .line(r#"{"cmd":"quit"}"#)
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(run.stdout.contains(r#""replay":""#), "{}", run.stdout);
+
+    let saved: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(saved.len(), 1);
+    assert!(saved[0].extension().is_some_and(|ext| ext == "kbreplay"));
+
+    let run = Kickbeats::new()
+        .args(&["replay", "--summary", saved[0].to_str().unwrap()])
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(run.stdout.contains("Pattern #2"), "{}", run.stdout);
+    assert!(run.stdout.contains("Tempo 140 BPM"), "{}", run.stdout);
+    assert!(run
+        .stdout
+        .contains("Session: 2 patterns, no answers checked, 1 revealed"));
+}
+
+#[test]
+#[cfg(feature = "midi")]
+fn stream_file_shows_the_current_pattern() {
+    let dir = concat!(env!("CARGO_TARGET_TMPDIR"), "/stream");
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).unwrap();
+    let text = format!("{}/overlay.txt", dir);
+    let json = format!("{}/overlay.json", dir);
+
+    for file in [&text, &json] {
+        let run = Kickbeats::new()
+            .args(&["--headless", "--no-midi", "--stream-file", file])
+            .line(r#"{"cmd":"new"}"#)
+            .line(r#"{"cmd":"reveal"}"#)
+            .line(r#"{"cmd":"quit"}"#)
+            .run();
+        assert_eq!(run.status, Some(0), "{}", run.stderr);
+    }
+
+    let text = std::fs::read_to_string(text).unwrap();
+    assert!(
+        text.starts_with("Pattern #2 · 120 BPM · 4/4\n|1 e + a |"),
+        "{}",
+        text
+    );
+    assert!(text.contains("|X "), "{}", text);
+    assert!(text.ends_with("○ ○ ○ ○\n"), "{}", text);
+    let json = std::fs::read_to_string(json).unwrap();
+    assert!(json.contains(r#""pattern":2"#), "{}", json);
+    assert!(
+        json.contains(r#""playing":false,"revealed":true"#),
+        "{}",
+        json
+    );
+}
+
+#[test]
+#[cfg(feature = "midi")]
+fn log_format_json_writes_session_events_to_replay() {
+    let path = concat!(env!("CARGO_TARGET_TMPDIR"), "/events.jsonl");
+    let _ = std::fs::remove_file(path);
+    let run = Kickbeats::new()
+        .args(&[
+            "--headless",
+            "--no-midi",
+            "--log-format",
+            "json",
+            "--log-file",
+            path,
+        ])
+        .line(r#"{"cmd":"reveal"}"#)
+        .line(r#"{"cmd":"quit"}"#)
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+
+    let log = std::fs::read_to_string(path).unwrap();
+    let events: Vec<serde_json::Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events[0]["event"], "pattern_generated", "{}", log);
+    assert_eq!(events[0]["number"], 1);
+    assert!(
+        events
+            .iter()
+            .any(|event| event["event"] == "reveal" && event["number"] == 1),
+        "{}",
+        log
+    );
+
+    // The log plays back like a saved replay
+    let run = Kickbeats::new().args(&["replay", "--summary", path]).run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(
+        run.stdout.contains("[00:00] 🎲 Pattern #1"),
+        "{}",
+        run.stdout
+    );
+    assert!(run.stdout.contains("👁  Revealed"), "{}", run.stdout);
+    assert!(run.stdout.contains("1 revealed"), "{}", run.stdout);
+}
+
+#[test]
+#[cfg(feature = "midi")]
+fn stats_counts_patterns_due_for_review() {
+    let home = concat!(env!("CARGO_TARGET_TMPDIR"), "/review");
+    let history = format!("{}/sessions", home);
+    let _ = std::fs::remove_dir_all(home);
+    std::fs::create_dir_all(format!("{}/kickbeats", home)).unwrap();
+    std::fs::create_dir_all(&history).unwrap();
+    std::fs::write(
+        format!("{}/kickbeats/config.toml", home),
+        format!("[storage]\ndir = \"{}\"\n", history),
+    )
+    .unwrap();
+    let card = r#"{"pattern": "4/4:x..x......x.....", "complexity": "medium", "easiness": 2.5,
+        "repetitions": 0, "interval_days": 1, "due_day": 0, "lapses": 1}"#;
+    std::fs::write(
+        format!("{}/review.json", history),
+        format!(r#"{{"cards": [{}]}}"#, card),
+    )
+    .unwrap();
+
+    // Headless sessions leave the queue as it was
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["--headless", "--no-midi"])
+        .line(r#"{"cmd":"quit"}"#)
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+
+    let run = Kickbeats::new().config_home(home).args(&["stats"]).run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(
+        run.stdout.contains("Practice stats: 1 session,"),
+        "{}",
+        run.stdout
+    );
+    assert!(
+        run.stdout
+            .contains("Review queue: 1 missed pattern, 1 due today"),
+        "{}",
+        run.stdout
+    );
+}
+
+#[test]
+fn generate_prints_requested_patterns() {
+    let run = Kickbeats::new()
+        .args(&["generate", "-n", "3", "--time-signature", "3/4"])
+        .run();
+
+    assert_eq!(run.status, Some(0));
+    assert_eq!(run.stdout.matches("Time: 3/4").count(), 3);
+    assert_eq!(
+        run.stdout.matches("|1 e + a |2 e + a |3 e + a |").count(),
+        3
+    );
+}
+
+#[test]
+fn generate_targets_a_difficulty_range() {
+    let run = Kickbeats::new()
+        .args(&["generate", "-n", "4", "--difficulty", "40-60"])
+        .run();
+
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    let scores: Vec<u8> = run
+        .stdout
+        .split("Difficulty: ")
+        .skip(1)
+        .map(|rest| rest.split('/').next().unwrap().parse().unwrap())
+        .collect();
+    assert_eq!(scores.len(), 4);
+    assert!(
+        scores.iter().all(|score| (40..=60).contains(score)),
+        "{:?}",
+        scores
+    );
+
+    let run = Kickbeats::new()
+        .args(&["generate", "--difficulty", "60-40"])
+        .run();
+    assert_ne!(run.status, Some(0));
+    assert!(run.stderr.contains("backwards"), "{}", run.stderr);
+}
+
+#[test]
+fn generate_changes_meter_inside_patterns() {
+    let run = Kickbeats::new()
+        .args(&["generate", "-n", "2", "--meters", "4/4,3/4"])
+        .run();
+
+    assert_eq!(run.status, Some(0));
+    assert_eq!(run.stdout.matches("Time: 4/4 + 3/4").count(), 2);
+    assert_eq!(
+        run.stdout
+            .matches("|1 e + a |2 e + a |3 e + a |4 e + a ||1 e + a |")
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn rejects_meters_with_a_single_bar() {
+    let run = Kickbeats::new()
+        .args(&["generate", "--meters", "5/4"])
+        .run();
+
+    assert_eq!(run.status, Some(1));
+    assert!(run.stderr.contains("2 to 8 measures"));
+}
+
+#[test]
+fn generate_shows_requested_reveal_styles() {
+    let run = Kickbeats::new()
+        .args(&["generate", "--reveal", "tab,notes"])
+        .run();
+
+    assert_eq!(run.status, Some(0));
+    assert!(run.stdout.contains("BD|o"));
+    assert!(run.stdout.contains("Notes: "));
+    assert!(!run.stdout.contains("|1 e + a |"));
+}
+
+#[test]
+fn generate_shows_rhythmic_notation() {
+    let run = Kickbeats::new()
+        .args(&[
+            "generate",
+            "--pattern",
+            "4/4:16:x...x..x....x.x.",
+            "--notation",
+            "rhythmic",
+        ])
+        .run();
+
+    assert_eq!(run.status, Some(0));
+    assert!(run.stdout.contains("Rhythm: | ♩  ♪. 𝅘𝅥𝅯  𝄽  ♪ ♪ |"));
+    assert!(!run.stdout.contains("|1 e + a |"));
+}
+
+#[test]
+fn generate_at_the_top_of_the_complexity_scale() {
+    let run = Kickbeats::new()
+        .args(&["generate", "-n", "5", "--level", "10"])
+        .run();
+
+    assert_eq!(run.status, Some(0));
+    let kick_rows: Vec<&str> = run
+        .stdout
+        .lines()
+        .filter(|line| line.starts_with("|X"))
+        .collect();
+    assert_eq!(kick_rows.len(), 5);
+    for row in kick_rows {
+        let kicks = row.matches('X').count();
+        assert!((7..=9).contains(&kicks), "{} kicks in {}", kicks, row);
+    }
+    assert!(run.stdout.contains("Complexity: Complex"));
+}
+
+#[test]
+fn generate_colors_only_when_asked_through_a_pipe() {
+    let pattern = ["generate", "--pattern", "4/4:16:x...x..x....x.x."];
+    let piped = Kickbeats::new().args(&pattern).run();
+    let colored = Kickbeats::new()
+        .args(&pattern)
+        .args(&["--color", "always"])
+        .run();
+
+    assert_eq!(colored.status, Some(0));
+    assert!(!piped.stdout.contains('\u{1b}'));
+    assert!(colored.stdout.contains("\u{1b}["));
+    assert!(colored.stdout.contains("Time: 4/4"));
+}
+
+#[test]
+fn generate_bars_odd_meters_by_their_groups() {
+    let run = Kickbeats::new()
+        .args(&[
+            "generate",
+            "--pattern",
+            "7/8:16:x.x.x..x.x....",
+            "--grouping",
+            "2+2+3",
+        ])
+        .run();
+
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(run.stdout.contains("|1 + 2 + |3 + 4 + |5 + 6 + 7 + |"));
+    assert!(run.stdout.contains("|X . X . |X . . X |. X . . . . |"));
+}
+
+#[test]
+fn generate_rock_style_leaves_backbeat_to_the_snare() {
+    let run = Kickbeats::new()
+        .args(&["generate", "-n", "5", "--style", "rock"])
+        .run();
+
+    assert_eq!(run.status, Some(0));
+    let rows: Vec<&str> = run.stdout.lines().filter(|l| l.starts_with("|X")).collect();
+    assert_eq!(rows.len(), 5);
+    for row in rows {
+        let beats: Vec<&str> = row.split('|').collect();
+        assert!(!beats[2].starts_with('X'), "kick on beat 2: {}", row);
+        assert!(!beats[4].starts_with('X'), "kick on beat 4: {}", row);
+    }
+}
+
+#[test]
+fn generate_mystery_skips_styles_outside_four_four() {
+    let run = Kickbeats::new()
+        .args(&[
+            "generate",
+            "-n",
+            "5",
+            "--mystery",
+            "--time-signature",
+            "3/4",
+        ])
+        .run();
+
+    assert_eq!(run.status, Some(0));
+    assert_eq!(run.stdout.matches("Time: 3/4").count(), 5);
+}
+
+#[test]
+fn generate_mystery_draws_imported_grooves() {
+    let grooves = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/grooves");
+    let run = Kickbeats::new()
+        .args(&["generate", "-n", "3", "--mystery", "--grooves", grooves])
+        .run();
+
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert_eq!(run.stdout.matches("Time: 4/4").count(), 3);
+}
+
+#[test]
+fn generate_euclidean_spreads_requested_hits() {
+    let run = Kickbeats::new()
+        .args(&[
+            "generate",
+            "-n",
+            "3",
+            "--generator",
+            "euclidean",
+            "--hits",
+            "5",
+        ])
+        .run();
+
+    assert_eq!(run.status, Some(0));
+    assert_eq!(run.stdout.matches("Time: 4/4").count(), 3);
+}
+
+#[test]
+fn generate_follows_custom_complexity_params() {
+    let run = Kickbeats::new()
+        .args(&[
+            "generate",
+            "-n",
+            "3",
+            "--kicks-min",
+            "3",
+            "--kicks-max",
+            "3",
+            "--allow-consecutive",
+            "1",
+        ])
+        .run();
+
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    // Three kicks in each 16-step measure
+    assert_eq!(run.stdout.matches("Density: 18.8%").count(), 3);
+}
+
+#[test]
+fn generate_starts_with_a_shared_pattern() {
+    let run = Kickbeats::new()
+        .args(&["generate", "-n", "2", "--pattern", "3/4:8:x.x..x"])
+        .run();
+
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(run.stdout.contains("|X . . . |X . . . |. . X . |"));
+    assert!(run.stdout.contains("Share: 3/4:16:x...x.....x.\n"));
+    // Later patterns follow the shared pattern's meter
+    assert_eq!(run.stdout.matches("Time: 3/4").count(), 2);
+}
+
+#[test]
+fn notation_writes_a_shared_pattern() {
+    let run = Kickbeats::new()
+        .args(&["notation", "--pattern", "3/4:8:x.x..x", "--tempo", "90"])
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(run.stdout.contains("\\tempo 4 = 90"), "{}", run.stdout);
+    assert!(run.stdout.contains("bd4 bd4 r8 bd8 |"), "{}", run.stdout);
+
+    let path = concat!(env!("CARGO_TARGET_TMPDIR"), "/notation.abc");
+    let run = Kickbeats::new()
+        .args(&[
+            "notation",
+            "-f",
+            "abc",
+            "-o",
+            path,
+            "--pattern",
+            "3/4:8:x.x..x",
+        ])
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    let abc = std::fs::read_to_string(path).unwrap();
+    assert!(abc.ends_with("|: F4 F4 z2F2 :|\n"), "{}", abc);
+}
+
+#[test]
+#[cfg(feature = "wav")]
+fn audio_renders_the_loops_after_a_count_in() {
+    let path = concat!(env!("CARGO_TARGET_TMPDIR"), "/groove.wav");
+    let run = Kickbeats::new()
+        .args(&[
+            "audio",
+            "-o",
+            path,
+            "-n",
+            "2",
+            "--pattern",
+            "4/4:16:x...x..x....x.x.",
+            "--no-click",
+        ])
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(run.stdout.contains("2 loops at 120 BPM"), "{}", run.stdout);
+
+    // 44.1kHz 16-bit mono: 6s of count-in and loops, plus the last kick's ring
+    let wav = std::fs::read(path).unwrap();
+    assert_eq!(&wav[..4], b"RIFF");
+    assert_eq!(&wav[8..12], b"WAVE");
+    assert_eq!(wav.len(), 44 + 2 * (6 * 44_100 + 6615));
+
+    let run = Kickbeats::new()
+        .args(&["audio", "-o", path, "--kick-sample", "missing.wav"])
+        .run();
+    assert_eq!(run.status, Some(1));
+    assert!(
+        run.stderr.contains("Failed to read sample missing.wav"),
+        "{}",
+        run.stderr
+    );
+}
+
+#[test]
+fn generate_repeats_patterns_for_the_same_seed() {
+    let generate = |seed: &str| {
+        let run = Kickbeats::new()
+            .args(&["generate", "-n", "5", "--seed", seed])
+            .run();
+        assert_eq!(run.status, Some(0), "{}", run.stderr);
+        run.stdout
+    };
+
+    assert_eq!(generate("42"), generate("42"));
+    assert_ne!(generate("42"), generate("43"));
+}
+
+#[test]
+fn generate_uses_weight_profiles() {
+    let weights = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/weights.toml");
+    let run = Kickbeats::new()
+        .args(&["generate", "-n", "2", "--weights", weights])
+        .run();
+
+    assert_eq!(run.status, Some(0));
+    assert_eq!(run.stdout.matches("Time: 4/4").count(), 2);
+}
+
+#[test]
+fn completions_cover_subcommands() {
+    let run = Kickbeats::new().args(&["completions", "bash"]).run();
+
+    assert_eq!(run.status, Some(0));
+    assert!(run.stdout.contains("kickbeats"));
+    assert!(run.stdout.contains("generate"));
+}
+
+#[test]
+fn man_page_renders_roff() {
+    let run = Kickbeats::new().args(&["man"]).run();
+
+    assert_eq!(run.status, Some(0));
+    assert!(run.stdout.starts_with(".ie"));
+    assert!(run.stdout.contains(".TH kickbeats 1"));
+}
+
+#[test]
+fn loads_example_config_before_terminal_check() {
+    let config = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/kickbeats.toml");
+    let run = Kickbeats::new()
+        .args(&["--config", config, "--no-midi"])
+        .run();
+
+    assert_eq!(run.status, Some(1));
+    assert!(!run.stderr.contains("config file"));
+}
+
+#[test]
+fn default_config_file_sets_defaults_and_flags_override_it() {
+    let home = concat!(env!("CARGO_TARGET_TMPDIR"), "/config-precedence");
+    std::fs::create_dir_all(format!("{}/kickbeats", home)).unwrap();
+    std::fs::write(
+        format!("{}/kickbeats/config.toml", home),
+        "[practice]\ntempo = 90\ncomplexity = \"simple\"\ntime_signature = \"3/4\"\n",
+    )
+    .unwrap();
+
+    let run = Kickbeats::new().config_home(home).args(&["generate"]).run();
+    assert_eq!(run.status, Some(0));
+    assert!(run.stdout.contains("Tempo: 90 BPM | Complexity: Simple"));
+    assert!(run.stdout.contains("Time: 3/4"));
+
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["generate", "--tempo", "140", "--time-signature", "4/4"])
+        .run();
+    assert_eq!(run.status, Some(0));
+    assert!(run.stdout.contains("Tempo: 140 BPM | Complexity: Simple"));
+    assert!(run.stdout.contains("Time: 4/4"));
+}
+
+#[test]
+#[cfg(feature = "midi")]
+fn loads_example_plan_before_terminal_check() {
+    let plan = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/plan.toml");
+    let run = Kickbeats::new().args(&["--plan", plan, "--no-midi"]).run();
+
+    assert_eq!(run.status, Some(1));
+    assert!(!run.stderr.contains("plan file"));
+}
+
+#[test]
+#[cfg(feature = "midi")]
+fn rejects_invalid_plan() {
+    let plan = concat!(env!("CARGO_TARGET_TMPDIR"), "/invalid-plan.toml");
+    std::fs::write(plan, "[[segment]]\nminutes = 5\ntempo = 500\n").unwrap();
+
+    let run = Kickbeats::new().args(&["--plan", plan, "--no-midi"]).run();
+    assert_eq!(run.status, Some(1));
+    assert!(run
+        .stderr
+        .contains("Segment 1: tempo 500 must be between 40 and 300"));
+}
+
+#[test]
+fn rejects_invalid_config_values() {
+    let home = concat!(env!("CARGO_TARGET_TMPDIR"), "/config-invalid");
+    std::fs::create_dir_all(format!("{}/kickbeats", home)).unwrap();
+    std::fs::write(
+        format!("{}/kickbeats/config.toml", home),
+        "[practice]\ntempo = 500\n",
+    )
+    .unwrap();
+
+    let run = Kickbeats::new().config_home(home).args(&["generate"]).run();
+    assert_eq!(run.status, Some(1));
+    assert!(run
+        .stderr
+        .contains("practice.tempo 500 must be between 40 and 300"));
+}
+
+#[test]
+fn packs_export_install_and_apply() {
+    let home = concat!(env!("CARGO_TARGET_TMPDIR"), "/packs");
+    let _ = std::fs::remove_dir_all(home);
+    std::fs::create_dir_all(format!("{}/kickbeats", home)).unwrap();
+    std::fs::write(
+        format!("{}/teacher.toml", home),
+        "[practice]\ntempo = 96\ncomplexity = \"simple\"\n[midi]\nport = \"IAC\"\n",
+    )
+    .unwrap();
+    let file = format!("{}/funk101.kbpack", home);
+    let weights = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/weights.toml");
+    let playlist = format!("{}/funk.txt", home);
+    std::fs::write(&playlist, "# Week 1\nx...x...x...x...\nxx..xx..xx..xx..\n").unwrap();
+    let curriculum = format!("{}/stages.toml", home);
+    std::fs::write(
+        &curriculum,
+        "[[curriculum]]\nname = \"Downbeats\"\ncomplexity = \"simple\"\n\
+         advance_accuracy = 90\nadvance_after = 3\n\n\
+         [[curriculum]]\nname = \"Syncopation\"\ncomplexity = \"medium\"\n",
+    )
+    .unwrap();
+
+    let config = format!("{}/teacher.toml", home);
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["pack", "export", &file, "--name", "funk101"])
+        .args(&["--config", &config, "--weights", weights])
+        .args(&["--playlist", &playlist, "--curriculum", &curriculum])
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(run.stdout.contains("Created teacher key"));
+    assert!(std::path::Path::new(&format!("{}/kickbeats/teacher.key", home)).exists());
+    let contents = std::fs::read_to_string(&file).unwrap();
+    assert!(contents.contains("tempo = 96"));
+    assert!(contents.contains("[[playlist]]"));
+    assert!(contents.contains("name = \"Syncopation\""));
+    assert!(!contents.contains("IAC"));
+
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["pack", "install", &file])
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(run.stdout.contains("Installed funk101 1.0.0"));
+
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["pack", "list"])
+        .run();
+    assert!(run.stdout.contains("funk101 1.0.0"));
+
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["--pack", "funk101", "generate", "--tempo", "110", "-n", "3"])
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(run.stdout.contains("Tempo: 110 BPM | Complexity: Simple"));
+    assert_eq!(
+        run.stdout
+            .matches("|X . . . |X . . . |X . . . |X . . . |")
+            .count(),
+        2,
+        "{}",
+        run.stdout
+    );
+    assert_eq!(run.stdout.matches("|X X . . |X X . . |").count(), 1);
+
+    std::fs::write(&file, contents.replace("tempo = 96", "tempo = 200")).unwrap();
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["pack", "install", &file])
+        .run();
+    assert_eq!(run.status, Some(1));
+    assert!(run.stderr.contains("failed its signature check"));
+
+    // A newer release signed by someone else's key doesn't replace the teacher's
+    let other_key = format!("{}/other.key", home);
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["pack", "export", &file, "--name", "funk101"])
+        .args(&["--pack-version", "2.0.0", "--key", &other_key])
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["pack", "install", &file])
+        .run();
+    assert_eq!(run.status, Some(1));
+    assert!(
+        run.stderr.contains("signed by teacher key"),
+        "{}",
+        run.stderr
+    );
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["pack", "install", &file, "--force"])
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+}
+
+#[test]
+fn rejects_invalid_answer_resolution() {
+    let run = Kickbeats::new()
+        .args(&["--answer-resolution", "32nd"])
+        .run();
+
+    assert_eq!(run.status, Some(2));
+    assert!(run.stderr.contains("Must be one of: 16th, 8th"));
+}
+
+#[test]
+#[cfg(feature = "midi")]
+fn rejects_virtual_port_with_midi_port() {
+    let run = Kickbeats::new()
+        .args(&["--virtual-port", "Kickbeats Out", "--midi-port", "IAC"])
+        .run();
+
+    assert_eq!(run.status, Some(2));
+    assert!(run.stderr.contains("cannot be used with"));
+}
+
+#[test]
+#[cfg(feature = "midi")]
+fn rejects_invalid_goal() {
+    let run = Kickbeats::new().args(&["--goal", "10 hard"]).run();
+
+    assert_eq!(run.status, Some(2));
+    assert!(run.stderr.contains("Invalid complexity 'hard'"));
+}
+
+#[test]
+fn rejects_out_of_range_morph_bars() {
+    let run = Kickbeats::new().args(&["--morph-bars", "0"]).run();
+
+    assert_eq!(run.status, Some(2));
+    assert!(run.stderr.contains("--morph-bars"));
+}
+
+#[test]
+fn rejects_one_to_one_click_ratio() {
+    let run = Kickbeats::new().args(&["--click-ratio", "4:4"]).run();
+
+    assert_eq!(run.status, Some(2));
+    assert!(run.stderr.contains("Click ratio 1:1 is the normal click"));
+}
+
+#[test]
+fn notes_lists_saved_patterns_by_tag() {
+    let home = concat!(env!("CARGO_TARGET_TMPDIR"), "/notes");
+    let history = format!("{}/sessions", home);
+    let _ = std::fs::remove_dir_all(home);
+    std::fs::create_dir_all(format!("{}/kickbeats", home)).unwrap();
+    std::fs::create_dir_all(&history).unwrap();
+    std::fs::write(
+        format!("{}/kickbeats/config.toml", home),
+        format!("[storage]\ndir = \"{}\"\n", history),
+    )
+    .unwrap();
+
+    let run = Kickbeats::new().config_home(home).args(&["notes"]).run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(run.stdout.contains("No noted patterns saved yet"));
+
+    let record = r#"{"session_id": "aaaaaaaa-1", "started_at": 1760572800, "duration_secs": 300,
+        "tempo_bpm": 100, "complexity": "medium", "time_signature": "4/4",
+        "patterns_generated": 3, "answer_accuracies": [],
+        "notes": [
+            {"number": 1, "pattern": "4/4:16:x...x.......x...", "tags": ["tricky"]},
+            {"number": 3, "pattern": "4/4:16:x..x..x...x.x...", "text": "clave feel", "tags": ["samba-ish"]}
+        ]}"#;
+    std::fs::write(format!("{}/1760572800-aaaaaaaa.json", history), record).unwrap();
+
+    let run = Kickbeats::new().config_home(home).args(&["notes"]).run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    let lines: Vec<&str> = run.stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", run.stdout);
+    assert_eq!(
+        lines[0],
+        "2025-10-16  #3    4/4:16:x..x..x...x.x...  clave feel #samba-ish"
+    );
+
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["notes", "--tag", "#Tricky"])
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert_eq!(
+        run.stdout.trim(),
+        "2025-10-16  #1    4/4:16:x...x.......x...  #tricky"
+    );
+
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["notes", "--tag", "fills"])
+        .run();
+    assert!(run.stdout.contains("No saved patterns tagged #fills."));
+}
+
+#[test]
+#[cfg(feature = "midi")]
+fn stats_summarizes_the_saved_history() {
+    let home = concat!(env!("CARGO_TARGET_TMPDIR"), "/stats");
+    let history = format!("{}/sessions", home);
+    let _ = std::fs::remove_dir_all(home);
+    std::fs::create_dir_all(format!("{}/kickbeats", home)).unwrap();
+    std::fs::write(
+        format!("{}/kickbeats/config.toml", home),
+        format!("[storage]\ndir = \"{}\"\n", history),
+    )
+    .unwrap();
+
+    let run = Kickbeats::new().config_home(home).args(&["stats"]).run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(run.stdout.contains("No practice sessions saved yet"));
+
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["--headless", "--no-midi"])
+        .line(r#"{"cmd":"quit"}"#)
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+
+    let run = Kickbeats::new()
+        .config_home(home)
+        .args(&["stats", "--days", "3"])
+        .run();
+    assert_eq!(run.status, Some(0), "{}", run.stderr);
+    assert!(
+        run.stdout.contains("Practice stats: 1 session,"),
+        "{}",
+        run.stdout
+    );
+    assert!(
+        run.stdout.contains("Streak: 1 day (longest 1)"),
+        "{}",
+        run.stdout
+    );
+    assert_eq!(
+        run.stdout
+            .lines()
+            .filter(|line| line.starts_with("  20"))
+            .count(),
+        3
+    );
+}
+
+#[test]
+fn rejects_click_drift_with_click_ratio() {
+    let run = Kickbeats::new()
+        .args(&["--click-drift", "4bpm/16bars", "--click-ratio", "3:4"])
+        .run();
+    assert_eq!(run.status, Some(2));
+    assert!(run.stderr.contains("--click-ratio"));
+
+    let run = Kickbeats::new()
+        .args(&["--click-drift", "4bpm/2bars"])
+        .run();
+    assert_eq!(run.status, Some(2));
+    assert!(run.stderr.contains("at least 4 bars"));
+}
@@ -0,0 +1,87 @@
+// Shared harness for driving the kickbeats binary end to end.
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Builder for one scripted run of the compiled binary
+pub struct Kickbeats {
+    args: Vec<String>,
+    stdin: String,
+    config_home: String,
+    data_home: String,
+}
+
+/// Captured result of a run
+pub struct Run {
+    pub status: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Kickbeats {
+    pub fn new() -> Self {
+        Self {
+            args: Vec::new(),
+            stdin: String::new(),
+            // Keep the developer's own ~/.config/kickbeats out of the tests
+            config_home: concat!(env!("CARGO_TARGET_TMPDIR"), "/no-config").to_string(),
+            // ...and its practice history
+            data_home: concat!(env!("CARGO_TARGET_TMPDIR"), "/data").to_string(),
+        }
+    }
+
+    /// Append command-line arguments
+    pub fn args(mut self, args: &[&str]) -> Self {
+        self.args.extend(args.iter().map(|a| a.to_string()));
+        self
+    }
+
+    /// Look for the default config file under this directory (`$XDG_CONFIG_HOME`)
+    #[allow(dead_code)]
+    pub fn config_home(mut self, dir: &str) -> Self {
+        self.config_home = dir.to_string();
+        self
+    }
+
+    /// Feed one line of input on stdin
+    #[allow(dead_code)]
+    pub fn line(mut self, line: &str) -> Self {
+        self.stdin.push_str(line);
+        self.stdin.push('\n');
+        self
+    }
+
+    /// Run the binary to completion with piped stdio
+    pub fn run(self) -> Run {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_kickbeats"))
+            .args(&self.args)
+            .env("XDG_CONFIG_HOME", &self.config_home)
+            .env("XDG_DATA_HOME", &self.data_home)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("failed to spawn kickbeats");
+
+        // Ignore write errors: the binary may exit before reading its input
+        let _ = child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(self.stdin.as_bytes());
+
+        let Output {
+            status,
+            stdout,
+            stderr,
+        } = child
+            .wait_with_output()
+            .expect("failed to wait for kickbeats");
+
+        Run {
+            status: status.code(),
+            stdout: String::from_utf8_lossy(&stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+// Kickbeats WebAssembly bindings
+// Exposes pattern generation, validation, and ASCII rendering to JavaScript
+//
+// Build with: cargo build -p kickbeats-wasm --target wasm32-unknown-unknown --release
+
+use kickbeats_core::generator::WeightedGenerator;
+use kickbeats_core::models::{ComplexityLevel, Pattern, TimeSignature};
+use kickbeats_core::visualizer::ascii::pattern_to_ascii;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::VecDeque;
+use wasm_bindgen::prelude::*;
+
+/// Maximum number of patterns remembered for uniqueness checking
+const HISTORY_CAPACITY: usize = 20;
+
+/// Convert JS-facing step bytes (0 = rest, anything else = kick) to a pattern
+fn pattern_from_bytes(steps: &[u8], time_signature: &str) -> Result<Pattern, JsError> {
+    let time_signature: TimeSignature = time_signature
+        .parse()
+        .map_err(|e: String| JsError::new(&e))?;
+    let steps = steps.iter().map(|&s| s != 0).collect();
+    Ok(Pattern::new(
+        steps,
+        time_signature,
+        ComplexityLevel::default(),
+    ))
+}
+
+/// Seeded pattern generator for web pages
+///
+/// Two generators created with the same seed produce the same pattern
+/// sequence, both here and in the native CLI.
+#[wasm_bindgen]
+pub struct PatternGenerator {
+    generator: WeightedGenerator<StdRng>,
+    history: VecDeque<Pattern>,
+}
+
+#[wasm_bindgen]
+impl PatternGenerator {
+    /// Create a generator from a shared seed
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            generator: WeightedGenerator::with_rng(StdRng::seed_from_u64(seed)),
+            history: VecDeque::with_capacity(HISTORY_CAPACITY),
+        }
+    }
+
+    /// Generate the next pattern as step bytes (1 = kick, 0 = rest)
+    pub fn generate(&mut self, time_signature: &str, complexity: &str) -> Result<Vec<u8>, JsError> {
+        let time_signature: TimeSignature = time_signature
+            .parse()
+            .map_err(|e: String| JsError::new(&e))?;
+        let complexity: ComplexityLevel =
+            complexity.parse().map_err(|e: String| JsError::new(&e))?;
+
+        let (pattern, _) = self
+            .generator
+            .generate_unique(time_signature, complexity, &self.history)
+            .map_err(|e| JsError::new(e.message()))?;
+
+        let steps = pattern.steps.iter().map(|&s| s as u8).collect();
+
+        if self.history.len() >= HISTORY_CAPACITY {
+            self.history.pop_front();
+        }
+        self.history.push_back(pattern);
+
+        Ok(steps)
+    }
+}
+
+/// Validate step bytes against the pattern rules, returning the reason on failure
+#[wasm_bindgen]
+pub fn validate(steps: &[u8], time_signature: &str) -> Result<(), JsError> {
+    pattern_from_bytes(steps, time_signature)?
+        .validate_steps()
+        .map_err(|e| JsError::new(e.message()))
+}
+
+/// Render step bytes as the two-line ASCII grid
+#[wasm_bindgen(js_name = renderAscii)]
+pub fn render_ascii(steps: &[u8], time_signature: &str) -> Result<String, JsError> {
+    Ok(pattern_to_ascii(&pattern_from_bytes(
+        steps,
+        time_signature,
+    )?))
+}